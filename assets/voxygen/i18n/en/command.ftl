@@ -44,9 +44,12 @@ command-destroy_tethers-desc = Destroy all tethers connected to you
 command-disconnect_all_players-desc = Disconnects all players from the server
 command-dismount-desc = Dismount if you are riding, or dismount anything riding you
 command-dropall-desc = Drops all your items on the ground
+command-duel-desc = Challenge a nearby player to a duel, which doesn't depend on your battle modes
+command-duel_forfeit-desc = Give up the duel you are fighting
 command-dummy-desc = Spawns a training dummy
 command-explosion-desc = Explodes the ground around you
-command-faction-desc = Send messages to your faction
+command-export_character-desc = Export a character of an offline player, by alias or id, so that other servers can import it
+command-faction-desc = Send messages to your guild
 command-give_item-desc = Give yourself some items. For an example or to auto complete use Tab.
 command-gizmos-desc = Manage gizmo subscriptions.
 command-gizmos_range-desc = Change the range of gizmo subscriptions.
@@ -57,9 +60,16 @@ command-group_invite-desc = Invite a player to join a group
 command-group_kick-desc = Remove a player from a group
 command-group_leave-desc = Leave the current group
 command-group_promote-desc = Promote a player to group leader
+command-guild-desc = List the members of your guild
+command-guild_create-desc = Found a new guild with the given name
+command-guild_disband-desc = Disband your guild, only the leader can do this
+command-guild_invite-desc = Invite a player to join your guild
+command-guild_kick-desc = Remove a member of lower rank from your guild
+command-guild_leave-desc = Leave your guild
+command-guild_rank-desc = Change the rank of a guild member, making someone else the leader hands over your guild
 command-health-desc = Set your current health
+command-import_character-desc = Create a new character for a player from a file in the character export directory, optionally renaming it
 command-into_npc-desc = Convert yourself to an NPC. Be careful!
-command-join_faction-desc = Join/leave the specified faction
 command-jump-desc = Offset your current position
 command-kick-desc = Kick a player with a given username
 command-kill-desc = Kill yourself
@@ -84,8 +94,10 @@ command-poise-desc = Set your current poise
 command-portal-desc = Spawns a portal
 command-region-desc = Send messages to everyone in your region of the world
 command-reload_chunks-desc = Reloads chunks loaded on the server
+command-reload_plugin-desc = Reloads a server plugin from its file
 command-remove_lights-desc = Removes all lights spawned by players
 command-repair_equipment-desc = Repairs all equipped items
+command-reputation-desc = Show what the settlements and factions of the world think of you
 command-reset_recipes-desc = Resets your recipe book
 command-respawn-desc = Teleport to your waypoint
 command-revoke_build-desc = Revokes build area permission for player
@@ -104,6 +116,8 @@ command-spawn-desc = Spawn a test entity
 command-spot-desc = Find and teleport to the closest spot of a certain kind.
 command-sudo-desc = Run command as if you were another entity
 command-tell-desc = Send a message to another player
+command-terrain_history-desc = Show the latest edits of a block, defaults to the block you stand on
+command-terrain_rollback-desc = Undo the terrain edits of a player, optionally only within a radius and a time span
 command-tether-desc = Tether another entity to yourself
 command-time-desc = Set the time of day
 command-time_scale-desc = Set scaling of delta time
@@ -271,14 +285,30 @@ command-repaired-items = Repaired all equipped items
 command-repaired-inventory_items = Repaired all items
 command-message-group-missing = You are using group chat but do not belong to a group. Use /world or
   /region to change chat.
+command-message-guild-missing = You are using guild chat but do not belong to that guild. Use /world or
+  /region to change chat.
 command-tell-to-yourself = You can't /tell yourself.
 command-transform-invalid-presence = Cannot transform in the current presence
 command-aura-invalid-buff-parameters = Invalid buff parameters for aura
 command-aura-spawn = Spawned new aura attached to entity
 command-aura-spawn-new-entity = Spawned new aura
 command-reloaded-chunks = Reloaded { $reloaded } chunks
+command-reloaded-plugin = Reloaded plugin { $plugin }
+command-reload_plugin-failed = Failed to reload plugin { $plugin }, see the server log for details
+command-server-no-plugins = Server was compiled without plugin support
 command-server-no-experimental-terrain-persistence = Server was compiled without terrain persistence enabled
-command-experimental-terrain-persistence-disabled = Experimental terrain persistence is disabled
+command-experimental-terrain-persistence-disabled = Terrain persistence is disabled
+command-terrain-journal-error = Failed to read the terrain edit journal, see the server log for details
+command-terrain_history-empty = There are no recorded edits of the block at { $pos }
+command-terrain_history-header = Edits of the block at { $pos }:
+command-terrain_history-edit = { $time }: { $author } changed { $old } to { $new }
+command-terrain_history-server = Server
+command-terrain_rollback-done = Reverted { $count } blocks edited by { $player }
+command-terrain_rollback-done-skipped = Reverted { $count } blocks edited by { $player }, { $skipped } blocks were left alone because they changed since
+command-terrain_rollback-started = Searching the terrain edit journal, the rollback is applied once that's done
+command-export_character-done = Exported the character to { $file }
+command-import_character-done = Imported the character as character { $id } of { $player }
+command-character-transfer-invalid-file = { $file } is not a file name in the character export directory
 command-adminify-assign-higher-than-own = Cannot assign someone a temporary role higher than your own permanent one.
 command-adminify-reassign-to-above = Cannot reassign a role for anyone with your role or higher.
 command-adminify-cannot-find-player = Cannot find player entity!
@@ -291,13 +321,52 @@ command-ban-added = Added { $player } to the banlist with reason: { $reason }
 command-ban-already-added = { $player } is already on the banlist
 command-ban-ip-added = Added { $player } to the regular banlist and IP banlist with reason: { $reason }
 command-ban-ip-queued = Added { $player } to the regular banlist and queued an IP ban with reason: { $reason }
-command-faction-join = Please join a faction with /join_faction
+command-faction-join = Please join a guild first, or found one with /guild_create
 command-group-join = Please create a group first
+command-guild-roster = Members of { $guild }: { $members }
+command-guild-created = Founded the guild { $guild }
+command-guild-joined = { $player } joined the guild
+command-guild-left = { $player } left the guild
+command-guild-you-left = You left the guild { $guild }
+command-guild-kicked = { $player } was removed from the guild by { $by }
+command-guild-you-were-kicked = You were removed from the guild { $guild }
+command-guild-rank-changed = { $player } is now { $rank ->
+    [leader] the leader of the guild
+    [officer] an officer
+   *[member] a member
+}
+command-guild-rank-self = You can't change your own rank, make someone else the leader instead
+command-guild-disbanded = { $player } disbanded the guild
+command-guild-not_in_guild = You are not a member of a guild
+command-guild-already_in_guild = You are already a member of a guild
+command-guild-disabled = Guilds aren't available on this server
+command-guild-no_character = You have to be playing a character to found a guild
+command-guild-no_permission = Your rank in the guild doesn't allow this
+command-guild-member-missing = Your guild has no member named { $player }
+command-guild-name-empty = The guild needs a name
+command-guild-name-too_long = Guild names can be at most 32 characters long
+command-guild-name-forbidden = Guild names may only contain letters, digits, single spaces, '-' and '_'
+command-guild-name-taken = There already is a guild with that name
+command-guild_invite-invited = Invited { $player } to the guild.
+command-guild-invite-no_character = Only players that are playing a character can be invited to a guild
+command-guild-invite-already_in_guild = This player is already a member of a guild
+command-guild-invite-expired = You are no longer able to join, the inviter has left their guild
+command-duel-challenged = Challenged { $player } to a duel.
+command-duel-not_a_player = Only players can be challenged to a duel
+command-duel-already_dueling = One of you is already fighting a duel
+command-duel-same_group = Members of the same group can't duel each other
+command-duel-too_far = You have to be closer to challenge them to a duel
+command-duel-start_failed = The duel could not start, one of you moved away or is fighting another duel
+command-duel-not_dueling = You are not fighting a duel
 command-group_invite-invited-to-group = Invited { $player } to the group.
 command-group_invite-invited-to-your-group = { $player } has been invited to your group.
 command-into_npc-warning = I hope you aren't abusing this!
 command-kick-higher-role = Cannot kick players with roles higher than your own.
 command-respawn-no-waypoint = No waypoint set
+command-reputation-unavailable = Reputation is not available for this entity
+command-reputation-none = Nobody has an opinion of you yet
+command-reputation-site = { $site }: { $level }
+command-reputation-faction = Faction of { $site }: { $level }
 command-site-not-found = Site not found
 command-sudo-higher-role = Cannot sudo players with roles higher than your own.
 command-sudo-no-permission-for-non-players = You don't have permission to sudo non-players.
@@ -147,3 +147,12 @@ hud-diary-sections-character-title = Character
 hud-diary-sections-recipes-title = Recipes
 hud-battle-mode = Battle Mode
 hud-waypoint = Waypoint
+
+## Reputation
+reputation-level-hated = hated
+reputation-level-hostile = hostile
+reputation-level-unfriendly = unfriendly
+reputation-level-neutral = neutral
+reputation-level-friendly = friendly
+reputation-level-honored = honored
+reputation-level-revered = revered
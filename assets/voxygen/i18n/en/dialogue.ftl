@@ -18,6 +18,9 @@ dialogue-question-hire =
 dialogue-question-sentiment =
     .a0 = How do you feel about...
     .a1 = What do you think about...
+dialogue-question-reputation =
+    .a0 = What do people here think of me?
+    .a1 = How am I known around here?
 dialogue-cancel_hire = I want to stop hiring you.
 dialogue-me = Me
 dialogue-buy_hire_days =
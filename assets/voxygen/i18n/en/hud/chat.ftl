@@ -74,6 +74,16 @@ hud-chat-fall_kill_msg = [{ $name }] died from fall damage
 hud-chat-suicide_msg = [{ $name }] died from self-inflicted wounds
 hud-chat-default_death_msg = [{ $name }] died
 
+## Duels
+
+hud-chat-duel-started = The duel has begun! Stay within { $radius } blocks of where it started, it ends in a draw after { $minutes } minutes.
+hud-chat-duel-won = [{ $winner }] won a duel against [{ $loser }]
+hud-chat-duel-won-forfeit = [{ $winner }] won a duel against [{ $loser }], who gave up
+hud-chat-duel-won-left_arena = [{ $winner }] won a duel against [{ $loser }], who fled the arena
+hud-chat-duel-abandoned = [{ $winner }] won a duel, their opponent has left
+hud-chat-duel-draw = The duel between [{ $a }] and [{ $b }] ended in a draw
+hud-chat-duel-draw-timeout = The duel between [{ $a }] and [{ $b }] ended in a draw, time is up
+
 ## Chat utils
 
 hud-chat-all = All
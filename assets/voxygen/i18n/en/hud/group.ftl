@@ -5,6 +5,15 @@ hud-group-invite_to_trade = [{ $name }] would like to trade with you.
 hud-group-invite-accepted = { $target } accepted your group invite.
 hud-group-invite-declined = { $target } declined your group invite.
 hud-group-invite-timed_out= Group invite to { $target } timed out.
+hud-group-invite_to_guild = [{ $name }] invited you to their guild!
+hud-guild-invite-accepted = { $target } accepted your guild invite.
+hud-guild-invite-declined = { $target } declined your guild invite.
+hud-guild-invite-timed_out = Guild invite to { $target } timed out.
+hud-guild-chat_hint = Type /f or /faction to chat with your guild members
+hud-group-invite_to_duel = [{ $name }] challenged you to a duel!
+hud-duel-invite-accepted = { $target } accepted your challenge.
+hud-duel-invite-declined = { $target } declined your challenge.
+hud-duel-invite-timed_out = Challenge to { $target } timed out.
 
 hud-group-invite = Invite
 hud-group-kick = Kick
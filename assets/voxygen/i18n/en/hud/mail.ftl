@@ -0,0 +1,41 @@
+hud-mail-mailbox = Mailbox
+hud-mail-inbox = Inbox
+hud-mail-compose = Compose
+hud-mail-empty = No mail
+hud-mail-no_subject = (No subject)
+hud-mail-from = From: { $sender }
+hud-mail-returned_by = Returned by: { $sender }
+hud-mail-expires = { $days ->
+    [0] Expires today
+    [one] Expires in { $days } day
+   *[other] Expires in { $days } days
+}
+hud-mail-kept = Kept until the attachments are taken
+hud-mail-coins = { $amount } coins attached
+hud-mail-take = Take
+hud-mail-return = Return
+hud-mail-delete = Delete
+hud-mail-recipient = To
+hud-mail-subject = Subject
+hud-mail-body = Message
+hud-mail-coins_input = Coins
+hud-mail-attachments = Attachments
+hud-mail-send = Send
+hud-mail-sent = Your mail was sent.
+hud-mail-unread = { $count ->
+    [one] You have an unread mail
+   *[other] You have { $count } unread mail
+}
+hud-mail-error-unknown_recipient = There is no character with that name.
+hud-mail-error-ambiguous_recipient = Several characters have that name, the mail can't be delivered.
+hud-mail-error-self_recipient = You can't send mail to yourself.
+hud-mail-error-mailbox_full = The mailbox of the recipient is full.
+hud-mail-error-subject_too_long = The subject is too long.
+hud-mail-error-body_too_long = The message is too long.
+hud-mail-error-too_many_attachments = Too many items are attached.
+hud-mail-error-not_enough_coins = You don't have enough coins.
+hud-mail-error-inventory_full = Your inventory is full.
+hud-mail-error-has_attachments = Take the attachments before deleting the mail.
+hud-mail-error-cannot_return = This mail can't be returned.
+hud-mail-error-disabled = Mail isn't available on this server.
+hud-mail-error-not_found = The mail or item could not be found.
@@ -0,0 +1,32 @@
+hud-market-market = Market
+hud-market-browse = Browse
+hud-market-own = My listings
+hud-market-sell = Sell
+hud-market-empty = No items listed
+hud-market-seller = Seller: { $seller }
+hud-market-price = Price: { $price } coins
+hud-market-expires = { $days ->
+    [0] Expires today
+    [one] Expires in { $days } day
+   *[other] Expires in { $days } days
+}
+hud-market-buy = Buy
+hud-market-cancel = Take down
+hud-market-item = Item to sell
+hud-market-price_input = Price in coins
+hud-market-suggested_price = Local price: { $price } coins
+hud-market-fees = Listing costs { $listing_fee } coins, you receive { $proceeds } coins once it is sold.
+hud-market-post = List item
+hud-market-posted = Your item is now listed on the market.
+hud-market-sold_subject = Item sold
+hud-market-sold_body = Your listing was sold for { $price } coins, the market kept { $fee } coins.
+hud-market-expired_subject = Listing expired
+hud-market-expired_body = Your item wasn't sold and is returned to you.
+hud-market-error-too_many_listings = You have listed too many items already.
+hud-market-error-invalid_price = This price isn't allowed.
+hud-market-error-cannot_sell_coins = Coins can't be listed on the market.
+hud-market-error-not_enough_coins = You don't have enough coins.
+hud-market-error-inventory_full = Your inventory is full.
+hud-market-error-own_listing = You can't buy your own items.
+hud-market-error-disabled = The market isn't available on this server.
+hud-market-error-not_found = The listing or item could not be found.
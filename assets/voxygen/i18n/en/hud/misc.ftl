@@ -54,6 +54,9 @@ hud-open = Open
 hud-steal = Steal
 hud-use = Use
 hud-read = Read
+hud-open-storage = Open storage
+hud-open-mailbox = Open mailbox
+hud-open-market = Open market
 hud-unlock-requires = Open with { $item }
 hud-steal-requires = Steal with { $item }
 hud-unlock-consumes = Use { $item } to open
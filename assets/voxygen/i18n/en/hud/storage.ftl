@@ -0,0 +1 @@
+hud-storage-storage_window = Storage
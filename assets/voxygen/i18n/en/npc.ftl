@@ -414,6 +414,12 @@ npc-question-general =
     .a0 = What can I do for you?
     .a1 = G'day. What's the matter?
     .a2 = Hello there, what can I help you with?
+npc-question-general-admired =
+    .a0 = It's an honour! What can I do for you?
+    .a1 = Everyone's been talking about you! How can I help?
+npc-question-general-distrusted =
+    .a0 = Oh, it's you. What do you want?
+    .a1 = Make it quick, I've heard about you.
 
 npc-info-current_site =
     .a0 = This is { $site }.
@@ -458,6 +464,9 @@ npc-response-quest-nothing =
     .a0 = Nothing right now, sorry.
     .a1 = Not right now.
     .a2 = Maybe later!
+npc-response-quest-reputation_too_low =
+    .a0 = After what I've heard about you? I don't think so.
+    .a1 = I wouldn't trust you with anything.
 npc-response-quest-rejected =
     .a0 = Whatever! You do you.
     .a1 = No skin off my nose.
@@ -512,6 +521,12 @@ npc-response-ambivalent_you =
 npc-response-dislike_you =
     .a0 = I don't like you much.
     .a1 = You don't seem very nice.
+npc-response-reputation =
+    .a0 = Folks in { $site } consider you { $level }.
+    .a1 = Around { $site }, you're known as { $level }.
+npc-response-reputation-homeless =
+    .a0 = People like me consider you { $level }.
+    .a1 = Where I come from, you're known as { $level }.
 
 npc-question-directions =
     .a0 = Where do you want to go?
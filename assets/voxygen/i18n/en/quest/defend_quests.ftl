@@ -0,0 +1,13 @@
+## Defend quests ask to protect a site from raiders until the raid is over.
+npc-response-quest-defend-raid =
+    .a0 = Raiders are coming! I've seen { $count } of them heading this way.
+    .a1 = There's a band of { $count } raiders approaching, they'll be here any moment!
+npc-response-quest-defend-ask = Will you help us defend { $site }? I'll pay you { $coins } coins once the raid is over.
+npc-response-quest-defend-start =
+    .a0 = Thank you! Stay close to the village until they're gone.
+    .a1 = Be careful! Come back to me once the raiders have left.
+npc-response-quest-defend-not-over = The raid isn't over yet! Keep the raiders away from here.
+npc-response-quest-defend-thanks =
+    .a0 = We're safe again, thanks to you!
+    .a1 = You fought bravely. Thank you for protecting us!
+dialogue-question-quest-defend-claim = The raid is over.
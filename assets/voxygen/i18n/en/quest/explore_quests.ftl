@@ -0,0 +1,17 @@
+## Explore quests ask to visit a place and report back.
+npc-response-quest-explore-ask =
+    .a0 = I've been wondering what it's like at { $place } these days. Would you go there and tell me about it? I'll pay you { $coins } coins.
+    .a1 = Nobody has been to { $place } in a while. Could you take a look around there for { $coins } coins?
+npc-response-quest-explore-start = I've marked the place on your map. Come back to me once you've been there.
+npc-response-quest-explore-where = I've marked { $place } on your map again.
+npc-response-quest-explore-thanks =
+    .a0 = Fascinating! Thank you for taking a look.
+    .a1 = So that's what it's like there. Thank you!
+dialogue-question-quest-explore-claim = I've been to { $place }.
+dialogue-question-quest-explore-where = Where did you want me to go?
+hud-map-explore-label = Explore { $place } for { $name }.
+
+## Names of spots that could be explored.
+spot-name-witch-house = Witch House
+spot-name-dwarven-grave = Dwarven Grave
+spot-name-myrmidon-temple = Myrmidon Temple
@@ -0,0 +1,32 @@
+## These are all common dialogue entries that are shared across all of the
+## gather quests.
+npc-response-quest-gather-start =
+    .a0 = Thank you! Bring them to me once you have them all.
+    .a1 = Wonderful, come back to me when you've collected them.
+npc-response-quest-gather-thanks =
+    .a0 = That's exactly what I needed, thank you!
+    .a1 = Perfect, these will do nicely.
+npc-response-quest-gather-insufficient-items = That's not enough, I asked you for { $amount }. Come back when you have them all.
+npc-response-quest-gather-what-is-needed = I asked you to bring me { $amount } of them.
+dialogue-question-quest-gather-claim = I've brought what you asked for.
+dialogue-question-quest-gather-what = What did you need again?
+
+## This section is reserved for the different items that can be asked for. Each
+## item needs a question asking for it, given the { $amount } and the { $coins }
+## paid for them, and a reminder of what is needed, given the { $amount }.
+##
+## First up is Animal Hide.
+npc-response-quest-gather-animal-hide = My stock of hides is running low. Could you bring me { $amount } Animal Hides? I'll pay you { $coins } coins for them.
+npc-response-quest-gather-animal-hide-what-is-needed = I need { $amount } Animal Hides. You can get them from hunting wild animals.
+
+## Next up is Copper Ore.
+npc-response-quest-gather-copper = I could use some copper. Would you mine { $amount } pieces of Copper Ore for me? I'll pay you { $coins } coins.
+npc-response-quest-gather-copper-what-is-needed = I need { $amount } pieces of Copper Ore. Look for it in caves and on mountainsides.
+
+## Next up are Twigs.
+npc-response-quest-gather-twigs = We're running out of kindling. Could you gather { $amount } Twigs for me? There's { $coins } coins in it for you.
+npc-response-quest-gather-twigs-what-is-needed = I need { $amount } Twigs. You'll find plenty of them lying around in forests.
+
+## Next up is Honey.
+npc-response-quest-gather-honey = I've been craving something sweet. If you bring me { $amount } Honey, I'll pay you { $coins } coins.
+npc-response-quest-gather-honey-what-is-needed = I need { $amount } Honey. Bees keep their hives in trees, mind the stings!
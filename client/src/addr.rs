@@ -1,4 +1,5 @@
-use std::net::SocketAddr;
+pub use network::NetworkConditions;
+use std::{net::SocketAddr, path::PathBuf};
 use tokio::net::lookup_host;
 use tracing::trace;
 
@@ -15,6 +16,12 @@ pub enum ConnectionArgs {
         hostname: String,
         prefer_ipv6: bool,
     },
+    ///hostname: `(hostname|ip):[<port>]`, TCP wrapped in TLS
+    Tls {
+        hostname: String,
+        prefer_ipv6: bool,
+        validate_tls: bool,
+    },
     /// SRV lookup
     ///
     /// SRV lookups can not contain a port, but will be able to connect to
@@ -27,6 +34,21 @@ pub enum ConnectionArgs {
         use_quic: bool,
     },
     Mpsc(u64),
+    /// Connect via `args` while simulating bad network conditions for all
+    /// data the client sends, for testing only
+    Simulated {
+        args: Box<ConnectionArgs>,
+        conditions: NetworkConditions,
+    },
+    /// Connect via `args` and record every message received from the server
+    /// to the file at `path`
+    Recorded {
+        args: Box<ConnectionArgs>,
+        path: PathBuf,
+    },
+    /// Replay a session recorded with [`ConnectionArgs::Recorded`] from the
+    /// file at the given path, without any server
+    Replay(PathBuf),
 }
 
 impl ConnectionArgs {
@@ -60,6 +82,23 @@ pub(crate) async fn resolve(
     }
 }
 
+/// Strips the port and ipv6 brackets from `(hostname|ip):[<port>]`, leaving
+/// the name a server certificate is validated against.
+pub(crate) fn host_without_port(address: &str) -> &str {
+    let host = match address.rsplit_once(':') {
+        Some((host, port)) if port.parse::<u16>().is_ok() => {
+            // a bare ipv6 address without brackets has no port
+            if host.contains(':') && !host.starts_with('[') {
+                address
+            } else {
+                host
+            }
+        },
+        _ => address,
+    };
+    host.trim_start_matches('[').trim_end_matches(']')
+}
+
 pub(crate) async fn try_connect<F>(
     network: &network::Network,
     address: &str,
@@ -117,6 +156,15 @@ mod tests {
         assert_eq!(args[0].port(), 666);
     }
 
+    #[test]
+    fn strip_port() {
+        assert_eq!(host_without_port("veloren.net"), "veloren.net");
+        assert_eq!(host_without_port("veloren.net:14004"), "veloren.net");
+        assert_eq!(host_without_port("127.0.0.1:666"), "127.0.0.1");
+        assert_eq!(host_without_port("[::1]:14004"), "::1");
+        assert_eq!(host_without_port("::1"), "::1");
+    }
+
     #[tokio::test]
     async fn resolve_ipv6() {
         let args = resolve("localhost", true).await.expect("resolve failed");
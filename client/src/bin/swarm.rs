@@ -15,7 +15,10 @@ use std::{
 };
 use tokio::runtime::Runtime;
 use vek::*;
-use veloren_client::{Client, ClientType, addr::ConnectionArgs};
+use veloren_client::{
+    Client, ClientType,
+    addr::{ConnectionArgs, NetworkConditions},
+};
 
 const CHUNK_SIZE: f32 = TerrainChunkSize::RECT_SIZE.x as f32;
 
@@ -33,6 +36,38 @@ struct Opt {
     /// Whether the clients should move
     #[arg(short, long)]
     movement: bool,
+    /// Simulated latency in ms added to everything the clients send
+    #[arg(long)]
+    latency: Option<u64>,
+    /// Simulated random latency in ms added on top of `latency`
+    #[arg(long)]
+    jitter: Option<u64>,
+    /// Simulated bandwidth limit of each client in bytes per second
+    #[arg(long)]
+    bandwidth: Option<u64>,
+    /// Simulated ratio of dropped messages on unreliable streams
+    #[arg(long)]
+    drop: Option<f32>,
+}
+
+impl Opt {
+    fn network_conditions(&self, seed: u64) -> Option<NetworkConditions> {
+        if self.latency.is_none()
+            && self.jitter.is_none()
+            && self.bandwidth.is_none()
+            && self.drop.is_none()
+        {
+            return None;
+        }
+        Some(NetworkConditions {
+            latency: Duration::from_millis(self.latency.unwrap_or(0)),
+            jitter: Duration::from_millis(self.jitter.unwrap_or(0)),
+            bandwidth: self.bandwidth,
+            drop_ratio: self.drop.unwrap_or(0.0),
+            reorder: false,
+            seed,
+        })
+    }
 }
 
 fn main() {
@@ -114,6 +149,14 @@ fn run_client(
             prefer_ipv6: false,
             hostname: "localhost".into(),
         };
+        // every client gets its own, but reproducible, jitter and drops
+        let addr = match opt.network_conditions(index as u64) {
+            Some(conditions) => ConnectionArgs::Simulated {
+                args: Box::new(addr),
+                conditions,
+            },
+            None => addr,
+        };
         let runtime_clone = Arc::clone(&runtime);
         // NOTE: use a no-auth server
         match runtime.block_on(Client::new(
@@ -0,0 +1,196 @@
+//! Versions of the terrain chunks the client has, so the server can send
+//! `TerrainChunkDelta`s instead of full chunks.
+//!
+//! Every chunk the server sends comes with a version, which advances with
+//! every block update. If an update is missed the version of the chunk is no
+//! longer known, and the next delta for it is answered by requesting the full
+//! chunk again.
+
+use common::{
+    terrain::{TerrainChunk, TerrainGrid, block::Block},
+    vol::WriteVol,
+};
+use hashbrown::HashMap;
+use std::{collections::VecDeque, sync::Arc};
+use vek::*;
+
+/// How many unloaded chunks are kept around in case we get back to them
+const MAX_UNLOADED_CHUNKS: usize = 64;
+
+pub(crate) enum Delta {
+    /// The delta was applied, the chunk has to be inserted into the terrain
+    Applied(Arc<TerrainChunk>),
+    /// The version the delta is based on isn't known
+    Missing,
+}
+
+#[derive(Default)]
+pub(crate) struct ChunkVersions {
+    /// Versions of the loaded chunks, as far as they are known
+    loaded: HashMap<Vec2<i32>, u64>,
+    /// Recently unloaded chunks, newest last
+    unloaded: VecDeque<(Vec2<i32>, u64, Arc<TerrainChunk>)>,
+}
+
+impl ChunkVersions {
+    pub fn clear(&mut self) {
+        self.loaded.clear();
+        self.unloaded.clear();
+    }
+
+    /// Keeps a chunk that is being unloaded, if its version is known
+    pub fn unload(&mut self, key: Vec2<i32>, chunk: Option<Arc<TerrainChunk>>) {
+        if let (Some(version), Some(chunk)) = (self.loaded.remove(&key), chunk) {
+            self.unloaded.retain(|(k, _, _)| *k != key);
+            if self.unloaded.len() >= MAX_UNLOADED_CHUNKS {
+                self.unloaded.pop_front();
+            }
+            self.unloaded.push_back((key, version, chunk));
+        }
+    }
+
+    /// The version of an unloaded chunk we still have, to be sent along with a
+    /// request for it
+    pub fn known_version(&self, key: Vec2<i32>) -> Option<u64> {
+        self.unloaded
+            .iter()
+            .find(|(k, _, _)| *k == key)
+            .map(|(_, version, _)| *version)
+    }
+
+    /// A full chunk was received
+    pub fn received(&mut self, key: Vec2<i32>, version: u64) {
+        self.unloaded.retain(|(k, _, _)| *k != key);
+        self.loaded.insert(key, version);
+    }
+
+    /// Applies a delta either to the `loaded` chunk or to an unloaded one,
+    /// whichever has the version it is based on.
+    pub fn apply_delta(
+        &mut self,
+        key: Vec2<i32>,
+        base_version: u64,
+        version: u64,
+        blocks: Option<HashMap<Vec3<i32>, Block>>,
+        loaded: Option<&Arc<TerrainChunk>>,
+    ) -> Delta {
+        let base = loaded
+            .filter(|_| self.loaded.get(&key) == Some(&base_version))
+            .cloned()
+            .or_else(|| {
+                self.unloaded
+                    .iter()
+                    .position(|(k, v, _)| *k == key && *v == base_version)
+                    .and_then(|i| self.unloaded.remove(i))
+                    .map(|(_, _, chunk)| chunk)
+            });
+        self.unloaded.retain(|(k, _, _)| *k != key);
+
+        if let (Some(chunk), Some(blocks)) = (base, blocks) {
+            let mut chunk = Arc::unwrap_or_clone(chunk);
+            for (pos, block) in blocks {
+                let _ = chunk.set(TerrainGrid::chunk_offs(pos), block);
+            }
+            self.loaded.insert(key, version);
+            Delta::Applied(Arc::new(chunk))
+        } else {
+            self.loaded.remove(&key);
+            Delta::Missing
+        }
+    }
+
+    /// Advances the versions of the chunks touched by block updates, given as
+    /// `(previous, new)` version
+    pub fn block_updates(&mut self, versions: HashMap<Vec2<i32>, (u64, u64)>) {
+        for (key, (previous, new)) in versions {
+            // If we missed a change we no longer know the version of the chunk
+            if let Some(version) = self.loaded.get_mut(&key) {
+                if *version == previous {
+                    *version = new;
+                } else {
+                    self.loaded.remove(&key);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::{
+        terrain::{BlockKind, TerrainChunkMeta},
+        vol::ReadVol,
+    };
+
+    fn chunk() -> Arc<TerrainChunk> {
+        Arc::new(TerrainChunk::new(
+            0,
+            Block::new(BlockKind::Rock, Default::default()),
+            Block::empty(),
+            TerrainChunkMeta::void(),
+        ))
+    }
+
+    fn rock_at(pos: Vec3<i32>) -> Option<HashMap<Vec3<i32>, Block>> {
+        Some([(pos, Block::new(BlockKind::Rock, Default::default()))].into())
+    }
+
+    #[test]
+    fn apply_delta_to_loaded_and_unloaded_chunks() {
+        let mut versions = ChunkVersions::default();
+        let key = Vec2::new(1, 0);
+        let pos = TerrainGrid::key_chunk(key).with_z(5);
+        let offs = TerrainGrid::chunk_offs(pos);
+        let loaded = chunk();
+        versions.received(key, 1);
+
+        let Delta::Applied(changed) = versions.apply_delta(key, 1, 2, rock_at(pos), Some(&loaded))
+        else {
+            panic!("The delta should apply to the loaded chunk");
+        };
+        assert_eq!(changed.get(offs).unwrap().kind(), BlockKind::Rock);
+        assert!(loaded.get(offs).unwrap().kind().is_air());
+
+        versions.block_updates([(key, (2, 3))].into());
+        versions.unload(key, Some(changed));
+        assert_eq!(versions.known_version(key), Some(3));
+        assert!(matches!(
+            versions.apply_delta(key, 3, 4, rock_at(pos + Vec3::unit_z()), None),
+            Delta::Applied(_)
+        ));
+        assert_eq!(versions.known_version(key), None);
+    }
+
+    #[test]
+    fn missing_base_falls_back_to_full_chunk() {
+        let mut versions = ChunkVersions::default();
+        let key = Vec2::zero();
+        let pos = Vec3::new(0, 0, 5);
+        let loaded = chunk();
+        versions.received(key, 1);
+
+        // A missed block update makes the version unknown
+        versions.block_updates([(key, (2, 3))].into());
+        assert!(matches!(
+            versions.apply_delta(key, 3, 4, rock_at(pos), Some(&loaded)),
+            Delta::Missing
+        ));
+
+        // Unloaded chunks at another version can't be used either
+        versions.received(key, 5);
+        versions.unload(key, Some(chunk()));
+        assert!(matches!(
+            versions.apply_delta(key, 4, 6, rock_at(pos), None),
+            Delta::Missing
+        ));
+        assert_eq!(versions.known_version(key), None);
+
+        // A full chunk makes the version known again
+        versions.received(key, 6);
+        assert!(matches!(
+            versions.apply_delta(key, 6, 7, rock_at(pos), Some(&loaded)),
+            Delta::Applied(_)
+        ));
+    }
+}
@@ -2,7 +2,9 @@
 #![deny(clippy::clone_on_ref_ptr)]
 
 pub mod addr;
+mod chunk_versions;
 pub mod error;
+mod replay;
 
 // Reexports
 pub use crate::error::Error;
@@ -12,23 +14,31 @@ pub use specs::{
     Builder, DispatcherBuilder, Entity as EcsEntity, Join, LendJoin, ReadStorage, World, WorldExt,
 };
 
-use crate::addr::ConnectionArgs;
+use crate::{
+    addr::ConnectionArgs,
+    chunk_versions::{ChunkVersions, Delta},
+    replay::{RecordedKind, SessionRecorder},
+};
 use byteorder::{ByteOrder, LittleEndian};
 use common::{
     character::{CharacterId, CharacterItem},
     comp::{
         self, AdminRole, CharacterState, ChatMode, ControlAction, ControlEvent, Controller,
         ControllerInputs, GroupManip, Hardcore, InputKind, InventoryAction, InventoryEvent,
-        InventoryUpdateEvent, MapMarkerChange, PresenceKind, UtteranceKind,
+        InventoryUpdateEvent, MailAction, MapMarkerChange, MarketAction, PresenceKind,
+        UtteranceKind,
         chat::KillSource,
         controller::CraftEvent,
         gizmos::Gizmos,
         group,
+        guild::GuildRoster,
         inventory::{
             InventorySortOrder,
             item::{ItemKind, modular, tool},
         },
         invite::{InviteKind, InviteResponse},
+        mail::{Mail, MailDraft, MailId},
+        market::{ListingId, Market},
         skills::Skill,
         slot::{EquipSlot, InvSlotId, Slot},
     },
@@ -140,6 +150,8 @@ pub enum Event {
 #[derive(Debug)]
 pub enum UserNotification {
     WaypointUpdated,
+    /// Number of unread mail
+    UnreadMail(u32),
 }
 
 #[derive(Debug)]
@@ -319,6 +331,13 @@ pub struct Client {
     group_leader: Option<Uid>,
     // Note: potentially representable as a client only component
     group_members: HashMap<Uid, group::Role>,
+    guild: Option<GuildRoster>,
+    // The personal storage while it is open, and where it was opened
+    storage: Option<(Vec3<i32>, comp::Inventory)>,
+    // The mailbox while it is open, and where it was opened
+    mailbox: Option<(Vec3<i32>, Vec<Mail>)>,
+    // The market while it is open, and where it was opened
+    market: Option<(Vec3<i32>, Market)>,
     // Pending invites that this client has sent out
     pending_invites: HashSet<Uid>,
     // The pending trade the client is involved in, and it's id
@@ -333,6 +352,8 @@ pub struct Client {
     character_screen_stream: Stream,
     in_game_stream: Stream,
     terrain_stream: Stream,
+    /// Records every message received from the server, if enabled
+    recorder: Option<SessionRecorder>,
 
     client_timeout: Duration,
     last_server_ping: f64,
@@ -353,6 +374,7 @@ pub struct Client {
     loaded_distance: f32,
 
     pending_chunks: HashMap<Vec2<i32>, Instant>,
+    chunk_versions: ChunkVersions,
     target_time_of_day: Option<TimeOfDay>,
     dt_adjustment: f64,
 
@@ -371,6 +393,66 @@ pub struct CharacterList {
     pub loading: bool,
 }
 
+/// Accepts any server certificate, used when the user disabled validation of
+/// the server identity
+#[derive(Debug)]
+struct Verifier;
+impl rustls::client::danger::ServerCertVerifier for Verifier {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &rustls::pki_types::CertificateDer<'_>,
+        _intermediates: &[rustls::pki_types::CertificateDer<'_>],
+        _server_name: &rustls::pki_types::ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: rustls::pki_types::UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        _message: &[u8],
+        _cert: &rustls::pki_types::CertificateDer<'_>,
+        _dss: &rustls::DigitallySignedStruct,
+    ) -> Result<rustls::client::danger::HandshakeSignatureValid, rustls::Error> {
+        Ok(rustls::client::danger::HandshakeSignatureValid::assertion())
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        _message: &[u8],
+        _cert: &rustls::pki_types::CertificateDer<'_>,
+        _dss: &rustls::DigitallySignedStruct,
+    ) -> Result<rustls::client::danger::HandshakeSignatureValid, rustls::Error> {
+        Ok(rustls::client::danger::HandshakeSignatureValid::assertion())
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<rustls::SignatureScheme> {
+        vec![
+            rustls::SignatureScheme::RSA_PKCS1_SHA1,
+            rustls::SignatureScheme::ECDSA_SHA1_Legacy,
+            rustls::SignatureScheme::RSA_PKCS1_SHA256,
+            rustls::SignatureScheme::ECDSA_NISTP256_SHA256,
+            rustls::SignatureScheme::RSA_PKCS1_SHA384,
+            rustls::SignatureScheme::ECDSA_NISTP384_SHA384,
+            rustls::SignatureScheme::RSA_PKCS1_SHA512,
+            rustls::SignatureScheme::ECDSA_NISTP521_SHA512,
+            rustls::SignatureScheme::RSA_PSS_SHA256,
+            rustls::SignatureScheme::RSA_PSS_SHA384,
+            rustls::SignatureScheme::RSA_PSS_SHA512,
+            rustls::SignatureScheme::ED25519,
+            rustls::SignatureScheme::ED448,
+        ]
+    }
+}
+
+fn unverified_client_config() -> rustls::ClientConfig {
+    rustls::ClientConfig::builder()
+        .dangerous()
+        .with_custom_certificate_verifier(Arc::new(Verifier))
+        .with_no_client_auth()
+}
+
 async fn connect_quic(
     network: &Network,
     hostname: String,
@@ -385,63 +467,7 @@ async fn connect_quic(
             "skipping validation of server identity. There is no guarantee that the server you're \
              connected to is the one you expect to be connecting to."
         );
-        #[derive(Debug)]
-        struct Verifier;
-        impl rustls::client::danger::ServerCertVerifier for Verifier {
-            fn verify_server_cert(
-                &self,
-                _end_entity: &rustls::pki_types::CertificateDer<'_>,
-                _intermediates: &[rustls::pki_types::CertificateDer<'_>],
-                _server_name: &rustls::pki_types::ServerName<'_>,
-                _ocsp_response: &[u8],
-                _now: rustls::pki_types::UnixTime,
-            ) -> Result<ServerCertVerified, rustls::Error> {
-                Ok(ServerCertVerified::assertion())
-            }
-
-            fn verify_tls12_signature(
-                &self,
-                _message: &[u8],
-                _cert: &rustls::pki_types::CertificateDer<'_>,
-                _dss: &rustls::DigitallySignedStruct,
-            ) -> Result<rustls::client::danger::HandshakeSignatureValid, rustls::Error>
-            {
-                Ok(rustls::client::danger::HandshakeSignatureValid::assertion())
-            }
-
-            fn verify_tls13_signature(
-                &self,
-                _message: &[u8],
-                _cert: &rustls::pki_types::CertificateDer<'_>,
-                _dss: &rustls::DigitallySignedStruct,
-            ) -> Result<rustls::client::danger::HandshakeSignatureValid, rustls::Error>
-            {
-                Ok(rustls::client::danger::HandshakeSignatureValid::assertion())
-            }
-
-            fn supported_verify_schemes(&self) -> Vec<rustls::SignatureScheme> {
-                vec![
-                    rustls::SignatureScheme::RSA_PKCS1_SHA1,
-                    rustls::SignatureScheme::ECDSA_SHA1_Legacy,
-                    rustls::SignatureScheme::RSA_PKCS1_SHA256,
-                    rustls::SignatureScheme::ECDSA_NISTP256_SHA256,
-                    rustls::SignatureScheme::RSA_PKCS1_SHA384,
-                    rustls::SignatureScheme::ECDSA_NISTP384_SHA384,
-                    rustls::SignatureScheme::RSA_PKCS1_SHA512,
-                    rustls::SignatureScheme::ECDSA_NISTP521_SHA512,
-                    rustls::SignatureScheme::RSA_PSS_SHA256,
-                    rustls::SignatureScheme::RSA_PSS_SHA384,
-                    rustls::SignatureScheme::RSA_PSS_SHA512,
-                    rustls::SignatureScheme::ED25519,
-                    rustls::SignatureScheme::ED448,
-                ]
-            }
-        }
-
-        let mut cfg = rustls::ClientConfig::builder()
-            .dangerous()
-            .with_custom_certificate_verifier(Arc::new(Verifier))
-            .with_no_client_auth();
+        let mut cfg = unverified_client_config();
         cfg.enable_early_data = true;
 
         quinn::ClientConfig::new(Arc::new(
@@ -455,6 +481,32 @@ async fn connect_quic(
     .await
 }
 
+async fn connect_tls(
+    network: &Network,
+    hostname: String,
+    override_port: Option<u16>,
+    prefer_ipv6: bool,
+    validate_tls: bool,
+) -> Result<network::Participant, crate::error::Error> {
+    let config = if validate_tls {
+        use rustls_platform_verifier::ConfigVerifierExt;
+        rustls::ClientConfig::with_platform_verifier()?
+    } else {
+        warn!(
+            "skipping validation of server identity. There is no guarantee that the server you're \
+             connected to is the one you expect to be connecting to."
+        );
+        unverified_client_config()
+    };
+    let config = Arc::new(config);
+    let server_name = addr::host_without_port(&hostname).to_owned();
+
+    addr::try_connect(network, &hostname, override_port, prefer_ipv6, |a| {
+        ConnectAddr::Tls(a, Arc::clone(&config), server_name.clone())
+    })
+    .await
+}
+
 impl Client {
     pub async fn new(
         addr: ConnectionArgs,
@@ -477,6 +529,23 @@ impl Client {
 
         init_stage_update(ClientInitStage::ConnectionEstablish);
 
+        let mut addr = addr;
+        let mut recorder = None;
+        let addr = loop {
+            addr = match addr {
+                ConnectionArgs::Simulated { args, conditions } => {
+                    warn!(?conditions, "Simulating network conditions");
+                    network.set_network_conditions(Some(conditions));
+                    *args
+                },
+                ConnectionArgs::Recorded { args, path } => {
+                    recorder = Some(SessionRecorder::create(&path)?);
+                    *args
+                },
+                addr => break addr,
+            };
+        };
+
         let mut participant = match addr {
             ConnectionArgs::Srv {
                 hostname,
@@ -613,6 +682,11 @@ impl Client {
             } => {
                 addr::try_connect(&network, &hostname, None, prefer_ipv6, ConnectAddr::Tcp).await?
             },
+            ConnectionArgs::Tls {
+                hostname,
+                prefer_ipv6,
+                validate_tls,
+            } => connect_tls(&network, hostname, None, prefer_ipv6, validate_tls).await?,
             ConnectionArgs::Quic {
                 hostname,
                 prefer_ipv6,
@@ -626,6 +700,13 @@ impl Client {
                 connect_quic(&network, hostname, None, prefer_ipv6, validate_tls).await?
             },
             ConnectionArgs::Mpsc(id) => network.connect(ConnectAddr::Mpsc(id)).await?,
+            ConnectionArgs::Replay(path) => {
+                let id = replay::start_replay(&path, &runtime).await?;
+                network.connect(ConnectAddr::Mpsc(id)).await?
+            },
+            ConnectionArgs::Simulated { .. } | ConnectionArgs::Recorded { .. } => {
+                unreachable!("unwrapped above")
+            },
         };
 
         let stream = participant.opened().await?;
@@ -638,6 +719,9 @@ impl Client {
         init_stage_update(ClientInitStage::WatingForServerVersion);
         register_stream.send(client_type)?;
         let server_info: ServerInfo = register_stream.recv().await?;
+        if let Some(recorder) = &mut recorder {
+            recorder.record(RecordedKind::Info, &server_info);
+        }
         if server_info.git_hash != *common::util::GIT_HASH
             || server_info.git_timestamp != *common::util::GIT_TIMESTAMP
         {
@@ -663,12 +747,24 @@ impl Client {
             auth_trusted,
             &server_info,
             &mut register_stream,
+            recorder.as_mut(),
         )
         .await?;
 
         init_stage_update(ClientInitStage::LoadingInitData);
         // Wait for initial sync
         let mut ping_interval = tokio::time::interval(Duration::from_secs(1));
+        let server_init: ServerInit = loop {
+            tokio::select! {
+                // Spawn in a blocking thread (leaving the network thread free).  This is mostly
+                // useful for bots.
+                res = register_stream.recv() => break res?,
+                _ = ping_interval.tick() => ping_stream.send(PingMsg::Ping)?,
+            }
+        };
+        if let Some(recorder) = &mut recorder {
+            recorder.record(RecordedKind::Init, &server_init);
+        }
         let ServerInit::GameSync {
             entity_package,
             time_of_day,
@@ -683,14 +779,7 @@ impl Client {
             description,
             active_plugins: _active_plugins,
             role,
-        } = loop {
-            tokio::select! {
-                // Spawn in a blocking thread (leaving the network thread free).  This is mostly
-                // useful for bots.
-                res = register_stream.recv() => break res?,
-                _ = ping_interval.tick() => ping_stream.send(PingMsg::Ping)?,
-            }
-        };
+        } = server_init;
 
         init_stage_update(ClientInitStage::StartingClient);
         // Spawn in a blocking thread (leaving the network thread free).  This is mostly
@@ -717,7 +806,7 @@ impl Client {
                     add_foreign_systems(dispatch_builder);
                 },
                 #[cfg(feature = "plugins")]
-                common_state::plugin::PluginMgr::from_asset_or_default(),
+                common_state::plugin::PluginMgr::from_asset_or_default(GameMode::Client),
             );
 
             #[cfg_attr(not(feature = "plugins"), expect(unused_mut))]
@@ -1092,6 +1181,10 @@ impl Client {
             invite: None,
             group_leader: None,
             group_members: HashMap::new(),
+            guild: None,
+            storage: None,
+            mailbox: None,
+            market: None,
             pending_invites: HashSet::new(),
             pending_trade: None,
             waypoint: None,
@@ -1104,6 +1197,7 @@ impl Client {
             character_screen_stream,
             in_game_stream,
             terrain_stream,
+            recorder,
 
             client_timeout,
 
@@ -1123,6 +1217,7 @@ impl Client {
             loaded_distance: 0.0,
 
             pending_chunks: HashMap::new(),
+            chunk_versions: ChunkVersions::default(),
             target_time_of_day: None,
             dt_adjustment: 1.0,
 
@@ -1140,6 +1235,7 @@ impl Client {
         mut auth_trusted: impl FnMut(&str) -> bool,
         server_info: &ServerInfo,
         register_stream: &mut Stream,
+        recorder: Option<&mut SessionRecorder>,
     ) -> Result<(), Error> {
         // Authentication
         let token_or_username = match &server_info.auth_provider {
@@ -1179,7 +1275,11 @@ impl Client {
             locale,
         })?;
 
-        match register_stream.recv::<ServerRegisterAnswer>().await? {
+        let answer: ServerRegisterAnswer = register_stream.recv().await?;
+        if let Some(recorder) = recorder {
+            recorder.record(RecordedKind::RegisterAnswer, &answer);
+        }
+        match answer {
             Err(RegisterError::AuthError(err)) => Err(Error::AuthErr(err)),
             Err(RegisterError::InvalidCharacter) => Err(Error::InvalidCharacter),
             Err(RegisterError::NotOnWhitelist) => Err(Error::NotOnWhitelist),
@@ -1517,6 +1617,92 @@ impl Client {
         }
     }
 
+    /// Opens the personal storage at the storage sprite at `pos`
+    pub fn open_storage(&mut self, pos: Vec3<i32>) {
+        self.send_msg(ClientGeneral::ControlEvent(ControlEvent::InventoryEvent(
+            InventoryEvent::OpenStorage(pos),
+        )));
+    }
+
+    pub fn close_storage(&mut self) {
+        if self.storage.take().is_some() {
+            self.send_msg(ClientGeneral::ControlEvent(ControlEvent::InventoryEvent(
+                InventoryEvent::CloseStorage,
+            )));
+        }
+    }
+
+    /// Moves an item from the inventory slot into the storage slot
+    pub fn store(&mut self, inv_slot: InvSlotId, storage_slot: InvSlotId) {
+        self.send_msg(ClientGeneral::ControlEvent(ControlEvent::InventoryEvent(
+            InventoryEvent::Store(inv_slot, storage_slot),
+        )));
+    }
+
+    /// Moves an item from the storage slot into the inventory slot
+    pub fn retrieve(&mut self, storage_slot: InvSlotId, inv_slot: InvSlotId) {
+        self.send_msg(ClientGeneral::ControlEvent(ControlEvent::InventoryEvent(
+            InventoryEvent::Retrieve(storage_slot, inv_slot),
+        )));
+    }
+
+    pub fn swap_storage_slots(&mut self, a: InvSlotId, b: InvSlotId) {
+        self.send_msg(ClientGeneral::ControlEvent(ControlEvent::InventoryEvent(
+            InventoryEvent::StorageSwap(a, b),
+        )));
+    }
+
+    fn mail_action(&mut self, action: MailAction) {
+        self.send_msg(ClientGeneral::ControlEvent(ControlEvent::Mail(action)));
+    }
+
+    /// Opens the mailbox at the mailbox sprite at `pos`
+    pub fn open_mailbox(&mut self, pos: Vec3<i32>) { self.mail_action(MailAction::Open(pos)); }
+
+    pub fn close_mailbox(&mut self) {
+        if self.mailbox.take().is_some() {
+            self.mail_action(MailAction::Close);
+        }
+    }
+
+    pub fn send_mail(&mut self, draft: MailDraft) { self.mail_action(MailAction::Send(draft)); }
+
+    pub fn mark_mail_read(&mut self, id: MailId) { self.mail_action(MailAction::MarkRead(id)); }
+
+    /// Moves the coins and items attached to the mail into the inventory
+    pub fn take_mail_attachments(&mut self, id: MailId) {
+        self.mail_action(MailAction::TakeAttachments(id));
+    }
+
+    pub fn return_mail(&mut self, id: MailId) { self.mail_action(MailAction::Return(id)); }
+
+    pub fn delete_mail(&mut self, id: MailId) { self.mail_action(MailAction::Delete(id)); }
+
+    fn market_action(&mut self, action: MarketAction) {
+        self.send_msg(ClientGeneral::ControlEvent(ControlEvent::Market(action)));
+    }
+
+    /// Opens the market at the market board sprite at `pos`
+    pub fn open_market(&mut self, pos: Vec3<i32>) { self.market_action(MarketAction::Open(pos)); }
+
+    pub fn close_market(&mut self) {
+        if self.market.take().is_some() {
+            self.market_action(MarketAction::Close);
+        }
+    }
+
+    /// Lists the item in the inventory slot for sale, the listing fee is taken
+    /// from the inventory
+    pub fn post_listing(&mut self, slot: InvSlotId, price: u32) {
+        self.market_action(MarketAction::Post { slot, price });
+    }
+
+    pub fn buy_listing(&mut self, id: ListingId) { self.market_action(MarketAction::Buy(id)); }
+
+    pub fn cancel_listing(&mut self, id: ListingId) {
+        self.market_action(MarketAction::Cancel(id));
+    }
+
     pub fn pick_up(&mut self, entity: EcsEntity) {
         // Get the health component from the entity
 
@@ -1828,6 +2014,29 @@ impl Client {
 
     pub fn group_members(&self) -> &HashMap<Uid, group::Role> { &self.group_members }
 
+    /// The guild of the character, including all of its members
+    pub fn guild(&self) -> Option<&GuildRoster> { self.guild.as_ref() }
+
+    /// The position of the storage sprite and the content of the personal
+    /// storage, while it is open
+    pub fn storage(&self) -> Option<(Vec3<i32>, &comp::Inventory)> {
+        self.storage.as_ref().map(|(pos, storage)| (*pos, storage))
+    }
+
+    /// The position of the mailbox sprite and the mail of the character, newest
+    /// first, while the mailbox is open
+    pub fn mailbox(&self) -> Option<(Vec3<i32>, &[Mail])> {
+        self.mailbox
+            .as_ref()
+            .map(|(pos, mailbox)| (*pos, mailbox.as_slice()))
+    }
+
+    /// The position of the market board sprite and the market, while it is
+    /// open
+    pub fn market(&self) -> Option<(Vec3<i32>, &Market)> {
+        self.market.as_ref().map(|(pos, market)| (*pos, market))
+    }
+
     pub fn pending_invites(&self) -> &HashSet<Uid> { &self.pending_invites }
 
     pub fn pending_trade(&self) -> &Option<(TradeId, PendingTrade, Option<SitePrices>)> {
@@ -2251,6 +2460,7 @@ impl Client {
     pub fn clear_terrain(&mut self) {
         self.state.clear_terrain();
         self.pending_chunks.clear();
+        self.chunk_versions.clear();
     }
 
     pub fn place_block(&mut self, pos: Vec3<i32>, block: Block) {
@@ -2550,6 +2760,9 @@ impl Client {
                 }
             });
             for key in chunks_to_remove {
+                // Keep the chunk around for a while in case we get back to it
+                let chunk = self.state.terrain().get_key_arc_real(key).cloned();
+                self.chunk_versions.unload(key, chunk);
                 self.state.remove_chunk(key);
             }
 
@@ -2607,6 +2820,7 @@ impl Client {
                                 {
                                     self.send_msg_err(ClientGeneral::TerrainChunkRequest {
                                         key: *key,
+                                        known_version: self.chunk_versions.known_version(*key),
                                     })?;
                                     current_tick_send_chunk_requests += 1;
                                     self.pending_chunks.insert(*key, Instant::now());
@@ -2864,17 +3078,32 @@ impl Client {
                         .delete_entity_and_clear_uid_mapping(entity_uid);
                 }
             },
-            ServerGeneral::Notification(n) => {
-                let Notification::WaypointSaved { location_name } = n.clone();
-                self.waypoint = Some(location_name);
+            ServerGeneral::Notification(n) => match n {
+                Notification::WaypointSaved { location_name } => {
+                    self.waypoint = Some(location_name);
 
-                frontend_events.push(Event::Notification(UserNotification::WaypointUpdated));
+                    frontend_events.push(Event::Notification(UserNotification::WaypointUpdated));
+                },
+                Notification::UnreadMail { count } => {
+                    frontend_events.push(Event::Notification(UserNotification::UnreadMail(count)));
+                },
             },
             ServerGeneral::PluginData(d) => {
                 let plugin_len = d.len();
                 tracing::info!(?plugin_len, "plugin data");
                 frontend_events.push(Event::PluginDataReceived(d));
             },
+            #[cfg_attr(not(feature = "plugins"), expect(unused_variables))]
+            ServerGeneral::PluginReloaded { old, new } => {
+                #[cfg(feature = "plugins")]
+                {
+                    tracing::info!("Server reloaded plugin {old:x?}, requesting {new:x?}");
+                    self.state.ecs().write_resource::<PluginMgr>().unload(&old);
+                    if self.missing_plugins.insert(new) {
+                        self.send_msg_err(ClientGeneral::RequestPlugins(vec![new]))?;
+                    }
+                }
+            },
             ServerGeneral::SetPlayerRole(role) => {
                 debug!(?role, "Updating client role");
                 self.role = role;
@@ -3005,6 +3234,23 @@ impl Client {
             ServerGeneral::GroupInventoryUpdate(item, uid) => {
                 frontend_events.push(Event::GroupInventoryUpdate(item, uid));
             },
+            ServerGeneral::GuildUpdate(roster) => {
+                if self.guild.is_none() && roster.is_some() {
+                    frontend_events.push(Event::Chat(
+                        comp::ChatType::Meta.into_msg(Content::localized("hud-guild-chat_hint")),
+                    ));
+                }
+                self.guild = roster;
+            },
+            ServerGeneral::StorageUpdate(storage) => {
+                self.storage = storage;
+            },
+            ServerGeneral::MailboxUpdate(mailbox) => {
+                self.mailbox = mailbox;
+            },
+            ServerGeneral::MarketUpdate(market) => {
+                self.market = market;
+            },
             // Cleanup for when the client goes back to the `presence = None`
             ServerGeneral::ExitInGameSuccess => {
                 self.presence = None;
@@ -3103,22 +3349,60 @@ impl Client {
     fn handle_server_terrain_msg(&mut self, msg: ServerGeneral) -> Result<(), Error> {
         prof_span!("handle_server_terrain_mgs");
         match msg {
-            ServerGeneral::TerrainChunkUpdate { key, chunk } => {
+            ServerGeneral::TerrainChunkUpdate {
+                key,
+                chunk,
+                version,
+            } => {
                 if let Some(chunk) = chunk.ok().and_then(|c| c.to_chunk()) {
                     self.state.insert_chunk(key, Arc::new(chunk));
+                    self.chunk_versions.received(key, version);
                 }
                 self.pending_chunks.remove(&key);
             },
+            ServerGeneral::TerrainChunkDelta {
+                key,
+                base_version,
+                version,
+                blocks,
+            } => {
+                let loaded = self.state.terrain().get_key_arc_real(key).cloned();
+                match self.chunk_versions.apply_delta(
+                    key,
+                    base_version,
+                    version,
+                    blocks.decompress(),
+                    loaded.as_ref(),
+                ) {
+                    Delta::Applied(chunk) => {
+                        self.state.insert_chunk(key, chunk);
+                        self.pending_chunks.remove(&key);
+                    },
+                    // We don't know the chunk at the version the delta is based on, so request
+                    // it in full if we need it
+                    Delta::Missing if loaded.is_some() => {
+                        self.send_msg_err(ClientGeneral::TerrainChunkRequest {
+                            key,
+                            known_version: None,
+                        })?;
+                        self.pending_chunks.insert(key, Instant::now());
+                    },
+                    Delta::Missing => {
+                        self.pending_chunks.remove(&key);
+                    },
+                }
+            },
             ServerGeneral::LodZoneUpdate { key, zone } => {
                 self.lod_zones.insert(key, zone);
                 self.lod_last_requested = None;
             },
-            ServerGeneral::TerrainBlockUpdates(blocks) => {
+            ServerGeneral::TerrainBlockUpdates { blocks, versions } => {
                 if let Some(mut blocks) = blocks.decompress() {
                     blocks.drain().for_each(|(pos, block)| {
                         self.state.set_block(pos, block);
                     });
                 }
+                self.chunk_versions.block_updates(versions);
             },
             _ => unreachable!("Not a terrain message"),
         }
@@ -3200,6 +3484,12 @@ impl Client {
         Ok(())
     }
 
+    fn record_msg(&mut self, kind: RecordedKind, msg: &ServerGeneral) {
+        if let Some(recorder) = &mut self.recorder {
+            recorder.record(kind, msg);
+        }
+    }
+
     fn handle_messages(&mut self, frontend_events: &mut Vec<Event>) -> Result<u64, Error> {
         let mut cnt = 0;
         #[cfg(feature = "tracy")]
@@ -3209,6 +3499,7 @@ impl Client {
 
             while let Some(msg) = self.general_stream.try_recv()? {
                 cnt += 1;
+                self.record_msg(RecordedKind::General, &msg);
                 self.handle_server_msg(frontend_events, msg)?;
             }
             while let Some(msg) = self.ping_stream.try_recv()? {
@@ -3217,6 +3508,7 @@ impl Client {
             }
             while let Some(msg) = self.character_screen_stream.try_recv()? {
                 cnt += 1;
+                self.record_msg(RecordedKind::CharacterScreen, &msg);
                 self.handle_server_character_screen_msg(frontend_events, msg)?;
             }
             while let Some(msg) = self.in_game_stream.try_recv()? {
@@ -3225,6 +3517,7 @@ impl Client {
                 {
                     ingame_cnt += 1;
                 }
+                self.record_msg(RecordedKind::InGame, &msg);
                 self.handle_server_in_game_msg(frontend_events, msg)?;
             }
             while let Some(msg) = self.terrain_stream.try_recv()? {
@@ -3235,6 +3528,7 @@ impl Client {
                         terrain_cnt += chunk.as_ref().map(|x| x.approx_len()).unwrap_or(0);
                     }
                 }
+                self.record_msg(RecordedKind::Terrain, &msg);
                 self.handle_server_terrain_msg(msg)?;
             }
 
@@ -3358,6 +3652,10 @@ impl Client {
     fn clean_state(&mut self) {
         // Clear pending trade
         self.pending_trade = None;
+        self.guild = None;
+        self.storage = None;
+        self.mailbox = None;
+        self.market = None;
 
         let client_uid = self.uid().expect("Client doesn't have a Uid!!!");
 
@@ -3440,7 +3738,9 @@ impl Client {
                     | KillSource::Other => (),
                 };
             },
-            comp::ChatType::Tell(from, to) | comp::ChatType::NpcTell(from, to) => {
+            comp::ChatType::Tell(from, to)
+            | comp::ChatType::NpcTell(from, to)
+            | comp::ChatType::Duel(from, to) => {
                 add_data_of(from);
                 add_data_of(to);
             },
@@ -3545,6 +3845,30 @@ impl Client {
         self.missing_plugins.len()
     }
 
+    /// Load a plugin the server sent after reloading it, see
+    /// [`ServerGeneral::PluginReloaded`]
+    #[cfg(feature = "plugins")]
+    pub fn load_reloaded_plugin(
+        &mut self,
+        config_dir: &std::path::Path,
+        data: Vec<u8>,
+    ) -> Result<PluginHash, common_state::plugin::errors::PluginError> {
+        let hash = {
+            let ecs = self.state.ecs();
+            let ecs_world = common_state::plugin::memory_manager::EcsWorld {
+                entities: &ecs.entities(),
+                health: ecs.read_component().into(),
+                uid: ecs.read_component().into(),
+                id_maps: &ecs.read_resource::<IdMaps>().into(),
+                player: ecs.read_component().into(),
+            };
+            ecs.write_resource::<PluginMgr>()
+                .cache_reloaded_server_plugin(&ecs_world, config_dir, data, GameMode::Client)?
+        };
+        self.plugin_received(hash);
+        Ok(hash)
+    }
+
     /// true if missing_plugins is not empty
     pub fn are_plugins_missing(&self) -> bool { !self.missing_plugins.is_empty() }
 
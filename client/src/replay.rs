@@ -0,0 +1,350 @@
+//! Recording of the messages a [`Client`] receives from the server and
+//! replaying them later without a server.
+//!
+//! A recording starts with a [`RecordingHeader`], followed by frames. Each
+//! frame consists of the milliseconds since the recording started plus the
+//! [`RecordedKind`], followed by the message itself. Everything is encoded with
+//! the same bincode configuration the network uses on the wire.
+//!
+//! Replaying starts a tiny in-process server on an mpsc channel which walks
+//! through the handshake and then sends the recorded messages on the same
+//! streams and with the same timing as they were originally received.
+//!
+//! [`Client`]: crate::Client
+
+use crate::error::Error;
+use bincode::{
+    config::legacy,
+    error::DecodeError,
+    serde::{decode_from_std_read, encode_into_std_write},
+};
+use common_net::msg::{
+    ClientRegister, ClientType, DisconnectReason, PingMsg, ServerGeneral, ServerInfo, ServerInit,
+    ServerRegisterAnswer,
+};
+use network::{ListenAddr, Network, Pid, Promises};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use std::{
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::Path,
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Duration, Instant},
+};
+use tokio::runtime::Runtime;
+use tracing::{debug, info, warn};
+
+const MAGIC: [u8; 4] = *b"VREC";
+const FORMAT_VERSION: u32 = 1;
+
+/// Mpsc ids used by replay servers, far away from the singleplayer one.
+const REPLAY_MPSC_BASE: u64 = 0x5245_504c_0000_0000;
+static NEXT_REPLAY_ID: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
+struct RecordingHeader {
+    magic: [u8; 4],
+    version: u32,
+    git_hash: u32,
+    git_timestamp: i64,
+}
+
+impl RecordingHeader {
+    fn current() -> Self {
+        Self {
+            magic: MAGIC,
+            version: FORMAT_VERSION,
+            git_hash: *common::util::GIT_HASH,
+            git_timestamp: *common::util::GIT_TIMESTAMP,
+        }
+    }
+}
+
+/// Which message, and for [`ServerGeneral`] messages on which stream, a frame
+/// contains.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub(crate) enum RecordedKind {
+    /// [`ServerInfo`] on the register stream
+    Info,
+    /// [`ServerRegisterAnswer`] on the register stream
+    RegisterAnswer,
+    /// [`ServerInit`] on the register stream
+    Init,
+    General,
+    CharacterScreen,
+    InGame,
+    Terrain,
+}
+
+type RecordingError = Box<dyn std::error::Error + Send + Sync>;
+
+fn write_header(writer: &mut impl Write) -> Result<(), RecordingError> {
+    encode_into_std_write(RecordingHeader::current(), writer, legacy())?;
+    Ok(())
+}
+
+fn write_frame<M: Serialize + ?Sized>(
+    writer: &mut impl Write,
+    millis: u64,
+    kind: RecordedKind,
+    msg: &M,
+) -> Result<(), RecordingError> {
+    encode_into_std_write((millis, kind), writer, legacy())?;
+    encode_into_std_write(msg, writer, legacy())?;
+    Ok(())
+}
+
+/// Writes every message handed to [`record`] into a file.
+///
+/// I/O errors are logged once and stop the recording, they never affect the
+/// session itself.
+///
+/// [`record`]: SessionRecorder::record
+pub(crate) struct SessionRecorder {
+    writer: Option<BufWriter<File>>,
+    start: Instant,
+}
+
+impl SessionRecorder {
+    pub(crate) fn create(path: &Path) -> Result<Self, Error> {
+        let recording_err = |e: &dyn std::fmt::Display| {
+            Error::Other(format!(
+                "Could not create session recording {}: {e}",
+                path.display()
+            ))
+        };
+        let mut writer = BufWriter::new(File::create(path).map_err(|e| recording_err(&e))?);
+        write_header(&mut writer).map_err(|e| recording_err(&e))?;
+        info!(?path, "Recording session");
+        Ok(Self {
+            writer: Some(writer),
+            start: Instant::now(),
+        })
+    }
+
+    pub(crate) fn record<M: Serialize + ?Sized>(&mut self, kind: RecordedKind, msg: &M) {
+        if let Some(writer) = &mut self.writer {
+            let millis = self.start.elapsed().as_millis() as u64;
+            if let Err(e) = write_frame(writer, millis, kind, msg) {
+                warn!(
+                    ?e,
+                    "Failed to write session recording, stopping the recording"
+                );
+                self.writer = None;
+            }
+        }
+    }
+}
+
+impl Drop for SessionRecorder {
+    fn drop(&mut self) {
+        if let Some(Err(e)) = self.writer.as_mut().map(Write::flush) {
+            warn!(?e, "Failed to flush session recording");
+        }
+    }
+}
+
+/// Reads a recording frame by frame.
+struct SessionReader<R> {
+    reader: R,
+}
+
+impl<R: Read> SessionReader<R> {
+    fn new(mut reader: R) -> Result<Self, RecordingError> {
+        let header: RecordingHeader = decode_from_std_read(&mut reader, legacy())?;
+        if header.magic != MAGIC {
+            return Err("not a session recording".into());
+        }
+        if header.version != FORMAT_VERSION {
+            return Err(format!("unsupported recording format version {}", header.version).into());
+        }
+        if header.git_hash != *common::util::GIT_HASH
+            || header.git_timestamp != *common::util::GIT_TIMESTAMP
+        {
+            warn!(
+                "Recording was made with {}, you are running {}, replay might fail!",
+                common::util::make_display_version(header.git_hash, header.git_timestamp),
+                *common::util::DISPLAY_VERSION,
+            );
+        }
+        Ok(Self { reader })
+    }
+
+    /// Returns `None` once the end of the recording is reached. A recording
+    /// that was cut off in the middle of a frame ends at the last full frame.
+    fn next_frame(&mut self) -> Result<Option<(u64, RecordedKind)>, RecordingError> {
+        match decode_from_std_read(&mut self.reader, legacy()) {
+            Ok(frame) => Ok(Some(frame)),
+            Err(DecodeError::Io { inner, .. }) if inner.kind() == io::ErrorKind::UnexpectedEof => {
+                Ok(None)
+            },
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Reads the message of the frame returned by the last [`next_frame`].
+    ///
+    /// [`next_frame`]: SessionReader::next_frame
+    fn payload<M: DeserializeOwned>(&mut self) -> Result<M, RecordingError> {
+        Ok(decode_from_std_read(&mut self.reader, legacy())?)
+    }
+}
+
+/// Starts a replay server for the recording at `path` and returns the mpsc id
+/// to connect to.
+pub(crate) async fn start_replay(path: &Path, runtime: &Arc<Runtime>) -> Result<u64, Error> {
+    let file = File::open(path).map_err(|e| {
+        Error::Other(format!(
+            "Could not open session recording {}: {e}",
+            path.display()
+        ))
+    })?;
+    let reader = SessionReader::new(BufReader::new(file)).map_err(|e| {
+        Error::Other(format!(
+            "Could not read session recording {}: {e}",
+            path.display()
+        ))
+    })?;
+
+    let id = REPLAY_MPSC_BASE + NEXT_REPLAY_ID.fetch_add(1, Ordering::Relaxed);
+    let network = Network::new(Pid::new(), runtime);
+    network.listen(ListenAddr::Mpsc(id)).await?;
+    info!(?path, "Replaying session");
+
+    runtime.spawn(async move {
+        let mut network = network;
+        match serve(&mut network, reader).await {
+            Ok(()) => debug!("Replay finished"),
+            Err(e) => warn!(?e, "Replay stopped early"),
+        }
+    });
+    Ok(id)
+}
+
+async fn serve<R: Read>(
+    network: &mut Network,
+    mut reader: SessionReader<R>,
+) -> Result<(), RecordingError> {
+    let participant = network.connected().await?;
+
+    // Same streams as the server opens in its `ConnectionHandler`
+    let reliable = Promises::ORDERED | Promises::CONSISTENCY;
+    let reliablec = reliable | Promises::COMPRESSED;
+    let general_stream = participant.open(3, reliablec, 500).await?;
+    let mut ping_stream = participant.open(2, reliable, 500).await?;
+    let mut register_stream = participant.open(3, reliablec, 500).await?;
+    let character_screen_stream = participant.open(3, reliablec, 500).await?;
+    let in_game_stream = participant.open(3, reliablec, 100_000).await?;
+    let terrain_stream = participant.open(4, reliable, 20_000).await?;
+
+    // Messages are sent relative to when `ServerInit` was received originally
+    let mut epoch = (tokio::time::Instant::now(), 0);
+    while let Some((millis, kind)) = reader.next_frame()? {
+        let stream = match kind {
+            RecordedKind::Info => {
+                let mut server_info: ServerInfo = reader.payload()?;
+                // The client would try to authenticate against the real auth server
+                server_info.auth_provider = None;
+                let _: ClientType = register_stream.recv().await?;
+                register_stream.send(server_info)?;
+                continue;
+            },
+            RecordedKind::RegisterAnswer => {
+                let answer: ServerRegisterAnswer = reader.payload()?;
+                let _: ClientRegister = register_stream.recv().await?;
+                register_stream.send(&answer)?;
+                if answer.is_err() {
+                    return Ok(());
+                }
+                continue;
+            },
+            RecordedKind::Init => {
+                let init: ServerInit = reader.payload()?;
+                register_stream.send(init)?;
+                epoch = (tokio::time::Instant::now(), millis);
+                continue;
+            },
+            RecordedKind::General => &general_stream,
+            RecordedKind::CharacterScreen => &character_screen_stream,
+            RecordedKind::InGame => &in_game_stream,
+            RecordedKind::Terrain => &terrain_stream,
+        };
+        let msg: ServerGeneral = reader.payload()?;
+
+        // Keep the client alive while waiting for the next message
+        let due = epoch.0 + Duration::from_millis(millis.saturating_sub(epoch.1));
+        loop {
+            tokio::select! {
+                _ = tokio::time::sleep_until(due) => break,
+                ping = ping_stream.recv() => match ping? {
+                    PingMsg::Ping => ping_stream.send(PingMsg::Pong)?,
+                    PingMsg::Pong => {},
+                },
+            }
+        }
+        stream.send(msg)?;
+    }
+
+    general_stream.send(ServerGeneral::Disconnect(DisconnectReason::Shutdown))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common_net::msg::RegisterError;
+
+    #[test]
+    fn recording_roundtrip() {
+        let mut data = Vec::new();
+        write_header(&mut data).unwrap();
+        write_frame(
+            &mut data,
+            0,
+            RecordedKind::RegisterAnswer,
+            &Ok::<(), RegisterError>(()),
+        )
+        .unwrap();
+        write_frame(
+            &mut data,
+            42,
+            RecordedKind::General,
+            &ServerGeneral::Disconnect(DisconnectReason::Kicked("bye".to_owned())),
+        )
+        .unwrap();
+        // An incomplete frame at the end, e.g. from a crashed client
+        let full_len = data.len();
+        write_frame(
+            &mut data,
+            50,
+            RecordedKind::CharacterScreen,
+            &ServerGeneral::CharacterSuccess,
+        )
+        .unwrap();
+        data.truncate(full_len + 3);
+
+        let mut reader = SessionReader::new(data.as_slice()).unwrap();
+        assert_eq!(
+            reader.next_frame().unwrap(),
+            Some((0, RecordedKind::RegisterAnswer))
+        );
+        assert_eq!(reader.payload::<ServerRegisterAnswer>().unwrap(), Ok(()));
+        assert_eq!(
+            reader.next_frame().unwrap(),
+            Some((42, RecordedKind::General))
+        );
+        assert!(matches!(
+            reader.payload::<ServerGeneral>().unwrap(),
+            ServerGeneral::Disconnect(DisconnectReason::Kicked(reason)) if reason == "bye"
+        ));
+        assert_eq!(reader.next_frame().unwrap(), None);
+    }
+
+    #[test]
+    fn reject_foreign_file() {
+        assert!(SessionReader::new(b"not a recording at all".as_slice()).is_err());
+    }
+}
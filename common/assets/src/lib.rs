@@ -38,6 +38,10 @@ lazy_static! {
 #[cfg(feature = "plugins")]
 pub fn register_tar(path: PathBuf) -> std::io::Result<()> { ASSETS.register_tar(path) }
 
+// forget about a plugin, e.g. before registering a new version of it
+#[cfg(feature = "plugins")]
+pub fn unregister_tar(path: &std::path::Path) { ASSETS.unregister_tar(path) }
+
 pub type AssetHandle<T> = &'static assets_manager::Handle<T>;
 pub type AssetReadGuard<T> = assets_manager::AssetReadGuard<'static, T>;
 pub type AssetDirHandle<T> = AssetHandle<assets_manager::RecursiveDirectory<T>>;
@@ -1,4 +1,7 @@
-use std::{path::PathBuf, sync::RwLock};
+use std::{
+    path::{Path, PathBuf},
+    sync::RwLock,
+};
 
 use super::{ASSETS_PATH, Concatenate, fs::FileSystem};
 use assets_manager::{
@@ -201,6 +204,18 @@ impl CombinedCache {
         Ok(())
     }
 
+    /// Remove a previously registered tar archive, its files are no longer
+    /// considered as assets.
+    pub fn unregister_tar(&self, path: &Path) {
+        self.0
+            .downcast_raw_source::<CombinedSource>()
+            .unwrap()
+            .plugin_list
+            .write()
+            .unwrap()
+            .retain(|entry| entry.path != path);
+    }
+
     pub fn no_record<T>(&self, f: impl FnOnce() -> T) -> T { self.0.no_record(f) }
 
     // Just forward these methods to the cache
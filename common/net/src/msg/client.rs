@@ -116,6 +116,9 @@ pub enum ClientGeneral {
     //Only in Game, via terrain stream
     TerrainChunkRequest {
         key: Vec2<i32>,
+        /// Version of the chunk the client still has, allows the server to
+        /// answer with a `TerrainChunkDelta`
+        known_version: Option<u64>,
     },
     LodZoneRequest {
         key: Vec2<i32>,
@@ -168,6 +168,18 @@ pub enum ServerGeneral {
     InvitePending(Uid),
     /// Update the HUD of the clients in the group
     GroupInventoryUpdate(comp::FrontendItem, Uid),
+    /// The guild of the character changed, `None` if they are no longer in a
+    /// guild
+    GuildUpdate(Option<comp::guild::GuildRoster>),
+    /// The position of the storage sprite the personal storage is open at and
+    /// the content of the storage, `None` once it was closed
+    StorageUpdate(Option<(Vec3<i32>, comp::Inventory)>),
+    /// The position of the mailbox sprite the mailbox is open at and the mail
+    /// in it, newest first, `None` once it was closed
+    MailboxUpdate(Option<(Vec3<i32>, Vec<comp::mail::Mail>)>),
+    /// The position of the market board the market is open at and the
+    /// listings of the market, `None` once it was closed
+    MarketUpdate(Option<(Vec3<i32>, comp::market::Market)>),
     /// Note: this could potentially include all the failure cases such as
     /// inviting yourself in which case the `InvitePending` message could be
     /// removed and the client could consider their invite pending until
@@ -194,12 +206,29 @@ pub enum ServerGeneral {
     TerrainChunkUpdate {
         key: Vec2<i32>,
         chunk: Result<SerializedTerrainChunk, ()>,
+        /// Version of the chunk, `0` if unknown. Clients acknowledge it in
+        /// `ClientGeneral::TerrainChunkRequest` when requesting the chunk
+        /// again.
+        version: u64,
+    },
+    /// Sent instead of a `TerrainChunkUpdate` if the client acknowledged a
+    /// version of the chunk the server can still build a diff against.
+    /// `blocks` contains every block changed since `base_version`.
+    TerrainChunkDelta {
+        key: Vec2<i32>,
+        base_version: u64,
+        version: u64,
+        blocks: CompressedData<HashMap<Vec3<i32>, Block>>,
     },
     LodZoneUpdate {
         key: Vec2<i32>,
         zone: lod::Zone,
     },
-    TerrainBlockUpdates(CompressedData<HashMap<Vec3<i32>, Block>>),
+    TerrainBlockUpdates {
+        blocks: CompressedData<HashMap<Vec3<i32>, Block>>,
+        /// `(previous, new)` version of every chunk touched by `blocks`
+        versions: HashMap<Vec2<i32>, (u64, u64)>,
+    },
     // Always possible
     PlayerListUpdate(PlayerListUpdate),
     /// A message to go into the client chat box. The client is responsible for
@@ -232,6 +261,12 @@ pub enum ServerGeneral {
     UpdateRecipes,
     SetPlayerRole(Option<AdminRole>),
     Gizmos(Vec<Gizmos>),
+    /// A plugin was reloaded by the server, the client should drop the old
+    /// version and request the new one
+    PluginReloaded {
+        old: PluginHash,
+        new: PluginHash,
+    },
 }
 
 impl ServerGeneral {
@@ -304,7 +339,14 @@ pub enum InviteAnswer {
 /// not relevant to rendering.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Notification {
-    WaypointSaved { location_name: Content },
+    WaypointSaved {
+        location_name: Content,
+    },
+    /// The character has unread mail, sent when it logs in and when new mail
+    /// arrives
+    UnreadMail {
+        count: u32,
+    },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -366,9 +408,14 @@ impl ServerMsg {
                         | ServerGeneral::ExitInGameSuccess
                         | ServerGeneral::InventoryUpdate(_, _)
                         | ServerGeneral::GroupInventoryUpdate(_, _)
+                        | ServerGeneral::GuildUpdate(_)
+                        | ServerGeneral::StorageUpdate(_)
+                        | ServerGeneral::MailboxUpdate(_)
+                        | ServerGeneral::MarketUpdate(_)
                         | ServerGeneral::Dialogue(_, _)
                         | ServerGeneral::TerrainChunkUpdate { .. }
-                        | ServerGeneral::TerrainBlockUpdates(_)
+                        | ServerGeneral::TerrainChunkDelta { .. }
+                        | ServerGeneral::TerrainBlockUpdates { .. }
                         | ServerGeneral::SetViewDistance(_)
                         | ServerGeneral::Outcomes(_)
                         | ServerGeneral::Knockback(_)
@@ -397,7 +444,7 @@ impl ServerMsg {
                         | ServerGeneral::Notification(_)
                         | ServerGeneral::SetPlayerRole(_)
                         | ServerGeneral::LodZoneUpdate { .. } => true,
-                        ServerGeneral::PluginData(_) => true,
+                        ServerGeneral::PluginData(_) | ServerGeneral::PluginReloaded { .. } => true,
                     }
             },
             ServerMsg::Ping(_) => true,
@@ -8,8 +8,8 @@ use tokio::sync::watch;
 use tracing::error;
 use veloren_query_server::{
     client::QueryClient,
-    proto::{ServerBattleMode, ServerInfo},
-    server::{Metrics, QueryServer},
+    proto::{PluginInfo, ServerBattleMode, ServerInfo, WorldInfo, WorldWeather},
+    server::{Metrics, QueryServer, ServerDetails},
 };
 
 const DEFAULT_SERVER_INFO: ServerInfo = ServerInfo {
@@ -20,12 +20,36 @@ const DEFAULT_SERVER_INFO: ServerInfo = ServerInfo {
     battlemode: ServerBattleMode::GlobalPvE,
 };
 
+const DEFAULT_WORLD_INFO: WorldInfo = WorldInfo {
+    seed: 59686,
+    size_x: 1024,
+    size_y: 1024,
+    uptime: 3600,
+    time_of_day: 43200.0,
+    weather: WorldWeather {
+        cloud: 0.5,
+        rain: 0.1,
+        wind: 4.0,
+    },
+    battlemode: ServerBattleMode::GlobalPvE,
+};
+
 #[tokio::main]
 async fn main() {
     tracing_subscriber::fmt::init();
     let addr = SocketAddr::new(IpAddr::V6(Ipv6Addr::LOCALHOST), 14006);
     let (_sender, receiver) = watch::channel(DEFAULT_SERVER_INFO);
-    let mut server = QueryServer::new(addr, receiver, 10002);
+    let players = (0..100).map(|i| format!("player{i}")).collect::<Vec<_>>();
+    let plugins = vec![PluginInfo {
+        name: "dummy".to_owned(),
+        hash: [0; 32],
+    }];
+    let (_details_sender, details_receiver) = watch::channel(ServerDetails {
+        world: DEFAULT_WORLD_INFO,
+        players: Some(players.clone()),
+        plugins: plugins.clone(),
+    });
+    let mut server = QueryServer::new(addr, receiver, details_receiver, 10002);
     let metrics = Arc::new(Mutex::new(Metrics::default()));
     let metrics2 = Arc::clone(&metrics);
 
@@ -38,6 +62,12 @@ async fn main() {
     println!("Server info: {info:?}");
     assert_eq!(info, DEFAULT_SERVER_INFO);
 
+    let (world, _) = client.world_info().await.unwrap();
+    println!("World info: {world:?}");
+    assert_eq!(world, DEFAULT_WORLD_INFO);
+    assert_eq!(client.players().await.unwrap(), players);
+    assert_eq!(client.plugins().await.unwrap(), plugins);
+
     let start = Instant::now();
 
     for _i in 0..10000 {
@@ -70,4 +70,17 @@ async fn main() {
     {
         println!("{:?}", last_info);
     }
+
+    match client.world_info().await {
+        Ok((world, _)) => println!("{world:?}"),
+        Err(e) => error!(?e, "Failed to fetch world info from server"),
+    }
+    match client.players().await {
+        Ok(players) => println!("players: {players:?}"),
+        Err(e) => error!(?e, "Failed to fetch players from server"),
+    }
+    match client.plugins().await {
+        Ok(plugins) => println!("plugins: {plugins:?}"),
+        Err(e) => error!(?e, "Failed to fetch plugins from server"),
+    }
 }
@@ -9,8 +9,8 @@ use tokio::{net::UdpSocket, time::timeout};
 use tracing::trace;
 
 use crate::proto::{
-    MAX_RESPONSE_SIZE, QueryServerRequest, QueryServerResponse, RawQueryServerRequest,
-    RawQueryServerResponse, ServerInfo,
+    MAX_RESPONSE_SIZE, PlayerList, PluginInfo, PluginList, QueryServerRequest, QueryServerResponse,
+    RawQueryServerRequest, RawQueryServerResponse, ServerInfo, VERSION, WorldInfo,
 };
 
 // This must be at least 2 for the client to get a value for the `p` field.
@@ -23,11 +23,15 @@ pub enum QueryClientError {
     InvalidResponse,
     Timeout,
     ChallengeFailed,
+    /// The server runs an older version of the protocol which does not support
+    /// this request.
+    UnsupportedRequest,
+    /// The server does not share the requested information.
+    Unavailable,
 }
 
 struct ClientInitData {
     p: u64,
-    #[expect(dead_code)]
     server_max_version: u16,
 }
 
@@ -45,7 +49,6 @@ impl QueryClient {
         self.send_query(QueryServerRequest::ServerInfo)
             .await
             .and_then(|(response, duration)| {
-                #[expect(irrefutable_let_patterns)]
                 if let QueryServerResponse::ServerInfo(info) = response {
                     Ok((info, duration))
                 } else {
@@ -54,6 +57,77 @@ impl QueryClient {
             })
     }
 
+    pub async fn world_info(&mut self) -> Result<(WorldInfo, Duration), QueryClientError> {
+        self.send_query(QueryServerRequest::WorldInfo)
+            .await
+            .and_then(|(response, duration)| match response {
+                QueryServerResponse::WorldInfo(info) => Ok((info, duration)),
+                QueryServerResponse::Unavailable => Err(QueryClientError::Unavailable),
+                _ => Err(QueryClientError::InvalidResponse),
+            })
+    }
+
+    /// Requests a single page of the player list, see [`Self::players`] to
+    /// request all of them.
+    pub async fn player_list(
+        &mut self,
+        page: u8,
+    ) -> Result<(PlayerList, Duration), QueryClientError> {
+        self.send_query(QueryServerRequest::PlayerList(page))
+            .await
+            .and_then(|(response, duration)| match response {
+                QueryServerResponse::PlayerList(list) => Ok((list, duration)),
+                QueryServerResponse::Unavailable => Err(QueryClientError::Unavailable),
+                _ => Err(QueryClientError::InvalidResponse),
+            })
+    }
+
+    /// Requests a single page of the plugin list, see [`Self::plugins`] to
+    /// request all of them.
+    pub async fn plugin_list(
+        &mut self,
+        page: u8,
+    ) -> Result<(PluginList, Duration), QueryClientError> {
+        self.send_query(QueryServerRequest::Plugins(page))
+            .await
+            .and_then(|(response, duration)| match response {
+                QueryServerResponse::Plugins(list) => Ok((list, duration)),
+                QueryServerResponse::Unavailable => Err(QueryClientError::Unavailable),
+                _ => Err(QueryClientError::InvalidResponse),
+            })
+    }
+
+    /// Requests all pages of the player list.
+    ///
+    /// NOTE: The list may change between requests for separate pages, so
+    /// players can be missing or appear twice.
+    pub async fn players(&mut self) -> Result<Vec<String>, QueryClientError> {
+        let mut players = Vec::new();
+        let mut page = 0;
+        loop {
+            let (list, _) = self.player_list(page).await?;
+            players.extend(list.players);
+            page += 1;
+            if page >= list.pages {
+                break Ok(players);
+            }
+        }
+    }
+
+    /// Requests all pages of the plugin list.
+    pub async fn plugins(&mut self) -> Result<Vec<PluginInfo>, QueryClientError> {
+        let mut plugins = Vec::new();
+        let mut page = 0;
+        loop {
+            let (list, _) = self.plugin_list(page).await?;
+            plugins.extend(list.plugins);
+            page += 1;
+            if page >= list.pages {
+                break Ok(plugins);
+            }
+        }
+    }
+
     async fn send_query(
         &mut self,
         request: QueryServerRequest,
@@ -66,18 +140,26 @@ impl QueryClient {
         .await?;
 
         for _ in 0..MAX_REQUEST_RETRIES {
-            let request = if let Some(init) = &self.init {
-                // TODO: Use the maximum version supported by both the client and server once
-                // new protocol versions are added
-                RawQueryServerRequest { p: init.p, request }
-            } else {
-                // TODO: Use the legacy version here once new protocol versions are added
-                RawQueryServerRequest {
-                    p: 0,
-                    request: QueryServerRequest::Init,
+            let (request, version) = if let Some(init) = &self.init {
+                if init.server_max_version < request.min_version() {
+                    return Err(QueryClientError::UnsupportedRequest);
                 }
+                (
+                    RawQueryServerRequest { p: init.p, request },
+                    VERSION.min(init.server_max_version),
+                )
+            } else {
+                // The first request must always be done in V0, as the server might not
+                // support anything newer
+                (
+                    RawQueryServerRequest {
+                        p: 0,
+                        request: QueryServerRequest::Init,
+                    },
+                    0,
+                )
             };
-            let buf = request.serialize()?;
+            let buf = request.serialize(version)?;
             let query_sent = Instant::now();
             socket.send_to(&buf, self.addr).await?;
             let mut buf = vec![0; MAX_RESPONSE_SIZE];
@@ -1,13 +1,22 @@
 #![expect(non_local_definitions)] // necessary because of the Protocol derive macro
 use protocol::Protocol;
 
-pub(crate) const VERSION: u16 = 0;
+/// The latest version of the protocol supported by this crate.
+///
+/// Version history:
+/// - 0: [`QueryServerRequest::Init`] and [`QueryServerRequest::ServerInfo`]
+/// - 1: [`QueryServerRequest::WorldInfo`], [`QueryServerRequest::PlayerList`]
+///   and [`QueryServerRequest::Plugins`]
+pub(crate) const VERSION: u16 = 1;
 pub(crate) const VELOREN_HEADER: [u8; 7] = *b"veloren";
 pub(crate) const MAX_REQUEST_CONTENT_SIZE: usize = 300;
 // NOTE: The actual maximum size must never exceed 1200 or we risk getting near
 // MTU limits for some networks.
 pub(crate) const MAX_REQUEST_SIZE: usize = MAX_REQUEST_CONTENT_SIZE + VELOREN_HEADER.len() + 2;
 pub(crate) const MAX_RESPONSE_SIZE: usize = 256;
+/// Space available for the entries of a paged response, the rest is reserved
+/// for the discriminants and the page header.
+pub(crate) const MAX_PAGE_CONTENT_SIZE: usize = MAX_RESPONSE_SIZE - 16;
 
 #[derive(Protocol, Debug, Clone, Copy)]
 pub(crate) struct RawQueryServerRequest {
@@ -28,6 +37,14 @@ pub enum QueryServerRequest {
     /// will still be dropped as the supplied `P` value is invalid).
     Init,
     ServerInfo,
+    /// Requests [`WorldInfo`], since V1.
+    WorldInfo,
+    /// Requests the given page of the [`PlayerList`], since V1. Pages start at
+    /// 0.
+    PlayerList(u8),
+    /// Requests the given page of the [`PluginList`], since V1. Pages start at
+    /// 0.
+    Plugins(u8),
     // New requests should be added at the end to prevent breakage.
     // NOTE: Any new (sub-)variants must be added to the `check_request_sizes` test at the end of
     // this file
@@ -50,7 +67,7 @@ pub(crate) struct Init {
     pub max_supported_version: u16,
 }
 
-#[derive(Protocol, Debug, Clone, Copy)]
+#[derive(Protocol, Debug, Clone)]
 #[protocol(discriminant = "integer")]
 #[protocol(discriminator(u8))]
 pub(crate) enum RawQueryServerResponse {
@@ -58,11 +75,17 @@ pub(crate) enum RawQueryServerResponse {
     Init(Init),
 }
 
-#[derive(Protocol, Debug, Clone, Copy)]
+#[derive(Protocol, Debug, Clone, PartialEq)]
 #[protocol(discriminant = "integer")]
 #[protocol(discriminator(u8))]
 pub enum QueryServerResponse {
     ServerInfo(ServerInfo),
+    WorldInfo(WorldInfo),
+    PlayerList(PlayerList),
+    Plugins(PluginList),
+    /// The server does not share the requested information (e.g. the player
+    /// list was not enabled by the server operator).
+    Unavailable,
     // New responses should be added at the end to prevent breakage
 }
 
@@ -85,15 +108,78 @@ pub enum ServerBattleMode {
     PerPlayer,
 }
 
+#[derive(Protocol, Debug, Clone, Copy, PartialEq)]
+pub struct WorldInfo {
+    pub seed: u32,
+    /// Size of the world in chunks.
+    pub size_x: u32,
+    pub size_y: u32,
+    /// Seconds since the server was started.
+    pub uptime: u64,
+    /// In-game time in seconds, a day lasts 86400 seconds.
+    pub time_of_day: f64,
+    pub weather: WorldWeather,
+    pub battlemode: ServerBattleMode,
+}
+
+/// Weather averaged over the whole world.
+#[derive(Protocol, Debug, Clone, Copy, PartialEq)]
+pub struct WorldWeather {
+    /// Cloud cover between 0 and 1.
+    pub cloud: f32,
+    /// Rain between 0 and 1.
+    pub rain: f32,
+    /// Wind speed in blocks per second.
+    pub wind: f32,
+}
+
+#[derive(Protocol, Debug, Clone, PartialEq, Eq)]
+pub struct PlayerList {
+    pub page: u8,
+    /// Total amount of pages, requesting a page past this returns an empty
+    /// page.
+    pub pages: u8,
+    pub players: Vec<String>,
+}
+
+#[derive(Protocol, Debug, Clone, PartialEq, Eq)]
+pub struct PluginList {
+    pub page: u8,
+    /// Total amount of pages, requesting a page past this returns an empty
+    /// page.
+    pub pages: u8,
+    pub plugins: Vec<PluginInfo>,
+}
+
+#[derive(Protocol, Debug, Clone, PartialEq, Eq)]
+pub struct PluginInfo {
+    pub name: String,
+    /// The same hash used to identify plugins in the game protocol.
+    pub hash: [u8; 32],
+}
+
+impl QueryServerRequest {
+    /// The first protocol version in which this request is available.
+    pub fn min_version(&self) -> u16 {
+        match self {
+            Self::Init | Self::ServerInfo => 0,
+            Self::WorldInfo | Self::PlayerList(_) | Self::Plugins(_) => 1,
+        }
+    }
+}
+
 impl RawQueryServerRequest {
+    /// Serializes the request in the given protocol `version`, which must be
+    /// at least [`QueryServerRequest::min_version`] of the request.
     #[cfg(any(feature = "client", test))]
-    pub fn serialize(&self) -> Result<Vec<u8>, protocol::Error> {
+    pub fn serialize(&self, version: u16) -> Result<Vec<u8>, protocol::Error> {
         use protocol::Parcel;
 
+        debug_assert!(version >= self.request.min_version() && version <= VERSION);
         let mut buf = Vec::with_capacity(MAX_REQUEST_SIZE);
 
-        // 2 extra bytes for version information, currently unused
-        buf.extend(VERSION.to_le_bytes());
+        // 2 extra bytes for version information
+        buf.extend(version.to_le_bytes());
         buf.extend({
             let request_data =
                 <RawQueryServerRequest as Parcel>::raw_bytes(self, &Default::default())?;
@@ -115,18 +201,24 @@ impl RawQueryServerRequest {
 
 #[cfg(test)]
 mod tests {
-    use super::{QueryServerRequest, RawQueryServerRequest};
+    use super::{QueryServerRequest, RawQueryServerRequest, VERSION};
 
     #[test]
     fn check_request_sizes() {
-        const ALL_REQUESTS: &[QueryServerRequest] =
-            &[QueryServerRequest::ServerInfo, QueryServerRequest::Init];
+        const ALL_REQUESTS: &[QueryServerRequest] = &[
+            QueryServerRequest::ServerInfo,
+            QueryServerRequest::Init,
+            QueryServerRequest::WorldInfo,
+            QueryServerRequest::PlayerList(u8::MAX),
+            QueryServerRequest::Plugins(u8::MAX),
+        ];
         for request in ALL_REQUESTS {
             let request = RawQueryServerRequest {
                 p: 0,
                 request: *request,
             };
-            request.serialize().unwrap(); // This will panic if the size is above MAX_REQUEST_SIZE
+            // This will panic if the size is above MAX_REQUEST_SIZE
+            request.serialize(VERSION).unwrap();
         }
     }
 }
@@ -14,8 +14,9 @@ use tracing::{debug, error, trace};
 
 use crate::{
     proto::{
-        Init, MAX_REQUEST_SIZE, MAX_RESPONSE_SIZE, QueryServerRequest, QueryServerResponse,
-        RawQueryServerRequest, RawQueryServerResponse, ServerInfo, VELOREN_HEADER, VERSION,
+        Init, MAX_PAGE_CONTENT_SIZE, MAX_REQUEST_SIZE, MAX_RESPONSE_SIZE, PlayerList, PluginInfo,
+        PluginList, QueryServerRequest, QueryServerResponse, RawQueryServerRequest,
+        RawQueryServerResponse, ServerInfo, VELOREN_HEADER, VERSION, WorldInfo,
     },
     ratelimit::{RateLimiter, ReducedIpAddr},
 };
@@ -25,10 +26,22 @@ const SECRET_REGEN_INTERNVAL: Duration = Duration::from_secs(300);
 pub struct QueryServer {
     addr: SocketAddr,
     server_info: watch::Receiver<ServerInfo>,
+    server_details: watch::Receiver<ServerDetails>,
     settings: protocol::Settings,
     ratelimit: RateLimiter,
 }
 
+/// Information that is too large to fit into [`ServerInfo`], it is split
+/// into several requests that were added in version 1 of the protocol.
+#[derive(Clone, Debug)]
+pub struct ServerDetails {
+    pub world: WorldInfo,
+    /// The names of all online players, `None` if the server does not share
+    /// them.
+    pub players: Option<Vec<String>>,
+    pub plugins: Vec<PluginInfo>,
+}
+
 #[derive(Default, Clone, Copy, Debug)]
 pub struct Metrics {
     pub received_packets: u32,
@@ -38,6 +51,8 @@ pub struct Metrics {
     pub proccessing_errors: u32,
     pub info_requests: u32,
     pub init_requests: u32,
+    /// Requests for world info, players or plugins.
+    pub details_requests: u32,
     pub sent_responses: u32,
     pub failed_responses: u32,
     pub timed_out_responses: u32,
@@ -45,10 +60,16 @@ pub struct Metrics {
 }
 
 impl QueryServer {
-    pub fn new(addr: SocketAddr, server_info: watch::Receiver<ServerInfo>, ratelimit: u16) -> Self {
+    pub fn new(
+        addr: SocketAddr,
+        server_info: watch::Receiver<ServerInfo>,
+        server_details: watch::Receiver<ServerDetails>,
+        ratelimit: u16,
+    ) -> Self {
         Self {
             addr,
             server_info,
+            server_details,
             ratelimit: RateLimiter::new(ratelimit),
             settings: Default::default(),
         }
@@ -94,9 +115,12 @@ impl QueryServer {
             };
 
             let raw_msg_buf = &buf[..len];
-            let msg_buf = if Self::validate_datagram(raw_msg_buf) {
-                // Require 2 extra bytes for version (currently unused)
-                &raw_msg_buf[2..(raw_msg_buf.len() - VELOREN_HEADER.len())]
+            let (version, msg_buf) = if let Some(version) = Self::validate_datagram(raw_msg_buf) {
+                // Require 2 extra bytes for version
+                (
+                    version,
+                    &raw_msg_buf[2..(raw_msg_buf.len() - VELOREN_HEADER.len())],
+                )
             } else {
                 if let Ok(mut metrics) = metrics.lock() {
                     metrics.dropped_packets += 1;
@@ -104,8 +128,15 @@ impl QueryServer {
                 continue;
             };
 
-            self.process_datagram(msg_buf, remote_addr, secrets, &mut new_metrics, &socket)
-                .await;
+            self.process_datagram(
+                msg_buf,
+                version,
+                remote_addr,
+                secrets,
+                &mut new_metrics,
+                &socket,
+            )
+            .await;
 
             // Update metrics at the end of eath packet
             if let Ok(mut metrics) = metrics.lock() {
@@ -124,34 +155,35 @@ impl QueryServer {
         }
     }
 
-    // Header must be discarded after this validation passes
-    fn validate_datagram(data: &[u8]) -> bool {
+    /// Returns the protocol version of the datagram if it is valid. Header
+    /// must be discarded after this validation passes.
+    fn validate_datagram(data: &[u8]) -> Option<u16> {
         let len = data.len();
-        // Require 2 extra bytes for version (currently unused)
+        // Require 2 extra bytes for version
         if len < MAX_RESPONSE_SIZE.max(VELOREN_HEADER.len() + 2) {
             trace!(?len, "Datagram too short");
-            false
+            None
         } else if len > MAX_REQUEST_SIZE {
             trace!(?len, "Datagram too large");
-            false
+            None
         } else if data[(len - VELOREN_HEADER.len())..] != VELOREN_HEADER {
             trace!(?len, "Datagram header invalid");
-            false
-        // TODO: Allow lower versions once proper versioning is added.
-        } else if u16::from_ne_bytes(data[..2].try_into().unwrap()) != VERSION {
-            trace!(
-                "Datagram has invalid version {:?}, current {VERSION:?}",
-                &data[..2]
-            );
-            false
+            None
         } else {
-            true
+            let version = u16::from_le_bytes(data[..2].try_into().unwrap());
+            if version > VERSION {
+                trace!("Datagram has unsupported version {version}, current {VERSION}");
+                None
+            } else {
+                Some(version)
+            }
         }
     }
 
     async fn process_datagram(
         &mut self,
         datagram: &[u8],
+        version: u16,
         remote: SocketAddr,
         secrets: (u64, u64),
         metrics: &mut Metrics,
@@ -167,7 +199,17 @@ impl QueryServer {
             return;
         };
 
-        trace!(?request, "Received packet");
+        trace!(?request, ?version, "Received packet");
+
+        if request.min_version() > version {
+            trace!(
+                ?request,
+                ?version,
+                "Request is not part of the used protocol version"
+            );
+            metrics.invalid_packets += 1;
+            return;
+        }
 
         #[expect(deprecated)]
         let real_p = {
@@ -231,6 +273,61 @@ impl QueryServer {
                 )
                 .await;
             },
+            QueryServerRequest::WorldInfo => {
+                metrics.details_requests += 1;
+                let world = self.server_details.borrow().world;
+                Self::send_response(
+                    RawQueryServerResponse::Response(QueryServerResponse::WorldInfo(world)),
+                    remote,
+                    socket,
+                    metrics,
+                )
+                .await;
+            },
+            QueryServerRequest::PlayerList(page) => {
+                metrics.details_requests += 1;
+                let response = match &self.server_details.borrow().players {
+                    Some(players) => {
+                        // Length prefix and string
+                        let (pages, players) = paginate(players, page, |name| 4 + name.len());
+                        QueryServerResponse::PlayerList(PlayerList {
+                            page,
+                            pages,
+                            players,
+                        })
+                    },
+                    None => QueryServerResponse::Unavailable,
+                };
+                Self::send_response(
+                    RawQueryServerResponse::Response(response),
+                    remote,
+                    socket,
+                    metrics,
+                )
+                .await;
+            },
+            QueryServerRequest::Plugins(page) => {
+                metrics.details_requests += 1;
+                let response = {
+                    let details = self.server_details.borrow();
+                    // Length prefix, name and hash
+                    let (pages, plugins) = paginate(&details.plugins, page, |plugin| {
+                        4 + plugin.name.len() + plugin.hash.len()
+                    });
+                    QueryServerResponse::Plugins(PluginList {
+                        page,
+                        pages,
+                        plugins,
+                    })
+                };
+                Self::send_response(
+                    RawQueryServerResponse::Response(response),
+                    remote,
+                    socket,
+                    metrics,
+                )
+                .await;
+            },
         }
     }
 
@@ -240,8 +337,8 @@ impl QueryServer {
         socket: &UdpSocket,
         metrics: &mut Metrics,
     ) {
-        // TODO: Once more versions are added, send the packet in the same version as
-        // the request here.
+        // NOTE: Responses of older versions stay valid in newer ones, so they can be
+        // sent without regard to the version of the request.
         match <RawQueryServerResponse as Parcel>::raw_bytes(&response, &Default::default()) {
             Ok(data) => {
                 if data.len() > MAX_RESPONSE_SIZE {
@@ -279,6 +376,36 @@ impl QueryServer {
     }
 }
 
+/// Splits `items` into pages that fit into a single response and returns the
+/// total amount of pages together with the items on the requested page. Items
+/// which are too large to fit into a response on their own are skipped.
+fn paginate<T: Clone>(items: &[T], page: u8, size: impl Fn(&T) -> usize) -> (u8, Vec<T>) {
+    let mut current = 0usize;
+    let mut page_size = 0;
+    let mut requested = Vec::new();
+    for item in items {
+        let item_size = size(item);
+        if item_size > MAX_PAGE_CONTENT_SIZE {
+            trace!(
+                ?item_size,
+                "Skipping entry too large for a query server response"
+            );
+            continue;
+        }
+        if page_size + item_size > MAX_PAGE_CONTENT_SIZE {
+            current += 1;
+            page_size = 0;
+        }
+        page_size += item_size;
+        if current == page as usize {
+            requested.push(item.clone());
+        }
+    }
+    let pages = if page_size > 0 { current + 1 } else { 0 };
+
+    (pages.try_into().unwrap_or(u8::MAX), requested)
+}
+
 impl std::ops::AddAssign for Metrics {
     fn add_assign(
         &mut self,
@@ -289,6 +416,7 @@ impl std::ops::AddAssign for Metrics {
             proccessing_errors,
             info_requests,
             init_requests,
+            details_requests,
             sent_responses,
             failed_responses,
             timed_out_responses,
@@ -301,6 +429,7 @@ impl std::ops::AddAssign for Metrics {
         self.proccessing_errors += proccessing_errors;
         self.info_requests += info_requests;
         self.init_requests += init_requests;
+        self.details_requests += details_requests;
         self.sent_responses += sent_responses;
         self.failed_responses += failed_responses;
         self.timed_out_responses += timed_out_responses;
@@ -314,3 +443,40 @@ impl Metrics {
     /// Used by the consumer of the metrics.
     pub fn reset(&mut self) -> Self { std::mem::take(self) }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::paginate;
+    use crate::proto::MAX_PAGE_CONTENT_SIZE;
+
+    #[test]
+    fn paginate_fills_pages() {
+        let names = (0..100)
+            .map(|i| format!("player{i:03}"))
+            .collect::<Vec<_>>();
+        let size = |name: &String| 4 + name.len();
+        let per_page = MAX_PAGE_CONTENT_SIZE / size(&names[0]);
+
+        let (pages, first) = paginate(&names, 0, size);
+        assert_eq!(pages as usize, names.len().div_ceil(per_page));
+        assert_eq!(first, names[..per_page]);
+
+        let (_, last) = paginate(&names, pages - 1, size);
+        assert_eq!(last, names[(pages as usize - 1) * per_page..]);
+
+        let (_, past_end) = paginate(&names, pages, size);
+        assert!(past_end.is_empty());
+    }
+
+    #[test]
+    fn paginate_skips_oversized() {
+        let names = vec![
+            "a".to_owned(),
+            "b".repeat(MAX_PAGE_CONTENT_SIZE),
+            "c".to_owned(),
+        ];
+        let (pages, first) = paginate(&names, 0, |name| 4 + name.len());
+        assert_eq!(pages, 1);
+        assert_eq!(first, ["a", "c"]);
+    }
+}
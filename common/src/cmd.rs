@@ -2,7 +2,7 @@ use crate::{
     assets::{AssetCombined, Ron},
     combat::GroupTarget,
     comp::{
-        self, AdminRole as Role, Skill, aura::AuraKindVariant, buff::BuffKind,
+        self, AdminRole as Role, Skill, aura::AuraKindVariant, buff::BuffKind, guild::GuildRank,
         inventory::item::try_all_item_defs,
     },
     generation::try_all_entity_configs,
@@ -120,6 +120,8 @@ lazy_static! {
         let rbm = RecipeBookManifest::load().cloned();
         rbm.keys().cloned().collect::<Vec<String>>()
     };
+    static ref GUILD_RANKS: Vec<String> =
+        GuildRank::iter().map(|rank| rank.as_ref().to_string()).collect();
     static ref TIMES: Vec<String> = [
         "midnight", "night", "dawn", "morning", "day", "noon", "dusk"
     ]
@@ -388,8 +390,11 @@ pub enum ServerChatCommand {
     DisconnectAllPlayers,
     Dismount,
     DropAll,
+    Duel,
+    DuelForfeit,
     Dummy,
     Explosion,
+    ExportCharacter,
     Faction,
     GiveItem,
     Gizmos,
@@ -401,9 +406,16 @@ pub enum ServerChatCommand {
     GroupKick,
     GroupLeave,
     GroupPromote,
+    Guild,
+    GuildCreate,
+    GuildDisband,
+    GuildInvite,
+    GuildKick,
+    GuildLeave,
+    GuildRank,
     Health,
+    ImportCharacter,
     IntoNpc,
-    JoinFaction,
     Jump,
     Kick,
     Kill,
@@ -427,8 +439,10 @@ pub enum ServerChatCommand {
     Portal,
     Region,
     ReloadChunks,
+    ReloadPlugin,
     RemoveLights,
     RepairEquipment,
+    Reputation,
     ResetRecipes,
     Respawn,
     RevokeBuild,
@@ -453,6 +467,8 @@ pub enum ServerChatCommand {
     Spot,
     Sudo,
     Tell,
+    TerrainHistory,
+    TerrainRollback,
     Tether,
     Time,
     TimeScale,
@@ -654,6 +670,16 @@ impl ServerChatCommand {
                 Content::localized("command-dropall-desc"),
                 Some(Moderator),
             ),
+            ServerChatCommand::Duel => cmd(
+                vec![PlayerName(Required)],
+                Content::localized("command-duel-desc"),
+                None,
+            ),
+            ServerChatCommand::DuelForfeit => cmd(
+                vec![],
+                Content::localized("command-duel_forfeit-desc"),
+                None,
+            ),
             ServerChatCommand::Dummy => cmd(
                 vec![],
                 Content::localized("command-dummy-desc"),
@@ -664,6 +690,11 @@ impl ServerChatCommand {
                 Content::localized("command-explosion-desc"),
                 Some(Admin),
             ),
+            ServerChatCommand::ExportCharacter => cmd(
+                vec![PlayerName(Required), Any("character", Required)],
+                Content::localized("command-export_character-desc"),
+                Some(Admin),
+            ),
             ServerChatCommand::Faction => cmd(
                 vec![Message(Optional)],
                 Content::localized("command-faction-desc"),
@@ -738,21 +769,60 @@ impl ServerChatCommand {
                 Content::localized("command-group_promote-desc"),
                 None,
             ),
+            ServerChatCommand::Guild => cmd(vec![], Content::localized("command-guild-desc"), None),
+            ServerChatCommand::GuildCreate => cmd(
+                vec![Message(Required)],
+                Content::localized("command-guild_create-desc"),
+                None,
+            ),
+            ServerChatCommand::GuildDisband => cmd(
+                vec![],
+                Content::localized("command-guild_disband-desc"),
+                None,
+            ),
+            ServerChatCommand::GuildInvite => cmd(
+                vec![PlayerName(Required)],
+                Content::localized("command-guild_invite-desc"),
+                None,
+            ),
+            ServerChatCommand::GuildKick => cmd(
+                vec![Any("member", Required)],
+                Content::localized("command-guild_kick-desc"),
+                None,
+            ),
+            ServerChatCommand::GuildLeave => {
+                cmd(vec![], Content::localized("command-guild_leave-desc"), None)
+            },
+            ServerChatCommand::GuildRank => cmd(
+                vec![
+                    Any("member", Required),
+                    Enum("rank", GUILD_RANKS.clone(), Required),
+                ],
+                Content::localized("command-guild_rank-desc"),
+                None,
+            ),
             ServerChatCommand::Health => cmd(
                 vec![Integer("hp", 100, Required)],
                 Content::localized("command-health-desc"),
                 Some(Admin),
             ),
+            ServerChatCommand::ImportCharacter => cmd(
+                vec![
+                    PlayerName(Required),
+                    Any("file", Required),
+                    Any("alias", Optional),
+                ],
+                Content::localized("command-import_character-desc"),
+                Some(Admin),
+            ),
+            ServerChatCommand::Reputation => {
+                cmd(vec![], Content::localized("command-reputation-desc"), None)
+            },
             ServerChatCommand::Respawn => cmd(
                 vec![],
                 Content::localized("command-respawn-desc"),
                 Some(Moderator),
             ),
-            ServerChatCommand::JoinFaction => cmd(
-                vec![Any("faction", Optional)],
-                Content::localized("command-join_faction-desc"),
-                None,
-            ),
             ServerChatCommand::Jump => cmd(
                 vec![
                     Float("x", 0.0, Required),
@@ -873,6 +943,11 @@ impl ServerChatCommand {
                 Content::localized("command-reload_chunks-desc"),
                 Some(Admin),
             ),
+            ServerChatCommand::ReloadPlugin => cmd(
+                vec![Any("plugin", Required)],
+                Content::localized("command-reload_plugin-desc"),
+                Some(Admin),
+            ),
             ServerChatCommand::ResetRecipes => cmd(
                 vec![],
                 Content::localized("command-reset_recipes-desc"),
@@ -1004,6 +1079,24 @@ impl ServerChatCommand {
                 Content::localized("command-tell-desc"),
                 None,
             ),
+            ServerChatCommand::TerrainHistory => cmd(
+                vec![
+                    Integer("x", 0, Optional),
+                    Integer("y", 0, Optional),
+                    Integer("z", 0, Optional),
+                ],
+                Content::localized("command-terrain_history-desc"),
+                Some(Admin),
+            ),
+            ServerChatCommand::TerrainRollback => cmd(
+                vec![
+                    PlayerName(Required),
+                    Integer("radius", 32, Optional),
+                    Any("duration", Optional),
+                ],
+                Content::localized("command-terrain_rollback-desc"),
+                Some(Admin),
+            ),
             ServerChatCommand::Time => cmd(
                 vec![Enum("time", TIMES.clone(), Optional)],
                 Content::localized("command-time-desc"),
@@ -1189,8 +1282,11 @@ impl ServerChatCommand {
             ServerChatCommand::DebugWays => "debug_ways",
             ServerChatCommand::DisconnectAllPlayers => "disconnect_all_players",
             ServerChatCommand::DropAll => "dropall",
+            ServerChatCommand::Duel => "duel",
+            ServerChatCommand::DuelForfeit => "duel_forfeit",
             ServerChatCommand::Dummy => "dummy",
             ServerChatCommand::Explosion => "explosion",
+            ServerChatCommand::ExportCharacter => "export_character",
             ServerChatCommand::Faction => "faction",
             ServerChatCommand::GiveItem => "give_item",
             ServerChatCommand::Gizmos => "gizmos",
@@ -1202,15 +1298,23 @@ impl ServerChatCommand {
             ServerChatCommand::GroupKick => "group_kick",
             ServerChatCommand::GroupLeave => "group_leave",
             ServerChatCommand::GroupPromote => "group_promote",
+            ServerChatCommand::Guild => "guild",
+            ServerChatCommand::GuildCreate => "guild_create",
+            ServerChatCommand::GuildDisband => "guild_disband",
+            ServerChatCommand::GuildInvite => "guild_invite",
+            ServerChatCommand::GuildKick => "guild_kick",
+            ServerChatCommand::GuildLeave => "guild_leave",
+            ServerChatCommand::GuildRank => "guild_rank",
             ServerChatCommand::Health => "health",
+            ServerChatCommand::ImportCharacter => "import_character",
             ServerChatCommand::IntoNpc => "into_npc",
-            ServerChatCommand::JoinFaction => "join_faction",
             ServerChatCommand::Jump => "jump",
             ServerChatCommand::Kick => "kick",
             ServerChatCommand::Kill => "kill",
             ServerChatCommand::KillNpcs => "kill_npcs",
             ServerChatCommand::Kit => "kit",
             ServerChatCommand::Lantern => "lantern",
+            ServerChatCommand::Reputation => "reputation",
             ServerChatCommand::Respawn => "respawn",
             ServerChatCommand::Light => "light",
             ServerChatCommand::MakeBlock => "make_block",
@@ -1226,6 +1330,7 @@ impl ServerChatCommand {
             ServerChatCommand::ResetRecipes => "reset_recipes",
             ServerChatCommand::Region => "region",
             ServerChatCommand::ReloadChunks => "reload_chunks",
+            ServerChatCommand::ReloadPlugin => "reload_plugin",
             ServerChatCommand::RemoveLights => "remove_lights",
             ServerChatCommand::RevokeBuild => "revoke_build",
             ServerChatCommand::RevokeBuildAll => "revoke_build_all",
@@ -1242,6 +1347,8 @@ impl ServerChatCommand {
             ServerChatCommand::Spot => "spot",
             ServerChatCommand::Sudo => "sudo",
             ServerChatCommand::Tell => "tell",
+            ServerChatCommand::TerrainHistory => "terrain_history",
+            ServerChatCommand::TerrainRollback => "terrain_rollback",
             ServerChatCommand::Time => "time",
             ServerChatCommand::TimeScale => "time_scale",
             ServerChatCommand::Tp => "tp",
@@ -1,8 +1,8 @@
 use crate::{
     assets::{AssetExt, Ron},
     comp::{
-        Alignment, Body, Buffs, CharacterState, Combo, Energy, Group, Health, HealthChange,
-        InputKind, Inventory, Mass, Ori, Player, Poise, PoiseChange, SkillSet, Stats,
+        Alignment, Body, Buffs, CharacterState, Combo, Duel, Energy, Group, Guild, Health,
+        HealthChange, InputKind, Inventory, Mass, Ori, Player, Poise, PoiseChange, SkillSet, Stats,
         ability::Capability,
         aura::{AuraKindVariant, EnteredAuras},
         buff::{Buff, BuffChange, BuffData, BuffDescriptor, BuffKind, BuffSource, DestInfo},
@@ -1076,12 +1076,18 @@ pub fn allow_friendly_fire(
 /// e.g. if player with PvE mode will harm pets of other players
 /// or other players will do the same to such player.
 ///
+/// Members of the same guild are treated like the same player, so they (and
+/// their pets) can only harm each other with friendly fire, unless they are in
+/// a duel with each other.
+///
 /// If both players have PvP mode enabled, interact with NPC and
 /// in any other case, this function will return `true`
 // TODO: add parameter for doing self-harm?
 pub fn permit_pvp(
     alignments: &ReadStorage<Alignment>,
     players: &ReadStorage<Player>,
+    guilds: &ReadStorage<Guild>,
+    duels: &ReadStorage<Duel>,
     entered_auras: &ReadStorage<EnteredAuras>,
     id_maps: &IdMaps,
     attacker: Option<EcsEntity>,
@@ -1133,6 +1139,22 @@ pub fn permit_pvp(
         return allow_friendly_fire(entered_auras, attacker, target);
     }
 
+    // Duelists may harm each other, regardless of anything else
+    if duels
+        .get(attacker_owner)
+        .is_some_and(|duel| id_maps.uid_entity(duel.opponent) == Some(target_owner))
+    {
+        return true;
+    }
+
+    // Guild members are allies, regardless of their battle mode
+    if let (Some(attacker_guild), Some(target_guild)) =
+        (guilds.get(attacker_owner), guilds.get(target_owner))
+        && attacker_guild.id == target_guild.id
+    {
+        return allow_friendly_fire(entered_auras, attacker, target);
+    }
+
     // Get player components
     let attacker_info = players.get(attacker_owner);
     let target_info = players.get(target_owner);
@@ -2436,3 +2458,88 @@ pub fn get_equip_slot_by_block_priority(inventory: Option<&Inventory>) -> EquipS
             },
         )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        comp::{aura::AuraKey, guild::GuildId},
+        resources::BattleMode,
+    };
+    use specs::{Builder, World, WorldExt};
+
+    fn permit(world: &World, attacker: EcsEntity, target: EcsEntity) -> bool {
+        permit_pvp(
+            &world.read_storage(),
+            &world.read_storage(),
+            &world.read_storage(),
+            &world.read_storage(),
+            &world.read_storage(),
+            &world.read_resource::<IdMaps>(),
+            Some(attacker),
+            target,
+        )
+    }
+
+    #[test]
+    fn duel_does_not_extend_to_guild() {
+        let mut world = World::new();
+        world.insert(IdMaps::new());
+        world.register::<Alignment>();
+        world.register::<Player>();
+        world.register::<Guild>();
+        world.register::<Duel>();
+        world.register::<EnteredAuras>();
+
+        let [duelist, opponent, guildmate] = [(); 3].map(|_| {
+            world
+                .create_entity()
+                .with(Player::new(
+                    "player".to_owned(),
+                    BattleMode::PvP,
+                    uuid::Uuid::nil(),
+                    None,
+                ))
+                .with(Guild {
+                    id: GuildId(1),
+                    name: "guild".to_owned(),
+                })
+                .build()
+        });
+        let (duelist_uid, opponent_uid) = {
+            let mut id_maps = world.write_resource::<IdMaps>();
+            (id_maps.allocate(duelist), id_maps.allocate(opponent))
+        };
+        {
+            let mut duels = world.write_storage::<Duel>();
+            duels
+                .insert(duelist, Duel::new(opponent_uid, Vec3::zero(), Time(0.0)))
+                .unwrap();
+            duels
+                .insert(opponent, Duel::new(duelist_uid, Vec3::zero(), Time(0.0)))
+                .unwrap();
+        }
+
+        assert!(permit(&world, duelist, opponent));
+        assert!(permit(&world, opponent, duelist));
+        assert!(!permit(&world, duelist, guildmate));
+        assert!(!permit(&world, guildmate, duelist));
+
+        let friendly_fire = EnteredAuras {
+            auras: [(
+                AuraKindVariant::FriendlyFire,
+                [(duelist_uid, AuraKey::default())].into_iter().collect(),
+            )]
+            .into_iter()
+            .collect(),
+        };
+        let mut entered_auras = world.write_storage::<EnteredAuras>();
+        entered_auras
+            .insert(duelist, friendly_fire.clone())
+            .unwrap();
+        entered_auras.insert(guildmate, friendly_fire).unwrap();
+        drop(entered_auras);
+        assert!(permit(&world, duelist, guildmate));
+        assert!(permit(&world, guildmate, duelist));
+    }
+}
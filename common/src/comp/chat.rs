@@ -19,7 +19,7 @@ pub enum ChatMode {
     Region,
     /// Talk to your current group of players
     Group,
-    /// Talk to your faction
+    /// Talk to your guild, by guild name
     Faction(String),
     /// Talk to every player on the server
     World,
@@ -102,9 +102,11 @@ pub enum ChatType<G> {
     /// Inform players that someone died (Source, Victim) Source may be None
     /// (ex: fall damage)
     Kill(KillSource, Uid),
+    /// Inform players about the outcome of a duel between two players
+    Duel(Uid, Uid),
     /// Server notifications to a group, such as player join/leave
     GroupMeta(G),
-    /// Server notifications to a faction, such as player join/leave
+    /// Server notifications to a guild, such as members joining or leaving
     FactionMeta(String),
     /// One-on-one chat (from, to)
     Tell(Uid, Uid),
@@ -112,7 +114,7 @@ pub enum ChatType<G> {
     Say(Uid),
     /// Group chat
     Group(Uid, G),
-    /// Factional chat
+    /// Guild chat
     Faction(Uid, String),
     /// Regional chat
     Region(Uid),
@@ -167,6 +169,7 @@ impl<G> ChatType<G> {
             ChatType::FactionMeta(_) => None,
             ChatType::GroupMeta(_) => None,
             ChatType::Kill(_, _) => None,
+            ChatType::Duel(_, _) => None,
             ChatType::Tell(u, _t) => Some(*u),
             ChatType::Say(u) => Some(*u),
             ChatType::Group(u, _s) => Some(*u),
@@ -193,7 +196,8 @@ impl<G> ChatType<G> {
             | ChatType::NpcSay(_)
             | ChatType::NpcTell(_, _)
             | ChatType::Meta
-            | ChatType::Kill(_, _) => None,
+            | ChatType::Kill(_, _)
+            | ChatType::Duel(_, _) => None,
             ChatType::Tell(_, _) | ChatType::Group(_, _) | ChatType::Faction(_, _) => Some(true),
             ChatType::Say(_) | ChatType::Region(_) | ChatType::World(_) => Some(false),
         }
@@ -248,6 +252,7 @@ impl<G> GenericChatMsg<G> {
             ChatType::FactionMeta(a) => ChatType::FactionMeta(a),
             ChatType::GroupMeta(g) => ChatType::GroupMeta(f(g)),
             ChatType::Kill(a, b) => ChatType::Kill(a, b),
+            ChatType::Duel(a, b) => ChatType::Duel(a, b),
             ChatType::Tell(a, b) => ChatType::Tell(a, b),
             ChatType::Say(a) => ChatType::Say(a),
             ChatType::Group(a, g) => ChatType::Group(a, f(g)),
@@ -288,6 +293,7 @@ impl<G> GenericChatMsg<G> {
             ChatType::FactionMeta(_) => SpeechBubbleType::None,
             ChatType::GroupMeta(_) => SpeechBubbleType::None,
             ChatType::Kill(_, _) => SpeechBubbleType::None,
+            ChatType::Duel(_, _) => SpeechBubbleType::None,
             ChatType::Tell(_u, _) => SpeechBubbleType::Tell,
             ChatType::Say(_u) => SpeechBubbleType::Say,
             ChatType::Group(_u, _s) => SpeechBubbleType::Group,
@@ -311,19 +317,6 @@ impl<G> GenericChatMsg<G> {
     pub fn set_content(&mut self, content: Content) { self.content = content; }
 }
 
-/// Player factions are used to coordinate pvp vs hostile factions or segment
-/// chat from the world
-///
-/// Factions are currently just an associated String (the faction's name)
-#[derive(Clone, Debug)]
-pub struct Faction(pub String);
-impl Component for Faction {
-    type Storage = DenseVecStorage<Self>;
-}
-impl From<String> for Faction {
-    fn from(s: String) -> Self { Faction(s) }
-}
-
 /// List of chat types for players and NPCs. Each one has its own icon.
 ///
 /// This is a subset of `ChatType`, and a superset of `ChatMode`
@@ -7,6 +7,8 @@ use crate::{
             slot::{EquipSlot, InvSlotId, Slot},
         },
         invite::{InviteKind, InviteResponse},
+        mail::MailAction,
+        market::MarketAction,
     },
     mounting::VolumePos,
     rtsim,
@@ -34,6 +36,13 @@ pub enum InventoryEvent {
     OverflowMove(usize, InvSlotId),
     OverflowDrop(usize),
     OverflowSplitDrop(usize),
+    OpenStorage(Vec3<i32>),
+    CloseStorage,
+    /// Moves an item from the inventory into the personal storage
+    Store(InvSlotId, InvSlotId),
+    /// Moves an item from the personal storage into the inventory
+    Retrieve(InvSlotId, InvSlotId),
+    StorageSwap(InvSlotId, InvSlotId),
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -68,6 +77,17 @@ pub enum InventoryManip {
     },
     SwapEquippedWeapons,
     Delete(InvSlotId, NonZeroU32),
+    /// Opens the personal storage at the storage sprite at this position
+    OpenStorage(Vec3<i32>),
+    CloseStorage,
+    /// Moves an item from the inventory into the personal storage, the first
+    /// slot is the inventory slot and the second one the storage slot
+    Store(InvSlotId, InvSlotId),
+    /// Moves an item from the personal storage into the inventory, the first
+    /// slot is the storage slot and the second one the inventory slot
+    Retrieve(InvSlotId, InvSlotId),
+    /// Swaps two slots of the personal storage
+    StorageSwap(InvSlotId, InvSlotId),
 }
 
 impl From<InventoryEvent> for InventoryManip {
@@ -95,6 +115,11 @@ impl From<InventoryEvent> for InventoryManip {
             },
             InventoryEvent::OverflowDrop(o) => Self::Drop(Slot::Overflow(o)),
             InventoryEvent::OverflowSplitDrop(o) => Self::SplitDrop(Slot::Overflow(o)),
+            InventoryEvent::OpenStorage(pos) => Self::OpenStorage(pos),
+            InventoryEvent::CloseStorage => Self::CloseStorage,
+            InventoryEvent::Store(inv, storage) => Self::Store(inv, storage),
+            InventoryEvent::Retrieve(storage, inv) => Self::Retrieve(storage, inv),
+            InventoryEvent::StorageSwap(a, b) => Self::StorageSwap(a, b),
         }
     }
 }
@@ -158,6 +183,8 @@ pub enum ControlEvent {
     SetPetStay(Uid, bool),
     InventoryEvent(InventoryEvent),
     GroupManip(GroupManip),
+    Mail(MailAction),
+    Market(MarketAction),
     RemoveBuff(BuffKind),
     LeaveStance,
     GiveUp,
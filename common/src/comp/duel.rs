@@ -0,0 +1,62 @@
+use crate::{comp::Health, resources::Time, uid::Uid};
+use specs::{Component, DenseVecStorage};
+use vek::*;
+
+// Duels
+//
+// A duel is a fight between exactly two players that agreed to it, which is
+// independent of their battle mode. It takes place in an arena around the
+// point between both duelists and ends once one of them is low on health, gives
+// up, leaves the arena or the time runs out. Nobody dies in a duel.
+
+/// Maximum distance between two players for one to challenge the other
+pub const MAX_DUEL_INVITE_RANGE: f32 = 30.0;
+/// Horizontal radius of the arena
+pub const DUEL_ARENA_RADIUS: f32 = 40.0;
+/// Duration of a duel in seconds, after which it ends in a draw
+pub const DUEL_DURATION: f64 = 180.0;
+/// A duelist loses once their health drops to this fraction of its maximum
+pub const DUEL_LOSS_HEALTH_FRACTION: f32 = 0.1;
+
+/// An ongoing duel.
+///
+/// Both duelists have this component, each pointing to the other one. It is
+/// only present on the server.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Duel {
+    pub opponent: Uid,
+    /// Center of the arena
+    pub arena: Vec3<f32>,
+    /// When the duel ends in a draw
+    pub end_time: Time,
+    /// Set when the duelist gives up
+    pub forfeited: bool,
+}
+
+impl Duel {
+    pub fn new(opponent: Uid, arena: Vec3<f32>, time: Time) -> Self {
+        Self {
+            opponent,
+            arena,
+            end_time: Time(time.0 + DUEL_DURATION),
+            forfeited: false,
+        }
+    }
+
+    pub fn in_arena(&self, pos: Vec3<f32>) -> bool {
+        self.arena.xy().distance_squared(pos.xy()) <= DUEL_ARENA_RADIUS.powi(2)
+    }
+
+    /// Whether the health of a duelist is low enough for them to lose
+    pub fn is_defeated(health: &Health) -> bool { health.fraction() <= DUEL_LOSS_HEALTH_FRACTION }
+
+    /// The most damage the opponent may still deal, so that the duel ends
+    /// before anyone dies
+    pub fn max_damage(health: &Health) -> f32 {
+        (health.current() - health.maximum() * DUEL_LOSS_HEALTH_FRACTION).max(0.0)
+    }
+}
+
+impl Component for Duel {
+    type Storage = DenseVecStorage<Self>;
+}
@@ -0,0 +1,158 @@
+use crate::{character::CharacterId, uid::Uid};
+use serde::{Deserialize, Serialize};
+use specs::{Component, DenseVecStorage};
+use strum::{AsRefStr, EnumIter, EnumString};
+
+// Player guilds
+//
+// Unlike groups, guilds are made of characters rather than entities: they are
+// persisted and members stay part of their guild while they are offline. The
+// server keeps track of every guild, this module only contains what is shared
+// with clients and the systems of `common`.
+
+/// Maximum length of a guild name, in characters
+pub const MAX_GUILD_NAME_LEN: usize = 32;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct GuildId(pub u64);
+
+/// Ranks are ordered, higher ranks have every permission of the lower ones.
+#[derive(
+    Copy,
+    Clone,
+    Debug,
+    PartialEq,
+    Eq,
+    Hash,
+    PartialOrd,
+    Ord,
+    Serialize,
+    Deserialize,
+    EnumIter,
+    EnumString,
+    AsRefStr,
+)]
+#[strum(serialize_all = "snake_case")]
+pub enum GuildRank {
+    Member,
+    Officer,
+    Leader,
+}
+
+impl GuildRank {
+    pub fn can_invite(self) -> bool { self >= GuildRank::Officer }
+
+    /// Whether a member of this rank may remove a member of rank `other` from
+    /// the guild
+    pub fn can_kick(self, other: GuildRank) -> bool { self >= GuildRank::Officer && self > other }
+
+    pub fn can_assign_ranks(self) -> bool { self == GuildRank::Leader }
+}
+
+/// The guild of a character that is currently in game.
+///
+/// Only present on the server, where it is used to route guild chat and to
+/// keep guild members from harming each other.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Guild {
+    pub id: GuildId,
+    pub name: String,
+}
+
+impl Component for Guild {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Everything a member knows about their guild, sent whenever it changes
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GuildRoster {
+    pub id: GuildId,
+    pub name: String,
+    /// Ordered by rank, highest first
+    pub members: Vec<GuildRosterMember>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GuildRosterMember {
+    pub character_id: CharacterId,
+    /// Name of the character
+    pub name: String,
+    pub rank: GuildRank,
+    /// Set if the character is currently in game
+    pub online: Option<Uid>,
+}
+
+/// Changes a character can make to their guild. Members are identified by
+/// character name, since they may be offline.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GuildManip {
+    Create(String),
+    Leave,
+    Kick(String),
+    AssignRank(String, GuildRank),
+    Disband,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum GuildNameError {
+    Empty,
+    TooLong,
+    ForbiddenCharacters,
+}
+
+/// Guild names may contain letters, digits, single spaces, `-` and `_`.
+pub fn validate_guild_name(name: &str) -> Result<(), GuildNameError> {
+    if name.is_empty() {
+        Err(GuildNameError::Empty)
+    } else if name.chars().count() > MAX_GUILD_NAME_LEN {
+        Err(GuildNameError::TooLong)
+    } else if name.starts_with(' ')
+        || name.ends_with(' ')
+        || name.contains("  ")
+        || !name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == ' ' || c == '-' || c == '_')
+    {
+        Err(GuildNameError::ForbiddenCharacters)
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rank_permissions() {
+        assert!(GuildRank::Leader.can_kick(GuildRank::Officer));
+        assert!(GuildRank::Officer.can_kick(GuildRank::Member));
+        assert!(!GuildRank::Officer.can_kick(GuildRank::Officer));
+        assert!(!GuildRank::Member.can_kick(GuildRank::Member));
+        assert!(GuildRank::Officer.can_invite());
+        assert!(!GuildRank::Member.can_invite());
+        assert!(!GuildRank::Officer.can_assign_ranks());
+    }
+
+    #[test]
+    fn guild_names() {
+        assert_eq!(validate_guild_name("Knights of Veloren"), Ok(()));
+        assert_eq!(validate_guild_name(""), Err(GuildNameError::Empty));
+        assert_eq!(
+            validate_guild_name(&"a".repeat(MAX_GUILD_NAME_LEN + 1)),
+            Err(GuildNameError::TooLong)
+        );
+        assert_eq!(
+            validate_guild_name(" padded"),
+            Err(GuildNameError::ForbiddenCharacters)
+        );
+        assert_eq!(
+            validate_guild_name("two  spaces"),
+            Err(GuildNameError::ForbiddenCharacters)
+        );
+        assert_eq!(
+            validate_guild_name("<b>bold</b>"),
+            Err(GuildNameError::ForbiddenCharacters)
+        );
+    }
+}
@@ -587,7 +587,7 @@ impl Ord for ItemDefinitionIdOwned {
 }
 
 impl TradePricing {
-    const COIN_ITEM: &'static str = "common.items.utility.coins";
+    pub const COIN_ITEM: &'static str = "common.items.utility.coins";
     const CRAFTING_FACTOR: f32 = 0.95;
     // increase price a bit compared to sum of ingredients
     const INVEST_FACTOR: f32 = 0.33;
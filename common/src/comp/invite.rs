@@ -5,6 +5,8 @@ use specs::Component;
 pub enum InviteKind {
     Group,
     Trade,
+    Guild,
+    Duel,
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
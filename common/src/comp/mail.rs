@@ -0,0 +1,80 @@
+use crate::comp::{Content, Item, inventory::slot::InvSlotId};
+use serde::{Deserialize, Serialize};
+use vek::*;
+
+// Player mail
+//
+// Mail is sent between characters, whether the recipient is online or not. It
+// is composed and read at mailbox sprites, and can carry coins and items. The
+// server keeps every mail, clients only receive the mailbox of their character
+// while it is open.
+
+/// Maximum length of the subject of a mail, in characters
+pub const MAX_SUBJECT_LEN: usize = 64;
+/// Maximum length of the text of a mail, in characters
+pub const MAX_BODY_LEN: usize = 1000;
+/// Maximum number of item stacks that can be attached to a single mail
+pub const MAX_ATTACHMENTS: usize = 6;
+/// Maximum number of mail a mailbox holds, further mail is refused
+pub const MAX_MAILBOX_SIZE: usize = 100;
+/// Time in seconds mail stays in the mailbox of its recipient. Afterwards it is
+/// returned to its sender, and returned mail is deleted.
+pub const MAIL_LIFETIME_SECS: i64 = 30 * 24 * 60 * 60;
+/// Expiry of mail sent by the server that carries coins or items. It can't be
+/// returned, so it is kept until they are taken.
+pub const NEVER_EXPIRES: i64 = i64::MAX;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct MailId(pub u64);
+
+/// A mail as shown in the mailbox of its recipient
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Mail {
+    pub id: MailId,
+    /// Name of the character that sent the mail
+    pub sender: String,
+    /// Plain text, unless the mail was sent by the server
+    pub subject: Content,
+    pub body: Content,
+    pub coins: u32,
+    pub attachments: Vec<Item>,
+    /// Unix timestamp
+    pub sent_at: i64,
+    /// Unix timestamp
+    pub expires_at: i64,
+    pub read: bool,
+    /// Whether this mail was sent back, either by its recipient or because it
+    /// expired
+    pub returned: bool,
+}
+
+impl Mail {
+    pub fn has_attachments(&self) -> bool { self.coins > 0 || !self.attachments.is_empty() }
+}
+
+/// A mail composed at a mailbox
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MailDraft {
+    /// Name of the character the mail is sent to
+    pub recipient: String,
+    pub subject: String,
+    pub body: String,
+    pub coins: u32,
+    /// Inventory slots of the items to attach, the whole stack is attached
+    pub attachments: Vec<InvSlotId>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MailAction {
+    /// Opens the mailbox at the mailbox sprite at this position
+    Open(Vec3<i32>),
+    Close,
+    Send(MailDraft),
+    MarkRead(MailId),
+    /// Moves the coins and items attached to the mail into the inventory
+    TakeAttachments(MailId),
+    /// Sends the mail back to its sender, together with its attachments
+    Return(MailId),
+    /// Deletes a mail, only possible once its attachments were taken
+    Delete(MailId),
+}
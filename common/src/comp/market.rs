@@ -0,0 +1,77 @@
+use crate::{
+    comp::{Item, inventory::slot::InvSlotId},
+    trade::SitePrices,
+};
+use serde::{Deserialize, Serialize};
+use vek::*;
+
+// Player market
+//
+// Characters list items for sale at market boards, other characters can buy
+// them at any market board while the seller is offline. The coins of a sale,
+// and the items of listings that expired, are delivered to the seller by mail.
+
+/// Share of the price, in percent, paid when listing an item. It is not
+/// refunded when the listing is taken down or expires.
+pub const LISTING_FEE_PERCENT: u64 = 2;
+/// Share of the price, in percent, kept when an item is sold
+pub const SALE_FEE_PERCENT: u64 = 5;
+/// Maximum number of items a character can list at the same time
+pub const MAX_LISTINGS: usize = 20;
+/// Maximum price of a listing, in coins
+pub const MAX_PRICE: u32 = 1_000_000;
+/// Time in seconds an item stays listed, afterwards it is sent back to its
+/// seller
+pub const LISTING_LIFETIME_SECS: i64 = 7 * 24 * 60 * 60;
+
+/// Fee paid when listing an item at this price, at least one coin
+pub fn listing_fee(price: u32) -> u32 {
+    (price as u64 * LISTING_FEE_PERCENT).div_ceil(100).max(1) as u32
+}
+
+/// Coins the seller receives when the item is sold at this price
+pub fn sale_proceeds(price: u32) -> u32 { price - (price as u64 * SALE_FEE_PERCENT / 100) as u32 }
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct ListingId(pub u64);
+
+/// An item for sale
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Listing {
+    pub id: ListingId,
+    /// Name of the character selling the item
+    pub seller: String,
+    pub item: Item,
+    /// Price of the whole stack, in coins
+    pub price: u32,
+    /// Unix timestamp
+    pub expires_at: i64,
+}
+
+/// The market as seen from a market board
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Market {
+    /// Items listed by other characters, newest first
+    pub listings: Vec<Listing>,
+    /// Items listed by the character itself, newest first
+    pub own_listings: Vec<Listing>,
+    /// Prices of the site the market board is in, used to suggest the price
+    /// of listings
+    pub prices: Option<SitePrices>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MarketAction {
+    /// Opens the market at the market board sprite at this position
+    Open(Vec3<i32>),
+    Close,
+    /// Lists the whole stack in the inventory slot for sale
+    Post {
+        slot: InvSlotId,
+        price: u32,
+    },
+    Buy(ListingId),
+    /// Takes down a listing of the character, the item is put back into its
+    /// inventory
+    Cancel(ListingId),
+}
@@ -12,10 +12,12 @@ pub mod chat;
 pub mod combo;
 pub mod compass;
 pub mod controller;
+pub mod duel;
 mod energy;
 pub mod fluid_dynamics;
 pub mod gizmos;
 pub mod group;
+pub mod guild;
 mod hardcore;
 mod health;
 mod inputs;
@@ -24,6 +26,8 @@ pub mod invite;
 mod last;
 mod location;
 pub mod loot_owner;
+pub mod mail;
+pub mod market;
 pub mod melee;
 pub mod misc;
 pub mod ori;
@@ -37,6 +41,7 @@ pub mod projectile;
 pub mod shockwave;
 pub mod skillset;
 mod stats;
+pub mod storage;
 pub mod teleport;
 pub mod visual;
 
@@ -65,18 +70,18 @@ pub use self::{
         ModifierKind,
     },
     character_state::{CharacterActivity, CharacterState, StateUpdate},
-    chat::{
-        ChatMode, ChatMsg, ChatType, Faction, SpeechBubble, SpeechBubbleType, UnresolvedChatMsg,
-    },
+    chat::{ChatMode, ChatMsg, ChatType, SpeechBubble, SpeechBubbleType, UnresolvedChatMsg},
     combo::Combo,
     controller::{
         ControlAction, ControlEvent, Controller, ControllerInputs, GroupManip, InputAttr,
         InputKind, InventoryAction, InventoryEvent, InventoryManip, UtteranceKind,
     },
+    duel::Duel,
     energy::Energy,
     fluid_dynamics::Fluid,
     gizmos::GizmoSubscriber,
     group::Group,
+    guild::{Guild, GuildManip},
     hardcore::Hardcore,
     inputs::CanBuild,
     inventory::{
@@ -92,6 +97,8 @@ pub use self::{
     last::Last,
     location::{MapMarker, MapMarkerChange, MapMarkerUpdate, Waypoint, WaypointArea},
     loot_owner::LootOwner,
+    mail::MailAction,
+    market::MarketAction,
     melee::{Melee, MeleeConstructor, MeleeConstructorKind},
     misc::Object,
     ori::Ori,
@@ -111,6 +118,7 @@ pub use self::{
         skills::{self, Skill},
     },
     stats::{Stats, StatsModifier},
+    storage::PersonalStorage,
     teleport::Teleporting,
     visual::{FrontendMarker, LightAnimation, LightEmitter},
 };
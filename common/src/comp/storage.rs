@@ -0,0 +1,90 @@
+use crate::comp::{Inventory, inventory::slot::InvSlotId};
+use specs::{Component, DenseVecStorage};
+use vek::*;
+
+// Personal storage
+//
+// Every character has a storage for the items it doesn't want to carry around.
+// It is the same storage everywhere and can be accessed at any storage sprite,
+// for example the chests and wardrobes in houses.
+
+/// The personal storage of a character.
+///
+/// Only present on the server, the client receives the content of the storage
+/// while it is open.
+#[derive(Clone, Debug)]
+pub struct PersonalStorage {
+    /// Only the built-in slots of the inventory are used, items are never
+    /// equipped in a storage
+    pub inventory: Inventory,
+    /// Position of the storage sprite the storage was opened at
+    pub opened_at: Option<Vec3<i32>>,
+}
+
+impl PersonalStorage {
+    pub fn new(inventory: Inventory) -> Self {
+        Self {
+            inventory,
+            opened_at: None,
+        }
+    }
+}
+
+impl Default for PersonalStorage {
+    fn default() -> Self { Self::new(Inventory::with_empty()) }
+}
+
+impl Component for PersonalStorage {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Moves the item in slot `from` of `src` into slot `to` of `dst`, either
+/// stacking it onto the item that is already there or swapping both items.
+pub fn transfer(src: &mut Inventory, from: InvSlotId, dst: &mut Inventory, to: InvSlotId) {
+    if dst.slot(to).is_none() {
+        return;
+    }
+    let Some(item) = src.remove(from) else {
+        return;
+    };
+    match dst.insert_or_stack_at(to, item) {
+        Ok(None) => {},
+        // Either the item that was in `to` before, or the item itself if it couldn't be
+        // stacked. The slot was just emptied, so this can't fail.
+        Ok(Some(item)) | Err(item) => {
+            let _ = src.insert_at(from, item);
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::comp::Item;
+
+    fn slot(slot: u16) -> InvSlotId { InvSlotId::new(0, slot) }
+
+    #[test]
+    fn transfer_moves_swaps_and_stacks() {
+        let mut inventory = Inventory::with_empty();
+        let mut storage = Inventory::with_empty();
+        let cheese = || Item::new_from_asset_expect("common.items.food.cheese");
+        let apple = || Item::new_from_asset_expect("common.items.food.apple");
+        inventory.insert_at(slot(0), cheese()).unwrap();
+        inventory.insert_at(slot(1), cheese()).unwrap();
+        storage.insert_at(slot(0), apple()).unwrap();
+
+        transfer(&mut inventory, slot(0), &mut storage, slot(1));
+        assert!(inventory.get(slot(0)).is_none());
+        assert_eq!(storage.get(slot(1)).map(Item::amount), Some(1));
+
+        transfer(&mut inventory, slot(1), &mut storage, slot(1));
+        assert!(inventory.get(slot(1)).is_none());
+        assert_eq!(storage.get(slot(1)).map(Item::amount), Some(2));
+
+        transfer(&mut storage, slot(1), &mut inventory, slot(0));
+        transfer(&mut inventory, slot(0), &mut storage, slot(0));
+        assert_eq!(storage.get(slot(0)), Some(&cheese()));
+        assert_eq!(inventory.get(slot(0)), Some(&apple()));
+    }
+}
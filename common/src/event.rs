@@ -235,10 +235,12 @@ pub struct UpdateCharacterDataEvent {
         comp::Stats,
         comp::SkillSet,
         comp::Inventory,
+        comp::PersonalStorage,
         Option<comp::Waypoint>,
         Vec<(comp::Pet, comp::Body, comp::Stats)>,
         comp::ActiveAbilities,
         Option<comp::MapMarker>,
+        Option<crate::rtsim::PersistedReputation>,
     ),
     pub metadata: UpdateCharacterMetadata,
 }
@@ -285,6 +287,36 @@ pub struct RequestPluginsEvent {
     pub plugins: Vec<PluginHash>,
 }
 
+/// Gameplay events which are forwarded to server plugins once per tick.
+///
+/// Entities are referred to by [`Uid`] since they might already be deleted
+/// when the plugins are called.
+#[cfg(feature = "plugins")]
+#[derive(Clone, Debug)]
+pub enum PluginGameEvent {
+    EntityDeath {
+        entity: Uid,
+        killer: Option<Uid>,
+    },
+    EntityDamage {
+        entity: Uid,
+        attacker: Option<Uid>,
+        amount: f32,
+    },
+    ItemPickup {
+        entity: Uid,
+        item: String,
+        amount: u32,
+    },
+    ChunkLoad {
+        key: Vec2<i32>,
+    },
+    CharacterStateChange {
+        entity: Uid,
+        state: String,
+    },
+}
+
 pub struct SetBattleModeEvent {
     pub entity: EcsEntity,
     pub battle_mode: BattleMode,
@@ -380,6 +412,12 @@ pub struct InventoryManipEvent(pub EcsEntity, pub comp::InventoryManip);
 
 pub struct GroupManipEvent(pub EcsEntity, pub comp::GroupManip);
 
+pub struct GuildManipEvent(pub EcsEntity, pub comp::GuildManip);
+
+pub struct MailEvent(pub EcsEntity, pub comp::MailAction);
+
+pub struct MarketEvent(pub EcsEntity, pub comp::MarketAction);
+
 pub struct RespawnEvent(pub EcsEntity);
 
 pub struct ShootEvent {
@@ -154,8 +154,10 @@ pub struct PlayerPhysicsSettings {
 
 /// Describe how players interact with other players.
 ///
+/// Duels don't depend on it, see [`Duel`](crate::comp::Duel).
+///
 /// May be removed when we will discover better way
-/// to handle duels and murders
+/// to handle murders
 #[derive(PartialEq, Eq, Copy, Clone, Debug, Deserialize, Serialize)]
 pub enum BattleMode {
     PvP,
@@ -20,6 +20,7 @@ use crate::{
     util::Dir,
 };
 use common_i18n::Content;
+use hashbrown::HashMap;
 use rand::{RngExt, seq::IteratorRandom};
 use serde::{Deserialize, Serialize};
 use specs::Component;
@@ -495,3 +496,131 @@ impl Default for WorldSettings {
         }
     }
 }
+
+/// The standing of a player character with the sites and factions of the
+/// world.
+///
+/// Reputation is owned by rtsim, which derives it from (and feeds it back
+/// into) the sentiments of NPCs, and is mirrored onto the character entity as
+/// a [`PersistedReputation`] so that it can be saved. Values are in the range
+/// `-1.0..=1.0` and absent entries are neutral.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Reputation {
+    sites: HashMap<SiteId, f32>,
+    factions: HashMap<FactionId, f32>,
+}
+
+impl Reputation {
+    /// Reputation changes smaller than this are not worth remembering.
+    const NEGLIGIBLE: f32 = 0.005;
+
+    pub fn site(&self, site: SiteId) -> f32 { self.sites.get(&site).copied().unwrap_or(0.0) }
+
+    pub fn faction(&self, faction: FactionId) -> f32 {
+        self.factions.get(&faction).copied().unwrap_or(0.0)
+    }
+
+    /// The combined standing with a site and a faction (usually the home of an
+    /// NPC).
+    ///
+    /// Bad reputation with either one takes precedence, otherwise the better
+    /// of the two is used.
+    pub fn standing(&self, site: Option<SiteId>, faction: Option<FactionId>) -> f32 {
+        let site = site.map_or(0.0, |site| self.site(site));
+        let faction = faction.map_or(0.0, |faction| self.faction(faction));
+        if site < 0.0 || faction < 0.0 {
+            site.min(faction)
+        } else {
+            site.max(faction)
+        }
+    }
+
+    pub fn set_site(&mut self, site: SiteId, value: f32) {
+        Self::set(&mut self.sites, site, value);
+    }
+
+    pub fn set_faction(&mut self, faction: FactionId, value: f32) {
+        Self::set(&mut self.factions, faction, value);
+    }
+
+    pub fn change_site(&mut self, site: SiteId, change: f32) {
+        self.set_site(site, self.site(site) + change);
+    }
+
+    pub fn change_faction(&mut self, faction: FactionId, change: f32) {
+        self.set_faction(faction, self.faction(faction) + change);
+    }
+
+    pub fn sites(&self) -> impl ExactSizeIterator<Item = (SiteId, f32)> + '_ {
+        self.sites.iter().map(|(site, value)| (*site, *value))
+    }
+
+    pub fn factions(&self) -> impl ExactSizeIterator<Item = (FactionId, f32)> + '_ {
+        self.factions
+            .iter()
+            .map(|(faction, value)| (*faction, *value))
+    }
+
+    fn set<K: Eq + core::hash::Hash>(map: &mut HashMap<K, f32>, key: K, value: f32) {
+        let value = value.clamp(-1.0, 1.0);
+        if value.abs() < Self::NEGLIGIBLE {
+            map.remove(&key);
+        } else {
+            map.insert(key, value);
+        }
+    }
+}
+
+/// A [`Reputation`] as it is persisted with the character.
+///
+/// Site and faction ids are slotmap keys that change whenever rtsim data is
+/// regenerated, so sites are identified by their position and factions by
+/// their seed instead. Both lists are sorted, so that equal reputations
+/// compare equal.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct PersistedReputation {
+    pub sites: Vec<(Vec2<i32>, f32)>,
+    pub factions: Vec<(u32, f32)>,
+}
+
+impl Component for PersistedReputation {
+    type Storage = specs::DenseVecStorage<Self>;
+}
+
+/// A coarse, human-readable description of a reputation value.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, EnumIter)]
+pub enum ReputationLevel {
+    Hated,
+    Hostile,
+    Unfriendly,
+    Neutral,
+    Friendly,
+    Honored,
+    Revered,
+}
+
+impl ReputationLevel {
+    pub fn from_value(value: f32) -> Self {
+        match value {
+            x if x <= -0.8 => Self::Hated,
+            x if x <= -0.6 => Self::Hostile,
+            x if x <= -0.3 => Self::Unfriendly,
+            x if x >= 0.8 => Self::Revered,
+            x if x >= 0.6 => Self::Honored,
+            x if x >= 0.3 => Self::Friendly,
+            _ => Self::Neutral,
+        }
+    }
+
+    pub fn localization_key(&self) -> &'static str {
+        match self {
+            Self::Hated => "reputation-level-hated",
+            Self::Hostile => "reputation-level-hostile",
+            Self::Unfriendly => "reputation-level-unfriendly",
+            Self::Neutral => "reputation-level-neutral",
+            Self::Friendly => "reputation-level-friendly",
+            Self::Honored => "reputation-level-honored",
+            Self::Revered => "reputation-level-revered",
+        }
+    }
+}
@@ -22,7 +22,7 @@ use strum::EnumIter;
 ///
 /// Only add spots with randomly spawned NPCs here. Spots that only use
 /// EntitySpawner blocks can be added in assets/world/manifests/spots.ron
-#[derive(Copy, Clone, Debug, EnumIter, PartialEq)]
+#[derive(Copy, Clone, Debug, EnumIter, PartialEq, serde::Deserialize)]
 pub enum Spot {
     DwarvenGrave,
     SaurokAltar,
@@ -53,6 +53,7 @@ pub enum Spot {
     SaurokTotem,
     JungleOutpost,
     #[strum(disabled)]
+    #[serde(skip)]
     RonFile(&'static SpotProperties),
 }
 
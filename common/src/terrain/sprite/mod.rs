@@ -171,6 +171,8 @@ sprites! {
         BedrollPirate = 0x63,
         Sign          = 0x64,
         Helm          = 0x65,
+        Mailbox       = 0x66,
+        MarketBoard   = 0x67,
         // Misc
         Scarecrow      = 0x70,
         FountainArabic = 0x71,
@@ -818,6 +820,8 @@ impl SpriteKind {
             SpriteKind::MagicalSeal => 1.0,
             SpriteKind::Helm => 1.909,
             SpriteKind::Sign => 16.0 / 11.0,
+            SpriteKind::Mailbox => 14.0 / 11.0,
+            SpriteKind::MarketBoard => 20.0 / 11.0,
             SpriteKind::SmithingTable => 13.0 / 11.0,
             SpriteKind::Forge0 => 17.0 / 11.0,
             SpriteKind::GearWheel0 => 3.0 / 11.0,
@@ -1218,6 +1222,32 @@ impl SpriteKind {
     #[inline]
     pub fn is_controller(&self) -> bool { matches!(self, SpriteKind::Helm) }
 
+    /// Whether characters can access their personal storage at this sprite
+    #[inline]
+    pub fn is_storage(&self) -> bool {
+        matches!(
+            self,
+            SpriteKind::ChestWoodDouble
+                | SpriteKind::CupboardArabic
+                | SpriteKind::CupboardMesa
+                | SpriteKind::WardrobeSingleMesa
+                | SpriteKind::WardrobeDoubleMesa
+                | SpriteKind::WardrobesingleWoodWoodland
+                | SpriteKind::WardrobesingleWoodWoodland2
+                | SpriteKind::WardrobedoubleWoodWoodland
+                | SpriteKind::WardrobedoubleWoodWoodland2
+        )
+    }
+
+    /// Whether characters can send and receive mail at this sprite
+    #[inline]
+    pub fn is_mailbox(&self) -> bool { matches!(self, SpriteKind::Mailbox) }
+
+    /// Whether characters can buy and sell items on the player market at this
+    /// sprite
+    #[inline]
+    pub fn is_market_board(&self) -> bool { matches!(self, SpriteKind::MarketBoard) }
+
     #[inline]
     pub fn is_door(&self) -> bool {
         matches!(
@@ -3,7 +3,10 @@ use std::cmp::Ordering;
 use crate::{
     comp::inventory::{
         Inventory,
-        item::{AbilityMap, Item, ItemDefinitionIdOwned, MaterialStatManifest, Quality},
+        item::{
+            AbilityMap, Item, ItemDefinitionId, ItemDefinitionIdOwned, MaterialStatManifest,
+            Quality,
+        },
         slot::InvSlotId,
         trade_pricing::TradePricing,
     },
@@ -403,9 +406,29 @@ pub struct SiteInformation {
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct SitePrices {
     pub values: HashMap<Good, f32>,
+    /// How much more the merchant pays for goods they buy, and how much less
+    /// they ask for goods they sell, based on the reputation of the trading
+    /// partner (see [`SitePrices::with_reputation`]).
+    #[serde(default)]
+    pub reputation_factor: Option<f32>,
 }
 
 impl SitePrices {
+    /// The largest fraction by which reputation can change prices.
+    pub const MAX_REPUTATION_PRICE_CHANGE: f32 = 0.2;
+
+    /// Adjust prices to the reputation (in the range `-1.0..=1.0`) that the
+    /// trading partner has with the merchant.
+    #[must_use]
+    pub fn with_reputation(self, reputation: f32) -> Self {
+        Self {
+            reputation_factor: Some(
+                1.0 + reputation.clamp(-1.0, 1.0) * Self::MAX_REPUTATION_PRICE_CHANGE,
+            ),
+            ..self
+        }
+    }
+
     pub fn balance(
         &self,
         offers: &[HashMap<InvSlotId, u32>; 2],
@@ -443,6 +466,9 @@ impl SitePrices {
                                     )
                                 } else {
                                     1.0
+                                })
+                                * self.reputation_factor.map_or(1.0, |factor| {
+                                    if reduce { factor } else { 1.0 / factor }
                                 }),
                         )
                     })
@@ -450,6 +476,24 @@ impl SitePrices {
             })
             .try_fold(0.0, |a, p| Some(a + p?))
     }
+
+    /// The worth of an item stack in coins at these prices, without any
+    /// discount. Used to suggest prices on the player market.
+    pub fn coin_value(&self, item: &Item) -> Option<u32> {
+        let value = |name: &ItemDefinitionId| {
+            TradePricing::get_materials(name).map(|materials| {
+                materials
+                    .iter()
+                    .map(|(amount, good)| {
+                        self.values.get(good).copied().unwrap_or_default() * amount
+                    })
+                    .sum::<f32>()
+            })
+        };
+        let coin = value(&ItemDefinitionId::Simple(TradePricing::COIN_ITEM.into()))?;
+        let stack = value(&item.item_definition_id())? * item.amount() as f32;
+        (coin > 0.0).then(|| (stack / coin).round() as u32)
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -6,6 +6,7 @@ pub enum PluginError {
     Toml(toml::de::Error),
     NoConfig,
     NoSuchModule,
+    NoSuchPlugin,
     Encoding(Box<DecodeError>),
     PluginModuleError(String, String, PluginModuleError),
     FromDirDoesNotExist,
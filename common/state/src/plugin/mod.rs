@@ -1,9 +1,15 @@
 pub mod errors;
 pub mod memory_manager;
 pub mod module;
+pub mod storage;
 
 use bincode::error::DecodeError;
-use common::{assets::ASSETS_PATH, event::PluginHash, uid::Uid};
+use common::{
+    assets::ASSETS_PATH,
+    comp::BuffKind,
+    event::{PluginGameEvent, PluginHash},
+    uid::Uid,
+};
 use serde::{Deserialize, Serialize};
 use std::{
     collections::{HashMap, HashSet},
@@ -12,11 +18,13 @@ use std::{
     path::{Path, PathBuf},
 };
 use tracing::{error, info};
+use vek::Vec3;
 
 use self::{
     errors::{PluginError, PluginModuleError},
     memory_manager::EcsWorld,
     module::PluginModule,
+    storage::PluginStorage,
 };
 
 use sha2::Digest;
@@ -26,6 +34,46 @@ pub struct PluginData {
     name: String,
     modules: HashSet<PathBuf>,
     dependencies: HashSet<String>,
+    #[serde(default)]
+    permissions: HashSet<PluginPermission>,
+}
+
+/// Capabilities a plugin has to request in its `plugin.toml` before it is
+/// allowed to call the corresponding functions of the `gameplay` interface.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PluginPermission {
+    SpawnNpc,
+    Teleport,
+    GiveItem,
+    ApplyBuff,
+}
+
+/// A modification of the game world requested by a plugin.
+///
+/// Plugins only get read access to the ECS while they are executed, so these
+/// are queued up and applied by the server afterwards.
+#[derive(Clone, Debug)]
+pub enum PluginAction {
+    SpawnNpc {
+        entity_config: String,
+        pos: Vec3<f32>,
+    },
+    Teleport {
+        entity: Uid,
+        pos: Vec3<f32>,
+    },
+    GiveItem {
+        entity: Uid,
+        item: String,
+        amount: u32,
+    },
+    ApplyBuff {
+        entity: Uid,
+        kind: BuffKind,
+        strength: f32,
+        duration: Option<f32>,
+    },
 }
 
 fn compute_hash(data: &[u8]) -> PluginHash {
@@ -78,7 +126,13 @@ pub struct Plugin {
 }
 
 impl Plugin {
-    pub fn from_path(path_buf: PathBuf) -> Result<Self, PluginError> {
+    /// `metered` limits the execution time of the plugin per tick, see
+    /// [`PluginMgr::tick`]
+    pub fn from_path(
+        path_buf: PathBuf,
+        storage: &PluginStorage,
+        metered: bool,
+    ) -> Result<Self, PluginError> {
         let mut reader = fs::File::open(path_buf.as_path()).map_err(PluginError::Io)?;
         let mut buf = Vec::new();
         reader.read_to_end(&mut buf).map_err(PluginError::Io)?;
@@ -113,7 +167,16 @@ impl Plugin {
             .iter()
             .map(|path| {
                 let wasm_data = files.remove(path).ok_or(PluginError::NoSuchModule)?;
-                PluginModule::new(data.name.to_owned(), &wasm_data).map_err(|e| {
+                let permissions = data.permissions.iter().copied().collect();
+                let storage = storage.scoped(&data.name);
+                PluginModule::new(
+                    data.name.to_owned(),
+                    permissions,
+                    storage,
+                    metered,
+                    &wasm_data,
+                )
+                .map_err(|e| {
                     PluginError::PluginModuleError(data.name.to_owned(), "<init>".to_owned(), e)
                 })
             })
@@ -162,6 +225,34 @@ impl Plugin {
         result
     }
 
+    pub fn game_event(
+        &mut self,
+        ecs: &EcsWorld,
+        event: &PluginGameEvent,
+    ) -> Result<(), PluginModuleError> {
+        self.modules
+            .iter_mut()
+            .try_for_each(|module| module.game_event(ecs, event))
+    }
+
+    pub fn tick(&mut self, ecs: &EcsWorld, dt: f32) -> Result<(), PluginModuleError> {
+        self.modules
+            .iter_mut()
+            .try_for_each(|module| module.tick(ecs, dt))
+    }
+
+    fn refuel(&mut self) { self.modules.iter_mut().for_each(PluginModule::refuel); }
+
+    /// Take the world modifications requested by this plugin
+    pub fn take_actions(&mut self) -> impl Iterator<Item = PluginAction> + '_ {
+        self.modules
+            .iter_mut()
+            .flat_map(|module| module.take_actions())
+    }
+
+    /// The name from the plugin's `plugin.toml`
+    pub fn name(&self) -> &str { &self.data.name }
+
     /// get the path to the plugin file
     pub fn path(&self) -> &Path { self.path.as_path() }
 
@@ -197,15 +288,18 @@ impl Plugin {
 #[derive(Default)]
 pub struct PluginMgr {
     plugins: Vec<Plugin>,
+    storage: PluginStorage,
+    /// Whether plugins get a fuel budget per tick, only done on the server
+    metered: bool,
 }
 
 impl PluginMgr {
-    pub fn from_asset_or_default() -> Self {
+    pub fn from_asset_or_default(mode: common::resources::GameMode) -> Self {
         let mut path = (*ASSETS_PATH).clone();
         path.push("plugins");
         info!("Searching {:?} for plugins...", path);
 
-        match Self::from_dir(&path) {
+        match Self::from_dir(&path, mode != common::resources::GameMode::Client) {
             Ok(plugin_mgr) => {
                 info!("{} plugin(s) loaded", plugin_mgr.plugins.len());
                 plugin_mgr
@@ -221,7 +315,8 @@ impl PluginMgr {
         }
     }
 
-    fn from_dir(path: &Path) -> Result<Self, PluginError> {
+    fn from_dir(path: &Path, metered: bool) -> Result<Self, PluginError> {
+        let storage = PluginStorage::default();
         let plugins = fs::read_dir(path)
             .map_err(|e| {
                 if e.kind() == std::io::ErrorKind::NotFound {
@@ -241,7 +336,7 @@ impl PluginMgr {
                         .unwrap_or(false)
                 {
                     info!("Loading plugin at {:?}", entry.path());
-                    Plugin::from_path(entry.path()).map(|plugin| {
+                    Plugin::from_path(entry.path(), &storage, metered).map(|plugin| {
                         if let Err(e) = common::assets::register_tar(entry.path()) {
                             error!("Plugin {:?} tar error {e:?}", entry.path());
                         }
@@ -265,12 +360,16 @@ impl PluginMgr {
             );
         }
 
-        Ok(Self { plugins })
+        Ok(Self {
+            plugins,
+            storage,
+            metered,
+        })
     }
 
     /// Add a plugin received from the server
     pub fn load_server_plugin(&mut self, path: PathBuf) -> Result<PluginHash, PluginError> {
-        Plugin::from_path(path.clone()).map(|plugin| {
+        Plugin::from_path(path.clone(), &self.storage, self.metered).map(|plugin| {
             if let Err(e) = common::assets::register_tar(path.clone()) {
                 error!("Plugin {:?} tar error {e:?}", path.as_path());
             }
@@ -289,6 +388,29 @@ impl PluginMgr {
         self.load_server_plugin(path)
     }
 
+    /// Add a plugin the server reloaded while the game is running and call
+    /// its `load` event, which the state already did for the plugins present
+    /// at startup. The plugin is dropped again if that fails.
+    pub fn cache_reloaded_server_plugin(
+        &mut self,
+        ecs: &EcsWorld,
+        base_dir: &Path,
+        data: Vec<u8>,
+        mode: common::resources::GameMode,
+    ) -> Result<PluginHash, PluginError> {
+        let hash = self.cache_server_plugin(base_dir, data)?;
+        let plugin = self.plugins.last_mut().expect("The plugin was just added");
+        if let Err(e) = plugin.load_event(ecs, mode) {
+            let name = plugin.data.name.clone();
+            self.unload(&hash);
+            return Err(PluginError::PluginModuleError(name, "load".to_owned(), e));
+        }
+        Ok(hash)
+    }
+
+    /// The key-value storage shared by all plugins
+    pub fn storage(&self) -> &PluginStorage { &self.storage }
+
     /// list all registered plugins
     pub fn plugin_list(&self) -> Vec<PluginHash> {
         self.plugins.iter().map(|plugin| plugin.hash).collect()
@@ -299,6 +421,56 @@ impl PluginMgr {
         self.plugins.iter().find(|plugin| &plugin.hash == hash)
     }
 
+    /// Re-read the plugin with the given name from its file and replace the
+    /// running instance by it, `load` is called again on the new instance.
+    ///
+    /// Everything the old instance registered (commands, timers, queued
+    /// actions) is dropped with it, while its storage is kept. If the new
+    /// version fails to load the old one stays active.
+    ///
+    /// Returns the hashes of the previous and the new version.
+    pub fn reload(
+        &mut self,
+        ecs: &EcsWorld,
+        name: &str,
+        mode: common::resources::GameMode,
+    ) -> Result<(PluginHash, PluginHash), PluginError> {
+        let plugin = self
+            .plugins
+            .iter_mut()
+            .find(|plugin| plugin.data.name == name)
+            .ok_or(PluginError::NoSuchPlugin)?;
+
+        let mut new_plugin = Plugin::from_path(plugin.path.clone(), &self.storage, self.metered)?;
+        new_plugin
+            .load_event(ecs, mode)
+            .map_err(|e| PluginError::PluginModuleError(name.to_owned(), "load".to_owned(), e))?;
+
+        let old_plugin = core::mem::replace(plugin, new_plugin);
+        common::assets::unregister_tar(old_plugin.path());
+        if let Err(e) = common::assets::register_tar(plugin.path.clone()) {
+            error!("Plugin {:?} tar error {e:?}", plugin.path());
+        }
+        info!(
+            "Reloaded plugin '{}' with {} module(s)",
+            name,
+            plugin.modules.len()
+        );
+
+        Ok((old_plugin.hash, plugin.hash))
+    }
+
+    /// Remove a plugin, e.g. because the server replaced it by a new version
+    pub fn unload(&mut self, hash: &PluginHash) -> Option<Plugin> {
+        let index = self
+            .plugins
+            .iter()
+            .position(|plugin| &plugin.hash == hash)?;
+        let plugin = self.plugins.remove(index);
+        common::assets::unregister_tar(plugin.path());
+        Some(plugin)
+    }
+
     pub fn load_event(
         &mut self,
         ecs: &EcsWorld,
@@ -332,6 +504,44 @@ impl PluginMgr {
         result
     }
 
+    /// Forward a game event to all plugins, errors are logged per plugin
+    pub fn game_event(&mut self, ecs: &EcsWorld, event: &PluginGameEvent) {
+        self.plugins.iter_mut().for_each(|plugin| {
+            if let Err(e) = plugin.game_event(ecs, event) {
+                error!(
+                    ?e,
+                    ?event,
+                    "Plugin '{}' failed to handle event",
+                    plugin.data.name
+                );
+            }
+        });
+    }
+
+    /// Advance the timers of all plugins and call their `tick` export, errors
+    /// are logged per plugin.
+    ///
+    /// This starts a new tick, so the fuel budget of every plugin is refilled
+    /// first. Game events, commands and joins until the next call use up the
+    /// rest of it.
+    pub fn tick(&mut self, ecs: &EcsWorld, dt: f32) {
+        self.plugins.iter_mut().for_each(|plugin| {
+            plugin.refuel();
+            if let Err(e) = plugin.tick(ecs, dt) {
+                error!(?e, "Plugin '{}' failed to tick", plugin.data.name);
+            }
+        });
+    }
+
+    /// Take the world modifications requested by all plugins since the last
+    /// call
+    pub fn take_actions(&mut self) -> Vec<PluginAction> {
+        self.plugins
+            .iter_mut()
+            .flat_map(|plugin| plugin.take_actions())
+            .collect()
+    }
+
     pub fn create_body(&mut self, name: &str) -> Option<module::Body> {
         let mut result = None;
         self.plugins.iter_mut().for_each(|plugin| {
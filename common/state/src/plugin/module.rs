@@ -7,11 +7,14 @@ use std::{
 };
 
 use super::{
-    CommandResults,
+    CommandResults, PluginAction, PluginPermission,
     errors::PluginModuleError,
     memory_manager::{EcsAccessManager, EcsWorld},
+    storage::{self, ScopedStorage},
 };
+use common::event::PluginGameEvent;
 use hashbrown::{HashMap, HashSet};
+use timer_queue::TimerQueue;
 use tokio::io::AsyncWrite;
 use wasmtime::{
     Config, Engine, Store,
@@ -32,13 +35,24 @@ pub(crate) mod types_mod {
 
 wasmtime::component::bindgen!({
     path: "../../plugin/wit/veloren.wit",
-    world: "plugin",
+    world: "full-plugin",
     with: {
         "veloren:plugin/types@0.0.1": types_mod::veloren::plugin::types,
         "veloren:plugin/information@0.0.1.entity": Entity,
     },
 });
 
+mod legacy_plugin {
+    wasmtime::component::bindgen!({
+        path: "../../plugin/wit/veloren.wit",
+        world: "plugin",
+        with: {
+            "veloren:plugin/types@0.0.1": super::types_mod::veloren::plugin::types,
+            "veloren:plugin/information@0.0.1.entity": super::Entity,
+        },
+    });
+}
+
 mod animation_plugin {
     wasmtime::component::bindgen!({
         path: "../../plugin/wit/veloren.wit",
@@ -49,6 +63,17 @@ mod animation_plugin {
     });
 }
 
+mod gameplay_plugin {
+    wasmtime::component::bindgen!({
+        path: "../../plugin/wit/veloren.wit",
+        world: "gameplay-plugin",
+        with: {
+            "veloren:plugin/types@0.0.1": super::types_mod::veloren::plugin::types,
+            "veloren:plugin/information@0.0.1.entity": super::Entity,
+        },
+    });
+}
+
 mod server_plugin {
     wasmtime::component::bindgen!({
         path: "../../plugin/wit/veloren.wit",
@@ -69,17 +94,61 @@ use exports::veloren::plugin::animation;
 pub use types_mod::veloren::plugin::types::{
     self, CharacterState, Dependency, Skeleton, Transform,
 };
-use veloren::plugin::{actions, information};
+use veloren::plugin::{actions, gameplay, information, storage as storage_iface};
 
 type StoreType = wasmtime::Store<WasiHostCtx>;
 
+/// Fuel available to a server plugin module per server tick, shared by all
+/// calls into it during that tick. Fuel roughly corresponds to executed WASM
+/// instructions, so a plugin stuck in a loop traps instead of stalling the
+/// server tick.
+const TICK_FUEL_BUDGET: u64 = 10_000_000;
+/// convert seconds to milliseconds to use in TimerQueue
+const SECONDS_TO_MILLISECONDS: f64 = 1000.0;
+
 /// This enum abstracts over the different types of plugins we defined
 enum PluginWrapper {
-    Full(Plugin),
+    Full(FullPlugin),
+    /// Plugins built against the `plugin` world, which has no game events
+    Legacy(legacy_plugin::Plugin),
+    Gameplay(gameplay_plugin::GameplayPlugin),
     Animation(animation_plugin::AnimationPlugin),
     Server(server_plugin::ServerPlugin),
 }
 
+/// Calls the matching function of a `game-events` export, the generated
+/// interfaces differ in type between the worlds.
+macro_rules! call_game_event {
+    ($iface:expr, $store:expr, $event:expr) => {
+        match $event {
+            PluginGameEvent::EntityDeath { entity, killer } => $iface.call_entity_death(
+                $store,
+                entity.0.into(),
+                killer.map(|killer| killer.0.into()),
+            ),
+            PluginGameEvent::EntityDamage {
+                entity,
+                attacker,
+                amount,
+            } => $iface.call_entity_damage(
+                $store,
+                entity.0.into(),
+                attacker.map(|attacker| attacker.0.into()),
+                *amount,
+            ),
+            PluginGameEvent::ItemPickup {
+                entity,
+                item,
+                amount,
+            } => $iface.call_item_pickup($store, entity.0.into(), item, *amount),
+            PluginGameEvent::ChunkLoad { key } => $iface.call_chunk_load($store, key.x, key.y),
+            PluginGameEvent::CharacterStateChange { entity, state } => {
+                $iface.call_character_state_change($store, entity.0.into(), state)
+            },
+        }
+    };
+}
+
 impl PluginWrapper {
     fn load_event<S: wasmtime::AsContextMut>(
         &self,
@@ -96,6 +165,8 @@ impl PluginWrapper {
         };
         match self {
             PluginWrapper::Full(pl) => pl.veloren_plugin_events().call_load(store, mode),
+            PluginWrapper::Legacy(pl) => pl.veloren_plugin_events().call_load(store, mode),
+            PluginWrapper::Gameplay(pl) => pl.veloren_plugin_events().call_load(store, mode),
             PluginWrapper::Animation(pl) => pl.veloren_plugin_events().call_load(store, mode),
             PluginWrapper::Server(pl) => pl.veloren_plugin_events().call_load(store, mode),
         }
@@ -115,6 +186,12 @@ impl PluginWrapper {
             PluginWrapper::Full(pl) => pl
                 .veloren_plugin_server_events()
                 .call_command(store, name, args, player),
+            PluginWrapper::Legacy(pl) => pl
+                .veloren_plugin_server_events()
+                .call_command(store, name, args, player),
+            PluginWrapper::Gameplay(pl) => pl
+                .veloren_plugin_server_events()
+                .call_command(store, name, args, player),
             PluginWrapper::Animation(_) => Ok(Err("not implemented".into())),
             PluginWrapper::Server(pl) => pl
                 .veloren_plugin_server_events()
@@ -132,6 +209,12 @@ impl PluginWrapper {
             PluginWrapper::Full(pl) => pl
                 .veloren_plugin_server_events()
                 .call_join(store, name, uuid),
+            PluginWrapper::Legacy(pl) => pl
+                .veloren_plugin_server_events()
+                .call_join(store, name, uuid),
+            PluginWrapper::Gameplay(pl) => pl
+                .veloren_plugin_server_events()
+                .call_join(store, name, uuid),
             PluginWrapper::Animation(_) => Ok(types::JoinResult::None),
             PluginWrapper::Server(pl) => pl
                 .veloren_plugin_server_events()
@@ -139,17 +222,55 @@ impl PluginWrapper {
         }
     }
 
+    fn game_event(&self, store: &mut StoreType, event: &PluginGameEvent) -> wasmtime::Result<()> {
+        match self {
+            PluginWrapper::Full(pl) => {
+                call_game_event!(pl.veloren_plugin_game_events(), store, event)
+            },
+            PluginWrapper::Gameplay(pl) => {
+                call_game_event!(pl.veloren_plugin_game_events(), store, event)
+            },
+            PluginWrapper::Legacy(_) | PluginWrapper::Animation(_) | PluginWrapper::Server(_) => {
+                Ok(())
+            },
+        }
+    }
+
+    fn tick(&self, store: &mut StoreType, dt: f32) -> wasmtime::Result<()> {
+        match self {
+            PluginWrapper::Full(pl) => pl.veloren_plugin_game_events().call_tick(store, dt),
+            PluginWrapper::Gameplay(pl) => pl.veloren_plugin_game_events().call_tick(store, dt),
+            PluginWrapper::Legacy(_) | PluginWrapper::Animation(_) | PluginWrapper::Server(_) => {
+                Ok(())
+            },
+        }
+    }
+
+    fn timer(&self, store: &mut StoreType, id: u64) -> wasmtime::Result<()> {
+        match self {
+            PluginWrapper::Full(pl) => pl.veloren_plugin_game_events().call_timer(store, id),
+            PluginWrapper::Gameplay(pl) => pl.veloren_plugin_game_events().call_timer(store, id),
+            PluginWrapper::Legacy(_) | PluginWrapper::Animation(_) | PluginWrapper::Server(_) => {
+                Ok(())
+            },
+        }
+    }
+
     fn create_body(&self, store: &mut StoreType, bodytype: i32) -> Option<animation::Body> {
         match self {
             PluginWrapper::Full(pl) => {
                 let body_iface = pl.veloren_plugin_animation().body();
                 body_iface.call_constructor(store, bodytype).ok()
             },
+            PluginWrapper::Legacy(pl) => {
+                let body_iface = pl.veloren_plugin_animation().body();
+                body_iface.call_constructor(store, bodytype).ok()
+            },
             PluginWrapper::Animation(pl) => {
                 let body_iface = pl.veloren_plugin_animation().body();
                 body_iface.call_constructor(store, bodytype).ok()
             },
-            PluginWrapper::Server(_) => None,
+            PluginWrapper::Gameplay(_) | PluginWrapper::Server(_) => None,
         }
     }
 
@@ -165,11 +286,15 @@ impl PluginWrapper {
                 let body_iface = pl.veloren_plugin_animation().body();
                 body_iface.call_update_skeleton(store, body, dep, time).ok()
             },
+            PluginWrapper::Legacy(pl) => {
+                let body_iface = pl.veloren_plugin_animation().body();
+                body_iface.call_update_skeleton(store, body, dep, time).ok()
+            },
             PluginWrapper::Animation(pl) => {
                 let body_iface = pl.veloren_plugin_animation().body();
                 body_iface.call_update_skeleton(store, body, dep, time).ok()
             },
-            PluginWrapper::Server(_) => None,
+            PluginWrapper::Gameplay(_) | PluginWrapper::Server(_) => None,
         }
     }
 }
@@ -180,6 +305,12 @@ pub struct PluginModule {
     plugin: PluginWrapper,
     store: Mutex<wasmtime::Store<WasiHostCtx>>,
     name: String,
+    /// Whether calls are limited to [`TICK_FUEL_BUDGET`], client plugins only
+    /// drive animations and aren't metered
+    metered: bool,
+    /// Set once a call trapped (e.g. by exceeding the fuel budget), the
+    /// component instance can't be entered again after a trap.
+    trapped: bool,
 }
 
 struct WasiHostCtx {
@@ -188,6 +319,35 @@ struct WasiHostCtx {
     ecs: Arc<EcsAccessManager>,
     registered_commands: HashSet<String>,
     registered_bodies: HashMap<String, types::BodyIndex>,
+    permissions: HashSet<PluginPermission>,
+    pending_actions: Vec<PluginAction>,
+    /// Game time seen by this plugin in seconds, advanced by each tick
+    elapsed: f64,
+    timers: TimerQueue<u64>,
+    storage: ScopedStorage,
+}
+
+impl WasiHostCtx {
+    fn check_permission(&self, permission: PluginPermission) -> Result<(), types::HostError> {
+        if self.permissions.contains(&permission) {
+            Ok(())
+        } else {
+            Err(types::HostError::PermissionDenied)
+        }
+    }
+
+    /// Check that the entity currently exists in the ECS
+    fn existing_uid(&self, uid: types::Uid) -> Result<common::uid::Uid, types::HostError> {
+        let uid = common::uid::Uid(NonZeroU64::new(uid).ok_or(types::HostError::RuntimeError)?);
+        self.ecs.with(|world| {
+            let world = world.ok_or(types::HostError::EcsPointerNotAvailable)?;
+            world
+                .id_maps
+                .uid_entity(uid)
+                .map(|_| uid)
+                .ok_or(types::HostError::EcsEntityNotFound)
+        })
+    }
 }
 
 impl WasiView for WasiHostCtx {
@@ -218,6 +378,104 @@ impl actions::Host for WasiHostCtx {
     }
 }
 
+impl gameplay::Host for WasiHostCtx {
+    fn spawn_npc(
+        &mut self,
+        entity_config: String,
+        position: types::Vec3,
+    ) -> Result<(), types::HostError> {
+        self.check_permission(PluginPermission::SpawnNpc)?;
+        self.pending_actions.push(PluginAction::SpawnNpc {
+            entity_config,
+            pos: position.into(),
+        });
+        Ok(())
+    }
+
+    fn teleport(
+        &mut self,
+        entity: types::Uid,
+        position: types::Vec3,
+    ) -> Result<(), types::HostError> {
+        self.check_permission(PluginPermission::Teleport)?;
+        let entity = self.existing_uid(entity)?;
+        self.pending_actions.push(PluginAction::Teleport {
+            entity,
+            pos: position.into(),
+        });
+        Ok(())
+    }
+
+    fn give_item(
+        &mut self,
+        entity: types::Uid,
+        item: String,
+        amount: u32,
+    ) -> Result<(), types::HostError> {
+        self.check_permission(PluginPermission::GiveItem)?;
+        if amount == 0 {
+            return Err(types::HostError::InvalidArgument);
+        }
+        let entity = self.existing_uid(entity)?;
+        self.pending_actions.push(PluginAction::GiveItem {
+            entity,
+            item,
+            amount,
+        });
+        Ok(())
+    }
+
+    fn apply_buff(
+        &mut self,
+        entity: types::Uid,
+        buff: String,
+        strength: f32,
+        duration: Option<f32>,
+    ) -> Result<(), types::HostError> {
+        self.check_permission(PluginPermission::ApplyBuff)?;
+        let kind = common::cmd::BUFF_PARSER
+            .get(&buff)
+            .copied()
+            // complex buffs need additional data which isn't exposed to plugins
+            .filter(|kind| kind.is_simple())
+            .ok_or(types::HostError::InvalidArgument)?;
+        if !strength.is_finite() || duration.is_some_and(|d| !d.is_finite() || d <= 0.0) {
+            return Err(types::HostError::InvalidArgument);
+        }
+        let entity = self.existing_uid(entity)?;
+        self.pending_actions.push(PluginAction::ApplyBuff {
+            entity,
+            kind,
+            strength,
+            duration,
+        });
+        Ok(())
+    }
+
+    fn schedule_timer(&mut self, delay: f32, id: u64) {
+        let expiry = self.elapsed + f64::from(delay.max(0.0));
+        self.timers
+            .insert((expiry * SECONDS_TO_MILLISECONDS) as u64, id);
+    }
+}
+
+impl storage_iface::Host for WasiHostCtx {
+    fn get(&mut self, key: String) -> Option<Vec<u8>> { self.storage.get(&key) }
+
+    fn set(&mut self, key: String, value: Vec<u8>) -> Result<(), types::HostError> {
+        if key.len() > storage::MAX_KEY_LEN || value.len() > storage::MAX_VALUE_LEN {
+            return Err(types::HostError::InvalidArgument);
+        }
+        self.storage
+            .set(key, value)
+            .map_err(|storage::StorageFull| types::HostError::StorageFull)
+    }
+
+    fn delete(&mut self, key: String) { self.storage.delete(&key) }
+
+    fn list_keys(&mut self, prefix: String) -> Vec<String> { self.storage.list_keys(&prefix) }
+}
+
 impl information::HostEntity for WasiHostCtx {
     fn find_entity(
         &mut self,
@@ -362,12 +620,19 @@ impl StdoutStream for LogStream {
 
 impl PluginModule {
     /// This function takes bytes from a WASM File and compile them
-    pub fn new(name: String, wasm_data: &[u8]) -> Result<Self, PluginModuleError> {
+    pub fn new(
+        name: String,
+        permissions: HashSet<PluginPermission>,
+        storage: ScopedStorage,
+        metered: bool,
+        wasm_data: &[u8],
+    ) -> Result<Self, PluginModuleError> {
         let ecs = Arc::new(EcsAccessManager::default());
 
         // configure the wasm runtime
         let mut config = Config::new();
         config.wasm_component_model(true);
+        config.consume_fuel(metered);
 
         let engine = Engine::new(&config).map_err(PluginModuleError::Wasmtime)?;
         // create a WASI environment (std implementing system calls)
@@ -381,9 +646,20 @@ impl PluginModule {
             ecs: Arc::clone(&ecs),
             registered_commands: HashSet::new(),
             registered_bodies: HashMap::new(),
+            permissions,
+            pending_actions: Vec::new(),
+            elapsed: 0.0,
+            timers: TimerQueue::default(),
+            storage,
         };
         // the store contains all data of a wasm instance
         let mut store = Store::new(&engine, host_ctx);
+        if metered {
+            // Instantiating and the load event use the budget of the first tick
+            store
+                .set_fuel(TICK_FUEL_BUDGET)
+                .map_err(PluginModuleError::Wasmtime)?;
+        }
 
         // load wasm from binary
         let module =
@@ -392,18 +668,24 @@ impl PluginModule {
         // register WASI and Veloren methods with the runtime
         let mut linker = Linker::new(&engine);
         wasmtime_wasi::p2::add_to_linker_sync(&mut linker).map_err(PluginModuleError::Wasmtime)?;
-        Plugin::add_to_linker::<_, HasSelf<_>>(&mut linker, |x| x)
+        FullPlugin::add_to_linker::<_, HasSelf<_>>(&mut linker, |x| x)
             .map_err(PluginModuleError::Wasmtime)?;
 
         let instance_fut = linker.instantiate(&mut store, &module);
         let instance = (instance_fut).map_err(PluginModuleError::Wasmtime)?;
 
-        let plugin = match Plugin::new(&mut store, &instance) {
+        let plugin = match FullPlugin::new(&mut store, &instance) {
             Ok(pl) => Ok(PluginWrapper::Full(pl)),
-            Err(_) => match animation_plugin::AnimationPlugin::new(&mut store, &instance) {
-                Ok(pl) => Ok(PluginWrapper::Animation(pl)),
-                Err(_) => server_plugin::ServerPlugin::new(&mut store, &instance)
-                    .map(PluginWrapper::Server),
+            Err(_) => match legacy_plugin::Plugin::new(&mut store, &instance) {
+                Ok(pl) => Ok(PluginWrapper::Legacy(pl)),
+                Err(_) => match gameplay_plugin::GameplayPlugin::new(&mut store, &instance) {
+                    Ok(pl) => Ok(PluginWrapper::Gameplay(pl)),
+                    Err(_) => match animation_plugin::AnimationPlugin::new(&mut store, &instance) {
+                        Ok(pl) => Ok(PluginWrapper::Animation(pl)),
+                        Err(_) => server_plugin::ServerPlugin::new(&mut store, &instance)
+                            .map(PluginWrapper::Server),
+                    },
+                },
             },
         }
         .map_err(PluginModuleError::Wasmtime)?;
@@ -413,22 +695,34 @@ impl PluginModule {
             ecs,
             store: store.into(),
             name,
+            metered,
+            trapped: false,
         })
     }
 
     pub fn name(&self) -> &str { &self.name }
 
+    /// Refill the fuel budget, called once per server tick
+    pub fn refuel(&mut self) {
+        if self.metered {
+            // This only fails if fuel consumption isn't enabled for the engine
+            let _ = self.store.get_mut().unwrap().set_fuel(TICK_FUEL_BUDGET);
+        }
+    }
+
     // Implementation of the commands called from veloren and provided in plugins
     pub fn load_event(
         &mut self,
         ecs: &EcsWorld,
         mode: common::resources::GameMode,
     ) -> Result<(), PluginModuleError> {
-        self.ecs
-            .execute_with(ecs, || {
-                self.plugin.load_event(self.store.get_mut().unwrap(), mode)
-            })
-            .map_err(PluginModuleError::Wasmtime)
+        if self.trapped {
+            return Ok(());
+        }
+        let result = self.ecs.execute_with(ecs, || {
+            self.plugin.load_event(self.store.get_mut().unwrap(), mode)
+        });
+        self.check_trap(result)
     }
 
     pub fn command_event(
@@ -438,27 +732,29 @@ impl PluginModule {
         args: &[String],
         player: common::uid::Uid,
     ) -> Result<Vec<String>, CommandResults> {
-        if !self
-            .store
-            .get_mut()
-            .unwrap()
-            .data()
-            .registered_commands
-            .contains(name)
+        // The commands of a disabled plugin are gone with it
+        if self.trapped
+            || !self
+                .store
+                .get_mut()
+                .unwrap()
+                .data()
+                .registered_commands
+                .contains(name)
         {
             return Err(CommandResults::UnknownCommand);
         }
-        self.ecs.execute_with(ecs, || {
-            match self.plugin.command_event(
-                self.store.get_mut().unwrap(),
-                name,
-                args,
-                player.0.into(),
-            ) {
-                Err(err) => Err(CommandResults::HostError(err)),
-                Ok(result) => result.map_err(CommandResults::PluginError),
-            }
-        })
+        let result = self.ecs.execute_with(ecs, || {
+            self.plugin
+                .command_event(self.store.get_mut().unwrap(), name, args, player.0.into())
+        });
+        match result {
+            Err(err) => {
+                self.note_trap(&err);
+                Err(CommandResults::HostError(err))
+            },
+            Ok(result) => result.map_err(CommandResults::PluginError),
+        }
     }
 
     pub fn player_join_event(
@@ -467,24 +763,92 @@ impl PluginModule {
         name: &str,
         uuid: common::uuid::Uuid,
     ) -> types::JoinResult {
-        self.ecs.execute_with(ecs, || {
-            match self.plugin.player_join_event(
-                self.store.get_mut().unwrap(),
-                name,
-                uuid.as_u64_pair(),
-            ) {
-                Ok(value) => {
-                    tracing::info!("JoinResult {value:?}");
-                    value
-                },
-                Err(err) => {
-                    tracing::error!("join_event: {err:?}");
-                    types::JoinResult::None
-                },
-            }
+        if self.trapped {
+            return types::JoinResult::None;
+        }
+        let result = self.ecs.execute_with(ecs, || {
+            self.plugin
+                .player_join_event(self.store.get_mut().unwrap(), name, uuid.as_u64_pair())
+        });
+        match result {
+            Ok(value) => {
+                tracing::info!("JoinResult {value:?}");
+                value
+            },
+            Err(err) => {
+                tracing::error!("join_event: {err:?}");
+                self.note_trap(&err);
+                types::JoinResult::None
+            },
+        }
+    }
+
+    pub fn game_event(
+        &mut self,
+        ecs: &EcsWorld,
+        event: &PluginGameEvent,
+    ) -> Result<(), PluginModuleError> {
+        if self.trapped {
+            return Ok(());
+        }
+        let result = self.ecs.execute_with(ecs, || {
+            self.plugin.game_event(self.store.get_mut().unwrap(), event)
+        });
+        self.check_trap(result)
+    }
+
+    /// Call the `tick` export followed by all expired timers
+    pub fn tick(&mut self, ecs: &EcsWorld, dt: f32) -> Result<(), PluginModuleError> {
+        if self.trapped {
+            return Ok(());
+        }
+        let store = self.store.get_mut().unwrap();
+        let host = store.data_mut();
+        host.elapsed += f64::from(dt);
+        let now = (host.elapsed * SECONDS_TO_MILLISECONDS) as u64;
+        let mut expired = Vec::new();
+        while let Some(id) = host.timers.poll(now) {
+            expired.push(id);
+        }
+        let result = self.ecs.execute_with(ecs, || {
+            self.plugin.tick(store, dt)?;
+            expired
+                .into_iter()
+                .try_for_each(|id| self.plugin.timer(store, id))
+        });
+        self.check_trap(result)
+    }
+
+    fn check_trap(&mut self, result: wasmtime::Result<()>) -> Result<(), PluginModuleError> {
+        result.map_err(|err| {
+            self.note_trap(&err);
+            PluginModuleError::Wasmtime(err)
         })
     }
 
+    /// Disable the plugin if the error is a trap
+    fn note_trap(&mut self, err: &wasmtime::Error) {
+        match err.downcast_ref::<wasmtime::Trap>() {
+            Some(wasmtime::Trap::OutOfFuel) => {
+                tracing::error!(
+                    "Plugin {} exceeded its execution budget and was disabled",
+                    self.name
+                );
+                self.trapped = true;
+            },
+            Some(trap) => {
+                tracing::error!(?trap, "Plugin {} trapped and was disabled", self.name);
+                self.trapped = true;
+            },
+            None => {},
+        }
+    }
+
+    /// Take the world modifications queued by this module
+    pub fn take_actions(&mut self) -> Vec<PluginAction> {
+        std::mem::take(&mut self.store.get_mut().unwrap().data_mut().pending_actions)
+    }
+
     pub fn create_body(&mut self, bodytype: &str) -> Option<animation::Body> {
         let store = self.store.get_mut().unwrap();
         let bodytype = store.data().registered_bodies.get(bodytype).copied();
@@ -501,3 +865,328 @@ impl PluginModule {
             .update_skeleton(self.store.get_mut().unwrap(), *body, *dep, time)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plugin::{Plugin, PluginData, PluginMgr, storage::PluginStorage};
+    use common::{
+        comp::{Health, Player},
+        uid::{IdMaps, Uid},
+    };
+    use specs::WorldExt;
+    use vek::{Vec2, Vec3};
+
+    /// A `gameplay-plugin` component doing nothing but running `TICK` on each
+    /// tick
+    const GAMEPLAY_PLUGIN: &str = r#"
+(component
+  (core module $m
+    (memory (export "memory") 1)
+    (func (export "cabi_realloc") (param i32 i32 i32 i32) (result i32)
+      i32.const 256)
+    (func (export "load") (param i32))
+    (func (export "join") (param i32 i32 i64 i64) (result i32)
+      i32.const 16)
+    (func (export "command") (param i32 i32 i32 i32 i64) (result i32)
+      i32.const 32)
+    (func (export "entity-death") (param i64 i32 i64))
+    (func (export "entity-damage") (param i64 i32 i64 f32))
+    (func (export "item-pickup") (param i64 i32 i32 i32))
+    (func (export "chunk-load") (param i32 i32))
+    (func (export "character-state-change") (param i64 i32 i32))
+    (func (export "tick") (param f32) (local $n i32)
+      TICK)
+    (func (export "timer") (param i64))
+    ;; join-result::none, the command result at 32 is an empty list
+    (data (i32.const 16) "\01"))
+  (core instance $i (instantiate $m))
+
+  (type $game-mode (enum "server" "client" "single-player"))
+  (func $load (param "mode" $game-mode) (canon lift (core func $i "load")))
+  (component $events
+    (type $def (enum "server" "client" "single-player"))
+    (import "import-type-game-mode" (type $imported (eq $def)))
+    (import "import-func-load" (func $load (param "mode" $imported)))
+    (export $game-mode "game-mode" (type $imported))
+    (export "load" (func $load) (func (param "mode" $game-mode))))
+  (instance $events-instance (instantiate $events
+    (with "import-type-game-mode" (type $game-mode))
+    (with "import-func-load" (func $load))))
+  (export "veloren:plugin/events@0.0.1" (instance $events-instance))
+
+  (type $join-result (variant (case "kick" string) (case "none")))
+  (func $join
+    (param "player-name" string) (param "player-id" (tuple u64 u64)) (result $join-result)
+    (canon lift (core func $i "join")
+      (memory $i "memory") (realloc (func $i "cabi_realloc"))))
+  (func $command
+    (param "command" string) (param "command-args" (list string)) (param "player" u64)
+    (result (result (list string) (error string)))
+    (canon lift (core func $i "command")
+      (memory $i "memory") (realloc (func $i "cabi_realloc"))))
+  (component $server-events
+    (type $def (variant (case "kick" string) (case "none")))
+    (import "import-type-join-result" (type $imported (eq $def)))
+    (import "import-func-join" (func $join
+      (param "player-name" string) (param "player-id" (tuple u64 u64)) (result $imported)))
+    (import "import-func-command" (func $command
+      (param "command" string) (param "command-args" (list string)) (param "player" u64)
+      (result (result (list string) (error string)))))
+    (export $join-result "join-result" (type $imported))
+    (export "join" (func $join) (func
+      (param "player-name" string) (param "player-id" (tuple u64 u64)) (result $join-result)))
+    (export "command" (func $command)))
+  (instance $server-events-instance (instantiate $server-events
+    (with "import-type-join-result" (type $join-result))
+    (with "import-func-join" (func $join))
+    (with "import-func-command" (func $command))))
+  (export "veloren:plugin/server-events@0.0.1" (instance $server-events-instance))
+
+  (func $entity-death (param "entity" u64) (param "killer" (option u64))
+    (canon lift (core func $i "entity-death")))
+  (func $entity-damage
+    (param "entity" u64) (param "attacker" (option u64)) (param "amount" f32)
+    (canon lift (core func $i "entity-damage")))
+  (func $item-pickup (param "entity" u64) (param "item" string) (param "amount" u32)
+    (canon lift (core func $i "item-pickup")
+      (memory $i "memory") (realloc (func $i "cabi_realloc"))))
+  (func $chunk-load (param "x" s32) (param "y" s32) (canon lift (core func $i "chunk-load")))
+  (func $character-state-change (param "entity" u64) (param "state" string)
+    (canon lift (core func $i "character-state-change")
+      (memory $i "memory") (realloc (func $i "cabi_realloc"))))
+  (func $tick (param "dt" f32) (canon lift (core func $i "tick")))
+  (func $timer (param "id" u64) (canon lift (core func $i "timer")))
+  (instance $game-events
+    (export "entity-death" (func $entity-death))
+    (export "entity-damage" (func $entity-damage))
+    (export "item-pickup" (func $item-pickup))
+    (export "chunk-load" (func $chunk-load))
+    (export "character-state-change" (func $character-state-change))
+    (export "tick" (func $tick))
+    (export "timer" (func $timer)))
+  (export "veloren:plugin/game-events@0.0.1" (instance $game-events)))
+"#;
+
+    /// Never returns
+    const LOOPING_TICK: &str = "(loop $l (br $l))";
+    /// Uses up more than half of the budget, at 6 fuel per iteration
+    const BOUNDED_TICK: &str = "
+      (local.set $n (i32.const 1000000))
+      (loop $l
+        (local.set $n (i32.sub (local.get $n) (i32.const 1)))
+        (br_if $l (local.get $n)))";
+
+    fn gameplay_module(name: &str, storage: &PluginStorage, tick: &str) -> PluginModule {
+        gameplay_module_with(name, storage, tick, HashSet::new())
+    }
+
+    fn gameplay_module_with(
+        name: &str,
+        storage: &PluginStorage,
+        tick: &str,
+        permissions: HashSet<PluginPermission>,
+    ) -> PluginModule {
+        let wasm = wat::parse_str(GAMEPLAY_PLUGIN.replace("TICK", tick)).unwrap();
+        PluginModule::new(
+            name.to_owned(),
+            permissions,
+            storage.scoped(name),
+            true,
+            &wasm,
+        )
+        .unwrap()
+    }
+
+    fn with_ecs(f: impl FnOnce(&EcsWorld)) {
+        let mut world = specs::World::new();
+        world.register::<Health>();
+        world.register::<Uid>();
+        world.register::<Player>();
+        world.insert(IdMaps::new());
+        let ecs = EcsWorld {
+            entities: &world.entities(),
+            health: world.read_component().into(),
+            uid: world.read_component().into(),
+            id_maps: &world.read_resource::<IdMaps>().into(),
+            player: world.read_component().into(),
+        };
+        f(&ecs)
+    }
+
+    fn remaining_fuel(module: &mut PluginModule) -> u64 {
+        module.store.get_mut().unwrap().get_fuel().unwrap()
+    }
+
+    fn host(module: &mut PluginModule) -> &mut WasiHostCtx {
+        module.store.get_mut().unwrap().data_mut()
+    }
+
+    #[test]
+    fn looping_plugin_is_disabled() {
+        let mut module = gameplay_module("looping", &PluginStorage::default(), LOOPING_TICK);
+        with_ecs(|ecs| {
+            let PluginModuleError::Wasmtime(err) = module.tick(ecs, 0.1).unwrap_err();
+            assert_eq!(
+                err.downcast_ref::<wasmtime::Trap>(),
+                Some(&wasmtime::Trap::OutOfFuel)
+            );
+            assert!(module.trapped);
+
+            // the plugin isn't entered again, so its new budget stays untouched
+            module.refuel();
+            module.tick(ecs, 0.1).unwrap();
+            module
+                .game_event(ecs, &PluginGameEvent::ChunkLoad { key: Vec2::zero() })
+                .unwrap();
+            module
+                .load_event(ecs, common::resources::GameMode::Server)
+                .unwrap();
+            assert_eq!(remaining_fuel(&mut module), TICK_FUEL_BUDGET);
+        });
+    }
+
+    #[test]
+    fn budget_is_refilled() {
+        let mut module = gameplay_module("bounded", &PluginStorage::default(), BOUNDED_TICK);
+        with_ecs(|ecs| {
+            for _ in 0..4 {
+                module.refuel();
+                assert_eq!(remaining_fuel(&mut module), TICK_FUEL_BUDGET);
+                module.tick(ecs, 0.1).unwrap();
+                assert!(remaining_fuel(&mut module) < TICK_FUEL_BUDGET / 2);
+            }
+            assert!(!module.trapped);
+            // the budget is shared by all calls during a tick
+            assert!(module.tick(ecs, 0.1).is_err());
+            assert!(module.trapped);
+        });
+    }
+
+    #[test]
+    fn plugin_mgr_tick_only_disables_looping_plugin() {
+        let storage = PluginStorage::default();
+        let plugin = |name: &str, tick| Plugin {
+            data: PluginData {
+                name: name.to_owned(),
+                modules: Default::default(),
+                dependencies: Default::default(),
+                permissions: Default::default(),
+            },
+            modules: vec![gameplay_module(name, &storage, tick)],
+            hash: Default::default(),
+            path: Default::default(),
+            data_buf: Vec::new(),
+        };
+        let mut mgr = PluginMgr {
+            plugins: vec![
+                plugin("looping", LOOPING_TICK),
+                plugin("bounded", BOUNDED_TICK),
+            ],
+            storage: storage.clone(),
+            metered: true,
+        };
+        with_ecs(|ecs| {
+            for _ in 0..4 {
+                mgr.tick(ecs, 0.1);
+            }
+        });
+        assert!(mgr.plugins[0].modules[0].trapped);
+        assert!(!mgr.plugins[1].modules[0].trapped);
+    }
+
+    #[test]
+    fn actions_require_permission() {
+        let mut module = gameplay_module("unprivileged", &PluginStorage::default(), "");
+        let host = host(&mut module);
+        let pos = (1.0, 2.0, 3.0);
+        assert!(matches!(
+            gameplay::Host::spawn_npc(host, "common.entity.wild.peaceful.rat".to_owned(), pos),
+            Err(types::HostError::PermissionDenied)
+        ));
+        // the permission is checked before the entity is looked up
+        assert!(matches!(
+            gameplay::Host::teleport(host, 1, pos),
+            Err(types::HostError::PermissionDenied)
+        ));
+        assert!(matches!(
+            gameplay::Host::give_item(host, 1, "common.items.food.apple".to_owned(), 1),
+            Err(types::HostError::PermissionDenied)
+        ));
+        assert!(matches!(
+            gameplay::Host::apply_buff(host, 1, "regeneration".to_owned(), 1.0, None),
+            Err(types::HostError::PermissionDenied)
+        ));
+        assert!(module.take_actions().is_empty());
+    }
+
+    #[test]
+    fn permitted_action_is_queued() {
+        let mut module = gameplay_module_with(
+            "spawner",
+            &PluginStorage::default(),
+            "",
+            [PluginPermission::SpawnNpc].into_iter().collect(),
+        );
+        gameplay::Host::spawn_npc(
+            host(&mut module),
+            "common.entity.wild.peaceful.rat".to_owned(),
+            (1.0, 2.0, 3.0),
+        )
+        .unwrap();
+        // other permissions aren't implied
+        assert!(matches!(
+            gameplay::Host::teleport(host(&mut module), 1, (0.0, 0.0, 0.0)),
+            Err(types::HostError::PermissionDenied)
+        ));
+
+        let actions = module.take_actions();
+        assert!(matches!(
+            actions.as_slice(),
+            [PluginAction::SpawnNpc { entity_config, pos }]
+                if entity_config == "common.entity.wild.peaceful.rat"
+                    && *pos == Vec3::new(1.0, 2.0, 3.0)
+        ));
+        // the queue is drained
+        assert!(module.take_actions().is_empty());
+    }
+
+    #[test]
+    fn plugin_mgr_collects_permitted_actions() {
+        let storage = PluginStorage::default();
+        let plugin = |name: &str, permissions: &[PluginPermission]| Plugin {
+            data: PluginData {
+                name: name.to_owned(),
+                modules: Default::default(),
+                dependencies: Default::default(),
+                permissions: permissions.iter().copied().collect(),
+            },
+            modules: vec![gameplay_module_with(
+                name,
+                &storage,
+                "",
+                permissions.iter().copied().collect(),
+            )],
+            hash: Default::default(),
+            path: Default::default(),
+            data_buf: Vec::new(),
+        };
+        let mut mgr = PluginMgr {
+            plugins: vec![
+                plugin("unprivileged", &[]),
+                plugin("spawner", &[PluginPermission::SpawnNpc]),
+            ],
+            storage: storage.clone(),
+            metered: true,
+        };
+        for plugin in &mut mgr.plugins {
+            let _ = gameplay::Host::spawn_npc(
+                host(&mut plugin.modules[0]),
+                "common.entity.wild.peaceful.rat".to_owned(),
+                (0.0, 0.0, 0.0),
+            );
+        }
+        assert_eq!(mgr.take_actions().len(), 1);
+        assert!(mgr.take_actions().is_empty());
+    }
+}
@@ -0,0 +1,230 @@
+//! Persistent key-value storage available to plugins through the `storage`
+//! interface.
+//!
+//! Every plugin gets its own namespace (the plugin name), so plugins can't
+//! read or overwrite each other's data. The entries live in memory, the
+//! server loads them from its database on startup and regularly writes back
+//! the changes collected via [`PluginStorage::take_changes`]. On the client
+//! nothing is persisted.
+
+use hashbrown::HashMap;
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+};
+
+/// Longest key (in bytes) a plugin may use
+pub const MAX_KEY_LEN: usize = 256;
+/// Largest value (in bytes) a plugin may store under a single key
+pub const MAX_VALUE_LEN: usize = 64 * 1024;
+/// Most bytes (keys and values) a single plugin may store in total
+pub const MAX_PLUGIN_BYTES: usize = 16 * 1024 * 1024;
+
+/// The write would exceed [`MAX_PLUGIN_BYTES`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StorageFull;
+
+/// A modification of a single storage entry which wasn't persisted yet,
+/// `value` is `None` for deleted entries.
+#[derive(Clone, Debug)]
+pub struct PluginStorageChange {
+    pub plugin: String,
+    pub key: String,
+    pub value: Option<Vec<u8>>,
+}
+
+#[derive(Default)]
+struct PluginEntries {
+    entries: BTreeMap<String, Vec<u8>>,
+    /// Sum of the lengths of all keys and values
+    bytes: usize,
+}
+
+impl PluginEntries {
+    fn insert(&mut self, key: String, value: Vec<u8>) {
+        let key_len = key.len();
+        self.bytes += key_len + value.len();
+        if let Some(old) = self.entries.insert(key, value) {
+            self.bytes -= key_len + old.len();
+        }
+    }
+
+    fn remove(&mut self, key: &str) -> bool {
+        let removed = self.entries.remove(key);
+        if let Some(old) = &removed {
+            self.bytes -= key.len() + old.len();
+        }
+        removed.is_some()
+    }
+}
+
+#[derive(Default)]
+struct StorageData {
+    entries: HashMap<String, PluginEntries>,
+    /// Latest change of each entry since the last call to `take_changes`
+    changes: BTreeMap<(String, String), Option<Vec<u8>>>,
+}
+
+/// Storage of all plugins, cheap to clone and shared with the plugin modules.
+#[derive(Clone, Default)]
+pub struct PluginStorage(Arc<Mutex<StorageData>>);
+
+impl PluginStorage {
+    /// Insert entries read from the database, these aren't reported as changes
+    pub fn load(&self, entries: impl IntoIterator<Item = (String, String, Vec<u8>)>) {
+        let mut data = self.0.lock().unwrap();
+        for (plugin, key, value) in entries {
+            data.entries.entry(plugin).or_default().insert(key, value);
+        }
+    }
+
+    /// Restrict access to the entries of a single plugin
+    pub fn scoped(&self, plugin: &str) -> ScopedStorage {
+        ScopedStorage {
+            storage: self.clone(),
+            plugin: plugin.to_owned(),
+        }
+    }
+
+    /// Take all modifications made since the last call
+    pub fn take_changes(&self) -> Vec<PluginStorageChange> {
+        let mut data = self.0.lock().unwrap();
+        core::mem::take(&mut data.changes)
+            .into_iter()
+            .map(|((plugin, key), value)| PluginStorageChange { plugin, key, value })
+            .collect()
+    }
+}
+
+/// The namespace of a single plugin within [`PluginStorage`]
+pub struct ScopedStorage {
+    storage: PluginStorage,
+    plugin: String,
+}
+
+impl ScopedStorage {
+    pub fn get(&self, key: &str) -> Option<Vec<u8>> {
+        let data = self.storage.0.lock().unwrap();
+        data.entries.get(&self.plugin)?.entries.get(key).cloned()
+    }
+
+    /// Rejects the write if the plugin would store more than
+    /// [`MAX_PLUGIN_BYTES`] afterwards
+    pub fn set(&self, key: String, value: Vec<u8>) -> Result<(), StorageFull> {
+        let mut data = self.storage.0.lock().unwrap();
+        let data = &mut *data;
+        let entries = data.entries.entry(self.plugin.clone()).or_default();
+        let replaced = entries
+            .entries
+            .get(&key)
+            .map_or(0, |old| key.len() + old.len());
+        if entries.bytes - replaced + key.len() + value.len() > MAX_PLUGIN_BYTES {
+            return Err(StorageFull);
+        }
+        entries.insert(key.clone(), value.clone());
+        data.changes.insert((self.plugin.clone(), key), Some(value));
+        Ok(())
+    }
+
+    pub fn delete(&self, key: &str) {
+        let mut data = self.storage.0.lock().unwrap();
+        if data
+            .entries
+            .get_mut(&self.plugin)
+            .is_some_and(|entries| entries.remove(key))
+        {
+            data.changes
+                .insert((self.plugin.clone(), key.to_owned()), None);
+        }
+    }
+
+    /// All keys starting with `prefix` in lexicographic order
+    pub fn list_keys(&self, prefix: &str) -> Vec<String> {
+        let data = self.storage.0.lock().unwrap();
+        data.entries
+            .get(&self.plugin)
+            .map(|entries| {
+                entries
+                    .entries
+                    .range::<str, _>(prefix..)
+                    .map(|(key, _)| key)
+                    .take_while(|key| key.starts_with(prefix))
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn namespaces_are_isolated() {
+        let storage = PluginStorage::default();
+        let (a, b) = (storage.scoped("a"), storage.scoped("b"));
+        a.set("key".to_owned(), vec![1]).unwrap();
+        b.set("key".to_owned(), vec![2]).unwrap();
+        assert_eq!(a.get("key"), Some(vec![1]));
+        assert_eq!(b.get("key"), Some(vec![2]));
+        b.delete("key");
+        assert_eq!(a.get("key"), Some(vec![1]));
+        assert_eq!(b.get("key"), None);
+        assert!(b.list_keys("").is_empty());
+
+        let mut changes = storage
+            .take_changes()
+            .into_iter()
+            .map(|change| (change.plugin, change.key, change.value))
+            .collect::<Vec<_>>();
+        changes.sort();
+        assert_eq!(changes, vec![
+            ("a".to_owned(), "key".to_owned(), Some(vec![1])),
+            ("b".to_owned(), "key".to_owned(), None),
+        ]);
+        assert!(storage.take_changes().is_empty());
+    }
+
+    #[test]
+    fn list_keys_by_prefix() {
+        let storage = PluginStorage::default();
+        storage.load([("a".to_owned(), "player.2".to_owned(), vec![])]);
+        let a = storage.scoped("a");
+        for key in ["player.1", "player", "playerx", "other", "player.3"] {
+            a.set(key.to_owned(), vec![]).unwrap();
+        }
+        storage
+            .scoped("b")
+            .set("player.4".to_owned(), vec![])
+            .unwrap();
+        assert_eq!(a.list_keys("player."), vec![
+            "player.1", "player.2", "player.3"
+        ]);
+        assert_eq!(a.list_keys("").len(), 6);
+        assert!(a.list_keys("zzz").is_empty());
+        // loaded entries aren't changes
+        assert_eq!(storage.take_changes().len(), 6);
+    }
+
+    #[test]
+    fn quota_rejects_writes() {
+        let storage = PluginStorage::default();
+        let a = storage.scoped("a");
+        let value = vec![0; MAX_VALUE_LEN];
+        let key = |i: usize| format!("{i:08}");
+        let fitting = MAX_PLUGIN_BYTES / (key(0).len() + MAX_VALUE_LEN);
+        for i in 0..fitting {
+            a.set(key(i), value.clone()).unwrap();
+        }
+        assert_eq!(a.set(key(fitting), value.clone()), Err(StorageFull));
+        assert_eq!(a.get(&key(fitting)), None);
+        // other plugins have their own quota
+        storage.scoped("b").set(key(0), value.clone()).unwrap();
+        // replacing an entry only counts the difference
+        a.set(key(0), value.clone()).unwrap();
+        a.delete(&key(0));
+        a.set(key(fitting), value).unwrap();
+        assert_eq!(a.list_keys("").len(), fitting);
+    }
+}
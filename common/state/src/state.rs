@@ -373,7 +373,9 @@ impl State {
         ecs.register::<comp::Melee>();
         ecs.register::<comp::ItemDrops>();
         ecs.register::<comp::ChatMode>();
-        ecs.register::<comp::Faction>();
+        ecs.register::<comp::Guild>();
+        ecs.register::<comp::Duel>();
+        ecs.register::<comp::PersonalStorage>();
         ecs.register::<comp::invite::Invite>();
         ecs.register::<comp::invite::PendingInvites>();
         ecs.register::<VolumeRiders>();
@@ -2,8 +2,8 @@ use common::{
     GroupTarget,
     combat::{self, AttackOptions, AttackSource, AttackerInfo, TargetInfo},
     comp::{
-        Alignment, Arcing, Body, Buffs, CharacterState, Combo, Energy, Group, Health, Inventory,
-        Mass, Ori, Player, Pos, Scale, Stats, aura::EnteredAuras,
+        Alignment, Arcing, Body, Buffs, CharacterState, Combo, Duel, Energy, Group, Guild, Health,
+        Inventory, Mass, Ori, Player, Pos, Scale, Stats, aura::EnteredAuras,
     },
     event::{
         BuffEvent, ComboChangeEvent, DeleteEvent, EmitExt, EnergyChangeEvent,
@@ -56,6 +56,8 @@ pub struct ReadData<'a> {
     buffs: ReadStorage<'a, Buffs>,
     alignments: ReadStorage<'a, Alignment>,
     players: ReadStorage<'a, Player>,
+    guilds: ReadStorage<'a, Guild>,
+    duels: ReadStorage<'a, Duel>,
 }
 
 /// This system is responsible for hit detection of arcing attacks. Arcing
@@ -238,6 +240,8 @@ impl<'a> System<'a> for Sys {
                         let permit_pvp = combat::permit_pvp(
                             &read_data.alignments,
                             &read_data.players,
+                            &read_data.guilds,
+                            &read_data.duels,
                             &read_data.entered_auras,
                             &read_data.id_maps,
                             arc_owner,
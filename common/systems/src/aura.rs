@@ -3,7 +3,8 @@ use std::collections::HashSet;
 use common::{
     combat,
     comp::{
-        Alignment, Aura, Auras, BuffKind, Buffs, CharacterState, Health, Mass, Player, Pos, Stats,
+        Alignment, Aura, Auras, BuffKind, Buffs, CharacterState, Duel, Guild, Health, Mass, Player,
+        Pos, Stats,
         aura::{AuraChange, AuraKey, AuraKind, AuraTarget, EnteredAuras},
         buff::{Buff, BuffCategory, BuffChange, BuffSource, DestInfo},
         group::Group,
@@ -27,6 +28,8 @@ event_emitters! {
 pub struct ReadData<'a> {
     entities: Entities<'a>,
     players: ReadStorage<'a, Player>,
+    guilds: ReadStorage<'a, Guild>,
+    duels: ReadStorage<'a, Duel>,
     time: Read<'a, Time>,
     events: Events<'a>,
     id_maps: Read<'a, IdMaps>,
@@ -241,6 +244,8 @@ fn activate_aura(
                 combat::permit_pvp(
                     &read_data.alignments,
                     &read_data.players,
+                    &read_data.guilds,
+                    &read_data.duels,
                     &read_data.entered_auras,
                     &read_data.id_maps,
                     owner,
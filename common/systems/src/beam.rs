@@ -4,8 +4,8 @@ use common::{
     GroupTarget,
     combat::{self, AttackOptions, AttackSource, AttackerInfo, TargetInfo},
     comp::{
-        Alignment, Beam, Body, Buffs, CharacterState, Combo, Energy, Group, Health, Inventory,
-        Mass, Ori, PhysicsState, Player, Pos, Scale, Stats,
+        Alignment, Beam, Body, Buffs, CharacterState, Combo, Duel, Energy, Group, Guild, Health,
+        Inventory, Mass, Ori, PhysicsState, Player, Pos, Scale, Stats,
         ability::Dodgeable,
         agent::{Sound, SoundKind},
         aura::EnteredAuras,
@@ -45,6 +45,8 @@ event_emitters! {
 pub struct ReadData<'a> {
     entities: Entities<'a>,
     players: ReadStorage<'a, Player>,
+    guilds: ReadStorage<'a, Guild>,
+    duels: ReadStorage<'a, Duel>,
     time: Read<'a, Time>,
     dt: Read<'a, DeltaTime>,
     terrain: ReadExpect<'a, TerrainGrid>,
@@ -274,6 +276,8 @@ impl<'a> System<'a> for Sys {
                             let permit_pvp = combat::permit_pvp(
                                 &read_data.alignments,
                                 &read_data.players,
+                                &read_data.guilds,
+                                &read_data.duels,
                                 &read_data.entered_auras,
                                 &read_data.id_maps,
                                 Some(entity),
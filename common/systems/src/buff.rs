@@ -2,7 +2,7 @@ use common::{
     Damage, DamageSource,
     combat::{self, DamageContributor},
     comp::{
-        Alignment, Energy, Group, Health, HealthChange, Inventory, LightEmitter, Mass,
+        Alignment, Duel, Energy, Group, Guild, Health, HealthChange, Inventory, LightEmitter, Mass,
         ModifierKind, PhysicsState, Player, Pos, Stats,
         agent::{Sound, SoundKind},
         aura::{Auras, EnteredAuras},
@@ -69,6 +69,8 @@ pub struct ReadData<'a> {
     light_emitters: ReadStorage<'a, LightEmitter>,
     alignments: ReadStorage<'a, Alignment>,
     players: ReadStorage<'a, Player>,
+    guilds: ReadStorage<'a, Guild>,
+    duels: ReadStorage<'a, Duel>,
     masses: ReadStorage<'a, Mass>,
 }
 
@@ -190,6 +192,8 @@ impl<'a> System<'a> for Sys {
                             combat::permit_pvp(
                                 &read_data.alignments,
                                 &read_data.players,
+                                &read_data.guilds,
+                                &read_data.duels,
                                 &read_data.entered_auras,
                                 &read_data.id_maps,
                                 Some(entity),
@@ -28,6 +28,8 @@ event_emitters! {
         process_trade_action: event::ProcessTradeActionEvent,
         inventory_manip: event::InventoryManipEvent,
         group_manip: event::GroupManipEvent,
+        mail: event::MailEvent,
+        market: event::MarketEvent,
         respawn: event::RespawnEvent,
         sound: event::SoundEvent,
         change_ability: event::ChangeAbilityEvent,
@@ -131,6 +133,12 @@ impl<'a> System<'a> for Sys {
                         ControlEvent::GroupManip(manip) => {
                             emitters.emit(event::GroupManipEvent(entity, manip))
                         },
+                        ControlEvent::Mail(action) => {
+                            emitters.emit(event::MailEvent(entity, action))
+                        },
+                        ControlEvent::Market(action) => {
+                            emitters.emit(event::MarketEvent(entity, action))
+                        },
                         ControlEvent::GiveUp => {
                             if read_data
                                 .healths
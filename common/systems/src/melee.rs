@@ -2,8 +2,8 @@ use common::{
     GroupTarget,
     combat::{self, AttackOptions, AttackSource, AttackerInfo, TargetInfo},
     comp::{
-        Alignment, Body, Buffs, CharacterState, Combo, Energy, Group, Health, Inventory, Mass,
-        Melee, Ori, PhysicsState, Player, Pos, Scale, Stats,
+        Alignment, Body, Buffs, CharacterState, Combo, Duel, Energy, Group, Guild, Health,
+        Inventory, Mass, Melee, Ori, PhysicsState, Player, Pos, Scale, Stats,
         ability::Dodgeable,
         agent::{Sound, SoundKind},
         aura::EnteredAuras,
@@ -49,6 +49,8 @@ pub struct ReadData<'a> {
     id_maps: Read<'a, IdMaps>,
     entities: Entities<'a>,
     players: ReadStorage<'a, Player>,
+    guilds: ReadStorage<'a, Guild>,
+    duels: ReadStorage<'a, Duel>,
     uids: ReadStorage<'a, Uid>,
     positions: ReadStorage<'a, Pos>,
     orientations: ReadStorage<'a, Ori>,
@@ -260,6 +262,8 @@ impl<'a> System<'a> for Sys {
                     let permit_pvp = combat::permit_pvp(
                         &read_data.alignments,
                         &read_data.players,
+                        &read_data.guilds,
+                        &read_data.duels,
                         &read_data.entered_auras,
                         &read_data.id_maps,
                         Some(attacker),
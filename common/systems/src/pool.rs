@@ -2,9 +2,9 @@ use common::{
     GroupTarget,
     combat::{self, AttackOptions, AttackSource, AttackerInfo, TargetInfo},
     comp::{
-        Alignment, Body, Buffs, CharacterState, Combo, Energy, Group, Health, Inventory, Mass, Ori,
-        PhysicsState, Player, Pos, Scale, Stats, ability::Dodgeable, aura::EnteredAuras,
-        pool::Pool,
+        Alignment, Body, Buffs, CharacterState, Combo, Duel, Energy, Group, Guild, Health,
+        Inventory, Mass, Ori, PhysicsState, Player, Pos, Scale, Stats, ability::Dodgeable,
+        aura::EnteredAuras, pool::Pool,
     },
     event::{
         BuffEvent, ComboChangeEvent, DeleteEvent, EmitExt, EnergyChangeEvent,
@@ -63,6 +63,8 @@ pub struct ReadData<'a> {
     buffs: ReadStorage<'a, Buffs>,
     alignments: ReadStorage<'a, Alignment>,
     players: ReadStorage<'a, Player>,
+    guilds: ReadStorage<'a, Guild>,
+    duels: ReadStorage<'a, Duel>,
     scales: ReadStorage<'a, Scale>,
     entered_auras: ReadStorage<'a, EnteredAuras>,
     outcomes: Read<'a, EventBus<Outcome>>,
@@ -205,6 +207,8 @@ impl<'a> System<'a> for Sys {
                     let permit_pvp = combat::permit_pvp(
                         &read_data.alignments,
                         &read_data.players,
+                        &read_data.guilds,
+                        &read_data.duels,
                         &read_data.entered_auras,
                         &read_data.id_maps,
                         pool_owner,
@@ -2,8 +2,8 @@ use common::{
     Damage, DamageKind, Explosion, GroupTarget, RadiusEffect,
     combat::{self, AttackOptions, AttackSource, AttackerInfo, TargetInfo},
     comp::{
-        Alignment, Body, Buffs, CharacterState, Combo, Content, Energy, Group, Health, Inventory,
-        Mass, Ori, PhysicsState, Player, Poise, Pos, Projectile, Stats, Vel,
+        Alignment, Body, Buffs, CharacterState, Combo, Content, Duel, Energy, Group, Guild, Health,
+        Inventory, Mass, Ori, PhysicsState, Player, Poise, Pos, Projectile, Stats, Vel,
         agent::{Sound, SoundKind},
         aura::EnteredAuras,
         object,
@@ -67,6 +67,8 @@ pub struct ReadData<'a> {
     time: Read<'a, Time>,
     entities: Entities<'a>,
     players: ReadStorage<'a, Player>,
+    guilds: ReadStorage<'a, Guild>,
+    duels: ReadStorage<'a, Duel>,
     dt: Read<'a, DeltaTime>,
     id_maps: Read<'a, IdMaps>,
     events: Events<'a>,
@@ -637,6 +639,8 @@ fn dispatch_hit(
             let permit_pvp = combat::permit_pvp(
                 &read_data.alignments,
                 &read_data.players,
+                &read_data.guilds,
+                &read_data.duels,
                 &read_data.entered_auras,
                 &read_data.id_maps,
                 owner,
@@ -2,8 +2,9 @@ use common::{
     GroupTarget,
     combat::{self, AttackOptions, AttackerInfo, TargetInfo},
     comp::{
-        Alignment, Body, Buffs, CharacterState, Combo, Energy, Group, Health, Inventory, Mass, Ori,
-        PhysicsState, Player, Pos, Scale, Shockwave, ShockwaveHitEntities, Stats,
+        Alignment, Body, Buffs, CharacterState, Combo, Duel, Energy, Group, Guild, Health,
+        Inventory, Mass, Ori, PhysicsState, Player, Pos, Scale, Shockwave, ShockwaveHitEntities,
+        Stats,
         ability::Dodgeable,
         agent::{Sound, SoundKind},
         aura::EnteredAuras,
@@ -47,6 +48,8 @@ pub struct ReadData<'a> {
     events: Events<'a>,
     time: Read<'a, Time>,
     players: ReadStorage<'a, Player>,
+    guilds: ReadStorage<'a, Guild>,
+    duels: ReadStorage<'a, Duel>,
     dt: Read<'a, DeltaTime>,
     id_maps: Read<'a, IdMaps>,
     uids: ReadStorage<'a, Uid>,
@@ -263,6 +266,8 @@ impl<'a> System<'a> for Sys {
                     let permit_pvp = combat::permit_pvp(
                         &read_data.alignments,
                         &read_data.players,
+                        &read_data.guilds,
+                        &read_data.duels,
                         &read_data.entered_auras,
                         &read_data.id_maps,
                         shockwave_owner,
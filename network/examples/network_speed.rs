@@ -3,7 +3,11 @@
 /// (cd network/examples/network-speed && RUST_BACKTRACE=1 cargo run --profile=debuginfo -Z unstable-options -- --trace=error --protocol=tcp --mode=server)
 /// (cd network/examples/network-speed && RUST_BACKTRACE=1 cargo run --profile=debuginfo -Z unstable-options -- --trace=error --protocol=tcp --mode=client)
 /// ```
-use clap::{Arg, Command};
+/// simulate a bad connection
+/// ```bash
+/// (cd network/examples/network-speed && RUST_BACKTRACE=1 cargo run --profile=debuginfo -Z unstable-options -- --trace=error --protocol=mpsc --latency=100 --jitter=30 --bandwidth=1000000)
+/// ```
+use clap::{Arg, ArgAction, Command};
 use prometheus::Registry;
 use prometheus_hyper::Server;
 use serde::{Deserialize, Serialize};
@@ -16,7 +20,9 @@ use std::{
 use tokio::runtime::Runtime;
 use tracing::*;
 use tracing_subscriber::EnvFilter;
-use veloren_network::{ConnectAddr, ListenAddr, Message, Network, Pid, Promises};
+use veloren_network::{
+    ConnectAddr, ListenAddr, Message, Network, NetworkConditions, Pid, Promises,
+};
 
 #[derive(Serialize, Deserialize, Debug)]
 enum Msg {
@@ -65,6 +71,43 @@ fn main() {
                     "underlying protocol used for this test, mpsc can only combined with mode=both",
                 ),
         )
+        .arg(
+            Arg::new("latency")
+                .long("latency")
+                .value_parser(clap::value_parser!(u64))
+                .help("simulated latency in ms added to every message"),
+        )
+        .arg(
+            Arg::new("jitter")
+                .long("jitter")
+                .value_parser(clap::value_parser!(u64))
+                .help("simulated random latency in ms added on top of --latency"),
+        )
+        .arg(
+            Arg::new("bandwidth")
+                .long("bandwidth")
+                .value_parser(clap::value_parser!(u64))
+                .help("simulated bandwidth limit in bytes per second"),
+        )
+        .arg(
+            Arg::new("drop")
+                .long("drop")
+                .value_parser(clap::value_parser!(f32))
+                .help("simulated ratio of dropped messages, only affects unreliable streams"),
+        )
+        .arg(
+            Arg::new("reorder")
+                .long("reorder")
+                .action(ArgAction::SetTrue)
+                .help("allow simulated jitter to reorder messages of unordered streams"),
+        )
+        .arg(
+            Arg::new("seed")
+                .long("seed")
+                .default_value("0")
+                .value_parser(clap::value_parser!(u64))
+                .help("seed for the simulated jitter and drops"),
+        )
         .arg(
             Arg::new("trace")
                 .short('t')
@@ -101,20 +144,41 @@ fn main() {
             ListenAddr::Udp(format!("{}:{}", ip, port).parse().unwrap()),
             ConnectAddr::Udp(format!("{}:{}", ip, port).parse().unwrap()),
         ),
+        Some("mpsc") => (
+            ListenAddr::Mpsc(*port as u64),
+            ConnectAddr::Mpsc(*port as u64),
+        ),
         _ => panic!("invalid mode, run --help!"),
     };
 
+    let simulate = ["latency", "jitter", "bandwidth", "drop"]
+        .iter()
+        .any(|arg| matches.contains_id(arg))
+        || matches.get_flag("reorder");
+    let conditions = simulate.then(|| NetworkConditions {
+        latency: Duration::from_millis(*matches.get_one::<u64>("latency").unwrap_or(&0)),
+        jitter: Duration::from_millis(*matches.get_one::<u64>("jitter").unwrap_or(&0)),
+        bandwidth: matches.get_one::<u64>("bandwidth").copied(),
+        drop_ratio: *matches.get_one::<f32>("drop").unwrap_or(&0.0),
+        reorder: matches.get_flag("reorder"),
+        seed: *matches.get_one::<u64>("seed").unwrap(),
+    });
+    if let Some(conditions) = &conditions {
+        info!(?conditions, "simulating network conditions");
+    }
+
     let mut background = None;
     let runtime = Arc::new(Runtime::new().unwrap());
     match matches.get_one::<String>("mode").map(|s| s.as_str()) {
-        Some("server") => server(addresses.0, Arc::clone(&runtime)),
-        Some("client") => client(addresses.1, Arc::clone(&runtime)),
+        Some("server") => server(addresses.0, Arc::clone(&runtime), conditions),
+        Some("client") => client(addresses.1, Arc::clone(&runtime), conditions),
         Some("both") => {
             let s = addresses.0;
             let runtime2 = Arc::clone(&runtime);
-            background = Some(thread::spawn(|| server(s, runtime2)));
+            let conditions2 = conditions.clone();
+            background = Some(thread::spawn(|| server(s, runtime2, conditions2)));
             thread::sleep(Duration::from_millis(200)); //start client after server
-            client(addresses.1, Arc::clone(&runtime));
+            client(addresses.1, Arc::clone(&runtime), conditions);
         },
         _ => panic!("Invalid mode, run --help!"),
     };
@@ -123,9 +187,10 @@ fn main() {
     }
 }
 
-fn server(address: ListenAddr, runtime: Arc<Runtime>) {
+fn server(address: ListenAddr, runtime: Arc<Runtime>, conditions: Option<NetworkConditions>) {
     let registry = Arc::new(Registry::new());
     let mut server = Network::new_with_registry(Pid::new(), &runtime, &registry, usize::MAX);
+    server.set_network_conditions(conditions);
     runtime.spawn(Server::run(
         Arc::clone(&registry),
         SocketAddr::from(([0; 4], 59112)),
@@ -155,9 +220,10 @@ fn server(address: ListenAddr, runtime: Arc<Runtime>) {
     }
 }
 
-fn client(address: ConnectAddr, runtime: Arc<Runtime>) {
+fn client(address: ConnectAddr, runtime: Arc<Runtime>, conditions: Option<NetworkConditions>) {
     let registry = Arc::new(Registry::new());
     let client = Network::new_with_registry(Pid::new(), &runtime, &registry, usize::MAX);
+    client.set_network_conditions(conditions);
     runtime.spawn(Server::run(
         Arc::clone(&registry),
         SocketAddr::from(([0; 4], 59111)),
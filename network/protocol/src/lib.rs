@@ -14,8 +14,10 @@
 //!  - TCP
 //!  - MPSC
 //!  - QUIC
+//!  - UDP
 //!
-//! eventually a pure UDP implementation will follow
+//! Additionally [`SimSendProtocol`] wraps any of them to simulate bad network
+//! conditions in tests.
 //!
 //! warning: don't mix protocol, using the TCP variant for actual UDP socket
 //! will result in dropped data  using UDP with a TCP socket will be a waste of
@@ -48,6 +50,7 @@
 //! [`SendProtocol`]: crate::SendProtocol
 //! [`RecvProtocol`]: crate::RecvProtocol
 //! [`InitProtocol`]: crate::InitProtocol
+//! [`SimSendProtocol`]: crate::SimSendProtocol
 
 mod error;
 mod event;
@@ -58,8 +61,10 @@ mod metrics;
 mod mpsc;
 mod prio;
 mod quic;
+mod sim;
 mod tcp;
 mod types;
+mod udp;
 mod util;
 
 pub use error::{InitProtocolError, ProtocolError};
@@ -69,8 +74,10 @@ pub use metrics::ProtocolMetricCache;
 pub use metrics::ProtocolMetrics;
 pub use mpsc::{MpscMsg, MpscRecvProtocol, MpscSendProtocol};
 pub use quic::{QuicDataFormat, QuicDataFormatStream, QuicRecvProtocol, QuicSendProtocol};
+pub use sim::{NetworkConditions, SimSendProtocol};
 pub use tcp::{TcpRecvProtocol, TcpSendProtocol};
 pub use types::{Bandwidth, Cid, HIGHEST_PRIO, Pid, Prio, Promises, Sid, VELOREN_NETWORK_VERSION};
+pub use udp::{UdpRecvProtocol, UdpSendProtocol, is_udp_handshake, udp_protocols};
 
 ///use at own risk, might change any time, for internal benchmarks
 pub mod _internal {
@@ -0,0 +1,442 @@
+use crate::{
+    SendProtocol,
+    error::ProtocolError,
+    event::ProtocolEvent,
+    types::{Bandwidth, Promises, Sid},
+};
+use async_trait::async_trait;
+use hashbrown::HashMap;
+use rand::{RngExt, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use std::{
+    collections::BTreeMap,
+    time::{Duration, Instant},
+};
+#[cfg(feature = "trace_pedantic")]
+use tracing::trace;
+
+/// Conditions a [`SimSendProtocol`] applies to all outgoing events, e.g. to
+/// reproduce lag spikes or a slow connection in tests.
+///
+/// All random decisions are taken from a rng seeded with `seed`, so the same
+/// sequence of events will always be delayed and dropped in the same way.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NetworkConditions {
+    /// constant delay added to every event
+    pub latency: Duration,
+    /// random delay between `0` and `jitter` added on top of `latency`
+    pub jitter: Duration,
+    /// upper bound of message bytes released per second, `None` is unlimited
+    pub bandwidth: Option<Bandwidth>,
+    /// chance (`0.0..=1.0`) that a message is dropped. Only messages of
+    /// streams without [`Promises::GUARANTEED_DELIVERY`] are dropped
+    pub drop_ratio: f32,
+    /// allows messages of streams without [`Promises::ORDERED`] to overtake
+    /// each other when their jitter differs
+    pub reorder: bool,
+    pub seed: u64,
+}
+
+impl Default for NetworkConditions {
+    fn default() -> Self {
+        Self {
+            latency: Duration::ZERO,
+            jitter: Duration::ZERO,
+            bandwidth: None,
+            drop_ratio: 0.0,
+            reorder: false,
+            seed: 0,
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Delayed {
+    Send(ProtocolEvent),
+    Notify(ProtocolEvent),
+}
+
+/// Wraps any [`SendProtocol`] and holds back events according to the
+/// [`NetworkConditions`]. Events are only handed to the inner protocol in
+/// `flush`, so it works the same for protocols that send directly (MPSC) and
+/// those that buffer (TCP).
+///
+/// Only the sending side is simulated, wrap both ends to simulate a round
+/// trip.
+///
+/// [`SendProtocol`]: crate::SendProtocol
+#[derive(Debug)]
+pub struct SimSendProtocol<P>
+where
+    P: SendProtocol,
+{
+    inner: P,
+    conditions: NetworkConditions,
+    rng: ChaCha8Rng,
+    promises: HashMap<Sid, Promises>,
+    /// earliest release of the next ordered event
+    last_release: Instant,
+    /// release of the open per stream, no message may overtake it
+    stream_open: HashMap<Sid, Instant>,
+    /// latest release of any event per stream, a close must come after it
+    stream_release: HashMap<Sid, Instant>,
+    delayed: BTreeMap<(Instant, u64), Delayed>,
+    next_seq: u64,
+    /// bytes that may be released before the `bandwidth` limit kicks in
+    budget: f64,
+}
+
+impl<P> SimSendProtocol<P>
+where
+    P: SendProtocol,
+{
+    pub fn new(inner: P, conditions: NetworkConditions) -> Self {
+        Self {
+            inner,
+            rng: ChaCha8Rng::seed_from_u64(conditions.seed),
+            conditions,
+            promises: HashMap::new(),
+            last_release: Instant::now(),
+            stream_open: HashMap::new(),
+            stream_release: HashMap::new(),
+            delayed: BTreeMap::new(),
+            next_seq: 0,
+            budget: 0.0,
+        }
+    }
+
+    pub fn inner(&self) -> &P { &self.inner }
+
+    pub fn conditions(&self) -> &NetworkConditions { &self.conditions }
+
+    fn delay(&mut self) -> Instant {
+        let jitter = if self.conditions.jitter.is_zero() {
+            Duration::ZERO
+        } else {
+            self.conditions
+                .jitter
+                .mul_f32(self.rng.random_range(0.0..=1.0))
+        };
+        Instant::now() + self.conditions.latency + jitter
+    }
+
+    fn push(&mut self, release: Instant, delayed: Delayed) {
+        self.delayed.insert((release, self.next_seq), delayed);
+        self.next_seq += 1;
+    }
+
+    /// keeps `release` after everything that was ordered before it
+    fn push_ordered(&mut self, sid: Sid, release: Instant, delayed: Delayed) -> Instant {
+        let mut release = release.max(self.last_release);
+        if let Some(stream) = self.stream_release.get(&sid) {
+            release = release.max(*stream);
+        }
+        self.last_release = release;
+        self.stream_release.insert(sid, release);
+        self.push(release, delayed);
+        release
+    }
+
+    async fn release(&mut self, delayed: Delayed) -> Result<(), ProtocolError<P::CustomErr>> {
+        match delayed {
+            Delayed::Send(event) => self.inner.send(event).await,
+            Delayed::Notify(event) => {
+                self.inner.notify_from_recv(event);
+                Ok(())
+            },
+        }
+    }
+}
+
+#[async_trait]
+impl<P> SendProtocol for SimSendProtocol<P>
+where
+    P: SendProtocol + Send,
+{
+    type CustomErr = P::CustomErr;
+
+    fn notify_from_recv(&mut self, event: ProtocolEvent) {
+        match event {
+            ProtocolEvent::OpenStream { sid, promises, .. } => {
+                // remote streams must be known before we send on them
+                self.promises.insert(sid, promises);
+                self.inner.notify_from_recv(event);
+            },
+            ProtocolEvent::CloseStream { sid } => {
+                // delay the close till all our held back messages are released
+                self.promises.remove(&sid);
+                let release = self
+                    .stream_release
+                    .remove(&sid)
+                    .unwrap_or_else(Instant::now);
+                self.push(release, Delayed::Notify(event));
+            },
+            event => self.inner.notify_from_recv(event),
+        }
+    }
+
+    async fn send(&mut self, event: ProtocolEvent) -> Result<(), ProtocolError<Self::CustomErr>> {
+        #[cfg(feature = "trace_pedantic")]
+        trace!(?event, "send");
+        match event {
+            ProtocolEvent::Shutdown => {
+                // nobody will flush us after a shutdown, release everything now
+                while let Some((_, delayed)) = self.delayed.pop_first() {
+                    self.release(delayed).await?;
+                }
+                self.inner.send(event).await
+            },
+            ProtocolEvent::OpenStream { sid, promises, .. } => {
+                self.promises.insert(sid, promises);
+                let release = self.delay();
+                let release = self.push_ordered(sid, release, Delayed::Send(event));
+                self.stream_open.insert(sid, release);
+                Ok(())
+            },
+            ProtocolEvent::CloseStream { sid } => {
+                self.promises.remove(&sid);
+                let release = self.delay();
+                self.push_ordered(sid, release, Delayed::Send(event));
+                self.stream_open.remove(&sid);
+                self.stream_release.remove(&sid);
+                Ok(())
+            },
+            ProtocolEvent::Message { sid, .. } => {
+                let promises = self
+                    .promises
+                    .get(&sid)
+                    .copied()
+                    .unwrap_or(Promises::empty());
+                if !promises.contains(Promises::GUARANTEED_DELIVERY)
+                    && self.conditions.drop_ratio > 0.0
+                    && self.rng.random::<f32>() < self.conditions.drop_ratio
+                {
+                    #[cfg(feature = "trace_pedantic")]
+                    trace!(?sid, "simulate dropped message");
+                    return Ok(());
+                }
+                let release = self.delay();
+                if self.conditions.reorder && !promises.contains(Promises::ORDERED) {
+                    // may overtake other messages, but never its stream's open
+                    let release = match self.stream_open.get(&sid) {
+                        Some(open) => release.max(*open),
+                        None => release,
+                    };
+                    let latest = self.stream_release.entry(sid).or_insert(release);
+                    *latest = (*latest).max(release);
+                    self.push(release, Delayed::Send(event));
+                } else {
+                    self.push_ordered(sid, release, Delayed::Send(event));
+                }
+                Ok(())
+            },
+        }
+    }
+
+    async fn flush(
+        &mut self,
+        bandwidth: Bandwidth,
+        dt: Duration,
+    ) -> Result<Bandwidth, ProtocolError<Self::CustomErr>> {
+        if let Some(limit) = self.conditions.bandwidth {
+            // don't allow to save up more than one second worth of bandwidth
+            self.budget = (self.budget + limit as f64 * dt.as_secs_f64()).min(limit as f64);
+        }
+        let now = Instant::now();
+        while let Some(entry) = self.delayed.first_entry() {
+            if entry.key().0 > now {
+                break;
+            }
+            if let Delayed::Send(ProtocolEvent::Message { data, .. }) = entry.get()
+                && self.conditions.bandwidth.is_some()
+            {
+                // a message larger than the budget is released once the budget
+                // is positive, the debt is paid by the following flushes
+                if self.budget <= 0.0 {
+                    break;
+                }
+                self.budget -= data.len() as f64;
+            }
+            let delayed = entry.remove();
+            self.release(delayed).await?;
+        }
+        self.inner.flush(bandwidth, dt).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{RecvProtocol, mpsc::test_utils::*, types::STREAM_ID_OFFSET1};
+    use bytes::Bytes;
+
+    fn open(sid: Sid, promises: Promises) -> ProtocolEvent {
+        ProtocolEvent::OpenStream {
+            sid,
+            prio: 5,
+            promises,
+            guaranteed_bandwidth: 0,
+        }
+    }
+
+    fn msg(sid: Sid, i: u8) -> ProtocolEvent {
+        ProtocolEvent::Message {
+            data: Bytes::from(vec![i; 100]),
+            sid,
+        }
+    }
+
+    async fn flush_for<P: SendProtocol + Send>(p: &mut SimSendProtocol<P>, time: Duration) {
+        let end = Instant::now() + time;
+        while Instant::now() < end {
+            p.flush(1_000_000_000, Duration::from_millis(5))
+                .await
+                .unwrap();
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn latency_holds_back_events() {
+        let [(s, _), (_, mut r)] = ac_bound(100, None);
+        let conditions = NetworkConditions {
+            latency: Duration::from_millis(200),
+            ..Default::default()
+        };
+        let mut s = SimSendProtocol::new(s, conditions);
+        let sid = STREAM_ID_OFFSET1;
+        let start = Instant::now();
+        s.send(open(sid, Promises::ORDERED)).await.unwrap();
+        s.send(msg(sid, 1)).await.unwrap();
+        flush_for(&mut s, Duration::from_millis(50)).await;
+        assert!(s.delayed.len() == 2, "nothing may be released yet");
+        flush_for(&mut s, Duration::from_millis(250)).await;
+        assert!(s.delayed.is_empty());
+        assert_eq!(r.recv().await.unwrap(), open(sid, Promises::ORDERED));
+        assert_eq!(r.recv().await.unwrap(), msg(sid, 1));
+        assert!(start.elapsed() >= Duration::from_millis(200));
+    }
+
+    #[tokio::test]
+    async fn jitter_keeps_ordered_streams_ordered() {
+        let [(s, _), (_, mut r)] = ac_bound(100, None);
+        let conditions = NetworkConditions {
+            jitter: Duration::from_millis(50),
+            reorder: true,
+            seed: 42,
+            ..Default::default()
+        };
+        let mut s = SimSendProtocol::new(s, conditions);
+        let sid = STREAM_ID_OFFSET1;
+        s.send(open(sid, Promises::ORDERED)).await.unwrap();
+        for i in 0..20 {
+            s.send(msg(sid, i)).await.unwrap();
+        }
+        s.send(ProtocolEvent::CloseStream { sid }).await.unwrap();
+        flush_for(&mut s, Duration::from_millis(150)).await;
+        assert_eq!(r.recv().await.unwrap(), open(sid, Promises::ORDERED));
+        for i in 0..20 {
+            assert_eq!(r.recv().await.unwrap(), msg(sid, i));
+        }
+        assert_eq!(r.recv().await.unwrap(), ProtocolEvent::CloseStream { sid });
+    }
+
+    #[tokio::test]
+    async fn drops_only_unguaranteed_messages() {
+        let [(s, _), (_, mut r)] = ac_bound(1000, None);
+        let conditions = NetworkConditions {
+            drop_ratio: 0.5,
+            seed: 1337,
+            ..Default::default()
+        };
+        let mut s = SimSendProtocol::new(s, conditions);
+        let reliable = STREAM_ID_OFFSET1;
+        let unreliable = Sid::new(1);
+        s.send(open(reliable, Promises::GUARANTEED_DELIVERY))
+            .await
+            .unwrap();
+        s.send(open(unreliable, Promises::empty())).await.unwrap();
+        for i in 0..100 {
+            s.send(msg(reliable, i)).await.unwrap();
+            s.send(msg(unreliable, i)).await.unwrap();
+        }
+        s.send(ProtocolEvent::Shutdown).await.unwrap();
+        let mut reliable_cnt = 0;
+        let mut unreliable_cnt = 0;
+        loop {
+            match r.recv().await.unwrap() {
+                ProtocolEvent::Message { sid, .. } if sid == reliable => reliable_cnt += 1,
+                ProtocolEvent::Message { .. } => unreliable_cnt += 1,
+                ProtocolEvent::Shutdown => break,
+                _ => {},
+            }
+        }
+        assert_eq!(reliable_cnt, 100);
+        assert!(unreliable_cnt > 20 && unreliable_cnt < 80);
+    }
+
+    #[tokio::test]
+    async fn same_seed_same_drops() {
+        async fn run() -> Vec<u8> {
+            let [(s, _), (_, mut r)] = ac_bound(1000, None);
+            let conditions = NetworkConditions {
+                drop_ratio: 0.3,
+                seed: 7,
+                ..Default::default()
+            };
+            let mut s = SimSendProtocol::new(s, conditions);
+            let sid = STREAM_ID_OFFSET1;
+            s.send(open(sid, Promises::empty())).await.unwrap();
+            for i in 0..50 {
+                s.send(msg(sid, i)).await.unwrap();
+            }
+            s.send(ProtocolEvent::Shutdown).await.unwrap();
+            let mut received = vec![];
+            loop {
+                match r.recv().await.unwrap() {
+                    ProtocolEvent::Message { data, .. } => received.push(data[0]),
+                    ProtocolEvent::Shutdown => break received,
+                    _ => {},
+                }
+            }
+        }
+        assert_eq!(run().await, run().await);
+    }
+
+    #[tokio::test]
+    async fn bandwidth_throttles_messages() {
+        let [(s, _), (_, _r)] = ac_bound(1000, None);
+        let conditions = NetworkConditions {
+            bandwidth: Some(1000),
+            ..Default::default()
+        };
+        let mut s = SimSendProtocol::new(s, conditions);
+        let sid = STREAM_ID_OFFSET1;
+        s.send(open(sid, Promises::ORDERED)).await.unwrap();
+        for i in 0..30 {
+            s.send(msg(sid, i)).await.unwrap();
+        }
+        // 1000 bytes/s allow roughly 5 messages of 100 bytes in 500ms
+        flush_for(&mut s, Duration::from_millis(500)).await;
+        let left = s.delayed.len();
+        assert!(left > 20 && left < 28, "{left} messages left");
+    }
+
+    #[tokio::test]
+    async fn remote_close_waits_for_delayed_messages() {
+        let [(s, _), (_, mut r)] = ac_bound(100, None);
+        let conditions = NetworkConditions {
+            latency: Duration::from_millis(100),
+            ..Default::default()
+        };
+        let mut s = SimSendProtocol::new(s, conditions);
+        let sid = Sid::new(1);
+        s.notify_from_recv(open(sid, Promises::ORDERED));
+        s.send(msg(sid, 1)).await.unwrap();
+        s.notify_from_recv(ProtocolEvent::CloseStream { sid });
+        assert_eq!(s.delayed.len(), 2);
+        flush_for(&mut s, Duration::from_millis(150)).await;
+        assert!(s.delayed.is_empty());
+        assert_eq!(r.recv().await.unwrap(), msg(sid, 1));
+    }
+}
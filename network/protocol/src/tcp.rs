@@ -78,6 +78,10 @@ where
             | Promises::GUARANTEED_DELIVERY
             | Promises::COMPRESSED
     }
+
+    /// returns all promises that this Protocol can take care of, if the drain
+    /// encrypts all data, e.g. via TLS
+    pub fn encrypted_promises() -> Promises { Self::supported_promises() | Promises::ENCRYPTED }
 }
 
 impl<S> TcpRecvProtocol<S>
@@ -1,7 +1,11 @@
-// TODO: quick and dirty which activly waits for an ack!
 /*
 UDP protocol
 
+Every frame is sent in its own datagram, prefixed with a checksum. Corrupted
+datagrams are dropped and recovered like lost ones. Reliable messages
+(ORDERED or GUARANTEED_DELIVERY) are kept by the sender until the receiver
+confirms them with FINISHED, unreliable messages are sent once.
+
 All Good Case:
 S --HEADER--> R
 S --DATA--> R
@@ -34,4 +38,1494 @@ S --STATUS--> R
 S <--MISSING_DATA -- R
 S --DATA--> R
 S <--FINISHED-- R
+
+Each HEADER contains the mid of the event it depends on (`prev`), e.g. the
+previous message on an ORDERED stream. The receiver holds back complete
+events until their `prev` was handed out. CloseStream and Shutdown are only
+handed out after all prior reliable events.
 */
+use crate::{
+    RecvProtocol, SendProtocol, UnreliableDrain, UnreliableSink,
+    error::ProtocolError,
+    event::ProtocolEvent,
+    frame::{InitFrame, OTFrame},
+    handshake::{ReliableDrain, ReliableSink},
+    message::{ALLOC_BLOCK, OTMessage},
+    metrics::{ProtocolMetricCache, RemoveReason},
+    prio::PrioManager,
+    types::{Bandwidth, HIGHEST_PRIO, Mid, Prio, Promises, Sid},
+};
+use async_trait::async_trait;
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use hashbrown::{HashMap, HashSet};
+use std::{
+    collections::{BTreeMap, BTreeSet, VecDeque},
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    time::Duration,
+};
+use tracing::info;
+#[cfg(feature = "trace_pedantic")]
+use tracing::trace;
+
+const FRAME_HEADER: u8 = 1;
+const FRAME_DATA: u8 = 2;
+const FRAME_FINISHED: u8 = 3;
+const FRAME_MISSING_HEADER: u8 = 4;
+const FRAME_STATUS: u8 = 5;
+const FRAME_MISSING_DATA: u8 = 6;
+const FRAME_HANDSHAKE: u8 = 7;
+
+const EVENT_MESSAGE: u8 = 0;
+const EVENT_OPEN_STREAM: u8 = 1;
+const EVENT_CLOSE_STREAM: u8 = 2;
+const EVENT_SHUTDOWN: u8 = 3;
+
+const CHECKSUM_SIZE: usize = 4;
+const CHUNK_SIZE: u64 = OTMessage::FRAME_DATA_SIZE;
+/// Messages which are neither ORDERED nor GUARANTEED_DELIVERY have this bit
+/// set in their [`Mid`]. All other mids are assigned without gaps.
+const UNRELIABLE_MID: Mid = 1 << 63;
+/// `prev` of an event which doesn't depend on any other event
+const NO_PREV: Mid = Mid::MAX;
+/// Time after which a HEADER is resent or a STATUS is requested
+const RESEND_TIMEOUT: Duration = Duration::from_millis(100);
+/// Time without any response after which the remote side is considered gone
+const GIVE_UP_TIMEOUT: Duration = Duration::from_secs(30);
+/// Time DATA is stored without its HEADER before asking for it
+const ORPHAN_TIMEOUT: Duration = Duration::from_millis(10);
+/// The handshake isn't acknowledged, so every frame is sent multiple times
+const HANDSHAKE_REDUNDANCY: usize = 3;
+const MAX_MISSING_CHUNKS: usize = 256;
+/// Max DATA frames stored without a HEADER, also limits datagrams buffered
+/// during the handshake
+const MAX_ORPHANS: usize = 1024;
+/// Max incomplete unreliable messages, older ones are dropped
+const MAX_UNRELIABLE_INCOMING: usize = 64;
+/// Longer messages are a protocol violation
+const MAX_MESSAGE_LENGTH: u64 = 1 << 30;
+/// Max incomplete reliable messages, exceeding it is a protocol violation
+const MAX_RELIABLE_INCOMING: usize = 4096;
+/// Max bytes of all incomplete messages, exceeding it is a protocol violation.
+/// A single message of `MAX_MESSAGE_LENGTH` has to fit.
+const MAX_INCOMING_BYTES: usize = MAX_MESSAGE_LENGTH as usize;
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+enum UdpEvent {
+    Message {
+        sid: Sid,
+        length: u64,
+    },
+    OpenStream {
+        sid: Sid,
+        prio: Prio,
+        promises: Promises,
+        guaranteed_bandwidth: Bandwidth,
+    },
+    CloseStream {
+        sid: Sid,
+    },
+    Shutdown,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+enum UdpFrame {
+    Header {
+        mid: Mid,
+        prev: Mid,
+        event: UdpEvent,
+    },
+    Data {
+        mid: Mid,
+        start: u64,
+        data: Bytes,
+    },
+    Finished {
+        mid: Mid,
+    },
+    MissingHeader {
+        mid: Mid,
+    },
+    Status {
+        mid: Mid,
+    },
+    MissingData {
+        mid: Mid,
+        chunks: Vec<u32>,
+    },
+    Handshake(InitFrame),
+}
+
+/// FNV-1a, only meant to detect corrupted datagrams
+fn checksum(bytes: &[u8]) -> u32 {
+    bytes.iter().fold(0x811c_9dc5, |hash, &b| {
+        (hash ^ b as u32).wrapping_mul(0x0100_0193)
+    })
+}
+
+fn is_reliable(mid: Mid) -> bool { mid & UNRELIABLE_MID == 0 }
+
+/// Returns whether `datagram` starts a UDP handshake. As UDP has no
+/// connection setup of its own, listeners should only create new channels for
+/// such datagrams.
+pub fn is_udp_handshake(datagram: &[u8]) -> bool {
+    datagram.len() > CHECKSUM_SIZE
+        && datagram[CHECKSUM_SIZE] == FRAME_HANDSHAKE
+        && datagram[..CHECKSUM_SIZE] == checksum(&datagram[CHECKSUM_SIZE..]).to_le_bytes()
+}
+
+impl UdpFrame {
+    fn into_datagram(self) -> BytesMut {
+        let mut bytes = BytesMut::with_capacity(CHUNK_SIZE as usize + 32);
+        bytes.put_u32_le(0);
+        match self {
+            UdpFrame::Header { mid, prev, event } => {
+                bytes.put_u8(FRAME_HEADER);
+                bytes.put_u64_le(mid);
+                bytes.put_u64_le(prev);
+                match event {
+                    UdpEvent::Message { sid, length } => {
+                        bytes.put_u8(EVENT_MESSAGE);
+                        sid.to_bytes(&mut bytes);
+                        bytes.put_u64_le(length);
+                    },
+                    UdpEvent::OpenStream {
+                        sid,
+                        prio,
+                        promises,
+                        guaranteed_bandwidth,
+                    } => {
+                        bytes.put_u8(EVENT_OPEN_STREAM);
+                        sid.to_bytes(&mut bytes);
+                        bytes.put_u8(prio);
+                        bytes.put_u8(promises.to_le_bytes()[0]);
+                        bytes.put_u64_le(guaranteed_bandwidth);
+                    },
+                    UdpEvent::CloseStream { sid } => {
+                        bytes.put_u8(EVENT_CLOSE_STREAM);
+                        sid.to_bytes(&mut bytes);
+                    },
+                    UdpEvent::Shutdown => bytes.put_u8(EVENT_SHUTDOWN),
+                }
+            },
+            UdpFrame::Data { mid, start, data } => {
+                bytes.put_u8(FRAME_DATA);
+                bytes.put_u64_le(mid);
+                bytes.put_u64_le(start);
+                bytes.put_u16_le(data.len() as u16);
+                bytes.put_slice(&data);
+            },
+            UdpFrame::Finished { mid } => {
+                bytes.put_u8(FRAME_FINISHED);
+                bytes.put_u64_le(mid);
+            },
+            UdpFrame::MissingHeader { mid } => {
+                bytes.put_u8(FRAME_MISSING_HEADER);
+                bytes.put_u64_le(mid);
+            },
+            UdpFrame::Status { mid } => {
+                bytes.put_u8(FRAME_STATUS);
+                bytes.put_u64_le(mid);
+            },
+            UdpFrame::MissingData { mid, chunks } => {
+                bytes.put_u8(FRAME_MISSING_DATA);
+                bytes.put_u64_le(mid);
+                bytes.put_u16_le(chunks.len() as u16);
+                for chunk in chunks {
+                    bytes.put_u32_le(chunk);
+                }
+            },
+            UdpFrame::Handshake(frame) => {
+                bytes.put_u8(FRAME_HANDSHAKE);
+                frame.write_bytes(&mut bytes);
+            },
+        }
+        let checksum = checksum(&bytes[CHECKSUM_SIZE..]);
+        bytes[..CHECKSUM_SIZE].copy_from_slice(&checksum.to_le_bytes());
+        bytes
+    }
+
+    /// returns `None` for corrupted or incomplete datagrams
+    fn read_datagram(mut bytes: BytesMut) -> Option<Self> {
+        if bytes.len() < CHECKSUM_SIZE + 1 {
+            return None;
+        }
+        if bytes.get_u32_le() != checksum(&bytes) {
+            return None;
+        }
+        let frame = match bytes.get_u8() {
+            FRAME_HEADER => {
+                if bytes.len() < 17 {
+                    return None;
+                }
+                let mid = bytes.get_u64_le();
+                let prev = bytes.get_u64_le();
+                let event = match bytes.get_u8() {
+                    EVENT_MESSAGE if bytes.len() >= 16 => UdpEvent::Message {
+                        sid: Sid::from_bytes(&mut bytes),
+                        length: bytes.get_u64_le(),
+                    },
+                    EVENT_OPEN_STREAM if bytes.len() >= 18 => UdpEvent::OpenStream {
+                        sid: Sid::from_bytes(&mut bytes),
+                        prio: bytes.get_u8(),
+                        promises: Promises::from_bits_truncate(bytes.get_u8()),
+                        guaranteed_bandwidth: bytes.get_u64_le(),
+                    },
+                    EVENT_CLOSE_STREAM if bytes.len() >= 8 => UdpEvent::CloseStream {
+                        sid: Sid::from_bytes(&mut bytes),
+                    },
+                    EVENT_SHUTDOWN => UdpEvent::Shutdown,
+                    _ => return None,
+                };
+                UdpFrame::Header { mid, prev, event }
+            },
+            FRAME_DATA => {
+                if bytes.len() < 18 {
+                    return None;
+                }
+                let mid = bytes.get_u64_le();
+                let start = bytes.get_u64_le();
+                let length = bytes.get_u16_le() as usize;
+                if bytes.len() < length {
+                    return None;
+                }
+                UdpFrame::Data {
+                    mid,
+                    start,
+                    data: bytes.split_to(length).freeze(),
+                }
+            },
+            FRAME_FINISHED if bytes.len() >= 8 => UdpFrame::Finished {
+                mid: bytes.get_u64_le(),
+            },
+            FRAME_MISSING_HEADER if bytes.len() >= 8 => UdpFrame::MissingHeader {
+                mid: bytes.get_u64_le(),
+            },
+            FRAME_STATUS if bytes.len() >= 8 => UdpFrame::Status {
+                mid: bytes.get_u64_le(),
+            },
+            FRAME_MISSING_DATA => {
+                if bytes.len() < 10 {
+                    return None;
+                }
+                let mid = bytes.get_u64_le();
+                let count = bytes.get_u16_le() as usize;
+                if bytes.len() < count * 4 {
+                    return None;
+                }
+                UdpFrame::MissingData {
+                    mid,
+                    chunks: (0..count).map(|_| bytes.get_u32_le()).collect(),
+                }
+            },
+            FRAME_HANDSHAKE => UdpFrame::Handshake(InitFrame::read_frame(&mut bytes)?),
+            _ => return None,
+        };
+        Some(frame)
+    }
+}
+
+/// Everything the [`UdpRecvProtocol`] received that needs to be answered or
+/// acted upon by the [`UdpSendProtocol`]
+#[derive(Debug, Default)]
+struct UdpShared {
+    replies: Vec<UdpFrame>,
+    finished: Vec<Mid>,
+    missing_headers: Vec<Mid>,
+    missing_data: Vec<(Mid, Vec<u32>)>,
+    /// reliable messages with DATA but without a HEADER, and since when the
+    /// sender knows about them
+    orphans: HashMap<Mid, Option<Duration>>,
+}
+
+fn lock(shared: &Mutex<UdpShared>) -> MutexGuard<'_, UdpShared> {
+    shared.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Set of reliable [`Mid`]s, as they are assigned without gaps most of them
+/// are represented by `cursor`
+#[derive(Debug, Default)]
+struct MidSet {
+    /// all mids below are contained
+    cursor: Mid,
+    above: BTreeSet<Mid>,
+}
+
+impl MidSet {
+    fn insert(&mut self, mid: Mid) {
+        if mid >= self.cursor {
+            self.above.insert(mid);
+        }
+        while self.above.remove(&self.cursor) {
+            self.cursor += 1;
+        }
+    }
+
+    fn contains(&self, mid: Mid) -> bool { mid < self.cursor || self.above.contains(&mid) }
+}
+
+#[derive(Debug)]
+struct UdpStream {
+    promises: Promises,
+    /// mid of the OpenStream event, `NO_PREV` if opened by the remote side
+    open: Mid,
+    /// mid of the last reliable event on this stream
+    last: Mid,
+}
+
+/// An event which isn't confirmed (reliable) or completely sent (unreliable)
+#[derive(Debug)]
+struct UdpOutMessage {
+    header: UdpFrame,
+    length: u64,
+    sent: u64,
+    /// only kept for reliable messages, all but the last are `CHUNK_SIZE`
+    chunks: Vec<Bytes>,
+    last_sent: Duration,
+    last_response: Duration,
+}
+
+#[derive(Debug, Default)]
+struct UdpInMessage {
+    /// `prev`, sid and length, known once the HEADER arrived
+    header: Option<(Mid, Sid, u64)>,
+    /// grows with the received chunks, so a HEADER alone doesn't allocate the
+    /// message (anti-ddos)
+    data: BytesMut,
+    /// grows together with `data`
+    received: Vec<bool>,
+    missing: usize,
+    /// DATA which arrived before the HEADER
+    orphans: Vec<(u64, Bytes)>,
+}
+
+impl UdpInMessage {
+    /// returns the number of bytes `data` grew by, `Err` if the chunk doesn't
+    /// fit into the message. Chunks far beyond the received data are dropped,
+    /// they are resent once the remote side asks for the status of the message.
+    fn write(&mut self, start: u64, data: &[u8]) -> Result<usize, ()> {
+        let Some((_, _, length)) = self.header else {
+            return Err(());
+        };
+        let end = start.checked_add(data.len() as u64).ok_or(())?;
+        if data.is_empty()
+            || start % CHUNK_SIZE != 0
+            || end > length
+            || (end != length && data.len() as u64 != CHUNK_SIZE)
+        {
+            return Err(());
+        }
+        let index = (start / CHUNK_SIZE) as usize;
+        if self.received.get(index).copied().unwrap_or(false)
+            || start > (self.data.len() + ALLOC_BLOCK) as u64
+        {
+            return Ok(0);
+        }
+        let grown = (end as usize).saturating_sub(self.data.len());
+        if grown > 0 {
+            self.data.resize(end as usize, 0);
+        }
+        if self.received.len() <= index {
+            self.received.resize(index + 1, false);
+        }
+        self.received[index] = true;
+        self.missing -= 1;
+        self.data[start as usize..end as usize].copy_from_slice(data);
+        Ok(grown)
+    }
+}
+
+/// UDP implementation of [`SendProtocol`]
+///
+/// Create it together with its [`UdpRecvProtocol`] via [`udp_protocols`].
+///
+/// [`SendProtocol`]: crate::SendProtocol
+#[derive(Debug)]
+pub struct UdpSendProtocol<D>
+where
+    D: UnreliableDrain<DataFormat = BytesMut>,
+{
+    shared: Arc<Mutex<UdpShared>>,
+    store: PrioManager,
+    streams: HashMap<Sid, UdpStream>,
+    next_mid: Mid,
+    next_unreliable_mid: Mid,
+    /// `prev` of messages in the store, until their HEADER is sent
+    prevs: HashMap<Mid, Mid>,
+    outgoing: HashMap<Mid, UdpOutMessage>,
+    closing_streams: Vec<Sid>,
+    notify_closing_streams: Vec<Sid>,
+    pending_shutdown: bool,
+    /// sum of all `dt` passed to `flush`
+    clock: Duration,
+    drain: D,
+    metrics: ProtocolMetricCache,
+}
+
+/// UDP implementation of [`RecvProtocol`]
+///
+/// [`RecvProtocol`]: crate::RecvProtocol
+#[derive(Debug)]
+pub struct UdpRecvProtocol<S>
+where
+    S: UnreliableSink<DataFormat = BytesMut>,
+{
+    shared: Arc<Mutex<UdpShared>>,
+    /// datagrams which arrived during the handshake
+    buffered: VecDeque<BytesMut>,
+    last_init_frame: Option<InitFrame>,
+    incoming: HashMap<Mid, UdpInMessage>,
+    /// sum of the `data` of all incoming messages
+    incoming_bytes: usize,
+    orphans: usize,
+    completed: MidSet,
+    delivered: MidSet,
+    /// complete events with their `prev`, waiting to be handed out
+    pending: BTreeMap<Mid, (Mid, ProtocolEvent)>,
+    ready: VecDeque<ProtocolEvent>,
+    closed_streams: HashSet<Sid>,
+    sink: S,
+    metrics: ProtocolMetricCache,
+}
+
+/// Creates both halves of a UDP channel. Every datagram passed to the drain
+/// must be sent as a single UDP datagram, the sink must return single
+/// datagrams as well.
+///
+/// Known limitations:
+///  - the handshake isn't acknowledged, losing all copies of a handshake frame
+///    stalls it.
+///  - the remote side doesn't wait for outstanding acknowledgements after a
+///    `Shutdown`, reliable data lost right before it is not resent.
+pub fn udp_protocols<D, S>(
+    drain: D,
+    sink: S,
+    metrics: ProtocolMetricCache,
+) -> (UdpSendProtocol<D>, UdpRecvProtocol<S>)
+where
+    D: UnreliableDrain<DataFormat = BytesMut>,
+    S: UnreliableSink<DataFormat = BytesMut>,
+{
+    let shared = Arc::new(Mutex::new(UdpShared::default()));
+    (
+        UdpSendProtocol {
+            shared: Arc::clone(&shared),
+            store: PrioManager::new(metrics.clone()),
+            streams: HashMap::new(),
+            next_mid: 0u64,
+            next_unreliable_mid: 0u64,
+            prevs: HashMap::new(),
+            outgoing: HashMap::new(),
+            closing_streams: vec![],
+            notify_closing_streams: vec![],
+            pending_shutdown: false,
+            clock: Duration::ZERO,
+            drain,
+            metrics: metrics.clone(),
+        },
+        UdpRecvProtocol {
+            shared,
+            buffered: VecDeque::new(),
+            last_init_frame: None,
+            incoming: HashMap::new(),
+            incoming_bytes: 0,
+            orphans: 0,
+            completed: MidSet::default(),
+            delivered: MidSet::default(),
+            pending: BTreeMap::new(),
+            ready: VecDeque::new(),
+            closed_streams: HashSet::new(),
+            sink,
+            metrics,
+        },
+    )
+}
+
+impl<D> UdpSendProtocol<D>
+where
+    D: UnreliableDrain<DataFormat = BytesMut>,
+{
+    /// returns all promises that this Protocol can take care of
+    /// If you open a Stream anyway, unsupported promises are ignored.
+    pub fn supported_promises() -> Promises {
+        Promises::ORDERED
+            | Promises::CONSISTENCY
+            | Promises::GUARANTEED_DELIVERY
+            | Promises::COMPRESSED
+    }
+
+    fn reliable_mid(&mut self) -> Mid {
+        let mid = self.next_mid;
+        self.next_mid += 1;
+        mid
+    }
+
+    /// sends an event without data and keeps it until it's confirmed
+    async fn send_event(
+        &mut self,
+        event: UdpEvent,
+        redundancy: usize,
+    ) -> Result<Mid, ProtocolError<D::CustomErr>> {
+        let mid = self.reliable_mid();
+        let header = UdpFrame::Header {
+            mid,
+            prev: NO_PREV,
+            event,
+        };
+        for _ in 0..redundancy {
+            self.drain.send(header.clone().into_datagram()).await?;
+        }
+        self.outgoing.insert(mid, UdpOutMessage {
+            header,
+            length: 0,
+            sent: 0,
+            chunks: vec![],
+            last_sent: self.clock,
+            last_response: self.clock,
+        });
+        Ok(mid)
+    }
+
+    /// collects everything the receiving half asked for
+    fn answer_remote(&mut self, frames: &mut Vec<UdpFrame>) {
+        let mut shared = lock(&self.shared);
+        let shared = &mut *shared;
+        frames.append(&mut shared.replies);
+        for (&mid, since) in shared.orphans.iter_mut() {
+            match since {
+                None => *since = Some(self.clock),
+                Some(t) if self.clock - *t >= ORPHAN_TIMEOUT => {
+                    frames.push(UdpFrame::MissingHeader { mid });
+                    *since = Some(self.clock);
+                },
+                Some(_) => {},
+            }
+        }
+        for mid in shared.finished.drain(..) {
+            self.outgoing.remove(&mid);
+        }
+        for mid in shared.missing_headers.drain(..) {
+            if let Some(msg) = self.outgoing.get_mut(&mid) {
+                msg.last_response = self.clock;
+                frames.push(msg.header.clone());
+            }
+        }
+        for (mid, chunks) in shared.missing_data.drain(..) {
+            if let Some(msg) = self.outgoing.get_mut(&mid) {
+                msg.last_response = self.clock;
+                msg.last_sent = self.clock;
+                for chunk in chunks {
+                    if let Some(data) = msg.chunks.get(chunk as usize) {
+                        frames.push(UdpFrame::Data {
+                            mid,
+                            start: chunk as u64 * CHUNK_SIZE,
+                            data: data.clone(),
+                        });
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<S> UdpRecvProtocol<S>
+where
+    S: UnreliableSink<DataFormat = BytesMut>,
+{
+    fn reply(&self, frame: UdpFrame) { lock(&self.shared).replies.push(frame); }
+
+    fn handle(&mut self, datagram: BytesMut) -> Result<(), ProtocolError<S::CustomErr>> {
+        let Some(frame) = UdpFrame::read_datagram(datagram) else {
+            #[cfg(feature = "trace_pedantic")]
+            trace!("drop corrupted datagram");
+            return Ok(());
+        };
+        #[cfg(feature = "trace_pedantic")]
+        trace!(?frame, "recv");
+        match frame {
+            UdpFrame::Header { mid, prev, event } => self.handle_header(mid, prev, event)?,
+            UdpFrame::Data { mid, start, data } => self.handle_data(mid, start, data)?,
+            UdpFrame::Finished { mid } => lock(&self.shared).finished.push(mid),
+            UdpFrame::MissingHeader { mid } => lock(&self.shared).missing_headers.push(mid),
+            UdpFrame::MissingData { mid, chunks } => {
+                lock(&self.shared).missing_data.push((mid, chunks))
+            },
+            UdpFrame::Status { mid } => self.reply(self.status(mid)),
+            // late duplicates of the handshake
+            UdpFrame::Handshake(_) => {},
+        }
+        Ok(())
+    }
+
+    fn handle_header(
+        &mut self,
+        mid: Mid,
+        prev: Mid,
+        event: UdpEvent,
+    ) -> Result<(), ProtocolError<S::CustomErr>> {
+        if is_reliable(mid) && self.completed.contains(mid) {
+            self.reply(UdpFrame::Finished { mid });
+            return Ok(());
+        }
+        let event = match event {
+            UdpEvent::Message { sid, length } => {
+                if length > MAX_MESSAGE_LENGTH {
+                    info!(
+                        ?mid,
+                        ?length,
+                        "protocol violation by remote side: message too long"
+                    );
+                    return Err(ProtocolError::Violated);
+                }
+                lock(&self.shared).orphans.remove(&mid);
+                self.check_incoming_count(mid)?;
+                let msg = self.incoming.entry(mid).or_default();
+                if msg.header.is_some() {
+                    return Ok(());
+                }
+                self.metrics.rmsg_ib(sid, length);
+                msg.header = Some((prev, sid, length));
+                msg.missing = length.div_ceil(CHUNK_SIZE) as usize;
+                self.orphans -= msg.orphans.len();
+                let mut grown = 0;
+                for (start, data) in std::mem::take(&mut msg.orphans) {
+                    let Ok(bytes) = msg.write(start, &data) else {
+                        info!(
+                            ?mid,
+                            "protocol violation by remote side: Data outside of message"
+                        );
+                        return Err(ProtocolError::Violated);
+                    };
+                    grown += bytes;
+                }
+                self.grow_incoming(mid, grown)?;
+                self.try_complete(mid);
+                if !is_reliable(mid) {
+                    self.prune_unreliable();
+                }
+                return Ok(());
+            },
+            UdpEvent::OpenStream {
+                sid,
+                prio,
+                promises,
+                guaranteed_bandwidth,
+            } => ProtocolEvent::OpenStream {
+                sid,
+                prio: prio.min(HIGHEST_PRIO),
+                promises,
+                guaranteed_bandwidth,
+            },
+            UdpEvent::CloseStream { sid } => ProtocolEvent::CloseStream { sid },
+            UdpEvent::Shutdown => ProtocolEvent::Shutdown,
+        };
+        self.finish(mid, prev, event);
+        Ok(())
+    }
+
+    fn handle_data(
+        &mut self,
+        mid: Mid,
+        start: u64,
+        data: Bytes,
+    ) -> Result<(), ProtocolError<S::CustomErr>> {
+        self.metrics.rdata_frames_b(data.len() as u64);
+        if is_reliable(mid) && self.completed.contains(mid) {
+            self.reply(UdpFrame::Finished { mid });
+            return Ok(());
+        }
+        self.check_incoming_count(mid)?;
+        let msg = self.incoming.entry(mid).or_default();
+        if msg.header.is_some() {
+            let Ok(grown) = msg.write(start, &data) else {
+                info!(
+                    ?mid,
+                    "protocol violation by remote side: Data outside of message"
+                );
+                return Err(ProtocolError::Violated);
+            };
+            self.grow_incoming(mid, grown)?;
+            self.try_complete(mid);
+        } else {
+            if self.orphans < MAX_ORPHANS {
+                msg.orphans.push((start, data));
+                self.orphans += 1;
+            }
+            if is_reliable(mid) {
+                lock(&self.shared).orphans.entry(mid).or_insert(None);
+            }
+        }
+        if !is_reliable(mid) {
+            self.prune_unreliable();
+        }
+        Ok(())
+    }
+
+    fn status(&self, mid: Mid) -> UdpFrame {
+        if is_reliable(mid) && self.completed.contains(mid) {
+            return UdpFrame::Finished { mid };
+        }
+        let header = self
+            .incoming
+            .get(&mid)
+            .and_then(|msg| Some((msg, msg.header?.2)));
+        match header {
+            Some((msg, length)) => UdpFrame::MissingData {
+                mid,
+                chunks: (0..length.div_ceil(CHUNK_SIZE) as u32)
+                    .filter(|&i| !msg.received.get(i as usize).copied().unwrap_or(false))
+                    .take(MAX_MISSING_CHUNKS)
+                    .collect(),
+            },
+            _ => UdpFrame::MissingHeader { mid },
+        }
+    }
+
+    /// Reliable messages can't be dropped, so the remote side may only leave a
+    /// limited number of them incomplete
+    fn check_incoming_count(&self, mid: Mid) -> Result<(), ProtocolError<S::CustomErr>> {
+        if is_reliable(mid)
+            && !self.incoming.contains_key(&mid)
+            && self
+                .incoming
+                .keys()
+                .filter(|&&mid| is_reliable(mid))
+                .count()
+                >= MAX_RELIABLE_INCOMING
+        {
+            info!(
+                ?mid,
+                "protocol violation by remote side: too many incomplete messages"
+            );
+            return Err(ProtocolError::Violated);
+        }
+        Ok(())
+    }
+
+    fn grow_incoming(&mut self, mid: Mid, grown: usize) -> Result<(), ProtocolError<S::CustomErr>> {
+        self.incoming_bytes += grown;
+        if self.incoming_bytes > MAX_INCOMING_BYTES {
+            info!(
+                ?mid,
+                "protocol violation by remote side: too much incomplete data"
+            );
+            return Err(ProtocolError::Violated);
+        }
+        Ok(())
+    }
+
+    fn try_complete(&mut self, mid: Mid) {
+        if !matches!(
+            self.incoming.get(&mid),
+            Some(UdpInMessage {
+                header: Some(_),
+                missing: 0,
+                ..
+            })
+        ) {
+            return;
+        }
+        if let Some(UdpInMessage {
+            header: Some((prev, sid, _)),
+            data,
+            ..
+        }) = self.incoming.remove(&mid)
+        {
+            self.incoming_bytes -= data.len();
+            self.metrics
+                .rmsg_ob(sid, RemoveReason::Finished, data.len() as u64);
+            self.finish(mid, prev, ProtocolEvent::Message {
+                sid,
+                data: data.freeze(),
+            });
+        }
+    }
+
+    /// Unreliable messages are never resent, so incomplete ones are dropped
+    /// once there are too many of them
+    fn prune_unreliable(&mut self) {
+        let mut unreliable: Vec<Mid> = self
+            .incoming
+            .keys()
+            .copied()
+            .filter(|&mid| !is_reliable(mid))
+            .collect();
+        if unreliable.len() <= MAX_UNRELIABLE_INCOMING {
+            return;
+        }
+        unreliable.sort_unstable();
+        for mid in &unreliable[..unreliable.len() - MAX_UNRELIABLE_INCOMING] {
+            if let Some(msg) = self.incoming.remove(mid) {
+                self.orphans -= msg.orphans.len();
+                self.incoming_bytes -= msg.data.len();
+                if let Some((_, sid, length)) = msg.header {
+                    self.metrics.rmsg_ob(sid, RemoveReason::Dropped, length);
+                }
+            }
+        }
+    }
+
+    fn finish(&mut self, mid: Mid, prev: Mid, event: ProtocolEvent) {
+        if is_reliable(mid) {
+            self.completed.insert(mid);
+            self.reply(UdpFrame::Finished { mid });
+        }
+        self.pending.insert(mid, (prev, event));
+
+        let deliverable =
+            |delivered: &MidSet, mid: Mid, prev: Mid, event: &ProtocolEvent| match event {
+                ProtocolEvent::Message { .. } => prev == NO_PREV || delivered.contains(prev),
+                ProtocolEvent::OpenStream { .. } => true,
+                ProtocolEvent::CloseStream { .. } | ProtocolEvent::Shutdown => {
+                    delivered.cursor >= mid
+                },
+            };
+        loop {
+            let next = self
+                .pending
+                .iter()
+                .find(|(mid, (prev, event))| deliverable(&self.delivered, **mid, *prev, event))
+                // the remote side won't resend anything after a Shutdown, so don't wait for it
+                .or_else(|| {
+                    self.pending
+                        .iter()
+                        .find(|(_, (_, event))| matches!(event, ProtocolEvent::Shutdown))
+                })
+                .map(|(mid, _)| *mid);
+            let Some((mid, (_, event))) = next.and_then(|mid| self.pending.remove_entry(&mid))
+            else {
+                break;
+            };
+            if is_reliable(mid) {
+                self.delivered.insert(mid);
+            }
+            match &event {
+                ProtocolEvent::Message { sid, .. } if self.closed_streams.contains(sid) => continue,
+                ProtocolEvent::CloseStream { sid } => {
+                    self.closed_streams.insert(*sid);
+                },
+                _ => {},
+            }
+            self.ready.push_back(event);
+        }
+    }
+}
+
+#[async_trait]
+impl<D> SendProtocol for UdpSendProtocol<D>
+where
+    D: UnreliableDrain<DataFormat = BytesMut>,
+{
+    type CustomErr = D::CustomErr;
+
+    fn notify_from_recv(&mut self, event: ProtocolEvent) {
+        match event {
+            ProtocolEvent::OpenStream {
+                sid,
+                prio,
+                promises,
+                guaranteed_bandwidth,
+            } => {
+                self.store
+                    .open_stream(sid, prio, promises, guaranteed_bandwidth);
+                self.streams.insert(sid, UdpStream {
+                    promises,
+                    open: NO_PREV,
+                    last: NO_PREV,
+                });
+            },
+            ProtocolEvent::CloseStream { sid } => {
+                self.streams.remove(&sid);
+                if !self.store.try_close_stream(sid) {
+                    #[cfg(feature = "trace_pedantic")]
+                    trace!(?sid, "hold back notify close stream");
+                    self.notify_closing_streams.push(sid);
+                }
+            },
+            _ => {},
+        }
+    }
+
+    async fn send(&mut self, event: ProtocolEvent) -> Result<(), ProtocolError<Self::CustomErr>> {
+        #[cfg(feature = "trace_pedantic")]
+        trace!(?event, "send");
+        match event {
+            ProtocolEvent::OpenStream {
+                sid,
+                prio,
+                promises,
+                guaranteed_bandwidth,
+            } => {
+                self.store
+                    .open_stream(sid, prio, promises, guaranteed_bandwidth);
+                let event = UdpEvent::OpenStream {
+                    sid,
+                    prio,
+                    promises,
+                    guaranteed_bandwidth,
+                };
+                let mid = self.send_event(event, 1).await?;
+                self.streams.insert(sid, UdpStream {
+                    promises,
+                    open: mid,
+                    last: mid,
+                });
+            },
+            ProtocolEvent::CloseStream { sid } => {
+                self.streams.remove(&sid);
+                if self.store.try_close_stream(sid) {
+                    self.send_event(UdpEvent::CloseStream { sid }, 1).await?;
+                } else {
+                    #[cfg(feature = "trace_pedantic")]
+                    trace!(?sid, "hold back close stream");
+                    self.closing_streams.push(sid);
+                }
+            },
+            ProtocolEvent::Shutdown => {
+                if self.store.is_empty() {
+                    // nothing is resent after a Shutdown
+                    self.send_event(UdpEvent::Shutdown, HANDSHAKE_REDUNDANCY)
+                        .await?;
+                } else {
+                    #[cfg(feature = "trace_pedantic")]
+                    trace!("hold back shutdown");
+                    self.pending_shutdown = true;
+                }
+            },
+            ProtocolEvent::Message { data, sid } => {
+                self.metrics.smsg_ib(sid, data.len() as u64);
+                let (mid, prev) = match self.streams.get_mut(&sid) {
+                    Some(stream) if stream.promises.contains(Promises::ORDERED) => {
+                        let mid = self.next_mid;
+                        self.next_mid += 1;
+                        (mid, std::mem::replace(&mut stream.last, mid))
+                    },
+                    Some(stream) if stream.promises.contains(Promises::GUARANTEED_DELIVERY) => {
+                        let mid = self.next_mid;
+                        self.next_mid += 1;
+                        (mid, stream.open)
+                    },
+                    stream => {
+                        let mid = UNRELIABLE_MID | self.next_unreliable_mid;
+                        self.next_unreliable_mid += 1;
+                        (mid, stream.map_or(NO_PREV, |stream| stream.open))
+                    },
+                };
+                self.prevs.insert(mid, prev);
+                self.store.add(data, mid, sid);
+            },
+        }
+        Ok(())
+    }
+
+    async fn flush(
+        &mut self,
+        bandwidth: Bandwidth,
+        dt: Duration,
+    ) -> Result</* actual */ Bandwidth, ProtocolError<Self::CustomErr>> {
+        self.clock += dt;
+        let mut frames = vec![];
+        self.answer_remote(&mut frames);
+
+        let (grabbed, _) = self.store.grab(bandwidth, dt);
+        let mut data_frames = 0;
+        let mut data_bandwidth = 0;
+        for (_, frame) in grabbed {
+            match frame {
+                OTFrame::DataHeader { mid, sid, length } => {
+                    let header = UdpFrame::Header {
+                        mid,
+                        prev: self.prevs.remove(&mid).unwrap_or(NO_PREV),
+                        event: UdpEvent::Message { sid, length },
+                    };
+                    if is_reliable(mid) || length > 0 {
+                        self.outgoing.insert(mid, UdpOutMessage {
+                            header: header.clone(),
+                            length,
+                            sent: 0,
+                            chunks: vec![],
+                            last_sent: self.clock,
+                            last_response: self.clock,
+                        });
+                    }
+                    frames.push(header);
+                },
+                OTFrame::Data { mid, data } => {
+                    data_frames += 1;
+                    data_bandwidth += data.len();
+                    if let Some(msg) = self.outgoing.get_mut(&mid) {
+                        let start = msg.sent;
+                        msg.sent += data.len() as u64;
+                        msg.last_sent = self.clock;
+                        msg.last_response = self.clock;
+                        if is_reliable(mid) {
+                            msg.chunks.push(data.clone());
+                        } else if msg.sent >= msg.length {
+                            self.outgoing.remove(&mid);
+                        }
+                        frames.push(UdpFrame::Data { mid, start, data });
+                    }
+                },
+                // the store only contains messages
+                _ => {},
+            }
+        }
+
+        for (&mid, msg) in self.outgoing.iter_mut() {
+            if msg.sent < msg.length || self.clock - msg.last_sent < RESEND_TIMEOUT {
+                continue;
+            }
+            if self.clock - msg.last_response >= GIVE_UP_TIMEOUT {
+                info!(?mid, "remote side stopped responding");
+                return Err(ProtocolError::Violated);
+            }
+            msg.last_sent = self.clock;
+            frames.push(if msg.length == 0 {
+                msg.header.clone()
+            } else {
+                UdpFrame::Status { mid }
+            });
+        }
+
+        for frame in frames {
+            self.drain.send(frame.into_datagram()).await?;
+        }
+        self.metrics
+            .sdata_frames_b(data_frames, data_bandwidth as u64);
+
+        let (closed, closing) = std::mem::take(&mut self.closing_streams)
+            .into_iter()
+            .partition::<Vec<_>, _>(|&sid| self.store.try_close_stream(sid));
+        self.closing_streams = closing;
+        for sid in closed {
+            #[cfg(feature = "trace_pedantic")]
+            trace!(?sid, "close stream, as it's now empty");
+            self.send_event(UdpEvent::CloseStream { sid }, 1).await?;
+        }
+        self.notify_closing_streams
+            .retain(|&sid| !self.store.try_close_stream(sid));
+
+        if self.pending_shutdown && self.store.is_empty() {
+            #[cfg(feature = "trace_pedantic")]
+            trace!("shutdown, as it's now empty");
+            self.send_event(UdpEvent::Shutdown, HANDSHAKE_REDUNDANCY)
+                .await?;
+            self.pending_shutdown = false;
+        }
+        Ok(data_bandwidth as u64)
+    }
+}
+
+#[async_trait]
+impl<S> RecvProtocol for UdpRecvProtocol<S>
+where
+    S: UnreliableSink<DataFormat = BytesMut>,
+{
+    type CustomErr = S::CustomErr;
+
+    async fn recv(&mut self) -> Result<ProtocolEvent, ProtocolError<Self::CustomErr>> {
+        loop {
+            if let Some(event) = self.ready.pop_front() {
+                return Ok(event);
+            }
+            let datagram = match self.buffered.pop_front() {
+                Some(datagram) => datagram,
+                None => self.sink.recv().await?,
+            };
+            self.handle(datagram)?;
+        }
+    }
+}
+
+#[async_trait]
+impl<D> ReliableDrain for UdpSendProtocol<D>
+where
+    D: UnreliableDrain<DataFormat = BytesMut>,
+{
+    type CustomErr = D::CustomErr;
+
+    async fn send(&mut self, frame: InitFrame) -> Result<(), ProtocolError<Self::CustomErr>> {
+        let datagram = UdpFrame::Handshake(frame).into_datagram();
+        for _ in 0..HANDSHAKE_REDUNDANCY {
+            self.drain.send(datagram.clone()).await?;
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<S> ReliableSink for UdpRecvProtocol<S>
+where
+    S: UnreliableSink<DataFormat = BytesMut>,
+{
+    type CustomErr = S::CustomErr;
+
+    async fn recv(&mut self) -> Result<InitFrame, ProtocolError<Self::CustomErr>> {
+        loop {
+            let datagram = self.sink.recv().await?;
+            match UdpFrame::read_datagram(datagram.clone()) {
+                Some(UdpFrame::Handshake(frame)) => {
+                    // every frame of the handshake differs from the previous one
+                    if self.last_init_frame.as_ref() != Some(&frame) {
+                        self.last_init_frame = Some(frame.clone());
+                        return Ok(frame);
+                    }
+                },
+                // the remote side might already be done with the handshake
+                Some(_) if self.buffered.len() < MAX_ORPHANS => self.buffered.push_back(datagram),
+                _ => {},
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_utils {
+    //UDP protocol based on Channel
+    use super::*;
+    use crate::metrics::{ProtocolMetricCache, ProtocolMetrics};
+    use async_channel::*;
+
+    pub struct UdpDrain {
+        pub sender: Sender<BytesMut>,
+        pub drop_ratio: f32,
+    }
+
+    pub struct UdpSink {
+        pub receiver: Receiver<BytesMut>,
+    }
+
+    /// emulate Udp protocol on Channels
+    pub fn udp_bound(
+        cap: usize,
+        drop_ratio: f32,
+        metrics: Option<ProtocolMetricCache>,
+    ) -> [(UdpSendProtocol<UdpDrain>, UdpRecvProtocol<UdpSink>); 2] {
+        let (s1, r1) = bounded(cap);
+        let (s2, r2) = bounded(cap);
+        let m = metrics.unwrap_or_else(|| {
+            ProtocolMetricCache::new("udp", Arc::new(ProtocolMetrics::new().unwrap()))
+        });
+        [
+            udp_protocols(
+                UdpDrain {
+                    sender: s1,
+                    drop_ratio,
+                },
+                UdpSink { receiver: r2 },
+                m.clone(),
+            ),
+            udp_protocols(
+                UdpDrain {
+                    sender: s2,
+                    drop_ratio,
+                },
+                UdpSink { receiver: r1 },
+                m,
+            ),
+        ]
+    }
+
+    #[async_trait]
+    impl UnreliableDrain for UdpDrain {
+        type CustomErr = ();
+        type DataFormat = BytesMut;
+
+        async fn send(
+            &mut self,
+            data: Self::DataFormat,
+        ) -> Result<(), ProtocolError<Self::CustomErr>> {
+            use rand::RngExt;
+            if rand::rng().random::<f32>() < self.drop_ratio {
+                return Ok(());
+            }
+            self.sender
+                .send(data)
+                .await
+                .map_err(|_| ProtocolError::Custom(()))
+        }
+    }
+
+    #[async_trait]
+    impl UnreliableSink for UdpSink {
+        type CustomErr = ();
+        type DataFormat = BytesMut;
+
+        async fn recv(&mut self) -> Result<Self::DataFormat, ProtocolError<Self::CustomErr>> {
+            self.receiver
+                .recv()
+                .await
+                .map_err(|_| ProtocolError::Custom(()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        CHUNK_SIZE, MAX_INCOMING_BYTES, MAX_MESSAGE_LENGTH, MAX_RELIABLE_INCOMING, NO_PREV,
+        UdpEvent, UdpFrame, is_udp_handshake,
+    };
+    use crate::{
+        InitProtocol, ProtocolError, ProtocolEvent, RecvProtocol, SendProtocol,
+        frame::InitFrame,
+        message::ALLOC_BLOCK,
+        types::{Pid, Promises, STREAM_ID_OFFSET1, STREAM_ID_OFFSET2, Sid},
+        udp::test_utils::*,
+    };
+    use bytes::Bytes;
+    use std::time::Duration;
+
+    fn open_event(sid: u64, promises: Promises) -> ProtocolEvent {
+        ProtocolEvent::OpenStream {
+            sid: Sid::new(sid),
+            prio: 3u8,
+            promises,
+            guaranteed_bandwidth: 1_000_000,
+        }
+    }
+
+    fn header(mid: u64, length: u64) -> bytes::BytesMut {
+        UdpFrame::Header {
+            mid,
+            prev: NO_PREV,
+            event: UdpEvent::Message {
+                sid: Sid::new(10),
+                length,
+            },
+        }
+        .into_datagram()
+    }
+
+    fn chunk(mid: u64, index: u64) -> bytes::BytesMut {
+        UdpFrame::Data {
+            mid,
+            start: index * CHUNK_SIZE,
+            data: Bytes::from(vec![1u8; CHUNK_SIZE as usize]),
+        }
+        .into_datagram()
+    }
+
+    #[tokio::test]
+    async fn handshake_all_good() {
+        let [mut p1, mut p2] = udp_bound(30, 0.0, None);
+        let r1 = tokio::spawn(async move { p1.initialize(true, Pid::fake(2), 1337).await });
+        let r2 = tokio::spawn(async move { p2.initialize(false, Pid::fake(3), 42).await });
+        let (r1, r2) = tokio::join!(r1, r2);
+        assert_eq!(r1.unwrap(), Ok((Pid::fake(3), STREAM_ID_OFFSET1, 42)));
+        assert_eq!(r2.unwrap(), Ok((Pid::fake(2), STREAM_ID_OFFSET2, 1337)));
+    }
+
+    #[test]
+    fn frames_survive_datagram() {
+        let frames = [
+            UdpFrame::Data {
+                mid: 4,
+                start: 1400,
+                data: Bytes::from(&[7u8; 300][..]),
+            },
+            UdpFrame::MissingData {
+                mid: 5,
+                chunks: vec![1, 3, 7],
+            },
+            UdpFrame::Handshake(InitFrame::Init {
+                pid: Pid::fake(2),
+                secret: 42,
+            }),
+        ];
+        for frame in frames {
+            let datagram = frame.clone().into_datagram();
+            assert_eq!(
+                is_udp_handshake(&datagram),
+                matches!(frame, UdpFrame::Handshake(_))
+            );
+            assert_eq!(UdpFrame::read_datagram(datagram), Some(frame));
+        }
+    }
+
+    #[test]
+    fn corrupted_datagram_is_dropped() {
+        let mut datagram = UdpFrame::Finished { mid: 3 }.into_datagram();
+        datagram[6] ^= 0b100;
+        assert_eq!(UdpFrame::read_datagram(datagram), None);
+    }
+
+    #[tokio::test]
+    async fn open_stream() {
+        let [p1, p2] = udp_bound(10, 0.0, None);
+        let (mut s, mut r) = (p1.0, p2.1);
+        let event = open_event(10, Promises::ORDERED);
+        s.send(event.clone()).await.unwrap();
+        let e = r.recv().await.unwrap();
+        assert_eq!(event, e);
+    }
+
+    #[tokio::test]
+    async fn send_long_msg() {
+        let [p1, p2] = udp_bound(100, 0.0, None);
+        let (mut s, mut r) = (p1.0, p2.1);
+        s.send(open_event(10, Promises::ORDERED)).await.unwrap();
+        let _ = r.recv().await.unwrap();
+        let event = ProtocolEvent::Message {
+            sid: Sid::new(10),
+            data: Bytes::from(&[99u8; 50_000][..]),
+        };
+        s.send(event.clone()).await.unwrap();
+        s.flush(1_000_000, Duration::from_secs(1)).await.unwrap();
+        let e = r.recv().await.unwrap();
+        assert_eq!(event, e);
+    }
+
+    #[tokio::test]
+    async fn msg_finishes_after_close() {
+        let [p1, p2] = udp_bound(100, 0.0, None);
+        let (mut s, mut r) = (p1.0, p2.1);
+        s.send(open_event(10, Promises::ORDERED)).await.unwrap();
+        let _ = r.recv().await.unwrap();
+        let event = ProtocolEvent::Message {
+            sid: Sid::new(10),
+            data: Bytes::from(&[99u8; 5_000][..]),
+        };
+        s.send(event.clone()).await.unwrap();
+        let close = ProtocolEvent::CloseStream { sid: Sid::new(10) };
+        s.send(close.clone()).await.unwrap();
+        s.flush(1_000_000, Duration::from_secs(1)).await.unwrap();
+        assert_eq!(r.recv().await.unwrap(), event);
+        assert_eq!(r.recv().await.unwrap(), close);
+    }
+
+    #[tokio::test]
+    async fn msg_finishes_after_shutdown() {
+        let [p1, p2] = udp_bound(100, 0.0, None);
+        let (mut s, mut r) = (p1.0, p2.1);
+        s.send(open_event(10, Promises::ORDERED)).await.unwrap();
+        let _ = r.recv().await.unwrap();
+        let event = ProtocolEvent::Message {
+            sid: Sid::new(10),
+            data: Bytes::from(&[99u8; 5_000][..]),
+        };
+        s.send(event.clone()).await.unwrap();
+        s.send(ProtocolEvent::Shutdown).await.unwrap();
+        s.send(ProtocolEvent::CloseStream { sid: Sid::new(10) })
+            .await
+            .unwrap();
+        s.flush(1_000_000, Duration::from_secs(1)).await.unwrap();
+        assert_eq!(r.recv().await.unwrap(), event);
+        assert!(matches!(
+            r.recv().await.unwrap(),
+            ProtocolEvent::CloseStream { .. }
+        ));
+        assert_eq!(r.recv().await.unwrap(), ProtocolEvent::Shutdown);
+    }
+
+    #[tokio::test]
+    async fn lossy_ordered_delivery() {
+        const MSG_COUNT: u64 = 20;
+        let [(mut s1, mut r1), (mut s2, mut r2)] = udp_bound(10_000, 0.3, None);
+        let promises = Promises::ORDERED | Promises::GUARANTEED_DELIVERY;
+        s1.send(open_event(10, promises)).await.unwrap();
+        for i in 0..MSG_COUNT {
+            s1.send(ProtocolEvent::Message {
+                sid: Sid::new(10),
+                data: Bytes::from(vec![i as u8; 3_000 + i as usize]),
+            })
+            .await
+            .unwrap();
+        }
+        // r1 only receives acknowledgements, which are handled internally
+        let acks = tokio::spawn(async move { r1.recv().await });
+        let recv = tokio::spawn(async move {
+            let mut events = vec![];
+            while events.len() < MSG_COUNT as usize + 1 {
+                events.push(r2.recv().await.unwrap());
+            }
+            events
+        });
+        for _ in 0..10_000 {
+            if recv.is_finished() {
+                break;
+            }
+            s1.flush(1_000_000, Duration::from_millis(10))
+                .await
+                .unwrap();
+            s2.flush(1_000_000, Duration::from_millis(10))
+                .await
+                .unwrap();
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+        let events = recv.await.unwrap();
+        assert_eq!(events[0], open_event(10, promises));
+        for (i, event) in events[1..].iter().enumerate() {
+            assert_eq!(event, &ProtocolEvent::Message {
+                sid: Sid::new(10),
+                data: Bytes::from(vec![i as u8; 3_000 + i]),
+            });
+        }
+        acks.abort();
+    }
+
+    #[test]
+    fn oversized_header_allocates_incrementally() {
+        let [_, (_, mut r)] = udp_bound(10, 0.0, None);
+        r.handle(header(1, MAX_MESSAGE_LENGTH)).unwrap();
+        assert_eq!(r.incoming[&1].data.capacity(), 0);
+        assert_eq!(r.incoming_bytes, 0);
+        r.handle(chunk(1, 2)).unwrap();
+        assert_eq!(r.incoming_bytes, 3 * CHUNK_SIZE as usize);
+        // chunks far beyond the received data are dropped, not allocated
+        r.handle(chunk(1, ALLOC_BLOCK as u64 / CHUNK_SIZE + 4))
+            .unwrap();
+        assert_eq!(r.incoming_bytes, 3 * CHUNK_SIZE as usize);
+        assert!(r.incoming[&1].data.capacity() < ALLOC_BLOCK);
+    }
+
+    #[test]
+    fn repeated_headers_are_bounded() {
+        let [_, (_, mut r)] = udp_bound(10, 0.0, None);
+        for mid in 0..MAX_RELIABLE_INCOMING as u64 {
+            r.handle(header(mid, MAX_MESSAGE_LENGTH)).unwrap();
+        }
+        // repeating a known header is fine
+        r.handle(header(0, MAX_MESSAGE_LENGTH)).unwrap();
+        assert_eq!(r.incoming_bytes, 0);
+        assert!(
+            r.incoming
+                .values()
+                .all(|msg| msg.data.capacity() == 0 && msg.received.is_empty())
+        );
+        assert_eq!(
+            r.handle(header(MAX_RELIABLE_INCOMING as u64, MAX_MESSAGE_LENGTH)),
+            Err(ProtocolError::Violated)
+        );
+    }
+
+    #[test]
+    fn incomplete_data_is_bounded() {
+        let [_, (_, mut r)] = udp_bound(10, 0.0, None);
+        r.handle(header(0, 2 * CHUNK_SIZE)).unwrap();
+        r.handle(chunk(0, 0)).unwrap();
+        r.handle(chunk(0, 1)).unwrap();
+        // completed messages no longer count
+        assert_eq!(r.incoming_bytes, 0);
+        r.handle(header(1, MAX_MESSAGE_LENGTH)).unwrap();
+        r.incoming_bytes = MAX_INCOMING_BYTES - CHUNK_SIZE as usize;
+        r.handle(chunk(1, 0)).unwrap();
+        assert_eq!(r.handle(chunk(1, 1)), Err(ProtocolError::Violated));
+    }
+
+    #[tokio::test]
+    async fn unreliable_msg_is_not_resent() {
+        let [p1, p2] = udp_bound(100, 0.0, None);
+        let (mut s, mut r) = (p1.0, p2.1);
+        s.send(open_event(10, Promises::empty())).await.unwrap();
+        let _ = r.recv().await.unwrap();
+        let event = ProtocolEvent::Message {
+            sid: Sid::new(10),
+            data: Bytes::from(&[99u8; 500][..]),
+        };
+        s.send(event.clone()).await.unwrap();
+        s.flush(1_000_000, Duration::from_secs(1)).await.unwrap();
+        assert_eq!(r.recv().await.unwrap(), event);
+        // only the OpenStream is kept until it's confirmed
+        assert_eq!(s.outgoing.len(), 1);
+    }
+}
@@ -8,7 +8,7 @@ use bytes::Bytes;
 use hashbrown::HashMap;
 #[cfg(feature = "compression")]
 use lz_fear::raw::DecodeError;
-use network_protocol::{Bandwidth, InitProtocolError, Pid, Prio, Promises, Sid};
+use network_protocol::{Bandwidth, InitProtocolError, NetworkConditions, Pid, Prio, Promises, Sid};
 #[cfg(feature = "metrics")]
 use prometheus::Registry;
 use serde::{Serialize, de::DeserializeOwned};
@@ -29,10 +29,14 @@ use tracing::*;
 
 type A2sDisconnect = Arc<Mutex<Option<mpsc::UnboundedSender<(Pid, S2bShutdownBparticipant)>>>>;
 
-/// Represents a Tcp, Quic, Udp or Mpsc connection address
+/// Represents a Tcp, Tls, Quic, Udp or Mpsc connection address
 #[derive(Clone, Debug)]
 pub enum ConnectAddr {
     Tcp(SocketAddr),
+    /// TCP wrapped in TLS, the `String` is the name the server certificate is
+    /// validated against
+    #[cfg(feature = "tls")]
+    Tls(SocketAddr, Arc<rustls::ClientConfig>, String),
     Udp(SocketAddr),
     #[cfg(feature = "quic")]
     Quic(SocketAddr, quinn::ClientConfig, String),
@@ -40,11 +44,13 @@ pub enum ConnectAddr {
 }
 
 impl ConnectAddr {
-    /// Returns the `Some` if the protocol is TCP or QUIC and `None` if the
-    /// protocol is a local channel (mpsc).
+    /// Returns the `Some` if the protocol is TCP, TLS, UDP or QUIC and `None`
+    /// if the protocol is a local channel (mpsc).
     pub fn socket_addr(&self) -> Option<SocketAddr> {
         match self {
             Self::Tcp(addr) => Some(*addr),
+            #[cfg(feature = "tls")]
+            Self::Tls(addr, _, _) => Some(*addr),
             Self::Udp(addr) => Some(*addr),
             Self::Mpsc(_) => None,
             #[cfg(feature = "quic")]
@@ -53,10 +59,14 @@ impl ConnectAddr {
     }
 }
 
-/// Represents a Tcp, Quic, Udp or Mpsc listen address
+/// Represents a Tcp, Tls, Quic, Udp or Mpsc listen address
 #[derive(Clone, Debug)]
 pub enum ListenAddr {
     Tcp(SocketAddr),
+    /// TCP wrapped in TLS, channels accepted here are reported as
+    /// [`ConnectAddr::Tcp`]
+    #[cfg(feature = "tls")]
+    Tls(SocketAddr, Arc<rustls::ServerConfig>),
     Udp(SocketAddr),
     #[cfg(feature = "quic")]
     Quic(SocketAddr, quinn::ServerConfig),
@@ -214,6 +224,7 @@ pub struct Network {
     connect_sender: mpsc::UnboundedSender<A2sConnect>,
     connected_receiver: mpsc::UnboundedReceiver<Participant>,
     shutdown_network_s: Option<oneshot::Sender<oneshot::Sender<()>>>,
+    network_conditions_s: watch::Sender<Option<NetworkConditions>>,
 }
 
 impl Network {
@@ -299,12 +310,14 @@ impl Network {
         let p = participant_id;
         let span = info_span!("network", ?p);
         span.in_scope(|| trace!("Starting Network"));
+        let (network_conditions_s, network_conditions_r) = watch::channel(None);
         let (scheduler, listen_sender, connect_sender, connected_receiver, shutdown_sender) =
             Scheduler::new(
                 participant_id,
                 #[cfg(feature = "metrics")]
                 registry,
                 output_limit,
+                network_conditions_r,
             );
         let participant_disconnect_sender = Arc::new(Mutex::new(HashMap::new()));
         let (shutdown_network_s, shutdown_network_r) = oneshot::channel();
@@ -330,9 +343,36 @@ impl Network {
             connect_sender,
             connected_receiver,
             shutdown_network_s: Some(shutdown_network_s),
+            network_conditions_s,
         }
     }
 
+    /// Simulates bad network conditions, e.g. latency, packet loss or a
+    /// bandwidth limit, on all channels that are created afterwards. Only
+    /// data sent by this `Network` is affected, `None` disables the
+    /// simulation again.
+    ///
+    /// This is meant for testing, e.g. client prediction or chunk streaming,
+    /// and works with every protocol.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use std::time::Duration;
+    /// use tokio::runtime::Runtime;
+    /// use veloren_network::{Network, NetworkConditions, Pid};
+    ///
+    /// let runtime = Runtime::new().unwrap();
+    /// let network = Network::new(Pid::new(), &runtime);
+    /// network.set_network_conditions(Some(NetworkConditions {
+    ///     latency: Duration::from_millis(100),
+    ///     jitter: Duration::from_millis(20),
+    ///     ..Default::default()
+    /// }));
+    /// ```
+    pub fn set_network_conditions(&self, conditions: Option<NetworkConditions>) {
+        self.network_conditions_s.send_replace(conditions);
+    }
+
     /// starts listening on an [`ListenAddr`].
     /// When the method returns the `Network` is ready to listen for incoming
     /// connections OR has returned a [`NetworkError`] (e.g. port already used).
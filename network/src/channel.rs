@@ -4,12 +4,15 @@ use bytes::BytesMut;
 use futures_util::FutureExt;
 use hashbrown::HashMap;
 use network_protocol::{
-    Bandwidth, Cid, InitProtocolError, MpscMsg, MpscRecvProtocol, MpscSendProtocol, Pid,
-    ProtocolError, ProtocolEvent, ProtocolMetricCache, ProtocolMetrics, Sid, TcpRecvProtocol,
-    TcpSendProtocol, UnreliableDrain, UnreliableSink,
+    Bandwidth, Cid, InitProtocolError, MpscMsg, MpscRecvProtocol, MpscSendProtocol,
+    NetworkConditions, Pid, ProtocolError, ProtocolEvent, ProtocolMetricCache, ProtocolMetrics,
+    Sid, SimSendProtocol, TcpRecvProtocol, TcpSendProtocol, UdpRecvProtocol, UdpSendProtocol,
+    UnreliableDrain, UnreliableSink,
 };
 #[cfg(feature = "quic")]
 use network_protocol::{QuicDataFormat, QuicDataFormatStream, QuicRecvProtocol, QuicSendProtocol};
+#[cfg(feature = "tls")]
+use rustls::pki_types::ServerName;
 use std::{
     io,
     net::SocketAddr,
@@ -19,6 +22,8 @@ use std::{
     },
     time::Duration,
 };
+#[cfg(feature = "tls")]
+use tokio::io::{ReadHalf, WriteHalf};
 use tokio::{
     io::{AsyncReadExt, AsyncWriteExt},
     net,
@@ -26,28 +31,55 @@ use tokio::{
     select,
     sync::{Mutex, mpsc, oneshot},
 };
+#[cfg(feature = "tls")]
+use tokio_rustls::{TlsAcceptor, TlsConnector};
 use tracing::{error, info, trace, warn};
 
+#[cfg(feature = "tls")]
+type TlsStream = tokio_rustls::TlsStream<net::TcpStream>;
+
 #[derive(Debug)]
 pub(crate) enum Protocols {
     Tcp((TcpSendProtocol<TcpDrain>, TcpRecvProtocol<TcpSink>)),
     Mpsc((MpscSendProtocol<MpscDrain>, MpscRecvProtocol<MpscSink>)),
+    Udp((UdpSendProtocol<UdpDrain>, UdpRecvProtocol<UdpSink>)),
+    #[cfg(feature = "tls")]
+    Tls((TcpSendProtocol<TlsDrain>, TcpRecvProtocol<TlsSink>)),
     #[cfg(feature = "quic")]
     Quic((QuicSendProtocol<QuicDrain>, QuicRecvProtocol<QuicSink>)),
+    /// sending side is delayed and throttled according to the conditions
+    Sim(Box<Protocols>, NetworkConditions),
 }
 
 #[derive(Debug)]
 pub(crate) enum SendProtocols {
     Tcp(TcpSendProtocol<TcpDrain>),
     Mpsc(MpscSendProtocol<MpscDrain>),
+    Udp(UdpSendProtocol<UdpDrain>),
+    #[cfg(feature = "tls")]
+    Tls(TcpSendProtocol<TlsDrain>),
     #[cfg(feature = "quic")]
     Quic(QuicSendProtocol<QuicDrain>),
+    Sim(Box<SimSendProtocol<SendProtocols>>),
+}
+
+impl SendProtocols {
+    /// the actual protocol, looking through a simulation wrapper
+    pub(crate) fn unsimulated(&self) -> &SendProtocols {
+        match self {
+            SendProtocols::Sim(s) => s.inner().unsimulated(),
+            p => p,
+        }
+    }
 }
 
 #[derive(Debug)]
 pub(crate) enum RecvProtocols {
     Tcp(TcpRecvProtocol<TcpSink>),
     Mpsc(MpscRecvProtocol<MpscSink>),
+    Udp(UdpRecvProtocol<UdpSink>),
+    #[cfg(feature = "tls")]
+    Tls(TcpRecvProtocol<TlsSink>),
     #[cfg(feature = "quic")]
     Quic(QuicRecvProtocol<QuicSink>),
 }
@@ -83,6 +115,13 @@ fn anonymize_addr(addr: &SocketAddr) -> String {
 
 impl Protocols {
     const MPSC_CHANNEL_BOUND: usize = 1000;
+    /// don't let a client that never finishes the handshake block a task
+    #[cfg(feature = "tls")]
+    const TLS_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+    const UDP_CHANNEL_BOUND: usize = 1000;
+    const UDP_CLEANUP_INTERVAL: Duration = Duration::from_secs(1);
+    /// larger than any datagram of the UDP protocol
+    const UDP_RECV_BUFFER_SIZE: usize = 2048;
 
     pub(crate) async fn with_tcp_connect(
         addr: SocketAddr,
@@ -102,13 +141,9 @@ impl Protocols {
         Ok(Self::new_tcp(stream, metrics))
     }
 
-    pub(crate) async fn with_tcp_listen(
-        addr: SocketAddr,
-        cids: Arc<AtomicU64>,
-        metrics: Arc<ProtocolMetrics>,
-        s2s_stop_listening_r: oneshot::Receiver<()>,
-        c2s_protocol_s: mpsc::UnboundedSender<C2sProtocol>,
-    ) -> io::Result<()> {
+    /// Binds a [`net::TcpListener`] that is shared by the Tcp and Tls
+    /// protocols
+    fn tcp_listener(addr: SocketAddr) -> io::Result<net::TcpListener> {
         use socket2::{Domain, Socket, Type};
         let domain = Domain::for_address(addr);
         let socket2_socket = Socket::new(domain, Type::STREAM, None)?;
@@ -131,7 +166,17 @@ impl Protocols {
         socket2_socket.bind(&socket2_addr)?;
         socket2_socket.listen(1024)?;
         let std_listener: std::net::TcpListener = socket2_socket.into();
-        let listener = net::TcpListener::from_std(std_listener)?;
+        net::TcpListener::from_std(std_listener)
+    }
+
+    pub(crate) async fn with_tcp_listen(
+        addr: SocketAddr,
+        cids: Arc<AtomicU64>,
+        metrics: Arc<ProtocolMetrics>,
+        s2s_stop_listening_r: oneshot::Receiver<()>,
+        c2s_protocol_s: mpsc::UnboundedSender<C2sProtocol>,
+    ) -> io::Result<()> {
+        let listener = Self::tcp_listener(addr)?;
         trace!(?addr, "Tcp Listener bound");
         let mut end_receiver = s2s_stop_listening_r.fuse();
         tokio::spawn(async move {
@@ -182,6 +227,129 @@ impl Protocols {
         Protocols::Tcp((sp, rp))
     }
 
+    #[cfg(feature = "tls")]
+    pub(crate) async fn with_tls_connect(
+        addr: SocketAddr,
+        config: Arc<rustls::ClientConfig>,
+        name: String,
+        metrics: ProtocolMetricCache,
+    ) -> Result<Self, NetworkConnectError> {
+        let server_name = ServerName::try_from(name)
+            .map_err(|e| NetworkConnectError::Io(io::Error::new(io::ErrorKind::InvalidInput, e)))?;
+        let stream = net::TcpStream::connect(addr)
+            .await
+            .and_then(|s| {
+                s.set_nodelay(true)?;
+                Ok(s)
+            })
+            .map_err(NetworkConnectError::Io)?;
+        info!(
+            "Connecting Tls to: {}",
+            stream.peer_addr().map_err(NetworkConnectError::Io)?
+        );
+        let stream = TlsConnector::from(config)
+            .connect(server_name, stream)
+            .await
+            .map_err(|e| {
+                trace!(?e, "tls handshake failed");
+                NetworkConnectError::Io(e)
+            })?;
+        Ok(Self::new_tls(stream.into(), metrics))
+    }
+
+    #[cfg(feature = "tls")]
+    pub(crate) async fn with_tls_listen(
+        addr: SocketAddr,
+        server_config: Arc<rustls::ServerConfig>,
+        cids: Arc<AtomicU64>,
+        metrics: Arc<ProtocolMetrics>,
+        s2s_stop_listening_r: oneshot::Receiver<()>,
+        c2s_protocol_s: mpsc::UnboundedSender<C2sProtocol>,
+    ) -> io::Result<()> {
+        let listener = Self::tcp_listener(addr)?;
+        trace!(?addr, "Tls Listener bound");
+        let acceptor = TlsAcceptor::from(server_config);
+        let mut end_receiver = s2s_stop_listening_r.fuse();
+        tokio::spawn(async move {
+            while let Some(data) = select! {
+                    next = listener.accept().fuse() => Some(next),
+                    _ = &mut end_receiver => None,
+            } {
+                let (stream, remote_addr) = match data {
+                    Ok((s, p)) => (s, p),
+                    Err(e) => {
+                        trace!(?e, "TcpStream Error, ignoring connection attempt");
+                        continue;
+                    },
+                };
+                if let Err(e) = stream.set_nodelay(true) {
+                    warn!(
+                        ?e,
+                        "Failed to set TCP_NODELAY, client may have degraded latency"
+                    );
+                }
+                // the handshake is done in its own task, so a slow client can't stall the
+                // accept loop
+                let acceptor = acceptor.clone();
+                let cids = Arc::clone(&cids);
+                let metrics = Arc::clone(&metrics);
+                let c2s_protocol_s = c2s_protocol_s.clone();
+                tokio::spawn(async move {
+                    let stream = match tokio::time::timeout(
+                        Self::TLS_HANDSHAKE_TIMEOUT,
+                        acceptor.accept(stream),
+                    )
+                    .await
+                    {
+                        Ok(Ok(s)) => s,
+                        Ok(Err(e)) => {
+                            tracing::debug!(
+                                ?e,
+                                remote_addr = anonymize_addr(&remote_addr),
+                                "tls handshake failed, skipping connection attempt"
+                            );
+                            return;
+                        },
+                        Err(_) => {
+                            tracing::debug!(
+                                remote_addr = anonymize_addr(&remote_addr),
+                                "tls handshake timed out, skipping connection attempt"
+                            );
+                            return;
+                        },
+                    };
+                    let cid = cids.fetch_add(1, Ordering::Relaxed);
+                    info!(
+                        remote_addr = anonymize_addr(&remote_addr),
+                        ?cid,
+                        "Accepting Tls from"
+                    );
+                    let metrics = ProtocolMetricCache::new(&cid.to_string(), metrics);
+                    let _ = c2s_protocol_s.send((
+                        Self::new_tls(stream.into(), metrics),
+                        ConnectAddr::Tcp(remote_addr),
+                        cid,
+                    ));
+                });
+            }
+        });
+        Ok(())
+    }
+
+    #[cfg(feature = "tls")]
+    pub(crate) fn new_tls(stream: TlsStream, metrics: ProtocolMetricCache) -> Self {
+        let (r, w) = tokio::io::split(stream);
+        let sp = TcpSendProtocol::new(TlsDrain { half: w }, metrics.clone());
+        let rp = TcpRecvProtocol::new(
+            TlsSink {
+                half: r,
+                buffer: BytesMut::new(),
+            },
+            metrics,
+        );
+        Protocols::Tls((sp, rp))
+    }
+
     pub(crate) async fn with_mpsc_connect(
         addr: u64,
         metrics: ProtocolMetricCache,
@@ -264,6 +432,135 @@ impl Protocols {
         Protocols::Mpsc((sp, rp))
     }
 
+    pub(crate) async fn with_udp_connect(
+        addr: SocketAddr,
+        metrics: ProtocolMetricCache,
+    ) -> Result<Self, NetworkConnectError> {
+        use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+        let bindsock = match addr {
+            SocketAddr::V4(_) => SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 0),
+            SocketAddr::V6(_) => SocketAddr::new(IpAddr::V6(Ipv6Addr::UNSPECIFIED), 0),
+        };
+        let socket = net::UdpSocket::bind(bindsock)
+            .await
+            .map_err(NetworkConnectError::Io)?;
+        socket
+            .connect(addr)
+            .await
+            .map_err(NetworkConnectError::Io)?;
+        info!("Connecting Udp to: {}", &addr);
+        let socket = Arc::new(socket);
+        let (datagram_s, datagram_r) = mpsc::channel(Self::UDP_CHANNEL_BOUND);
+        let reader = Arc::clone(&socket);
+        tokio::spawn(async move {
+            let mut buffer = vec![0u8; Self::UDP_RECV_BUFFER_SIZE];
+            loop {
+                let n = select! {
+                    next = reader.recv(&mut buffer) => match next {
+                        Ok(n) => n,
+                        Err(e) => {
+                            trace!(?e, "UdpSocket Error, stopping");
+                            break;
+                        },
+                    },
+                    _ = datagram_s.closed() => break,
+                };
+                if datagram_s.send(BytesMut::from(&buffer[..n])).await.is_err() {
+                    break;
+                }
+            }
+        });
+        Ok(Self::new_udp(socket, None, datagram_r, metrics))
+    }
+
+    pub(crate) async fn with_udp_listen(
+        addr: SocketAddr,
+        cids: Arc<AtomicU64>,
+        metrics: Arc<ProtocolMetrics>,
+        s2s_stop_listening_r: oneshot::Receiver<()>,
+        c2s_protocol_s: mpsc::UnboundedSender<C2sProtocol>,
+    ) -> io::Result<()> {
+        let socket = Arc::new(net::UdpSocket::bind(addr).await?);
+        trace!(?addr, "Udp Listener bound");
+        let mut end_receiver = s2s_stop_listening_r.fuse();
+        tokio::spawn(async move {
+            // all channels share the socket, so datagrams are dispatched by their sender
+            let mut connections: HashMap<SocketAddr, mpsc::Sender<BytesMut>> = HashMap::new();
+            let mut listening = true;
+            let mut cleanup = tokio::time::interval(Self::UDP_CLEANUP_INTERVAL);
+            let mut buffer = vec![0u8; Self::UDP_RECV_BUFFER_SIZE];
+            loop {
+                let next = select! {
+                    next = socket.recv_from(&mut buffer) => Some(next),
+                    _ = &mut end_receiver, if listening => {
+                        listening = false;
+                        None
+                    },
+                    _ = cleanup.tick() => {
+                        connections.retain(|_, sender| !sender.is_closed());
+                        None
+                    },
+                };
+                match next {
+                    Some(Ok((n, remote_addr))) => {
+                        let datagram = BytesMut::from(&buffer[..n]);
+                        if let Some(sender) = connections
+                            .get(&remote_addr)
+                            .filter(|sender| !sender.is_closed())
+                        {
+                            // drop what the channel can't keep up with, like the OS would
+                            let _ = sender.try_send(datagram);
+                        } else if listening && network_protocol::is_udp_handshake(&datagram) {
+                            let (datagram_s, datagram_r) = mpsc::channel(Self::UDP_CHANNEL_BOUND);
+                            let _ = datagram_s.try_send(datagram);
+                            connections.insert(remote_addr, datagram_s);
+                            let cid = cids.fetch_add(1, Ordering::Relaxed);
+                            info!(
+                                remote_addr = anonymize_addr(&remote_addr),
+                                ?cid,
+                                "Accepting Udp from"
+                            );
+                            let metrics =
+                                ProtocolMetricCache::new(&cid.to_string(), Arc::clone(&metrics));
+                            let _ = c2s_protocol_s.send((
+                                Self::new_udp(
+                                    Arc::clone(&socket),
+                                    Some(remote_addr),
+                                    datagram_r,
+                                    metrics,
+                                ),
+                                ConnectAddr::Udp(remote_addr),
+                                cid,
+                            ));
+                        }
+                    },
+                    Some(Err(e)) => trace!(?e, "UdpSocket Error, ignoring datagram"),
+                    None => {},
+                }
+                if !listening && connections.is_empty() {
+                    break;
+                }
+            }
+            trace!(?addr, "Udp Listener stopped");
+        });
+        Ok(())
+    }
+
+    pub(crate) fn new_udp(
+        socket: Arc<net::UdpSocket>,
+        remote: Option<SocketAddr>,
+        receiver: mpsc::Receiver<BytesMut>,
+        metrics: ProtocolMetricCache,
+    ) -> Self {
+        let (sp, rp) = network_protocol::udp_protocols(
+            UdpDrain { socket, remote },
+            UdpSink { receiver },
+            metrics,
+        );
+        Protocols::Udp((sp, rp))
+    }
+
     #[cfg(feature = "quic")]
     pub(crate) async fn with_quic_connect(
         addr: SocketAddr,
@@ -399,8 +696,18 @@ impl Protocols {
         match self {
             Protocols::Tcp((s, r)) => (SendProtocols::Tcp(s), RecvProtocols::Tcp(r)),
             Protocols::Mpsc((s, r)) => (SendProtocols::Mpsc(s), RecvProtocols::Mpsc(r)),
+            Protocols::Udp((s, r)) => (SendProtocols::Udp(s), RecvProtocols::Udp(r)),
+            #[cfg(feature = "tls")]
+            Protocols::Tls((s, r)) => (SendProtocols::Tls(s), RecvProtocols::Tls(r)),
             #[cfg(feature = "quic")]
             Protocols::Quic((s, r)) => (SendProtocols::Quic(s), RecvProtocols::Quic(r)),
+            Protocols::Sim(p, conditions) => {
+                let (s, r) = p.split();
+                (
+                    SendProtocols::Sim(Box::new(SimSendProtocol::new(s, conditions))),
+                    r,
+                )
+            },
         }
     }
 }
@@ -418,8 +725,12 @@ impl network_protocol::InitProtocol for Protocols {
         match self {
             Protocols::Tcp(p) => p.initialize(initializer, local_pid, secret).await,
             Protocols::Mpsc(p) => p.initialize(initializer, local_pid, secret).await,
+            Protocols::Udp(p) => p.initialize(initializer, local_pid, secret).await,
+            #[cfg(feature = "tls")]
+            Protocols::Tls(p) => p.initialize(initializer, local_pid, secret).await,
             #[cfg(feature = "quic")]
             Protocols::Quic(p) => p.initialize(initializer, local_pid, secret).await,
+            Protocols::Sim(p, _) => p.initialize(initializer, local_pid, secret).await,
         }
     }
 }
@@ -432,8 +743,12 @@ impl network_protocol::SendProtocol for SendProtocols {
         match self {
             SendProtocols::Tcp(s) => s.notify_from_recv(event),
             SendProtocols::Mpsc(s) => s.notify_from_recv(event),
+            SendProtocols::Udp(s) => s.notify_from_recv(event),
+            #[cfg(feature = "tls")]
+            SendProtocols::Tls(s) => s.notify_from_recv(event),
             #[cfg(feature = "quic")]
             SendProtocols::Quic(s) => s.notify_from_recv(event),
+            SendProtocols::Sim(s) => s.notify_from_recv(event),
         }
     }
 
@@ -441,8 +756,12 @@ impl network_protocol::SendProtocol for SendProtocols {
         match self {
             SendProtocols::Tcp(s) => s.send(event).await,
             SendProtocols::Mpsc(s) => s.send(event).await,
+            SendProtocols::Udp(s) => s.send(event).await,
+            #[cfg(feature = "tls")]
+            SendProtocols::Tls(s) => s.send(event).await,
             #[cfg(feature = "quic")]
             SendProtocols::Quic(s) => s.send(event).await,
+            SendProtocols::Sim(s) => s.send(event).await,
         }
     }
 
@@ -454,8 +773,12 @@ impl network_protocol::SendProtocol for SendProtocols {
         match self {
             SendProtocols::Tcp(s) => s.flush(bandwidth, dt).await,
             SendProtocols::Mpsc(s) => s.flush(bandwidth, dt).await,
+            SendProtocols::Udp(s) => s.flush(bandwidth, dt).await,
+            #[cfg(feature = "tls")]
+            SendProtocols::Tls(s) => s.flush(bandwidth, dt).await,
             #[cfg(feature = "quic")]
             SendProtocols::Quic(s) => s.flush(bandwidth, dt).await,
+            SendProtocols::Sim(s) => s.flush(bandwidth, dt).await,
         }
     }
 }
@@ -468,6 +791,9 @@ impl network_protocol::RecvProtocol for RecvProtocols {
         match self {
             RecvProtocols::Tcp(r) => r.recv().await,
             RecvProtocols::Mpsc(r) => r.recv().await,
+            RecvProtocols::Udp(r) => r.recv().await,
+            #[cfg(feature = "tls")]
+            RecvProtocols::Tls(r) => r.recv().await,
             #[cfg(feature = "quic")]
             RecvProtocols::Quic(r) => r.recv().await,
         }
@@ -546,6 +872,103 @@ impl UnreliableSink for TcpSink {
     }
 }
 
+///////////////////////////////////////
+// TLS
+#[cfg(feature = "tls")]
+#[derive(Debug)]
+pub struct TlsDrain {
+    half: WriteHalf<TlsStream>,
+}
+
+#[cfg(feature = "tls")]
+#[derive(Debug)]
+pub struct TlsSink {
+    half: ReadHalf<TlsStream>,
+    buffer: BytesMut,
+}
+
+#[cfg(feature = "tls")]
+#[async_trait]
+impl UnreliableDrain for TlsDrain {
+    type CustomErr = ProtocolsError;
+    type DataFormat = BytesMut;
+
+    async fn send(&mut self, data: Self::DataFormat) -> Result<(), ProtocolError<Self::CustomErr>> {
+        // the tls layer buffers records internally, flush them out right away
+        async {
+            self.half.write_all(&data).await?;
+            self.half.flush().await
+        }
+        .await
+        .map_err(|e| ProtocolError::Custom(ProtocolsError::Tcp(e)))
+    }
+}
+
+#[cfg(feature = "tls")]
+#[async_trait]
+impl UnreliableSink for TlsSink {
+    type CustomErr = ProtocolsError;
+    type DataFormat = BytesMut;
+
+    async fn recv(&mut self) -> Result<Self::DataFormat, ProtocolError<Self::CustomErr>> {
+        if self.buffer.capacity() < 1500 {
+            self.buffer.reserve(1500 * 4); // reserve multiple, so that we alloc less often
+        }
+        match self.half.read_buf(&mut self.buffer).await {
+            Ok(0) => Err(ProtocolError::Custom(ProtocolsError::Tcp(io::Error::new(
+                io::ErrorKind::BrokenPipe,
+                "read returned 0 bytes",
+            )))),
+            Ok(_) => Ok(self.buffer.split()),
+            Err(e) => Err(ProtocolError::Custom(ProtocolsError::Tcp(e))),
+        }
+    }
+}
+
+///////////////////////////////////////
+// UDP
+#[derive(Debug)]
+pub struct UdpDrain {
+    socket: Arc<net::UdpSocket>,
+    /// `None` if the socket is connected
+    remote: Option<SocketAddr>,
+}
+
+#[derive(Debug)]
+pub struct UdpSink {
+    receiver: mpsc::Receiver<BytesMut>,
+}
+
+#[async_trait]
+impl UnreliableDrain for UdpDrain {
+    type CustomErr = ProtocolsError;
+    type DataFormat = BytesMut;
+
+    async fn send(&mut self, data: Self::DataFormat) -> Result<(), ProtocolError<Self::CustomErr>> {
+        match self.remote {
+            Some(remote) => self.socket.send_to(&data, remote).await,
+            None => self.socket.send(&data).await,
+        }
+        .map(|_| ())
+        .map_err(|e| ProtocolError::Custom(ProtocolsError::Udp(e)))
+    }
+}
+
+#[async_trait]
+impl UnreliableSink for UdpSink {
+    type CustomErr = ProtocolsError;
+    type DataFormat = BytesMut;
+
+    async fn recv(&mut self) -> Result<Self::DataFormat, ProtocolError<Self::CustomErr>> {
+        self.receiver.recv().await.ok_or_else(|| {
+            ProtocolError::Custom(ProtocolsError::Udp(io::Error::new(
+                io::ErrorKind::BrokenPipe,
+                "udp socket closed",
+            )))
+        })
+    }
+}
+
 ///////////////////////////////////////
 // MPSC
 #[derive(Debug)]
@@ -206,21 +206,42 @@ impl BParticipant {
 
     fn best_protocol(all: &SortedVec<Cid, SendProtocols>, promises: Promises) -> Option<Cid> {
         // check for mpsc
-        all.data.iter().find(|(_, p)| matches!(p, SendProtocols::Mpsc(_))).map(|(c, _)| *c).or_else(
+        all.data.iter().find(|(_, p)| matches!(p.unsimulated(), SendProtocols::Mpsc(_))).map(|(c, _)| *c).or_else(
             || if network_protocol::TcpSendProtocol::<crate::channel::TcpDrain>::supported_promises()
                 .contains(promises)
             {
                 // check for tcp
-                all.data.iter().find(|(_, p)| matches!(p, SendProtocols::Tcp(_))).map(|(c, _)| *c)
+                all.data.iter().find(|(_, p)| matches!(p.unsimulated(), SendProtocols::Tcp(_))).map(|(c, _)| *c)
             } else {
                 None
             }
+        ).or_else(
+            // check for tcp wrapped in tls, which also satisfies ENCRYPTED
+            || {
+                #[cfg(feature = "tls")]
+                {
+                    if network_protocol::TcpSendProtocol::<crate::channel::TlsDrain>::encrypted_promises()
+                        .contains(promises)
+                    {
+                        return all.data.iter().find(|(_, p)| matches!(p.unsimulated(), SendProtocols::Tls(_))).map(|(c, _)| *c);
+                    }
+                }
+                None
+            }
         ).or_else(
             // check for quic, TODO: evaluate to order quic BEFORE tcp once its stable
             || if network_protocol::QuicSendProtocol::<crate::channel::QuicDrain>::supported_promises()
                 .contains(promises)
             {
-                all.data.iter().find(|(_, p)| matches!(p, SendProtocols::Quic(_))).map(|(c, _)| *c)
+                all.data.iter().find(|(_, p)| matches!(p.unsimulated(), SendProtocols::Quic(_))).map(|(c, _)| *c)
+            } else {
+                None
+            }
+        ).or_else(
+            || if network_protocol::UdpSendProtocol::<crate::channel::UdpDrain>::supported_promises()
+                .contains(promises)
+            {
+                all.data.iter().find(|(_, p)| matches!(p.unsimulated(), SendProtocols::Udp(_))).map(|(c, _)| *c)
             } else {
                 None
             }
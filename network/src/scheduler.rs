@@ -6,7 +6,7 @@ use crate::{
 };
 use futures_util::StreamExt;
 use hashbrown::HashMap;
-use network_protocol::{Cid, Pid, ProtocolMetricCache, ProtocolMetrics};
+use network_protocol::{Cid, NetworkConditions, Pid, ProtocolMetricCache, ProtocolMetrics};
 #[cfg(feature = "metrics")]
 use prometheus::Registry;
 use rand::RngExt;
@@ -19,7 +19,7 @@ use std::{
 };
 use tokio::{
     io,
-    sync::{Mutex, mpsc, oneshot},
+    sync::{Mutex, mpsc, oneshot, watch},
 };
 use tokio_stream::wrappers::UnboundedReceiverStream;
 use tracing::*;
@@ -77,6 +77,7 @@ pub struct Scheduler {
     metrics: Arc<NetworkMetrics>,
     protocol_metrics: Arc<ProtocolMetrics>,
     output_limit: usize,
+    network_conditions_r: watch::Receiver<Option<NetworkConditions>>,
 }
 
 impl Scheduler {
@@ -84,6 +85,7 @@ impl Scheduler {
         local_pid: Pid,
         #[cfg(feature = "metrics")] registry: Option<&Registry>,
         output_limit: usize,
+        network_conditions_r: watch::Receiver<Option<NetworkConditions>>,
     ) -> (
         Self,
         mpsc::UnboundedSender<A2sListen>,
@@ -140,6 +142,7 @@ impl Scheduler {
                 metrics,
                 protocol_metrics,
                 output_limit,
+                network_conditions_r,
             },
             a2s_listen_s,
             a2s_connect_s,
@@ -200,6 +203,18 @@ impl Scheduler {
                             )
                             .await
                         },
+                        #[cfg(feature = "tls")]
+                        ListenAddr::Tls(addr, ref server_config) => {
+                            Protocols::with_tls_listen(
+                                addr,
+                                Arc::clone(server_config),
+                                cids,
+                                metrics,
+                                s2s_stop_listening_r,
+                                c2s_protocol_s,
+                            )
+                            .await
+                        },
                         #[cfg(feature = "quic")]
                         ListenAddr::Quic(addr, ref server_config) => {
                             Protocols::with_quic_listen(
@@ -222,7 +237,16 @@ impl Scheduler {
                             )
                             .await
                         },
-                        _ => unimplemented!(),
+                        ListenAddr::Udp(addr) => {
+                            Protocols::with_udp_listen(
+                                addr,
+                                cids,
+                                metrics,
+                                s2s_stop_listening_r,
+                                c2s_protocol_s,
+                            )
+                            .await
+                        },
                     };
                     let _ = s2a_listen_result_s.send(res);
 
@@ -244,12 +268,16 @@ impl Scheduler {
             self.metrics.connect_request(&addr);
             let protocol = match addr.clone() {
                 ConnectAddr::Tcp(addr) => Protocols::with_tcp_connect(addr, metrics).await,
+                #[cfg(feature = "tls")]
+                ConnectAddr::Tls(addr, ref config, name) => {
+                    Protocols::with_tls_connect(addr, Arc::clone(config), name, metrics).await
+                },
                 #[cfg(feature = "quic")]
                 ConnectAddr::Quic(addr, ref config, name) => {
                     Protocols::with_quic_connect(addr, config.clone(), name, metrics).await
                 },
                 ConnectAddr::Mpsc(addr) => Protocols::with_mpsc_connect(addr, metrics).await,
-                _ => unimplemented!(),
+                ConnectAddr::Udp(addr) => Protocols::with_udp_connect(addr, metrics).await,
             };
             let protocol = match protocol {
                 Ok(p) => p,
@@ -399,6 +427,7 @@ impl Scheduler {
         let local_pid = self.local_pid;
         let local_secret = self.local_secret;
         let output_limit = self.output_limit;
+        let network_conditions = self.network_conditions_r.borrow().clone();
         // this is necessary for UDP to work at all and to remove code duplication
         tokio::spawn(
             async move {
@@ -415,6 +444,13 @@ impl Scheduler {
                             ?pid,
                             "Detected that my channel is ready!, activating it :)"
                         );
+                        let protocol = match network_conditions {
+                            Some(conditions) => {
+                                debug!(?cid, ?conditions, "simulating network conditions");
+                                Protocols::Sim(Box::new(protocol), conditions)
+                            },
+                            None => protocol,
+                        };
                         let mut participants = participants.lock().await;
                         if !participants.contains_key(&pid) {
                             debug!(?cid, "New participant connected via a channel");
@@ -128,6 +128,46 @@ pub fn quic() -> (ListenAddr, ConnectAddr) {
     )
 }
 
+#[allow(dead_code)]
+pub fn tls() -> (ListenAddr, ConnectAddr) {
+    lazy_static! {
+        static ref PORTS: AtomicU16 = AtomicU16::new(6000);
+    }
+    const LOCALHOST: &str = "localhost";
+    let port = PORTS.fetch_add(1, Ordering::Relaxed);
+
+    trace!("generating self-signed certificate");
+    let cert = rcgen::generate_simple_self_signed(vec![LOCALHOST.into()]).unwrap();
+    let key = cert.signing_key.serialize_der();
+    let cert = cert.cert.der();
+
+    let key = PrivateKeyDer::from(PrivatePkcs8KeyDer::from(key));
+
+    let mut root_store = rustls::RootCertStore::empty();
+    root_store
+        .add(cert.clone())
+        .expect("cannot add cert to rootstore");
+
+    let server_config = rustls::ServerConfig::builder()
+        .with_no_client_auth()
+        .with_single_cert(vec![cert.clone()], key)
+        .expect("Server Config Cert/Key failed");
+    let client_config = rustls::ClientConfig::builder()
+        .with_root_certificates(root_store)
+        .with_no_client_auth();
+    (
+        ListenAddr::Tls(
+            SocketAddr::from((Ipv4Addr::LOCALHOST, port)),
+            Arc::new(server_config),
+        ),
+        ConnectAddr::Tls(
+            SocketAddr::from((Ipv4Addr::LOCALHOST, port)),
+            Arc::new(client_config),
+            LOCALHOST.to_owned(),
+        ),
+    )
+}
+
 #[allow(dead_code)]
 pub fn udp() -> (ListenAddr, ConnectAddr) {
     let port = UDP_PORTS.fetch_add(1, Ordering::Relaxed);
@@ -1,10 +1,17 @@
-use std::sync::Arc;
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
 use tokio::runtime::Runtime;
 use veloren_network::{NetworkError, StreamError};
 mod helper;
-use helper::{SLEEP_EXTERNAL, SLEEP_INTERNAL, mpsc, network_participant_stream, quic, tcp, udp};
+use helper::{
+    SLEEP_EXTERNAL, SLEEP_INTERNAL, mpsc, network_participant_stream, quic, tcp, tls, udp,
+};
 use std::io::ErrorKind;
-use veloren_network::{ConnectAddr, ListenAddr, Network, ParticipantEvent, Pid, Promises};
+use veloren_network::{
+    ConnectAddr, ListenAddr, Network, NetworkConditions, ParticipantEvent, Pid, Promises,
+};
 
 #[test]
 fn stream_simple() {
@@ -90,7 +97,55 @@ fn stream_simple_quic_3msg() {
 }
 
 #[test]
-#[ignore]
+fn stream_simple_tls() {
+    let (_, _) = helper::setup(false, 0);
+    let (r, _n_a, _p_a, s1_a, _n_b, _p_b, mut s1_b) = network_participant_stream(tls());
+
+    s1_a.send("Hello World").unwrap();
+    assert_eq!(r.block_on(s1_b.recv()), Ok("Hello World".to_string()));
+    drop((_n_a, _n_b, _p_a, _p_b)); //clean teardown
+}
+
+#[test]
+fn stream_simple_tls_3msg() {
+    let (_, _) = helper::setup(false, 0);
+    let (r, _n_a, _p_a, s1_a, _n_b, _p_b, mut s1_b) = network_participant_stream(tls());
+
+    s1_a.send("Hello World").unwrap();
+    s1_a.send(1337).unwrap();
+    assert_eq!(r.block_on(s1_b.recv()), Ok("Hello World".to_string()));
+    assert_eq!(r.block_on(s1_b.recv()), Ok(1337));
+    s1_a.send("3rdMessage").unwrap();
+    assert_eq!(r.block_on(s1_b.recv()), Ok("3rdMessage".to_string()));
+    drop((_n_a, _n_b, _p_a, _p_b)); //clean teardown
+}
+
+#[test]
+fn stream_encrypted_tls() {
+    let (_, _) = helper::setup(false, 0);
+    let r = Arc::new(Runtime::new().unwrap());
+    let (addr_a, addr_b) = tls();
+    let mut n_a = Network::new(Pid::fake(0), &r);
+    let n_b = Network::new(Pid::fake(1), &r);
+    r.block_on(async {
+        n_a.listen(addr_a).await.unwrap();
+        let mut p1_b = n_b.connect(addr_b).await.unwrap();
+        let p1_a = n_a.connected().await.unwrap();
+
+        let s1_a = p1_a
+            .open(4, Promises::ORDERED | Promises::ENCRYPTED, 0)
+            .await
+            .unwrap();
+        let mut s1_b = p1_b.opened().await.unwrap();
+
+        s1_a.send("Hello World").unwrap();
+        assert_eq!(s1_b.recv().await, Ok("Hello World".to_string()));
+        drop((p1_a, p1_b)); //clean teardown
+    });
+    drop((n_a, n_b));
+}
+
+#[test]
 fn stream_simple_udp() {
     let (_, _) = helper::setup(false, 0);
     let (r, _n_a, _p_a, s1_a, _n_b, _p_b, mut s1_b) = network_participant_stream(udp());
@@ -101,7 +156,6 @@ fn stream_simple_udp() {
 }
 
 #[test]
-#[ignore]
 fn stream_simple_udp_3msg() {
     let (_, _) = helper::setup(false, 0);
     let (r, _n_a, _p_a, s1_a, _n_b, _p_b, mut s1_b) = network_participant_stream(udp());
@@ -115,6 +169,34 @@ fn stream_simple_udp_3msg() {
     drop((_n_a, _n_b, _p_a, _p_b)); //clean teardown
 }
 
+#[test]
+fn stream_simulated_latency() {
+    let (_, _) = helper::setup(false, 0);
+    let r = Arc::new(Runtime::new().unwrap());
+    let (addr_a, addr_b) = tcp();
+    let mut n_a = Network::new(Pid::fake(0), &r);
+    let n_b = Network::new(Pid::fake(1), &r);
+    n_a.set_network_conditions(Some(NetworkConditions {
+        latency: Duration::from_millis(300),
+        ..Default::default()
+    }));
+    r.block_on(async {
+        n_a.listen(addr_a).await.unwrap();
+        let mut p1_b = n_b.connect(addr_b).await.unwrap();
+        let p1_a = n_a.connected().await.unwrap();
+
+        let s1_a = p1_a.open(4, Promises::ORDERED, 0).await.unwrap();
+        let mut s1_b = p1_b.opened().await.unwrap();
+
+        let start = Instant::now();
+        s1_a.send("Hello World").unwrap();
+        assert_eq!(s1_b.recv().await, Ok("Hello World".to_string()));
+        assert!(start.elapsed() >= Duration::from_millis(300));
+        drop((p1_a, p1_b)); //clean teardown
+    });
+    drop((n_a, n_b));
+}
+
 #[test]
 #[ignore]
 fn tcp_and_udp_2_connections() -> Result<(), Box<dyn std::error::Error>> {
@@ -143,7 +225,6 @@ fn tcp_and_udp_2_connections() -> Result<(), Box<dyn std::error::Error>> {
 }
 
 #[test]
-#[ignore]
 fn failed_listen_on_used_ports() -> Result<(), Box<dyn std::error::Error>> {
     let (_, _) = helper::setup(false, 0);
     let r = Arc::new(Runtime::new().unwrap());
@@ -0,0 +1,484 @@
+//! Loads saved rtsim data, optionally fast-forwards the simulation without a
+//! server and dumps statistics about the population, factions, sites, reports
+//! and quests.
+//!
+//! Only the rules that work without a running server are started: NPCs are
+//! simulated, the architect respawns the population and natural resources
+//! replenish. NPC AI is by far the most expensive rule and is only run with
+//! `--npc-ai`.
+//!
+//! Example:
+//! ```text
+//! cargo run --release -p veloren-rtsim --features bin_inspect --bin rtsim_inspect -- \
+//!     userdata/server/saves/rtsim/data.dat --days 10 --format csv --out rtsim_stats
+//! ```
+
+use clap::{Parser, ValueEnum};
+use common::{
+    assets::AssetExt,
+    comp::{self, gizmos::RtsimGizmos, item::MaterialStatManifest, tool::AbilityMap},
+    consts::DAY_LENGTH_DEFAULT,
+    resources::{Time, TimeOfDay},
+    shared_server_config::ServerConstants,
+    uid::IdMaps,
+    weather::WeatherGrid,
+};
+use serde::Serialize;
+use specs::WorldExt;
+use std::{
+    error::Error,
+    fs::{self, File},
+    io::{BufReader, BufWriter},
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::Instant,
+};
+use tracing::{Level, info, warn};
+use tracing_subscriber::EnvFilter;
+use vek::*;
+use veloren_rtsim::{
+    RtState,
+    ai::ActorSystemData,
+    data::{CURRENT_VERSION, Data, ReadError, ReportKind, architect::Population, quest::QuestKind},
+    event::OnSetup,
+    rule,
+};
+use world::{
+    IndexRef, World,
+    sim::{DEFAULT_WORLD_MAP, DEFAULT_WORLD_SEED, FileOpts, WorldOpts},
+};
+
+const DAY: f64 = 60.0 * 60.0 * 24.0;
+
+#[derive(Parser)]
+struct Cli {
+    /// Path to the saved rtsim data, `rtsim/data.dat` in the server's save
+    /// directory
+    data: PathBuf,
+    /// Seed of the world the data belongs to
+    #[arg(long, default_value_t = DEFAULT_WORLD_SEED)]
+    seed: u32,
+    /// Load the world map from this file instead of the default map
+    #[arg(long)]
+    map: Option<PathBuf>,
+    /// Number of in-game days to simulate before collecting statistics
+    #[arg(long, default_value_t = 0.0)]
+    days: f64,
+    /// Real-time length of an in-game day in minutes, as in the server
+    /// settings
+    #[arg(long, default_value_t = DAY_LENGTH_DEFAULT)]
+    day_length: f64,
+    /// Real time (in seconds) that passes each tick, the server ticks 30 times
+    /// per second
+    #[arg(long, default_value_t = 1.0 / 30.0)]
+    dt: f32,
+    /// Also simulate NPC AI, this is much slower
+    #[arg(long)]
+    npc_ai: bool,
+    /// Load the data even if it was saved with a different rtsim version
+    #[arg(long)]
+    ignore_version: bool,
+    #[arg(long, value_enum, default_value_t = Format::Json)]
+    format: Format,
+    /// Output file for JSON (stdout if omitted), output directory for CSV
+    #[arg(short, long)]
+    out: Option<PathBuf>,
+    /// Save the simulated rtsim data to this path
+    #[arg(long)]
+    save: Option<PathBuf>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    Json,
+    Csv,
+}
+
+#[derive(Serialize)]
+struct Stats {
+    version: u32,
+    tick: u64,
+    day: f64,
+    actors: ActorStats,
+    population: Vec<PopulationRecord>,
+    population_history: Vec<PopulationRecord>,
+    factions: Vec<FactionRecord>,
+    sites: Vec<SiteRecord>,
+    reports: Vec<ReportRecord>,
+    quests: Vec<QuestRecord>,
+}
+
+#[derive(Serialize)]
+struct ActorStats {
+    total: usize,
+    npcs: usize,
+    characters: usize,
+    alive: usize,
+    dead: usize,
+    absent: usize,
+    homeless: usize,
+}
+
+#[derive(Serialize)]
+struct PopulationRecord {
+    day: u32,
+    kind: String,
+    current: u32,
+    wanted: u32,
+}
+
+#[derive(Serialize)]
+struct FactionRecord {
+    id: String,
+    good_or_evil: bool,
+    leader_alive: bool,
+    members: usize,
+    sites: usize,
+}
+
+#[derive(Serialize)]
+struct SiteRecord {
+    id: String,
+    name: Option<String>,
+    faction: Option<String>,
+    x: i32,
+    y: i32,
+    population: usize,
+    known_reports: usize,
+}
+
+#[derive(Serialize)]
+struct ReportRecord {
+    kind: &'static str,
+    count: usize,
+}
+
+#[derive(Serialize)]
+struct QuestRecord {
+    kind: &'static str,
+    open: usize,
+    succeeded: usize,
+    failed: usize,
+}
+
+fn population_records(
+    day: u32,
+    current: &Population,
+    wanted: &Population,
+) -> Vec<PopulationRecord> {
+    current
+        .iter()
+        .zip(wanted.iter())
+        .map(|((kind, current), (_, wanted))| PopulationRecord {
+            day,
+            kind: format!("{kind:?}"),
+            current,
+            wanted,
+        })
+        .collect()
+}
+
+impl Stats {
+    fn collect(data: &Data, index: IndexRef, day: u32, history: Vec<PopulationRecord>) -> Self {
+        let actors = ActorStats {
+            total: data.actors.len(),
+            npcs: data.actors.values().filter(|a| a.npc().is_some()).count(),
+            characters: data
+                .actors
+                .values()
+                .filter(|a| a.character().is_some())
+                .count(),
+            alive: data
+                .actors
+                .values()
+                .filter(|a| a.is_present_and_alive())
+                .count(),
+            dead: data
+                .actors
+                .values()
+                .filter(|a| a.is_present_and_dead())
+                .count(),
+            absent: data
+                .actors
+                .values()
+                .filter(|a| a.presence.is_none())
+                .count(),
+            homeless: data
+                .actors
+                .values()
+                .filter(|a| a.npc().is_some() && a.home.is_none())
+                .count(),
+        };
+
+        let factions = data
+            .factions
+            .iter()
+            .map(|(id, faction)| FactionRecord {
+                id: format!("{id:?}"),
+                good_or_evil: faction.good_or_evil,
+                leader_alive: faction
+                    .leader
+                    .and_then(|leader| data.actors.get(leader))
+                    .is_some_and(|leader| leader.is_present_and_alive()),
+                members: data
+                    .actors
+                    .values()
+                    .filter(|a| a.faction == Some(id))
+                    .count(),
+                sites: data
+                    .sites
+                    .values()
+                    .filter(|s| s.faction == Some(id))
+                    .count(),
+            })
+            .collect();
+
+        let sites = data
+            .sites
+            .iter()
+            .map(|(id, site)| SiteRecord {
+                id: format!("{id:?}"),
+                name: site
+                    .world_site
+                    .and_then(|ws| index.sites.get(ws).name())
+                    .map(String::from),
+                faction: site.faction.map(|faction| format!("{faction:?}")),
+                x: site.wpos.x,
+                y: site.wpos.y,
+                population: site.population.len(),
+                known_reports: site.known_reports.len(),
+            })
+            .collect();
+
+        let (deaths, thefts) = data
+            .reports
+            .values()
+            .fold((0, 0), |(deaths, thefts), report| match report.kind {
+                ReportKind::Death { .. } => (deaths + 1, thefts),
+                ReportKind::Theft { .. } => (deaths, thefts + 1),
+            });
+        let reports = vec![
+            ReportRecord {
+                kind: "death",
+                count: deaths,
+            },
+            ReportRecord {
+                kind: "theft",
+                count: thefts,
+            },
+        ];
+
+        let mut quests =
+            ["escort", "slay", "courier", "gather", "explore", "defend"].map(|kind| QuestRecord {
+                kind,
+                open: 0,
+                succeeded: 0,
+                failed: 0,
+            });
+        for (_, quest) in data.quests.iter() {
+            let record = &mut quests[match quest.kind {
+                QuestKind::Escort { .. } => 0,
+                QuestKind::Slay { .. } => 1,
+                QuestKind::Courier { .. } => 2,
+                QuestKind::Gather { .. } => 3,
+                QuestKind::Explore { .. } => 4,
+                QuestKind::Defend { .. } => 5,
+            }];
+            match quest.resolution() {
+                None => record.open += 1,
+                Some(true) => record.succeeded += 1,
+                Some(false) => record.failed += 1,
+            }
+        }
+
+        Self {
+            version: data.version,
+            tick: data.tick,
+            day: data.time_of_day.0 / DAY,
+            actors,
+            population: population_records(
+                day,
+                &data.architect.population,
+                &data.architect.wanted_population,
+            ),
+            population_history: history,
+            factions,
+            sites,
+            reports,
+            quests: quests.into(),
+        }
+    }
+
+    fn write_csv(&self, dir: &Path) -> Result<(), Box<dyn Error>> {
+        fn write<T: Serialize>(path: PathBuf, records: &[T]) -> Result<(), Box<dyn Error>> {
+            let mut wtr = csv::Writer::from_path(path)?;
+            for record in records {
+                wtr.serialize(record)?;
+            }
+            wtr.flush()?;
+            Ok(())
+        }
+
+        fs::create_dir_all(dir)?;
+        write(dir.join("actors.csv"), &[&self.actors])?;
+        write(dir.join("population.csv"), &self.population)?;
+        write(dir.join("population_history.csv"), &self.population_history)?;
+        write(dir.join("factions.csv"), &self.factions)?;
+        write(dir.join("sites.csv"), &self.sites)?;
+        write(dir.join("reports.csv"), &self.reports)?;
+        write(dir.join("quests.csv"), &self.quests)?;
+        Ok(())
+    }
+}
+
+fn load_data(cli: &Cli) -> Result<Data, Box<dyn Error>> {
+    let file = File::open(&cli.data)?;
+    match Data::from_reader(BufReader::new(file)) {
+        Ok(data) => Ok(*data),
+        Err(ReadError::VersionMismatch(data)) => {
+            warn!(
+                "Rtsim data has version {} but the current version is {}",
+                data.version, CURRENT_VERSION
+            );
+            if cli.ignore_version {
+                Ok(*data)
+            } else {
+                Err("rtsim data version mismatch, use --ignore-version to load it anyway".into())
+            }
+        },
+        Err(ReadError::Load(err)) => Err(format!("failed to load rtsim data: {err}").into()),
+    }
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    tracing_subscriber::fmt()
+        .with_max_level(Level::INFO)
+        .with_env_filter(EnvFilter::from_default_env())
+        .with_writer(std::io::stderr)
+        .init();
+
+    let cli = Cli::parse();
+
+    let data = load_data(&cli)?;
+    if data.should_purge {
+        warn!("The should_purge flag is set, a server would discard this data");
+    }
+
+    info!("Generating world, seed: {}", cli.seed);
+    let threadpool = rayon::ThreadPoolBuilder::new().build()?;
+    let (world, index) = World::generate(
+        cli.seed,
+        WorldOpts {
+            seed_elements: true,
+            world_file: match &cli.map {
+                Some(path) => FileOpts::Load(path.clone()),
+                None => FileOpts::LoadAsset(DEFAULT_WORLD_MAP.into()),
+            },
+            calendar: None,
+        },
+        &threadpool,
+        &|_| {},
+    );
+    let index = index.as_index_ref();
+
+    let mut rtstate = RtState::without_rules(data);
+    rtstate.start_rule::<rule::migrate::Migrate>();
+    rtstate.start_rule::<rule::architect::Architect>();
+    rtstate.start_rule::<rule::replenish_resources::ReplenishResources>();
+    rtstate.start_rule::<rule::report::ReportEvents>();
+    rtstate.start_rule::<rule::sync_npcs::SyncNpcs>();
+    rtstate.start_rule::<rule::simulate_npcs::SimulateNpcs>();
+    if cli.npc_ai {
+        rtstate.start_rule::<rule::npc_ai::NpcAi>();
+    }
+    rtstate.start_rule::<rule::cleanup::CleanUp>();
+    rtstate.emit(OnSetup, &mut (), &world, index);
+
+    let day_cycle_coefficient = 1440.0 / cli.day_length;
+
+    // There are no entities, NPCs are always simulated
+    let mut ecs = specs::World::new();
+    ecs.register::<comp::Pos>();
+    ecs.register::<comp::Inventory>();
+    ecs.insert(IdMaps::new());
+    ecs.insert(ServerConstants {
+        day_cycle_coefficient,
+    });
+    // Weather isn't simulated, it never rains
+    ecs.insert(WeatherGrid::new(Vec2::zero()));
+    ecs.insert(RtsimGizmos::default());
+    ecs.insert(AbilityMap::load().cloned());
+    ecs.insert(MaterialStatManifest::load().cloned());
+    let mut system_data = ActorSystemData {
+        positions: ecs.system_data(),
+        id_maps: ecs.system_data(),
+        server_constants: ecs.system_data(),
+        weather_grid: ecs.system_data(),
+        rtsim_gizmos: ecs.system_data(),
+        ability_map: ecs.system_data(),
+        msm: ecs.system_data(),
+        inventories: Mutex::new(ecs.system_data()),
+    };
+
+    let tod_per_tick = cli.dt as f64 * day_cycle_coefficient;
+    let ticks = (cli.days.max(0.0) * DAY / tod_per_tick).ceil() as u64;
+    let start_tod = rtstate.data().time_of_day;
+    let mut time = Time(0.0);
+    let mut day = 0;
+    let mut history = {
+        let data = rtstate.data();
+        population_records(
+            0,
+            &data.architect.population,
+            &data.architect.wanted_population,
+        )
+    };
+
+    info!("Simulating {} days ({} ticks)...", cli.days, ticks);
+    let start = Instant::now();
+    for tick in 1..=ticks {
+        let time_of_day = TimeOfDay(start_tod.0 + tick as f64 * tod_per_tick);
+        time.0 += cli.dt as f64;
+        rtstate.tick(&mut system_data, &world, index, time_of_day, time, cli.dt);
+
+        let elapsed_days = ((time_of_day.0 - start_tod.0) / DAY) as u32;
+        if elapsed_days > day {
+            day = elapsed_days;
+            let data = rtstate.data();
+            info!(
+                "Day {}: {} actors, population {}/{} ({:.1}s)",
+                day,
+                data.actors.len(),
+                data.architect.population.total(),
+                data.architect.wanted_population.total(),
+                start.elapsed().as_secs_f32(),
+            );
+            history.extend(population_records(
+                day,
+                &data.architect.population,
+                &data.architect.wanted_population,
+            ));
+        }
+    }
+
+    let stats = Stats::collect(&rtstate.data(), index, day, history);
+    match cli.format {
+        Format::Json => {
+            let json = serde_json::to_string_pretty(&stats)?;
+            match &cli.out {
+                Some(path) => fs::write(path, json)?,
+                None => println!("{json}"),
+            }
+        },
+        Format::Csv => stats.write_csv(cli.out.as_deref().unwrap_or(Path::new("rtsim_stats")))?,
+    }
+
+    if let Some(path) = &cli.save {
+        info!("Saving rtsim data to {}", path.display());
+        rtstate
+            .data()
+            .write_to(BufWriter::new(File::create(path)?))?;
+    }
+
+    Ok(())
+}
@@ -12,7 +12,7 @@ use common::{
     resources::{Time, TimeOfDay},
     rtsim::{
         Dialogue, DialogueId, DialogueKind, FactionId, NpcAction, NpcActivity, NpcInput, NpcMsg,
-        Personality, QuestId, ReportId, Response, Role, SiteId, TerrainResource,
+        Personality, QuestId, ReportId, Reputation, Response, Role, SiteId, TerrainResource,
     },
     store::Id,
     terrain::CoordinateConversions,
@@ -69,6 +69,9 @@ pub struct Controller {
     pub look_dir: Option<Dir>,
     pub job: Option<Job>,
     pub quests_to_create: Vec<(QuestId, Quest)>,
+    /// The site a raid led by the NPC was started against, or `None` once it
+    /// ended, see [`crate::data::Raids`]
+    pub raid: Option<Option<SiteId>>,
 
     /// Each pilot gets assigned to a route, and as the server ticks onward, the
     /// current leg of each pilot's assigned route increments. This gets
@@ -141,6 +144,10 @@ impl Controller {
         self.new_home = Some(new_home.into());
     }
 
+    pub fn set_raid(&mut self, target: impl Into<Option<SiteId>>) {
+        self.raid = Some(target.into());
+    }
+
     pub fn set_newly_hired(&mut self, actor: ActorId, expires: Time) {
         self.job = Some(Job::Hired(actor, expires));
     }
@@ -302,6 +309,10 @@ pub struct Character {
     // The tick on which the character was last present. If this value falls behind the global
     // rtsim tick, we assume the character has logged off and remove its presence
     pub last_present_at: Option<u64>,
+    /// The standing of this character with sites and factions, see
+    /// [`crate::rule::reputation`].
+    #[serde(default)]
+    pub reputation: Reputation,
 }
 
 #[allow(clippy::large_enum_variant)]
@@ -375,6 +386,7 @@ impl Clone for Actor {
                 ActorKind::Character(c) => ActorKind::Character(Character {
                     id: c.id,
                     last_present_at: c.last_present_at,
+                    reputation: c.reputation.clone(),
                 }),
             },
             seed: self.seed,
@@ -450,6 +462,7 @@ impl Actor {
             kind: ActorKind::Character(Character {
                 id,
                 last_present_at: None,
+                reputation: Reputation::default(),
             }),
             seed,
             wpos,
@@ -18,7 +18,7 @@ pub struct Death {
     pub faction: Option<FactionId>,
 }
 
-#[derive(enum_map::Enum)]
+#[derive(Debug, enum_map::Enum)]
 pub enum TrackedPopulation {
     Adventurers,
     Merchants,
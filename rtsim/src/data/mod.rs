@@ -4,6 +4,7 @@ pub mod architect;
 pub mod faction;
 pub mod nature;
 pub mod quest;
+pub mod raid;
 pub mod report;
 pub mod sentiment;
 pub mod site;
@@ -13,6 +14,7 @@ pub use self::{
     faction::{Faction, FactionId, Factions},
     nature::Nature,
     quest::Quests,
+    raid::Raids,
     report::{Report, ReportId, ReportKind, Reports},
     sentiment::{Sentiment, Sentiments},
     site::{Site, SiteId, Sites},
@@ -55,6 +57,8 @@ pub struct Data {
     pub architect: Architect,
     #[serde(default)]
     pub quests: Quests,
+    #[serde(skip)]
+    pub raids: Raids,
 
     #[serde(default)]
     pub tick: u64,
@@ -117,6 +121,18 @@ impl Data {
     ///
     /// This might include populating caches, normalising data, etc.
     pub fn prepare(&mut self) { self.quests.prepare(); }
+
+    /// The standing of `character` with the home site and faction of `npc`.
+    ///
+    /// Returns a neutral standing if `character` is not a character.
+    pub fn reputation_standing(&self, npc: &Actor, character: ActorId) -> f32 {
+        self.actors
+            .get(character)
+            .and_then(|actor| actor.character())
+            .map_or(0.0, |character| {
+                character.reputation.standing(npc.home, npc.faction)
+            })
+    }
 }
 
 fn rugged_ser_enum_map<
@@ -1,16 +1,29 @@
+use crate::data::{Actor, Actors, Raids, Sites};
 use common::{
+    assets::{AssetExt, AssetReadGuard, BoxedError, FileAsset, load_ron},
     resources::Time,
     rtsim::{ActorId, ItemResource, QuestId, SiteId},
+    spot::Spot,
+    terrain::CoordinateConversions,
 };
 use hashbrown::{HashMap, HashSet};
 use itertools::Either;
 use serde::{Deserialize, Serialize};
 use std::{
+    borrow::Cow,
     num::NonZeroU32,
     sync::atomic::{AtomicU8, AtomicU64, Ordering},
 };
 use vek::Vec2;
 
+/// How close an explorer needs to get to a site to have explored it
+pub const EXPLORE_SITE_RADIUS: f32 = 150.0;
+/// How close an explorer needs to get to a spot to have explored it
+pub const EXPLORE_SPOT_RADIUS: f32 = 48.0;
+/// The area around a site that raiders and defenders need to be in to count
+/// as being at the site
+pub const DEFEND_RADIUS: f32 = 300.0;
+
 /// The easiest way to think about quests is as a virtual Jira board (or,
 /// perhaps, a community jobs noticeboard).
 ///
@@ -70,6 +83,10 @@ impl Quests {
 
     pub fn get(&self, id: QuestId) -> Option<&Quest> { self.quests.get(&id) }
 
+    pub fn iter(&self) -> impl Iterator<Item = (QuestId, &Quest)> + '_ {
+        self.quests.iter().map(|(id, quest)| (*id, quest))
+    }
+
     pub fn related_to(&self, actor: impl Into<ActorId>) -> impl Iterator<Item = QuestId> + '_ {
         match self.related_quests.get(&actor.into()) {
             Some(quests) => Either::Left(
@@ -104,6 +121,73 @@ impl Quests {
         related.into_iter()
     }
 
+    /// Check the completion conditions of quests that the arbiter can't witness
+    /// directly: explorers reaching their target, and raids on defended sites
+    /// coming to an end.
+    ///
+    /// Reaching the goal doesn't resolve the quest, the quester still needs to
+    /// report back to the arbiter (see [`Quest::goal_reached`]).
+    pub fn update_goals(&self, actors: &Actors, sites: &Sites, raids: &Raids) {
+        for quest in self.quests.values() {
+            if quest.resolution().is_some() || quest.goal_reached() {
+                continue;
+            }
+
+            match &quest.kind {
+                QuestKind::Explore { explorer, target } => {
+                    if let Some(explorer) = actors.get(*explorer)
+                        && let Some(target_wpos) = target.wpos(sites)
+                        && explorer.wpos.xy().distance_squared(target_wpos)
+                            < target.radius().powi(2)
+                    {
+                        quest.progress.set(QuestProgress::GOAL_REACHED);
+                    }
+                },
+                QuestKind::Defend {
+                    defender,
+                    site,
+                    raiders,
+                } => {
+                    let target = *site;
+                    let Some(site) = sites.get(target) else {
+                        continue;
+                    };
+                    let site_wpos = site.wpos.as_::<f32>();
+                    let at_site = |actor: &Actor| {
+                        actor.wpos.xy().distance_squared(site_wpos) < DEFEND_RADIUS.powi(2)
+                    };
+                    let raider_at_site = |raider: &ActorId| {
+                        actors
+                            .get(*raider)
+                            .is_some_and(|raider| !raider.is_present_and_dead() && at_site(raider))
+                    };
+
+                    if quest.progress.get() < QuestProgress::STARTED {
+                        // The raid only starts once the raiders have arrived
+                        if raiders.iter().any(raider_at_site) {
+                            quest.progress.set(QuestProgress::STARTED);
+                        }
+                    } else if raiders
+                        .first()
+                        .and_then(|leader| raids.get(*leader))
+                        .is_none_or(|raid| raid.target != target)
+                        // Hired raiders might linger after their leader headed home
+                        && !raiders.iter().any(raider_at_site)
+                        // The defender must be around when the raid comes to an end
+                        && actors.get(*defender).is_some_and(at_site)
+                    {
+                        quest.progress.set(QuestProgress::GOAL_REACHED);
+                    }
+                },
+                // The arbiter checks these when the quester reports back
+                QuestKind::Escort { .. }
+                | QuestKind::Slay { .. }
+                | QuestKind::Courier { .. }
+                | QuestKind::Gather { .. } => {},
+            }
+        }
+    }
+
     pub(super) fn prepare(&mut self) {
         // Populate quest lookup table
         for (quest_id, quest) in &self.quests {
@@ -142,9 +226,14 @@ pub struct Quest {
 
     outcome: QuestOutcome,
 
-    /// The only aspect of the quest that mutates over time. Resolving quests is
-    /// monotonic: once resolved, they cannot be unresolved (to avoid the
-    /// deposit being paid back twice, for example).
+    /// Progress towards the goal of the quest, for quests whose completion is
+    /// determined by [`Quests::update_goals`].
+    #[serde(default)]
+    progress: QuestProgress,
+
+    /// The only aspect of the quest that mutates over time (besides its
+    /// progress). Resolving quests is monotonic: once resolved, they cannot be
+    /// unresolved (to avoid the deposit being paid back twice, for example).
     res: QuestRes,
 }
 
@@ -163,6 +252,7 @@ impl Quest {
             },
             timeout: None,
             outcome: QuestOutcome::default(),
+            progress: QuestProgress::default(),
             res: QuestRes(AtomicU8::new(0)),
         }
     }
@@ -174,6 +264,7 @@ impl Quest {
             kind: QuestKind::Slay { target, slayer },
             timeout: None,
             outcome: QuestOutcome::default(),
+            progress: QuestProgress::default(),
             res: QuestRes(AtomicU8::new(0)),
         }
     }
@@ -189,6 +280,59 @@ impl Quest {
             kind: QuestKind::Courier { instance },
             timeout: None,
             outcome: QuestOutcome::default(),
+            progress: QuestProgress::default(),
+            res: QuestRes(AtomicU8::new(0)),
+        }
+    }
+
+    /// Create a new gather quest that requires the gatherer to bring the
+    /// arbiter a number of items.
+    pub fn gather(arbiter: ActorId, gatherer: ActorId, item: String, amount: u32) -> Self {
+        Self {
+            arbiter,
+            kind: QuestKind::Gather {
+                gatherer,
+                item,
+                amount,
+            },
+            timeout: None,
+            outcome: QuestOutcome::default(),
+            progress: QuestProgress::default(),
+            res: QuestRes(AtomicU8::new(0)),
+        }
+    }
+
+    /// Create a new explore quest that requires the explorer to visit a site
+    /// or spot and then report back to the arbiter.
+    pub fn explore(arbiter: ActorId, explorer: ActorId, target: ExploreTarget) -> Self {
+        Self {
+            arbiter,
+            kind: QuestKind::Explore { explorer, target },
+            timeout: None,
+            outcome: QuestOutcome::default(),
+            progress: QuestProgress::default(),
+            res: QuestRes(AtomicU8::new(0)),
+        }
+    }
+
+    /// Create a new defend quest that requires the defender to protect a site
+    /// from the given raiders until the raid is over.
+    pub fn defend(
+        arbiter: ActorId,
+        defender: ActorId,
+        site: SiteId,
+        raiders: Vec<ActorId>,
+    ) -> Self {
+        Self {
+            arbiter,
+            kind: QuestKind::Defend {
+                defender,
+                site,
+                raiders,
+            },
+            timeout: None,
+            outcome: QuestOutcome::default(),
+            progress: QuestProgress::default(),
             res: QuestRes(AtomicU8::new(0)),
         }
     }
@@ -246,6 +390,10 @@ impl Quest {
 
     pub fn resolution(&self) -> Option<bool> { self.res.get() }
 
+    /// Whether the goal of an explore or defend quest has been reached, after
+    /// which the arbiter may resolve the quest successfully.
+    pub fn goal_reached(&self) -> bool { self.progress.get() >= QuestProgress::GOAL_REACHED }
+
     pub fn get_related_actors(&self) -> HashSet<ActorId> {
         let mut related = HashSet::default();
         self.for_related_actors(|actor| {
@@ -273,6 +421,10 @@ impl Quest {
                 f(instance.source_actor);
                 f(instance.messenger);
             },
+            QuestKind::Gather { gatherer, .. } => f(*gatherer),
+            QuestKind::Explore { explorer, .. } => f(*explorer),
+            // Raiders aren't part of the quest, they don't know about it
+            QuestKind::Defend { defender, .. } => f(*defender),
         }
     }
 }
@@ -291,6 +443,27 @@ impl QuestRes {
     }
 }
 
+// 0 = not started, 1 = started, 2.. = goal reached
+#[derive(Default, Serialize, Deserialize)]
+struct QuestProgress(AtomicU8);
+
+impl QuestProgress {
+    const GOAL_REACHED: u8 = 2;
+    const STARTED: u8 = 1;
+
+    fn get(&self) -> u8 { self.0.load(Ordering::Relaxed) }
+
+    // Progress is monotonic, like the resolution
+    fn set(&self, progress: u8) { self.0.fetch_max(progress, Ordering::Relaxed); }
+}
+
+impl Clone for QuestProgress {
+    fn clone(&self) -> Self {
+        // See `QuestRes`
+        Self(AtomicU8::new(self.get()))
+    }
+}
+
 impl Clone for QuestRes {
     fn clone(&self) -> Self {
         // This isn't strictly kosher in a multi-threaded context, but we assume that
@@ -334,11 +507,55 @@ pub enum QuestKind {
     Courier {
         instance: CourierQuestInstance,
     },
+    Gather {
+        gatherer: ActorId,
+        /// Asset specifier of the item to bring
+        item: String,
+        amount: u32,
+    },
+    Explore {
+        explorer: ActorId,
+        target: ExploreTarget,
+    },
+    Defend {
+        defender: ActorId,
+        site: SiteId,
+        /// The leader of the raid followed by the raiders it hired, as of when
+        /// the quest was given, see [`Raids`]
+        raiders: Vec<ActorId>,
+    },
 }
 
-/// [`COURIER_QUEST_VARIANTS`] is worth looking at, as it contains all possible
-/// quest permutations and can be extended.
-#[derive(Debug, Copy, Clone, Serialize, Deserialize, PartialEq, PartialOrd, Eq, Ord, Hash)]
+/// The place an explore quest asks to visit.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum ExploreTarget {
+    Site(SiteId),
+    Spot {
+        chunk: Vec2<i32>,
+        /// i18n key of the name of the spot
+        name: String,
+    },
+}
+
+impl ExploreTarget {
+    pub fn wpos(&self, sites: &Sites) -> Option<Vec2<f32>> {
+        match self {
+            ExploreTarget::Site(site) => sites.get(*site).map(|site| site.wpos.as_()),
+            ExploreTarget::Spot { chunk, .. } => Some(chunk.cpos_to_wpos_center().as_()),
+        }
+    }
+
+    fn radius(&self) -> f32 {
+        match self {
+            ExploreTarget::Site(_) => EXPLORE_SITE_RADIUS,
+            ExploreTarget::Spot { .. } => EXPLORE_SPOT_RADIUS,
+        }
+    }
+}
+
+/// The possible courier quests are defined by the templates in the
+/// [`QuestManifest`].
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, PartialOrd, Eq, Ord, Hash)]
 pub enum CourierQuest {
     /// Find an NPC and speak to them. No items are required.
     Message,
@@ -356,22 +573,37 @@ pub enum Recipient {
     Giver,
 }
 
-/// For [`CourierQuest`].
-#[derive(Debug, Copy, Clone, Serialize, Deserialize, PartialEq, PartialOrd, Eq, Ord, Hash)]
-pub enum Payload {
-    LegoomLeaf,
-    GnarlingCarving,
+/// For [`CourierQuest`], refers to one of the payloads defined in the
+/// [`QuestManifest`].
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, PartialOrd, Eq, Ord, Hash)]
+#[serde(from = "String", into = "String")]
+pub struct Payload(pub String);
+
+impl From<String> for Payload {
+    fn from(id: String) -> Self {
+        // Payloads used to be hardcoded variants, keep courier quests that were
+        // given out back then working
+        Self(match id.as_str() {
+            "LegoomLeaf" => "legoom_leaf".to_string(),
+            "GnarlingCarving" => "gnarling_carving".to_string(),
+            _ => id,
+        })
+    }
+}
+
+impl From<Payload> for String {
+    fn from(payload: Payload) -> Self { payload.0 }
 }
 
 impl CourierQuest {
-    pub fn payload(self) -> Option<Payload> {
+    pub fn payload(&self) -> Option<&Payload> {
         match self {
             CourierQuest::Message => None,
             CourierQuest::Deliver { payload, .. } => Some(payload),
         }
     }
 
-    pub fn delivers_to_giver(self) -> bool {
+    pub fn delivers_to_giver(&self) -> bool {
         matches!(self, CourierQuest::Deliver {
             recipient: Recipient::Giver,
             ..
@@ -379,31 +611,8 @@ impl CourierQuest {
     }
 }
 
-/// Stack-allocated array of all of the possible courier quests that can be
-/// rolled.
-pub const COURIER_QUEST_VARIANTS: [CourierQuest; 5] = [
-    // Add more here later!
-    CourierQuest::Message,
-    CourierQuest::Deliver {
-        payload: Payload::GnarlingCarving,
-        recipient: Recipient::Other,
-    },
-    CourierQuest::Deliver {
-        payload: Payload::GnarlingCarving,
-        recipient: Recipient::Giver,
-    },
-    CourierQuest::Deliver {
-        payload: Payload::LegoomLeaf,
-        recipient: Recipient::Other,
-    },
-    CourierQuest::Deliver {
-        payload: Payload::LegoomLeaf,
-        recipient: Recipient::Giver,
-    },
-];
-
 /// Contains a completable courier quest.
-#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct CourierQuestInstance {
     pub kind: CourierQuest,
     pub spot: Option<Vec2<i32>>,
@@ -420,4 +629,165 @@ pub struct CourierQuestInstance {
     pub messenger: ActorId,
     /// The distance to be traversed, calculated once at quest start.
     pub distance: NonZeroU32,
+    /// The number of coins paid on completion, calculated once at quest start.
+    #[serde(default)]
+    pub reward: f32,
+}
+
+/// Payloads and templates that quests are generated from, loaded from
+/// `common.rtsim.quests`.
+///
+/// Changing the templates only affects new quests, but removing a payload
+/// makes courier quests that deliver it impossible to complete.
+#[derive(Clone, Debug, Deserialize)]
+pub struct QuestManifest {
+    pub payloads: HashMap<Payload, PayloadDef>,
+    pub courier: Vec<CourierTemplate>,
+    pub gather: Vec<GatherTemplate>,
+    pub explore: Vec<ExploreTemplate>,
+    pub defend: DefendTemplate,
+}
+
+impl FileAsset for QuestManifest {
+    const EXTENSION: &'static str = "ron";
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Result<Self, BoxedError> { load_ron(&bytes) }
+}
+
+impl QuestManifest {
+    pub fn get() -> AssetReadGuard<Self> { Self::load_expect("common.rtsim.quests").read() }
+
+    pub fn payload(&self, payload: &Payload) -> Option<&PayloadDef> { self.payloads.get(payload) }
+
+    pub fn gather_template(&self, item: &str) -> Option<&GatherTemplate> {
+        self.gather.iter().find(|template| template.item == item)
+    }
+}
+
+/// Something that can be delivered by a courier.
+#[derive(Clone, Debug, Deserialize)]
+pub struct PayloadDef {
+    /// Asset specifiers and amounts of the items to deliver
+    pub items: Vec<(String, u32)>,
+    /// Where the items can be found, if they need to be fetched from a spot
+    #[serde(default)]
+    pub spot: Option<PayloadSpot>,
+    /// i18n key of the statement introducing the quest
+    pub start: String,
+    /// i18n key of the answer when asked what needs to be delivered
+    pub what_is_needed: String,
+    /// i18n key of the answer when the courier doesn't have all items
+    pub insufficient_items: String,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct PayloadSpot {
+    pub kind: Spot,
+    /// i18n key of the name of the spot
+    pub name: String,
+    /// i18n key of the label of the map marker pointing at the spot
+    pub map_label: String,
+}
+
+/// Coins paid for completing a quest, scaled by the distance travelled.
+#[derive(Copy, Clone, Debug, Deserialize)]
+pub struct Reward {
+    pub min: f32,
+    /// The reward for the longest possible journey
+    #[serde(default)]
+    pub at_max_distance: f32,
+}
+
+impl Reward {
+    /// `distance` is the fraction of the longest possible journey
+    pub fn amount(&self, distance: f32) -> f32 { self.min.max(self.at_max_distance * distance) }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct CourierTemplate {
+    pub kind: CourierQuest,
+    pub reward: Reward,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct GatherTemplate {
+    /// Asset specifier of the item to gather
+    pub item: String,
+    /// Inclusive range of the amount asked for
+    pub amount: (u32, u32),
+    pub reward_per_item: f32,
+    /// i18n key of the quest offer, given the `amount` and `coins`
+    pub ask: String,
+    /// i18n key of the answer when asked what needs to be gathered, given the
+    /// `amount`
+    pub what_is_needed: String,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ExploreTemplate {
+    pub target: ExploreKind,
+    pub reward: Reward,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub enum ExploreKind {
+    /// Any site that is neither too close nor too far away
+    Site,
+    /// The nearest spot of the given kind
+    Spot {
+        kind: Spot,
+        /// i18n key of the name of the spot
+        name: String,
+    },
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct DefendTemplate {
+    /// Raids by fewer raiders don't need outside help
+    pub min_raiders: usize,
+    pub reward_per_raider: f32,
+    /// In minutes
+    pub time_limit: f64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::comp::Item;
+
+    #[test]
+    fn load_quest_manifest() {
+        let manifest = QuestManifest::get();
+
+        for payload in manifest.payloads.values() {
+            for (item, amount) in &payload.items {
+                assert!(Item::new_from_asset(item).is_ok(), "Unknown item {item}");
+                assert!(*amount > 0);
+            }
+        }
+        for template in &manifest.courier {
+            if let Some(payload) = template.kind.payload() {
+                assert!(
+                    manifest.payload(payload).is_some(),
+                    "Unknown payload {payload:?}"
+                );
+            }
+        }
+        // Payloads of courier quests given out before they were moved to the
+        // manifest are still known
+        for old_payload in ["LegoomLeaf", "GnarlingCarving"] {
+            assert!(
+                manifest
+                    .payload(&Payload::from(old_payload.to_owned()))
+                    .is_some()
+            );
+        }
+        for template in &manifest.gather {
+            assert!(Item::new_from_asset(&template.item).is_ok());
+            assert!(manifest.gather_template(&template.item).is_some());
+            assert!(0 < template.amount.0 && template.amount.0 <= template.amount.1);
+        }
+        assert!(!manifest.explore.is_empty());
+        assert!(manifest.defend.reward_per_raider > 0.0);
+    }
 }
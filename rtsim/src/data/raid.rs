@@ -0,0 +1,121 @@
+use crate::data::Actors;
+use common::{
+    resources::Time,
+    rtsim::{ActorId, SiteId},
+};
+use hashbrown::HashMap;
+
+/// Raids that went on for longer than this (in seconds) are assumed to have
+/// been abandoned by their leader
+const MAX_RAID_DURATION: f64 = 2.0 * 60.0 * 60.0;
+
+/// The raids currently led by pirates against other sites, keyed by their
+/// leader.
+///
+/// A raid starts when its leader sets off towards the target and ends when the
+/// leader heads home again. The raid is driven by the behaviour of its leader,
+/// which isn't persisted either.
+#[derive(Clone, Default)]
+pub struct Raids {
+    raids: HashMap<ActorId, Raid>,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct Raid {
+    pub target: SiteId,
+    pub started: Time,
+}
+
+impl Raids {
+    pub fn start(&mut self, leader: ActorId, target: SiteId, now: Time) {
+        self.raids.insert(leader, Raid {
+            target,
+            started: now,
+        });
+    }
+
+    pub fn end(&mut self, leader: ActorId) { self.raids.remove(&leader); }
+
+    pub fn get(&self, leader: ActorId) -> Option<&Raid> { self.raids.get(&leader) }
+
+    /// The leaders of all raids against a site
+    pub fn against(&self, site: SiteId) -> impl Iterator<Item = ActorId> + '_ {
+        self.raids
+            .iter()
+            .filter(move |(_, raid)| raid.target == site)
+            .map(|(leader, _)| *leader)
+    }
+
+    /// The leader of a raid followed by the pirates it hired for it
+    pub fn raiders(&self, actors: &Actors, leader: ActorId) -> Vec<ActorId> {
+        core::iter::once(leader)
+            .chain(
+                actors
+                    .iter()
+                    .filter(|(_, actor)| {
+                        !actor.is_present_and_dead()
+                            && actor.hired().is_some_and(|(hirer, _)| hirer == leader)
+                    })
+                    .map(|(actor_id, _)| actor_id),
+            )
+            .collect()
+    }
+
+    /// End the raids whose leader died, or that went on for far too long
+    pub fn cleanup(&mut self, actors: &Actors, now: Time) {
+        self.raids.retain(|leader, raid| {
+            actors
+                .get(*leader)
+                .is_some_and(|leader| !leader.is_present_and_dead())
+                && now.0 - raid.started.0 < MAX_RAID_DURATION
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::{Actor, actor::Job};
+    use common::{
+        comp::{Body, humanoid},
+        rtsim::{Profession, Role},
+    };
+    use vek::Vec3;
+
+    fn pirate(actors: &mut Actors, is_leader: bool) -> ActorId {
+        actors.create_actor(Actor::new_npc(
+            0,
+            Vec3::zero(),
+            Body::Humanoid(humanoid::Body::random()),
+            Role::Civilised(Some(Profession::Pirate(is_leader))),
+        ))
+    }
+
+    #[test]
+    fn raiders_and_cleanup() {
+        let mut actors = Actors::default();
+        let leader = pirate(&mut actors, true);
+        let hireling = pirate(&mut actors, false);
+        let bystander = pirate(&mut actors, false);
+        actors[hireling].npc_mut().unwrap().job = Some(Job::Hired(leader, Time(1000.0)));
+
+        let target = SiteId::default();
+        let mut raids = Raids::default();
+        raids.start(leader, target, Time(0.0));
+        assert_eq!(raids.against(target).collect::<Vec<_>>(), vec![leader]);
+        let raiders = raids.raiders(&actors, leader);
+        assert_eq!(raiders, vec![leader, hireling]);
+        assert!(!raiders.contains(&bystander));
+
+        raids.cleanup(&actors, Time(60.0));
+        assert!(raids.get(leader).is_some());
+        // A raid that never ended was abandoned
+        raids.cleanup(&actors, Time(MAX_RAID_DURATION));
+        assert!(raids.get(leader).is_none());
+
+        raids.start(leader, target, Time(0.0));
+        actors.remove(leader);
+        raids.cleanup(&actors, Time(60.0));
+        assert_eq!(raids.against(target).count(), 0);
+    }
+}
@@ -136,7 +136,8 @@ impl Sentiment {
     /// generally try to harm the actor in any way they can.
     pub const VILLAIN: f32 = -0.8;
 
-    fn value(&self) -> f32 { self.positivity as f32 * (1.0 / 126.0) }
+    /// The sentiment as a value in the range `-1.0..=1.0`.
+    pub fn value(&self) -> f32 { self.positivity as f32 * (1.0 / 126.0) }
 
     /// Change the sentiment toward the given target by the given amount,
     /// capping out at the given value.
@@ -99,6 +99,7 @@ impl Data {
             airship_sim: Default::default(),
             architect: Default::default(),
             quests: Default::default(),
+            raids: Default::default(),
 
             tick: 0,
             time_of_day: TimeOfDay(settings.start_time),
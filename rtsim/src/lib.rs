@@ -176,19 +176,27 @@ type EventHandlersOf<E> = Vec<
 >;
 
 impl RtState {
-    pub fn new(mut data: Data) -> Self {
+    pub fn new(data: Data) -> Self {
+        let mut this = Self::without_rules(data);
+
+        this.start_default_rules();
+
+        this
+    }
+
+    /// Create the state without starting any of the default rules, they need
+    /// to be started manually with [`RtState::start_rule`].
+    ///
+    /// Useful to simulate only a part of rtsim, e.g. outside of a server.
+    pub fn without_rules(mut data: Data) -> Self {
         data.prepare();
 
-        let mut this = Self {
+        Self {
             resources: SendSyncAnyMap::new(),
             rules: SendSyncAnyMap::new(),
             event_handlers: SendSyncAnyMap::new(),
         }
-        .with_resource(data);
-
-        this.start_default_rules();
-
-        this
+        .with_resource(data)
     }
 
     pub fn with_resource<R: Send + Sync + 'static>(mut self, r: R) -> Self {
@@ -202,6 +210,7 @@ impl RtState {
         self.start_rule::<rule::architect::Architect>();
         self.start_rule::<rule::replenish_resources::ReplenishResources>();
         self.start_rule::<rule::report::ReportEvents>();
+        self.start_rule::<rule::reputation::UpdateReputation>();
         self.start_rule::<rule::sync_npcs::SyncNpcs>();
         self.start_rule::<rule::simulate_npcs::SimulateNpcs>();
         self.start_rule::<rule::npc_ai::NpcAi>();
@@ -4,6 +4,7 @@ pub mod migrate;
 pub mod npc_ai;
 pub mod replenish_resources;
 pub mod report;
+pub mod reputation;
 pub mod simulate_npcs;
 pub mod sync_npcs;
 
@@ -1,4 +1,5 @@
-use crate::{data::quest::Payload, rule::npc_ai::quest::get_nearest_spot};
+use crate::rule::npc_ai::quest::get_nearest_spot;
+use common::rtsim::ReputationLevel;
 
 use super::*;
 
@@ -154,7 +155,7 @@ pub fn general<S: State>(tgt: ActorId, session: DialogueSession) -> impl Action<
                             .unwrap_or_else(|| "<unknown>".to_string());
 
                         if is_talking_to_courier_target {
-                            let quest = *instance;
+                            let quest = instance.clone();
                             let (claim, thanks) = quest.get_courier_claim_dialogue();
                             responses.push((
                                 Response::from(claim),
@@ -189,41 +190,39 @@ pub fn general<S: State>(tgt: ActorId, session: DialogueSession) -> impl Action<
                         let (dialogue_question, dialogue_response) = instance
                             .what_items_needed(is_talking_to_courier_target, npc_name.as_str());
 
-                        match instance.kind.payload() {
+                        if instance.needs_spot() {
                             // For the gnarling carving (or any other
                             // spot-based) quest, the quest giver can be asked
                             // for the required items, and the quest giver will
                             // mark the map with the nearest spot.
-                            Some(Payload::GnarlingCarving) => {
-                                let quest = *instance;
-                                responses.push((
-                                    Response::from(dialogue_question),
-                                    session
-                                        .say_statement(dialogue_response)
-                                        .then(now(move |ctx, _| {
-                                            // attempt to provide a map marker that points to
-                                            // the nearest spot. Any spot is sufficient, it
-                                            // doesn't need to be the original one that was given at
-                                            // quest start. In fact, it's more convenient if
-                                            // you get to your location first and the courier
-                                            // recipient also has the ability to point out
-                                            // where the nearest spot might be.
-                                            let tgt_npc_name = tgt_npc_name.as_str();
-                                            get_nearest_spot(
-                                                ctx,
-                                                quest.kind,
-                                                ctx.actor.wpos.xy().wpos_to_cpos().as_(),
-                                            )
-                                            .map(|chunk_pos| {
-                                                session.give_marker(
-                                                    quest.get_quest_spot_start_marker(
-                                                        chunk_pos.cpos_to_wpos().as_(),
-                                                        tgt_npc_name,
-                                                        quest_id,
-                                                    ),
-                                                )
-                                            })
-                                            .unwrap_or_else(|| {
+                            let quest = instance.clone();
+                            responses.push((
+                                Response::from(dialogue_question),
+                                session
+                                    .say_statement(dialogue_response)
+                                    .then(now(move |ctx, _| {
+                                        // attempt to provide a map marker that points to
+                                        // the nearest spot. Any spot is sufficient, it
+                                        // doesn't need to be the original one that was given at
+                                        // quest start. In fact, it's more convenient if
+                                        // you get to your location first and the courier
+                                        // recipient also has the ability to point out
+                                        // where the nearest spot might be.
+                                        let tgt_npc_name = tgt_npc_name.as_str();
+                                        get_nearest_spot(
+                                            ctx,
+                                            &quest.kind,
+                                            ctx.actor.wpos.xy().wpos_to_cpos().as_(),
+                                        )
+                                        .map(|chunk_pos| {
+                                            session.give_marker(quest.get_quest_spot_start_marker(
+                                                chunk_pos.cpos_to_wpos().as_(),
+                                                tgt_npc_name,
+                                                quest_id,
+                                            ))
+                                        })
+                                        .unwrap_or_else(
+                                            || {
                                                 // provide a map marker that points to the
                                                 // courier target as a fallback
                                                 session.give_marker(
@@ -233,18 +232,17 @@ pub fn general<S: State>(tgt: ActorId, session: DialogueSession) -> impl Action<
                                                         tgt_npc_id,
                                                     ),
                                                 )
-                                            })
-                                        }))
-                                        .boxed(),
-                                ));
-                            },
+                                            },
+                                        )
+                                    }))
+                                    .boxed(),
+                            ));
+                        } else {
                             // No spot is required for these, so things are much more simple:
-                            None | Some(Payload::LegoomLeaf) => {
-                                responses.push((
-                                    Response::from(dialogue_question),
-                                    session.say_statement(dialogue_response).boxed(),
-                                ));
-                            },
+                            responses.push((
+                                Response::from(dialogue_question),
+                                session.say_statement(dialogue_response).boxed(),
+                            ));
                         }
 
                         // Allow asking where the courier target is, but only
@@ -269,6 +267,9 @@ pub fn general<S: State>(tgt: ActorId, session: DialogueSession) -> impl Action<
                         }
                     }
                 },
+                QuestKind::Gather { .. } | QuestKind::Explore { .. } | QuestKind::Defend { .. } => {
+                    responses.extend(quest::quester_responses(ctx, quest_id, session));
+                },
                 _ => {},
             }
         }
@@ -297,6 +298,10 @@ pub fn general<S: State>(tgt: ActorId, session: DialogueSession) -> impl Action<
             Response::from(Content::localized("dialogue-question-sentiment")),
             dialogue::sentiments(tgt, session).boxed(),
         ));
+        responses.push((
+            Response::from(Content::localized("dialogue-question-reputation")),
+            dialogue::reputation(tgt, session).boxed(),
+        ));
 
         // Local activities
         responses.push((
@@ -312,7 +317,17 @@ pub fn general<S: State>(tgt: ActorId, session: DialogueSession) -> impl Action<
                 .boxed(),
         ));
 
-        session.ask_question(Content::localized("npc-question-general"), responses)
+        // Characters with a notable reputation get a different greeting
+        let standing = ctx.data.reputation_standing(ctx.actor, tgt);
+        let question = if standing >= Sentiment::FRIEND {
+            "npc-question-general-admired"
+        } else if standing <= Sentiment::RIVAL {
+            "npc-question-general-distrusted"
+        } else {
+            "npc-question-general"
+        };
+
+        session.ask_question(Content::localized(question), responses)
     })
 }
 
@@ -454,6 +469,24 @@ fn sentiments<S: State>(tgt: ActorId, session: DialogueSession) -> impl Action<S
     )])
 }
 
+fn reputation<S: State>(tgt: ActorId, session: DialogueSession) -> impl Action<S> {
+    now(move |ctx, _| {
+        let level = ReputationLevel::from_value(ctx.data.reputation_standing(ctx.actor, tgt));
+        if let Some(site_name) = util::site_name(ctx, ctx.actor.home) {
+            session.say_statement(
+                Content::localized("npc-response-reputation")
+                    .with_arg("site", site_name)
+                    .with_arg("level", Content::localized(level.localization_key())),
+            )
+        } else {
+            session.say_statement(
+                Content::localized("npc-response-reputation-homeless")
+                    .with_arg("level", Content::localized(level.localization_key())),
+            )
+        }
+    })
+}
+
 fn hire<S: State>(tgt: ActorId, session: DialogueSession) -> impl Action<S> {
     now(move |ctx, _| {
         if ctx.npc.job.is_none() && ctx.actor.rng(38792).random_bool(0.5) {
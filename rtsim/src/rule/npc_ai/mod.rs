@@ -79,6 +79,7 @@ use vek::*;
 use world::{
     IndexRef, World,
     civ::{self, Track},
+    pathfinding::{SearchCfg, Searcher},
     site::{self, PlotKind, Site as WorldSite, SiteKind, Structure, TileKind, plot::tavern},
     util::NEIGHBORS,
 };
@@ -218,7 +219,9 @@ fn idle<S: State>() -> impl Action<S> + Clone {
 
 fn talk_to<S: State>(tgt: ActorId) -> impl Action<S> {
     now(move |ctx, _| {
-        if ctx.sentiments.toward(tgt).is(Sentiment::ENEMY) {
+        if ctx.sentiments.toward(tgt).is(Sentiment::ENEMY)
+            || ctx.data.reputation_standing(ctx.actor, tgt) <= Sentiment::ENEMY
+        {
             just(move |ctx, _| {
                 ctx.controller
                     .say(tgt, Content::localized("npc-speech-reject_rival"))
@@ -488,6 +491,8 @@ fn pirate(is_leader: bool) -> impl Action<DefaultState> {
                     }
                 })
                 .debug(|| "preparing for raid")
+                // Residents of the target can ask for help once the raid has started
+                .then(just(move |ctx, _| ctx.controller.set_raid(site_to_raid)))
                 .then(travel_to_site(site_to_raid, 0.8).debug(|| "travel to raid site"))
                 .then(
                     // TODO: Replace this with raiding stuff
@@ -495,6 +500,7 @@ fn pirate(is_leader: bool) -> impl Action<DefaultState> {
                         .stop_if(timeout(ctx.rng.random_range(60.0..120.0)))
                         .debug(|| "raiding"),
                 )
+                .then(just(|ctx, _| ctx.controller.set_raid(None)))
                 .then(travel_to_site(home, 0.6).debug(|| "traveling home from raid"))
                 // End hiring of hirlings
                 .then(just(|ctx, _| {
@@ -1486,7 +1492,12 @@ fn check_for_enemies<S: State>(ctx: &mut NpcCtx) -> Option<impl Action<S> + use<
     ctx.data
         .actors
         .nearby(Some(ctx.actor_id), ctx.actor.wpos, 24.0)
-        .find(|actor| ctx.sentiments.toward(*actor).is(Sentiment::ENEMY))
+        .find(|actor| {
+            ctx.sentiments.toward(*actor).is(Sentiment::ENEMY)
+                // Guards also protect their home from characters with a terrible reputation
+                || (matches!(ctx.actor.profession(), Some(Profession::Guard))
+                    && ctx.data.reputation_standing(ctx.actor, *actor) <= Sentiment::ENEMY)
+        })
         .map(|enemy| just(move |ctx, _| ctx.controller.attack(enemy)))
 }
 
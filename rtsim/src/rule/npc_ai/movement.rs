@@ -175,6 +175,21 @@ fn path_between_towns(
     }
 }
 
+/// Find a route across the land between two positions, avoiding water and
+/// cliffs and preferring roads.
+fn path_across_land(start: Vec2<f32>, end: Vec2<f32>, world: &World) -> Option<Path<Vec2<i32>>> {
+    // Enough to cross a few hundred chunks of open land
+    const MAX_ITERS: usize = 20_000;
+
+    Searcher::new(world.sim(), SearchCfg::default())
+        .with_max_iters(MAX_ITERS)
+        .search(
+            start.as_::<i32>().wpos_to_cpos(),
+            end.as_::<i32>().wpos_to_cpos(),
+        )
+        .map(|(path, _cost)| path)
+}
+
 // Actions
 
 /// Try to walk toward a 3D position without caring for obstacles.
@@ -466,8 +481,31 @@ pub fn travel_to_site<S: State>(tgt_site: SiteId, speed_factor: f32) -> impl Act
             }, speed_factor)
                 .boxed()
         } else if let Some(site) = sites.get(tgt_site) {
-            // If all else fails, just walk toward the target site in a straight line
-            travel_to_point(site.wpos.map(|e| e as f32 + 0.5), speed_factor).debug(|| "travel to point fallback").boxed()
+            let tgt_wpos = site.wpos.map(|e| e as f32 + 0.5);
+            // Otherwise, try to find a route across the land
+            if let Some(route) = path_across_land(ctx.actor.wpos.xy(), tgt_wpos, ctx.world) {
+                let mut waypoints = route.nodes
+                    .into_iter()
+                    // Skip the chunk we're already in
+                    .skip(1)
+                    .map(|chunk| {
+                        let chunk_wpos = TerrainChunkSize::center_wpos(chunk);
+                        ctx.world.sim()
+                            .get_nearest_path(chunk_wpos)
+                            .map_or(chunk_wpos, |(_, wpos, _, _)| wpos.as_())
+                            .as_::<f32>()
+                    })
+                    .chain([tgt_wpos])
+                    .collect::<Vec<_>>()
+                    .into_iter();
+
+                traverse_points(move |_| waypoints.next(), speed_factor)
+                    .debug(|| "travel across land")
+                    .boxed()
+            } else {
+                // If all else fails, just walk toward the target site in a straight line
+                travel_to_point(tgt_wpos, speed_factor).debug(|| "travel to point fallback").boxed()
+            }
         } else {
             // If we can't find a way to get to the site at all, there's nothing more to be done
             finish().boxed()
@@ -1,11 +1,11 @@
 use super::*;
 use crate::data::quest::{
-    COURIER_QUEST_VARIANTS, CourierQuest, CourierQuestInstance, Payload, Recipient,
+    CourierQuest, CourierQuestInstance, ExploreKind, ExploreTarget, PayloadDef, QuestManifest,
+    Recipient,
 };
 use common::{
     comp::{Item, item::ItemBase},
     rtsim::ActorId,
-    spot::Spot,
 };
 use std::num::NonZeroU32;
 
@@ -108,47 +108,16 @@ pub fn resolve_take_deposit(
 }
 
 /// Checks if a courier quest can be completed based on inventory and entity
-/// presence.
-///
-/// The inventory check/consume operation is atomic. All inventory items'
-/// presence are verified first, then the items are subsequently removed in the
-/// same transaction/lock.
-///
-/// That being said, if `read_only` is true, the inventory will not be modified,
-/// only checked.
+/// presence, see [`take_items`].
 ///
 /// This should support checking for completion regardless of if it's being
 /// completed by a player or an rtsim NPC.
 pub fn finalize_courier_task(ctx: &mut NpcCtx, quest_id: QuestId, read_only: bool) -> bool {
-    fn required_count(raw: f32) -> u32 {
-        debug_assert!(
-            raw.is_finite() && raw >= 0.0,
-            "courier quest required amount must be finite and non-negative, got {raw}",
-        );
-        raw.round() as u32
-    }
-
     if let Some(quest) = ctx.data.quests.get(quest_id)
         && let QuestKind::Courier { instance } = &quest.kind
-        && let Some(entity) = ctx.system_data.id_maps.rtsim_entity(instance.messenger)
-        && let Ok(mut inventories) = ctx.system_data.inventories.lock()
-        && let Some(mut inv) = inventories.get_mut(entity)
-        && let Ok(required_items) = instance.get_required_items()
-        && required_items.iter().all(|(item_def, amount)| {
-            inv.item_count(item_def) >= u64::from(required_count(*amount))
-        })
-        && (read_only
-            || required_items.iter().all(|(item_def, amount)| {
-                inv.remove_item_amount(
-                    item_def,
-                    required_count(*amount),
-                    &ctx.system_data.ability_map,
-                    &ctx.system_data.msm,
-                )
-                .is_some()
-            }))
+        && let Some(required_items) = instance.get_required_items()
     {
-        true
+        take_items(ctx, instance.messenger, &required_items, read_only)
     } else {
         false
     }
@@ -170,6 +139,13 @@ pub fn create_quest<S: State>(quest: Quest) -> impl Action<S, QuestId> {
 
 pub fn quest_request<S: State>(session: DialogueSession) -> impl Action<S> {
     now(move |ctx, _| {
+        // Nobody here trusts characters with a bad reputation with anything
+        if ctx.data.reputation_standing(ctx.actor, session.target) <= Sentiment::RIVAL {
+            return session
+                .say_statement(Content::localized("npc-response-quest-reputation_too_low"))
+                .boxed();
+        }
+
         let mut quests = Vec::new();
 
         // Escort quest.
@@ -367,7 +343,7 @@ pub fn quest_request<S: State>(session: DialogueSession) -> impl Action<S> {
                     .and_then(move |yes| {
                         now(move |_ctx, _| {
                             if yes {
-                                let quest = Quest::courier(tgt_actor, courier_quest)
+                                let quest = Quest::courier(tgt_actor, courier_quest.clone())
                                     .with_deposit(COURIER_REWARD_ITEM, courier_quest.get_reward())
                                     .with_timeout(quest_exp);
                                 create_quest(quest)
@@ -446,6 +422,159 @@ pub fn quest_request<S: State>(session: DialogueSession) -> impl Action<S> {
             }
         }
 
+        // Gather quest
+        const GATHER_REWARD_ITEM: ItemResource = ItemResource::Coin;
+        let gather_template = QuestManifest::get().gather.choose(&mut ctx.rng).cloned();
+        if let Some(template) = gather_template
+            && let amount = ctx
+                .rng
+                .random_range(template.amount.0..=template.amount.1.max(template.amount.0))
+            && let gather_reward_amount = template.reward_per_item * amount as f32
+            && let Some(accept_quest) = create_deposit(
+                ctx,
+                GATHER_REWARD_ITEM,
+                gather_reward_amount,
+                session.ask_yes_no_question(
+                    Content::localized(template.ask.as_str())
+                        .with_arg("amount", amount)
+                        .with_arg("coins", gather_reward_amount as u64),
+                ),
+            )
+        {
+            let item = template.item;
+            quests.push(
+                accept_quest
+                    .and_then(move |yes| {
+                        now(move |ctx, _| {
+                            if yes {
+                                let quest =
+                                    Quest::gather(ctx.actor_id, session.target, item, amount)
+                                        .with_deposit(GATHER_REWARD_ITEM, gather_reward_amount)
+                                        .with_timeout(ctx.time.add_minutes(120.0));
+                                create_quest(quest)
+                                    .then(session.say_statement(Content::localized(
+                                        "npc-response-quest-gather-start",
+                                    )))
+                                    .boxed()
+                            } else {
+                                session
+                                    .say_statement(Content::localized(
+                                        "npc-response-quest-rejected",
+                                    ))
+                                    .boxed()
+                            }
+                        })
+                    })
+                    .boxed(),
+            );
+        }
+
+        // Explore quest
+        const EXPLORE_REWARD_ITEM: ItemResource = ItemResource::Coin;
+        if let Some((target, place, target_wpos, explore_reward_amount)) = roll_explore_target(ctx)
+            && let Some(accept_quest) = create_deposit(
+                ctx,
+                EXPLORE_REWARD_ITEM,
+                explore_reward_amount,
+                session.ask_yes_no_question(
+                    Content::localized("npc-response-quest-explore-ask")
+                        .with_arg("place", place.clone())
+                        .with_arg("coins", explore_reward_amount as u64),
+                ),
+            )
+        {
+            quests.push(
+                accept_quest
+                    .and_then(move |yes| {
+                        now(move |ctx, _| {
+                            if yes {
+                                let quest = Quest::explore(ctx.actor_id, session.target, target)
+                                    .with_deposit(EXPLORE_REWARD_ITEM, explore_reward_amount)
+                                    .with_timeout(ctx.time.add_minutes(120.0));
+                                let marker = explore_marker(ctx, target_wpos, place);
+                                create_quest(quest)
+                                    .and_then(move |quest_id| {
+                                        session.give_marker(marker.with_id(quest_id))
+                                    })
+                                    .then(session.say_statement(Content::localized(
+                                        "npc-response-quest-explore-start",
+                                    )))
+                                    .boxed()
+                            } else {
+                                session
+                                    .say_statement(Content::localized(
+                                        "npc-response-quest-rejected",
+                                    ))
+                                    .boxed()
+                            }
+                        })
+                    })
+                    .boxed(),
+            );
+        }
+
+        // Defend quest
+        const DEFEND_REWARD_ITEM: ItemResource = ItemResource::Coin;
+        let defend_template = QuestManifest::get().defend.clone();
+        if let Some(site) = ctx.actor.current_site
+            // Only residents care enough about the site to pay for its defence
+            && ctx.actor.home == Some(site)
+            // Don't ask somebody who is already defending the site
+            && !ctx.data.quests.related_to(session.target).any(|quest_id| {
+                ctx.data.quests.get(quest_id).is_some_and(|quest| {
+                    matches!(quest.kind, QuestKind::Defend { site: s, .. } if s == site)
+                })
+            })
+            && let raiders = site_raiders(ctx, site)
+            && raiders.len() >= defend_template.min_raiders.max(1)
+            && let defend_reward_amount = defend_template.reward_per_raider * raiders.len() as f32
+            && let Some(site_name) = util::site_name(ctx, site)
+            && let Some(accept_quest) = create_deposit(
+                ctx,
+                DEFEND_REWARD_ITEM,
+                defend_reward_amount,
+                session
+                    .say_statement(
+                        Content::localized("npc-response-quest-defend-raid")
+                            .with_arg("count", raiders.len() as u64),
+                    )
+                    .then(
+                        session.ask_yes_no_question(
+                            Content::localized("npc-response-quest-defend-ask")
+                                .with_arg("site", site_name)
+                                .with_arg("coins", defend_reward_amount as u64),
+                        ),
+                    ),
+            )
+        {
+            let time_limit = defend_template.time_limit;
+            quests.push(
+                accept_quest
+                    .and_then(move |yes| {
+                        now(move |ctx, _| {
+                            if yes {
+                                let quest =
+                                    Quest::defend(ctx.actor_id, session.target, site, raiders)
+                                        .with_deposit(DEFEND_REWARD_ITEM, defend_reward_amount)
+                                        .with_timeout(ctx.time.add_minutes(time_limit));
+                                create_quest(quest)
+                                    .then(session.say_statement(Content::localized(
+                                        "npc-response-quest-defend-start",
+                                    )))
+                                    .boxed()
+                            } else {
+                                session
+                                    .say_statement(Content::localized(
+                                        "npc-response-quest-rejected",
+                                    ))
+                                    .boxed()
+                            }
+                        })
+                    })
+                    .boxed(),
+            );
+        }
+
         if quests.is_empty() {
             session
                 .say_statement(Content::localized("npc-response-quest-nothing"))
@@ -484,14 +613,156 @@ pub fn check_for_timeouts<S: State>(ctx: &mut NpcCtx) -> Option<impl Action<S> +
                             .boxed(),
                     );
                 },
-                QuestKind::Slay { .. } => {},
-                QuestKind::Courier { .. } => {},
+                QuestKind::Slay { .. }
+                | QuestKind::Courier { .. }
+                | QuestKind::Gather { .. }
+                | QuestKind::Explore { .. }
+                | QuestKind::Defend { .. } => {},
             }
         }
     }
     None
 }
 
+/// Resolve a quest successfully and hand the deposit to the quester we're
+/// talking to.
+fn reward_quester<S: State>(quest_id: QuestId, session: DialogueSession) -> impl Action<S> {
+    now(
+        move |ctx, _| match resolve_take_deposit(ctx, quest_id, true) {
+            Ok(deposit) => session
+                .say_statement_with_gift(Content::localized("npc-response-quest-reward"), deposit)
+                .boxed(),
+            Err(()) => finish().boxed(),
+        },
+    )
+}
+
+fn explore_marker(ctx: &NpcCtx, wpos: Vec2<f32>, place: Content) -> Marker {
+    Marker::at(wpos)
+        .with_label(
+            Content::localized("hud-map-explore-label")
+                .with_arg(
+                    "name",
+                    ctx.actor
+                        .get_name()
+                        .unwrap_or_else(|| "<unknown>".to_string()),
+                )
+                .with_arg("place", place),
+        )
+        .with_quest_flag(true)
+}
+
+fn explore_target_name(ctx: &NpcCtx, target: &ExploreTarget) -> Content {
+    match target {
+        ExploreTarget::Site(site) => {
+            Content::Plain(util::site_name(ctx, *site).unwrap_or_else(|| "<unknown>".to_string()))
+        },
+        ExploreTarget::Spot { name, .. } => Content::localized(name),
+    }
+}
+
+/// The dialogue responses for the quester of a gather, explore or defend quest
+/// that we're the arbiter of.
+pub fn quester_responses<S: State>(
+    ctx: &NpcCtx,
+    quest_id: QuestId,
+    session: DialogueSession,
+) -> Vec<(Response, Box<dyn Action<S>>)> {
+    let mut responses = Vec::new();
+    let Some(quest) = ctx.data.quests.get(quest_id) else {
+        return responses;
+    };
+    if quest.arbiter != ctx.actor_id {
+        return responses;
+    }
+
+    match &quest.kind {
+        QuestKind::Gather {
+            gatherer,
+            item,
+            amount,
+        } if *gatherer == session.target => {
+            let amount = *amount;
+            let what_is_needed = QuestManifest::get()
+                .gather_template(item)
+                .map_or_else(
+                    || Content::localized("npc-response-quest-gather-what-is-needed"),
+                    |template| Content::localized(template.what_is_needed.as_str()),
+                )
+                .with_arg("amount", amount);
+            responses.push((
+                Response::from(Content::localized("dialogue-question-quest-gather-what")),
+                session.say_statement(what_is_needed).boxed(),
+            ));
+            responses.push((
+                Response::from(Content::localized("dialogue-question-quest-gather-claim")),
+                now(move |ctx, _| {
+                    if finalize_gather_task(ctx, quest_id, false) {
+                        session
+                            .say_statement(Content::localized("npc-response-quest-gather-thanks"))
+                            .then(reward_quester(quest_id, session))
+                            .boxed()
+                    } else {
+                        session
+                            .say_statement(
+                                Content::localized("npc-response-quest-gather-insufficient-items")
+                                    .with_arg("amount", amount),
+                            )
+                            .boxed()
+                    }
+                })
+                .boxed(),
+            ));
+        },
+        QuestKind::Explore { explorer, target } if *explorer == session.target => {
+            let place = explore_target_name(ctx, target);
+            if quest.goal_reached() {
+                responses.push((
+                    Response::from(
+                        Content::localized("dialogue-question-quest-explore-claim")
+                            .with_arg("place", place),
+                    ),
+                    session
+                        .say_statement(Content::localized("npc-response-quest-explore-thanks"))
+                        .then(reward_quester(quest_id, session))
+                        .boxed(),
+                ));
+            } else if let Some(wpos) = target.wpos(&ctx.data.sites) {
+                responses.push((
+                    Response::from(Content::localized("dialogue-question-quest-explore-where")),
+                    session
+                        .give_marker(explore_marker(ctx, wpos, place.clone()).with_id(quest_id))
+                        .then(
+                            session.say_statement(
+                                Content::localized("npc-response-quest-explore-where")
+                                    .with_arg("place", place),
+                            ),
+                        )
+                        .boxed(),
+                ));
+            }
+        },
+        QuestKind::Defend { defender, .. } if *defender == session.target => {
+            responses.push((
+                Response::from(Content::localized("dialogue-question-quest-defend-claim")),
+                if quest.goal_reached() {
+                    session
+                        .say_statement(Content::localized("npc-response-quest-defend-thanks"))
+                        .then(reward_quester(quest_id, session))
+                        .boxed()
+                } else {
+                    session
+                        .say_statement(Content::localized("npc-response-quest-defend-not-over"))
+                        .boxed()
+                },
+            ));
+        },
+        _ => {},
+    }
+
+    responses
+}
+
 pub fn escorted<S: State>(
     quest_id: QuestId,
     escorter: ActorId,
@@ -546,98 +817,121 @@ pub fn escorted<S: State>(
 /// checks.
 pub fn get_nearest_spot(
     ctx: &mut NpcCtx,
-    quest: CourierQuest,
+    quest: &CourierQuest,
     target_chunk: Vec2<i32>,
 ) -> Option<Vec2<i32>> {
-    match quest.payload() {
-        // These do not have spots
-        None | Some(Payload::LegoomLeaf) => None,
-        // Add more here later!
-        Some(Payload::GnarlingCarving) => ctx
-            .world
-            .sim()
-            .get_nearest_spot(target_chunk, |spot| matches!(spot, Spot::GnarlingTotem)),
-    }
+    let spot = QuestManifest::get()
+        .payload(quest.payload()?)?
+        .spot
+        .as_ref()?
+        .kind;
+    ctx.world
+        .sim()
+        .get_nearest_spot(target_chunk, |s| *s == spot)
 }
 
 const MAX_COURIER_QUEST_DISTANCE: f32 = 5_000.0;
+const MAX_EXPLORE_QUEST_DISTANCE: f32 = 5_000.0;
+
+/// Loads the item definitions of a list of item asset specifiers and amounts.
+fn load_items(items: &[(String, u32)]) -> Option<Vec<(Arc<ItemDef>, u32)>> {
+    items
+        .iter()
+        .map(|(item, amount)| Some((Arc::<ItemDef>::load_cloned(item).ok()?, *amount)))
+        .collect()
+}
+
+/// Checks whether an actor carries all of the given items.
+///
+/// The inventory check/consume operation is atomic. All inventory items'
+/// presence are verified first, then the items are subsequently removed in the
+/// same transaction/lock. If `read_only` is true, the inventory will not be
+/// modified, only checked.
+fn take_items(
+    ctx: &mut NpcCtx,
+    actor: ActorId,
+    items: &[(Arc<ItemDef>, u32)],
+    read_only: bool,
+) -> bool {
+    if let Some(entity) = ctx.system_data.id_maps.rtsim_entity(actor)
+        && let Ok(mut inventories) = ctx.system_data.inventories.lock()
+        && let Some(mut inv) = inventories.get_mut(entity)
+        && items
+            .iter()
+            .all(|(item_def, amount)| inv.item_count(item_def) >= u64::from(*amount))
+        && (read_only
+            || items.iter().all(|(item_def, amount)| {
+                inv.remove_item_amount(
+                    item_def,
+                    *amount,
+                    &ctx.system_data.ability_map,
+                    &ctx.system_data.msm,
+                )
+                .is_some()
+            }))
+    {
+        true
+    } else {
+        false
+    }
+}
+
+/// Checks if a gather quest can be completed, taking the gathered items from
+/// the gatherer unless `read_only` is true.
+pub fn finalize_gather_task(ctx: &mut NpcCtx, quest_id: QuestId, read_only: bool) -> bool {
+    if let Some(quest) = ctx.data.quests.get(quest_id)
+        && let QuestKind::Gather {
+            gatherer,
+            item,
+            amount,
+        } = &quest.kind
+        && let Some(items) = load_items(&[(item.clone(), *amount)])
+    {
+        take_items(ctx, *gatherer, &items, read_only)
+    } else {
+        false
+    }
+}
 
 /// This file only contains an implementation for quest interactions. Make sure
 /// to look for other implementations.
 impl CourierQuestInstance {
+    /// Looks up an i18n key of the payload definition, `None` for messenger
+    /// quests and unknown payloads.
+    fn payload_content(&self, key: impl FnOnce(&PayloadDef) -> Option<&str>) -> Option<Content> {
+        let manifest = QuestManifest::get();
+        key(manifest.payload(self.kind.payload()?)?).map(Content::localized)
+    }
+
     /// Returns a list of all items that are required for completing this
-    /// courier quest.
-    pub fn get_required_items(self) -> Result<Vec<(Arc<ItemDef>, f32)>, common::assets::Error> {
+    /// courier quest, or `None` if the payload no longer exists.
+    pub fn get_required_items(&self) -> Option<Vec<(Arc<ItemDef>, u32)>> {
         match self.kind.payload() {
-            None => Ok(vec![]),
-            Some(Payload::GnarlingCarving) => Ok(vec![(
-                Arc::<ItemDef>::load_cloned("common.items.quest.gnarling_carving")?,
-                1.0_f32,
-            )]),
-            Some(Payload::LegoomLeaf) => Ok(vec![(
-                Arc::<ItemDef>::load_cloned("common.items.quest.legoom_leaf")?,
-                1.0_f32,
-            )]),
+            None => Some(Vec::new()),
+            Some(payload) => load_items(&QuestManifest::get().payload(payload)?.items),
         }
     }
 
     /// Returns the number of coins that the quest arbiter must pay upon courier
     /// quest completion. Note that in some cases the arbiter is not the
     /// person that paid the quest deposit.
-    pub fn get_reward(self) -> f32 {
-        match self.kind {
-            CourierQuest::Message => f32::max(
-                150.0,
-                1000.0 * (self.distance.get() as f32 / MAX_COURIER_QUEST_DISTANCE),
-            ),
-            CourierQuest::Deliver {
-                payload: Payload::GnarlingCarving,
-                recipient: Recipient::Other,
-            } => f32::max(
-                500.0,
-                1400.0 * (self.distance.get() as f32 / MAX_COURIER_QUEST_DISTANCE),
-            ),
-            CourierQuest::Deliver {
-                payload: Payload::GnarlingCarving,
-                recipient: Recipient::Giver,
-            } => 350.0,
-            CourierQuest::Deliver {
-                payload: Payload::LegoomLeaf,
-                recipient: Recipient::Other,
-            } => f32::max(
-                400.0,
-                1200.0 * (self.distance.get() as f32 / MAX_COURIER_QUEST_DISTANCE),
-            ),
-            CourierQuest::Deliver {
-                payload: Payload::LegoomLeaf,
-                recipient: Recipient::Giver,
-            } => 200.0,
-        }
-    }
+    pub fn get_reward(&self) -> f32 { self.reward }
 
     /// Retrieves the i18n content that will be shown on the map when hovering
     /// over the courier quest's map marker.
-    pub fn get_spot_map_label(self, npc_name: &str) -> Content {
-        Content::localized(match self.kind.payload() {
-            Some(Payload::GnarlingCarving) => "hud-map-spot-gnarling-carving-label",
-            // These shouldn't be encountered since they don't have spot requirements:
-            None | Some(Payload::LegoomLeaf) => "hud-map-spot-unspecified",
-        })
-        .with_arg("name", npc_name)
+    pub fn get_spot_map_label(&self, npc_name: &str) -> Content {
+        self.payload_content(|payload| Some(payload.spot.as_ref()?.map_label.as_str()))
+            // This shouldn't be encountered since only quests with a spot have spot markers
+            .unwrap_or_else(|| Content::localized("hud-map-spot-unspecified"))
+            .with_arg("name", npc_name)
     }
 
     /// "You don't have enough items on you to complete this quest."
-    pub fn lacks_items(self) -> Content {
-        Content::localized(match self.kind.payload() {
-            Some(Payload::GnarlingCarving) => {
-                "npc-response-quest-courier-gnarling-carving-insufficient-items"
-            },
-            Some(Payload::LegoomLeaf) => {
-                "npc-response-quest-courier-legoom-leaf-insufficient-items"
-            },
-            // For quests that do not require items, use this arm.
-            None => "npc-response-quest-courier-generic-insufficient-items",
-        })
+    pub fn lacks_items(&self) -> Content {
+        self.payload_content(|payload| Some(payload.insufficient_items.as_str()))
+            .unwrap_or_else(|| {
+                Content::localized("npc-response-quest-courier-generic-insufficient-items")
+            })
     }
 
     /// Assembles the dialogue question and response when asking what items
@@ -645,7 +939,7 @@ impl CourierQuestInstance {
     ///
     /// "What am I supposed to be getting for you/target again?"
     /// "You need X, Y, and Z to complete this courier quest."
-    pub fn what_items_needed(self, is_target_npc: bool, npc_name: &str) -> (Content, Content) {
+    pub fn what_items_needed(&self, is_target_npc: bool, npc_name: &str) -> (Content, Content) {
         (
             Content::localized(match self.kind {
                 CourierQuest::Deliver {
@@ -671,31 +965,31 @@ impl CourierQuestInstance {
                 },
             })
             .with_arg("name", npc_name),
-            Content::localized(match self.kind.payload() {
-                Some(Payload::GnarlingCarving) => {
-                    "npc-response-quest-courier-gnarling-carving-what-is-needed"
-                },
-                Some(Payload::LegoomLeaf) => {
-                    "npc-response-quest-courier-legoom-leaf-what-is-needed"
-                },
-                None => {
-                    if is_target_npc {
+            self.payload_content(|payload| Some(payload.what_is_needed.as_str()))
+                .unwrap_or_else(|| {
+                    Content::localized(if is_target_npc {
                         "npc-response-quest-messenger-what-is-needed-target"
                     } else {
                         "npc-response-quest-messenger-what-is-needed"
-                    }
-                },
-            })
-            .with_arg("name", npc_name),
+                    })
+                })
+                .with_arg("name", npc_name),
         )
     }
 
     /// Retrieves the i18n content for the name of the spot, or a generic
     /// response if the courier quest variant does not need a spot.
-    pub fn get_spot_name(self) -> Content {
-        Content::localized(match self.kind.payload() {
-            Some(Payload::GnarlingCarving) => "spot-name-gnarling-totem",
-            None | Some(Payload::LegoomLeaf) => "spot-name-unspecified",
+    pub fn get_spot_name(&self) -> Content {
+        self.payload_content(|payload| Some(payload.spot.as_ref()?.name.as_str()))
+            .unwrap_or_else(|| Content::localized("spot-name-unspecified"))
+    }
+
+    /// Whether the items of the quest need to be fetched from a spot.
+    pub fn needs_spot(&self) -> bool {
+        self.kind.payload().is_some_and(|payload| {
+            QuestManifest::get()
+                .payload(payload)
+                .is_some_and(|payload| payload.spot.is_some())
         })
     }
 
@@ -706,58 +1000,42 @@ impl CourierQuestInstance {
     /// Note that not every quest uses the target npc name or the target site
     /// name.
     pub fn get_start_dialogue(
-        self,
+        &self,
         tgt_npc_name_str: &str,
         tgt_site_name: &str,
     ) -> (Content, Content) {
-        const COURIER_GNARLING_CARVING_START_STMT: &str =
-            "npc-response-quest-courier-gnarling-carving";
-        const COURIER_LEGOOM_LEAF_START_STMT: &str = "npc-response-quest-courier-legoom-leaf";
-        const MESSENGER_SEND_WORD_START_STMT: &str = "npc-response-quest-messenger-send-word";
-
-        match self.kind {
-            CourierQuest::Deliver {
-                payload: Payload::GnarlingCarving,
-                recipient: Recipient::Other,
-            } => (
-                Content::localized(COURIER_GNARLING_CARVING_START_STMT),
-                Content::localized("npc-response-quest-spot-courier-ask")
-                    .with_arg("spot", self.get_spot_name())
-                    .with_arg("coins", self.get_reward() as u64)
-                    .with_arg("name", tgt_npc_name_str)
-                    .with_arg("site", tgt_site_name),
-            ),
-            CourierQuest::Deliver {
-                payload: Payload::GnarlingCarving,
-                recipient: Recipient::Giver,
-            } => (
-                Content::localized(COURIER_GNARLING_CARVING_START_STMT),
-                Content::localized("npc-response-quest-spot-fetch-ask")
-                    .with_arg("spot", self.get_spot_name())
-                    .with_arg("coins", self.get_reward() as u64),
-            ),
-            CourierQuest::Deliver {
-                payload: Payload::LegoomLeaf,
-                recipient: Recipient::Other,
-            } => (
-                Content::localized(COURIER_LEGOOM_LEAF_START_STMT),
-                Content::localized("npc-response-quest-courier-ask")
-                    .with_arg("coins", self.get_reward() as u64)
-                    .with_arg("name", tgt_npc_name_str)
-                    .with_arg("site", tgt_site_name),
-            ),
-            CourierQuest::Deliver {
-                payload: Payload::LegoomLeaf,
-                recipient: Recipient::Giver,
-            } => (
-                Content::localized(COURIER_LEGOOM_LEAF_START_STMT),
-                Content::localized("npc-response-quest-fetch-ask")
-                    .with_arg("coins", self.get_reward() as u64),
-            ),
+        let coins = self.get_reward() as u64;
+        match &self.kind {
+            CourierQuest::Deliver { recipient, .. } => {
+                let start_stmt = self
+                    .payload_content(|payload| Some(payload.start.as_str()))
+                    .unwrap_or_else(|| Content::localized("npc-response-quest-nothing"));
+                let question = match (recipient, self.needs_spot()) {
+                    (Recipient::Other, true) => {
+                        Content::localized("npc-response-quest-spot-courier-ask")
+                            .with_arg("spot", self.get_spot_name())
+                    },
+                    (Recipient::Other, false) => {
+                        Content::localized("npc-response-quest-courier-ask")
+                    },
+                    (Recipient::Giver, true) => {
+                        Content::localized("npc-response-quest-spot-fetch-ask")
+                            .with_arg("spot", self.get_spot_name())
+                    },
+                    (Recipient::Giver, false) => Content::localized("npc-response-quest-fetch-ask"),
+                };
+                let question = match recipient {
+                    Recipient::Other => question
+                        .with_arg("name", tgt_npc_name_str)
+                        .with_arg("site", tgt_site_name),
+                    Recipient::Giver => question,
+                };
+                (start_stmt, question.with_arg("coins", coins))
+            },
             CourierQuest::Message => (
-                Content::localized(MESSENGER_SEND_WORD_START_STMT),
+                Content::localized("npc-response-quest-messenger-send-word"),
                 Content::localized("npc-response-quest-messenger-ask")
-                    .with_arg("coins", self.get_reward() as u64)
+                    .with_arg("coins", coins)
                     .with_arg("name", tgt_npc_name_str)
                     .with_arg("site", tgt_site_name),
             ),
@@ -767,7 +1045,7 @@ impl CourierQuestInstance {
     /// "Where is my target again?"
     /// Map gets marked with a marker, and the NPC responds with their location.
     pub fn get_dialogue_where_target(
-        self,
+        &self,
         npc_name: &str,
         at: Vec2<f32>,
         target: ActorId,
@@ -788,7 +1066,7 @@ impl CourierQuestInstance {
     /// Returns the dialogue question and response that an entity will use when
     /// the courier quest's messenger is speaking to the quest target and is
     /// attempting to finish the quest (claim the reward).
-    pub fn get_courier_claim_dialogue(self) -> (Content, Content) {
+    pub fn get_courier_claim_dialogue(&self) -> (Content, Content) {
         (
             Content::localized("dialogue-question-quest-courier-claim"),
             Content::localized("npc-response-quest-courier-thanks"),
@@ -798,7 +1076,7 @@ impl CourierQuestInstance {
     /// Generates a map marker that represents the position of the courier
     /// quest's targeted spot's position.
     pub fn get_quest_spot_start_marker(
-        self,
+        &self,
         at: Vec2<f32>,
         tgt_npc_name: &str,
         quest_id: QuestId,
@@ -813,7 +1091,7 @@ impl CourierQuestInstance {
     /// Generates a map marker that represents the position of the courier
     /// quest's target entity.
     pub fn get_quest_actor_target_marker(
-        self,
+        &self,
         at: Vec2<f32>,
         tgt_actor_name: &str,
         target_actor_id: ActorId,
@@ -830,10 +1108,8 @@ impl CourierQuestInstance {
 
 /// Attempts to build a valid courier quest.
 fn roll_courier_quest(ctx: &mut NpcCtx, messenger: ActorId) -> Option<CourierQuestInstance> {
-    let kind = COURIER_QUEST_VARIANTS
-        .choose(&mut ctx.rng)
-        .copied()
-        .unwrap_or(CourierQuest::Message);
+    let template = QuestManifest::get().courier.choose(&mut ctx.rng)?.clone();
+    let kind = template.kind;
 
     let (target_site, target_actor, distance) = match kind {
         // target and source are the same npc for this kind of courier quest
@@ -878,15 +1154,14 @@ fn roll_courier_quest(ctx: &mut NpcCtx, messenger: ActorId) -> Option<CourierQue
             })?,
     };
 
-    let spot = get_nearest_spot(ctx, kind, ctx.actor.wpos.xy().wpos_to_cpos().as_());
-
-    // check if the payload necessitates visiting a spot. Make sure to add more
-    // here later (the compiler will guide you), and avoid using `_` match arms
-    // please... otherwise the compiler won't guide you
-    if match kind.payload() {
-        Some(Payload::GnarlingCarving) => spot.is_none(),
-        Some(Payload::LegoomLeaf) | None => false,
-    } {
+    // Payloads that have been removed from the manifest can't be delivered
+    let needs_spot = match kind.payload() {
+        Some(payload) => QuestManifest::get().payload(payload)?.spot.is_some(),
+        None => false,
+    };
+    let spot = get_nearest_spot(ctx, &kind, ctx.actor.wpos.xy().wpos_to_cpos().as_());
+    // check if the payload necessitates visiting a spot
+    if needs_spot && spot.is_none() {
         return None;
     }
 
@@ -900,5 +1175,65 @@ fn roll_courier_quest(ctx: &mut NpcCtx, messenger: ActorId) -> Option<CourierQue
         target_site,
         messenger,
         distance: NonZeroU32::new(distance as u32).unwrap_or(ONE),
+        reward: template
+            .reward
+            .amount(distance / MAX_COURIER_QUEST_DISTANCE),
     })
 }
+
+/// Attempts to find a place to explore: a site that is neither too close nor
+/// too far away, or the nearest spot of some kind.
+fn roll_explore_target(ctx: &mut NpcCtx) -> Option<(ExploreTarget, Content, Vec2<f32>, f32)> {
+    let template = QuestManifest::get().explore.choose(&mut ctx.rng)?.clone();
+    let (target, name) = match template.target {
+        ExploreKind::Site => {
+            let (site_id, _) = ctx
+                .data
+                .sites
+                .iter()
+                .filter(|(site_id, site)| {
+                    Some(*site_id) != ctx.actor.current_site
+                        && (1000.0..MAX_EXPLORE_QUEST_DISTANCE)
+                            .contains(&site.wpos.as_().distance(ctx.actor.wpos.xy()))
+                })
+                .choose(&mut ctx.rng)?;
+            (
+                ExploreTarget::Site(site_id),
+                Content::Plain(util::site_name(ctx, site_id)?),
+            )
+        },
+        ExploreKind::Spot { kind, name } => {
+            let chunk = ctx
+                .world
+                .sim()
+                .get_nearest_spot(ctx.actor.wpos.xy().wpos_to_cpos().as_(), |s| *s == kind)?;
+            (
+                ExploreTarget::Spot {
+                    chunk,
+                    name: name.clone(),
+                },
+                Content::localized(name),
+            )
+        },
+    };
+    let wpos = target.wpos(&ctx.data.sites)?;
+    let distance = wpos.distance(ctx.actor.wpos.xy());
+    if distance > MAX_EXPLORE_QUEST_DISTANCE {
+        return None;
+    }
+    let reward = template
+        .reward
+        .amount(distance / MAX_EXPLORE_QUEST_DISTANCE);
+    Some((target, name, wpos, reward))
+}
+
+/// The raiders of the largest raid currently led against a site, the leader
+/// first. Empty if the site isn't being raided, see [`crate::data::Raids`].
+pub fn site_raiders(ctx: &NpcCtx, site: SiteId) -> Vec<ActorId> {
+    ctx.data
+        .raids
+        .against(site)
+        .map(|leader| ctx.data.raids.raiders(&ctx.data.actors, leader))
+        .max_by_key(Vec::len)
+        .unwrap_or_default()
+}
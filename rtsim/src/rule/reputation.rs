@@ -0,0 +1,291 @@
+//! Player reputation with sites and factions.
+//!
+//! Sentiments are individual and short-lived: an NPC that saw a player steal
+//! from a market stall will eventually forget about it, and NPCs that weren't
+//! around will never know. Reputation is the collective memory of a site or
+//! faction about a character. It is changed directly by notable deeds, drifts
+//! toward the average opinion of the residents of a site and, in turn, slowly
+//! rubs off on the sentiments of residents that have no opinion of their own
+//! yet.
+
+use crate::{
+    RtState, Rule, RuleError,
+    data::{Data, Factions, Sentiment, Sites},
+    event::{EventCtx, OnDeath, OnHelped, OnTheft, OnTick},
+};
+use common::rtsim::{ActorId, PersistedReputation, Profession, Reputation, Role, SiteId};
+use rand::prelude::*;
+use rand_chacha::ChaChaRng;
+
+/// Only update the reputation of each character every few ticks.
+const REPUTATION_TICK_SKIP: u64 = 300;
+/// How far reputation moves toward the collective opinion on each update.
+const REPUTATION_DRIFT: f32 = 0.005;
+/// How much a single resident's sentiment is nudged toward the reputation on
+/// each update.
+const SENTIMENT_SPREAD: f32 = 0.02;
+
+/// Reputation lost with the home site and faction of a murdered NPC.
+const MURDER_PENALTY: f32 = 0.3;
+/// Reputation lost with a site and its faction when stealing from it.
+const THEFT_PENALTY: f32 = 0.05;
+/// Reputation gained with a site for defending it from monsters and outlaws.
+const DEFENCE_BONUS: f32 = 0.01;
+/// Reputation gained with the home site and faction of an NPC that was
+/// helped.
+const HELP_BONUS: f32 = 0.05;
+
+pub struct UpdateReputation;
+
+impl Rule for UpdateReputation {
+    fn start(rtstate: &mut RtState) -> Result<Self, RuleError> {
+        rtstate.bind::<Self, OnDeath>(on_death);
+        rtstate.bind::<Self, OnTheft>(on_theft);
+        rtstate.bind::<Self, OnHelped>(on_helped);
+        rtstate.bind::<Self, OnTick>(on_tick);
+
+        Ok(Self)
+    }
+}
+
+fn on_death(ctx: EventCtx<UpdateReputation, OnDeath>) {
+    let data = &mut *ctx.state.data_mut();
+
+    let Some(killer) = ctx.event.killer else {
+        return;
+    };
+    let Some(victim) = data.actors.get(ctx.event.actor) else {
+        return;
+    };
+    let (home, faction) = (victim.home, victim.faction);
+    let is_outlaw = matches!(
+        victim.role,
+        Role::Monster | Role::Civilised(Some(Profession::Pirate(_) | Profession::Cultist))
+    );
+    let is_npc = victim.npc().is_some();
+
+    let Some(killer) = data.actors.get_mut(killer) else {
+        return;
+    };
+    let current_site = killer.current_site;
+    let Some(character) = killer.character_mut() else {
+        return;
+    };
+
+    if is_outlaw {
+        // Outlaws don't take kindly to losing their own, but the site that the
+        // character was defending is grateful
+        if let Some(faction) = faction {
+            character
+                .reputation
+                .change_faction(faction, -MURDER_PENALTY);
+        }
+        if let Some(site) = current_site.filter(|site| Some(*site) != home) {
+            character.reputation.change_site(site, DEFENCE_BONUS);
+        }
+    } else if is_npc {
+        if let Some(home) = home {
+            character.reputation.change_site(home, -MURDER_PENALTY);
+        }
+        if let Some(faction) = faction {
+            character
+                .reputation
+                .change_faction(faction, -MURDER_PENALTY);
+        }
+    }
+}
+
+fn on_theft(ctx: EventCtx<UpdateReputation, OnTheft>) {
+    let data = &mut *ctx.state.data_mut();
+
+    let Some(site) = ctx.event.site else {
+        return;
+    };
+    let faction = data.sites.get(site).and_then(|site| site.faction);
+    if let Some(character) = data
+        .actors
+        .get_mut(ctx.event.actor)
+        .and_then(|actor| actor.character_mut())
+    {
+        character.reputation.change_site(site, -THEFT_PENALTY);
+        if let Some(faction) = faction {
+            character.reputation.change_faction(faction, -THEFT_PENALTY);
+        }
+    }
+}
+
+fn on_helped(ctx: EventCtx<UpdateReputation, OnHelped>) {
+    let data = &mut *ctx.state.data_mut();
+
+    let Some(saver) = ctx.event.saver else {
+        return;
+    };
+    let Some((home, faction)) = data
+        .actors
+        .get(ctx.event.actor)
+        .filter(|actor| actor.npc().is_some())
+        .map(|actor| (actor.home, actor.faction))
+    else {
+        return;
+    };
+    if let Some(character) = data
+        .actors
+        .get_mut(saver)
+        .and_then(|actor| actor.character_mut())
+    {
+        if let Some(home) = home {
+            character.reputation.change_site(home, HELP_BONUS);
+        }
+        if let Some(faction) = faction {
+            character.reputation.change_faction(faction, HELP_BONUS);
+        }
+    }
+}
+
+fn on_tick(ctx: EventCtx<UpdateReputation, OnTick>) {
+    let data = &mut *ctx.state.data_mut();
+    let mut rng = ChaChaRng::from_seed(rand::rng().random::<[u8; 32]>());
+
+    let characters = data
+        .actors
+        .iter()
+        .filter(|(_, actor)| actor.character().is_some() && actor.presence.is_some())
+        // Only every few ticks
+        .filter(|(_, actor)| {
+            (actor.seed as u64 + ctx.event.tick).is_multiple_of(REPUTATION_TICK_SKIP)
+        })
+        .filter_map(|(id, actor)| Some((id, actor.current_site?)))
+        .collect::<Vec<_>>();
+
+    for (character_id, site_id) in characters {
+        update_site_reputation(data, &mut rng, character_id, site_id);
+    }
+}
+
+/// Let the reputation of a character with the site they are in and the
+/// faction that owns it converge with the sentiments of its residents.
+fn update_site_reputation(
+    data: &mut Data,
+    rng: &mut impl Rng,
+    character_id: ActorId,
+    site_id: SiteId,
+) {
+    let Some(site) = data.sites.get(site_id) else {
+        return;
+    };
+    let faction_id = site.faction;
+
+    // The collective opinion of the residents, residents that never heard of the
+    // character count as neutral
+    let residents = site
+        .population
+        .iter()
+        .filter_map(|id| Some((*id, data.actors.get(*id)?.npc()?)))
+        .collect::<Vec<_>>();
+    let opinion = if residents.is_empty() {
+        0.0
+    } else {
+        residents
+            .iter()
+            .map(|(_, npc)| npc.sentiments.toward(character_id).value())
+            .sum::<f32>()
+            / residents.len() as f32
+    };
+    let spread_to = residents.choose(rng).map(|(id, _)| *id);
+    let faction_opinion = faction_id
+        .and_then(|faction| data.factions.get(faction))
+        .map(|faction| faction.sentiments.toward(character_id).value());
+
+    let Some(character) = data
+        .actors
+        .get_mut(character_id)
+        .and_then(|actor| actor.character_mut())
+    else {
+        return;
+    };
+    let reputation = &mut character.reputation;
+    let site_reputation = reputation.site(site_id);
+    reputation.change_site(site_id, (opinion - site_reputation) * REPUTATION_DRIFT);
+    let site_reputation = reputation.site(site_id);
+
+    // Factions judge characters by what their own sites think of them too
+    let faction_reputation = faction_id.zip(faction_opinion).map(|(faction, opinion)| {
+        let target = (opinion + site_reputation) * 0.5;
+        reputation.change_faction(
+            faction,
+            (target - reputation.faction(faction)) * REPUTATION_DRIFT,
+        );
+        (faction, reputation.faction(faction))
+    });
+
+    // Word gets around: residents and the faction pick up on the reputation
+    if let Some(npc) = spread_to
+        .and_then(|id| data.actors.get_mut(id))
+        .and_then(|actor| actor.npc_mut())
+    {
+        spread(npc.sentiments.toward_mut(character_id), site_reputation);
+    }
+    if let Some((faction_id, faction_reputation)) = faction_reputation
+        && let Some(faction) = data.factions.get_mut(faction_id)
+    {
+        spread(
+            faction.sentiments.toward_mut(character_id),
+            faction_reputation,
+        );
+    }
+}
+
+/// Nudge a sentiment toward a reputation, without overriding stronger
+/// sentiments.
+fn spread(sentiment: &mut Sentiment, reputation: f32) {
+    if reputation.abs() >= Sentiment::POSITIVE
+        && (reputation - sentiment.value()) * reputation.signum() > 0.0
+    {
+        sentiment.change_by(SENTIMENT_SPREAD * reputation.signum(), reputation);
+    }
+}
+
+/// The reputation of a character, keyed by identifiers that survive a
+/// regeneration of the rtsim data.
+pub fn persist_reputation(
+    reputation: &Reputation,
+    sites: &Sites,
+    factions: &Factions,
+) -> PersistedReputation {
+    let mut persisted = PersistedReputation {
+        sites: reputation
+            .sites()
+            .filter_map(|(site, value)| Some((sites.get(site)?.wpos, value)))
+            .collect(),
+        factions: reputation
+            .factions()
+            .filter_map(|(faction, value)| Some((factions.get(faction)?.seed, value)))
+            .collect(),
+    };
+    persisted
+        .sites
+        .sort_unstable_by_key(|(wpos, _)| (wpos.x, wpos.y));
+    persisted.factions.sort_unstable_by_key(|(seed, _)| *seed);
+    persisted
+}
+
+/// The reputation of a character as it was persisted, sites and factions that
+/// no longer exist are forgotten.
+pub fn restore_reputation(
+    persisted: &PersistedReputation,
+    sites: &Sites,
+    factions: &Factions,
+) -> Reputation {
+    let mut reputation = Reputation::default();
+    for (wpos, value) in &persisted.sites {
+        if let Some((site, _)) = sites.iter().find(|(_, site)| site.wpos == *wpos) {
+            reputation.set_site(site, *value);
+        }
+    }
+    for (seed, value) in &persisted.factions {
+        if let Some((faction, _)) = factions.iter().find(|(_, faction)| faction.seed == *seed) {
+            reputation.set_faction(faction, *value);
+        }
+    }
+    reputation
+}
@@ -16,6 +16,9 @@ use common::{
 use slotmap::SecondaryMap;
 use vek::{Clamp, Vec2};
 
+/// Checking quest goals requires looking at every quest, don't do it every tick
+const QUEST_GOAL_TICK_SKIP: u64 = 10;
+
 pub struct SimulateNpcs;
 
 impl Rule for SimulateNpcs {
@@ -342,6 +345,13 @@ fn on_tick(ctx: EventCtx<SimulateNpcs, OnTick>) {
             actor.home = new_home;
         }
 
+        // Start or end the raid led by the NPC
+        match npc.controller.raid.take() {
+            Some(Some(target)) => data.raids.start(actor_id, target, ctx.event.time),
+            Some(None) => data.raids.end(actor_id),
+            None => {},
+        }
+
         // Create registered quests
         for (id, quest) in core::mem::take(&mut npc.controller.quests_to_create) {
             data.quests.create(id, quest);
@@ -357,4 +367,11 @@ fn on_tick(ctx: EventCtx<SimulateNpcs, OnTick>) {
             npc.inbox.push_back(input);
         }
     }
+
+    // Notice explorers reaching their target and raids coming to an end
+    if ctx.event.tick.is_multiple_of(QUEST_GOAL_TICK_SKIP) {
+        data.raids.cleanup(&data.actors, ctx.event.time);
+        data.quests
+            .update_goals(&data.actors, &data.sites, &data.raids);
+    }
 }
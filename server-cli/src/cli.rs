@@ -5,7 +5,7 @@
 use clap::{Parser, builder::ValueParser};
 use common::comp;
 use server::persistence::SqlLogMode;
-use std::{str::FromStr, sync::mpsc::Sender};
+use std::{path::PathBuf, str::FromStr, sync::mpsc::Sender};
 use tracing::error;
 
 // Custom value parser for case-insensitive parsing of AdminRole
@@ -47,6 +47,20 @@ pub enum Shutdown {
     Cancel,
 }
 
+#[derive(Clone, Debug, Parser)]
+pub enum Whitelist {
+    /// Adds a player to the whitelist
+    Add {
+        /// Name of the player to whitelist
+        username: String,
+    },
+    /// Removes a player from the whitelist
+    Remove {
+        /// Name of the player to remove from the whitelist
+        username: String,
+    },
+}
+
 #[derive(Clone, Debug, Parser)]
 pub enum SharedCommand {
     /// Perform operations on the admin list
@@ -86,12 +100,86 @@ pub enum Message {
     SendGlobalMsg {
         msg: String,
     },
+    /// Reloads a server plugin from its file without restarting the server
+    #[cfg(feature = "plugins")]
+    ReloadPlugin {
+        /// Name of the plugin as given in its plugin.toml
+        name: String,
+    },
+    /// Disconnects an online player
+    Kick {
+        /// Name of the player to kick
+        username: String,
+        /// Reason shown to the player
+        #[arg(default_value = "")]
+        reason: String,
+    },
+    /// Bans a player and disconnects them if they are online
+    Ban {
+        /// Name of the player to ban
+        username: String,
+        /// Duration of the ban in seconds, the ban is permanent if omitted
+        #[arg(short, long)]
+        seconds: Option<u64>,
+        /// Replace an existing ban of the player
+        #[arg(short, long)]
+        overwrite: bool,
+        /// Reason shown to the player
+        #[arg(short, long, default_value = "")]
+        reason: String,
+    },
+    /// Lifts the ban of a player
+    Unban {
+        /// Name of the player to unban
+        username: String,
+    },
+    /// Perform operations on the whitelist
+    Whitelist {
+        #[command(subcommand)]
+        command: Whitelist,
+    },
+    /// Teleports an online player to the given position
+    Teleport {
+        /// Name of the player to teleport
+        username: String,
+        x: f32,
+        y: f32,
+        z: f32,
+    },
+    /// Unloads all chunks so that they get regenerated
+    ReloadChunks,
+    /// Takes a snapshot of the character database, rtsim, persisted terrain
+    /// and the server config
+    Snapshot,
+    /// Exports a character of an offline player to a file that other servers
+    /// sharing the `character_transfer_key` can import
+    ExportCharacter {
+        /// Name of the player owning the character
+        username: String,
+        /// Alias or id of the character
+        character: String,
+        /// File to write the export to
+        path: PathBuf,
+    },
+    /// Creates a new character for a player from an exported character
+    ImportCharacter {
+        /// Name of the player receiving the character
+        username: String,
+        /// File written by `export-character`
+        path: PathBuf,
+        /// Rename the character
+        #[arg(short, long)]
+        alias: Option<String>,
+    },
 }
 
 #[derive(Debug, Clone)]
 pub enum MessageReturn {
     Players(Vec<String>),
     Logs(Vec<String>),
+    /// Outcome of a command that changes the server, with an error message
+    /// if it failed.
+    Outcome(Result<(), String>),
 }
 
 #[derive(Parser)]
@@ -124,6 +212,13 @@ pub enum ArgvCommand {
     /// Load an area, run the server for some time, and then exit (useful for
     /// profiling).
     Bench(BenchParams),
+    /// Restore a snapshot taken with the `snapshot` command, the data it
+    /// replaces is moved next to the snapshots. The server must not be
+    /// running.
+    Restore {
+        /// Path to the snapshot archive
+        archive: PathBuf,
+    },
 }
 
 #[derive(Parser)]
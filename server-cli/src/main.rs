@@ -20,6 +20,7 @@ mod web;
 use crate::{
     cli::{
         Admin, ArgvApp, ArgvCommand, BenchParams, Message, MessageReturn, SharedCommand, Shutdown,
+        Whitelist,
     },
     settings::Settings,
     shutdown_coordinator::ShutdownCoordinator,
@@ -34,14 +35,19 @@ use common::{
 use common_base::span;
 use core::sync::atomic::{AtomicUsize, Ordering};
 use rand::distr::SampleString;
-use server::{Event, Input, Server, persistence::DatabaseSettings, settings::Protocol};
+use server::{
+    Event, Input, Server,
+    persistence::{DatabaseSettings, character_loader::TransferRequester},
+    settings::Protocol,
+};
 use std::{
     io,
     sync::{Arc, atomic::AtomicBool},
     time::{Duration, Instant},
 };
 use tokio::sync::Notify;
-use tracing::{info, trace};
+use tracing::{error, info, trace};
+use vek::Vec3;
 
 lazy_static::lazy_static! {
     pub static ref LOG: TuiLog<'static> = TuiLog::default();
@@ -137,6 +143,7 @@ fn main() -> io::Result<()> {
     let database_settings = DatabaseSettings {
         db_dir: server_data_dir.join(PERSISTENCE_DB_DIR),
         sql_log_mode,
+        backend: server_settings.database.clone(),
     };
 
     let mut bench = None;
@@ -189,6 +196,19 @@ fn main() -> io::Result<()> {
                 // annoying, might require a more involved refactor to get
                 // working nicely
             },
+            ArgvCommand::Restore { archive } => {
+                return server::backup::restore(
+                    &archive,
+                    &server_data_dir,
+                    &database_settings,
+                    &server_settings.snapshots,
+                )
+                .map(|aside| info!("Snapshot restored, replaced data was moved to {aside:?}"))
+                .map_err(|e| {
+                    error!("{e}");
+                    io::Error::other(e)
+                });
+            },
         };
     }
 
@@ -257,6 +277,11 @@ fn main() -> io::Result<()> {
                 cert_file_path: _,
                 key_file_path: _,
             } => ("QUIC", address),
+            Protocol::Tls {
+                address,
+                cert_file_path: _,
+                key_file_path: _,
+            } => ("TLS", address),
         });
 
     info!(
@@ -344,7 +369,10 @@ fn server_loop(
             match msg {
                 Message::Shutdown {
                     command: Shutdown::Cancel,
-                } => shutdown_coordinator.abort_shutdown(&mut server),
+                } => {
+                    shutdown_coordinator.abort_shutdown(&mut server);
+                    let _ = response.send(MessageReturn::Outcome(Ok(())));
+                },
                 Message::Shutdown {
                     command: Shutdown::Graceful { seconds, reason },
                 } => {
@@ -353,6 +381,7 @@ fn server_loop(
                         Duration::from_secs(seconds),
                         reason,
                     );
+                    let _ = response.send(MessageReturn::Outcome(Ok(())));
                 },
                 Message::Shutdown {
                     command: Shutdown::Immediate,
@@ -362,12 +391,22 @@ fn server_loop(
                 Message::Shared(SharedCommand::Admin {
                     command: Admin::Add { username, role },
                 }) => {
-                    server.add_admin(&username, role);
+                    let outcome = if server.add_admin(&username, role) {
+                        Ok(())
+                    } else {
+                        Err(format!("The admin list was not changed for {username}"))
+                    };
+                    let _ = response.send(MessageReturn::Outcome(outcome));
                 },
                 Message::Shared(SharedCommand::Admin {
                     command: Admin::Remove { username },
                 }) => {
-                    server.remove_admin(&username);
+                    let outcome = if server.remove_admin(&username) {
+                        Ok(())
+                    } else {
+                        Err(format!("The admin list was not changed for {username}"))
+                    };
+                    let _ = response.send(MessageReturn::Outcome(outcome));
                 },
                 #[cfg(feature = "worldgen")]
                 Message::LoadArea { view_distance } => {
@@ -405,6 +444,89 @@ fn server_loop(
                     let msg = ChatType::Meta.into_plain_msg(msg);
                     server.state().send_chat(msg, false);
                 },
+                #[cfg(feature = "plugins")]
+                Message::ReloadPlugin { name } => {
+                    if let Err(e) = server.reload_plugin(&name) {
+                        tracing::error!(?e, "Failed to reload plugin {name}");
+                    }
+                },
+                Message::Kick { username, reason } => {
+                    let _ = response.send(MessageReturn::Outcome(
+                        server.kick_player(&username, reason),
+                    ));
+                },
+                Message::Ban {
+                    username,
+                    seconds,
+                    overwrite,
+                    reason,
+                } => {
+                    let _ = response.send(MessageReturn::Outcome(server.ban_player(
+                        &username,
+                        reason,
+                        seconds.map(Duration::from_secs),
+                        overwrite,
+                    )));
+                },
+                Message::Unban { username } => {
+                    let _ = response.send(MessageReturn::Outcome(server.unban_player(&username)));
+                },
+                Message::Whitelist {
+                    command: Whitelist::Add { username },
+                } => {
+                    let _ =
+                        response.send(MessageReturn::Outcome(server.add_to_whitelist(&username)));
+                },
+                Message::Whitelist {
+                    command: Whitelist::Remove { username },
+                } => {
+                    let _ = response.send(MessageReturn::Outcome(
+                        server.remove_from_whitelist(&username),
+                    ));
+                },
+                Message::Teleport { username, x, y, z } => {
+                    let _ = response.send(MessageReturn::Outcome(
+                        server.teleport_player(&username, Vec3::new(x, y, z)),
+                    ));
+                },
+                Message::ReloadChunks => {
+                    server.reload_all_chunks();
+                    let _ = response.send(MessageReturn::Outcome(Ok(())));
+                },
+                Message::ExportCharacter {
+                    username,
+                    character,
+                    path,
+                } => {
+                    server.export_character(
+                        &username,
+                        &character,
+                        path,
+                        TransferRequester::Callback(Box::new(move |outcome| {
+                            let _ = response.send(MessageReturn::Outcome(outcome));
+                        })),
+                    );
+                },
+                Message::ImportCharacter {
+                    username,
+                    path,
+                    alias,
+                } => {
+                    server.import_character(
+                        &username,
+                        path,
+                        alias,
+                        TransferRequester::Callback(Box::new(move |outcome| {
+                            let _ = response.send(MessageReturn::Outcome(outcome));
+                        })),
+                    );
+                },
+                Message::Snapshot => {
+                    let outcome = server
+                        .create_snapshot()
+                        .map(|path| info!("Taking snapshot {}", path.display()));
+                    let _ = response.send(MessageReturn::Outcome(outcome));
+                },
             }
             false
         };
@@ -420,6 +542,8 @@ fn server_loop(
                     match msg_answ {
                         MessageReturn::Players(players) => info!("Players: {:?}", players),
                         MessageReturn::Logs(_) => info!("skipp sending logs to tui"),
+                        MessageReturn::Outcome(Ok(())) => {},
+                        MessageReturn::Outcome(Err(err)) => error!("{}", err),
                     };
                 }
             }
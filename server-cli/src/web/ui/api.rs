@@ -1,4 +1,4 @@
-use crate::cli::{Message, MessageReturn};
+use crate::cli::{Admin, Message, MessageReturn, SharedCommand, Shutdown, Whitelist};
 use axum::{
     Json, Router,
     extract::{ConnectInfo, Request, State},
@@ -7,6 +7,7 @@ use axum::{
     response::{IntoResponse, Response},
     routing::{get, post},
 };
+use common::comp::AdminRole;
 use hyper::StatusCode;
 use serde::Deserialize;
 use std::{
@@ -65,7 +66,8 @@ async fn log_users(
 }
 
 //TODO: do security audit before we extend this api with more security relevant
-// functionality (e.g. account management)
+// functionality (e.g. account management). The moderation endpoints are only
+// as safe as the secret token, which must never leave the host.
 pub fn router(web_ui_request_s: UiRequestSender, secret_token: String) -> Router {
     let token = UiApiToken { secret_token };
     let ip_addrs = IpAddresses::default();
@@ -73,6 +75,18 @@ pub fn router(web_ui_request_s: UiRequestSender, secret_token: String) -> Router
         .route("/players", get(players))
         .route("/logs", get(logs))
         .route("/send_global_msg", post(send_global_msg))
+        .route("/kick", post(kick))
+        .route("/ban", post(ban))
+        .route("/unban", post(unban))
+        .route("/whitelist/add", post(whitelist_add))
+        .route("/whitelist/remove", post(whitelist_remove))
+        .route("/admin/add", post(admin_add))
+        .route("/admin/remove", post(admin_remove))
+        .route("/teleport", post(teleport))
+        .route("/reload_chunks", post(reload_chunks))
+        .route("/snapshot", post(snapshot))
+        .route("/shutdown/graceful", post(shutdown_graceful))
+        .route("/shutdown/cancel", post(shutdown_cancel))
         .layer(axum::middleware::from_fn_with_state(ip_addrs, log_users))
         .layer(axum::middleware::from_fn_with_state(token, validate_secret))
         .with_state(web_ui_request_s)
@@ -121,3 +135,399 @@ async fn send_global_msg(
         .await;
     Ok(())
 }
+
+/// Sends a command to the server and waits for its outcome, failures are
+/// reported back to the caller as `400 Bad Request` with the reason as body.
+async fn run_command(
+    web_ui_request_s: &UiRequestSender,
+    msg: Message,
+) -> Result<StatusCode, Response> {
+    let (sender, receiver) = tokio::sync::oneshot::channel();
+    let _ = web_ui_request_s.send((msg, sender)).await;
+    match receiver
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response())?
+    {
+        MessageReturn::Outcome(Ok(())) => Ok(StatusCode::OK),
+        MessageReturn::Outcome(Err(err)) => Err((StatusCode::BAD_REQUEST, err).into_response()),
+        _ => Err(StatusCode::INTERNAL_SERVER_ERROR.into_response()),
+    }
+}
+
+#[derive(Deserialize)]
+struct UsernameBody {
+    username: String,
+}
+
+#[derive(Deserialize)]
+struct KickBody {
+    username: String,
+    #[serde(default)]
+    reason: String,
+}
+
+async fn kick(
+    State(web_ui_request_s): State<UiRequestSender>,
+    Json(payload): Json<KickBody>,
+) -> Result<impl IntoResponse, Response> {
+    run_command(&web_ui_request_s, Message::Kick {
+        username: payload.username,
+        reason: payload.reason,
+    })
+    .await
+}
+
+#[derive(Deserialize)]
+struct BanBody {
+    username: String,
+    #[serde(default)]
+    reason: String,
+    /// Duration of the ban, the ban is permanent if omitted.
+    seconds: Option<u64>,
+    #[serde(default)]
+    overwrite: bool,
+}
+
+async fn ban(
+    State(web_ui_request_s): State<UiRequestSender>,
+    Json(payload): Json<BanBody>,
+) -> Result<impl IntoResponse, Response> {
+    run_command(&web_ui_request_s, Message::Ban {
+        username: payload.username,
+        seconds: payload.seconds,
+        overwrite: payload.overwrite,
+        reason: payload.reason,
+    })
+    .await
+}
+
+async fn unban(
+    State(web_ui_request_s): State<UiRequestSender>,
+    Json(payload): Json<UsernameBody>,
+) -> Result<impl IntoResponse, Response> {
+    run_command(&web_ui_request_s, Message::Unban {
+        username: payload.username,
+    })
+    .await
+}
+
+async fn whitelist_add(
+    State(web_ui_request_s): State<UiRequestSender>,
+    Json(payload): Json<UsernameBody>,
+) -> Result<impl IntoResponse, Response> {
+    run_command(&web_ui_request_s, Message::Whitelist {
+        command: Whitelist::Add {
+            username: payload.username,
+        },
+    })
+    .await
+}
+
+async fn whitelist_remove(
+    State(web_ui_request_s): State<UiRequestSender>,
+    Json(payload): Json<UsernameBody>,
+) -> Result<impl IntoResponse, Response> {
+    run_command(&web_ui_request_s, Message::Whitelist {
+        command: Whitelist::Remove {
+            username: payload.username,
+        },
+    })
+    .await
+}
+
+#[derive(Deserialize)]
+struct AdminAddBody {
+    username: String,
+    /// Either `moderator` or `admin`.
+    role: String,
+}
+
+async fn admin_add(
+    State(web_ui_request_s): State<UiRequestSender>,
+    Json(payload): Json<AdminAddBody>,
+) -> Result<impl IntoResponse, Response> {
+    let role = payload
+        .role
+        .to_lowercase()
+        .parse::<AdminRole>()
+        .map_err(|err| (StatusCode::BAD_REQUEST, err).into_response())?;
+    run_command(
+        &web_ui_request_s,
+        Message::Shared(SharedCommand::Admin {
+            command: Admin::Add {
+                username: payload.username,
+                role,
+            },
+        }),
+    )
+    .await
+}
+
+async fn admin_remove(
+    State(web_ui_request_s): State<UiRequestSender>,
+    Json(payload): Json<UsernameBody>,
+) -> Result<impl IntoResponse, Response> {
+    run_command(
+        &web_ui_request_s,
+        Message::Shared(SharedCommand::Admin {
+            command: Admin::Remove {
+                username: payload.username,
+            },
+        }),
+    )
+    .await
+}
+
+#[derive(Deserialize)]
+struct TeleportBody {
+    username: String,
+    x: f32,
+    y: f32,
+    z: f32,
+}
+
+async fn teleport(
+    State(web_ui_request_s): State<UiRequestSender>,
+    Json(payload): Json<TeleportBody>,
+) -> Result<impl IntoResponse, Response> {
+    run_command(&web_ui_request_s, Message::Teleport {
+        username: payload.username,
+        x: payload.x,
+        y: payload.y,
+        z: payload.z,
+    })
+    .await
+}
+
+async fn reload_chunks(
+    State(web_ui_request_s): State<UiRequestSender>,
+) -> Result<impl IntoResponse, Response> {
+    run_command(&web_ui_request_s, Message::ReloadChunks).await
+}
+
+async fn snapshot(
+    State(web_ui_request_s): State<UiRequestSender>,
+) -> Result<impl IntoResponse, Response> {
+    run_command(&web_ui_request_s, Message::Snapshot).await
+}
+
+#[derive(Deserialize)]
+struct ShutdownGracefulBody {
+    seconds: u64,
+    #[serde(default = "default_shutdown_reason")]
+    reason: String,
+}
+
+fn default_shutdown_reason() -> String { "The server is shutting down".to_owned() }
+
+async fn shutdown_graceful(
+    State(web_ui_request_s): State<UiRequestSender>,
+    Json(payload): Json<ShutdownGracefulBody>,
+) -> Result<impl IntoResponse, Response> {
+    run_command(&web_ui_request_s, Message::Shutdown {
+        command: Shutdown::Graceful {
+            seconds: payload.seconds,
+            reason: payload.reason,
+        },
+    })
+    .await
+}
+
+async fn shutdown_cancel(
+    State(web_ui_request_s): State<UiRequestSender>,
+) -> Result<impl IntoResponse, Response> {
+    run_command(&web_ui_request_s, Message::Shutdown {
+        command: Shutdown::Cancel,
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::{Body, to_bytes};
+    use tokio::sync::mpsc::Receiver;
+    use tower::ServiceExt;
+
+    const SECRET: &str = "secret";
+
+    const ROUTES: [&str; 13] = [
+        "/kick",
+        "/ban",
+        "/unban",
+        "/whitelist/add",
+        "/whitelist/remove",
+        "/admin/add",
+        "/admin/remove",
+        "/teleport",
+        "/reload_chunks",
+        "/snapshot",
+        "/shutdown/graceful",
+        "/shutdown/cancel",
+        "/send_global_msg",
+    ];
+
+    fn request(route: &str, cookie: Option<&str>, body: &str) -> Request {
+        let mut builder =
+            axum::http::Request::post(route).header("content-type", "application/json");
+        if let Some(cookie) = cookie {
+            builder = builder.header(COOKIE, cookie);
+        }
+        let mut req = builder.body(Body::from(body.to_owned())).unwrap();
+        req.extensions_mut()
+            .insert(ConnectInfo(SocketAddr::from(([127, 0, 0, 1], 0))));
+        req
+    }
+
+    fn authorized(route: &str, body: &str) -> Request {
+        request(route, Some(&format!("X-Secret-Token={SECRET}")), body)
+    }
+
+    fn setup() -> (
+        Router,
+        Receiver<(Message, tokio::sync::oneshot::Sender<MessageReturn>)>,
+    ) {
+        let (sender, receiver) = tokio::sync::mpsc::channel(16);
+        (router(sender, SECRET.to_owned()), receiver)
+    }
+
+    async fn body_text(response: Response) -> String {
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        String::from_utf8(bytes.to_vec()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn new_routes_require_secret() {
+        let (router, mut receiver) = setup();
+        for route in ROUTES {
+            for cookie in [None, Some("X-Secret-Token=wrong")] {
+                let response = router
+                    .clone()
+                    .oneshot(request(route, cookie, r#"{"username":"a"}"#))
+                    .await
+                    .unwrap();
+                assert_eq!(response.status(), StatusCode::UNAUTHORIZED, "{route}");
+            }
+        }
+        assert!(receiver.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn malformed_bodies_are_rejected() {
+        let (router, mut receiver) = setup();
+        let cases = [
+            ("/ban", "not json", StatusCode::BAD_REQUEST),
+            (
+                "/ban",
+                r#"{"reason":"no username"}"#,
+                StatusCode::UNPROCESSABLE_ENTITY,
+            ),
+            (
+                "/ban",
+                r#"{"username":"a","seconds":-1}"#,
+                StatusCode::UNPROCESSABLE_ENTITY,
+            ),
+            ("/kick", "{}", StatusCode::UNPROCESSABLE_ENTITY),
+            ("/unban", "[]", StatusCode::UNPROCESSABLE_ENTITY),
+            ("/whitelist/add", "", StatusCode::BAD_REQUEST),
+            (
+                "/admin/add",
+                r#"{"username":"a"}"#,
+                StatusCode::UNPROCESSABLE_ENTITY,
+            ),
+            (
+                "/admin/add",
+                r#"{"username":"a","role":"king"}"#,
+                StatusCode::BAD_REQUEST,
+            ),
+            (
+                "/teleport",
+                r#"{"username":"a","x":"1","y":2,"z":3}"#,
+                StatusCode::UNPROCESSABLE_ENTITY,
+            ),
+            (
+                "/shutdown/graceful",
+                r#"{"reason":"x"}"#,
+                StatusCode::UNPROCESSABLE_ENTITY,
+            ),
+        ];
+        for (route, body, status) in cases {
+            let response = router
+                .clone()
+                .oneshot(authorized(route, body))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), status, "{route} {body}");
+        }
+        let mut missing_type = authorized("/kick", r#"{"username":"a"}"#);
+        missing_type.headers_mut().remove("content-type");
+        let response = router.oneshot(missing_type).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+        assert!(receiver.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn ban_unban_round_trip() {
+        let (router, mut receiver) = setup();
+        // stands in for the server, which answers with the outcome of each command
+        let server = tokio::spawn(async move {
+            let mut banned = HashSet::new();
+            let mut bans = Vec::new();
+            while let Some((msg, response)) = receiver.recv().await {
+                let changed = match msg {
+                    Message::Ban {
+                        username,
+                        seconds,
+                        overwrite,
+                        reason,
+                    } => {
+                        bans.push((username.clone(), seconds, overwrite, reason));
+                        banned.insert(username) || overwrite
+                    },
+                    Message::Unban { username } => banned.remove(&username),
+                    _ => panic!("unexpected message {msg:?}"),
+                };
+                let _ = response.send(MessageReturn::Outcome(
+                    changed
+                        .then_some(())
+                        .ok_or_else(|| "The ban list was not changed".to_owned()),
+                ));
+            }
+            bans
+        });
+
+        let ban = r#"{"username":"griefer","reason":"griefing","seconds":60}"#;
+        let steps = [
+            ("/ban", ban, StatusCode::OK),
+            ("/ban", ban, StatusCode::BAD_REQUEST),
+            (
+                "/ban",
+                r#"{"username":"griefer","overwrite":true}"#,
+                StatusCode::OK,
+            ),
+            ("/unban", r#"{"username":"griefer"}"#, StatusCode::OK),
+            (
+                "/unban",
+                r#"{"username":"griefer"}"#,
+                StatusCode::BAD_REQUEST,
+            ),
+        ];
+        for (route, body, status) in steps {
+            let response = router
+                .clone()
+                .oneshot(authorized(route, body))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), status, "{route} {body}");
+            if status == StatusCode::BAD_REQUEST {
+                assert_eq!(body_text(response).await, "The ban list was not changed");
+            }
+        }
+        drop(router);
+        assert_eq!(server.await.unwrap(), vec![
+            ("griefer".to_owned(), Some(60), false, "griefing".to_owned()),
+            ("griefer".to_owned(), Some(60), false, "griefing".to_owned()),
+            ("griefer".to_owned(), None, true, String::new()),
+        ]);
+    }
+}
@@ -0,0 +1,619 @@
+//! Snapshots of everything the server persists: the character database, rtsim
+//! state, terrain persistence and the server config.
+//!
+//! A snapshot is a `.tar.gz` archive with a [`Manifest`] as first entry. The
+//! logged in characters, mail and market are queued for persistence and the
+//! database is then copied by the persistence thread, so the copy contains
+//! everything up to the tick the snapshot was started. Terrain and rtsim are
+//! flushed and captured at the same tick. A PostgreSQL database is copied with
+//! `pg_dump` and restored with `pg_restore`, both have to be installed next to
+//! the server.
+
+#[cfg(feature = "postgres")]
+use crate::persistence::backend;
+#[cfg(feature = "persistent_world")]
+use crate::terrain_persistence::TerrainPersistence;
+use crate::{
+    persistence::{DatabaseBackend, DatabaseSettings, character_updater::CharacterUpdater},
+    settings::{SnapshotSettings, with_config_dir},
+    sys::{PersistenceScheduler, persistence},
+};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use common_ecs::run_now;
+use flate2::{Compression, read::GzDecoder, write::GzEncoder};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::{Path, PathBuf},
+    thread::JoinHandle,
+    time::Instant,
+};
+use tracing::{error, info, warn};
+
+const FORMAT_VERSION: u32 = 1;
+const MANIFEST_ENTRY: &str = "manifest.ron";
+const DB_ENTRY: &str = "db/db.sqlite";
+const PG_DUMP_ENTRY: &str = "db/db.pgdump";
+const RTSIM_ENTRY: &str = "rtsim/data.dat";
+const TERRAIN_ENTRY: &str = "terrain";
+const CONFIG_ENTRY: &str = "server_config";
+const NAME_FORMAT: &str = "snapshot_%Y%m%d_%H%M%S";
+const EXTENSION: &str = ".tar.gz";
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Manifest {
+    format_version: u32,
+    created: DateTime<Utc>,
+    version: String,
+}
+
+/// The format the persistence thread copied the database in
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum DatabaseCopy {
+    /// A copy of the SQLite database file
+    Sqlite,
+    /// A `pg_dump` archive of the PostgreSQL database
+    PgDump,
+}
+
+impl DatabaseCopy {
+    fn entry(self) -> &'static str {
+        match self {
+            Self::Sqlite => DB_ENTRY,
+            Self::PgDump => PG_DUMP_ENTRY,
+        }
+    }
+}
+
+/// Keeps track of scheduled and running snapshots.
+pub(crate) struct Snapshots {
+    last: Instant,
+    running: Option<JoinHandle<()>>,
+}
+
+impl Snapshots {
+    pub fn new() -> Self {
+        Self {
+            last: Instant::now(),
+            running: None,
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running
+            .as_ref()
+            .is_some_and(|running| !running.is_finished())
+    }
+
+    /// Whether a scheduled snapshot should be taken now
+    pub fn due(&self, settings: &SnapshotSettings) -> bool {
+        settings
+            .interval
+            .is_some_and(|interval| self.last.elapsed() >= interval)
+            && !self.is_running()
+    }
+
+    /// Starts a snapshot in the background, returning the path of the archive
+    /// it will be written to.
+    pub fn start(
+        &mut self,
+        ecs: &specs::World,
+        data_dir: &Path,
+        settings: &SnapshotSettings,
+    ) -> Result<PathBuf, String> {
+        if self.is_running() {
+            return Err("A snapshot is already being taken".to_owned());
+        }
+        self.last = Instant::now();
+        self.wait();
+
+        let dir = data_dir.join(&settings.dir);
+        fs::create_dir_all(&dir)
+            .map_err(|e| format!("Failed to create snapshot directory {}: {e}", dir.display()))?;
+        let created = Utc::now();
+        let name = format!("{}{EXTENSION}", created.format(NAME_FORMAT));
+        let path = dir.join(&name);
+        if path.exists() {
+            return Err(format!("Snapshot {} already exists", path.display()));
+        }
+
+        // Queue the logged in characters, mail and market first, otherwise the
+        // database copy lags behind rtsim and terrain by up to one persistence
+        // interval
+        ecs.write_resource::<PersistenceScheduler>().run_next();
+        run_now::<persistence::Sys>(ecs);
+
+        // `VACUUM INTO` refuses to overwrite files
+        let db_copy = dir.join(format!("{name}.db.tmp"));
+        let _ = fs::remove_file(&db_copy);
+        let db_outcome = ecs
+            .write_resource::<CharacterUpdater>()
+            .snapshot(db_copy.clone());
+
+        #[cfg(feature = "worldgen")]
+        let rtsim = {
+            let mut bytes = Vec::new();
+            ecs.read_resource::<crate::rtsim::RtSim>()
+                .state()
+                .data()
+                .write_to(&mut bytes)
+                .map_err(|e| format!("Failed to serialize rtsim data: {e}"))?;
+            Some(bytes)
+        };
+        #[cfg(not(feature = "worldgen"))]
+        let rtsim: Option<Vec<u8>> = None;
+
+        #[cfg(feature = "persistent_world")]
+        let terrain_dir =
+            ecs.try_fetch_mut::<TerrainPersistence>()
+                .map(|mut terrain_persistence| {
+                    terrain_persistence.flush();
+                    TerrainPersistence::dir(data_dir.to_owned())
+                });
+        #[cfg(not(feature = "persistent_world"))]
+        let terrain_dir: Option<PathBuf> = None;
+
+        let manifest = Manifest {
+            format_version: FORMAT_VERSION,
+            created,
+            version: common::util::DISPLAY_VERSION.clone(),
+        };
+        let config_dir = with_config_dir(data_dir);
+        let settings = settings.clone();
+        let archive_path = path.clone();
+        info!(?path, "Taking snapshot");
+        let running = std::thread::Builder::new()
+            .name("snapshot".to_owned())
+            .spawn(move || {
+                let result = db_outcome
+                    .recv()
+                    .unwrap_or_else(|_| Err("The persistence thread stopped".to_owned()))
+                    .and_then(|copy| {
+                        write_archive(
+                            &archive_path,
+                            &manifest,
+                            (copy, &db_copy),
+                            rtsim.as_deref(),
+                            terrain_dir.as_deref(),
+                            &config_dir,
+                        )
+                        .map_err(|e| format!("Failed to write snapshot: {e}"))
+                    });
+                let _ = fs::remove_file(&db_copy);
+                match result {
+                    Ok(()) => {
+                        info!(path = ?archive_path, "Snapshot complete");
+                        prune(&dir, &settings);
+                    },
+                    Err(e) => error!(path = ?archive_path, "{e}"),
+                }
+            })
+            .map_err(|e| format!("Failed to spawn snapshot thread: {e}"))?;
+        self.running = Some(running);
+        Ok(path)
+    }
+
+    /// Blocks until a running snapshot is complete.
+    pub fn wait(&mut self) {
+        if let Some(running) = self.running.take()
+            && running.join().is_err()
+        {
+            error!("Snapshot thread panicked");
+        }
+    }
+}
+
+fn write_archive(
+    path: &Path,
+    manifest: &Manifest,
+    (db_format, db): (DatabaseCopy, &Path),
+    rtsim: Option<&[u8]>,
+    terrain_dir: Option<&Path>,
+    config_dir: &Path,
+) -> io::Result<()> {
+    let mut partial_path = path.as_os_str().to_owned();
+    partial_path.push(".partial");
+    let partial_path = PathBuf::from(partial_path);
+
+    let result = (|| {
+        let file = File::create(&partial_path)?;
+        let mut archive =
+            tar::Builder::new(GzEncoder::new(BufWriter::new(file), Compression::default()));
+        let manifest = ron::ser::to_string_pretty(manifest, ron::ser::PrettyConfig::default())
+            .map_err(io::Error::other)?;
+        append(
+            &mut archive,
+            MANIFEST_ENTRY,
+            manifest.as_bytes().len() as u64,
+            manifest.as_bytes(),
+        )?;
+        append_file(&mut archive, db_format.entry(), db)?;
+        if let Some(rtsim) = rtsim {
+            append(&mut archive, RTSIM_ENTRY, rtsim.len() as u64, rtsim)?;
+        }
+        if let Some(terrain_dir) = terrain_dir.filter(|dir| dir.is_dir()) {
+            append_dir(&mut archive, Path::new(TERRAIN_ENTRY), terrain_dir)?;
+        }
+        if config_dir.is_dir() {
+            append_dir(&mut archive, Path::new(CONFIG_ENTRY), config_dir)?;
+        }
+        let file = archive
+            .into_inner()?
+            .finish()?
+            .into_inner()
+            .map_err(|e| e.into_error())?;
+        file.sync_all()
+    })();
+
+    match result {
+        Ok(()) => fs::rename(&partial_path, path),
+        Err(e) => {
+            let _ = fs::remove_file(&partial_path);
+            Err(e)
+        },
+    }
+}
+
+fn append<W: Write>(
+    archive: &mut tar::Builder<W>,
+    name: impl AsRef<Path>,
+    len: u64,
+    data: impl Read,
+) -> io::Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(len);
+    header.set_mode(0o644);
+    header.set_mtime(Utc::now().timestamp().max(0) as u64);
+    header.set_cksum();
+    archive.append_data(&mut header, name, data)
+}
+
+/// Appends the first `len` bytes the file has right now, files like the
+/// terrain region files might be appended to while the archive is written.
+fn append_file<W: Write>(
+    archive: &mut tar::Builder<W>,
+    name: impl AsRef<Path>,
+    path: &Path,
+) -> io::Result<()> {
+    let file = File::open(path)?;
+    let len = file.metadata()?.len();
+    append(archive, name, len, BufReader::new(file).take(len))
+}
+
+fn append_dir<W: Write>(archive: &mut tar::Builder<W>, name: &Path, dir: &Path) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        let entry_name = name.join(entry.file_name());
+        if file_type.is_dir() {
+            append_dir(archive, &entry_name, &entry.path())?;
+        } else if file_type.is_file() {
+            append_file(archive, entry_name, &entry.path())?;
+        }
+    }
+    Ok(())
+}
+
+/// Deletes the snapshots in `dir` that fall outside of the retention policy.
+fn prune(dir: &Path, settings: &SnapshotSettings) {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            warn!(?e, ?dir, "Failed to list snapshots for pruning");
+            return;
+        },
+    };
+    let snapshots = entries
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            let created = snapshot_time(path.file_name()?.to_str()?)?;
+            Some((path, created))
+        })
+        .collect();
+    for path in expired_snapshots(snapshots, Utc::now(), settings) {
+        match fs::remove_file(&path) {
+            Ok(()) => info!(?path, "Deleted old snapshot"),
+            Err(e) => warn!(?e, ?path, "Failed to delete old snapshot"),
+        }
+    }
+}
+
+fn snapshot_time(file_name: &str) -> Option<DateTime<Utc>> {
+    let name = file_name.strip_suffix(EXTENSION)?;
+    NaiveDateTime::parse_from_str(name, NAME_FORMAT)
+        .ok()
+        .map(|time| time.and_utc())
+}
+
+fn expired_snapshots(
+    mut snapshots: Vec<(PathBuf, DateTime<Utc>)>,
+    now: DateTime<Utc>,
+    settings: &SnapshotSettings,
+) -> Vec<PathBuf> {
+    // Newest first
+    snapshots.sort_by(|(_, a), (_, b)| b.cmp(a));
+    snapshots
+        .into_iter()
+        .enumerate()
+        .skip(1)
+        .filter(|(i, (_, created))| {
+            let too_many = settings.keep != 0 && *i >= settings.keep;
+            let too_old = settings
+                .max_age
+                .is_some_and(|max_age| (now - *created).to_std().is_ok_and(|age| age > max_age));
+            too_many || too_old
+        })
+        .map(|(_, (path, _))| path)
+        .collect()
+}
+
+/// Replaces the persisted data in `data_dir` and the database with the
+/// contents of the snapshot at `archive`. Data that is not part of the
+/// snapshot, e.g. terrain of a server without terrain persistence, is left
+/// alone.
+///
+/// The data that is replaced is moved to a `before_restore_*` directory next
+/// to the snapshots, which is returned. A PostgreSQL database is dumped there.
+///
+/// This must not be used while a server is running on this data.
+pub fn restore(
+    archive: &Path,
+    data_dir: &Path,
+    database: &DatabaseSettings,
+    settings: &SnapshotSettings,
+) -> Result<PathBuf, String> {
+    let snapshot_dir = data_dir.join(&settings.dir);
+    let now = Utc::now().format("%Y%m%d_%H%M%S");
+    let staging = snapshot_dir.join(format!("restoring_{now}"));
+    let aside = snapshot_dir.join(format!("before_restore_{now}"));
+
+    let result = unpack(archive, &staging).and_then(|()| {
+        // The database is restored first as it is the most likely to fail
+        restore_database(&staging, &aside, database)?;
+
+        // Where each part of the snapshot belongs, the database also consists of the
+        // write-ahead log next to it
+        let db = database.db_dir.join("db.sqlite");
+        #[cfg_attr(not(feature = "persistent_world"), expect(unused_mut))]
+        let mut targets = vec![
+            (PathBuf::from(DB_ENTRY), db.clone()),
+            (
+                PathBuf::from(RTSIM_ENTRY),
+                crate::rtsim::RtSim::get_file_path(data_dir.to_owned()),
+            ),
+            (PathBuf::from(CONFIG_ENTRY), with_config_dir(data_dir)),
+        ];
+        #[cfg(feature = "persistent_world")]
+        targets.push((
+            PathBuf::from(TERRAIN_ENTRY),
+            TerrainPersistence::dir(data_dir.to_owned()),
+        ));
+        #[cfg(not(feature = "persistent_world"))]
+        if staging.join(TERRAIN_ENTRY).exists() {
+            warn!("Not restoring terrain, the server was compiled without terrain persistence");
+        }
+
+        for (entry, target) in targets {
+            if !staging.join(&entry).exists() {
+                continue;
+            }
+            let mut displaced = vec![(target.clone(), aside.join(&entry))];
+            if target == db {
+                for suffix in ["-wal", "-shm"] {
+                    let mut wal = db.as_os_str().to_owned();
+                    wal.push(suffix);
+                    let mut wal_aside = aside.join(&entry).into_os_string();
+                    wal_aside.push(suffix);
+                    displaced.push((PathBuf::from(wal), PathBuf::from(wal_aside)));
+                }
+            }
+            for (from, to) in displaced {
+                if from.exists() {
+                    move_path(&from, &to)?;
+                }
+            }
+            move_path(&staging.join(&entry), &target)?;
+            info!(?target, "Restored {}", entry.display());
+        }
+        Ok(())
+    });
+    let _ = fs::remove_dir_all(&staging);
+
+    result
+        .map(|()| aside)
+        .map_err(|e| format!("Failed to restore {}: {e}", archive.display()))
+}
+
+/// Checks that the snapshot in `staging` was taken with the same database
+/// backend and restores a PostgreSQL dump, SQLite databases are restored like
+/// the other files.
+#[cfg_attr(not(feature = "postgres"), expect(unused_variables))]
+fn restore_database(staging: &Path, aside: &Path, database: &DatabaseSettings) -> io::Result<()> {
+    let pg_dump = staging.join(PG_DUMP_ENTRY);
+    match &database.backend {
+        DatabaseBackend::Sqlite if pg_dump.exists() => Err(io::Error::other(
+            "the snapshot contains a PostgreSQL database, but the server uses SQLite",
+        )),
+        DatabaseBackend::Sqlite => Ok(()),
+        DatabaseBackend::Postgres { .. } if staging.join(DB_ENTRY).exists() => {
+            Err(io::Error::other(
+                "the snapshot contains a SQLite database, but the server uses PostgreSQL",
+            ))
+        },
+        DatabaseBackend::Postgres { .. } if !pg_dump.exists() => Ok(()),
+        #[cfg(feature = "postgres")]
+        DatabaseBackend::Postgres { url, .. } => {
+            let displaced = aside.join(PG_DUMP_ENTRY);
+            if let Some(parent) = displaced.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            backend::pg_dump(url, &displaced).map_err(io::Error::other)?;
+            backend::pg_restore(url, &pg_dump).map_err(io::Error::other)?;
+            info!("Restored the PostgreSQL database");
+            Ok(())
+        },
+        #[cfg(not(feature = "postgres"))]
+        DatabaseBackend::Postgres { .. } => Err(io::Error::other(
+            "the server was compiled without the `postgres` feature",
+        )),
+    }
+}
+
+fn move_path(from: &Path, to: &Path) -> io::Result<()> {
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::rename(from, to)
+}
+
+/// Unpacks a snapshot into `dir`, verifying its manifest.
+fn unpack(archive: &Path, dir: &Path) -> io::Result<()> {
+    let file = File::open(archive)?;
+    let mut archive = tar::Archive::new(GzDecoder::new(BufReader::new(file)));
+    let mut entries = archive.entries()?;
+
+    let mut manifest_entry = entries
+        .next()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "empty archive"))??;
+    if *manifest_entry.path()? != *Path::new(MANIFEST_ENTRY) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "not a snapshot archive",
+        ));
+    }
+    let mut manifest = String::new();
+    manifest_entry.read_to_string(&mut manifest)?;
+    let manifest: Manifest = ron::from_str(&manifest).map_err(io::Error::other)?;
+    if manifest.format_version != FORMAT_VERSION {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "unsupported snapshot format version {}",
+                manifest.format_version
+            ),
+        ));
+    }
+    info!(
+        "Restoring snapshot taken at {} by {}",
+        manifest.created, manifest.version
+    );
+
+    fs::create_dir_all(dir)?;
+    for entry in entries {
+        let mut entry = entry?;
+        let path = entry.path()?.into_owned();
+        let known = [
+            DB_ENTRY,
+            PG_DUMP_ENTRY,
+            RTSIM_ENTRY,
+            TERRAIN_ENTRY,
+            CONFIG_ENTRY,
+        ]
+        .iter()
+        .any(|known| path.starts_with(known));
+        // `unpack_in` refuses paths escaping `dir`
+        if !known || !entry.unpack_in(dir)? {
+            warn!(?path, "Skipping unexpected snapshot entry");
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn retention() {
+        let now = Utc::now();
+        let snapshots = (0..5)
+            .map(|days| {
+                (
+                    PathBuf::from(days.to_string()),
+                    now - chrono::Duration::days(days),
+                )
+            })
+            .collect::<Vec<_>>();
+        let settings = |keep, max_age_days: Option<u64>| SnapshotSettings {
+            keep,
+            max_age: max_age_days.map(|days| Duration::from_secs(days * 24 * 60 * 60 + 1)),
+            ..Default::default()
+        };
+
+        let expired = expired_snapshots(snapshots.clone(), now, &settings(3, None));
+        assert_eq!(expired, vec![PathBuf::from("3"), PathBuf::from("4")]);
+        let expired = expired_snapshots(snapshots.clone(), now, &settings(0, Some(1)));
+        assert_eq!(expired, vec![
+            PathBuf::from("2"),
+            PathBuf::from("3"),
+            PathBuf::from("4")
+        ]);
+        // The newest snapshot is never deleted
+        let expired = expired_snapshots(snapshots[3..].to_vec(), now, &settings(0, Some(0)));
+        assert_eq!(expired, vec![PathBuf::from("4")]);
+    }
+
+    #[test]
+    fn snapshot_names() {
+        let time = NaiveDateTime::parse_from_str("20261018_101500", "%Y%m%d_%H%M%S")
+            .unwrap()
+            .and_utc();
+        let name = format!("{}{EXTENSION}", time.format(NAME_FORMAT));
+        assert_eq!(name, "snapshot_20261018_101500.tar.gz");
+        assert_eq!(snapshot_time(&name), Some(time));
+        assert_eq!(
+            snapshot_time("snapshot_20261018_101500.tar.gz.partial"),
+            None
+        );
+    }
+
+    #[test]
+    fn archive_roundtrip() {
+        let dir =
+            std::env::temp_dir().join(format!("veloren_snapshot_test_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let terrain = dir.join("terrain_src");
+        let config = dir.join("config_src");
+        fs::create_dir_all(terrain.join("nested")).unwrap();
+        fs::create_dir_all(&config).unwrap();
+        fs::write(terrain.join("region_0_0.dat"), b"region").unwrap();
+        fs::write(terrain.join("nested").join("file"), b"nested").unwrap();
+        fs::write(config.join("settings.ron"), b"()").unwrap();
+        let db = dir.join("db");
+        fs::write(&db, b"database").unwrap();
+
+        let archive = dir.join("snapshot.tar.gz");
+        let manifest = Manifest {
+            format_version: FORMAT_VERSION,
+            created: Utc::now(),
+            version: "test".to_owned(),
+        };
+        write_archive(
+            &archive,
+            &manifest,
+            (DatabaseCopy::Sqlite, &db),
+            Some(b"rtsim"),
+            Some(&terrain),
+            &config,
+        )
+        .unwrap();
+
+        let out = dir.join("out");
+        unpack(&archive, &out).unwrap();
+        assert_eq!(fs::read(out.join(DB_ENTRY)).unwrap(), b"database");
+        assert_eq!(fs::read(out.join(RTSIM_ENTRY)).unwrap(), b"rtsim");
+        assert_eq!(
+            fs::read(out.join(TERRAIN_ENTRY).join("nested").join("file")).unwrap(),
+            b"nested"
+        );
+        assert_eq!(
+            fs::read(out.join(CONFIG_ENTRY).join("settings.ron")).unwrap(),
+            b"()"
+        );
+
+        assert!(unpack(&db, &dir.join("invalid")).is_err());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -80,10 +80,12 @@ pub fn create_character(
         stats,
         skill_set,
         inventory,
+        storage: Default::default(),
         waypoint,
         pets: Vec::new(),
         active_abilities: common::comp::ActiveAbilities::default_limited(BASE_ABILITY_LIMIT),
         map_marker,
+        reputation: None,
     });
     Ok(())
 }
@@ -38,6 +38,7 @@ pub enum ChatParties {
     CommandInfo(PlayerInfo),
     CommandError(PlayerInfo),
     Kill(KillSource, PlayerInfo),
+    Duel(PlayerInfo, PlayerInfo),
     GroupMeta(Vec<PlayerInfo>),
     Group(PlayerInfo, Vec<PlayerInfo>),
     Tell(PlayerInfo, PlayerInfo),
@@ -170,6 +171,16 @@ impl ChatExporter {
                     ));
                 }
             },
+            ChatType::Duel(a, b) => {
+                if let (Some(a_player_info), Some(b_player_info)) =
+                    (player_info_from_uid(*a), player_info_from_uid(*b))
+                {
+                    return Some(ChatMessage::new(
+                        chatmsg,
+                        ChatParties::Duel(a_player_info, b_player_info),
+                    ));
+                }
+            },
             ChatType::FactionMeta(s) => {
                 return Some(ChatMessage::new(
                     chatmsg,
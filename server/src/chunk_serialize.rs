@@ -1,6 +1,12 @@
 use crate::client::PreparedMsg;
+use common::{
+    terrain::{Block, TerrainChunk, TerrainChunkSize, TerrainGrid},
+    vol::{ReadVol, RectVolSize},
+};
+use hashbrown::HashMap;
 use specs::Entity;
-use vek::Vec2;
+use std::collections::VecDeque;
+use vek::{Vec2, Vec3};
 
 /// Sending a chunk to the user works the following way:
 /// A system like `msg::terrain` `terrain` or `terrain_sync` either decide to
@@ -17,6 +23,8 @@ use vek::Vec2;
 pub struct ChunkSendEntry {
     pub(crate) entity: Entity,
     pub(crate) chunk_key: Vec2<i32>,
+    /// Version of the chunk the client acknowledged to still have
+    pub(crate) known_version: Option<u64>,
 }
 
 pub struct SerializedChunk {
@@ -24,3 +32,267 @@ pub struct SerializedChunk {
     pub(crate) msg: PreparedMsg,
     pub(crate) recipients: Vec<Entity>,
 }
+
+/// Upper bound of changed blocks remembered per chunk. Older changes are
+/// forgotten, clients with a version before them get the full chunk again.
+const MAX_HISTORY_BLOCKS: usize = 4096;
+
+/// Tracks a version of every loaded chunk together with its recent block
+/// changes, so a chunk can be sent as a `TerrainChunkDelta` against a version
+/// a client acknowledged instead of sending it in full.
+///
+/// Versions are taken from a single counter and are therefore never reused
+/// while the server runs, not even after a chunk got unloaded.
+pub struct ChunkVersions {
+    last_version: u64,
+    chunks: HashMap<Vec2<i32>, ChunkHistory>,
+    /// Chunks replaced since the last call to [`Self::take_replaced`] that can
+    /// be sent as a delta, with their version before the replacement
+    replaced: HashMap<Vec2<i32>, u64>,
+}
+
+struct ChunkHistory {
+    version: u64,
+    /// Oldest version a delta can be built against
+    base_version: u64,
+    /// Block changes in ascending order, each with the version it resulted in
+    changes: VecDeque<(u64, Vec<(Vec3<i32>, Block)>)>,
+    changed_blocks: usize,
+}
+
+impl ChunkHistory {
+    fn new(version: u64) -> Self {
+        Self {
+            version,
+            base_version: version,
+            changes: VecDeque::new(),
+            changed_blocks: 0,
+        }
+    }
+}
+
+impl Default for ChunkVersions {
+    fn default() -> Self {
+        Self {
+            // `0` is used on the wire for unknown versions
+            last_version: 0,
+            chunks: HashMap::new(),
+            replaced: HashMap::new(),
+        }
+    }
+}
+
+impl ChunkVersions {
+    fn next_version(&mut self) -> u64 {
+        self.last_version += 1;
+        self.last_version
+    }
+
+    /// Current version of a chunk, starting to track it if necessary.
+    pub fn version(&mut self, key: Vec2<i32>) -> u64 {
+        if let Some(history) = self.chunks.get(&key) {
+            history.version
+        } else {
+            let version = self.next_version();
+            self.chunks.insert(key, ChunkHistory::new(version));
+            version
+        }
+    }
+
+    /// The chunk was replaced as a whole without knowing which blocks changed.
+    /// No delta can be built against any of its previous versions.
+    pub fn replace(&mut self, key: Vec2<i32>) {
+        let version = self.next_version();
+        self.chunks.insert(key, ChunkHistory::new(version));
+        self.replaced.remove(&key);
+    }
+
+    /// The chunk was replaced by a new one, e.g. because it got regenerated.
+    /// The blocks that differ are recorded like any other block change, unless
+    /// there are too many of them.
+    pub fn replace_with(&mut self, key: Vec2<i32>, old: &TerrainChunk, new: &TerrainChunk) {
+        if !self.chunks.contains_key(&key) {
+            return;
+        }
+        let origin = TerrainGrid::key_chunk(key);
+        let min_z = old.get_min_z().min(new.get_min_z());
+        let max_z = old.get_max_z().max(new.get_max_z());
+        let mut changes = HashMap::new();
+        for z in min_z..max_z {
+            for y in 0..TerrainChunkSize::RECT_SIZE.y as i32 {
+                for x in 0..TerrainChunkSize::RECT_SIZE.x as i32 {
+                    let pos = Vec3::new(x, y, z);
+                    if let Ok(block) = new.get(pos)
+                        && old.get(pos).ok() != Some(block)
+                    {
+                        if changes.len() >= MAX_HISTORY_BLOCKS {
+                            self.replace(key);
+                            return;
+                        }
+                        changes.insert(origin.with_z(0) + pos, *block);
+                    }
+                }
+            }
+        }
+        let previous = match self.record_blocks(&changes).remove(&key) {
+            Some((previous, _)) => previous,
+            // Nothing changed
+            None => self.version(key),
+        };
+        // Clients only know the version before the first replacement of the tick
+        self.replaced.entry(key).or_insert(previous);
+    }
+
+    /// Takes the chunks replaced by [`Self::replace_with`] since the last call,
+    /// with the version clients have to know to receive them as a delta.
+    pub fn take_replaced(&mut self) -> HashMap<Vec2<i32>, u64> {
+        std::mem::take(&mut self.replaced)
+    }
+
+    pub fn remove(&mut self, key: Vec2<i32>) {
+        self.chunks.remove(&key);
+        self.replaced.remove(&key);
+    }
+
+    /// Records the blocks changed in a tick and returns the `(previous, new)`
+    /// version of every tracked chunk they touched.
+    pub fn record_blocks(
+        &mut self,
+        blocks: &HashMap<Vec3<i32>, Block>,
+    ) -> HashMap<Vec2<i32>, (u64, u64)> {
+        let mut per_chunk = HashMap::<_, Vec<_>>::new();
+        for (pos, block) in blocks {
+            per_chunk
+                .entry(TerrainGrid::chunk_key(*pos))
+                .or_default()
+                .push((*pos, *block));
+        }
+
+        let mut versions = HashMap::with_capacity(per_chunk.len());
+        for (key, changes) in per_chunk {
+            let version = self.next_version();
+            let Some(history) = self.chunks.get_mut(&key) else {
+                continue;
+            };
+            versions.insert(key, (history.version, version));
+            history.version = version;
+            history.changed_blocks += changes.len();
+            history.changes.push_back((version, changes));
+            while history.changed_blocks > MAX_HISTORY_BLOCKS
+                && let Some((version, changes)) = history.changes.pop_front()
+            {
+                history.changed_blocks -= changes.len();
+                history.base_version = version;
+            }
+        }
+        versions
+    }
+
+    /// Returns the current version of the chunk and all blocks changed since
+    /// `known_version`, or `None` if the changes are no longer known.
+    pub fn delta(
+        &self,
+        key: Vec2<i32>,
+        known_version: u64,
+    ) -> Option<(u64, HashMap<Vec3<i32>, Block>)> {
+        let history = self.chunks.get(&key)?;
+        if !(history.base_version..=history.version).contains(&known_version) {
+            return None;
+        }
+        let blocks = history
+            .changes
+            .iter()
+            .filter(|(version, _)| *version > known_version)
+            .flat_map(|(_, changes)| changes.iter().copied())
+            .collect();
+        Some((history.version, blocks))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::{
+        terrain::{BlockKind, TerrainChunkMeta},
+        vol::WriteVol,
+    };
+
+    fn blocks(positions: &[Vec3<i32>], kind: BlockKind) -> HashMap<Vec3<i32>, Block> {
+        positions
+            .iter()
+            .map(|pos| (*pos, Block::new(kind, Default::default())))
+            .collect()
+    }
+
+    #[test]
+    fn delta_contains_changes_since_known_version() {
+        let mut versions = ChunkVersions::default();
+        let key = Vec2::new(1, 2);
+        let origin = TerrainGrid::key_chunk(key).with_z(10);
+        let v0 = versions.version(key);
+
+        let a = origin;
+        let b = origin + Vec3::unit_x();
+        let changed = versions.record_blocks(&blocks(&[a], BlockKind::Rock));
+        let (_, v1) = changed[&key];
+        assert_eq!(changed[&key].0, v0);
+        versions.record_blocks(&blocks(&[a, b], BlockKind::Air));
+        let v2 = versions.version(key);
+
+        let (version, delta) = versions.delta(key, v1).unwrap();
+        assert_eq!(version, v2);
+        assert_eq!(delta, blocks(&[a, b], BlockKind::Air));
+
+        let (_, delta) = versions.delta(key, v0).unwrap();
+        assert_eq!(delta, blocks(&[a, b], BlockKind::Air));
+
+        let (_, delta) = versions.delta(key, v2).unwrap();
+        assert!(delta.is_empty());
+
+        // Changes in other chunks don't bump this chunk
+        let other = origin + Vec3::unit_x() * TerrainChunkSize::RECT_SIZE.x as i32;
+        let changed = versions.record_blocks(&blocks(&[other], BlockKind::Rock));
+        assert!(!changed.contains_key(&key));
+        assert_eq!(versions.version(key), v2);
+    }
+
+    #[test]
+    fn replaced_chunk_is_sent_as_delta() {
+        let mut versions = ChunkVersions::default();
+        let key = Vec2::new(0, 1);
+        let v0 = versions.version(key);
+        let old = TerrainChunk::new(
+            0,
+            Block::new(BlockKind::Rock, Default::default()),
+            Block::empty(),
+            TerrainChunkMeta::void(),
+        );
+        let mut new = old.clone();
+        let offs = Vec3::new(3, 4, -2);
+        new.set(offs, Block::empty()).unwrap();
+
+        versions.replace_with(key, &old, &new);
+        assert_eq!(versions.take_replaced()[&key], v0);
+        let (_, delta) = versions.delta(key, v0).unwrap();
+        let pos = TerrainGrid::key_chunk(key).with_z(0) + offs;
+        assert_eq!(delta, [(pos, Block::empty())].into());
+        assert!(versions.take_replaced().is_empty());
+    }
+
+    #[test]
+    fn no_delta_after_replace_or_trim() {
+        let mut versions = ChunkVersions::default();
+        let key = Vec2::zero();
+        let v0 = versions.version(key);
+        versions.replace(key);
+        assert!(versions.delta(key, v0).is_none());
+
+        let v1 = versions.version(key);
+        for i in 0..=MAX_HISTORY_BLOCKS as i32 {
+            versions.record_blocks(&blocks(&[Vec3::new(0, 0, i)], BlockKind::Rock));
+        }
+        assert!(versions.delta(key, v1).is_none());
+        let latest = versions.version(key);
+        assert!(versions.delta(key, latest).is_some());
+    }
+}
@@ -129,8 +129,9 @@ impl Client {
                     },
                     //Ingame related, terrain
                     ServerGeneral::TerrainChunkUpdate { .. }
+                    | ServerGeneral::TerrainChunkDelta { .. }
                     | ServerGeneral::LodZoneUpdate { .. }
-                    | ServerGeneral::TerrainBlockUpdates(_) => {
+                    | ServerGeneral::TerrainBlockUpdates { .. } => {
                         self.terrain_stream.lock().unwrap().send(g)
                     },
                     // Always possible
@@ -191,6 +192,10 @@ impl Client {
                     | ServerGeneral::ExitInGameSuccess
                     | ServerGeneral::InventoryUpdate(_, _)
                     | ServerGeneral::GroupInventoryUpdate(_, _)
+                    | ServerGeneral::GuildUpdate(_)
+                    | ServerGeneral::StorageUpdate(_)
+                    | ServerGeneral::MailboxUpdate(_)
+                    | ServerGeneral::MarketUpdate(_)
                     | ServerGeneral::Dialogue(_, _)
                     | ServerGeneral::SetViewDistance(_)
                     | ServerGeneral::Outcomes(_)
@@ -208,8 +213,9 @@ impl Client {
                     },
                     // Terrain
                     ServerGeneral::TerrainChunkUpdate { .. }
+                    | ServerGeneral::TerrainChunkDelta { .. }
                     | ServerGeneral::LodZoneUpdate { .. }
-                    | ServerGeneral::TerrainBlockUpdates(_) => {
+                    | ServerGeneral::TerrainBlockUpdates { .. } => {
                         PreparedMsg::new(5, &g, &self.terrain_stream_params)
                     },
                     // Always possible
@@ -225,7 +231,8 @@ impl Client {
                     | ServerGeneral::Disconnect(_)
                     | ServerGeneral::Notification(_)
                     | ServerGeneral::SetPlayerRole(_)
-                    | ServerGeneral::PluginData(_) => {
+                    | ServerGeneral::PluginData(_)
+                    | ServerGeneral::PluginReloaded { .. } => {
                         PreparedMsg::new(3, &g, &self.general_stream_params)
                     },
                 }
@@ -6,8 +6,10 @@ use crate::weather::WeatherJob;
 use crate::{
     Server, Settings, StateExt,
     client::Client,
+    guild::GuildManager,
     location::Locations,
     login_provider::LoginProvider,
+    persistence::character_loader::TransferRequester,
     settings::{
         BanInfo, BanOperation, BanOperationError, EditableSetting, SettingError, WhitelistInfo,
         WhitelistRecord,
@@ -40,6 +42,7 @@ use common::{
         agent::{FlightMode, PidControllers},
         aura::{AuraKindVariant, AuraTarget},
         buff::{Buff, BuffData, BuffKind, BuffSource, DestInfo, MiscBuffData},
+        guild::GuildRank,
         inventory::{
             item::{MaterialStatManifest, Quality, all_items_expect, tool::AbilityMap},
             slot::Slot,
@@ -51,7 +54,7 @@ use common::{
     effect::Effect,
     event::{
         ClientDisconnectEvent, CreateNpcEvent, CreateSpecialEntityEvent, EventBus, ExplosionEvent,
-        GroupManipEvent, InitiateInviteEvent, PermanentChange, TamePetEvent,
+        GroupManipEvent, GuildManipEvent, InitiateInviteEvent, PermanentChange, TamePetEvent,
     },
     generation::{EntityConfig, EntityInfo, SpecialEntity},
     link::Is,
@@ -64,7 +67,7 @@ use common::{
     spiral::Spiral2d,
     terrain::{Block, BlockKind, CoordinateConversions, SpriteKind, StructureSprite},
     tether::Tethered,
-    uid::Uid,
+    uid::{IdMaps, Uid},
     vol::ReadVol,
 };
 #[cfg(feature = "worldgen")]
@@ -83,7 +86,13 @@ use humantime::Duration as HumanDuration;
 use rand::{RngExt, rng};
 use specs::{Builder, Entity as EcsEntity, Join, LendJoin, WorldExt, storage::StorageEntry};
 use std::{
-    fmt::Write, net::SocketAddr, num::NonZeroU32, ops::DerefMut, str::FromStr, sync::Arc,
+    fmt::Write,
+    net::SocketAddr,
+    num::NonZeroU32,
+    ops::DerefMut,
+    path::{Component, Path, PathBuf},
+    str::FromStr,
+    sync::Arc,
     time::Duration,
 };
 use vek::*;
@@ -169,8 +178,11 @@ fn do_command(
         ServerChatCommand::DebugWays => handle_debug_ways,
         ServerChatCommand::DisconnectAllPlayers => handle_disconnect_all_players,
         ServerChatCommand::DropAll => handle_drop_all,
+        ServerChatCommand::Duel => handle_duel,
+        ServerChatCommand::DuelForfeit => handle_duel_forfeit,
         ServerChatCommand::Dummy => handle_spawn_training_dummy,
         ServerChatCommand::Explosion => handle_explosion,
+        ServerChatCommand::ExportCharacter => handle_export_character,
         ServerChatCommand::Faction => handle_faction,
         ServerChatCommand::GiveItem => handle_give_item,
         ServerChatCommand::Gizmos => handle_gizmos,
@@ -182,9 +194,16 @@ fn do_command(
         ServerChatCommand::GroupKick => handle_group_kick,
         ServerChatCommand::GroupLeave => handle_group_leave,
         ServerChatCommand::GroupPromote => handle_group_promote,
+        ServerChatCommand::Guild => handle_guild,
+        ServerChatCommand::GuildCreate => handle_guild_create,
+        ServerChatCommand::GuildDisband => handle_guild_disband,
+        ServerChatCommand::GuildInvite => handle_guild_invite,
+        ServerChatCommand::GuildKick => handle_guild_kick,
+        ServerChatCommand::GuildLeave => handle_guild_leave,
+        ServerChatCommand::GuildRank => handle_guild_rank,
         ServerChatCommand::Health => handle_health,
+        ServerChatCommand::ImportCharacter => handle_import_character,
         ServerChatCommand::IntoNpc => handle_into_npc,
-        ServerChatCommand::JoinFaction => handle_join_faction,
         ServerChatCommand::Jump => handle_jump,
         ServerChatCommand::Kick => handle_kick,
         ServerChatCommand::Kill => handle_kill,
@@ -205,7 +224,9 @@ fn do_command(
         ServerChatCommand::ResetRecipes => handle_reset_recipes,
         ServerChatCommand::Region => handle_region,
         ServerChatCommand::ReloadChunks => handle_reload_chunks,
+        ServerChatCommand::ReloadPlugin => handle_reload_plugin,
         ServerChatCommand::RemoveLights => handle_remove_lights,
+        ServerChatCommand::Reputation => handle_reputation,
         ServerChatCommand::Respawn => handle_respawn,
         ServerChatCommand::RevokeBuild => handle_revoke_build,
         ServerChatCommand::RevokeBuildAll => handle_revoke_build_all,
@@ -223,6 +244,8 @@ fn do_command(
         ServerChatCommand::Spot => handle_spot,
         ServerChatCommand::Sudo => handle_sudo,
         ServerChatCommand::Tell => handle_tell,
+        ServerChatCommand::TerrainHistory => handle_terrain_history,
+        ServerChatCommand::TerrainRollback => handle_terrain_rollback,
         ServerChatCommand::Time => handle_time,
         ServerChatCommand::TimeScale => handle_time_scale,
         ServerChatCommand::Tp => handle_tp,
@@ -825,6 +848,11 @@ fn handle_make_block(
             let pos = position(server, target, "target")?;
             let new_block = Block::new(bk, Rgb::new(r, g, b).map(|e| e.unwrap_or(255)));
             let pos = pos.0.map(|e| e.floor() as i32);
+            #[cfg(feature = "persistent_world")]
+            let old_block = server
+                .state
+                .get_block(pos)
+                .unwrap_or_else(|| Block::air(SpriteKind::Empty));
             server.state.set_block(pos, new_block);
             #[cfg(feature = "persistent_world")]
             if let Some(terrain_persistence) = server
@@ -833,7 +861,12 @@ fn handle_make_block(
                 .try_fetch_mut::<crate::TerrainPersistence>()
                 .as_mut()
             {
-                terrain_persistence.set_block(pos, new_block);
+                terrain_persistence.set_block(
+                    pos,
+                    old_block,
+                    new_block,
+                    uuid(server, _client, "client").ok(),
+                );
             }
             Ok(())
         } else {
@@ -1014,7 +1047,12 @@ fn handle_make_sprite(
                 .try_fetch_mut::<crate::TerrainPersistence>()
                 .as_mut()
             {
-                terrain_persistence.set_block(pos, block);
+                terrain_persistence.set_block(
+                    pos,
+                    old_block,
+                    block,
+                    uuid(server, _client, "client").ok(),
+                );
             }
         };
         if let Ok(sk) = SpriteKind::try_from(sprite_name.as_str()) {
@@ -1468,6 +1506,92 @@ fn handle_site(
     }
 }
 
+fn handle_reputation(
+    server: &mut Server,
+    client: EcsEntity,
+    target: EcsEntity,
+    _args: Vec<String>,
+    _action: &ServerChatCommand,
+) -> CmdResult<()> {
+    use crate::rtsim::RtSim;
+    use common::rtsim::ReputationLevel;
+
+    let actor_id = server
+        .state
+        .read_component_copied::<common::rtsim::ActorId>(target)
+        .ok_or(Content::localized("command-reputation-unavailable"))?;
+
+    let mut lines = Vec::new();
+    {
+        let rtsim = server.state.ecs().read_resource::<RtSim>();
+        let data = rtsim.state().data();
+        let index = server.index.as_index_ref();
+        let site_name = |site: common::rtsim::SiteId| {
+            data.sites
+                .get(site)
+                .and_then(|site| site.world_site)
+                .and_then(|ws| index.sites.get(ws).name())
+                .map(|name| name.to_string())
+        };
+        let reputation = data
+            .actors
+            .get(actor_id)
+            .and_then(|actor| actor.character())
+            .map(|character| character.reputation.clone())
+            .unwrap_or_default();
+
+        let mut sites = reputation
+            .sites()
+            .filter_map(|(site, value)| Some((site_name(site)?, value)))
+            .collect::<Vec<_>>();
+        sites.sort_by(|(a, _), (b, _)| a.cmp(b));
+        for (site, value) in sites {
+            lines.push(Content::localized_with_args("command-reputation-site", [
+                ("site", LocalizationArg::from(site)),
+                (
+                    "level",
+                    Content::localized(ReputationLevel::from_value(value).localization_key())
+                        .into(),
+                ),
+            ]));
+        }
+        // Factions don't have names, so describe them by one of the sites they own
+        for (faction, value) in reputation.factions() {
+            if let Some(site) = data
+                .sites
+                .iter()
+                .filter(|(_, site)| site.faction == Some(faction))
+                .find_map(|(site, _)| site_name(site))
+            {
+                lines.push(Content::localized_with_args(
+                    "command-reputation-faction",
+                    [
+                        ("site", LocalizationArg::from(site)),
+                        (
+                            "level",
+                            Content::localized(
+                                ReputationLevel::from_value(value).localization_key(),
+                            )
+                            .into(),
+                        ),
+                    ],
+                ));
+            }
+        }
+    }
+
+    if lines.is_empty() {
+        lines.push(Content::localized("command-reputation-none"));
+    }
+    for line in lines {
+        server.notify_client(
+            client,
+            ServerGeneral::server_msg(ChatType::CommandInfo, line),
+        );
+    }
+    Ok(())
+}
+
 fn handle_respawn(
     server: &mut Server,
     _client: EcsEntity,
@@ -2675,6 +2799,257 @@ fn handle_clear_persisted_terrain(
     ))
 }
 
+#[cfg(feature = "persistent_world")]
+fn handle_terrain_history(
+    server: &mut Server,
+    client: EcsEntity,
+    target: EcsEntity,
+    args: Vec<String>,
+    action: &ServerChatCommand,
+) -> CmdResult<()> {
+    use crate::terrain_persistence::TerrainPersistence;
+    const HISTORY_LIMIT: usize = 10;
+
+    let pos = if let (Some(x), Some(y), Some(z)) = parse_cmd_args!(args, i32, i32, i32) {
+        Vec3::new(x, y, z)
+    } else if args.is_empty() {
+        // The block the target is standing on
+        position(server, target, "target")?
+            .0
+            .map(|e| e.floor() as i32)
+            - Vec3::unit_z()
+    } else {
+        return Err(action.help_content());
+    };
+
+    server
+        .state
+        .ecs()
+        .try_fetch_mut::<TerrainPersistence>()
+        .ok_or_else(|| Content::localized("command-experimental-terrain-persistence-disabled"))?
+        .query_history(client, pos, HISTORY_LIMIT)
+        .map_err(|err| {
+            error!(?err, "Failed to read terrain edit journal");
+            Content::localized("command-terrain-journal-error")
+        })
+}
+
+/// Replies to a finished `/terrain_history` or `/terrain_rollback` query,
+/// applying the rollback.
+#[cfg(feature = "persistent_world")]
+pub fn handle_journal_response(
+    server: &mut Server,
+    response: crate::terrain_persistence::JournalResponse,
+) {
+    use crate::terrain_persistence::{JournalResponse, TerrainPersistence};
+
+    let journal_error = |client: EcsEntity, err: std::io::Error| {
+        error!(?err, "Failed to read terrain edit journal");
+        server.notify_client(
+            client,
+            ServerGeneral::server_msg(
+                ChatType::CommandError,
+                Content::localized("command-terrain-journal-error"),
+            ),
+        );
+    };
+
+    let (client, msgs) = match response {
+        JournalResponse::History { client, pos, edits } => {
+            let history = match edits {
+                Ok(history) => history,
+                Err(err) => return journal_error(client, err),
+            };
+            if history.is_empty() {
+                server.notify_client(
+                    client,
+                    ServerGeneral::server_msg(
+                        ChatType::CommandError,
+                        Content::localized_with_args("command-terrain_history-empty", [(
+                            "pos",
+                            pos.to_string(),
+                        )]),
+                    ),
+                );
+                return;
+            }
+
+            let describe_block = |block: Block| match block.get_sprite() {
+                Some(sprite) if sprite != SpriteKind::Empty => format!("{sprite:?}"),
+                _ => format!("{:?}", block.kind()),
+            };
+            let players = server.state.ecs().read_storage::<comp::Player>();
+            let author_name = |author: Option<Uuid>| match author {
+                Some(uuid) => Content::Plain(
+                    (&players)
+                        .join()
+                        .find(|player| player.uuid() == uuid)
+                        .map_or_else(|| uuid.to_string(), |player| player.alias.clone()),
+                ),
+                None => Content::localized("command-terrain_history-server"),
+            };
+            let header = Content::localized_with_args("command-terrain_history-header", [(
+                "pos",
+                pos.to_string(),
+            )]);
+            let msgs = std::iter::once(header)
+                .chain(history.iter().map(|edit| {
+                    let time = DateTime::from_timestamp(edit.time as i64, 0)
+                        .map_or_else(|| edit.time.to_string(), |time| time.to_string());
+                    Content::localized_with_args("command-terrain_history-edit", [
+                        ("time", Content::Plain(time)),
+                        ("author", author_name(edit.author)),
+                        ("old", Content::Plain(describe_block(edit.old))),
+                        ("new", Content::Plain(describe_block(edit.new))),
+                    ])
+                }))
+                .collect::<Vec<_>>();
+            (client, msgs)
+        },
+        JournalResponse::Rollback {
+            client,
+            player,
+            author,
+            reverts,
+        } => {
+            let reverts = match reverts {
+                Ok(reverts) => reverts,
+                Err(err) => return journal_error(client, err),
+            };
+            let Some(mut terrain_persistence) =
+                server.state.ecs().try_fetch_mut::<TerrainPersistence>()
+            else {
+                return;
+            };
+            let terrain = server.state.terrain();
+            let (reverted, skipped) =
+                terrain_persistence.rollback(reverts, author, |pos| terrain.get(pos).ok().copied());
+            drop(terrain);
+            drop(terrain_persistence);
+            // Blocks in unloaded chunks are applied by the terrain persistence when they
+            // load
+            for revert in &reverted {
+                server.state.set_block(revert.pos, revert.restore);
+            }
+
+            let count = reverted.len().to_string();
+            let msg = if skipped > 0 {
+                Content::localized_with_args("command-terrain_rollback-done-skipped", [
+                    ("count", count),
+                    ("player", player),
+                    ("skipped", skipped.to_string()),
+                ])
+            } else {
+                Content::localized_with_args("command-terrain_rollback-done", [
+                    ("count", count),
+                    ("player", player),
+                ])
+            };
+            (client, vec![msg])
+        },
+    };
+    for msg in msgs {
+        server.notify_client(
+            client,
+            ServerGeneral::server_msg(ChatType::CommandInfo, msg),
+        );
+    }
+}
+
+#[cfg(not(feature = "persistent_world"))]
+fn handle_terrain_history(
+    _server: &mut Server,
+    _client: EcsEntity,
+    _target: EcsEntity,
+    _args: Vec<String>,
+    _action: &ServerChatCommand,
+) -> CmdResult<()> {
+    Err(Content::localized(
+        "command-server-no-experimental-terrain-persistence",
+    ))
+}
+
+#[cfg(feature = "persistent_world")]
+fn handle_terrain_rollback(
+    server: &mut Server,
+    client: EcsEntity,
+    target: EcsEntity,
+    args: Vec<String>,
+    action: &ServerChatCommand,
+) -> CmdResult<()> {
+    use crate::terrain_persistence::{EditFilter, TerrainPersistence, unix_time};
+
+    let (Some(username), radius, parse_duration) =
+        parse_cmd_args!(args, String, i32, HumanDuration)
+    else {
+        return Err(action.help_content());
+    };
+
+    let area = match radius {
+        Some(radius) => {
+            let center = position(server, target, "target")?
+                .0
+                .map(|e| e.floor() as i32);
+            // Wider than any world, larger areas wouldn't select more edits
+            const MAX_RADIUS: i32 = 1 << 16;
+            let radius = radius.clamp(0, MAX_RADIUS);
+            Some(Aabb {
+                min: center
+                    .xy()
+                    .map(|e| e.saturating_sub(radius))
+                    .with_z(i32::MIN),
+                max: center
+                    .xy()
+                    .map(|e| e.saturating_add(radius))
+                    .with_z(i32::MAX),
+            })
+        },
+        None => None,
+    };
+    let filter = EditFilter {
+        author: Some(find_username(server, &username)?),
+        area,
+        since: parse_duration.map(|duration| {
+            let duration: Duration = duration.into();
+            unix_time().saturating_sub(duration.as_secs())
+        }),
+    };
+    let client_uuid = uuid(server, client, "client").ok();
+
+    server
+        .state
+        .ecs()
+        .try_fetch_mut::<TerrainPersistence>()
+        .ok_or_else(|| Content::localized("command-experimental-terrain-persistence-disabled"))?
+        .query_rollback(client, filter, username, client_uuid)
+        .map_err(|err| {
+            error!(?err, "Failed to read terrain edit journal");
+            Content::localized("command-terrain-journal-error")
+        })?;
+
+    server.notify_client(
+        client,
+        ServerGeneral::server_msg(
+            ChatType::CommandInfo,
+            Content::localized("command-terrain_rollback-started"),
+        ),
+    );
+    Ok(())
+}
+
+#[cfg(not(feature = "persistent_world"))]
+fn handle_terrain_rollback(
+    _server: &mut Server,
+    _client: EcsEntity,
+    _target: EcsEntity,
+    _args: Vec<String>,
+    _action: &ServerChatCommand,
+) -> CmdResult<()> {
+    Err(Content::localized(
+        "command-server-no-experimental-terrain-persistence",
+    ))
+}
+
 fn handle_safezone(
     server: &mut Server,
     client: EcsEntity,
@@ -2905,10 +3280,7 @@ fn handle_build(
         can_build.enabled ^= true;
 
         let msg = Content::localized(
-            match (
-                can_build.enabled,
-                server.settings().experimental_terrain_persistence,
-            ) {
+            match (can_build.enabled, server.settings().terrain_persistence) {
                 (false, _) => "command-set-build-mode-off",
                 (true, false) => "command-set-build-mode-on-unpersistent",
                 (true, true) => "command-set-build-mode-on-persistent",
@@ -3806,6 +4178,70 @@ fn handle_lantern(
     }
 }
 
+/// Exported characters are only read and written within this directory when
+/// using the chat commands.
+fn character_exports_dir(server: &Server) -> PathBuf {
+    server.data_dir().path.join("character_exports")
+}
+
+fn handle_export_character(
+    server: &mut Server,
+    client: EcsEntity,
+    _target: EcsEntity,
+    args: Vec<String>,
+    action: &ServerChatCommand,
+) -> CmdResult<()> {
+    let (Some(username), Some(character)) = parse_cmd_args!(args, String, String) else {
+        return Err(action.help_content());
+    };
+
+    let dir = character_exports_dir(server);
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| Content::Plain(format!("Failed to create {}: {e}", dir.display())))?;
+    let file_safe = |name: &str| {
+        name.chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect::<String>()
+    };
+    let file = format!(
+        "{}_{}_{}.json",
+        file_safe(&username),
+        file_safe(&character),
+        Utc::now().format("%Y%m%d_%H%M%S")
+    );
+    server.export_character(
+        &username,
+        &character,
+        dir.join(file),
+        TransferRequester::Client(client),
+    );
+    Ok(())
+}
+
+fn handle_import_character(
+    server: &mut Server,
+    client: EcsEntity,
+    _target: EcsEntity,
+    args: Vec<String>,
+    action: &ServerChatCommand,
+) -> CmdResult<()> {
+    let (Some(username), Some(file), alias) = parse_cmd_args!(args, String, String, String) else {
+        return Err(action.help_content());
+    };
+    if !matches!(Path::new(&file).components().collect::<Vec<_>>()[..], [
+        Component::Normal(_)
+    ]) {
+        return Err(Content::localized_with_args(
+            "command-character-transfer-invalid-file",
+            [("file", file)],
+        ));
+    }
+
+    let path = character_exports_dir(server).join(&file);
+    server.import_character(&username, path, alias, TransferRequester::Client(client));
+    Ok(())
+}
+
 fn handle_explosion(
     server: &mut Server,
     _client: EcsEntity,
@@ -4157,10 +4593,10 @@ fn handle_faction(
     no_sudo(client, target)?;
     can_send_message(target, server)?;
 
-    let factions = server.state.ecs().read_storage();
-    if let Some(comp::Faction(faction)) = factions.get(target) {
-        let mode = comp::ChatMode::Faction(faction.to_string());
-        drop(factions);
+    let guilds = server.state.ecs().read_storage::<comp::Guild>();
+    if let Some(guild) = guilds.get(target) {
+        let mode = comp::ChatMode::Faction(guild.name.clone());
+        drop(guilds);
         insert_or_replace_component(server, target, mode.clone(), "target")?;
         let msg = args.join(" ");
         if !msg.is_empty()
@@ -4310,6 +4746,231 @@ fn handle_group_promote(
     }
 }
 
+fn handle_guild(
+    server: &mut Server,
+    _client: EcsEntity,
+    target: EcsEntity,
+    _args: Vec<String>,
+    _action: &ServerChatCommand,
+) -> CmdResult<()> {
+    let character_id = server
+        .state
+        .ecs()
+        .read_storage::<comp::Presence>()
+        .get(target)
+        .and_then(|presence| presence.kind.character_id());
+    let roster = {
+        let guild_manager = server.state.ecs().read_resource::<GuildManager>();
+        let id_maps = server.state.ecs().read_resource::<IdMaps>();
+        let uids = server.state.ecs().read_storage::<Uid>();
+        character_id
+            .and_then(|character_id| guild_manager.guild_of(character_id))
+            .and_then(|id| {
+                guild_manager.roster(id, |character_id| {
+                    id_maps
+                        .character_entity(character_id)
+                        .and_then(|entity| uids.get(entity).copied())
+                })
+            })
+    };
+    let Some(roster) = roster else {
+        return Err(Content::localized("command-guild-not_in_guild"));
+    };
+
+    let members = roster
+        .members
+        .iter()
+        .map(|member| {
+            format!(
+                "{} ({}{})",
+                member.name,
+                member.rank.as_ref(),
+                if member.online.is_some() {
+                    ", online"
+                } else {
+                    ""
+                }
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+    server.notify_client(
+        target,
+        ServerGeneral::server_msg(
+            ChatType::CommandInfo,
+            Content::localized_with_args("command-guild-roster", [
+                ("guild", roster.name),
+                ("members", members),
+            ]),
+        ),
+    );
+    Ok(())
+}
+
+fn handle_guild_create(
+    server: &mut Server,
+    _client: EcsEntity,
+    target: EcsEntity,
+    args: Vec<String>,
+    action: &ServerChatCommand,
+) -> CmdResult<()> {
+    let name = args.join(" ");
+    if name.is_empty() {
+        return Err(action.help_content());
+    }
+
+    server
+        .state
+        .emit_event_now(GuildManipEvent(target, comp::GuildManip::Create(name)));
+    Ok(())
+}
+
+fn handle_guild_disband(
+    server: &mut Server,
+    _client: EcsEntity,
+    target: EcsEntity,
+    _args: Vec<String>,
+    _action: &ServerChatCommand,
+) -> CmdResult<()> {
+    server
+        .state
+        .emit_event_now(GuildManipEvent(target, comp::GuildManip::Disband));
+    Ok(())
+}
+
+fn handle_guild_invite(
+    server: &mut Server,
+    client: EcsEntity,
+    target: EcsEntity,
+    args: Vec<String>,
+    action: &ServerChatCommand,
+) -> CmdResult<()> {
+    // Checking whether the target may invite is done in guild_manip
+    if let Some(target_alias) = parse_cmd_args!(args, String) {
+        let target_player = find_alias(server.state.ecs(), &target_alias, false)?.0;
+        let uid = uid(server, target_player, "player")?;
+
+        server
+            .state
+            .emit_event_now(InitiateInviteEvent(target, uid, InviteKind::Guild));
+
+        server.notify_client(
+            client,
+            ServerGeneral::server_msg(
+                ChatType::CommandInfo,
+                Content::localized_with_args("command-guild_invite-invited", [(
+                    "player",
+                    target_alias,
+                )]),
+            ),
+        );
+        Ok(())
+    } else {
+        Err(action.help_content())
+    }
+}
+
+fn handle_duel(
+    server: &mut Server,
+    client: EcsEntity,
+    target: EcsEntity,
+    args: Vec<String>,
+    action: &ServerChatCommand,
+) -> CmdResult<()> {
+    // Whether the players can duel is checked when the invite is sent
+    if let Some(target_alias) = parse_cmd_args!(args, String) {
+        let target_player = find_alias(server.state.ecs(), &target_alias, false)?.0;
+        let uid = uid(server, target_player, "player")?;
+
+        server
+            .state
+            .emit_event_now(InitiateInviteEvent(target, uid, InviteKind::Duel));
+
+        server.notify_client(
+            client,
+            ServerGeneral::server_msg(
+                ChatType::CommandInfo,
+                Content::localized_with_args("command-duel-challenged", [("player", target_alias)]),
+            ),
+        );
+        Ok(())
+    } else {
+        Err(action.help_content())
+    }
+}
+
+fn handle_duel_forfeit(
+    server: &mut Server,
+    _client: EcsEntity,
+    target: EcsEntity,
+    _args: Vec<String>,
+    _action: &ServerChatCommand,
+) -> CmdResult<()> {
+    // The duel ends on the next tick, see `sys::duel`
+    match server
+        .state
+        .ecs()
+        .write_storage::<comp::Duel>()
+        .get_mut(target)
+    {
+        Some(duel) => {
+            duel.forfeited = true;
+            Ok(())
+        },
+        None => Err(Content::localized("command-duel-not_dueling")),
+    }
+}
+
+fn handle_guild_kick(
+    server: &mut Server,
+    _client: EcsEntity,
+    target: EcsEntity,
+    args: Vec<String>,
+    action: &ServerChatCommand,
+) -> CmdResult<()> {
+    // Members may be offline, so they are looked up by character name in
+    // guild_manip, which also checks the ranks
+    if let Some(member) = parse_cmd_args!(args, String) {
+        server
+            .state
+            .emit_event_now(GuildManipEvent(target, comp::GuildManip::Kick(member)));
+        Ok(())
+    } else {
+        Err(action.help_content())
+    }
+}
+
+fn handle_guild_leave(
+    server: &mut Server,
+    _client: EcsEntity,
+    target: EcsEntity,
+    _args: Vec<String>,
+    _action: &ServerChatCommand,
+) -> CmdResult<()> {
+    server
+        .state
+        .emit_event_now(GuildManipEvent(target, comp::GuildManip::Leave));
+    Ok(())
+}
+
+fn handle_guild_rank(
+    server: &mut Server,
+    _client: EcsEntity,
+    target: EcsEntity,
+    args: Vec<String>,
+    action: &ServerChatCommand,
+) -> CmdResult<()> {
+    if let (Some(member), Some(rank)) = parse_cmd_args!(args, String, GuildRank) {
+        server.state.emit_event_now(GuildManipEvent(
+            target,
+            comp::GuildManip::AssignRank(member, rank),
+        ));
+        Ok(())
+    } else {
+        Err(action.help_content())
+    }
+}
+
 fn handle_reset_recipes(
     server: &mut Server,
     _client: EcsEntity,
@@ -4403,73 +5064,6 @@ fn handle_world(
     Ok(())
 }
 
-fn handle_join_faction(
-    server: &mut Server,
-    client: EcsEntity,
-    target: EcsEntity,
-    args: Vec<String>,
-    _action: &ServerChatCommand,
-) -> CmdResult<()> {
-    no_sudo(client, target)?;
-    let emit_join_message = server
-        .state
-        .ecs()
-        .read_storage::<Client>()
-        .get(target)
-        .is_some_and(|client| client.client_type.emit_login_events());
-
-    let players = server.state.ecs().read_storage::<comp::Player>();
-    if let Some(alias) = players.get(target).map(|player| player.alias.clone()) {
-        drop(players);
-        let (faction_leave, mode) = if let Some(faction) = parse_cmd_args!(args, String) {
-            let mode = comp::ChatMode::Faction(faction.clone());
-            insert_or_replace_component(server, target, mode.clone(), "target")?;
-            let faction_join = server
-                .state
-                .ecs()
-                .write_storage()
-                .insert(target, comp::Faction(faction.clone()))
-                .ok()
-                .flatten()
-                .map(|f| f.0);
-
-            if emit_join_message {
-                server.state.send_chat(
-                    // TODO: Localise
-                    ChatType::FactionMeta(faction.clone())
-                        .into_plain_msg(format!("[{}] joined faction ({})", alias, faction)),
-                    false,
-                );
-            }
-            (faction_join, mode)
-        } else {
-            let mode = comp::ChatMode::default();
-            insert_or_replace_component(server, target, mode.clone(), "target")?;
-            let faction_leave = server
-                .state
-                .ecs()
-                .write_storage()
-                .remove(target)
-                .map(|comp::Faction(f)| f);
-            (faction_leave, mode)
-        };
-        if let Some(faction) = faction_leave
-            && emit_join_message
-        {
-            server.state.send_chat(
-                // TODO: Localise
-                ChatType::FactionMeta(faction.clone())
-                    .into_plain_msg(format!("[{}] left faction ({})", alias, faction)),
-                false,
-            );
-        }
-        server.notify_client(target, ServerGeneral::ChatMode(mode));
-        Ok(())
-    } else {
-        Err(Content::Plain("Could not find your player alias".into()))
-    }
-}
-
 fn handle_death_effect(
     server: &mut Server,
     _client: EcsEntity,
@@ -4868,6 +5462,48 @@ fn handle_reload_chunks(
     Ok(())
 }
 
+#[cfg(feature = "plugins")]
+fn handle_reload_plugin(
+    server: &mut Server,
+    client: EcsEntity,
+    _target: EcsEntity,
+    args: Vec<String>,
+    action: &ServerChatCommand,
+) -> CmdResult<()> {
+    let Some(name) = parse_cmd_args!(args, String) else {
+        return Err(action.help_content());
+    };
+
+    if let Err(error) = server.reload_plugin(&name) {
+        warn!(?error, "Failed to reload plugin {name}");
+        return Err(Content::localized_with_args(
+            "command-reload_plugin-failed",
+            [("plugin", name)],
+        ));
+    }
+
+    server.notify_client(
+        client,
+        ServerGeneral::server_msg(
+            ChatType::CommandInfo,
+            Content::localized_with_args("command-reloaded-plugin", [("plugin", name)]),
+        ),
+    );
+
+    Ok(())
+}
+
+#[cfg(not(feature = "plugins"))]
+fn handle_reload_plugin(
+    _server: &mut Server,
+    _client: EcsEntity,
+    _target: EcsEntity,
+    _args: Vec<String>,
+    _action: &ServerChatCommand,
+) -> CmdResult<()> {
+    Err(Content::localized("command-server-no-plugins"))
+}
+
 fn handle_remove_lights(
     server: &mut Server,
     client: EcsEntity,
@@ -6013,7 +6649,12 @@ fn build_buff(
     }
 }
 
-fn cast_buff(buffkind: BuffKind, data: BuffData, server: &mut Server, target: EcsEntity) {
+pub(crate) fn cast_buff(
+    buffkind: BuffKind,
+    data: BuffData,
+    server: &mut Server,
+    target: EcsEntity,
+) {
     let ecs = &server.state.ecs();
     let mut buffs_all = ecs.write_storage::<comp::Buffs>();
     let stats = ecs.read_storage::<comp::Stats>();
@@ -83,10 +83,12 @@ pub fn handle_loaded_character_data(server: &mut Server, ev: UpdateCharacterData
         stats: ev.components.2,
         skill_set: ev.components.3,
         inventory: ev.components.4,
-        waypoint: ev.components.5,
-        pets: ev.components.6,
-        active_abilities: ev.components.7,
-        map_marker: ev.components.8,
+        storage: ev.components.5,
+        waypoint: ev.components.6,
+        pets: ev.components.7,
+        active_abilities: ev.components.8,
+        map_marker: ev.components.9,
+        reputation: ev.components.10,
     };
     if let Some(marker) = loaded_components.map_marker {
         server.notify_client(
@@ -145,6 +145,8 @@ event_emitters! {
         destroy: DestroyEvent,
         downed: DownedEvent,
         outcome: Outcome,
+        #[cfg(feature = "plugins")]
+        plugin: common::event::PluginGameEvent,
     }
 
     struct DestroyEvents[DestroyEmitters] {
@@ -158,6 +160,8 @@ event_emitters! {
         combo_change: ComboChangeEvent,
         poise_change: PoiseChangeEvent,
         knockback: KnockbackEvent,
+        #[cfg(feature = "plugins")]
+        plugin: common::event::PluginGameEvent,
     }
 }
 
@@ -200,6 +204,29 @@ impl ServerEvent for PoiseChangeEvent {
     }
 }
 
+/// Duelists can't be killed by their opponent (or its pets), the duel ends
+/// before that
+fn limit_duel_damage(
+    change: &mut HealthChange,
+    health: &Health,
+    duel: Option<&comp::Duel>,
+    id_maps: &IdMaps,
+    alignments: &ReadStorage<Alignment>,
+) {
+    if change.amount < 0.0
+        && let Some(duel) = duel
+        && change.damage_by().is_some_and(|by| {
+            by.uid() == duel.opponent
+                || id_maps
+                    .uid_entity(by.uid())
+                    .and_then(|attacker| alignments.get(attacker))
+                    == Some(&Alignment::Owned(duel.opponent))
+        })
+    {
+        change.amount = change.amount.max(-comp::Duel::max_damage(health));
+    }
+}
+
 #[derive(SystemData)]
 pub struct HealthChangeEventData<'a> {
     entities: Entities<'a>,
@@ -208,7 +235,6 @@ pub struct HealthChangeEventData<'a> {
     rtsim: WriteExpect<'a, RtSim>,
     events: HealthChangeEvents<'a>,
     time: Read<'a, Time>,
-    #[cfg(feature = "worldgen")]
     id_maps: Read<'a, IdMaps>,
     #[cfg(feature = "worldgen")]
     world: ReadExpect<'a, Arc<World>>,
@@ -219,6 +245,8 @@ pub struct HealthChangeEventData<'a> {
     #[cfg(feature = "worldgen")]
     rtsim_actors: ReadStorage<'a, rtsim::ActorId>,
     inventories: ReadStorage<'a, Inventory>,
+    alignments: ReadStorage<'a, Alignment>,
+    duels: ReadStorage<'a, comp::Duel>,
     agents: WriteStorage<'a, Agent>,
     healths: WriteStorage<'a, Health>,
     heads: WriteStorage<'a, Heads>,
@@ -230,7 +258,7 @@ impl ServerEvent for HealthChangeEvent {
     fn handle(events: impl ExactSizeIterator<Item = Self>, mut data: Self::SystemData<'_>) {
         let mut emitters = data.events.get_emitters();
         let mut rng = rand::rng();
-        for ev in events {
+        for mut ev in events {
             if let Some((mut health, inventory, pos, uid, heads)) = (
                 &mut data.healths,
                 data.inventories.maybe(),
@@ -249,6 +277,14 @@ impl ServerEvent for HealthChangeEvent {
                     continue;
                 }
 
+                limit_duel_damage(
+                    &mut ev.change,
+                    &health,
+                    data.duels.get(ev.entity),
+                    &data.id_maps,
+                    &data.alignments,
+                );
+
                 // If the change amount was not zero
                 let changed = health.change_by(ev.change);
                 if let Some(mut heads) = heads {
@@ -291,6 +327,18 @@ impl ServerEvent for HealthChangeEvent {
                     }
                 }
 
+                #[cfg(feature = "plugins")]
+                if let Some(uid) = uid
+                    && changed
+                    && ev.change.amount < 0.0
+                {
+                    emitters.emit(common::event::PluginGameEvent::EntityDamage {
+                        entity: *uid,
+                        attacker: ev.change.damage_by().map(|by| by.uid()),
+                        amount: -ev.change.amount,
+                    });
+                }
+
                 if let (Some(pos), Some(uid)) = (pos, uid)
                     && changed
                 {
@@ -599,6 +647,14 @@ impl ServerEvent for DestroyEvent {
                         data.entities_died_last_tick.0.push((ev.entity, pos));
                     }
 
+                    #[cfg(feature = "plugins")]
+                    if let Some(uid) = data.uids.get(ev.entity) {
+                        emitters.emit(common::event::PluginGameEvent::EntityDeath {
+                            entity: *uid,
+                            killer: ev.cause.damage_by().map(|by| by.uid()),
+                        });
+                    }
+
                     if let Some(body) = data.bodies.get(ev.entity) {
                         let npc_names = NPC_NAMES.read();
                         let body_type = npc_names
@@ -1577,6 +1633,8 @@ pub struct ExplosionData<'a> {
     auras: ReadStorage<'a, Auras>,
     positions: ReadStorage<'a, Pos>,
     players: ReadStorage<'a, Player>,
+    guilds: ReadStorage<'a, comp::Guild>,
+    duels: ReadStorage<'a, comp::Duel>,
     energies: ReadStorage<'a, Energy>,
     combos: ReadStorage<'a, comp::Combo>,
     inventories: ReadStorage<'a, Inventory>,
@@ -2025,6 +2083,8 @@ impl ServerEvent for ExplosionEvent {
                                 let permit_pvp = combat::permit_pvp(
                                     &data.alignments,
                                     &data.players,
+                                    &data.guilds,
+                                    &data.duels,
                                     &data.entered_auras,
                                     &data.id_maps,
                                     owner_entity,
@@ -2085,6 +2145,8 @@ impl ServerEvent for ExplosionEvent {
                                 combat::permit_pvp(
                                     &data.alignments,
                                     &data.players,
+                                    &data.guilds,
+                                    &data.duels,
                                     &data.entered_auras,
                                     &data.id_maps,
                                     owner_entity,
@@ -3672,3 +3734,60 @@ pub fn handle_start_interaction(
         debug!("Error trying to start interaction between {i:?} and {t:?}: {e:?}");
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::comp::{Duel, humanoid};
+    use specs::{Builder, World};
+    use vek::Vec3;
+
+    fn hit(by: Uid) -> HealthChange {
+        HealthChange {
+            amount: -10_000.0,
+            by: Some(DamageContributor::Solo(by)),
+            cause: Some(DamageSource::Other),
+            time: Time(0.0),
+            precise: false,
+            instance: 0,
+        }
+    }
+
+    #[test]
+    fn duel_hits_cannot_kill() {
+        let mut world = World::new();
+        world.insert(IdMaps::new());
+        world.register::<Alignment>();
+        let [opponent, stranger] = [(); 2].map(|_| world.create_entity().build());
+        let opponent_uid = world.write_resource::<IdMaps>().allocate(opponent);
+        let pet = world
+            .create_entity()
+            .with(Alignment::Owned(opponent_uid))
+            .build();
+        let (stranger_uid, pet_uid) = {
+            let mut id_maps = world.write_resource::<IdMaps>();
+            (id_maps.allocate(stranger), id_maps.allocate(pet))
+        };
+        let duel = Duel::new(opponent_uid, Vec3::zero(), Time(0.0));
+        let id_maps = world.read_resource::<IdMaps>();
+        let alignments = world.read_storage::<Alignment>();
+        let body = Body::Humanoid(humanoid::Body::random());
+
+        for by in [opponent_uid, pet_uid] {
+            let mut health = Health::new(body);
+            let mut change = hit(by);
+            limit_duel_damage(&mut change, &health, Some(&duel), &id_maps, &alignments);
+            health.change_by(change);
+            assert!(health.current() > 0.0);
+            assert!(Duel::is_defeated(&health));
+        }
+
+        let health = Health::new(body);
+        let mut change = hit(stranger_uid);
+        limit_duel_damage(&mut change, &health, Some(&duel), &id_maps, &alignments);
+        assert_eq!(change, hit(stranger_uid));
+        let mut change = hit(opponent_uid);
+        limit_duel_damage(&mut change, &health, None, &id_maps, &alignments);
+        assert_eq!(change, hit(opponent_uid));
+    }
+}
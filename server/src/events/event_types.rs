@@ -5,19 +5,20 @@ pub use common::event::{
     CreateNpcGroupEvent, CreateObjectEvent, CreatePoolEvent, CreateShipEvent,
     CreateSpecialEntityEvent, CreateSpriteEvent, DeleteCharacterEvent, DeleteEvent, DestroyEvent,
     DialogueEvent, DownedEvent, EnergyChangeEvent, EntityAttackedHookEvent, EventBus,
-    ExitIngameEvent, ExplosionEvent, GroupManipEvent, HealthChangeEvent, HelpDownedEvent,
-    InitializeCharacterEvent, InitializeSpectatorEvent, InitiateInviteEvent, InventoryManipEvent,
-    InviteResponseEvent, KillEvent, KnockbackEvent, LandOnGroundEvent, MakeAdminEvent,
-    MineBlockEvent, MountEvent, NpcInteractEvent, ParryHookEvent, PoiseChangeEvent, PossessEvent,
-    ProcessTradeActionEvent, RegrowHeadEvent, RemoveLightEmitterEvent, RequestSiteInfoEvent,
-    RespawnEvent, SetBattleModeEvent, SetLanternEvent, SetPetStayEvent, ShockwaveEvent, ShootEvent,
-    SoundEvent, StartInteractionEvent, StartTeleportingEvent, SummonBeamPillarsEvent, TamePetEvent,
+    ExitIngameEvent, ExplosionEvent, GroupManipEvent, GuildManipEvent, HealthChangeEvent,
+    HelpDownedEvent, InitializeCharacterEvent, InitializeSpectatorEvent, InitiateInviteEvent,
+    InventoryManipEvent, InviteResponseEvent, KillEvent, KnockbackEvent, LandOnGroundEvent,
+    MailEvent, MakeAdminEvent, MarketEvent, MineBlockEvent, MountEvent, NpcInteractEvent,
+    ParryHookEvent, PoiseChangeEvent, PossessEvent, ProcessTradeActionEvent, RegrowHeadEvent,
+    RemoveLightEmitterEvent, RequestSiteInfoEvent, RespawnEvent, SetBattleModeEvent,
+    SetLanternEvent, SetPetStayEvent, ShockwaveEvent, ShootEvent, SoundEvent,
+    StartInteractionEvent, StartTeleportingEvent, SummonBeamPillarsEvent, TamePetEvent,
     TeleportToEvent, TeleportToPositionEvent, ThrowEvent, ToggleSpriteLightEvent, TransformEvent,
     UpdateCharacterDataEvent, UpdateMapMarkerEvent,
 };
 
 #[cfg(feature = "plugins")]
-pub use common::event::RequestPluginsEvent;
+pub use common::event::{PluginGameEvent, RequestPluginsEvent};
 
 /// X-macro that provides list of server events to the macro this is called
 /// with.
@@ -45,6 +46,9 @@ macro_rules! server_events {
             DestroyEvent
             InventoryManipEvent
             GroupManipEvent
+            GuildManipEvent
+            MailEvent
+            MarketEvent
             RespawnEvent
             ShootEvent
             ThrowEvent
@@ -99,6 +103,7 @@ macro_rules! server_events {
         #[cfg(feature = "plugins")]
         $macro! {
             RequestPluginsEvent
+            PluginGameEvent
         }
     };
 }
@@ -0,0 +1,345 @@
+use crate::{
+    client::Client,
+    guild::{GuildError, GuildManager, GuildMember},
+};
+use common::{
+    character::CharacterId,
+    comp::{
+        self, ChatType, Content, GuildManip, Presence,
+        guild::{GuildId, GuildRank},
+    },
+    event::GuildManipEvent,
+    uid::{IdMaps, Uid},
+};
+use common_net::msg::ServerGeneral;
+use specs::{
+    DispatcherBuilder, Entity, Read, ReadStorage, SystemData, WriteExpect, WriteStorage, shred,
+};
+
+use super::{ServerEvent, event_dispatch};
+
+pub(super) fn register_event_systems(builder: &mut DispatcherBuilder) {
+    event_dispatch::<GuildManipEvent>(builder, &[]);
+}
+
+/// Sends the roster of the guild to all of its members that are in game
+pub fn send_guild_roster(
+    guild_manager: &GuildManager,
+    id_maps: &IdMaps,
+    uids: &ReadStorage<'_, Uid>,
+    clients: &ReadStorage<'_, Client>,
+    id: GuildId,
+) {
+    let Some(roster) = guild_manager.roster(id, |character_id| {
+        id_maps
+            .character_entity(character_id)
+            .and_then(|entity| uids.get(entity).copied())
+    }) else {
+        return;
+    };
+
+    for client in roster
+        .members
+        .iter()
+        .filter_map(|member| id_maps.character_entity(member.character_id))
+        .filter_map(|entity| clients.get(entity))
+    {
+        client.send_fallible(ServerGeneral::GuildUpdate(Some(roster.clone())));
+    }
+}
+
+/// Checks whether the inviter may invite the invitee into their guild and
+/// informs the inviter if not
+pub fn can_invite(
+    guild_manager: &GuildManager,
+    clients: &ReadStorage<'_, Client>,
+    presences: &ReadStorage<'_, Presence>,
+    inviter: Entity,
+    invitee: Entity,
+) -> bool {
+    let character_id = |entity| {
+        presences
+            .get(entity)
+            .and_then(|presence| presence.kind.character_id())
+    };
+    let inviter_rank = character_id(inviter)
+        .and_then(|character_id| guild_manager.member(character_id))
+        .map(|(_, member)| member.rank);
+
+    let error = match (inviter_rank, character_id(invitee)) {
+        (None, _) => GuildError::NotInGuild.into(),
+        (Some(rank), _) if !rank.can_invite() => Content::localized("command-guild-no_permission"),
+        (_, None) => Content::localized("command-guild-invite-no_character"),
+        (_, Some(invitee)) if guild_manager.guild_of(invitee).is_some() => {
+            Content::localized("command-guild-invite-already_in_guild")
+        },
+        _ => return true,
+    };
+    if let Some(client) = clients.get(inviter) {
+        client.send_fallible(ServerGeneral::server_msg(ChatType::Meta, error));
+    }
+    false
+}
+
+#[derive(SystemData)]
+pub struct GuildData<'a> {
+    guild_manager: WriteExpect<'a, GuildManager>,
+    id_maps: Read<'a, IdMaps>,
+    guilds: WriteStorage<'a, comp::Guild>,
+    chat_modes: WriteStorage<'a, comp::ChatMode>,
+    clients: ReadStorage<'a, Client>,
+    uids: ReadStorage<'a, Uid>,
+    presences: ReadStorage<'a, Presence>,
+    stats: ReadStorage<'a, comp::Stats>,
+    players: ReadStorage<'a, comp::Player>,
+}
+
+impl GuildData<'_> {
+    fn character_id(&self, entity: Entity) -> Option<CharacterId> {
+        self.presences
+            .get(entity)
+            .and_then(|presence| presence.kind.character_id())
+    }
+
+    /// The character of the entity, as it would join a guild
+    fn new_member(&self, entity: Entity) -> Option<(CharacterId, GuildMember)> {
+        Some((self.character_id(entity)?, GuildMember {
+            name: self.stats.get(entity)?.name.clone(),
+            player_uuid: self.players.get(entity)?.uuid().to_string(),
+            rank: GuildRank::Member,
+        }))
+    }
+
+    fn inform(&self, entity: Entity, chat_type: ChatType<String>, content: Content) {
+        if let Some(client) = self.clients.get(entity) {
+            client.send_fallible(ServerGeneral::server_msg(chat_type, content));
+        }
+    }
+
+    /// Sends a notification to every member of the guild that is in game
+    fn notify(&self, id: GuildId, content: Content) {
+        let Some(guild) = self.guild_manager.guild(id) else {
+            return;
+        };
+        for character_id in guild.members.keys() {
+            if let Some(entity) = self.id_maps.character_entity(*character_id) {
+                self.inform(
+                    entity,
+                    ChatType::FactionMeta(guild.name.clone()),
+                    content.clone(),
+                );
+            }
+        }
+    }
+
+    fn send_roster(&self, id: GuildId) {
+        send_guild_roster(
+            &self.guild_manager,
+            &self.id_maps,
+            &self.uids,
+            &self.clients,
+            id,
+        );
+    }
+
+    /// Gives the character the guild component if it is in game
+    fn attach(&mut self, character_id: CharacterId, id: GuildId) {
+        if let Some(entity) = self.id_maps.character_entity(character_id)
+            && let Some(guild) = self.guild_manager.guild(id)
+        {
+            let _ = self.guilds.insert(entity, guild.component(id));
+        }
+    }
+
+    /// Removes everything related to a guild the character is no longer part
+    /// of, if it is in game
+    fn detach(&mut self, character_id: CharacterId) {
+        let Some(entity) = self.id_maps.character_entity(character_id) else {
+            return;
+        };
+        self.guilds.remove(entity);
+        if let Some(mode) = self.chat_modes.get_mut(entity)
+            && matches!(mode, comp::ChatMode::Faction(_))
+        {
+            *mode = comp::ChatMode::default();
+            if let Some(client) = self.clients.get(entity) {
+                client.send_fallible(ServerGeneral::ChatMode(mode.clone()));
+            }
+        }
+        if let Some(client) = self.clients.get(entity) {
+            client.send_fallible(ServerGeneral::GuildUpdate(None));
+        }
+    }
+}
+
+impl ServerEvent for GuildManipEvent {
+    type SystemData<'a> = GuildData<'a>;
+
+    fn handle(events: impl ExactSizeIterator<Item = Self>, mut data: Self::SystemData<'_>) {
+        for GuildManipEvent(entity, manip) in events {
+            if let Err(error) = handle_guild_manip(&mut data, entity, manip) {
+                data.inform(entity, ChatType::CommandError, error);
+            }
+        }
+    }
+}
+
+fn handle_guild_manip(
+    data: &mut GuildData,
+    entity: Entity,
+    manip: GuildManip,
+) -> Result<(), Content> {
+    if let GuildManip::Create(name) = manip {
+        let (character_id, member) = data
+            .new_member(entity)
+            .ok_or_else(|| Content::localized("command-guild-no_character"))?;
+        let id = data
+            .guild_manager
+            .create(name.clone(), character_id, member)?;
+        data.attach(character_id, id);
+        data.notify(
+            id,
+            Content::localized_with_args("command-guild-created", [("guild", name)]),
+        );
+        data.send_roster(id);
+        return Ok(());
+    }
+
+    let character_id = data.character_id(entity).ok_or(GuildError::NotInGuild)?;
+    let (id, member) = data
+        .guild_manager
+        .member(character_id)
+        .ok_or(GuildError::NotInGuild)?;
+    let (rank, name) = (member.rank, member.name.clone());
+    let find_member = |data: &GuildData, target: &str| {
+        data.guild_manager
+            .guild(id)
+            .and_then(|guild| {
+                let character_id = guild.find_member(target)?;
+                Some((character_id, guild.members.get(&character_id)?))
+            })
+            .map(|(character_id, member)| (character_id, member.rank, member.name.clone()))
+            .ok_or_else(|| {
+                Content::localized_with_args("command-guild-member-missing", [(
+                    "player",
+                    target.to_owned(),
+                )])
+            })
+    };
+
+    match manip {
+        GuildManip::Create(_) => {},
+        GuildManip::Leave => {
+            let guild_name = data.guild_manager.guild(id).map(|guild| guild.name.clone());
+            data.guild_manager.remove_member(character_id);
+            data.detach(character_id);
+            data.inform(
+                entity,
+                ChatType::Meta,
+                Content::localized_with_args("command-guild-you-left", [(
+                    "guild",
+                    guild_name.unwrap_or_default(),
+                )]),
+            );
+            data.notify(
+                id,
+                Content::localized_with_args("command-guild-left", [("player", name)]),
+            );
+            data.send_roster(id);
+        },
+        GuildManip::Kick(target) => {
+            let (target, target_rank, target_name) = find_member(data, &target)?;
+            if !rank.can_kick(target_rank) {
+                return Err(Content::localized("command-guild-no_permission"));
+            }
+            if let Some(target_entity) = data.id_maps.character_entity(target)
+                && let Some(guild) = data.guild_manager.guild(id)
+            {
+                data.inform(
+                    target_entity,
+                    ChatType::Meta,
+                    Content::localized_with_args("command-guild-you-were-kicked", [(
+                        "guild",
+                        guild.name.clone(),
+                    )]),
+                );
+            }
+            data.guild_manager.remove_member(target);
+            data.detach(target);
+            data.notify(
+                id,
+                Content::localized_with_args("command-guild-kicked", [
+                    ("player", target_name),
+                    ("by", name),
+                ]),
+            );
+            data.send_roster(id);
+        },
+        GuildManip::AssignRank(target, new_rank) => {
+            if !rank.can_assign_ranks() {
+                return Err(Content::localized("command-guild-no_permission"));
+            }
+            let (target, _, target_name) = find_member(data, &target)?;
+            // There always has to be a leader, who can only pass on their rank
+            if target == character_id {
+                return Err(Content::localized("command-guild-rank-self"));
+            }
+            data.guild_manager.set_rank(target, new_rank);
+            data.notify(
+                id,
+                Content::localized_with_args("command-guild-rank-changed", [
+                    ("player", target_name),
+                    ("rank", new_rank.as_ref().to_owned()),
+                ]),
+            );
+            data.send_roster(id);
+        },
+        GuildManip::Disband => {
+            if rank != GuildRank::Leader {
+                return Err(Content::localized("command-guild-no_permission"));
+            }
+            data.notify(
+                id,
+                Content::localized_with_args("command-guild-disbanded", [("player", name)]),
+            );
+            if let Some(guild) = data.guild_manager.disband(id) {
+                for member in guild.members.into_keys() {
+                    data.detach(member);
+                }
+            }
+        },
+    }
+
+    Ok(())
+}
+
+/// Adds the invitee to the guild of the inviter after they accepted the invite
+pub fn join_guild(data: &mut GuildData, inviter: Entity, invitee: Entity) {
+    // The inviter could have left the guild while the invite was pending
+    let Some(id) = data
+        .character_id(inviter)
+        .and_then(|character_id| data.guild_manager.guild_of(character_id))
+    else {
+        data.inform(
+            invitee,
+            ChatType::Meta,
+            Content::localized("command-guild-invite-expired"),
+        );
+        return;
+    };
+    let Some((character_id, member)) = data.new_member(invitee) else {
+        return;
+    };
+
+    let name = member.name.clone();
+    if let Err(error) = data.guild_manager.add_member(id, character_id, member) {
+        data.inform(invitee, ChatType::CommandError, error.into());
+        return;
+    }
+    data.attach(character_id, id);
+    data.notify(
+        id,
+        Content::localized_with_args("command-guild-joined", [("player", name)]),
+    );
+    data.send_roster(id);
+}
@@ -33,6 +33,8 @@ use common::{
     vol::ReadVol,
 };
 use comp::LightEmitter;
+#[cfg(feature = "plugins")]
+use comp::item::ItemDesc;
 
 use crate::client::Client;
 use common::comp::{Alignment, CollectFailedReason, Group, InventoryUpdateEvent, pet::is_tameable};
@@ -73,6 +75,8 @@ event_emitters! {
         change_body: ChangeBodyEvent,
         outcome: Outcome,
         stance: ChangeStanceEvent,
+        #[cfg(feature = "plugins")]
+        plugin: common::event::PluginGameEvent,
     }
 }
 #[derive(SystemData)]
@@ -115,6 +119,7 @@ pub struct InventoryManipData<'a> {
     agents: ReadStorage<'a, comp::Agent>,
     pets: ReadStorage<'a, comp::Pet>,
     masses: ReadStorage<'a, comp::Mass>,
+    storages: WriteStorage<'a, comp::PersonalStorage>,
     #[cfg(feature = "worldgen")]
     rtsim_actors: ReadStorage<'a, common::rtsim::ActorId>,
 }
@@ -247,6 +252,8 @@ impl ServerEvent for InventoryManipEvent {
                                                               its PickupItem component.";
 
                     let (item, reinsert_item) = item.pick_up();
+                    #[cfg(feature = "plugins")]
+                    let item_id = item.persistence_item_id();
 
                     let mut item_msg = item.frontend_item(&data.ability_map, &data.msm);
 
@@ -339,6 +346,15 @@ impl ServerEvent for InventoryManipEvent {
                         },
                     };
 
+                    #[cfg(feature = "plugins")]
+                    if let InventoryUpdateEvent::Collected(collected) = &event {
+                        emitters.emit(common::event::PluginGameEvent::ItemPickup {
+                            entity: *uid,
+                            item: item_id,
+                            amount: collected.amount().get(),
+                        });
+                    }
+
                     if let Some(buf) = data.inventory_update_buffers.get_mut(entity) {
                         buf.push(event);
                     }
@@ -1134,6 +1150,63 @@ impl ServerEvent for InventoryManipEvent {
                 comp::InventoryManip::Delete(slot, amount) => {
                     let _ = inventory.take_amount(slot, amount, &data.ability_map, &data.msm);
                 },
+                comp::InventoryManip::OpenStorage(sprite_pos) => {
+                    if can_access_storage(&data.terrain, get_cylinder(entity), sprite_pos)
+                        && let Some(storage) = data.storages.get_mut(entity)
+                    {
+                        storage.opened_at = Some(sprite_pos);
+                        send_storage(&data.clients, entity, storage);
+                    }
+                },
+                comp::InventoryManip::CloseStorage => {
+                    if let Some(storage) = data.storages.get_mut(entity) {
+                        storage.opened_at = None;
+                    }
+                },
+                manip @ (comp::InventoryManip::Store(..)
+                | comp::InventoryManip::Retrieve(..)
+                | comp::InventoryManip::StorageSwap(..)) => {
+                    let Some(storage) = data.storages.get_mut(entity) else {
+                        continue;
+                    };
+                    if !storage.opened_at.is_some_and(|sprite_pos| {
+                        can_access_storage(&data.terrain, get_cylinder(entity), sprite_pos)
+                    }) {
+                        // The storage sprite is out of range or was destroyed
+                        storage.opened_at = None;
+                        send_storage(&data.clients, entity, storage);
+                        continue;
+                    }
+
+                    match manip {
+                        comp::InventoryManip::Store(inv_slot, storage_slot) => {
+                            comp::storage::transfer(
+                                &mut inventory,
+                                inv_slot,
+                                &mut storage.inventory,
+                                storage_slot,
+                            );
+                        },
+                        comp::InventoryManip::Retrieve(storage_slot, inv_slot) => {
+                            comp::storage::transfer(
+                                &mut storage.inventory,
+                                storage_slot,
+                                &mut inventory,
+                                inv_slot,
+                            );
+                        },
+                        comp::InventoryManip::StorageSwap(a, b) => {
+                            if !storage.inventory.merge_stack_into(a, b) {
+                                storage.inventory.swap_slots(a, b);
+                            }
+                        },
+                        _ => {},
+                    }
+                    send_storage(&data.clients, entity, storage);
+                    if let Some(buf) = data.inventory_update_buffers.get_mut(entity) {
+                        buf.push(InventoryUpdateEvent::Swapped);
+                    }
+                },
             }
             if data.trades.in_mutable_trade(uid) {
                 // manipulating the inventory mutated the trade, so reset the accept flags
@@ -1163,6 +1236,33 @@ impl ServerEvent for InventoryManipEvent {
     }
 }
 
+/// Whether the entity is close enough to a storage sprite at `sprite_pos` to
+/// access its personal storage
+fn can_access_storage(
+    terrain: &common::terrain::TerrainGrid,
+    entity_cylinder: Option<find_dist::Cylinder>,
+    sprite_pos: Vec3<i32>,
+) -> bool {
+    terrain
+        .get(sprite_pos)
+        .ok()
+        .and_then(|block| block.get_sprite())
+        .is_some_and(|sprite| sprite.is_storage())
+        && within_pickup_range(entity_cylinder, || {
+            Some(sprite_pos.map(|e| e as f32) + Vec3::broadcast(0.5))
+        })
+}
+
+fn send_storage(clients: &ReadStorage<Client>, entity: EcsEntity, storage: &comp::PersonalStorage) {
+    if let Some(client) = clients.get(entity) {
+        client.send_fallible(ServerGeneral::StorageUpdate(
+            storage
+                .opened_at
+                .map(|sprite_pos| (sprite_pos, storage.inventory.clone())),
+        ));
+    }
+}
+
 fn within_pickup_range<S: FindDist<find_dist::Cylinder>>(
     entity_cylinder: Option<find_dist::Cylinder>,
     shape_fn: impl FnOnce() -> Option<S>,
@@ -1,25 +1,29 @@
 use super::{
     ServerEvent, event_dispatch,
     group_manip::{self, update_map_markers},
+    guild_manip::{self, GuildData},
 };
-use crate::{Settings, client::Client};
+#[cfg(feature = "worldgen")]
+use crate::rtsim::RtSim;
+use crate::{Settings, client::Client, guild::GuildManager};
 use common::{
     comp::{
-        self, CharacterState, ChatType, Content, Group, Health, Pos,
+        self, CharacterState, ChatType, Content, Duel, Group, Health, Pos, Presence,
         agent::{Agent, AgentEvent},
+        duel::{DUEL_ARENA_RADIUS, DUEL_DURATION, MAX_DUEL_INVITE_RANGE},
         group::GroupManager,
         invite::{Invite, InviteKind, InviteResponse, PendingInvites},
     },
     consts::MAX_TRADE_RANGE,
     event::{InitiateInviteEvent, InviteResponseEvent},
+    resources::Time,
     trade::{TradeResult, Trades},
     uid::{IdMaps, Uid},
 };
 use common_net::msg::{InviteAnswer, ServerGeneral};
-#[cfg(feature = "worldgen")]
-use specs::ReadExpect;
 use specs::{
-    DispatcherBuilder, Entities, Entity, Read, ReadStorage, SystemData, Write, WriteStorage, shred,
+    DispatcherBuilder, Entities, Entity, Read, ReadExpect, ReadStorage, SystemData, Write,
+    WriteStorage, shred,
 };
 use std::time::{Duration, Instant};
 use tracing::{error, warn};
@@ -43,6 +47,7 @@ impl ServerEvent for InitiateInviteEvent {
         Read<'a, Settings>,
         Read<'a, IdMaps>,
         Read<'a, GroupManager>,
+        ReadExpect<'a, GuildManager>,
         WriteStorage<'a, PendingInvites>,
         WriteStorage<'a, Agent>,
         WriteStorage<'a, Invite>,
@@ -52,6 +57,8 @@ impl ServerEvent for InitiateInviteEvent {
         ReadStorage<'a, Group>,
         ReadStorage<'a, Health>,
         ReadStorage<'a, CharacterState>,
+        ReadStorage<'a, Presence>,
+        ReadStorage<'a, Duel>,
     );
 
     fn handle(
@@ -62,6 +69,7 @@ impl ServerEvent for InitiateInviteEvent {
             settings,
             id_maps,
             group_manager,
+            guild_manager,
             mut pending_invites,
             mut agents,
             mut invites,
@@ -71,6 +79,8 @@ impl ServerEvent for InitiateInviteEvent {
             groups,
             healths,
             character_states,
+            presences,
+            duels,
         ): Self::SystemData<'_>,
     ) {
         for InitiateInviteEvent(inviter, invitee_uid, kind) in events {
@@ -98,17 +108,17 @@ impl ServerEvent for InitiateInviteEvent {
                 continue;
             }
 
+            let is_alive_and_well = |entity| {
+                entities.is_alive(entity)
+                    && !comp::is_downed_or_dead(healths.get(entity), character_states.get(entity))
+            };
             if matches!(kind, InviteKind::Trade) {
-                let is_alive_and_well = |entity| {
-                    entities.is_alive(entity)
-                        && !comp::is_downed_or_dead(
-                            healths.get(entity),
-                            character_states.get(entity),
-                        )
-                };
                 // Check whether the inviter is in range of the invitee or dead
-                if !within_trading_range(positions.get(inviter), positions.get(invitee))
-                    || !is_alive_and_well(inviter)
+                if !within_range(
+                    positions.get(inviter),
+                    positions.get(invitee),
+                    MAX_TRADE_RANGE,
+                ) || !is_alive_and_well(inviter)
                     || !is_alive_and_well(invitee)
                 {
                     continue;
@@ -127,6 +137,42 @@ impl ServerEvent for InitiateInviteEvent {
                 ) {
                     continue;
                 }
+            } else if let InviteKind::Guild = kind {
+                if !guild_manip::can_invite(&guild_manager, &clients, &presences, inviter, invitee)
+                {
+                    continue;
+                }
+            } else if let InviteKind::Duel = kind {
+                if !is_alive_and_well(inviter) || !is_alive_and_well(invitee) {
+                    continue;
+                }
+                let error = if !clients.contains(invitee) {
+                    Some("command-duel-not_a_player")
+                } else if duels.contains(inviter) || duels.contains(invitee) {
+                    Some("command-duel-already_dueling")
+                } else if groups.get(inviter).is_some()
+                    && groups.get(inviter) == groups.get(invitee)
+                {
+                    // Group members can't harm each other
+                    Some("command-duel-same_group")
+                } else if !within_range(
+                    positions.get(inviter),
+                    positions.get(invitee),
+                    MAX_DUEL_INVITE_RANGE,
+                ) {
+                    Some("command-duel-too_far")
+                } else {
+                    None
+                };
+                if let Some(error) = error {
+                    if let Some(client) = clients.get(inviter) {
+                        client.send_fallible(ServerGeneral::server_msg(
+                            ChatType::Meta,
+                            Content::localized(error),
+                        ));
+                    }
+                    continue;
+                }
             } else {
                 // cancel current trades for inviter before inviting someone else to trade
                 if let Some(inviter_uid) = uids.get(inviter).copied()
@@ -234,6 +280,10 @@ pub struct InviteResponseData<'a> {
     trades: Write<'a, Trades>,
     #[cfg(feature = "worldgen")]
     index: ReadExpect<'a, IndexOwned>,
+    #[cfg(feature = "worldgen")]
+    rtsim: ReadExpect<'a, RtSim>,
+    #[cfg(feature = "worldgen")]
+    rtsim_actors: ReadStorage<'a, common::rtsim::ActorId>,
     id_maps: Read<'a, IdMaps>,
     invites: WriteStorage<'a, Invite>,
     pending_invites: WriteStorage<'a, PendingInvites>,
@@ -243,6 +293,10 @@ pub struct InviteResponseData<'a> {
     clients: ReadStorage<'a, Client>,
     alignments: ReadStorage<'a, comp::Alignment>,
     map_markers: ReadStorage<'a, comp::MapMarker>,
+    positions: ReadStorage<'a, Pos>,
+    duels: WriteStorage<'a, Duel>,
+    time: Read<'a, Time>,
+    guild_data: GuildData<'a>,
 }
 
 impl ServerEvent for InviteResponseEvent {
@@ -286,6 +340,8 @@ pub fn handle_invite_accept(data: &mut InviteResponseData, entity: Entity) {
                     },
                 );
             },
+            InviteKind::Guild => guild_manip::join_guild(&mut data.guild_data, inviter, entity),
+            InviteKind::Duel => start_duel(data, inviter, entity),
             InviteKind::Trade => {
                 if let (Some(inviter_uid), Some(invitee_uid)) = (
                     data.uids.get(inviter).copied(),
@@ -325,21 +381,27 @@ pub fn handle_invite_accept(data: &mut InviteResponseData, entity: Entity) {
                             .push_back(AgentEvent::TradeAccepted(invitee_uid));
                     }
                     #[cfg(feature = "worldgen")]
-                    let pricing = data
-                        .agents
-                        .get(inviter)
-                        .and_then(|a| {
-                            a.behavior
-                                .trade_site()
-                                .and_then(|id| data.index.get_site_prices(id))
-                        })
-                        .or_else(|| {
-                            data.agents.get(entity).and_then(|a| {
+                    let pricing = [(inviter, entity), (entity, inviter)].into_iter().find_map(
+                        |(merchant, customer)| {
+                            let prices = data.agents.get(merchant).and_then(|a| {
                                 a.behavior
                                     .trade_site()
                                     .and_then(|id| data.index.get_site_prices(id))
-                            })
-                        });
+                            })?;
+                            // Merchants adjust their prices to the reputation of their customer
+                            Some(
+                                match (
+                                    data.rtsim_actors.get(merchant),
+                                    data.rtsim_actors.get(customer),
+                                ) {
+                                    (Some(merchant), Some(customer)) => prices.with_reputation(
+                                        data.rtsim.reputation_standing(*merchant, *customer),
+                                    ),
+                                    _ => prices,
+                                },
+                            )
+                        },
+                    );
                     #[cfg(not(feature = "worldgen"))]
                     let pricing = None;
 
@@ -359,6 +421,47 @@ pub fn handle_invite_accept(data: &mut InviteResponseData, entity: Entity) {
     }
 }
 
+/// Starts the duel once the invitee accepted, unless one of the duelists
+/// walked away or started another duel in the meantime
+fn start_duel(data: &mut InviteResponseData, inviter: Entity, invitee: Entity) {
+    let inform = |entity, content: Content| {
+        if let Some(client) = data.clients.get(entity) {
+            client.send_fallible(ServerGeneral::server_msg(ChatType::Meta, content));
+        }
+    };
+    let (Some(inviter_uid), Some(invitee_uid), Some(inviter_pos), Some(invitee_pos)) = (
+        data.uids.get(inviter).copied(),
+        data.uids.get(invitee).copied(),
+        data.positions.get(inviter),
+        data.positions.get(invitee),
+    ) else {
+        return;
+    };
+    if data.duels.contains(inviter)
+        || data.duels.contains(invitee)
+        || !within_range(Some(inviter_pos), Some(invitee_pos), MAX_DUEL_INVITE_RANGE)
+    {
+        for entity in [inviter, invitee] {
+            inform(entity, Content::localized("command-duel-start_failed"));
+        }
+        return;
+    }
+
+    let arena = (inviter_pos.0 + invitee_pos.0) / 2.0;
+    for (entity, opponent) in [(inviter, invitee_uid), (invitee, inviter_uid)] {
+        let _ = data
+            .duels
+            .insert(entity, Duel::new(opponent, arena, *data.time));
+        inform(
+            entity,
+            Content::localized_with_args("hud-chat-duel-started", [
+                ("radius", DUEL_ARENA_RADIUS as u64),
+                ("minutes", (DUEL_DURATION / 60.0) as u64),
+            ]),
+        );
+    }
+}
+
 fn get_inviter_and_kind(
     entity: Entity,
     data: &mut InviteResponseData,
@@ -423,9 +526,13 @@ pub fn handle_invite_decline(data: &mut InviteResponseData, entity: Entity) {
     }
 }
 
-fn within_trading_range(requester_position: Option<&Pos>, invitee_position: Option<&Pos>) -> bool {
+fn within_range(
+    requester_position: Option<&Pos>,
+    invitee_position: Option<&Pos>,
+    range: f32,
+) -> bool {
     match (requester_position, invitee_position) {
-        (Some(rpos), Some(ipos)) => rpos.0.distance_squared(ipos.0) < MAX_TRADE_RANGE.powi(2),
+        (Some(rpos), Some(ipos)) => rpos.0.distance_squared(ipos.0) < range.powi(2),
         _ => false,
     }
 }
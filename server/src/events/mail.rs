@@ -0,0 +1,298 @@
+use crate::{
+    client::Client,
+    mail::{MailError, MailManager},
+};
+use common::{
+    assets::AssetExt,
+    character::CharacterId,
+    comp::{
+        self, ChatType, Content, InventoryUpdateEvent, MailAction, Presence,
+        item::{ItemDef, MaterialStatManifest, tool::AbilityMap},
+        mail::MailDraft,
+    },
+    consts::MAX_PICKUP_RANGE,
+    event::MailEvent,
+    terrain::TerrainGrid,
+    trade::Trades,
+    uid::{IdMaps, Uid},
+    util::find_dist::{self, FindDist},
+    vol::ReadVol,
+};
+use common_net::msg::{Notification, ServerGeneral};
+use specs::{
+    DispatcherBuilder, Entity, Read, ReadExpect, ReadStorage, SystemData, WriteExpect,
+    WriteStorage, shred,
+};
+use std::sync::Arc;
+use vek::*;
+
+use super::{ServerEvent, event_dispatch};
+
+pub(super) fn register_event_systems(builder: &mut DispatcherBuilder) {
+    event_dispatch::<MailEvent>(builder, &[]);
+}
+
+const COINS: &str = "common.items.utility.coins";
+
+/// Sends the mailbox to the character if it has one open, and tells it about
+/// its unread mail
+pub fn notify_mail_recipient(
+    mail_manager: &MailManager,
+    id_maps: &IdMaps,
+    clients: &ReadStorage<'_, Client>,
+    character_id: CharacterId,
+) {
+    let Some(client) = id_maps
+        .character_entity(character_id)
+        .and_then(|entity| clients.get(entity))
+    else {
+        return;
+    };
+    if let Some(pos) = mail_manager.opened_at(character_id) {
+        client.send_fallible(ServerGeneral::MailboxUpdate(Some((
+            pos,
+            mail_manager.mailbox(character_id),
+        ))));
+    }
+    let count = mail_manager.unread(character_id);
+    if count > 0 {
+        client.send_fallible(ServerGeneral::Notification(Notification::UnreadMail {
+            count,
+        }));
+    }
+}
+
+#[derive(SystemData)]
+pub struct MailData<'a> {
+    mail_manager: WriteExpect<'a, MailManager>,
+    id_maps: Read<'a, IdMaps>,
+    trades: Read<'a, Trades>,
+    terrain: ReadExpect<'a, TerrainGrid>,
+    ability_map: ReadExpect<'a, AbilityMap>,
+    msm: ReadExpect<'a, MaterialStatManifest>,
+    inventories: WriteStorage<'a, comp::Inventory>,
+    inventory_update_buffers: WriteStorage<'a, comp::InventoryUpdateBuffer>,
+    presences: ReadStorage<'a, Presence>,
+    clients: ReadStorage<'a, Client>,
+    uids: ReadStorage<'a, Uid>,
+    positions: ReadStorage<'a, comp::Pos>,
+    scales: ReadStorage<'a, comp::Scale>,
+    colliders: ReadStorage<'a, comp::Collider>,
+    character_states: ReadStorage<'a, comp::CharacterState>,
+}
+
+impl MailData<'_> {
+    fn inform(&self, entity: Entity, chat_type: ChatType<String>, content: Content) {
+        if let Some(client) = self.clients.get(entity) {
+            client.send_fallible(ServerGeneral::server_msg(chat_type, content));
+        }
+    }
+
+    /// Whether the entity is close enough to a mailbox at `sprite_pos`
+    fn can_access_mailbox(&self, entity: Entity, sprite_pos: Vec3<i32>) -> bool {
+        let is_mailbox = self
+            .terrain
+            .get(sprite_pos)
+            .ok()
+            .and_then(|block| block.get_sprite())
+            .is_some_and(|sprite| sprite.is_mailbox());
+        let cylinder = self.positions.get(entity).map(|pos| {
+            find_dist::Cylinder::from_components(
+                pos.0,
+                self.scales.get(entity).copied(),
+                self.colliders.get(entity),
+                self.character_states.get(entity),
+            )
+        });
+        is_mailbox
+            && cylinder.is_some_and(|cylinder| {
+                (sprite_pos.map(|e| e as f32) + Vec3::broadcast(0.5)).min_distance(cylinder)
+                    < MAX_PICKUP_RANGE
+            })
+    }
+
+    fn send_mailbox(&self, entity: Entity, character_id: CharacterId) {
+        if let Some(client) = self.clients.get(entity) {
+            client.send_fallible(ServerGeneral::MailboxUpdate(
+                self.mail_manager
+                    .opened_at(character_id)
+                    .map(|pos| (pos, self.mail_manager.mailbox(character_id))),
+            ));
+        }
+    }
+
+    fn notify_recipient(&self, character_id: CharacterId) {
+        notify_mail_recipient(
+            &self.mail_manager,
+            &self.id_maps,
+            &self.clients,
+            character_id,
+        );
+    }
+
+    /// Takes the attachments of the draft from the inventory, nothing is taken
+    /// if any of them is missing
+    fn take_attachments(
+        &mut self,
+        entity: Entity,
+        draft: &MailDraft,
+    ) -> Result<Vec<comp::Item>, MailError> {
+        let inventory = self
+            .inventories
+            .get_mut(entity)
+            .ok_or(MailError::NotFound)?;
+        let mut new_inventory = inventory.clone();
+        if draft.coins > 0 {
+            let coins = Arc::<ItemDef>::load_cloned(COINS).map_err(|_| MailError::NotFound)?;
+            new_inventory
+                .remove_item_amount(&coins, draft.coins, &self.ability_map, &self.msm)
+                .ok_or(MailError::NotEnoughCoins)?;
+        }
+        let attachments = draft
+            .attachments
+            .iter()
+            .map(|slot| new_inventory.remove(*slot).ok_or(MailError::NotFound))
+            .collect::<Result<Vec<_>, _>>()?;
+        *inventory = new_inventory;
+        Ok(attachments)
+    }
+}
+
+impl ServerEvent for MailEvent {
+    type SystemData<'a> = MailData<'a>;
+
+    fn handle(events: impl ExactSizeIterator<Item = Self>, mut data: Self::SystemData<'_>) {
+        for MailEvent(entity, action) in events {
+            let Some(character_id) = data
+                .presences
+                .get(entity)
+                .and_then(|presence| presence.kind.character_id())
+            else {
+                continue;
+            };
+            if let Err(error) = handle_mail_action(&mut data, entity, character_id, action) {
+                data.inform(entity, ChatType::CommandError, error.into());
+            }
+        }
+    }
+}
+
+/// Moves the coins and items into the inventory, returning whatever doesn't fit
+fn give_attachments(
+    inventory: &mut comp::Inventory,
+    coins: u32,
+    attachments: Vec<comp::Item>,
+) -> (u32, Vec<comp::Item>) {
+    let coins_left = if coins == 0 {
+        0
+    } else {
+        match comp::Item::new_from_asset(COINS) {
+            Ok(mut item) => match item.set_amount(coins) {
+                Ok(()) => inventory
+                    .push(item)
+                    .map_or_else(|(item, _)| item.amount(), |()| 0),
+                Err(_) => coins,
+            },
+            Err(_) => coins,
+        }
+    };
+    let items_left = inventory
+        .push_all(attachments.into_iter())
+        .map_or_else(|error| error.returned_items().collect(), |()| Vec::new());
+    (coins_left, items_left)
+}
+
+fn handle_mail_action(
+    data: &mut MailData,
+    entity: Entity,
+    character_id: CharacterId,
+    action: MailAction,
+) -> Result<(), MailError> {
+    match action {
+        MailAction::Open(sprite_pos) => {
+            if data.can_access_mailbox(entity, sprite_pos) {
+                data.mail_manager.open(character_id, sprite_pos)?;
+                data.send_mailbox(entity, character_id);
+            }
+            return Ok(());
+        },
+        MailAction::Close => {
+            data.mail_manager.close(character_id);
+            return Ok(());
+        },
+        _ => {},
+    }
+
+    if !data
+        .mail_manager
+        .opened_at(character_id)
+        .is_some_and(|sprite_pos| data.can_access_mailbox(entity, sprite_pos))
+    {
+        // The mailbox is out of range or was destroyed
+        data.mail_manager.close(character_id);
+        data.send_mailbox(entity, character_id);
+        return Ok(());
+    }
+    // Attachments can't be added to or taken from an inventory that is traded
+    let in_trade = data
+        .uids
+        .get(entity)
+        .is_some_and(|uid| data.trades.in_immutable_trade(uid));
+
+    match action {
+        MailAction::Open(_) | MailAction::Close => {},
+        MailAction::Send(draft) => {
+            if in_trade {
+                return Ok(());
+            }
+            let recipient = data.mail_manager.check_draft(character_id, &draft)?;
+            let attachments = data.take_attachments(entity, &draft)?;
+            if let Some(buf) = data.inventory_update_buffers.get_mut(entity) {
+                buf.push(InventoryUpdateEvent::Gave);
+            }
+            let now = chrono::Utc::now().timestamp();
+            data.mail_manager
+                .send(character_id, recipient, draft, attachments, now);
+            data.inform(entity, ChatType::Meta, Content::localized("hud-mail-sent"));
+            data.notify_recipient(recipient);
+        },
+        MailAction::MarkRead(id) => {
+            data.mail_manager.mark_read(character_id, id)?;
+            data.send_mailbox(entity, character_id);
+        },
+        MailAction::TakeAttachments(id) => {
+            if in_trade {
+                return Ok(());
+            }
+            let inventory = data
+                .inventories
+                .get_mut(entity)
+                .ok_or(MailError::NotFound)?;
+            let now = chrono::Utc::now().timestamp();
+            let complete = data.mail_manager.take_attachments(
+                character_id,
+                id,
+                now,
+                |coins, attachments| give_attachments(inventory, coins, attachments),
+            )?;
+            if let Some(buf) = data.inventory_update_buffers.get_mut(entity) {
+                buf.push(InventoryUpdateEvent::Given);
+            }
+            data.send_mailbox(entity, character_id);
+            if !complete {
+                return Err(MailError::InventoryFull);
+            }
+        },
+        MailAction::Return(id) => {
+            let now = chrono::Utc::now().timestamp();
+            let sender = data.mail_manager.return_mail(character_id, id, now)?;
+            data.send_mailbox(entity, character_id);
+            data.notify_recipient(sender);
+        },
+        MailAction::Delete(id) => {
+            data.mail_manager.delete(character_id, id)?;
+            data.send_mailbox(entity, character_id);
+        },
+    }
+    Ok(())
+}
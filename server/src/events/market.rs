@@ -0,0 +1,347 @@
+use crate::{
+    client::Client,
+    mail::MailManager,
+    market::{MarketError, MarketManager},
+    persistence::market::ListingRow,
+};
+#[cfg(feature = "worldgen")]
+use common::terrain::CoordinateConversions;
+use common::{
+    assets::AssetExt,
+    character::CharacterId,
+    comp::{
+        self, ChatType, Content, InventoryUpdateEvent, MarketAction, Presence,
+        inventory::trade_pricing::TradePricing,
+        item::{ItemDef, MaterialStatManifest, tool::AbilityMap},
+        market::{ListingId, listing_fee, sale_proceeds},
+    },
+    consts::MAX_PICKUP_RANGE,
+    event::MarketEvent,
+    terrain::TerrainGrid,
+    trade::{SitePrices, Trades},
+    uid::{IdMaps, Uid},
+    util::find_dist::{self, FindDist},
+    vol::ReadVol,
+};
+use common_net::msg::ServerGeneral;
+use specs::{
+    DispatcherBuilder, Entity, Read, ReadExpect, ReadStorage, SystemData, WriteExpect,
+    WriteStorage, shred,
+};
+use std::sync::Arc;
+use vek::*;
+#[cfg(feature = "worldgen")]
+use world::{IndexOwned, World};
+
+use super::{ServerEvent, event_dispatch, shared::notify_mail_recipient};
+
+pub(super) fn register_event_systems(builder: &mut DispatcherBuilder) {
+    event_dispatch::<MarketEvent>(builder, &[]);
+}
+
+/// Sends the market to every character that has a market board open, after
+/// listings were posted or removed
+pub fn notify_market_viewers(
+    market_manager: &MarketManager,
+    id_maps: &IdMaps,
+    clients: &ReadStorage<'_, Client>,
+) {
+    for character_id in market_manager.viewers() {
+        if let Some(client) = id_maps
+            .character_entity(character_id)
+            .and_then(|entity| clients.get(entity))
+        {
+            client.send_fallible(ServerGeneral::MarketUpdate(
+                market_manager.market(character_id),
+            ));
+        }
+    }
+}
+
+#[derive(SystemData)]
+pub struct MarketData<'a> {
+    market_manager: WriteExpect<'a, MarketManager>,
+    mail_manager: WriteExpect<'a, MailManager>,
+    id_maps: Read<'a, IdMaps>,
+    trades: Read<'a, Trades>,
+    terrain: ReadExpect<'a, TerrainGrid>,
+    #[cfg(feature = "worldgen")]
+    world: ReadExpect<'a, Arc<World>>,
+    #[cfg(feature = "worldgen")]
+    index: ReadExpect<'a, IndexOwned>,
+    ability_map: ReadExpect<'a, AbilityMap>,
+    msm: ReadExpect<'a, MaterialStatManifest>,
+    inventories: WriteStorage<'a, comp::Inventory>,
+    inventory_update_buffers: WriteStorage<'a, comp::InventoryUpdateBuffer>,
+    presences: ReadStorage<'a, Presence>,
+    clients: ReadStorage<'a, Client>,
+    uids: ReadStorage<'a, Uid>,
+    positions: ReadStorage<'a, comp::Pos>,
+    scales: ReadStorage<'a, comp::Scale>,
+    colliders: ReadStorage<'a, comp::Collider>,
+    character_states: ReadStorage<'a, comp::CharacterState>,
+}
+
+impl MarketData<'_> {
+    fn inform(&self, entity: Entity, chat_type: ChatType<String>, content: Content) {
+        if let Some(client) = self.clients.get(entity) {
+            client.send_fallible(ServerGeneral::server_msg(chat_type, content));
+        }
+    }
+
+    /// Whether the entity is close enough to a market board at `sprite_pos`
+    fn can_access_market(&self, entity: Entity, sprite_pos: Vec3<i32>) -> bool {
+        let is_market_board = self
+            .terrain
+            .get(sprite_pos)
+            .ok()
+            .and_then(|block| block.get_sprite())
+            .is_some_and(|sprite| sprite.is_market_board());
+        let cylinder = self.positions.get(entity).map(|pos| {
+            find_dist::Cylinder::from_components(
+                pos.0,
+                self.scales.get(entity).copied(),
+                self.colliders.get(entity),
+                self.character_states.get(entity),
+            )
+        });
+        is_market_board
+            && cylinder.is_some_and(|cylinder| {
+                (sprite_pos.map(|e| e as f32) + Vec3::broadcast(0.5)).min_distance(cylinder)
+                    < MAX_PICKUP_RANGE
+            })
+    }
+
+    /// Prices of the site the market board is in
+    #[cfg(feature = "worldgen")]
+    fn site_prices(&self, sprite_pos: Vec3<i32>) -> Option<SitePrices> {
+        self.world
+            .sim()
+            .get(sprite_pos.xy().wpos_to_cpos())?
+            .sites
+            .iter()
+            .find_map(|site| self.index.get_site_prices(site.id()))
+    }
+
+    #[cfg(not(feature = "worldgen"))]
+    fn site_prices(&self, _sprite_pos: Vec3<i32>) -> Option<SitePrices> { None }
+
+    fn send_market(&self, entity: Entity, character_id: CharacterId) {
+        if let Some(client) = self.clients.get(entity) {
+            client.send_fallible(ServerGeneral::MarketUpdate(
+                self.market_manager.market(character_id),
+            ));
+        }
+    }
+
+    fn notify_viewers(&self) {
+        notify_market_viewers(&self.market_manager, &self.id_maps, &self.clients);
+    }
+
+    /// Takes the listing fee and the item in the slot from the inventory,
+    /// nothing is taken if either of them is missing
+    fn take_item(
+        &mut self,
+        entity: Entity,
+        slot: comp::slot::InvSlotId,
+        price: u32,
+    ) -> Result<comp::Item, MarketError> {
+        let inventory = self
+            .inventories
+            .get_mut(entity)
+            .ok_or(MarketError::NotFound)?;
+        if inventory
+            .get(slot)
+            .ok_or(MarketError::NotFound)?
+            .item_definition_id()
+            .itemdef_id()
+            == Some(TradePricing::COIN_ITEM)
+        {
+            return Err(MarketError::CannotSellCoins);
+        }
+        let mut new_inventory = inventory.clone();
+        let coins = Arc::<ItemDef>::load_cloned(TradePricing::COIN_ITEM)
+            .map_err(|_| MarketError::NotFound)?;
+        new_inventory
+            .remove_item_amount(&coins, listing_fee(price), &self.ability_map, &self.msm)
+            .ok_or(MarketError::NotEnoughCoins)?;
+        let item = new_inventory.remove(slot).ok_or(MarketError::NotFound)?;
+        *inventory = new_inventory;
+        Ok(item)
+    }
+
+    /// Gives the item to the entity in exchange for `price` coins, nothing is
+    /// exchanged if the entity can't afford it or has no room for it. The item
+    /// is handed back then.
+    fn exchange(
+        &mut self,
+        entity: Entity,
+        item: comp::Item,
+        price: u32,
+    ) -> Result<(), (MarketError, comp::Item)> {
+        let Some(inventory) = self.inventories.get_mut(entity) else {
+            return Err((MarketError::NotFound, item));
+        };
+        let mut new_inventory = inventory.clone();
+        if price > 0 {
+            let Ok(coins) = Arc::<ItemDef>::load_cloned(TradePricing::COIN_ITEM) else {
+                return Err((MarketError::NotFound, item));
+            };
+            if new_inventory
+                .remove_item_amount(&coins, price, &self.ability_map, &self.msm)
+                .is_none()
+            {
+                return Err((MarketError::NotEnoughCoins, item));
+            }
+        }
+        let amount = item.amount();
+        if let Err((mut item, _)) = new_inventory.push(item) {
+            // Part of the stack may have been pushed into the discarded inventory
+            item.set_amount(amount)
+                .expect("The amount of the item was valid before it was pushed");
+            return Err((MarketError::InventoryFull, item));
+        }
+        *inventory = new_inventory;
+        Ok(())
+    }
+}
+
+impl ServerEvent for MarketEvent {
+    type SystemData<'a> = MarketData<'a>;
+
+    fn handle(events: impl ExactSizeIterator<Item = Self>, mut data: Self::SystemData<'_>) {
+        for MarketEvent(entity, action) in events {
+            let Some(character_id) = data
+                .presences
+                .get(entity)
+                .and_then(|presence| presence.kind.character_id())
+            else {
+                continue;
+            };
+            if let Err(error) = handle_market_action(&mut data, entity, character_id, action) {
+                data.inform(entity, ChatType::CommandError, error.into());
+            }
+        }
+    }
+}
+
+fn handle_market_action(
+    data: &mut MarketData,
+    entity: Entity,
+    character_id: CharacterId,
+    action: MarketAction,
+) -> Result<(), MarketError> {
+    match action {
+        MarketAction::Open(sprite_pos) => {
+            if data.can_access_market(entity, sprite_pos) {
+                let prices = data.site_prices(sprite_pos);
+                data.market_manager.open(character_id, sprite_pos, prices)?;
+                data.send_market(entity, character_id);
+            }
+            return Ok(());
+        },
+        MarketAction::Close => {
+            data.market_manager.close(character_id);
+            return Ok(());
+        },
+        _ => {},
+    }
+
+    if !data
+        .market_manager
+        .opened_at(character_id)
+        .is_some_and(|sprite_pos| data.can_access_market(entity, sprite_pos))
+    {
+        // The market board is out of range or was destroyed
+        data.market_manager.close(character_id);
+        data.send_market(entity, character_id);
+        return Ok(());
+    }
+    // Items can't be added to or taken from an inventory that is traded
+    if data
+        .uids
+        .get(entity)
+        .is_some_and(|uid| data.trades.in_immutable_trade(uid))
+    {
+        return Ok(());
+    }
+
+    match action {
+        MarketAction::Open(_) | MarketAction::Close => {},
+        MarketAction::Post { slot, price } => {
+            data.market_manager.check_post(character_id, price)?;
+            let seller_name = data
+                .mail_manager
+                .character_name(character_id)
+                .ok_or(MarketError::NotFound)?
+                .to_owned();
+            let item = data.take_item(entity, slot, price)?;
+            if let Some(buf) = data.inventory_update_buffers.get_mut(entity) {
+                buf.push(InventoryUpdateEvent::Gave);
+            }
+            let now = chrono::Utc::now().timestamp();
+            data.market_manager
+                .post(character_id, &seller_name, item, price, now);
+            data.inform(
+                entity,
+                ChatType::Meta,
+                Content::localized("hud-market-posted"),
+            );
+            data.notify_viewers();
+        },
+        MarketAction::Buy(id) => buy(data, entity, character_id, id)?,
+        MarketAction::Cancel(id) => {
+            let listing = data.market_manager.take_own(character_id, id)?;
+            if let Err((error, item)) = data.exchange(entity, listing.item, 0) {
+                data.market_manager.put_back(ListingRow { item, ..listing });
+                return Err(error);
+            }
+            if let Some(buf) = data.inventory_update_buffers.get_mut(entity) {
+                buf.push(InventoryUpdateEvent::Given);
+            }
+            data.notify_viewers();
+        },
+    }
+    Ok(())
+}
+
+fn buy(
+    data: &mut MarketData,
+    entity: Entity,
+    character_id: CharacterId,
+    id: ListingId,
+) -> Result<(), MarketError> {
+    // The listing is removed before its item is moved to the buyer, so it can't be
+    // bought a second time. It is listed again if the buyer can't take the item.
+    let listing = data.market_manager.take_for_sale(character_id, id)?;
+    let price = listing.price;
+    if let Err((error, item)) = data.exchange(entity, listing.item, price) {
+        data.market_manager.put_back(ListingRow { item, ..listing });
+        return Err(error);
+    }
+    if let Some(buf) = data.inventory_update_buffers.get_mut(entity) {
+        buf.push(InventoryUpdateEvent::Given);
+    }
+
+    let proceeds = sale_proceeds(price);
+    data.mail_manager.deliver(
+        "Market",
+        listing.seller,
+        Content::localized("hud-market-sold_subject"),
+        Content::localized_with_args("hud-market-sold_body", [
+            ("price", price as u64),
+            ("fee", (price - proceeds) as u64),
+        ]),
+        proceeds,
+        Vec::new(),
+        chrono::Utc::now().timestamp(),
+    );
+    notify_mail_recipient(
+        &data.mail_manager,
+        &data.id_maps,
+        &data.clients,
+        listing.seller,
+    );
+    data.notify_viewers();
+    Ok(())
+}
@@ -34,12 +34,16 @@ mod entity_creation;
 mod entity_manipulation;
 mod event_types;
 mod group_manip;
+mod guild_manip;
 mod information;
 mod interaction;
 mod inventory_manip;
 mod invite;
+mod mail;
+mod market;
 mod mounting;
 mod player;
+#[cfg(feature = "plugins")] mod plugin;
 mod trade;
 
 pub(crate) use event_types::register_event_busses;
@@ -48,6 +52,9 @@ pub(crate) mod shared {
     pub(crate) use super::{
         entity_manipulation::{TransformEntityError, transform_entity},
         group_manip::update_map_markers,
+        guild_manip::send_guild_roster,
+        mail::notify_mail_recipient,
+        market::notify_market_viewers,
         trade::cancel_trades_for,
     };
 }
@@ -108,6 +115,9 @@ pub fn register_event_systems(builder: &mut DispatcherBuilder) {
     interaction::register_event_systems(builder);
     invite::register_event_systems(builder);
     group_manip::register_event_systems(builder);
+    guild_manip::register_event_systems(builder);
+    mail::register_event_systems(builder);
+    market::register_event_systems(builder);
     information::register_event_systems(builder);
 }
 
@@ -204,6 +214,16 @@ impl Server {
         self.handle_serial_events(handle_tame_pet);
         self.handle_serial_events(handle_process_trade_action);
         self.handle_serial_events(handle_set_battle_mode);
+        // Handled last so that actions queued by plugin commands are applied in
+        // the same tick
+        #[cfg(feature = "plugins")]
+        {
+            let mut plugin_events = Vec::new();
+            self.handle_serial_events(|_, ev: common::event::PluginGameEvent| {
+                plugin_events.push(ev)
+            });
+            plugin::handle_plugin_game_events(self, plugin_events);
+        }
     }
 
     pub fn handle_events(&mut self) -> Vec<Event> {
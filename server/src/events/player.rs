@@ -1,11 +1,13 @@
 use super::Event;
 use crate::{
-    BattleModeBuffer, Server, client::Client, metrics::PlayerMetrics,
+    BattleModeBuffer, Server, client::Client, guild::GuildManager, mail::MailManager,
+    market::MarketManager, metrics::PlayerMetrics,
     persistence::character_updater::CharacterUpdater, settings::banlist::NormalizedIpAddr,
     state_ext::StateExt,
 };
 use common::{
-    comp::{self, Content, Presence, PresenceKind, group, pet::is_tameable},
+    character::CharacterId,
+    comp::{self, Content, Presence, PresenceKind, group, guild::GuildId, pet::is_tameable},
     event::{DeleteCharacterEvent, PossessEvent, SetBattleModeEvent},
     resources::Time,
     uid::{IdMaps, Uid},
@@ -36,10 +38,61 @@ pub fn handle_character_delete(server: &mut Server, ev: DeleteCharacterEvent) {
         handle_exit_ingame(server, ev.entity, true);
     }
 
+    let guild = server
+        .state
+        .ecs()
+        .write_resource::<GuildManager>()
+        .remove_character(ev.character_id, &ev.requesting_player_uuid);
+    if let Some(guild) = guild {
+        send_guild_roster(&server.state, guild);
+    }
+    remove_mail_and_listings(&server.state, ev.character_id, &ev.requesting_player_uuid);
+
     let mut updater = server.state.ecs().fetch_mut::<CharacterUpdater>();
     updater.queue_character_deletion(ev.requesting_player_uuid, ev.character_id);
 }
 
+/// Returns the mail of a character that is being deleted to its senders, and
+/// takes down its market listings
+fn remove_mail_and_listings(state: &State, character_id: CharacterId, player_uuid: &str) {
+    let ecs = state.ecs();
+    let mut mail_manager = ecs.write_resource::<MailManager>();
+    let now = chrono::Utc::now().timestamp();
+    let Some(returned_to) = mail_manager.remove_character(character_id, player_uuid, now) else {
+        return;
+    };
+    for sender in returned_to {
+        super::shared::notify_mail_recipient(
+            &mail_manager,
+            &ecs.read_resource(),
+            &ecs.read_storage(),
+            sender,
+        );
+    }
+
+    let mut market_manager = ecs.write_resource::<MarketManager>();
+    if market_manager.remove_seller(character_id) {
+        super::shared::notify_market_viewers(
+            &market_manager,
+            &ecs.read_resource(),
+            &ecs.read_storage(),
+        );
+    }
+}
+
+/// Lets the remaining members of the guild know that the character left the
+/// game
+fn send_guild_roster(state: &State, guild: GuildId) {
+    let ecs = state.ecs();
+    super::shared::send_guild_roster(
+        &ecs.read_resource(),
+        &ecs.read_resource(),
+        &ecs.read_storage(),
+        &ecs.read_storage(),
+        guild,
+    );
+}
+
 pub fn handle_exit_ingame(server: &mut Server, entity: EcsEntity, skip_persistence: bool) {
     span!(_guard, "handle_exit_ingame");
     let state = server.state_mut();
@@ -69,6 +122,7 @@ pub fn handle_exit_ingame(server: &mut Server, entity: EcsEntity, skip_persisten
     super::trade::cancel_trades_for(state, entity);
 
     let maybe_group = state.read_component_copied::<group::Group>(entity);
+    let maybe_guild = state.delete_component::<comp::Guild>(entity);
     let maybe_admin = state.delete_component::<comp::Admin>(entity);
     // Not sure if we still need to actually remove the Uid or if the group
     // logic below relies on this...
@@ -142,6 +196,9 @@ pub fn handle_exit_ingame(server: &mut Server, entity: EcsEntity, skip_persisten
         maybe_character.flatten(),
         maybe_rtsim,
     );
+    if let Some(guild) = maybe_guild {
+        send_guild_roster(state, guild.id);
+    }
 
     // We don't want to use delete_entity_recorded since we are transfering the
     // Uid to a new entity (and e.g. don't want it to be unmapped).
@@ -281,11 +338,15 @@ pub fn handle_client_disconnect(
     }
 
     // Delete client entity
+    let maybe_guild = server.state.delete_component::<comp::Guild>(entity);
     if let Err(e) = server.state.delete_entity_recorded(entity)
         && !already_disconnected
     {
         error!(?e, ?entity, "Failed to delete disconnected client");
     }
+    if let Some(guild) = maybe_guild {
+        send_guild_roster(&server.state, guild.id);
+    }
 
     disconnected_event
 }
@@ -308,6 +369,7 @@ pub(super) fn persist_entity(state: &mut State, entity: EcsEntity) -> EcsEntity
         Some(presence),
         Some(skill_set),
         Some(inventory),
+        Some(storage),
         Some(active_abilities),
         Some(player_uid),
         Some(player_info),
@@ -317,6 +379,7 @@ pub(super) fn persist_entity(state: &mut State, entity: EcsEntity) -> EcsEntity
         state.read_storage::<Presence>().get(entity),
         state.read_storage::<comp::SkillSet>().get(entity),
         state.read_storage::<comp::Inventory>().get(entity),
+        state.read_storage::<comp::PersonalStorage>().get(entity),
         state
             .read_storage::<comp::ability::ActiveAbilities>()
             .get(entity),
@@ -342,6 +405,12 @@ pub(super) fn persist_entity(state: &mut State, entity: EcsEntity) -> EcsEntity
                     // Delete dead hardcore characters instead of persisting
                     character_updater
                         .queue_character_deletion(player_info.uuid().to_string(), char_id);
+                    // The remaining members are sent the roster when the entity is removed
+                    state
+                        .ecs()
+                        .write_resource::<GuildManager>()
+                        .remove_character(char_id, &player_info.uuid().to_string());
+                    remove_mail_and_listings(state, char_id, &player_info.uuid().to_string());
                 } else {
                     let waypoint = state
                         .ecs()
@@ -353,6 +422,12 @@ pub(super) fn persist_entity(state: &mut State, entity: EcsEntity) -> EcsEntity
                         .read_storage::<comp::MapMarker>()
                         .get(entity)
                         .cloned();
+                    let reputation = state
+                        .ecs()
+                        .read_storage::<common::rtsim::PersistedReputation>()
+                        .get(entity)
+                        .cloned()
+                        .unwrap_or_default();
                     // Store last battle mode change
                     if let Some(change) = player_info.last_battlemode_change {
                         let mode = player_info.battle_mode;
@@ -384,10 +459,12 @@ pub(super) fn persist_entity(state: &mut State, entity: EcsEntity) -> EcsEntity
                         char_id,
                         skill_set.clone(),
                         inventory.clone(),
+                        storage.inventory.clone(),
                         pets,
                         waypoint,
                         active_abilities.clone(),
                         map_marker,
+                        reputation,
                     ));
                 }
             },
@@ -0,0 +1,156 @@
+use crate::{Server, StateExt, cmd::cast_buff, sys::terrain::SpawnEntityData};
+use common::{
+    assets::{AssetExt, Ron},
+    comp::{
+        self, Inventory, Item,
+        buff::BuffData,
+        inventory::item::{MaterialStatManifest, tool::AbilityMap},
+    },
+    event::{CreateNpcEvent, EventBus, PluginGameEvent},
+    generation::{EntityConfig, EntityInfo},
+    resources::Secs,
+    uid::IdMaps,
+};
+use common_net::sync::WorldSyncExt;
+use common_state::plugin::{PluginAction, PluginMgr, memory_manager::EcsWorld};
+use specs::WorldExt;
+use tracing::warn;
+
+/// Forward the game events of this tick to the server plugins and apply the
+/// actions they requested.
+pub fn handle_plugin_game_events(server: &mut Server, events: Vec<PluginGameEvent>) {
+    if !events.is_empty() {
+        let ecs = server.state.ecs();
+        let mut plugin_mgr = ecs.write_resource::<PluginMgr>();
+        let ecs_world = EcsWorld {
+            entities: &ecs.entities(),
+            health: ecs.read_component().into(),
+            uid: ecs.read_component().into(),
+            id_maps: &ecs.read_resource::<IdMaps>().into(),
+            player: ecs.read_component().into(),
+        };
+        for event in &events {
+            plugin_mgr.game_event(&ecs_world, event);
+        }
+    }
+
+    apply_plugin_actions(server);
+}
+
+/// Apply the world modifications which plugins queued since the last call.
+///
+/// Permissions were already checked when the actions were queued.
+fn apply_plugin_actions(server: &mut Server) {
+    let actions = server
+        .state
+        .ecs()
+        .write_resource::<PluginMgr>()
+        .take_actions();
+
+    for action in actions {
+        match action {
+            PluginAction::SpawnNpc { entity_config, pos } => {
+                let Ok(config) = Ron::<EntityConfig>::load(&entity_config) else {
+                    warn!(
+                        ?entity_config,
+                        "Plugin tried to spawn unknown entity config"
+                    );
+                    continue;
+                };
+                let entity_info = EntityInfo::at(pos).with_entity_config(
+                    config.read().clone().into_inner(),
+                    Some(&entity_config),
+                    &mut rand::rng(),
+                    None,
+                );
+                match SpawnEntityData::from_entity_info(entity_info) {
+                    SpawnEntityData::Special(_, _) => {
+                        warn!(?entity_config, "Plugins can't spawn special entities");
+                    },
+                    SpawnEntityData::Npc(data) => {
+                        let (npc_builder, pos) = data.to_npc_builder();
+                        server
+                            .state
+                            .ecs()
+                            .read_resource::<EventBus<CreateNpcEvent>>()
+                            .emit_now(CreateNpcEvent {
+                                pos,
+                                ori: comp::Ori::default(),
+                                npc: npc_builder,
+                            });
+                    },
+                }
+            },
+            PluginAction::Teleport { entity, pos } => {
+                let Some(entity) = server.state.ecs().entity_from_uid(entity) else {
+                    continue;
+                };
+                if let Err(error) = server
+                    .state
+                    .position_mut(entity, true, |current_pos| current_pos.0 = pos)
+                {
+                    warn!(?error, "Plugin failed to teleport entity");
+                }
+            },
+            PluginAction::GiveItem {
+                entity,
+                item,
+                amount,
+            } => {
+                let Some(entity) = server.state.ecs().entity_from_uid(entity) else {
+                    continue;
+                };
+                give_item(server, entity, &item, amount);
+            },
+            PluginAction::ApplyBuff {
+                entity,
+                kind,
+                strength,
+                duration,
+            } => {
+                let Some(entity) = server.state.ecs().entity_from_uid(entity) else {
+                    continue;
+                };
+                let data = BuffData::new(strength, duration.map(|d| Secs(d as f64)));
+                cast_buff(kind, data, server, entity);
+            },
+        }
+    }
+}
+
+fn give_item(server: &Server, entity: specs::Entity, item_name: &str, amount: u32) {
+    let mut item = match Item::new_from_asset(item_name) {
+        Ok(item) => item,
+        Err(error) => {
+            warn!(?error, ?item_name, "Plugin tried to give unknown item");
+            return;
+        },
+    };
+
+    let ecs = server.state.ecs();
+    let mut inventories = ecs.write_storage::<Inventory>();
+    let Some(inventory) = inventories.get_mut(entity) else {
+        return;
+    };
+
+    // NOTE: Deliberately ignores items that couldn't be pushed, just like the
+    // give_item command.
+    if item.set_amount(amount).is_ok() {
+        let _ = inventory.push(item);
+    } else {
+        let ability_map = ecs.read_resource::<AbilityMap>();
+        let msm = ecs.read_resource::<MaterialStatManifest>();
+        for _ in 0..amount {
+            if inventory.push(item.duplicate(&ability_map, &msm)).is_err() {
+                break;
+            }
+        }
+    }
+
+    if let Some(buf) = ecs
+        .write_storage::<comp::InventoryUpdateBuffer>()
+        .get_mut(entity)
+    {
+        buf.push(comp::InventoryUpdateEvent::Given);
+    }
+}
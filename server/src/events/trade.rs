@@ -1,4 +1,6 @@
 use crate::Server;
+#[cfg(feature = "worldgen")]
+use crate::rtsim::RtSim;
 use common::{
     comp::{
         self, CharacterState, Health,
@@ -11,6 +13,8 @@ use common::{
     event::ProcessTradeActionEvent,
     trade::{PendingTrade, ReducedInventory, TradeAction, TradeResult, Trades},
 };
+#[cfg(feature = "worldgen")]
+use common::{rtsim::ActorId, trade::SitePrices};
 use common_net::{
     msg::ServerGeneral,
     sync::{Uid, WorldSyncExt},
@@ -32,21 +36,48 @@ pub fn notify_agent_simple(
     }
 }
 
+/// Adjust the prices of `merchant` to the reputation that `customer` has with
+/// it.
+#[cfg(feature = "worldgen")]
+fn reputation_prices(
+    ecs: &specs::World,
+    prices: SitePrices,
+    merchant: EcsEntity,
+    customer: Option<EcsEntity>,
+) -> SitePrices {
+    let rtsim_actors = ecs.read_storage::<ActorId>();
+    match (
+        rtsim_actors.get(merchant),
+        customer.and_then(|customer| rtsim_actors.get(customer)),
+    ) {
+        (Some(merchant), Some(customer)) => prices.with_reputation(
+            ecs.read_resource::<RtSim>()
+                .reputation_standing(*merchant, *customer),
+        ),
+        _ => prices,
+    }
+}
+
 #[cfg(feature = "worldgen")]
 fn notify_agent_prices(
-    mut agents: specs::WriteStorage<Agent>,
+    ecs: &specs::World,
     index: &IndexOwned,
     entity: EcsEntity,
+    customer: Option<EcsEntity>,
     event: AgentEvent,
 ) {
-    if let Some((site_id, agent)) = agents.get_mut(entity).map(|a| (a.behavior.trade_site(), a))
+    let site_prices = ecs
+        .read_storage::<Agent>()
+        .get(entity)
+        .and_then(|a| a.behavior.trade_site())
+        .and_then(|site_id| index.get_site_prices(site_id))
+        .map(|prices| reputation_prices(ecs, prices, entity, customer));
+    if let Some(agent) = ecs.write_storage::<Agent>().get_mut(entity)
         && let AgentEvent::UpdatePendingTrade(boxval) = event
     {
         // Prefer using this Agent's price data, but use the counterparty's price
         // data if we don't have price data
-        let prices = site_id
-            .and_then(|site_id| index.get_site_prices(site_id))
-            .unwrap_or(boxval.2);
+        let prices = site_prices.unwrap_or(boxval.2);
         // Box<(tid, pend, _, inventories)>) = event {
         agent
             .inbox
@@ -151,11 +182,17 @@ pub(super) fn handle_process_trade_action(
                                         .get(e)
                                         .and_then(|a| a.behavior.trade_site())
                                         .and_then(|id| server.index.get_site_prices(id))
+                                        .map(|prices| (prices, e, i))
                                 });
                             }
                         }
                     }
                     drop(agents);
+                    // The merchant adjusts their prices to the reputation of the other party
+                    #[cfg(feature = "worldgen")]
+                    let prices = prices.map(|(prices, merchant, i)| {
+                        reputation_prices(server.state.ecs(), prices, merchant, entities[1 - i])
+                    });
                     for party in entities.iter() {
                         if let Some(e) = *party {
                             server.notify_client(
@@ -168,9 +205,10 @@ pub(super) fn handle_process_trade_action(
                             );
                             #[cfg(feature = "worldgen")]
                             notify_agent_prices(
-                                server.state.ecs().write_storage::<Agent>(),
+                                server.state.ecs(),
                                 &server.index,
                                 e,
+                                entities.iter().flatten().copied().find(|other| *other != e),
                                 AgentEvent::UpdatePendingTrade(Box::new((
                                     trade_id,
                                     entry.get().clone(),
@@ -0,0 +1,402 @@
+//! Player guilds
+//!
+//! Every guild is kept in the [`GuildManager`], whether its members are online
+//! or not. Characters that are in game additionally have a [`comp::Guild`]
+//! component, which is what chat and combat look at.
+
+use crate::persistence::guild::{GuildChange, GuildRows};
+use common::{
+    character::CharacterId,
+    comp::{
+        self, Content,
+        guild::{
+            GuildId, GuildNameError, GuildRank, GuildRoster, GuildRosterMember, validate_guild_name,
+        },
+    },
+    uid::Uid,
+};
+use hashbrown::HashMap;
+use std::collections::BTreeMap;
+use tracing::warn;
+
+#[derive(Clone, Debug)]
+pub struct GuildMember {
+    /// Name of the character, refreshed whenever it logs in
+    pub name: String,
+    pub player_uuid: String,
+    pub rank: GuildRank,
+}
+
+#[derive(Clone, Debug)]
+pub struct GuildInfo {
+    pub name: String,
+    pub members: BTreeMap<CharacterId, GuildMember>,
+}
+
+impl GuildInfo {
+    pub fn component(&self, id: GuildId) -> comp::Guild {
+        comp::Guild {
+            id,
+            name: self.name.clone(),
+        }
+    }
+
+    pub fn leader(&self) -> Option<CharacterId> {
+        self.members
+            .iter()
+            .find(|(_, member)| member.rank == GuildRank::Leader)
+            .map(|(character_id, _)| *character_id)
+    }
+
+    /// Looks up a member by the name of their character, ignoring case
+    pub fn find_member(&self, name: &str) -> Option<CharacterId> {
+        self.members
+            .iter()
+            .find(|(_, member)| member.name.eq_ignore_ascii_case(name))
+            .map(|(character_id, _)| *character_id)
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum GuildError {
+    InvalidName(GuildNameError),
+    NameTaken,
+    AlreadyInGuild,
+    NotInGuild,
+    /// The database is shared with other servers, see
+    /// [`GuildManager::disabled`]
+    Disabled,
+}
+
+impl From<GuildError> for Content {
+    fn from(error: GuildError) -> Self {
+        Content::localized(match error {
+            GuildError::InvalidName(GuildNameError::Empty) => "command-guild-name-empty",
+            GuildError::InvalidName(GuildNameError::TooLong) => "command-guild-name-too_long",
+            GuildError::InvalidName(GuildNameError::ForbiddenCharacters) => {
+                "command-guild-name-forbidden"
+            },
+            GuildError::NameTaken => "command-guild-name-taken",
+            GuildError::AlreadyInGuild => "command-guild-already_in_guild",
+            GuildError::NotInGuild => "command-guild-not_in_guild",
+            GuildError::Disabled => "command-guild-disabled",
+        })
+    }
+}
+
+/// All guilds of the server, changes are persisted by the
+/// [`CharacterUpdater`](crate::persistence::character_updater::CharacterUpdater).
+pub struct GuildManager {
+    guilds: HashMap<GuildId, GuildInfo>,
+    memberships: HashMap<CharacterId, GuildId>,
+    next_guild_id: u64,
+    changes: Vec<GuildChange>,
+    enabled: bool,
+}
+
+impl GuildManager {
+    pub fn new(rows: GuildRows) -> Self {
+        let mut guilds = rows
+            .guilds
+            .into_iter()
+            .map(|(id, name)| {
+                (id, GuildInfo {
+                    name,
+                    members: BTreeMap::new(),
+                })
+            })
+            .collect::<HashMap<_, _>>();
+        let mut memberships = HashMap::new();
+        for row in rows.members {
+            let Some(guild) = guilds.get_mut(&row.guild_id) else {
+                warn!(?row, "Guild member of a guild that doesn't exist");
+                continue;
+            };
+            guild.members.insert(row.character_id, GuildMember {
+                name: row.alias,
+                player_uuid: row.player_uuid,
+                rank: row.rank,
+            });
+            memberships.insert(row.character_id, row.guild_id);
+        }
+        let next_guild_id = guilds.keys().map(|id| id.0 + 1).max().unwrap_or(1);
+
+        Self {
+            guilds,
+            memberships,
+            next_guild_id,
+            changes: Vec::new(),
+            enabled: true,
+        }
+    }
+
+    /// A manager without any guilds that doesn't let characters found one.
+    /// Guild ids are allocated in memory, so servers sharing their database
+    /// can't have guilds.
+    pub fn disabled() -> Self {
+        Self {
+            enabled: false,
+            ..Self::new(GuildRows::default())
+        }
+    }
+
+    pub fn guild(&self, id: GuildId) -> Option<&GuildInfo> { self.guilds.get(&id) }
+
+    pub fn guild_of(&self, character_id: CharacterId) -> Option<GuildId> {
+        self.memberships.get(&character_id).copied()
+    }
+
+    pub fn member(&self, character_id: CharacterId) -> Option<(GuildId, &GuildMember)> {
+        let id = self.guild_of(character_id)?;
+        Some((id, self.guilds.get(&id)?.members.get(&character_id)?))
+    }
+
+    /// Creates a new guild led by the character
+    pub fn create(
+        &mut self,
+        name: String,
+        character_id: CharacterId,
+        leader: GuildMember,
+    ) -> Result<GuildId, GuildError> {
+        if !self.enabled {
+            return Err(GuildError::Disabled);
+        }
+        validate_guild_name(&name).map_err(GuildError::InvalidName)?;
+        if self.memberships.contains_key(&character_id) {
+            return Err(GuildError::AlreadyInGuild);
+        }
+        if self
+            .guilds
+            .values()
+            .any(|guild| guild.name.to_lowercase() == name.to_lowercase())
+        {
+            return Err(GuildError::NameTaken);
+        }
+
+        let id = GuildId(self.next_guild_id);
+        self.next_guild_id += 1;
+        self.changes.push(GuildChange::Create {
+            guild_id: id,
+            name: name.clone(),
+        });
+        self.guilds.insert(id, GuildInfo {
+            name,
+            members: BTreeMap::new(),
+        });
+        self.insert_member(id, character_id, GuildMember {
+            rank: GuildRank::Leader,
+            ..leader
+        });
+        Ok(id)
+    }
+
+    /// Adds the character to the guild as a regular member
+    pub fn add_member(
+        &mut self,
+        id: GuildId,
+        character_id: CharacterId,
+        member: GuildMember,
+    ) -> Result<(), GuildError> {
+        if self.memberships.contains_key(&character_id) {
+            return Err(GuildError::AlreadyInGuild);
+        }
+        if !self.guilds.contains_key(&id) {
+            return Err(GuildError::NotInGuild);
+        }
+        self.insert_member(id, character_id, GuildMember {
+            rank: GuildRank::Member,
+            ..member
+        });
+        Ok(())
+    }
+
+    fn insert_member(&mut self, id: GuildId, character_id: CharacterId, member: GuildMember) {
+        self.changes.push(GuildChange::SetMember {
+            character_id,
+            guild_id: id,
+            rank: member.rank,
+        });
+        self.memberships.insert(character_id, id);
+        if let Some(guild) = self.guilds.get_mut(&id) {
+            guild.members.insert(character_id, member);
+        }
+    }
+
+    /// Removes the character from its guild. If it was the leader, the highest
+    /// ranking member takes over, and the guild is disbanded once nobody is
+    /// left.
+    ///
+    /// Returns the guild the character was a member of.
+    pub fn remove_member(&mut self, character_id: CharacterId) -> Option<GuildId> {
+        let id = self.memberships.remove(&character_id)?;
+        self.changes.push(GuildChange::RemoveMember(character_id));
+        let guild = self.guilds.get_mut(&id)?;
+        let removed = guild.members.remove(&character_id)?;
+
+        if removed.rank == GuildRank::Leader {
+            // Among members of the same rank, the oldest character is preferred
+            let successor = guild
+                .members
+                .iter()
+                .max_by_key(|(character_id, member)| {
+                    (member.rank, std::cmp::Reverse(**character_id))
+                })
+                .map(|(character_id, _)| *character_id);
+            match successor {
+                Some(successor) => self.set_rank(successor, GuildRank::Leader),
+                None => {
+                    self.disband(id);
+                },
+            }
+        }
+        Some(id)
+    }
+
+    /// Changes the rank of a member. A guild only has a single leader, so
+    /// making someone else the leader demotes the current one to officer.
+    pub fn set_rank(&mut self, character_id: CharacterId, rank: GuildRank) {
+        let Some(id) = self.guild_of(character_id) else {
+            return;
+        };
+        let Some(guild) = self.guilds.get_mut(&id) else {
+            return;
+        };
+
+        let mut changed = Vec::new();
+        if rank == GuildRank::Leader
+            && let Some(leader) = guild.leader()
+            && leader != character_id
+            && let Some(member) = guild.members.get_mut(&leader)
+        {
+            member.rank = GuildRank::Officer;
+            changed.push((leader, GuildRank::Officer));
+        }
+        if let Some(member) = guild.members.get_mut(&character_id) {
+            member.rank = rank;
+            changed.push((character_id, rank));
+        }
+
+        self.changes
+            .extend(
+                changed
+                    .into_iter()
+                    .map(|(character_id, rank)| GuildChange::SetMember {
+                        character_id,
+                        guild_id: id,
+                        rank,
+                    }),
+            );
+    }
+
+    /// Removes the guild, returning it so that its members can be notified
+    pub fn disband(&mut self, id: GuildId) -> Option<GuildInfo> {
+        let guild = self.guilds.remove(&id)?;
+        for character_id in guild.members.keys() {
+            self.memberships.remove(character_id);
+        }
+        self.changes.push(GuildChange::Disband(id));
+        Some(guild)
+    }
+
+    /// Keeps the name of the member up to date with its character
+    pub fn update_member_name(&mut self, character_id: CharacterId, name: &str) {
+        if let Some(member) = self
+            .guild_of(character_id)
+            .and_then(|id| self.guilds.get_mut(&id))
+            .and_then(|guild| guild.members.get_mut(&character_id))
+        {
+            name.clone_into(&mut member.name);
+        }
+    }
+
+    /// Forgets a character that is being deleted, provided it belongs to the
+    /// player
+    pub fn remove_character(
+        &mut self,
+        character_id: CharacterId,
+        player_uuid: &str,
+    ) -> Option<GuildId> {
+        let (_, member) = self.member(character_id)?;
+        if member.player_uuid != player_uuid {
+            return None;
+        }
+        self.remove_member(character_id)
+    }
+
+    /// The roster of the guild as sent to its members
+    pub fn roster(
+        &self,
+        id: GuildId,
+        online: impl Fn(CharacterId) -> Option<Uid>,
+    ) -> Option<GuildRoster> {
+        let guild = self.guilds.get(&id)?;
+        let mut members = guild
+            .members
+            .iter()
+            .map(|(character_id, member)| GuildRosterMember {
+                character_id: *character_id,
+                name: member.name.clone(),
+                rank: member.rank,
+                online: online(*character_id),
+            })
+            .collect::<Vec<_>>();
+        members.sort_by_key(|member| std::cmp::Reverse(member.rank));
+
+        Some(GuildRoster {
+            id,
+            name: guild.name.clone(),
+            members,
+        })
+    }
+
+    /// Takes all modifications made since the last call, to be persisted
+    pub fn take_changes(&mut self) -> Vec<GuildChange> { std::mem::take(&mut self.changes) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn member(name: &str) -> GuildMember {
+        GuildMember {
+            name: name.to_owned(),
+            player_uuid: format!("{name}-uuid"),
+            rank: GuildRank::Member,
+        }
+    }
+
+    #[test]
+    fn leader_succession() {
+        let mut manager = GuildManager::new(GuildRows::default());
+        let id = manager
+            .create("Knights".to_owned(), CharacterId(1), member("a"))
+            .unwrap();
+        assert_eq!(
+            manager.create("knights".to_owned(), CharacterId(2), member("b")),
+            Err(GuildError::NameTaken)
+        );
+        for character_id in 2..=4 {
+            manager
+                .add_member(id, CharacterId(character_id), member("x"))
+                .unwrap();
+        }
+        manager.set_rank(CharacterId(3), GuildRank::Officer);
+
+        // The officer takes over, even though character 2 is older
+        manager.remove_member(CharacterId(1));
+        assert_eq!(manager.guild(id).unwrap().leader(), Some(CharacterId(3)));
+
+        manager.set_rank(CharacterId(4), GuildRank::Leader);
+        assert_eq!(manager.guild(id).unwrap().leader(), Some(CharacterId(4)));
+        assert_eq!(
+            manager.member(CharacterId(3)).unwrap().1.rank,
+            GuildRank::Officer
+        );
+
+        for character_id in 2..=4 {
+            manager.remove_member(CharacterId(character_id));
+        }
+        assert!(manager.guild(id).is_none());
+        assert_eq!(manager.guild_of(CharacterId(2)), None);
+    }
+}
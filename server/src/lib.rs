@@ -7,6 +7,7 @@
 #![feature(box_patterns, option_zip, const_type_name, slice_partition_dedup)]
 
 pub mod automod;
+pub mod backup;
 mod character_creator;
 pub mod chat;
 pub mod chunk_generator;
@@ -17,10 +18,13 @@ pub mod connection_handler;
 mod data_dir;
 pub mod error;
 pub mod events;
+pub mod guild;
 pub mod input;
 pub mod location;
 pub mod lod;
 pub mod login_provider;
+pub mod mail;
+pub mod market;
 pub mod metrics;
 pub mod persistence;
 mod pet;
@@ -55,8 +59,11 @@ use crate::{
     cmd::ChatCommandExt,
     connection_handler::ConnectionHandler,
     data_dir::DataDir,
+    guild::GuildManager,
     location::Locations,
     login_provider::LoginProvider,
+    mail::MailManager,
+    market::MarketManager,
     persistence::PersistedComponents,
     presence::{RegionSubscription, RepositionToFreeSpace},
     state_ext::StateExt,
@@ -101,7 +108,10 @@ use common_systems::add_local_systems;
 use metrics::{EcsSystemMetrics, GameplayMetrics, PhysicsMetrics, TickMetrics};
 use network::{ListenAddr, Network, Pid};
 use persistence::{
-    character_loader::{CharacterLoader, CharacterUpdaterMessage},
+    character_loader::{
+        CharacterLoader, CharacterTransferResponse, CharacterTransferResponseKind,
+        CharacterUpdaterMessage, TransferRequester,
+    },
     character_updater::CharacterUpdater,
 };
 use prometheus::Registry;
@@ -112,6 +122,7 @@ use specs::{
 };
 use std::{
     ops::{Deref, DerefMut},
+    path::PathBuf,
     sync::{Arc, Mutex},
     time::{Duration, Instant},
 };
@@ -248,7 +259,11 @@ pub struct Server {
     metrics_registry: Arc<Registry>,
     chat_cache: ChatCache,
     database_settings: Arc<RwLock<DatabaseSettings>>,
+    /// Keeps other servers from using the database, see
+    /// [`persistence::lock_database`]
+    _database_lock: persistence::DatabaseLock,
     disconnect_all_clients_requested: bool,
+    snapshots: backup::Snapshots,
 
     event_dispatcher: SendDispatcher<'static>,
 }
@@ -269,6 +284,8 @@ impl Server {
             info!("Authentication is disabled");
         }
 
+        let database_lock = persistence::lock_database(&database_settings)?;
+
         report_stage(ServerInitStage::DbMigrations);
         // Run pending DB migrations (if any)
         debug!("Running DB migrations...");
@@ -299,7 +316,14 @@ impl Server {
 
         // Load plugins before generating the world.
         #[cfg(feature = "plugins")]
-        let plugin_mgr = PluginMgr::from_asset_or_default();
+        let plugin_mgr = PluginMgr::from_asset_or_default(GameMode::Server);
+        // The storage has to be available before the plugins' load event runs
+        #[cfg(feature = "plugins")]
+        plugin_mgr
+            .storage()
+            .load(persistence::plugin_storage::load_plugin_storage(
+                &database_settings.read().unwrap(),
+            )?);
 
         debug!("Generating world, seed: {}", settings.world_seed);
         #[cfg(feature = "worldgen")]
@@ -377,6 +401,9 @@ impl Server {
         state
             .ecs_mut()
             .insert(EventBus::<chunk_serialize::ChunkSendEntry>::default());
+        state
+            .ecs_mut()
+            .insert(chunk_serialize::ChunkVersions::default());
         state.ecs_mut().insert(Locations::default());
         state.ecs_mut().insert(LoginProvider::new(
             settings.auth_server_address.clone(),
@@ -400,23 +427,18 @@ impl Server {
         state.ecs_mut().insert(server_event_metrics);
         state.ecs_mut().insert(gameplay_metrics);
         state.ecs_mut().insert(query_server_metrics);
-        if settings.experimental_terrain_persistence {
+        if settings.terrain_persistence {
             #[cfg(feature = "persistent_world")]
             {
-                warn!(
-                    "Experimental terrain persistence support is enabled. This feature may break, \
-                     be disabled, or otherwise change under your feet at *any time*. \
-                     Additionally, it is expected to be replaced in the future *without* \
-                     migration or warning. You have been warned."
-                );
+                info!("Terrain persistence is enabled");
                 state
                     .ecs_mut()
                     .insert(TerrainPersistence::new(data_dir.to_owned()));
             }
             #[cfg(not(feature = "persistent_world"))]
             error!(
-                "Experimental terrain persistence support was requested, but the server was not \
-                 compiled with the feature. Terrain modifications will *not* be persisted."
+                "Terrain persistence was requested, but the server was not compiled with the \
+                 feature. Terrain modifications will *not* be persisted."
             );
         }
         {
@@ -440,6 +462,33 @@ impl Server {
         state.ecs_mut().insert(CharacterUpdater::new(
             Arc::<RwLock<DatabaseSettings>>::clone(&database_settings),
         )?);
+        // Other servers can't see the guilds, mail and listings kept in memory
+        if database_settings.read().unwrap().backend.is_shared() {
+            warn!(
+                "The database is shared with other servers, guilds, mail and the market are \
+                 disabled"
+            );
+            state.ecs_mut().insert(GuildManager::disabled());
+            state.ecs_mut().insert(MailManager::disabled());
+            state.ecs_mut().insert(MarketManager::disabled());
+        } else {
+            let database_settings = database_settings.read().unwrap();
+            state
+                .ecs_mut()
+                .insert(GuildManager::new(persistence::guild::load_guilds(
+                    &database_settings,
+                )?));
+            state
+                .ecs_mut()
+                .insert(MailManager::new(persistence::mail::load_mail(
+                    &database_settings,
+                )?));
+            state
+                .ecs_mut()
+                .insert(MarketManager::new(persistence::market::load_listings(
+                    &database_settings,
+                )?));
+        }
 
         let ability_map = comp::item::tool::AbilityMap::<comp::AbilityItem>::load_expect_cloned(
             "common.abilities.ability_set_manifest",
@@ -475,6 +524,9 @@ impl Server {
         state.ecs_mut().register::<login_provider::PendingLogin>();
         state.ecs_mut().register::<RepositionToFreeSpace>();
         state.ecs_mut().register::<common::rtsim::ActorId>();
+        state
+            .ecs_mut()
+            .register::<common::rtsim::PersistedReputation>();
 
         // Load banned words list
         let banned_words = settings.moderation.load_banned_words(data_dir);
@@ -567,46 +619,8 @@ impl Server {
                     cert_file_path,
                     key_file_path,
                 } => {
-                    use rustls_pemfile::Item;
-                    use std::fs;
-
                     match || -> Result<_, Box<dyn std::error::Error>> {
-                        let key = fs::read(key_file_path)?;
-                        let key = if key_file_path.extension().is_some_and(|x| x == "der") {
-                            PrivateKeyDer::try_from(key).map_err(|_| "No valid pem key in file")?
-                        } else {
-                            debug!("convert pem key to der");
-                            rustls_pemfile::read_all(&mut key.as_slice())
-                                .find_map(|item| match item {
-                                    Ok(Item::Pkcs1Key(v)) => Some(PrivateKeyDer::Pkcs1(v)),
-                                    Ok(Item::Pkcs8Key(v)) => Some(PrivateKeyDer::Pkcs8(v)),
-                                    Ok(Item::Sec1Key(v)) => Some(PrivateKeyDer::Sec1(v)),
-                                    Ok(Item::Crl(_)) => None,
-                                    Ok(Item::Csr(_)) => None,
-                                    Ok(Item::X509Certificate(_)) => None,
-                                    Ok(_) => None,
-                                    Err(e) => {
-                                        tracing::warn!(?e, "error while reading key_file");
-                                        None
-                                    },
-                                })
-                                .ok_or("No valid pem key in file")?
-                        };
-                        let cert_chain = fs::read(cert_file_path)?;
-                        let cert_chain = if cert_file_path.extension().is_some_and(|x| x == "der") {
-                            vec![CertificateDer::from(cert_chain)]
-                        } else {
-                            debug!("convert pem cert to der");
-                            rustls_pemfile::certs(&mut cert_chain.as_slice())
-                                .filter_map(|item| match item {
-                                    Ok(cert) => Some(cert),
-                                    Err(e) => {
-                                        tracing::warn!(?e, "error while reading cert_file");
-                                        None
-                                    },
-                                })
-                                .collect()
-                        };
+                        let (cert_chain, key) = load_certificate(cert_file_path, key_file_path)?;
                         let server_config = quinn::ServerConfig::with_single_cert(cert_chain, key)?;
                         Ok(server_config)
                     }() {
@@ -632,11 +646,40 @@ impl Server {
                         },
                     }
                 },
+                Protocol::Tls {
+                    address,
+                    cert_file_path,
+                    key_file_path,
+                } => {
+                    match || -> Result<_, Box<dyn std::error::Error>> {
+                        let (cert_chain, key) = load_certificate(cert_file_path, key_file_path)?;
+                        let server_config = rustls::ServerConfig::builder()
+                            .with_no_client_auth()
+                            .with_single_cert(cert_chain, key)?;
+                        Ok(server_config)
+                    }() {
+                        Ok(server_config) => {
+                            runtime.block_on(
+                                network.listen(ListenAddr::Tls(*address, Arc::new(server_config))),
+                            )?;
+                        },
+                        Err(e) => {
+                            error!(
+                                ?e,
+                                "Failed to load the TLS certificate, running without TLS {}",
+                                *address
+                            );
+                        },
+                    }
+                },
             }
         }
 
         if let Some(addr) = settings.query_address {
-            use veloren_query_server::proto::ServerInfo;
+            use veloren_query_server::{
+                proto::{ServerInfo, WorldInfo, WorldWeather},
+                server::ServerDetails,
+            };
 
             const QUERY_SERVER_RATELIMIT: u16 = 120;
 
@@ -648,8 +691,31 @@ impl Server {
                     player_cap: settings.max_players,
                     battlemode: settings.gameplay.battle_mode.into(),
                 });
-            let mut query_server =
-                QueryServer::new(addr, query_server_info_rx, QUERY_SERVER_RATELIMIT);
+            let world_size = state.terrain().map_size_lg().chunks();
+            let (query_server_details_tx, query_server_details_rx) =
+                tokio::sync::watch::channel(ServerDetails {
+                    world: WorldInfo {
+                        seed: settings.world_seed,
+                        size_x: world_size.x.into(),
+                        size_y: world_size.y.into(),
+                        uptime: 0,
+                        time_of_day: state.ecs().read_resource::<TimeOfDay>().0,
+                        weather: WorldWeather {
+                            cloud: 0.0,
+                            rain: 0.0,
+                            wind: 0.0,
+                        },
+                        battlemode: settings.gameplay.battle_mode.into(),
+                    },
+                    players: settings.query_player_list.then(Vec::new),
+                    plugins: Vec::new(),
+                });
+            let mut query_server = QueryServer::new(
+                addr,
+                query_server_info_rx,
+                query_server_details_rx,
+                QUERY_SERVER_RATELIMIT,
+            );
             let query_server_metrics =
                 Arc::new(Mutex::new(veloren_query_server::server::Metrics::default()));
             let query_server_metrics2 = Arc::clone(&query_server_metrics);
@@ -658,6 +724,7 @@ impl Server {
                 error!(?err, "Query server stopped unexpectedly");
             });
             state.ecs_mut().insert(query_server_info_tx);
+            state.ecs_mut().insert(query_server_details_tx);
             state.ecs_mut().insert(query_server_metrics);
         }
 
@@ -696,7 +763,9 @@ impl Server {
             metrics_registry: registry,
             chat_cache,
             database_settings,
+            _database_lock: database_lock,
             disconnect_all_clients_requested: false,
+            snapshots: backup::Snapshots::new(),
 
             event_dispatcher: Self::create_event_dispatcher(pools),
         };
@@ -1068,6 +1137,9 @@ impl Server {
                 CharacterUpdaterMessage::DatabaseBatchCompletion(batch_id) => {
                     character_updater.process_batch_completion(batch_id);
                 },
+                CharacterUpdaterMessage::CharacterTransferResponse(response) => {
+                    self.handle_character_transfer_response(response);
+                },
                 CharacterUpdaterMessage::CharacterScreenResponse(response) => {
                     match response.response_kind {
                         CharacterScreenResponseKind::CharacterList(result) => match result {
@@ -1125,10 +1197,12 @@ impl Server {
                                         stats,
                                         skill_set,
                                         inventory,
+                                        storage,
                                         waypoint,
                                         pets,
                                         active_abilities,
                                         map_marker,
+                                        reputation,
                                     } = character_data;
                                     let character_data = (
                                         body,
@@ -1136,10 +1210,12 @@ impl Server {
                                         stats,
                                         skill_set,
                                         inventory,
+                                        storage,
                                         waypoint,
                                         pets,
                                         active_abilities,
                                         map_marker,
+                                        reputation,
                                     );
                                     // TODO: Does this need to be a server event? E.g. we could
                                     // just handle it here.
@@ -1268,10 +1344,27 @@ impl Server {
 
         // Maintain persisted terrain
         #[cfg(feature = "persistent_world")]
-        self.state
-            .ecs()
-            .try_fetch_mut::<TerrainPersistence>()
-            .map(|mut t| t.maintain());
+        {
+            let journal_responses = self
+                .state
+                .ecs()
+                .try_fetch_mut::<TerrainPersistence>()
+                .map(|mut t| {
+                    t.maintain();
+                    t.finished_journal_queries()
+                })
+                .unwrap_or_default();
+            for response in journal_responses {
+                cmd::handle_journal_response(self, response);
+            }
+        }
+
+        let due = self
+            .snapshots
+            .due(&self.state.ecs().fetch::<Settings>().snapshots);
+        if due && let Err(e) = self.create_snapshot() {
+            error!("Failed to take scheduled snapshot: {e}");
+        }
     }
 
     // Run RegionMap tick to update entity region occupancy
@@ -1469,65 +1562,412 @@ impl Server {
         self.state.ecs().read_storage::<Client>().join().count() as i64
     }
 
+    /// Returns `true` if the admin list was changed.
+    ///
     /// NOTE: Do *not* allow this to be called from any command that doesn't go
     /// through the CLI!
-    pub fn add_admin(&mut self, username: &str, role: comp::AdminRole) {
+    pub fn add_admin(&mut self, username: &str, role: comp::AdminRole) -> bool {
         let mut editable_settings = self.editable_settings_mut();
         let login_provider = self.state.ecs().fetch::<LoginProvider>();
         let data_dir = self.data_dir();
-        if let Some(entity) = add_admin(
+        let Some(uuid) = add_admin(
             username,
             role,
             &login_provider,
             &mut editable_settings,
             &data_dir.path,
-        )
-        .and_then(|uuid| {
-            let state = &self.state;
-            (
-                &state.ecs().entities(),
-                &state.read_storage::<comp::Player>(),
-            )
-                .join()
-                .find(|(_, player)| player.uuid() == uuid)
-                .map(|(e, _)| e)
-        }) {
-            drop((data_dir, login_provider, editable_settings));
-            // Add admin component if the player is ingame; if they are not, we can ignore
-            // the write failure.
+        ) else {
+            return false;
+        };
+        drop((data_dir, login_provider, editable_settings));
+        // Add admin component if the player is ingame; if they are not, we can ignore
+        // the write failure.
+        if let Some(entity) = self.find_player_by_uuid(uuid) {
             self.state
                 .write_component_ignore_entity_dead(entity, comp::Admin(role));
-        };
+        }
+        true
     }
 
+    /// Returns `true` if the admin list was changed.
+    ///
     /// NOTE: Do *not* allow this to be called from any command that doesn't go
     /// through the CLI!
-    pub fn remove_admin(&self, username: &str) {
+    pub fn remove_admin(&self, username: &str) -> bool {
         let mut editable_settings = self.editable_settings_mut();
         let login_provider = self.state.ecs().fetch::<LoginProvider>();
         let data_dir = self.data_dir();
-        if let Some(entity) = remove_admin(
+        let Some(uuid) = remove_admin(
             username,
             &login_provider,
             &mut editable_settings,
             &data_dir.path,
-        )
-        .and_then(|uuid| {
-            let state = &self.state;
-            (
-                &state.ecs().entities(),
-                &state.read_storage::<comp::Player>(),
-            )
-                .join()
-                .find(|(_, player)| player.uuid() == uuid)
-                .map(|(e, _)| e)
-        }) {
-            // Remove admin component if the player is ingame
+        ) else {
+            return false;
+        };
+        // Remove admin component if the player is ingame
+        if let Some(entity) = self.find_player_by_uuid(uuid) {
             self.state
                 .ecs()
                 .write_storage::<comp::Admin>()
                 .remove(entity);
+        }
+        true
+    }
+
+    fn find_player_by_uuid(&self, uuid: Uuid) -> Option<EcsEntity> {
+        (
+            &self.state.ecs().entities(),
+            &self.state.read_storage::<comp::Player>(),
+        )
+            .join()
+            .find(|(_, player)| player.uuid() == uuid)
+            .map(|(e, _)| e)
+    }
+
+    fn find_player_by_alias(&self, alias: &str) -> Option<EcsEntity> {
+        (
+            &self.state.ecs().entities(),
+            &self.state.read_storage::<comp::Player>(),
+        )
+            .join()
+            .find(|(_, player)| player.alias.eq_ignore_ascii_case(alias))
+            .map(|(e, _)| e)
+    }
+
+    fn username_to_uuid(&self, username: &str) -> Result<Uuid, String> {
+        self.state
+            .ecs()
+            .fetch::<LoginProvider>()
+            .username_to_uuid(username)
+            .map_err(|err| {
+                format!(
+                    "Could not find uuid for {username}; either the user does not exist or there \
+                     was an error communicating with the auth server: {err:?}"
+                )
+            })
+    }
+
+    /// Starts taking a snapshot of the character database, rtsim, persisted
+    /// terrain and the server config in the background. Returns the path
+    /// of the archive it will be written to.
+    ///
+    /// NOTE: Do *not* allow this to be called from any command that doesn't go
+    /// through the CLI!
+    pub fn create_snapshot(&mut self) -> Result<PathBuf, String> {
+        let ecs = self.state.ecs();
+        let data_dir = ecs.fetch::<DataDir>().path.clone();
+        let settings = ecs.fetch::<Settings>().snapshots.clone();
+        self.snapshots.start(ecs, &data_dir, &settings)
+    }
+
+    /// Disconnects an online player.
+    ///
+    /// NOTE: Do *not* allow this to be called from any command that doesn't go
+    /// through the CLI!
+    pub fn kick_player(&mut self, username: &str, reason: String) -> Result<(), String> {
+        let entity = self
+            .find_player_by_alias(username)
+            .ok_or_else(|| format!("{username} is not online"))?;
+        info!("Kicking {} with reason: {}", username, reason);
+        self.notify_client(
+            entity,
+            ServerGeneral::Disconnect(DisconnectReason::Kicked(reason)),
+        );
+        self.state.emit_event_now(ClientDisconnectEvent(
+            entity,
+            comp::DisconnectReason::Kicked,
+        ));
+        Ok(())
+    }
+
+    /// Bans a player, kicking them if they are online. The ban is permanent if
+    /// no `duration` is given.
+    ///
+    /// NOTE: Do *not* allow this to be called from any command that doesn't go
+    /// through the CLI!
+    pub fn ban_player(
+        &mut self,
+        username: &str,
+        reason: String,
+        duration: Option<Duration>,
+        overwrite: bool,
+    ) -> Result<(), String> {
+        let uuid = self.username_to_uuid(username)?;
+        let now = chrono::Utc::now();
+        // On overflow, just make the ban infinite
+        let end_date = duration
+            .and_then(|duration| chrono::Duration::from_std(duration).ok())
+            .and_then(|duration| now.checked_add_signed(duration));
+        let result = self.editable_settings_mut().banlist.ban_operation(
+            self.data_dir().as_ref(),
+            now,
+            uuid,
+            username.to_owned(),
+            settings::BanOperation::Ban {
+                reason: reason.clone(),
+                info: cli_ban_info(),
+                upgrade_to_ip: false,
+                end_date,
+            },
+            overwrite,
+        );
+        let persisted = handle_ban_operation(result)?;
+        info!("Banned {} ({}) with reason: {}", username, uuid, reason);
+
+        if let Some(entity) = self.find_player_by_uuid(uuid) {
+            self.notify_client(
+                entity,
+                ServerGeneral::Disconnect(DisconnectReason::Banned(
+                    common_net::msg::server::BanInfo {
+                        reason,
+                        until: end_date.map(|date| date.timestamp()),
+                    },
+                )),
+            );
+            self.state.emit_event_now(ClientDisconnectEvent(
+                entity,
+                comp::DisconnectReason::Kicked,
+            ));
+        }
+        ban_list_outcome(persisted)
+    }
+
+    /// NOTE: Do *not* allow this to be called from any command that doesn't go
+    /// through the CLI!
+    pub fn unban_player(&mut self, username: &str) -> Result<(), String> {
+        let uuid = self.username_to_uuid(username)?;
+        let result = self.editable_settings_mut().banlist.ban_operation(
+            self.data_dir().as_ref(),
+            chrono::Utc::now(),
+            uuid,
+            username.to_owned(),
+            settings::BanOperation::Unban {
+                info: cli_ban_info(),
+            },
+            false,
+        );
+        let persisted = handle_ban_operation(result)?;
+        info!("Unbanned {} ({})", username, uuid);
+        ban_list_outcome(persisted)
+    }
+
+    /// NOTE: Do *not* allow this to be called from any command that doesn't go
+    /// through the CLI!
+    pub fn add_to_whitelist(&mut self, username: &str) -> Result<(), String> {
+        use crate::settings::EditableSetting;
+        let uuid = self.username_to_uuid(username)?;
+        let record = settings::WhitelistRecord {
+            date: chrono::Utc::now(),
+            info: None,
+        };
+        let mut editable_settings = self.editable_settings_mut();
+        let data_dir = self.data_dir();
+        handle_edit(
+            uuid,
+            editable_settings
+                .whitelist
+                .edit(&data_dir.path, |whitelist| {
+                    whitelist
+                        .insert(uuid, record)
+                        .is_none()
+                        .then(|| format!("Added {} ({}) to the whitelist", username, uuid))
+                }),
+        )
+        .map(|_| ())
+        .ok_or_else(|| format!("{username} could not be added to the whitelist"))
+    }
+
+    /// NOTE: Do *not* allow this to be called from any command that doesn't go
+    /// through the CLI!
+    pub fn remove_from_whitelist(&mut self, username: &str) -> Result<(), String> {
+        use crate::settings::EditableSetting;
+        let uuid = self.username_to_uuid(username)?;
+        let mut editable_settings = self.editable_settings_mut();
+        let data_dir = self.data_dir();
+        handle_edit(
+            uuid,
+            editable_settings
+                .whitelist
+                .edit(&data_dir.path, |whitelist| {
+                    whitelist
+                        .remove(&uuid)
+                        .map(|_| format!("Removed {} ({}) from the whitelist", username, uuid))
+                }),
+        )
+        .map(|_| ())
+        .ok_or_else(|| format!("{username} could not be removed from the whitelist"))
+    }
+
+    /// Teleports an online player to the given position.
+    ///
+    /// NOTE: Do *not* allow this to be called from any command that doesn't go
+    /// through the CLI!
+    pub fn teleport_player(&mut self, username: &str, pos: Vec3<f32>) -> Result<(), String> {
+        let entity = self
+            .find_player_by_alias(username)
+            .ok_or_else(|| format!("{username} is not online"))?;
+        self.state
+            .position_mut(entity, true, |current_pos| current_pos.0 = pos)
+            .map_err(|err| format!("Could not teleport {username}: {err:?}"))?;
+        info!("Teleported {} to {:?}", username, pos);
+        Ok(())
+    }
+
+    fn character_transfer_key(&self) -> Result<String, String> {
+        self.state
+            .ecs()
+            .fetch::<Settings>()
+            .character_transfer_key
+            .clone()
+            .ok_or_else(|| {
+                "Character transfers are disabled, set a character_transfer_key in the server \
+                 settings"
+                    .to_owned()
+            })
+    }
+
+    /// Exports a character of a player, identified by its alias or id, to a
+    /// file that can be imported by servers sharing the
+    /// `character_transfer_key`. The requester is told about the outcome once
+    /// the persistence thread is done.
+    pub fn export_character(
+        &mut self,
+        username: &str,
+        character: &str,
+        path: PathBuf,
+        requester: TransferRequester,
+    ) {
+        let checked = self.character_transfer_key().and_then(|key| {
+            let uuid = self.username_to_uuid(username)?;
+            // The persisted character lags behind while the player is playing
+            if self.find_player_by_alias(username).is_some() {
+                return Err(format!(
+                    "{username} is online, characters can only be exported while their player is \
+                     offline"
+                ));
+            }
+            Ok((key, uuid))
+        });
+        let (key, uuid) = match checked {
+            Ok(checked) => checked,
+            Err(e) => return self.reply_character_transfer(requester, Err(e)),
+        };
+        let server_name = self.state.ecs().fetch::<Settings>().server_name.clone();
+        self.state
+            .ecs()
+            .write_resource::<CharacterUpdater>()
+            .export_character(
+                uuid.to_string(),
+                character.to_owned(),
+                server_name,
+                path,
+                key,
+                username.to_owned(),
+                requester,
+            );
+    }
+
+    /// Creates a new character for a player from a file written by
+    /// [`Server::export_character`], optionally renaming it. Each export can
+    /// only be imported once.
+    pub fn import_character(
+        &mut self,
+        username: &str,
+        path: PathBuf,
+        alias: Option<String>,
+        requester: TransferRequester,
+    ) {
+        let (key, uuid) = match self
+            .character_transfer_key()
+            .and_then(|key| Ok((key, self.username_to_uuid(username)?)))
+        {
+            Ok(checked) => checked,
+            Err(e) => return self.reply_character_transfer(requester, Err(e)),
+        };
+        self.state
+            .ecs()
+            .write_resource::<CharacterUpdater>()
+            .import_character(
+                uuid.to_string(),
+                path,
+                key,
+                alias,
+                username.to_owned(),
+                requester,
+            );
+    }
+
+    fn handle_character_transfer_response(&self, response: CharacterTransferResponse) {
+        let CharacterTransferResponse {
+            requester,
+            username,
+            response_kind,
+        } = response;
+        let outcome = match response_kind {
+            CharacterTransferResponseKind::Export(result) => result.map(|(alias, path)| {
+                info!(
+                    "Exported character {} of {} to {}",
+                    alias,
+                    username,
+                    path.display()
+                );
+                let file = path.file_name().map_or_else(
+                    || path.display().to_string(),
+                    |file| file.to_string_lossy().into_owned(),
+                );
+                Content::localized_with_args("command-export_character-done", [("file", file)])
+            }),
+            CharacterTransferResponseKind::Import(result) => result.map(|imported| {
+                info!(
+                    "Imported character {} of {} from {} as character {}",
+                    imported.original_alias, username, imported.source, imported.character_id.0
+                );
+                if imported.skills_reset {
+                    warn!(
+                        "The skill trees of {} differ from this server, its skill points were \
+                         refunded",
+                        imported.source
+                    );
+                }
+                Content::localized_with_args("command-import_character-done", [
+                    ("id", imported.character_id.0.to_string()),
+                    ("player", username),
+                ])
+            }),
         };
+        if let Err(e) = &outcome {
+            warn!("Character transfer failed: {}", e);
+        }
+        self.reply_character_transfer(requester, outcome);
+    }
+
+    fn reply_character_transfer(
+        &self,
+        requester: TransferRequester,
+        outcome: Result<Content, String>,
+    ) {
+        match requester {
+            TransferRequester::Client(client) => {
+                let (chat_type, content) = match outcome {
+                    Ok(content) => (ChatType::CommandInfo, content),
+                    Err(e) => (ChatType::CommandError, Content::Plain(e)),
+                };
+                self.notify_client(client, ServerGeneral::server_msg(chat_type, content));
+            },
+            TransferRequester::Callback(callback) => callback(outcome.map(|_| ())),
+        }
+    }
+
+    /// Unloads all chunks so that they get regenerated, returning the number
+    /// of chunks that were unloaded.
+    ///
+    /// NOTE: Do *not* allow this to be called from any command that doesn't go
+    /// through the CLI!
+    pub fn reload_all_chunks(&mut self) -> usize {
+        let removed = cmd::reload_chunks_inner(self, Vec3::zero(), None, false);
+        info!("Reloaded {} chunks", removed);
+        removed
     }
 
     /// Useful for testing without a client
@@ -1580,6 +2020,31 @@ impl Server {
         self.disconnect_all_clients_requested = true;
     }
 
+    /// Replaces a running plugin by the current contents of its file and lets
+    /// the clients fetch the new version if it changed.
+    #[cfg(feature = "plugins")]
+    pub fn reload_plugin(
+        &mut self,
+        name: &str,
+    ) -> Result<(), common_state::plugin::errors::PluginError> {
+        let (old, new) = {
+            let mut plugin_manager = self.state.ecs().write_resource::<PluginMgr>();
+            let ecs_world = EcsWorld {
+                entities: &self.state.ecs().entities(),
+                health: self.state.ecs().read_component().into(),
+                uid: self.state.ecs().read_component().into(),
+                id_maps: &self.state.ecs().read_resource::<IdMaps>().into(),
+                player: self.state.ecs().read_component().into(),
+            };
+            plugin_manager.reload(&ecs_world, name, GameMode::Server)?
+        };
+
+        if old != new {
+            self.notify_players(ServerGeneral::PluginReloaded { old, new });
+        }
+        Ok(())
+    }
+
     /// Sends the given client a message with their current battle mode and
     /// whether they can change it.
     ///
@@ -1797,11 +2262,46 @@ impl Drop for Server {
                 terrain_persistence.unload_all()
             });
 
+        if self.snapshots.is_running() {
+            info!("Waiting for snapshot to complete...");
+        }
+        self.snapshots.wait();
+
         #[cfg(feature = "worldgen")]
         {
             debug!("Saving rtsim state...");
             self.state.ecs().write_resource::<rtsim::RtSim>().save(true);
         }
+
+        // Written by the persistence thread before it shuts down
+        debug!("Saving guilds...");
+        let changes = self
+            .state
+            .ecs()
+            .write_resource::<GuildManager>()
+            .take_changes();
+        self.state
+            .ecs()
+            .write_resource::<CharacterUpdater>()
+            .update_guilds(changes);
+
+        // Mail and market listings aren't saved here, they move items between
+        // characters and are only written together with them
+
+        #[cfg(feature = "plugins")]
+        {
+            debug!("Saving plugin storage...");
+            let changes = self
+                .state
+                .ecs()
+                .read_resource::<PluginMgr>()
+                .storage()
+                .take_changes();
+            self.state
+                .ecs()
+                .write_resource::<CharacterUpdater>()
+                .update_plugin_storage(changes);
+        }
     }
 }
 
@@ -1833,6 +2333,49 @@ pub fn handle_edit<T, S: settings::EditableSetting>(
     }
 }
 
+/// Ban info for operations performed through the CLI, which isn't tied to any
+/// account.
+fn cli_ban_info() -> settings::BanInfo {
+    settings::BanInfo {
+        performed_by: Uuid::nil(),
+        performed_by_username: "server-cli".to_owned(),
+        performed_by_role: comp::AdminRole::Admin.into(),
+    }
+}
+
+/// Turns the result of a ban operation into a message that can be shown to the
+/// CLI user. Returns whether the ban list was written to disk, on IO errors the
+/// operation still succeeded in memory.
+fn handle_ban_operation(
+    result: Result<Option<common_net::msg::server::BanInfo>, settings::BanOperationError>,
+) -> Result<bool, String> {
+    use crate::settings::{BanOperationError, SettingError};
+    match result {
+        Ok(_) => Ok(true),
+        Err(BanOperationError::NoEffect) => Err("The ban list was not changed".to_owned()),
+        Err(BanOperationError::EditFailed(SettingError::Io(err))) => {
+            warn!(
+                ?err,
+                "Failed to write the ban list to disk, but succeeded in memory"
+            );
+            Ok(false)
+        },
+        Err(BanOperationError::EditFailed(SettingError::Integrity(err))) => {
+            error!(?err, "Encountered an error while validating the request");
+            Err(format!("Invalid request: {err:?}"))
+        },
+    }
+}
+
+/// Tells the CLI user if a ban operation is lost on restart
+fn ban_list_outcome(persisted: bool) -> Result<(), String> {
+    if persisted {
+        Ok(())
+    } else {
+        Err("Applied until the next restart, the ban list could not be written to disk".to_owned())
+    }
+}
+
 /// If successful returns the Some(uuid) of the added admin
 ///
 /// NOTE: Do *not* allow this to be called from any command that doesn't go
@@ -1919,3 +2462,51 @@ pub fn remove_admin(
         },
     }
 }
+
+/// Loads a certificate chain and private key from `.der` or `.pem` files,
+/// used for QUIC and TLS listeners
+fn load_certificate(
+    cert_file_path: &std::path::Path,
+    key_file_path: &std::path::Path,
+) -> Result<(Vec<CertificateDer<'static>>, PrivateKeyDer<'static>), Box<dyn std::error::Error>> {
+    use rustls_pemfile::Item;
+    use std::fs;
+
+    let key = fs::read(key_file_path)?;
+    let key = if key_file_path.extension().is_some_and(|x| x == "der") {
+        PrivateKeyDer::try_from(key).map_err(|_| "No valid pem key in file")?
+    } else {
+        debug!("convert pem key to der");
+        rustls_pemfile::read_all(&mut key.as_slice())
+            .find_map(|item| match item {
+                Ok(Item::Pkcs1Key(v)) => Some(PrivateKeyDer::Pkcs1(v)),
+                Ok(Item::Pkcs8Key(v)) => Some(PrivateKeyDer::Pkcs8(v)),
+                Ok(Item::Sec1Key(v)) => Some(PrivateKeyDer::Sec1(v)),
+                Ok(Item::Crl(_)) => None,
+                Ok(Item::Csr(_)) => None,
+                Ok(Item::X509Certificate(_)) => None,
+                Ok(_) => None,
+                Err(e) => {
+                    tracing::warn!(?e, "error while reading key_file");
+                    None
+                },
+            })
+            .ok_or("No valid pem key in file")?
+    };
+    let cert_chain = fs::read(cert_file_path)?;
+    let cert_chain = if cert_file_path.extension().is_some_and(|x| x == "der") {
+        vec![CertificateDer::from(cert_chain)]
+    } else {
+        debug!("convert pem cert to der");
+        rustls_pemfile::certs(&mut cert_chain.as_slice())
+            .filter_map(|item| match item {
+                Ok(cert) => Some(cert),
+                Err(e) => {
+                    tracing::warn!(?e, "error while reading cert_file");
+                    None
+                },
+            })
+            .collect()
+    };
+    Ok((cert_chain, key))
+}
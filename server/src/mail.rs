@@ -0,0 +1,594 @@
+//! Player mail
+//!
+//! All mail of the server is kept in the [`MailManager`], so it can be
+//! delivered to characters that aren't online. Characters read and send mail
+//! at mailbox sprites, see [`comp::mail`].
+
+use crate::persistence::mail::{MailChange, MailRow, MailRows};
+use common::{
+    character::CharacterId,
+    comp::{
+        self, Content,
+        mail::{
+            MAIL_LIFETIME_SECS, MAX_ATTACHMENTS, MAX_BODY_LEN, MAX_MAILBOX_SIZE, MAX_SUBJECT_LEN,
+            Mail, MailDraft, MailId, NEVER_EXPIRES,
+        },
+    },
+};
+use hashbrown::HashMap;
+use std::collections::{BTreeMap, BTreeSet};
+use vek::*;
+
+#[derive(Clone, Debug)]
+struct MailCharacter {
+    /// Name of the character, refreshed whenever it logs in
+    name: String,
+    player_uuid: String,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum MailError {
+    UnknownRecipient,
+    /// Several characters have the name of the recipient
+    AmbiguousRecipient,
+    SelfRecipient,
+    MailboxFull,
+    SubjectTooLong,
+    BodyTooLong,
+    TooManyAttachments,
+    NotEnoughCoins,
+    /// The attachments don't fit into the inventory
+    InventoryFull,
+    /// Mail can only be deleted once its attachments were taken
+    HasAttachments,
+    /// Mail without a sender, or mail that was already returned, can't be
+    /// returned
+    CannotReturn,
+    /// The database is shared with other servers, see [`MailManager::disabled`]
+    Disabled,
+    NotFound,
+}
+
+impl From<MailError> for Content {
+    fn from(error: MailError) -> Self {
+        Content::localized(match error {
+            MailError::UnknownRecipient => "hud-mail-error-unknown_recipient",
+            MailError::AmbiguousRecipient => "hud-mail-error-ambiguous_recipient",
+            MailError::SelfRecipient => "hud-mail-error-self_recipient",
+            MailError::MailboxFull => "hud-mail-error-mailbox_full",
+            MailError::SubjectTooLong => "hud-mail-error-subject_too_long",
+            MailError::BodyTooLong => "hud-mail-error-body_too_long",
+            MailError::TooManyAttachments => "hud-mail-error-too_many_attachments",
+            MailError::NotEnoughCoins => "hud-mail-error-not_enough_coins",
+            MailError::InventoryFull => "hud-mail-error-inventory_full",
+            MailError::HasAttachments => "hud-mail-error-has_attachments",
+            MailError::CannotReturn => "hud-mail-error-cannot_return",
+            MailError::Disabled => "hud-mail-error-disabled",
+            MailError::NotFound => "hud-mail-error-not_found",
+        })
+    }
+}
+
+/// All mail of the server, changes are persisted together with the characters
+/// by the
+/// [`CharacterUpdater`](crate::persistence::character_updater::CharacterUpdater).
+pub struct MailManager {
+    mail: BTreeMap<MailId, MailRow>,
+    /// Every character mail can be sent to
+    characters: HashMap<CharacterId, MailCharacter>,
+    next_mail_id: u64,
+    /// Mail modified since the last call to [`Self::take_changes`]
+    dirty: BTreeSet<MailId>,
+    /// Position of the mailbox each character has opened
+    open: HashMap<CharacterId, Vec3<i32>>,
+    enabled: bool,
+}
+
+impl MailManager {
+    pub fn new(rows: MailRows) -> Self {
+        let mail = rows
+            .mail
+            .into_iter()
+            .map(|row| (row.id, row))
+            .collect::<BTreeMap<_, _>>();
+        let next_mail_id = mail.keys().next_back().map_or(1, |id| id.0 + 1);
+
+        Self {
+            mail,
+            characters: rows
+                .characters
+                .into_iter()
+                .map(|(character_id, name, player_uuid)| {
+                    (character_id, MailCharacter { name, player_uuid })
+                })
+                .collect(),
+            next_mail_id,
+            dirty: BTreeSet::new(),
+            open: HashMap::new(),
+            enabled: true,
+        }
+    }
+
+    /// A manager without any mail that doesn't let characters open their
+    /// mailbox. Mail ids are allocated in memory, so servers sharing their
+    /// database can't send mail.
+    pub fn disabled() -> Self {
+        Self {
+            enabled: false,
+            ..Self::new(MailRows::default())
+        }
+    }
+
+    /// Keeps the name of the character up to date, characters that were
+    /// created after the server started can receive mail from now on.
+    ///
+    /// Returns the number of unread mail of the character.
+    pub fn character_logged_in(
+        &mut self,
+        character_id: CharacterId,
+        name: &str,
+        player_uuid: &str,
+    ) -> u32 {
+        self.characters.insert(character_id, MailCharacter {
+            name: name.to_owned(),
+            player_uuid: player_uuid.to_owned(),
+        });
+        self.open.remove(&character_id);
+        self.unread(character_id)
+    }
+
+    /// Forgets a character that is being deleted, provided it belongs to the
+    /// player. Mail it received is returned to its senders.
+    ///
+    /// Returns the characters that received mail, `None` if the character
+    /// doesn't belong to the player.
+    pub fn remove_character(
+        &mut self,
+        character_id: CharacterId,
+        player_uuid: &str,
+        now: i64,
+    ) -> Option<Vec<CharacterId>> {
+        if self
+            .characters
+            .get(&character_id)
+            .is_none_or(|character| character.player_uuid != player_uuid)
+        {
+            return None;
+        }
+        self.characters.remove(&character_id);
+        self.open.remove(&character_id);
+
+        let received = self
+            .mail
+            .values()
+            .filter(|mail| mail.recipient == character_id)
+            .map(|mail| mail.id)
+            .collect::<Vec<_>>();
+        let mut returned_to = Vec::new();
+        for id in received {
+            match self.return_mail(character_id, id, now) {
+                Ok(sender) => returned_to.push(sender),
+                Err(_) => self.delete_row(id),
+            }
+        }
+        for mail in self.mail.values_mut() {
+            if mail.sender == Some(character_id) {
+                mail.sender = None;
+                self.dirty.insert(mail.id);
+            }
+        }
+        Some(returned_to)
+    }
+
+    pub fn character_name(&self, character_id: CharacterId) -> Option<&str> {
+        self.characters
+            .get(&character_id)
+            .map(|character| character.name.as_str())
+    }
+
+    pub fn open(&mut self, character_id: CharacterId, pos: Vec3<i32>) -> Result<(), MailError> {
+        if !self.enabled {
+            return Err(MailError::Disabled);
+        }
+        self.open.insert(character_id, pos);
+        Ok(())
+    }
+
+    pub fn close(&mut self, character_id: CharacterId) { self.open.remove(&character_id); }
+
+    /// Position of the mailbox the character has opened
+    pub fn opened_at(&self, character_id: CharacterId) -> Option<Vec3<i32>> {
+        self.open.get(&character_id).copied()
+    }
+
+    /// The mail of the character as shown in its mailbox, newest first
+    pub fn mailbox(&self, character_id: CharacterId) -> Vec<Mail> {
+        let mut mailbox = self
+            .mail
+            .values()
+            .filter(|mail| mail.recipient == character_id)
+            .map(|mail| Mail {
+                id: mail.id,
+                sender: mail.sender_name.clone(),
+                subject: mail.subject.clone(),
+                body: mail.body.clone(),
+                coins: mail.coins,
+                attachments: mail.attachments.clone(),
+                sent_at: mail.sent_at,
+                expires_at: mail.expires_at,
+                read: mail.read,
+                returned: mail.returned,
+            })
+            .collect::<Vec<_>>();
+        mailbox.sort_by_key(|mail| std::cmp::Reverse((mail.sent_at, mail.id)));
+        mailbox
+    }
+
+    pub fn unread(&self, character_id: CharacterId) -> u32 {
+        self.mail
+            .values()
+            .filter(|mail| mail.recipient == character_id && !mail.read)
+            .count() as u32
+    }
+
+    fn mailbox_size(&self, character_id: CharacterId) -> usize {
+        self.mail
+            .values()
+            .filter(|mail| mail.recipient == character_id)
+            .count()
+    }
+
+    /// Looks up a character by its name, ignoring case
+    fn find_character(&self, name: &str) -> Result<CharacterId, MailError> {
+        let mut found = self
+            .characters
+            .iter()
+            .filter(|(_, character)| character.name.eq_ignore_ascii_case(name.trim()))
+            .map(|(character_id, _)| *character_id);
+        match (found.next(), found.next()) {
+            (Some(character_id), None) => Ok(character_id),
+            (Some(_), Some(_)) => Err(MailError::AmbiguousRecipient),
+            (None, _) => Err(MailError::UnknownRecipient),
+        }
+    }
+
+    /// Checks whether the draft can be sent, before its attachments are taken
+    /// from the sender.
+    ///
+    /// Returns the recipient.
+    pub fn check_draft(
+        &self,
+        sender: CharacterId,
+        draft: &MailDraft,
+    ) -> Result<CharacterId, MailError> {
+        if draft.subject.chars().count() > MAX_SUBJECT_LEN {
+            return Err(MailError::SubjectTooLong);
+        }
+        if draft.body.chars().count() > MAX_BODY_LEN {
+            return Err(MailError::BodyTooLong);
+        }
+        if draft.attachments.len() > MAX_ATTACHMENTS {
+            return Err(MailError::TooManyAttachments);
+        }
+        let recipient = self.find_character(&draft.recipient)?;
+        if recipient == sender {
+            return Err(MailError::SelfRecipient);
+        }
+        if self.mailbox_size(recipient) >= MAX_MAILBOX_SIZE {
+            return Err(MailError::MailboxFull);
+        }
+        Ok(recipient)
+    }
+
+    /// Delivers a mail that passed [`Self::check_draft`]
+    pub fn send(
+        &mut self,
+        sender: CharacterId,
+        recipient: CharacterId,
+        draft: MailDraft,
+        attachments: Vec<comp::Item>,
+        now: i64,
+    ) -> MailId {
+        let id = MailId(self.next_mail_id);
+        self.next_mail_id += 1;
+        let sender_name = self.character_name(sender).unwrap_or_default().to_owned();
+        self.insert(MailRow {
+            id,
+            sender: Some(sender),
+            sender_name,
+            recipient,
+            subject: Content::Plain(draft.subject),
+            body: Content::Plain(draft.body),
+            coins: draft.coins,
+            attachments,
+            sent_at: now,
+            expires_at: now + MAIL_LIFETIME_SECS,
+            read: false,
+            returned: false,
+        });
+        id
+    }
+
+    /// Delivers a mail sent by the server rather than by a character, even if
+    /// the mailbox of the recipient is full. It can't be returned, so it only
+    /// expires once it carries no coins or items.
+    #[expect(clippy::too_many_arguments)]
+    pub fn deliver(
+        &mut self,
+        sender_name: &str,
+        recipient: CharacterId,
+        subject: Content,
+        body: Content,
+        coins: u32,
+        attachments: Vec<comp::Item>,
+        now: i64,
+    ) -> MailId {
+        let id = MailId(self.next_mail_id);
+        self.next_mail_id += 1;
+        let expires_at = if coins > 0 || !attachments.is_empty() {
+            NEVER_EXPIRES
+        } else {
+            now + MAIL_LIFETIME_SECS
+        };
+        self.insert(MailRow {
+            id,
+            sender: None,
+            sender_name: sender_name.to_owned(),
+            recipient,
+            subject,
+            body,
+            coins,
+            attachments,
+            sent_at: now,
+            expires_at,
+            read: false,
+            returned: false,
+        });
+        id
+    }
+
+    fn insert(&mut self, mail: MailRow) {
+        self.dirty.insert(mail.id);
+        self.mail.insert(mail.id, mail);
+    }
+
+    /// A mail in the mailbox of the character
+    pub fn get(&self, character_id: CharacterId, id: MailId) -> Result<&MailRow, MailError> {
+        self.mail
+            .get(&id)
+            .filter(|mail| mail.recipient == character_id)
+            .ok_or(MailError::NotFound)
+    }
+
+    fn get_mut(
+        &mut self,
+        character_id: CharacterId,
+        id: MailId,
+    ) -> Result<&mut MailRow, MailError> {
+        let mail = self
+            .mail
+            .get_mut(&id)
+            .filter(|mail| mail.recipient == character_id)
+            .ok_or(MailError::NotFound)?;
+        self.dirty.insert(id);
+        Ok(mail)
+    }
+
+    pub fn mark_read(&mut self, character_id: CharacterId, id: MailId) -> Result<(), MailError> {
+        let mail = self.get_mut(character_id, id)?;
+        mail.read = true;
+        Ok(())
+    }
+
+    /// Moves the coins and items of the mail to `give`, which returns whatever
+    /// it couldn't take. That stays attached to the mail. Mail that was kept
+    /// for its attachments expires from now on once everything was taken.
+    ///
+    /// Returns whether everything was taken.
+    pub fn take_attachments(
+        &mut self,
+        character_id: CharacterId,
+        id: MailId,
+        now: i64,
+        give: impl FnOnce(u32, Vec<comp::Item>) -> (u32, Vec<comp::Item>),
+    ) -> Result<bool, MailError> {
+        let mail = self.get_mut(character_id, id)?;
+        let (coins, attachments) = give(
+            std::mem::take(&mut mail.coins),
+            std::mem::take(&mut mail.attachments),
+        );
+        let complete = coins == 0 && attachments.is_empty();
+        mail.coins = coins;
+        mail.attachments = attachments;
+        mail.read = true;
+        if complete && mail.expires_at == NEVER_EXPIRES {
+            mail.expires_at = now + MAIL_LIFETIME_SECS;
+        }
+        Ok(complete)
+    }
+
+    /// Sends the mail back to its sender, who can keep it for another
+    /// [`MAIL_LIFETIME_SECS`].
+    ///
+    /// Returns the sender.
+    pub fn return_mail(
+        &mut self,
+        character_id: CharacterId,
+        id: MailId,
+        now: i64,
+    ) -> Result<CharacterId, MailError> {
+        let name = self
+            .characters
+            .get(&character_id)
+            .map(|character| character.name.clone());
+        let mail = self.get_mut(character_id, id)?;
+        let sender = match mail.sender {
+            Some(sender) if !mail.returned => sender,
+            _ => return Err(MailError::CannotReturn),
+        };
+        mail.sender = Some(character_id);
+        if let Some(name) = name {
+            mail.sender_name = name;
+        }
+        mail.recipient = sender;
+        mail.expires_at = now + MAIL_LIFETIME_SECS;
+        mail.read = false;
+        mail.returned = true;
+        Ok(sender)
+    }
+
+    pub fn delete(&mut self, character_id: CharacterId, id: MailId) -> Result<(), MailError> {
+        let mail = self.get(character_id, id)?;
+        if mail.coins > 0 || !mail.attachments.is_empty() {
+            return Err(MailError::HasAttachments);
+        }
+        self.delete_row(id);
+        Ok(())
+    }
+
+    fn delete_row(&mut self, id: MailId) {
+        self.mail.remove(&id);
+        self.dirty.insert(id);
+    }
+
+    /// Returns expired mail to its sender. Mail that was already returned, or
+    /// whose sender was deleted, is deleted together with its attachments.
+    ///
+    /// Returns the characters whose mailbox changed.
+    pub fn expire(&mut self, now: i64) -> BTreeSet<CharacterId> {
+        let expired = self
+            .mail
+            .values()
+            .filter(|mail| mail.expires_at <= now)
+            .map(|mail| (mail.id, mail.recipient))
+            .collect::<Vec<_>>();
+        let mut changed = BTreeSet::new();
+        for (id, recipient) in expired {
+            changed.insert(recipient);
+            match self.return_mail(recipient, id, now) {
+                Ok(sender) => {
+                    changed.insert(sender);
+                },
+                Err(_) => self.delete_row(id),
+            }
+        }
+        changed
+    }
+
+    /// Takes all modifications made since the last call, to be persisted
+    pub fn take_changes(&mut self) -> Vec<MailChange> {
+        std::mem::take(&mut self.dirty)
+            .into_iter()
+            .map(|id| match self.mail.get(&id) {
+                Some(mail) => MailChange::Upsert(Box::new(mail.clone())),
+                None => MailChange::Delete(id),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn draft(recipient: &str) -> MailDraft {
+        MailDraft {
+            recipient: recipient.to_owned(),
+            subject: "Hello".to_owned(),
+            body: String::new(),
+            coins: 10,
+            attachments: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn send_return_and_expire() {
+        let mut manager = MailManager::new(MailRows {
+            mail: Vec::new(),
+            characters: vec![
+                (CharacterId(1), "Alice".to_owned(), "a".to_owned()),
+                (CharacterId(2), "Bob".to_owned(), "b".to_owned()),
+                (CharacterId(3), "bob".to_owned(), "c".to_owned()),
+            ],
+        });
+        assert_eq!(
+            manager.check_draft(CharacterId(1), &draft("BOB")),
+            Err(MailError::AmbiguousRecipient)
+        );
+        assert_eq!(
+            manager.check_draft(CharacterId(1), &draft("alice")),
+            Err(MailError::SelfRecipient)
+        );
+        manager.character_logged_in(CharacterId(3), "Carol", "c");
+        let recipient = manager.check_draft(CharacterId(1), &draft("bob")).unwrap();
+        let id = manager.send(CharacterId(1), recipient, draft("bob"), Vec::new(), 0);
+        assert_eq!(manager.unread(CharacterId(2)), 1);
+        assert_eq!(manager.take_changes().len(), 1);
+
+        // Mail with coins can't be deleted, but returned
+        assert_eq!(
+            manager.delete(CharacterId(2), id),
+            Err(MailError::HasAttachments)
+        );
+        assert_eq!(
+            manager.return_mail(CharacterId(2), id, 1),
+            Ok(CharacterId(1))
+        );
+        assert!(manager.mailbox(CharacterId(2)).is_empty());
+        assert_eq!(manager.mailbox(CharacterId(1))[0].sender, "Bob");
+
+        // Whatever doesn't fit into the inventory stays attached
+        assert_eq!(
+            manager.take_attachments(CharacterId(1), id, 1, |coins, items| (coins - 6, items)),
+            Ok(false)
+        );
+        assert_eq!(manager.mailbox(CharacterId(1))[0].coins, 4);
+        assert_eq!(
+            manager.take_attachments(CharacterId(1), id, 1, |_, items| (0, items)),
+            Ok(true)
+        );
+
+        // Returned mail isn't returned again once it expires
+        assert!(manager.expire(MAIL_LIFETIME_SECS).is_empty());
+        manager.expire(MAIL_LIFETIME_SECS + 1);
+        assert!(manager.mailbox(CharacterId(1)).is_empty());
+        assert!(matches!(manager.take_changes()[..], [MailChange::Delete(
+            deleted
+        )] if deleted == id));
+    }
+
+    #[test]
+    fn server_mail_with_attachments_is_kept() {
+        let mut manager = MailManager::new(MailRows::default());
+        let sold = manager.deliver(
+            "Market",
+            CharacterId(1),
+            Content::dummy(),
+            Content::dummy(),
+            10,
+            Vec::new(),
+            0,
+        );
+        let notice = manager.deliver(
+            "Market",
+            CharacterId(1),
+            Content::dummy(),
+            Content::dummy(),
+            0,
+            Vec::new(),
+            0,
+        );
+        manager.expire(MAIL_LIFETIME_SECS + 1);
+        assert!(manager.get(CharacterId(1), notice).is_err());
+        assert!(manager.get(CharacterId(1), sold).is_ok());
+
+        // Once the coins are taken it expires like any other mail
+        let now = 2 * MAIL_LIFETIME_SECS;
+        assert_eq!(
+            manager.take_attachments(CharacterId(1), sold, now, |_, items| (0, items)),
+            Ok(true)
+        );
+        manager.expire(now + MAIL_LIFETIME_SECS + 1);
+        assert!(manager.mailbox(CharacterId(1)).is_empty());
+    }
+}
@@ -0,0 +1,342 @@
+//! Player market
+//!
+//! All listings of the server are kept in the [`MarketManager`], so items can
+//! be bought while their seller is offline. Characters list and buy items at
+//! market boards, see [`comp::market`]. The coins of a sale, and the items of
+//! expired listings, are delivered to the seller by mail.
+//!
+//! A listing is removed as soon as it is bought, and the changes of the market
+//! are written in the same transaction as the inventories of the characters,
+//! so an item can neither be bought twice nor duplicated.
+
+use crate::persistence::market::{ListingChange, ListingRow, ListingRows};
+use common::{
+    character::CharacterId,
+    comp::{
+        self, Content,
+        market::{LISTING_LIFETIME_SECS, Listing, ListingId, MAX_LISTINGS, MAX_PRICE, Market},
+    },
+    trade::SitePrices,
+};
+use hashbrown::HashMap;
+use std::collections::{BTreeMap, BTreeSet};
+use vek::*;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum MarketError {
+    TooManyListings,
+    /// The price is zero or above [`MAX_PRICE`]
+    InvalidPrice,
+    CannotSellCoins,
+    NotEnoughCoins,
+    InventoryFull,
+    /// Characters can't buy their own listings
+    OwnListing,
+    /// The database is shared with other servers, see
+    /// [`MarketManager::disabled`]
+    Disabled,
+    NotFound,
+}
+
+impl From<MarketError> for Content {
+    fn from(error: MarketError) -> Self {
+        Content::localized(match error {
+            MarketError::TooManyListings => "hud-market-error-too_many_listings",
+            MarketError::InvalidPrice => "hud-market-error-invalid_price",
+            MarketError::CannotSellCoins => "hud-market-error-cannot_sell_coins",
+            MarketError::NotEnoughCoins => "hud-market-error-not_enough_coins",
+            MarketError::InventoryFull => "hud-market-error-inventory_full",
+            MarketError::OwnListing => "hud-market-error-own_listing",
+            MarketError::Disabled => "hud-market-error-disabled",
+            MarketError::NotFound => "hud-market-error-not_found",
+        })
+    }
+}
+
+/// All listings of the server, changes are persisted together with the
+/// characters by the
+/// [`CharacterUpdater`](crate::persistence::character_updater::CharacterUpdater).
+pub struct MarketManager {
+    listings: BTreeMap<ListingId, ListingRow>,
+    next_listing_id: u64,
+    /// Listings posted or removed since the last call to
+    /// [`Self::take_changes`]
+    dirty: BTreeSet<ListingId>,
+    /// Position of the market board each character has opened, and the
+    /// prices of the site it is in
+    open: HashMap<CharacterId, (Vec3<i32>, Option<SitePrices>)>,
+    enabled: bool,
+}
+
+impl MarketManager {
+    pub fn new(rows: ListingRows) -> Self {
+        let next_listing_id = rows
+            .listings
+            .iter()
+            .map(|listing| listing.id)
+            .chain(rows.invalid.iter().copied())
+            .max()
+            .map_or(1, |id| id.0 + 1);
+
+        Self {
+            listings: rows
+                .listings
+                .into_iter()
+                .map(|listing| (listing.id, listing))
+                .collect(),
+            next_listing_id,
+            dirty: rows.invalid.into_iter().collect(),
+            open: HashMap::new(),
+            enabled: true,
+        }
+    }
+
+    /// A manager without any listings that doesn't let characters open market
+    /// boards. Listings only exist in the memory of a single server, so on
+    /// servers sharing their database they could be bought on several of them.
+    pub fn disabled() -> Self {
+        Self {
+            enabled: false,
+            ..Self::new(ListingRows::default())
+        }
+    }
+
+    pub fn open(
+        &mut self,
+        character_id: CharacterId,
+        pos: Vec3<i32>,
+        prices: Option<SitePrices>,
+    ) -> Result<(), MarketError> {
+        if !self.enabled {
+            return Err(MarketError::Disabled);
+        }
+        self.open.insert(character_id, (pos, prices));
+        Ok(())
+    }
+
+    pub fn close(&mut self, character_id: CharacterId) { self.open.remove(&character_id); }
+
+    /// Position of the market board the character has opened
+    pub fn opened_at(&self, character_id: CharacterId) -> Option<Vec3<i32>> {
+        self.open.get(&character_id).map(|(pos, _)| *pos)
+    }
+
+    /// Characters that have a market board open
+    pub fn viewers(&self) -> impl Iterator<Item = CharacterId> + '_ { self.open.keys().copied() }
+
+    /// The market as seen by the character at the market board it has open
+    pub fn market(&self, character_id: CharacterId) -> Option<(Vec3<i32>, Market)> {
+        let (pos, prices) = self.open.get(&character_id)?;
+        let (mut own_listings, mut listings): (Vec<_>, Vec<_>) = self
+            .listings
+            .values()
+            .map(|listing| {
+                (listing.seller == character_id, Listing {
+                    id: listing.id,
+                    seller: listing.seller_name.clone(),
+                    item: listing.item.clone(),
+                    price: listing.price,
+                    expires_at: listing.expires_at,
+                })
+            })
+            .partition(|(own, _)| *own);
+        // Listing ids increase, so this puts the newest listings first
+        listings.reverse();
+        own_listings.reverse();
+
+        Some((*pos, Market {
+            listings: listings.into_iter().map(|(_, listing)| listing).collect(),
+            own_listings: own_listings
+                .into_iter()
+                .map(|(_, listing)| listing)
+                .collect(),
+            prices: prices.clone(),
+        }))
+    }
+
+    /// Checks whether the character can list an item at this price, before
+    /// the item is taken from it
+    pub fn check_post(&self, seller: CharacterId, price: u32) -> Result<(), MarketError> {
+        if price == 0 || price > MAX_PRICE {
+            return Err(MarketError::InvalidPrice);
+        }
+        if self
+            .listings
+            .values()
+            .filter(|listing| listing.seller == seller)
+            .count()
+            >= MAX_LISTINGS
+        {
+            return Err(MarketError::TooManyListings);
+        }
+        Ok(())
+    }
+
+    /// Lists an item that passed [`Self::check_post`]
+    pub fn post(
+        &mut self,
+        seller: CharacterId,
+        seller_name: &str,
+        item: comp::Item,
+        price: u32,
+        now: i64,
+    ) -> ListingId {
+        let id = ListingId(self.next_listing_id);
+        self.next_listing_id += 1;
+        self.listings.insert(id, ListingRow {
+            id,
+            seller,
+            seller_name: seller_name.to_owned(),
+            item,
+            price,
+            listed_at: now,
+            expires_at: now + LISTING_LIFETIME_SECS,
+        });
+        self.dirty.insert(id);
+        id
+    }
+
+    /// Removes a listing the character wants to buy, the caller has to move
+    /// its item to the buyer or [put it back](Self::put_back)
+    pub fn take_for_sale(
+        &mut self,
+        buyer: CharacterId,
+        id: ListingId,
+    ) -> Result<ListingRow, MarketError> {
+        let listing = self.listings.get(&id).ok_or(MarketError::NotFound)?;
+        if listing.seller == buyer {
+            return Err(MarketError::OwnListing);
+        }
+        self.remove(id).ok_or(MarketError::NotFound)
+    }
+
+    /// Removes a listing of the character itself, the caller has to move its
+    /// item back to the seller or [put it back](Self::put_back)
+    pub fn take_own(
+        &mut self,
+        seller: CharacterId,
+        id: ListingId,
+    ) -> Result<ListingRow, MarketError> {
+        if self
+            .listings
+            .get(&id)
+            .is_none_or(|listing| listing.seller != seller)
+        {
+            return Err(MarketError::NotFound);
+        }
+        self.remove(id).ok_or(MarketError::NotFound)
+    }
+
+    /// Lists a removed listing again, when its item couldn't be handed out
+    pub fn put_back(&mut self, listing: ListingRow) {
+        self.dirty.insert(listing.id);
+        self.listings.insert(listing.id, listing);
+    }
+
+    /// Removes a listing once it was bought or taken down, the caller has to
+    /// hand out its item
+    fn remove(&mut self, id: ListingId) -> Option<ListingRow> {
+        let listing = self.listings.remove(&id)?;
+        self.dirty.insert(id);
+        Some(listing)
+    }
+
+    /// Takes down all listings of a character that is being deleted.
+    ///
+    /// Returns whether the character had any listings.
+    pub fn remove_seller(&mut self, character_id: CharacterId) -> bool {
+        self.open.remove(&character_id);
+        let listings = self
+            .listings
+            .values()
+            .filter(|listing| listing.seller == character_id)
+            .map(|listing| listing.id)
+            .collect::<Vec<_>>();
+        for id in &listings {
+            self.remove(*id);
+        }
+        !listings.is_empty()
+    }
+
+    /// Removes the listings that expired, the caller has to send their items
+    /// back to the sellers
+    pub fn expire(&mut self, now: i64) -> Vec<ListingRow> {
+        let expired = self
+            .listings
+            .values()
+            .filter(|listing| listing.expires_at <= now)
+            .map(|listing| listing.id)
+            .collect::<Vec<_>>();
+        expired
+            .into_iter()
+            .filter_map(|id| self.remove(id))
+            .collect()
+    }
+
+    /// Takes all modifications made since the last call, to be persisted
+    pub fn take_changes(&mut self) -> Vec<ListingChange> {
+        std::mem::take(&mut self.dirty)
+            .into_iter()
+            .map(|id| match self.listings.get(&id) {
+                Some(listing) => ListingChange::Insert(Box::new(listing.clone())),
+                None => ListingChange::Delete(id),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item() -> comp::Item { comp::Item::new_from_asset_expect("common.items.food.apple") }
+
+    #[test]
+    fn post_buy_and_expire() {
+        let mut manager = MarketManager::new(ListingRows {
+            listings: Vec::new(),
+            invalid: vec![ListingId(4)],
+        });
+        let (alice, bob) = (CharacterId(1), CharacterId(2));
+        assert_eq!(
+            manager.check_post(alice, MAX_PRICE + 1),
+            Err(MarketError::InvalidPrice)
+        );
+        assert_eq!(manager.check_post(alice, 10), Ok(()));
+        let sold = manager.post(alice, "Alice", item(), 10, 0);
+        let unsold = manager.post(alice, "Alice", item(), 20, 1);
+        // Ids of listings that couldn't be loaded aren't reused
+        assert_eq!(sold, ListingId(5));
+
+        manager.open(bob, Vec3::zero(), None).unwrap();
+        let (_, market) = manager.market(bob).unwrap();
+        assert_eq!(market.listings[0].id, unsold);
+        assert!(market.own_listings.is_empty());
+
+        assert!(matches!(
+            manager.take_for_sale(alice, sold),
+            Err(MarketError::OwnListing)
+        ));
+        assert!(matches!(
+            manager.take_own(bob, sold),
+            Err(MarketError::NotFound)
+        ));
+        // A listing whose item couldn't be handed out is listed again
+        let listing = manager.take_for_sale(bob, sold).unwrap();
+        assert_eq!(listing.price, 10);
+        manager.put_back(listing);
+        assert_eq!(manager.take_for_sale(bob, sold).unwrap().id, sold);
+        assert!(matches!(
+            manager.take_for_sale(bob, sold),
+            Err(MarketError::NotFound)
+        ));
+        // The invalid and the sold listing are deleted, the other one is inserted
+        assert_eq!(manager.take_changes().len(), 3);
+
+        assert!(manager.expire(LISTING_LIFETIME_SECS).is_empty());
+        let expired = manager.expire(LISTING_LIFETIME_SECS + 1);
+        assert_eq!(expired[0].id, unsold);
+        let changes = manager.take_changes();
+        assert!(matches!(changes[..], [ListingChange::Delete(id)] if id == unsold));
+    }
+}
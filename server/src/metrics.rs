@@ -36,6 +36,7 @@ pub struct NetworkRequestMetrics {
     pub chunks_generation_triggered: IntCounter,
     pub chunks_served_lossy: IntCounter,
     pub chunks_served_lossless: IntCounter,
+    pub chunks_served_delta: IntCounter,
     pub chunks_serialisation_requests: IntCounter,
     pub chunks_distinct_serialisation_requests: IntCounter,
 }
@@ -82,6 +83,7 @@ pub struct QueryServerMetrics {
     pub proccessing_errors: IntCounter,
     pub info_requests: IntCounter,
     pub init_requests: IntCounter,
+    pub details_requests: IntCounter,
     pub sent_responses: IntCounter,
     pub failed_responses: IntCounter,
     pub timed_out_responses: IntCounter,
@@ -228,6 +230,10 @@ impl NetworkRequestMetrics {
             "chunks_served_lossless",
             "number of chunks that were sent with lossless compression requested",
         ))?;
+        let chunks_served_delta = IntCounter::with_opts(Opts::new(
+            "chunks_served_delta",
+            "number of chunks that were sent as a delta against a version the client already had",
+        ))?;
         let chunks_serialisation_requests = IntCounter::with_opts(Opts::new(
             "chunks_serialisation_requests",
             "number of requests for the sys chunk_serialisation",
@@ -242,6 +248,7 @@ impl NetworkRequestMetrics {
         registry.register(Box::new(chunks_generation_triggered.clone()))?;
         registry.register(Box::new(chunks_served_lossy.clone()))?;
         registry.register(Box::new(chunks_served_lossless.clone()))?;
+        registry.register(Box::new(chunks_served_delta.clone()))?;
         registry.register(Box::new(chunks_serialisation_requests.clone()))?;
         registry.register(Box::new(chunks_distinct_serialisation_requests.clone()))?;
 
@@ -251,6 +258,7 @@ impl NetworkRequestMetrics {
             chunks_generation_triggered,
             chunks_served_lossy,
             chunks_served_lossless,
+            chunks_served_delta,
             chunks_serialisation_requests,
             chunks_distinct_serialisation_requests,
         })
@@ -499,6 +507,10 @@ impl QueryServerMetrics {
             "query_server::ping_requests",
             "Amount of init requests received by the query server",
         ))?;
+        let details_requests = IntCounter::with_opts(Opts::new(
+            "query_server::details_requests",
+            "Amount of world info, player list and plugin requests received by the query server",
+        ))?;
         let sent_responses = IntCounter::with_opts(Opts::new(
             "query_server::sent_responses",
             "Amount of responses sent by the query server",
@@ -522,6 +534,7 @@ impl QueryServerMetrics {
         registry.register(Box::new(proccessing_errors.clone()))?;
         registry.register(Box::new(info_requests.clone()))?;
         registry.register(Box::new(init_requests.clone()))?;
+        registry.register(Box::new(details_requests.clone()))?;
         registry.register(Box::new(sent_responses.clone()))?;
         registry.register(Box::new(failed_responses.clone()))?;
         registry.register(Box::new(timed_out_responses.clone()))?;
@@ -534,6 +547,7 @@ impl QueryServerMetrics {
             proccessing_errors,
             info_requests,
             init_requests,
+            details_requests,
             sent_responses,
             failed_responses,
             timed_out_responses,
@@ -550,6 +564,7 @@ impl QueryServerMetrics {
             proccessing_errors,
             info_requests,
             init_requests,
+            details_requests,
             sent_responses,
             failed_responses,
             timed_out_responses,
@@ -562,6 +577,7 @@ impl QueryServerMetrics {
         self.proccessing_errors.inc_by(proccessing_errors as u64);
         self.info_requests.inc_by(info_requests as u64);
         self.init_requests.inc_by(init_requests as u64);
+        self.details_requests.inc_by(details_requests as u64);
         self.sent_responses.inc_by(sent_responses as u64);
         self.failed_responses.inc_by(failed_responses as u64);
         self.timed_out_responses.inc_by(timed_out_responses as u64);
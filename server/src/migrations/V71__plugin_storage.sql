@@ -0,0 +1,7 @@
+-- Creates the key-value storage of server plugins
+CREATE TABLE "plugin_storage" (
+      "plugin" TEXT NOT NULL,
+      "key" TEXT NOT NULL,
+      "value" BLOB NOT NULL,
+      PRIMARY KEY("plugin", "key")
+);
@@ -0,0 +1,5 @@
+-- Stores the reputation of characters with rtsim sites and factions
+CREATE TABLE "character_reputation" (
+      "character_id" INTEGER NOT NULL PRIMARY KEY REFERENCES "character"("character_id"),
+      "reputation" TEXT NOT NULL
+);
@@ -0,0 +1,13 @@
+-- Player guilds and the characters that are members of them
+CREATE TABLE "guild" (
+      "guild_id" INTEGER NOT NULL PRIMARY KEY,
+      "name" TEXT NOT NULL UNIQUE
+);
+
+CREATE TABLE "guild_member" (
+      "character_id" INTEGER NOT NULL PRIMARY KEY REFERENCES "character"("character_id"),
+      "guild_id" INTEGER NOT NULL REFERENCES "guild"("guild_id"),
+      "rank" TEXT NOT NULL
+);
+
+CREATE INDEX idx_guild_member_guild_id ON "guild_member"("guild_id");
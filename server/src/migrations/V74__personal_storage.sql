@@ -0,0 +1,33 @@
+-- Adds the personal storage pseudo container to every existing character
+CREATE TEMP TABLE _temp_character_storage_pairings
+(
+    temp_storage_container_id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    character_id INT NOT NULL,
+    storage_container_id INT
+);
+
+INSERT
+INTO _temp_character_storage_pairings
+SELECT	NULL,
+        i.item_id,
+        NULL
+FROM item i
+WHERE i.item_definition_id = 'veloren.core.pseudo_containers.character';
+
+UPDATE _temp_character_storage_pairings
+SET storage_container_id = ((SELECT MAX(entity_id) FROM entity) + temp_storage_container_id);
+
+INSERT
+INTO entity
+SELECT t.storage_container_id
+FROM _temp_character_storage_pairings t;
+
+INSERT
+INTO item
+SELECT	t.storage_container_id,
+        t.character_id,
+        'veloren.core.pseudo_containers.storage',
+        1,
+        'storage',
+        ''
+FROM _temp_character_storage_pairings t;
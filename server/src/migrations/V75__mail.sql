@@ -0,0 +1,17 @@
+-- Mail sent between characters, attachments are stored as JSON
+CREATE TABLE "mail" (
+      "mail_id" INTEGER NOT NULL PRIMARY KEY,
+      "sender_id" INTEGER REFERENCES "character"("character_id"),
+      "sender_name" TEXT NOT NULL,
+      "recipient_id" INTEGER NOT NULL REFERENCES "character"("character_id"),
+      "subject" TEXT NOT NULL,
+      "body" TEXT NOT NULL,
+      "coins" INTEGER NOT NULL,
+      "attachments" TEXT NOT NULL,
+      "sent_at" INTEGER NOT NULL,
+      "expires_at" INTEGER NOT NULL,
+      "is_read" INTEGER NOT NULL,
+      "is_returned" INTEGER NOT NULL
+);
+
+CREATE INDEX idx_mail_recipient_id ON "mail"("recipient_id");
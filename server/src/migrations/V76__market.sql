@@ -0,0 +1,11 @@
+-- Items listed for sale on the player market, the item is stored as JSON
+CREATE TABLE "market_listing" (
+      "listing_id" INTEGER NOT NULL PRIMARY KEY,
+      "seller_id" INTEGER NOT NULL REFERENCES "character"("character_id"),
+      "item" TEXT NOT NULL,
+      "price" INTEGER NOT NULL,
+      "listed_at" INTEGER NOT NULL,
+      "expires_at" INTEGER NOT NULL
+);
+
+CREATE INDEX idx_market_listing_seller_id ON "market_listing"("seller_id");
@@ -0,0 +1,7 @@
+-- Signatures of the character exports that have been imported, so the same
+-- export can't be imported twice. Rows are kept when the character is deleted.
+CREATE TABLE "character_import" (
+      "export_signature" TEXT NOT NULL PRIMARY KEY,
+      "character_id" INTEGER NOT NULL,
+      "imported_at" INTEGER NOT NULL
+);
@@ -0,0 +1,5 @@
+-- The subject and body of mail are stored as JSON, so mail sent by the server
+-- can be localised by the client. Mail written by characters is plain text.
+UPDATE "mail"
+SET     "subject" = json_object('Plain', "subject"),
+        "body" = json_object('Plain', "body");
@@ -0,0 +1,113 @@
+-- The schema of the SQLite database as of V72, from which on the migrations of
+-- both databases share their version numbers. Every later migration has to be
+-- added to both `migrations` and `migrations_postgres`.
+--
+-- Integers are BIGINT throughout since SQLite integers are 64 bit, and the
+-- foreign keys are deferrable since updates rely on them being checked at the
+-- end of the transaction.
+
+CREATE TABLE entity
+(
+    entity_id BIGINT NOT NULL PRIMARY KEY
+);
+
+CREATE TABLE item
+(
+    item_id                  BIGINT NOT NULL
+        PRIMARY KEY
+        REFERENCES entity(entity_id) DEFERRABLE,
+    parent_container_item_id BIGINT NOT NULL
+        REFERENCES item(item_id) DEFERRABLE,
+    item_definition_id       TEXT NOT NULL,
+    stack_size               BIGINT NOT NULL,
+    position                 TEXT NOT NULL,
+    properties               TEXT NOT NULL DEFAULT '{}',
+    -- Items swapping places can violate this temporarily, which SQLite avoids
+    -- by replacing the conflicting row
+    CONSTRAINT idx_parent_container_item_id_position
+        UNIQUE (parent_container_item_id, position) DEFERRABLE
+);
+
+CREATE INDEX idx_item_definition_id
+    ON item(item_definition_id);
+
+CREATE TABLE body
+(
+    body_id   BIGINT NOT NULL
+        PRIMARY KEY
+        REFERENCES entity(entity_id) DEFERRABLE,
+    variant   TEXT NOT NULL,
+    body_data TEXT NOT NULL
+);
+
+CREATE TABLE character
+(
+    character_id BIGINT NOT NULL
+        PRIMARY KEY
+        REFERENCES body(body_id) DEFERRABLE
+        REFERENCES item(item_id) DEFERRABLE,
+    player_uuid  TEXT NOT NULL,
+    alias        TEXT NOT NULL,
+    waypoint     TEXT,
+    hardcore     BIGINT NOT NULL DEFAULT 0
+);
+
+CREATE INDEX idx_character_player_uuid
+    ON character(player_uuid);
+
+CREATE TABLE pet
+(
+    pet_id       BIGINT NOT NULL
+        PRIMARY KEY
+        REFERENCES entity(entity_id) DEFERRABLE,
+    character_id BIGINT NOT NULL
+        REFERENCES character(character_id) DEFERRABLE,
+    name         TEXT NOT NULL
+);
+
+CREATE TABLE skill_group
+(
+    entity_id        BIGINT NOT NULL
+        REFERENCES entity(entity_id) DEFERRABLE,
+    skill_group_kind TEXT NOT NULL,
+    earned_exp       BIGINT NOT NULL,
+    spent_exp        BIGINT NOT NULL,
+    skills           TEXT NOT NULL,
+    hash_val         BYTEA NOT NULL,
+    PRIMARY KEY (entity_id, skill_group_kind)
+);
+
+CREATE TABLE ability_set
+(
+    entity_id    BIGINT NOT NULL
+        PRIMARY KEY
+        REFERENCES character(character_id) DEFERRABLE,
+    ability_sets TEXT NOT NULL
+);
+
+CREATE TABLE plugin_storage
+(
+    plugin TEXT NOT NULL,
+    key    TEXT NOT NULL,
+    value  BYTEA NOT NULL,
+    PRIMARY KEY (plugin, key)
+);
+
+CREATE TABLE character_reputation
+(
+    character_id BIGINT NOT NULL
+        PRIMARY KEY
+        REFERENCES character(character_id) DEFERRABLE,
+    reputation   TEXT NOT NULL
+);
+
+-- The world pseudo-container, every character's items are stored below it.
+-- This must be entity_id 1 as this is referred to in code.
+INSERT INTO entity (entity_id) VALUES (1);
+
+INSERT INTO item (item_id,
+                  parent_container_item_id,
+                  item_definition_id,
+                  stack_size,
+                  position)
+VALUES (1, 1, 'veloren.core.pseudo_containers.world', 1, 'world');
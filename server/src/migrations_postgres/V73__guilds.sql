@@ -0,0 +1,20 @@
+-- Player guilds and the characters that are members of them
+
+CREATE TABLE guild
+(
+    guild_id BIGINT NOT NULL PRIMARY KEY,
+    name     TEXT NOT NULL UNIQUE
+);
+
+CREATE TABLE guild_member
+(
+    character_id BIGINT NOT NULL
+        PRIMARY KEY
+        REFERENCES character(character_id) DEFERRABLE,
+    guild_id     BIGINT NOT NULL
+        REFERENCES guild(guild_id) DEFERRABLE,
+    rank         TEXT NOT NULL
+);
+
+CREATE INDEX idx_guild_member_guild_id
+    ON guild_member(guild_id);
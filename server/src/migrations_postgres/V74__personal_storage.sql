@@ -0,0 +1,26 @@
+-- Adds the personal storage pseudo container to every existing character
+
+CREATE TEMP TABLE _temp_character_storage_pairings
+AS
+SELECT  (SELECT COALESCE(MAX(entity_id), 0) FROM entity)
+            + ROW_NUMBER() OVER (ORDER BY i.item_id) AS storage_container_id,
+        i.item_id AS character_id
+FROM    item i
+WHERE   i.item_definition_id = 'veloren.core.pseudo_containers.character';
+
+INSERT
+INTO    entity
+SELECT  t.storage_container_id
+FROM    _temp_character_storage_pairings t;
+
+INSERT
+INTO    item
+SELECT  t.storage_container_id,
+        t.character_id,
+        'veloren.core.pseudo_containers.storage',
+        1,
+        'storage',
+        ''
+FROM    _temp_character_storage_pairings t;
+
+DROP TABLE _temp_character_storage_pairings;
@@ -0,0 +1,22 @@
+-- Mail sent between characters, attachments are stored as JSON
+
+CREATE TABLE mail
+(
+    mail_id      BIGINT NOT NULL PRIMARY KEY,
+    sender_id    BIGINT
+        REFERENCES character(character_id) DEFERRABLE,
+    sender_name  TEXT NOT NULL,
+    recipient_id BIGINT NOT NULL
+        REFERENCES character(character_id) DEFERRABLE,
+    subject      TEXT NOT NULL,
+    body         TEXT NOT NULL,
+    coins        BIGINT NOT NULL,
+    attachments  TEXT NOT NULL,
+    sent_at      BIGINT NOT NULL,
+    expires_at   BIGINT NOT NULL,
+    is_read      BIGINT NOT NULL,
+    is_returned  BIGINT NOT NULL
+);
+
+CREATE INDEX idx_mail_recipient_id
+    ON mail(recipient_id);
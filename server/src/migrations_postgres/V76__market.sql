@@ -0,0 +1,15 @@
+-- Items listed for sale on the player market, the item is stored as JSON
+
+CREATE TABLE market_listing
+(
+    listing_id BIGINT NOT NULL PRIMARY KEY,
+    seller_id  BIGINT NOT NULL
+        REFERENCES character(character_id) DEFERRABLE,
+    item       TEXT NOT NULL,
+    price      BIGINT NOT NULL,
+    listed_at  BIGINT NOT NULL,
+    expires_at BIGINT NOT NULL
+);
+
+CREATE INDEX idx_market_listing_seller_id
+    ON market_listing(seller_id);
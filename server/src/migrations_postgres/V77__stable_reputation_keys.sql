@@ -0,0 +1,5 @@
+-- Reputation used to be keyed by rtsim slotmap ids, which change whenever
+-- rtsim data is regenerated. It is now keyed by the position of sites and the
+-- seed of factions, the old rows can't be converted.
+
+DELETE FROM character_reputation;
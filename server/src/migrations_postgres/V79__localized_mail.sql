@@ -0,0 +1,6 @@
+-- The subject and body of mail are stored as JSON, so mail sent by the server
+-- can be localised by the client. Mail written by characters is plain text.
+
+UPDATE mail
+SET    subject = json_build_object('Plain', subject)::TEXT,
+       body = json_build_object('Plain', body)::TEXT;
@@ -0,0 +1,1087 @@
+//! The databases characters can be stored in, see [`DatabaseBackend`].
+//!
+//! The [`CharacterLoader`] and [`CharacterUpdater`] threads each open their own
+//! connection with [`connect`] and only talk to it through
+//! [`PersistenceBackend`], so they don't need to know which database is used.
+//!
+//! [`CharacterLoader`]: super::character_loader::CharacterLoader
+//! [`CharacterUpdater`]: super::character_updater::CharacterUpdater
+
+#[cfg(feature = "postgres")]
+use super::SqlLogMode;
+#[cfg(feature = "plugins")]
+use super::plugin_storage;
+use super::{
+    ConnectionMode, DatabaseBackend, DatabaseSettings, EditableComponents, PersistedComponents,
+    VelorenConnection,
+    character::{
+        self,
+        transfer::{CharacterExport, ImportedCharacter},
+    },
+    character_loader::{
+        CharacterCreationResult, CharacterDataResult, CharacterEditResult, CharacterListResult,
+    },
+    character_updater::DatabaseActionKind,
+    diesel_to_rusqlite,
+    error::PersistenceError,
+    establish_connection,
+    guild::{self, GuildChange, GuildRows},
+    mail::{self, MailRows},
+    market::{self, ListingRows},
+};
+use crate::backup::DatabaseCopy;
+use common::{character::CharacterId, event::PermanentChange};
+#[cfg(feature = "plugins")]
+use common_state::plugin::storage::PluginStorageChange;
+use refinery::Report;
+use rusqlite::DropBehavior;
+#[cfg(feature = "postgres")]
+use std::process::Command;
+use std::{
+    path::Path,
+    sync::{Arc, RwLock},
+};
+#[cfg(feature = "postgres")]
+use tracing::{debug, warn};
+use tracing::{info, trace};
+
+/// The operations of the persistence threads. Operations that write run in a
+/// single transaction, which is only committed if they succeed.
+pub(crate) trait PersistenceBackend {
+    /// Updates the SQL log mode if DatabaseSetting.sql_log_mode has changed
+    fn update_log_mode(&mut self, settings: &Arc<RwLock<DatabaseSettings>>);
+
+    /// Runs any pending schema migrations, panics if they fail
+    fn run_migrations(&mut self);
+
+    /// Reclaims the space of deleted rows, panics if it fails
+    fn vacuum(&mut self);
+
+    fn load_character_list(&mut self, player_uuid: &str) -> CharacterListResult;
+
+    fn load_character_data(
+        &mut self,
+        player_uuid: String,
+        character_id: CharacterId,
+    ) -> CharacterDataResult;
+
+    /// The outer error is returned if the transaction itself failed
+    fn create_character(
+        &mut self,
+        player_uuid: &str,
+        alias: &str,
+        persisted_components: PersistedComponents,
+    ) -> Result<CharacterCreationResult, PersistenceError>;
+
+    /// The outer error is returned if the transaction itself failed
+    fn edit_character(
+        &mut self,
+        player_uuid: &str,
+        character_id: CharacterId,
+        alias: Option<&str>,
+        editable_components: EditableComponents,
+        trusted_change: Option<PermanentChange>,
+    ) -> Result<CharacterEditResult, PersistenceError>;
+
+    fn batch_update(&mut self, updates: Vec<DatabaseActionKind>) -> Result<(), PersistenceError>;
+
+    #[cfg(feature = "plugins")]
+    fn load_plugin_storage(&mut self) -> Result<Vec<(String, String, Vec<u8>)>, PersistenceError>;
+
+    #[cfg(feature = "plugins")]
+    fn update_plugin_storage(
+        &mut self,
+        changes: Vec<PluginStorageChange>,
+    ) -> Result<(), PersistenceError>;
+
+    fn load_guilds(&mut self) -> Result<GuildRows, PersistenceError>;
+
+    fn update_guilds(&mut self, changes: Vec<GuildChange>) -> Result<(), PersistenceError>;
+
+    /// Mail is written by [`batch_update`](Self::batch_update), see
+    /// [`DatabaseActionKind::UpdateMail`]
+    fn load_mail(&mut self) -> Result<MailRows, PersistenceError>;
+
+    /// Listings are written by [`batch_update`](Self::batch_update), see
+    /// [`DatabaseActionKind::UpdateMarket`]
+    fn load_listings(&mut self) -> Result<ListingRows, PersistenceError>;
+
+    /// Copies the database to `path` for a [snapshot](crate::backup),
+    /// returning the format of the copy.
+    fn snapshot(&mut self, path: &Path) -> Result<DatabaseCopy, String>;
+
+    fn export_character(
+        &mut self,
+        player_uuid: &str,
+        character: &str,
+        server_name: &str,
+    ) -> Result<CharacterExport, PersistenceError>;
+
+    /// Rejects exports that have been imported before, see
+    /// [`character::transfer::import_character`]
+    fn import_character(
+        &mut self,
+        player_uuid: &str,
+        export: CharacterExport,
+        export_signature: &str,
+        alias: Option<String>,
+    ) -> Result<ImportedCharacter, PersistenceError>;
+}
+
+/// Connects to the database selected in the settings, panics if that fails.
+pub(crate) fn connect(
+    settings: &DatabaseSettings,
+    connection_mode: ConnectionMode,
+) -> Box<dyn PersistenceBackend> {
+    match &settings.backend {
+        DatabaseBackend::Sqlite => Box::new(SqliteBackend(establish_connection(
+            settings,
+            connection_mode,
+        ))),
+        #[cfg(feature = "postgres")]
+        DatabaseBackend::Postgres { url, .. } => {
+            Box::new(PostgresBackend::connect(url, settings, connection_mode))
+        },
+        #[cfg(not(feature = "postgres"))]
+        DatabaseBackend::Postgres { .. } => panic!(
+            "The server is configured to use PostgreSQL, but was compiled without the `postgres` \
+             feature"
+        ),
+    }
+}
+
+/// Key of the PostgreSQL advisory lock taken by [`lock`]
+#[cfg(feature = "postgres")]
+const DATABASE_LOCK_KEY: i64 = 0x5645_4c4f_5245_4e;
+
+/// Held for as long as the server runs, see [`lock`]
+pub struct DatabaseLock {
+    #[cfg(feature = "postgres")]
+    _client: Option<postgres::Client>,
+}
+
+/// Servers that aren't shared take an exclusive advisory lock on their own
+/// connection to PostgreSQL, and shared servers take it in shared mode. So a
+/// server that isn't shared can't run next to any other server.
+pub(crate) fn lock(settings: &DatabaseSettings) -> Result<DatabaseLock, PersistenceError> {
+    match &settings.backend {
+        DatabaseBackend::Sqlite => Ok(DatabaseLock {
+            #[cfg(feature = "postgres")]
+            _client: None,
+        }),
+        #[cfg(feature = "postgres")]
+        DatabaseBackend::Postgres { url, shared } => {
+            let mut client = postgres::Client::connect(url, postgres::NoTls)?;
+            let query = if *shared {
+                "SELECT pg_try_advisory_lock_shared($1)"
+            } else {
+                "SELECT pg_try_advisory_lock($1)"
+            };
+            let locked: bool = client.query_one(query, &[&DATABASE_LOCK_KEY])?.get(0);
+            if !locked {
+                return Err(PersistenceError::OtherError(
+                    if *shared {
+                        "The database is used by a server that isn't shared"
+                    } else {
+                        "The database is used by another server, all servers using it have to be \
+                         shared"
+                    }
+                    .to_owned(),
+                ));
+            }
+            Ok(DatabaseLock {
+                _client: Some(client),
+            })
+        },
+        // `connect` reports that PostgreSQL isn't available
+        #[cfg(not(feature = "postgres"))]
+        DatabaseBackend::Postgres { .. } => Ok(DatabaseLock {}),
+    }
+}
+
+pub(crate) struct SqliteBackend(VelorenConnection);
+
+impl PersistenceBackend for SqliteBackend {
+    fn update_log_mode(&mut self, settings: &Arc<RwLock<DatabaseSettings>>) {
+        self.0.update_log_mode(settings);
+    }
+
+    fn run_migrations(&mut self) {
+        diesel_to_rusqlite::migrate_from_diesel(&mut self.0)
+            .expect("One-time migration from Diesel to Refinery failed");
+
+        // If migrations fail to run, the server cannot start since the database will
+        // not be in the required state.
+        let report: Report = super::embedded::migrations::runner()
+            .set_abort_divergent(false)
+            .run(&mut self.0.connection)
+            .expect("Database migrations failed, server startup aborted");
+
+        let applied_migrations = report.applied_migrations().len();
+        info!("Applied {} database migrations", applied_migrations);
+    }
+
+    fn vacuum(&mut self) {
+        self.0
+            .execute("VACUUM main", [])
+            .expect("Database vacuuming failed, server startup aborted");
+
+        info!("Database vacuumed");
+    }
+
+    fn load_character_list(&mut self, player_uuid: &str) -> CharacterListResult {
+        character::load_character_list(player_uuid, &self.0)
+    }
+
+    fn load_character_data(
+        &mut self,
+        player_uuid: String,
+        character_id: CharacterId,
+    ) -> CharacterDataResult {
+        character::load_character_data(player_uuid, character_id, &self.0)
+    }
+
+    fn create_character(
+        &mut self,
+        player_uuid: &str,
+        alias: &str,
+        persisted_components: PersistedComponents,
+    ) -> Result<CharacterCreationResult, PersistenceError> {
+        let mut transaction = self.0.connection.transaction()?;
+        let result =
+            character::create_character(player_uuid, alias, persisted_components, &mut transaction);
+        if result.is_ok() {
+            transaction.commit()?;
+        }
+        Ok(result)
+    }
+
+    fn edit_character(
+        &mut self,
+        player_uuid: &str,
+        character_id: CharacterId,
+        alias: Option<&str>,
+        editable_components: EditableComponents,
+        trusted_change: Option<PermanentChange>,
+    ) -> Result<CharacterEditResult, PersistenceError> {
+        let mut transaction = self.0.connection.transaction()?;
+        let result = character::edit_character(
+            editable_components,
+            trusted_change,
+            &mut transaction,
+            character_id,
+            player_uuid,
+            alias,
+        );
+        if result.is_ok() {
+            transaction.commit()?;
+        }
+        Ok(result)
+    }
+
+    fn batch_update(&mut self, updates: Vec<DatabaseActionKind>) -> Result<(), PersistenceError> {
+        let mut transaction = self.0.connection.transaction()?;
+        transaction.set_drop_behavior(DropBehavior::Rollback);
+        trace!("Transaction started for character batch update");
+        updates.into_iter().try_for_each(|event| match event {
+            DatabaseActionKind::UpdateCharacter(box (
+                character_id,
+                stats,
+                inventory,
+                storage,
+                pets,
+                waypoint,
+                active_abilities,
+                map_marker,
+                reputation,
+            )) => character::update(
+                character_id,
+                stats,
+                inventory,
+                storage,
+                pets,
+                waypoint,
+                active_abilities,
+                map_marker,
+                reputation,
+                &mut transaction,
+            ),
+            DatabaseActionKind::DeleteCharacter {
+                requesting_player_uuid,
+                character_id,
+            } => {
+                character::delete_character(&requesting_player_uuid, character_id, &mut transaction)
+            },
+            DatabaseActionKind::UpdateMail(changes) => mail::update_mail(changes, &mut transaction),
+            DatabaseActionKind::UpdateMarket(changes) => {
+                market::update_listings(changes, &mut transaction)
+            },
+        })?;
+
+        transaction.commit()?;
+
+        trace!("Commit for character batch update completed");
+        Ok(())
+    }
+
+    #[cfg(feature = "plugins")]
+    fn load_plugin_storage(&mut self) -> Result<Vec<(String, String, Vec<u8>)>, PersistenceError> {
+        plugin_storage::load_entries(&self.0)
+    }
+
+    #[cfg(feature = "plugins")]
+    fn update_plugin_storage(
+        &mut self,
+        changes: Vec<PluginStorageChange>,
+    ) -> Result<(), PersistenceError> {
+        let mut transaction = self.0.connection.transaction()?;
+        transaction.set_drop_behavior(DropBehavior::Rollback);
+
+        plugin_storage::update_plugin_storage(changes, &mut transaction)?;
+
+        transaction.commit()?;
+
+        trace!("Commit for plugin storage update completed");
+        Ok(())
+    }
+
+    fn load_guilds(&mut self) -> Result<GuildRows, PersistenceError> {
+        guild::load_guild_rows(&self.0)
+    }
+
+    fn update_guilds(&mut self, changes: Vec<GuildChange>) -> Result<(), PersistenceError> {
+        let mut transaction = self.0.connection.transaction()?;
+        transaction.set_drop_behavior(DropBehavior::Rollback);
+
+        guild::update_guilds(changes, &mut transaction)?;
+
+        transaction.commit()?;
+
+        trace!("Commit for guild update completed");
+        Ok(())
+    }
+
+    fn load_mail(&mut self) -> Result<MailRows, PersistenceError> { mail::load_mail_rows(&self.0) }
+
+    fn load_listings(&mut self) -> Result<ListingRows, PersistenceError> {
+        market::load_listing_rows(&self.0)
+    }
+
+    fn snapshot(&mut self, path: &Path) -> Result<DatabaseCopy, String> {
+        self.0
+            .execute("VACUUM INTO ?1", [path.to_string_lossy()])
+            .map(|_| DatabaseCopy::Sqlite)
+            .map_err(|e| format!("Failed to copy the database: {e}"))
+    }
+
+    fn export_character(
+        &mut self,
+        player_uuid: &str,
+        character: &str,
+        server_name: &str,
+    ) -> Result<CharacterExport, PersistenceError> {
+        character::transfer::export_character(player_uuid, character, server_name, &self.0)
+    }
+
+    fn import_character(
+        &mut self,
+        player_uuid: &str,
+        export: CharacterExport,
+        export_signature: &str,
+        alias: Option<String>,
+    ) -> Result<ImportedCharacter, PersistenceError> {
+        let mut transaction = self.0.connection.transaction()?;
+        let imported = character::transfer::import_character(
+            player_uuid,
+            export,
+            export_signature,
+            alias,
+            &mut transaction,
+        )?;
+        transaction.commit()?;
+        Ok(imported)
+    }
+}
+
+/// A connection to a PostgreSQL server, see `migrations_postgres` for the
+/// schema.
+#[cfg(feature = "postgres")]
+pub(crate) struct PostgresBackend {
+    client: postgres::Client,
+    /// Passed on to `pg_dump` for snapshots
+    url: String,
+    sql_log_mode: SqlLogMode,
+}
+
+#[cfg(feature = "postgres")]
+impl PostgresBackend {
+    fn connect(url: &str, settings: &DatabaseSettings, connection_mode: ConnectionMode) -> Self {
+        // The url is not part of the message since it may contain the password
+        let mut client = postgres::Client::connect(url, postgres::NoTls)
+            .unwrap_or_else(|err| panic!("Error connecting to PostgreSQL, Error: {err:?}"));
+
+        if connection_mode == ConnectionMode::ReadOnly {
+            client
+                .batch_execute("SET SESSION CHARACTERISTICS AS TRANSACTION READ ONLY")
+                .expect("Failed to make the PostgreSQL connection read-only");
+        }
+
+        let mut backend = Self {
+            client,
+            url: url.to_owned(),
+            sql_log_mode: SqlLogMode::Disabled,
+        };
+        backend.set_log_mode(settings.sql_log_mode);
+        backend
+    }
+
+    fn set_log_mode(&mut self, sql_log_mode: SqlLogMode) {
+        if sql_log_mode != SqlLogMode::Disabled {
+            warn!(
+                "SQL logging is not supported for PostgreSQL, use its log_statement and \
+                 log_min_duration_statement settings instead"
+            );
+        }
+        self.sql_log_mode = sql_log_mode;
+    }
+}
+
+/// Runs one of the PostgreSQL client tools, which have to be installed next to
+/// the server. The url is only passed to the tool since it may contain the
+/// password.
+#[cfg(feature = "postgres")]
+fn run_pg_tool(command: &mut Command) -> Result<(), String> {
+    let program = command.get_program().to_string_lossy().into_owned();
+    let output = command
+        .output()
+        .map_err(|e| format!("Failed to run {program}: {e}"))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(format!(
+            "{program} failed with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    }
+}
+
+/// Dumps the PostgreSQL database at `url` to `path` in the custom format of
+/// `pg_dump`.
+#[cfg(feature = "postgres")]
+pub(crate) fn pg_dump(url: &str, path: &Path) -> Result<(), String> {
+    run_pg_tool(
+        Command::new("pg_dump")
+            .args(["--format=custom", "--no-owner", "--dbname", url, "--file"])
+            .arg(path),
+    )
+}
+
+/// Replaces the `public` schema of the PostgreSQL database at `url` with a dump
+/// written by [`pg_dump`].
+#[cfg(feature = "postgres")]
+pub(crate) fn pg_restore(url: &str, path: &Path) -> Result<(), String> {
+    let mut client = postgres::Client::connect(url, postgres::NoTls)
+        .map_err(|e| format!("Error connecting to PostgreSQL: {e}"))?;
+    // Unlike `pg_restore --clean` this also drops the tables of migrations that
+    // are newer than the dump
+    client
+        .batch_execute("DROP SCHEMA public CASCADE; CREATE SCHEMA public")
+        .map_err(|e| format!("Failed to clear the database: {e}"))?;
+    run_pg_tool(
+        Command::new("pg_restore")
+            .args(["--no-owner", "--single-transaction", "--dbname", url])
+            .arg(path),
+    )
+}
+
+#[cfg(feature = "postgres")]
+impl PersistenceBackend for PostgresBackend {
+    fn update_log_mode(&mut self, settings: &Arc<RwLock<DatabaseSettings>>) {
+        let sql_log_mode = settings
+            .read()
+            .expect("DatabaseSettings RwLock was poisoned")
+            .sql_log_mode;
+        if self.sql_log_mode != sql_log_mode {
+            self.set_log_mode(sql_log_mode);
+        }
+    }
+
+    fn run_migrations(&mut self) {
+        let report: Report = super::embedded_postgres::migrations::runner()
+            .set_abort_divergent(false)
+            .run(&mut self.client)
+            .expect("Database migrations failed, server startup aborted");
+
+        let applied_migrations = report.applied_migrations().len();
+        info!("Applied {} database migrations", applied_migrations);
+    }
+
+    fn vacuum(&mut self) {
+        debug!("Not vacuuming, PostgreSQL reclaims space with autovacuum");
+    }
+
+    fn load_character_list(&mut self, player_uuid: &str) -> CharacterListResult {
+        character::postgres::load_character_list(player_uuid, &mut self.client)
+    }
+
+    fn load_character_data(
+        &mut self,
+        player_uuid: String,
+        character_id: CharacterId,
+    ) -> CharacterDataResult {
+        character::postgres::load_character_data(player_uuid, character_id, &mut self.client)
+    }
+
+    fn create_character(
+        &mut self,
+        player_uuid: &str,
+        alias: &str,
+        persisted_components: PersistedComponents,
+    ) -> Result<CharacterCreationResult, PersistenceError> {
+        let mut transaction = self.client.transaction()?;
+        let result = character::postgres::create_character(
+            player_uuid,
+            alias,
+            persisted_components,
+            &mut transaction,
+        );
+        if result.is_ok() {
+            transaction.commit()?;
+        }
+        Ok(result)
+    }
+
+    fn edit_character(
+        &mut self,
+        player_uuid: &str,
+        character_id: CharacterId,
+        alias: Option<&str>,
+        editable_components: EditableComponents,
+        trusted_change: Option<PermanentChange>,
+    ) -> Result<CharacterEditResult, PersistenceError> {
+        let mut transaction = self.client.transaction()?;
+        let result = character::postgres::edit_character(
+            editable_components,
+            trusted_change,
+            &mut transaction,
+            character_id,
+            player_uuid,
+            alias,
+        );
+        if result.is_ok() {
+            transaction.commit()?;
+        }
+        Ok(result)
+    }
+
+    fn batch_update(&mut self, updates: Vec<DatabaseActionKind>) -> Result<(), PersistenceError> {
+        // Dropping the transaction without committing rolls it back
+        let mut transaction = self.client.transaction()?;
+        trace!("Transaction started for character batch update");
+        updates.into_iter().try_for_each(|event| match event {
+            DatabaseActionKind::UpdateCharacter(box (
+                character_id,
+                stats,
+                inventory,
+                storage,
+                pets,
+                waypoint,
+                active_abilities,
+                map_marker,
+                reputation,
+            )) => character::postgres::update(
+                character_id,
+                stats,
+                inventory,
+                storage,
+                pets,
+                waypoint,
+                active_abilities,
+                map_marker,
+                reputation,
+                &mut transaction,
+            ),
+            DatabaseActionKind::DeleteCharacter {
+                requesting_player_uuid,
+                character_id,
+            } => character::postgres::delete_character(
+                &requesting_player_uuid,
+                character_id,
+                &mut transaction,
+            ),
+            DatabaseActionKind::UpdateMail(changes) => {
+                mail::update_mail_postgres(changes, &mut transaction)
+            },
+            DatabaseActionKind::UpdateMarket(changes) => {
+                market::update_listings_postgres(changes, &mut transaction)
+            },
+        })?;
+
+        transaction.commit()?;
+
+        trace!("Commit for character batch update completed");
+        Ok(())
+    }
+
+    #[cfg(feature = "plugins")]
+    fn load_plugin_storage(&mut self) -> Result<Vec<(String, String, Vec<u8>)>, PersistenceError> {
+        plugin_storage::load_entries_postgres(&mut self.client)
+    }
+
+    #[cfg(feature = "plugins")]
+    fn update_plugin_storage(
+        &mut self,
+        changes: Vec<PluginStorageChange>,
+    ) -> Result<(), PersistenceError> {
+        let mut transaction = self.client.transaction()?;
+
+        plugin_storage::update_plugin_storage_postgres(changes, &mut transaction)?;
+
+        transaction.commit()?;
+
+        trace!("Commit for plugin storage update completed");
+        Ok(())
+    }
+
+    fn load_guilds(&mut self) -> Result<GuildRows, PersistenceError> {
+        guild::load_guild_rows_postgres(&mut self.client)
+    }
+
+    fn update_guilds(&mut self, changes: Vec<GuildChange>) -> Result<(), PersistenceError> {
+        let mut transaction = self.client.transaction()?;
+
+        guild::update_guilds_postgres(changes, &mut transaction)?;
+
+        transaction.commit()?;
+
+        trace!("Commit for guild update completed");
+        Ok(())
+    }
+
+    fn load_mail(&mut self) -> Result<MailRows, PersistenceError> {
+        mail::load_mail_rows_postgres(&mut self.client)
+    }
+
+    fn load_listings(&mut self) -> Result<ListingRows, PersistenceError> {
+        market::load_listing_rows_postgres(&mut self.client)
+    }
+
+    fn snapshot(&mut self, path: &Path) -> Result<DatabaseCopy, String> {
+        pg_dump(&self.url, path).map(|()| DatabaseCopy::PgDump)
+    }
+
+    fn export_character(
+        &mut self,
+        player_uuid: &str,
+        character: &str,
+        server_name: &str,
+    ) -> Result<CharacterExport, PersistenceError> {
+        character::postgres::export_character(player_uuid, character, server_name, &mut self.client)
+    }
+
+    fn import_character(
+        &mut self,
+        player_uuid: &str,
+        export: CharacterExport,
+        export_signature: &str,
+        alias: Option<String>,
+    ) -> Result<ImportedCharacter, PersistenceError> {
+        let mut transaction = self.client.transaction()?;
+        let imported = character::postgres::import_character(
+            player_uuid,
+            export,
+            export_signature,
+            alias,
+            &mut transaction,
+        )?;
+        transaction.commit()?;
+        Ok(imported)
+    }
+}
+
+/// Every test runs against SQLite, and also against PostgreSQL when
+/// `VELOREN_TEST_POSTGRES_URL` is set to a database the tests may write to,
+/// e.g. `postgresql://postgres@localhost/veloren_test`. The snapshot test needs
+/// `pg_dump` then.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::persistence::{
+        SqlLogMode,
+        mail::{MailChange, MailRow},
+        market::{ListingChange, ListingRow},
+    };
+    use common::{
+        comp::{
+            self, Inventory, Item,
+            guild::{GuildId, GuildRank},
+            inventory::loadout_builder::LoadoutBuilder,
+            mail::MailId,
+            market::ListingId,
+        },
+        rtsim::PersistedReputation,
+    };
+    use std::{collections::HashMap, fs, path::PathBuf};
+    use vek::Vec2;
+
+    #[cfg(feature = "postgres")]
+    const POSTGRES_URL: &str = "VELOREN_TEST_POSTGRES_URL";
+    const PLAYER: &str = "00000000-0000-0000-0000-000000000001";
+    const OTHER_PLAYER: &str = "00000000-0000-0000-0000-000000000002";
+    const CHEESE: &str = "common.items.food.cheese";
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!(
+            "veloren_backend_test_{}_{name}",
+            std::process::id()
+        ))
+    }
+
+    /// Migrated, empty databases of every backend that is available
+    fn backends(name: &str) -> Vec<Box<dyn PersistenceBackend>> {
+        let db_dir = temp_path(name);
+        let _ = fs::remove_dir_all(&db_dir);
+        let settings = DatabaseSettings {
+            db_dir,
+            sql_log_mode: SqlLogMode::Disabled,
+            backend: DatabaseBackend::Sqlite,
+        };
+        #[cfg_attr(not(feature = "postgres"), expect(unused_mut))]
+        let mut backends = vec![connect(&settings, ConnectionMode::ReadWrite)];
+
+        #[cfg(feature = "postgres")]
+        if let Ok(url) = std::env::var(POSTGRES_URL) {
+            let mut backend = PostgresBackend::connect(&url, &settings, ConnectionMode::ReadWrite);
+            // Tests run in parallel, so each of them gets a schema of its own
+            backend
+                .client
+                .batch_execute(&format!(
+                    "DROP SCHEMA IF EXISTS test_{name} CASCADE; CREATE SCHEMA test_{name}; SET \
+                     search_path TO test_{name}"
+                ))
+                .expect("Failed to create the test schema");
+            backends.push(Box::new(backend));
+        }
+
+        for backend in &mut backends {
+            backend.run_migrations();
+        }
+        backends
+    }
+
+    fn new_character(alias: &str) -> PersistedComponents {
+        let body = comp::Body::Humanoid(comp::humanoid::Body::random());
+        let mut inventory =
+            Inventory::with_loadout_humanoid(LoadoutBuilder::empty().defaults().build());
+        inventory
+            .push(Item::new_from_asset_expect(CHEESE))
+            .expect("New inventories have free slots");
+        PersistedComponents {
+            body,
+            hardcore: None,
+            stats: comp::Stats::new(comp::Content::Plain(alias.to_owned()), body),
+            skill_set: comp::SkillSet::default(),
+            inventory,
+            storage: Default::default(),
+            waypoint: None,
+            pets: Vec::new(),
+            active_abilities: comp::ActiveAbilities::default_limited(comp::BASE_ABILITY_LIMIT),
+            map_marker: None,
+            reputation: None,
+        }
+    }
+
+    fn create(backend: &mut dyn PersistenceBackend, player: &str, alias: &str) -> CharacterId {
+        let (character_id, _) = backend
+            .create_character(player, alias, new_character(alias))
+            .unwrap()
+            .unwrap();
+        character_id
+    }
+
+    fn cheese<'a>(items: impl Iterator<Item = &'a Item>) -> u32 {
+        items
+            .filter(|item| item.persistence_item_id() == CHEESE)
+            .map(Item::amount)
+            .sum()
+    }
+
+    #[test]
+    fn characters() {
+        for mut backend in backends("characters") {
+            let character_id = create(backend.as_mut(), PLAYER, "Tester");
+            let list = backend.load_character_list(PLAYER).unwrap();
+            assert_eq!(list.len(), 1);
+            assert_eq!(list[0].character.alias, "Tester");
+            assert!(
+                backend
+                    .load_character_list(OTHER_PLAYER)
+                    .unwrap()
+                    .is_empty()
+            );
+
+            let (mut components, _) = backend
+                .load_character_data(PLAYER.to_owned(), character_id)
+                .unwrap();
+            assert_eq!(cheese(components.inventory.slots().flatten()), 1);
+            assert_eq!(components.reputation, None);
+
+            components
+                .inventory
+                .push(Item::new_from_asset_expect(CHEESE))
+                .expect("Cheese stacks");
+            let reputation = PersistedReputation {
+                sites: vec![(Vec2::new(1, 2), 0.5)],
+                factions: vec![(3, -0.5)],
+            };
+            backend
+                .batch_update(vec![DatabaseActionKind::UpdateCharacter(Box::new((
+                    character_id,
+                    components.skill_set,
+                    components.inventory,
+                    components.storage.inventory,
+                    Vec::new(),
+                    None,
+                    components.active_abilities,
+                    None,
+                    reputation.clone(),
+                )))])
+                .unwrap();
+            let (components, _) = backend
+                .load_character_data(PLAYER.to_owned(), character_id)
+                .unwrap();
+            assert_eq!(cheese(components.inventory.slots().flatten()), 2);
+            assert_eq!(components.reputation, Some(reputation));
+
+            let (_, list) = backend
+                .edit_character(
+                    PLAYER,
+                    character_id,
+                    Some("Renamed"),
+                    (components.body,),
+                    None,
+                )
+                .unwrap()
+                .unwrap();
+            assert_eq!(list[0].character.alias, "Renamed");
+
+            // Requests of other players are ignored
+            for requesting_player_uuid in [OTHER_PLAYER, PLAYER] {
+                backend
+                    .batch_update(vec![DatabaseActionKind::DeleteCharacter {
+                        requesting_player_uuid: requesting_player_uuid.to_owned(),
+                        character_id,
+                    }])
+                    .unwrap();
+                assert_eq!(
+                    backend.load_character_list(PLAYER).unwrap().len(),
+                    usize::from(requesting_player_uuid == OTHER_PLAYER)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn character_transfer() {
+        for mut backend in backends("transfer") {
+            create(backend.as_mut(), PLAYER, "Tester");
+            let export = backend.export_character(PLAYER, "Tester", "Test").unwrap();
+            assert_eq!(export.alias, "Tester");
+
+            let imported = backend
+                .import_character(
+                    OTHER_PLAYER,
+                    export.clone(),
+                    "signature",
+                    Some("Imported".to_owned()),
+                )
+                .unwrap();
+            assert_eq!(imported.original_alias, "Tester");
+            assert_eq!(imported.source, "Test");
+            assert!(!imported.skills_reset);
+            let (components, _) = backend
+                .load_character_data(OTHER_PLAYER.to_owned(), imported.character_id)
+                .unwrap();
+            assert_eq!(cheese(components.inventory.slots().flatten()), 1);
+
+            // Importing the same export again is rejected and rolled back
+            assert!(
+                backend
+                    .import_character(OTHER_PLAYER, export, "signature", Some("Again".to_owned()))
+                    .is_err()
+            );
+            assert_eq!(backend.load_character_list(OTHER_PLAYER).unwrap().len(), 1);
+        }
+    }
+
+    #[test]
+    fn guilds() {
+        for mut backend in backends("guilds") {
+            let leader = create(backend.as_mut(), PLAYER, "Leader");
+            let member = create(backend.as_mut(), OTHER_PLAYER, "Member");
+            let guild_id = GuildId(1);
+            backend
+                .update_guilds(vec![
+                    GuildChange::Create {
+                        guild_id,
+                        name: "Guild".to_owned(),
+                    },
+                    GuildChange::SetMember {
+                        character_id: leader,
+                        guild_id,
+                        rank: GuildRank::Leader,
+                    },
+                    GuildChange::SetMember {
+                        character_id: member,
+                        guild_id,
+                        rank: GuildRank::Member,
+                    },
+                    GuildChange::SetMember {
+                        character_id: member,
+                        guild_id,
+                        rank: GuildRank::Officer,
+                    },
+                ])
+                .unwrap();
+            let rows = backend.load_guilds().unwrap();
+            assert_eq!(rows.guilds, vec![(guild_id, "Guild".to_owned())]);
+            let ranks = rows
+                .members
+                .iter()
+                .map(|member| (member.character_id, member.rank))
+                .collect::<HashMap<_, _>>();
+            assert_eq!(
+                ranks,
+                HashMap::from([(leader, GuildRank::Leader), (member, GuildRank::Officer)])
+            );
+
+            backend
+                .update_guilds(vec![GuildChange::RemoveMember(member)])
+                .unwrap();
+            assert_eq!(backend.load_guilds().unwrap().members.len(), 1);
+            backend
+                .update_guilds(vec![GuildChange::Disband(guild_id)])
+                .unwrap();
+            let rows = backend.load_guilds().unwrap();
+            assert!(rows.guilds.is_empty() && rows.members.is_empty());
+        }
+    }
+
+    #[test]
+    fn mail_and_listings() {
+        for mut backend in backends("mail") {
+            let sender = create(backend.as_mut(), PLAYER, "Sender");
+            let recipient = create(backend.as_mut(), OTHER_PLAYER, "Recipient");
+            let mail = MailRow {
+                id: MailId(1),
+                sender: Some(sender),
+                sender_name: "Sender".to_owned(),
+                recipient,
+                subject: comp::Content::Plain("Subject".to_owned()),
+                body: comp::Content::localized("hud-market-sold_body").with_arg("price", 10_u64),
+                coins: 10,
+                attachments: vec![Item::new_from_asset_expect(CHEESE)],
+                sent_at: 1,
+                expires_at: 2,
+                read: false,
+                returned: false,
+            };
+            let listing = ListingRow {
+                id: ListingId(1),
+                seller: sender,
+                seller_name: String::new(),
+                item: Item::new_from_asset_expect(CHEESE),
+                price: 100,
+                listed_at: 1,
+                expires_at: 2,
+            };
+            backend
+                .batch_update(vec![
+                    DatabaseActionKind::UpdateMail(vec![
+                        MailChange::Upsert(Box::new(mail.clone())),
+                        MailChange::Upsert(Box::new(MailRow {
+                            read: true,
+                            ..mail.clone()
+                        })),
+                    ]),
+                    DatabaseActionKind::UpdateMarket(vec![ListingChange::Insert(Box::new(
+                        listing,
+                    ))]),
+                ])
+                .unwrap();
+
+            let rows = backend.load_mail().unwrap();
+            assert_eq!(rows.characters.len(), 2);
+            let [loaded] = &rows.mail[..] else {
+                panic!("Expected a single mail, got {:?}", rows.mail);
+            };
+            assert_eq!(
+                (loaded.sender, loaded.recipient, loaded.coins, loaded.read),
+                (Some(sender), recipient, 10, true)
+            );
+            assert_eq!(cheese(loaded.attachments.iter()), 1);
+            assert_eq!((&loaded.subject, &loaded.body), (&mail.subject, &mail.body));
+            let rows = backend.load_listings().unwrap();
+            let [listing] = &rows.listings[..] else {
+                panic!("Expected a single listing, got {:?}", rows.listings);
+            };
+            // The name of the seller is read from the character
+            assert_eq!(
+                (listing.seller_name.as_str(), listing.price),
+                ("Sender", 100)
+            );
+            assert_eq!(cheese(std::iter::once(&listing.item)), 1);
+
+            backend
+                .batch_update(vec![
+                    DatabaseActionKind::UpdateMail(vec![MailChange::Delete(MailId(1))]),
+                    DatabaseActionKind::UpdateMarket(vec![ListingChange::Delete(ListingId(1))]),
+                ])
+                .unwrap();
+            assert!(backend.load_mail().unwrap().mail.is_empty());
+            assert!(backend.load_listings().unwrap().listings.is_empty());
+        }
+    }
+
+    #[cfg(feature = "postgres")]
+    #[test]
+    fn database_lock() {
+        let Ok(url) = std::env::var(POSTGRES_URL) else {
+            return;
+        };
+        let settings = |shared| DatabaseSettings {
+            db_dir: temp_path("database_lock"),
+            sql_log_mode: SqlLogMode::Disabled,
+            backend: DatabaseBackend::Postgres {
+                url: url.clone(),
+                shared,
+            },
+        };
+        let _first = lock(&settings(true)).unwrap();
+        let _second = lock(&settings(true)).unwrap();
+        // A server that isn't shared can't use the database next to them
+        assert!(lock(&settings(false)).is_err());
+    }
+
+    #[test]
+    fn snapshot() {
+        for mut backend in backends("snapshot") {
+            create(backend.as_mut(), PLAYER, "Tester");
+            let path = temp_path("snapshot_copy");
+            let _ = fs::remove_file(&path);
+
+            match backend.snapshot(&path).unwrap() {
+                DatabaseCopy::Sqlite => {
+                    let copy = rusqlite::Connection::open(&path).unwrap();
+                    let characters: i64 = copy
+                        .query_row("SELECT COUNT(*) FROM character", [], |row| row.get(0))
+                        .unwrap();
+                    assert_eq!(characters, 1);
+                },
+                DatabaseCopy::PgDump => assert!(fs::metadata(&path).unwrap().len() > 0),
+            }
+            fs::remove_file(path).unwrap();
+        }
+    }
+}
@@ -2,8 +2,8 @@ use crate::persistence::{
     character::EntityId,
     error::PersistenceError,
     json_models::{
-        self, CharacterPosition, DatabaseAbilitySet, DatabaseItemProperties, GenericBody,
-        HumanoidBody,
+        self, CharacterPosition, CharacterReputation, DatabaseAbilitySet, DatabaseItemProperties,
+        DatabaseMailItem, GenericBody, HumanoidBody,
     },
     models::{AbilitySets, Character, Item, SkillGroup},
 };
@@ -23,6 +23,7 @@ use common::{
         skillset::{self, SkillGroupKind, SkillSet, skills::Skill},
     },
     resources::Time,
+    rtsim::PersistedReputation,
 };
 use core::{convert::TryFrom, num::NonZeroU64};
 use hashbrown::HashMap;
@@ -53,12 +54,17 @@ lazy_static! {
 /// inventories. Although loadout items do store items inside them this does
 /// not currently utilise `parent_container_id` - all loadout items have the
 /// loadout pseudo-container as their parent.
+///
+/// Only the built-in slots of the personal storage are persisted, like the
+/// slots of the inventory they are keyed by their `InvSlotId`.
 pub fn convert_items_to_database_items(
     loadout_container_id: EntityId,
     inventory: &Inventory,
     inventory_container_id: EntityId,
     overflow_items_container_id: EntityId,
     recipe_book_container_id: EntityId,
+    storage: &Inventory,
+    storage_container_id: EntityId,
     next_id: &mut i64,
 ) -> Vec<ItemModelPair> {
     let loadout = inventory
@@ -92,12 +98,22 @@ pub fn convert_items_to_database_items(
         )
     });
 
+    // Personal storage slots.
+    let storage = storage.slots_with_id().map(|(pos, item)| {
+        (
+            serde_json::to_string(&pos).expect("failed to serialize InvSlotId"),
+            item.as_ref(),
+            storage_container_id,
+        )
+    });
+
     // Use Breadth-first search to recurse into containers/modular weapons to store
     // their parts
     let mut bfs_queue: VecDeque<_> = inventory
         .chain(loadout)
         .chain(overflow_items)
         .chain(recipe_book)
+        .chain(storage)
         .collect();
     let mut upserts = Vec::new();
     let mut depth = HashMap::new();
@@ -105,6 +121,7 @@ pub fn convert_items_to_database_items(
     depth.insert(loadout_container_id, 0);
     depth.insert(overflow_items_container_id, 0);
     depth.insert(recipe_book_container_id, 0);
+    depth.insert(storage_container_id, 0);
     while let Some((position, item, parent_container_item_id)) = bfs_queue.pop_front() {
         // Construct new items.
         if let Some(item) = item {
@@ -281,6 +298,42 @@ pub fn convert_waypoint_to_database_json(
     }
 }
 
+pub fn convert_reputation_to_database_json(
+    reputation: &PersistedReputation,
+) -> Result<String, PersistenceError> {
+    let reputation = CharacterReputation {
+        sites: reputation
+            .sites
+            .iter()
+            .map(|(wpos, value)| (wpos.into_array(), *value))
+            .collect(),
+        factions: reputation.factions.clone(),
+    };
+    serde_json::to_string(&reputation).map_err(|err| {
+        PersistenceError::ConversionError(format!("Error encoding reputation: {:?}", err))
+    })
+}
+
+pub fn convert_reputation_from_database_json(
+    reputation: &str,
+) -> Result<PersistedReputation, PersistenceError> {
+    let db_reputation =
+        serde_json::de::from_str::<CharacterReputation>(reputation).map_err(|err| {
+            PersistenceError::ConversionError(format!(
+                "Error de-serializing reputation: {} err: {}",
+                reputation, err
+            ))
+        })?;
+    Ok(PersistedReputation {
+        sites: db_reputation
+            .sites
+            .into_iter()
+            .map(|(wpos, value)| (vek::Vec2::from(wpos), value))
+            .collect(),
+        factions: db_reputation.factions,
+    })
+}
+
 pub fn convert_waypoint_from_database_json(
     position: &str,
 ) -> Result<(Option<Waypoint>, Option<MapMarker>), PersistenceError> {
@@ -397,6 +450,55 @@ pub fn convert_inventory_from_database_items(
     overflow_items.extend(duplicate_recipes);
 
     let mut inventory = Inventory::with_loadout_humanoid(loadout).with_recipe_book(recipe_book);
+
+    let failed_inserts =
+        insert_database_items_at_slots(inventory_container_id, inventory_items, &mut inventory)?;
+
+    // For overflow items and failed inserts, attempt to push to inventory. If push
+    // fails, move to overflow slots.
+    if let Err(inv_error) = inventory.push_all(
+        overflow_items
+            .into_iter()
+            .chain(failed_inserts.into_values()),
+    ) {
+        inventory.persistence_push_overflow_items(inv_error.returned_items());
+    }
+
+    // Some items may have had components added, so update the item config of each
+    // item to ensure that it correctly accounts for components that were added
+    inventory.persistence_update_all_item_states(&ABILITY_MAP, &MATERIAL_STATS_MANIFEST);
+
+    Ok(inventory)
+}
+
+/// Loads the personal storage of a character. Items that no longer fit into the
+/// storage are moved to the overflow slots of the character's `inventory`.
+pub fn convert_storage_from_database_items(
+    storage_container_id: i64,
+    storage_items: &[Item],
+    inventory: &mut Inventory,
+) -> Result<Inventory, PersistenceError> {
+    let mut storage = Inventory::with_empty();
+    let failed_inserts =
+        insert_database_items_at_slots(storage_container_id, storage_items, &mut storage)?;
+
+    if let Err(inv_error) = storage.push_all(failed_inserts.into_values()) {
+        inventory.persistence_push_overflow_items(inv_error.returned_items());
+    }
+
+    storage.persistence_update_all_item_states(&ABILITY_MAP, &MATERIAL_STATS_MANIFEST);
+
+    Ok(storage)
+}
+
+/// Inserts the items stored in the slots of the pseudo-container
+/// `container_id` into the built-in slots of `inventory`. Returns the items
+/// that didn't fit into the inventory, keyed by their position.
+fn insert_database_items_at_slots(
+    container_id: i64,
+    database_items: &[Item],
+    inventory: &mut Inventory,
+) -> Result<HashMap<String, VelorenItem>, PersistenceError> {
     let mut item_indices = HashMap::new();
 
     let mut failed_inserts = HashMap::new();
@@ -404,7 +506,7 @@ pub fn convert_inventory_from_database_items(
     // In order to items with components to properly load, it is important that this
     // item iteration occurs in order so that any modular items are loaded before
     // its components.
-    for (i, db_item) in inventory_items.iter().enumerate() {
+    for (i, db_item) in database_items.iter().enumerate() {
         item_indices.insert(db_item.item_id, i);
 
         let mut item = get_item_from_asset(db_item.item_definition_id.as_str())?;
@@ -449,7 +551,7 @@ pub fn convert_inventory_from_database_items(
             })
         };
 
-        if db_item.parent_container_item_id == inventory_container_id {
+        if db_item.parent_container_item_id == container_id {
             match slot(&db_item.position) {
                 Ok(slot) => {
                     let insert_res = inventory.insert_at(slot, item);
@@ -482,9 +584,9 @@ pub fn convert_inventory_from_database_items(
         } else if let Some(&j) = item_indices.get(&db_item.parent_container_item_id) {
             get_mutable_item(
                 j,
-                inventory_items,
+                database_items,
                 &item_indices,
-                &mut (&mut inventory, &mut failed_inserts),
+                &mut (&mut *inventory, &mut failed_inserts),
                 &|(inv, f_i): &mut (&mut Inventory, &mut HashMap<String, VelorenItem>), s| {
                     // Attempts first to access inventory if that slot exists there. If it does not
                     // it instead attempts to access failed inserts list.
@@ -504,21 +606,7 @@ pub fn convert_inventory_from_database_items(
         }
     }
 
-    // For overflow items and failed inserts, attempt to push to inventory. If push
-    // fails, move to overflow slots.
-    if let Err(inv_error) = inventory.push_all(
-        overflow_items
-            .into_iter()
-            .chain(failed_inserts.into_values()),
-    ) {
-        inventory.persistence_push_overflow_items(inv_error.returned_items());
-    }
-
-    // Some items may have had components added, so update the item config of each
-    // item to ensure that it correctly accounts for components that were added
-    inventory.persistence_update_all_item_states(&ABILITY_MAP, &MATERIAL_STATS_MANIFEST);
-
-    Ok(inventory)
+    Ok(failed_inserts)
 }
 
 pub fn convert_loadout_from_database_items(
@@ -945,3 +1033,73 @@ pub fn convert_recipe_book_from_database_items(
 
     Ok((recipe_book, duplicate_recipes))
 }
+
+fn convert_item_to_database_json_model(item: &VelorenItem) -> DatabaseMailItem {
+    DatabaseMailItem {
+        item_definition_id: item.persistence_item_id(),
+        stack_size: item.amount(),
+        properties: json_models::item_properties_to_db_model(item),
+        components: item
+            .components()
+            .iter()
+            .map(convert_item_to_database_json_model)
+            .collect(),
+    }
+}
+
+fn convert_item_from_database_json_model(
+    db_item: DatabaseMailItem,
+) -> Result<VelorenItem, PersistenceError> {
+    let mut item = get_item_from_asset(&db_item.item_definition_id)?;
+    json_models::apply_db_item_properties(&mut item, &db_item.properties);
+    if db_item.stack_size == 1 || item.is_stackable() {
+        item.set_amount(db_item.stack_size).map_err(|_| {
+            PersistenceError::ConversionError(format!(
+                "Invalid stack size for stackable={}: {}",
+                item.is_stackable(),
+                db_item.stack_size
+            ))
+        })?;
+    }
+    for component in db_item.components {
+        item.persistence_access_add_component(convert_item_from_database_json_model(component)?);
+    }
+    item.update_item_state(&ABILITY_MAP, &MATERIAL_STATS_MANIFEST);
+    Ok(item)
+}
+
+pub fn convert_mail_attachments_to_database_json(attachments: &[VelorenItem]) -> String {
+    serde_json::to_string(
+        &attachments
+            .iter()
+            .map(convert_item_to_database_json_model)
+            .collect::<Vec<_>>(),
+    )
+    .expect("Failed to convert mail attachments to a json string.")
+}
+
+/// Attachments that can't be loaded anymore, for example because the item was
+/// removed from the game, are skipped.
+pub fn convert_mail_attachments_from_database_json(
+    attachments: &str,
+) -> Result<Vec<VelorenItem>, PersistenceError> {
+    Ok(
+        serde_json::de::from_str::<Vec<DatabaseMailItem>>(attachments)?
+            .into_iter()
+            .filter_map(|db_item| {
+                convert_item_from_database_json_model(db_item)
+                    .inspect_err(|error| warn!(?error, "Skipping mail attachment"))
+                    .ok()
+            })
+            .collect(),
+    )
+}
+
+pub fn convert_market_item_to_database_json(item: &VelorenItem) -> String {
+    serde_json::to_string(&convert_item_to_database_json_model(item))
+        .expect("Failed to convert market item to a json string.")
+}
+
+pub fn convert_market_item_from_database_json(item: &str) -> Result<VelorenItem, PersistenceError> {
+    convert_item_from_database_json_model(serde_json::de::from_str(item)?)
+}
@@ -17,9 +17,11 @@ use crate::{
             convert_character_from_database, convert_hardcore_from_database,
             convert_hardcore_to_database, convert_inventory_from_database_items,
             convert_items_to_database_items, convert_loadout_from_database_items,
-            convert_recipe_book_from_database_items, convert_skill_groups_to_database,
+            convert_recipe_book_from_database_items, convert_reputation_from_database_json,
+            convert_reputation_to_database_json, convert_skill_groups_to_database,
             convert_skill_set_from_database, convert_stats_from_database,
-            convert_waypoint_from_database_json, convert_waypoint_to_database_json,
+            convert_storage_from_database_items, convert_waypoint_from_database_json,
+            convert_waypoint_to_database_json,
         },
         character_loader::{CharacterCreationResult, CharacterDataResult, CharacterListResult},
         character_updater::PetPersistenceData,
@@ -31,16 +33,27 @@ use common::{
     comp::Content,
     event::{PermanentChange, UpdateCharacterMetadata},
     npc::NPC_NAMES,
+    rtsim::PersistedReputation,
 };
 use core::ops::Range;
-use rusqlite::{Connection, ToSql, Transaction, types::Value};
+use rusqlite::{Connection, OptionalExtension, ToSql, Transaction, types::Value};
 use std::{num::NonZeroU64, rc::Rc};
 use tracing::{debug, error, trace, warn};
+use vek::Vec3;
 
 /// Private module for very tightly coupled database conversion methods.  In
 /// general, these have many invariants that need to be maintained when they're
 /// called--do not assume it's safe to make these public!
 mod conversions;
+#[cfg(feature = "postgres")] pub mod postgres;
+pub mod transfer;
+
+// Mail attachments and market items don't have entity ids, so these are fine to
+// use elsewhere
+pub(super) use conversions::{
+    convert_mail_attachments_from_database_json, convert_mail_attachments_to_database_json,
+    convert_market_item_from_database_json, convert_market_item_to_database_json,
+};
 
 pub(crate) type EntityId = i64;
 
@@ -50,10 +63,12 @@ const LOADOUT_PSEUDO_CONTAINER_DEF_ID: &str = "veloren.core.pseudo_containers.lo
 const OVERFLOW_ITEMS_PSEUDO_CONTAINER_DEF_ID: &str =
     "veloren.core.pseudo_containers.overflow_items";
 const RECIPE_BOOK_PSEUDO_CONTAINER_DEF_ID: &str = "veloren.core.pseudo_containers.recipe_book";
+const STORAGE_PSEUDO_CONTAINER_DEF_ID: &str = "veloren.core.pseudo_containers.storage";
 const INVENTORY_PSEUDO_CONTAINER_POSITION: &str = "inventory";
 const LOADOUT_PSEUDO_CONTAINER_POSITION: &str = "loadout";
 const OVERFLOW_ITEMS_PSEUDO_CONTAINER_POSITION: &str = "overflow_items";
 const RECIPE_BOOK_PSEUDO_CONTAINER_POSITION: &str = "recipe_book";
+const STORAGE_PSEUDO_CONTAINER_POSITION: &str = "storage";
 const WORLD_PSEUDO_CONTAINER_ID: EntityId = 1;
 
 #[derive(Clone, Copy)]
@@ -62,6 +77,7 @@ struct CharacterContainers {
     loadout_container_id: EntityId,
     overflow_items_container_id: EntityId,
     recipe_book_container_id: EntityId,
+    storage_container_id: EntityId,
 }
 
 /// Load the inventory/loadout
@@ -155,6 +171,7 @@ pub fn load_character_data(
     let overflow_items_items =
         load_items(connection, character_containers.overflow_items_container_id)?;
     let recipe_book_items = load_items(connection, character_containers.recipe_book_container_id)?;
+    let storage_items = load_items(connection, character_containers.storage_container_id)?;
 
     let mut stmt = connection.prepare_cached(
         "
@@ -191,9 +208,6 @@ pub fn load_character_data(
         },
     )?;
 
-    let (char_waypoint, char_map_marker) =
-        convert_waypoint_or_warn(character_data.waypoint.as_deref(), char_id);
-
     let mut stmt = connection.prepare_cached(
         "
         SELECT  skill_group_kind,
@@ -242,10 +256,125 @@ pub fn load_character_data(
         .filter_map(Result::ok)
         .collect::<Vec<Pet>>();
 
-    // Re-construct the pet components for the player's pets, including
-    // de-serializing the pets' bodies and creating their Pet and Stats
-    // components
-    let pets = db_pets
+    let mut stmt = connection.prepare_cached(
+        "
+            SELECT  ability_sets
+            FROM    ability_set
+            WHERE   entity_id = ?1",
+    )?;
+
+    let ability_set_data = stmt.query_row([char_id.0], |row| {
+        Ok(AbilitySets {
+            entity_id: char_id.0,
+            ability_sets: row.get(0)?,
+        })
+    })?;
+
+    let mut stmt = connection.prepare_cached(
+        "
+            SELECT  reputation
+            FROM    character_reputation
+            WHERE   character_id = ?1",
+    )?;
+
+    let reputation = stmt
+        .query_row([char_id.0], |row| row.get::<_, String>(0))
+        .optional()?;
+
+    CharacterRows {
+        character: character_data,
+        body: body_data,
+        containers: character_containers,
+        inventory_items,
+        loadout_items,
+        overflow_items_items,
+        recipe_book_items,
+        storage_items,
+        skill_groups: skill_group_data,
+        pets: db_pets,
+        ability_sets: ability_set_data,
+        reputation,
+    }
+    .into_character_data()
+}
+
+/// The rows the persisted components of a character are converted from
+struct CharacterRows {
+    character: Character,
+    body: Body,
+    containers: CharacterContainers,
+    inventory_items: Vec<Item>,
+    loadout_items: Vec<Item>,
+    overflow_items_items: Vec<Item>,
+    recipe_book_items: Vec<Item>,
+    storage_items: Vec<Item>,
+    skill_groups: Vec<SkillGroup>,
+    pets: Vec<Pet>,
+    ability_sets: AbilitySets,
+    /// Characters without a reputation row never did anything notable yet
+    reputation: Option<String>,
+}
+
+impl CharacterRows {
+    fn into_character_data(self) -> CharacterDataResult {
+        let char_id = CharacterId(self.character.character_id);
+        let (char_waypoint, char_map_marker) =
+            convert_waypoint_or_warn(self.character.waypoint.as_deref(), char_id);
+
+        let reputation = self.reputation.and_then(|reputation| {
+            convert_reputation_from_database_json(&reputation)
+                .inspect_err(|err| {
+                    warn!(
+                        "Error reading reputation from database for character ID {}, error: {}",
+                        char_id.0, err
+                    )
+                })
+                .ok()
+        });
+
+        let (skill_set, skill_set_persistence_load_error) =
+            convert_skill_set_from_database(&self.skill_groups);
+        let body = convert_body_from_database(&self.body.variant, &self.body.body_data)?;
+        let hardcore = convert_hardcore_from_database(self.character.hardcore)?;
+        let mut inventory = convert_inventory_from_database_items(
+            self.containers.inventory_container_id,
+            &self.inventory_items,
+            self.containers.loadout_container_id,
+            &self.loadout_items,
+            self.containers.overflow_items_container_id,
+            &self.overflow_items_items,
+            &self.recipe_book_items,
+        )?;
+        let storage = convert_storage_from_database_items(
+            self.containers.storage_container_id,
+            &self.storage_items,
+            &mut inventory,
+        )?;
+        Ok((
+            PersistedComponents {
+                body,
+                hardcore,
+                stats: convert_stats_from_database(self.character.alias, body),
+                skill_set,
+                inventory,
+                storage: comp::PersonalStorage::new(storage),
+                waypoint: char_waypoint,
+                pets: convert_pets_from_database(&self.pets, char_id),
+                active_abilities: convert_active_abilities_from_database(&self.ability_sets),
+                map_marker: char_map_marker,
+                reputation,
+            },
+            UpdateCharacterMetadata {
+                skill_set_persistence_load_error,
+            },
+        ))
+    }
+}
+
+/// Re-constructs the pet components for the player's pets, including
+/// de-serializing the pets' bodies and creating their Pet and Stats components
+fn convert_pets_from_database(db_pets: &[Pet], char_id: CharacterId) -> Vec<PetPersistenceData> {
+    db_pets
         .iter()
         .filter_map(|db_pet| {
             if let Ok(pet_body) =
@@ -271,50 +400,7 @@ pub fn load_character_data(
                 None
             }
         })
-        .collect::<Vec<(comp::Pet, comp::Body, comp::Stats)>>();
-
-    let mut stmt = connection.prepare_cached(
-        "
-            SELECT  ability_sets
-            FROM    ability_set
-            WHERE   entity_id = ?1",
-    )?;
-
-    let ability_set_data = stmt.query_row([char_id.0], |row| {
-        Ok(AbilitySets {
-            entity_id: char_id.0,
-            ability_sets: row.get(0)?,
-        })
-    })?;
-
-    let (skill_set, skill_set_persistence_load_error) =
-        convert_skill_set_from_database(&skill_group_data);
-    let body = convert_body_from_database(&body_data.variant, &body_data.body_data)?;
-    let hardcore = convert_hardcore_from_database(character_data.hardcore)?;
-    Ok((
-        PersistedComponents {
-            body,
-            hardcore,
-            stats: convert_stats_from_database(character_data.alias, body),
-            skill_set,
-            inventory: convert_inventory_from_database_items(
-                character_containers.inventory_container_id,
-                &inventory_items,
-                character_containers.loadout_container_id,
-                &loadout_items,
-                character_containers.overflow_items_container_id,
-                &overflow_items_items,
-                &recipe_book_items,
-            )?,
-            waypoint: char_waypoint,
-            pets,
-            active_abilities: convert_active_abilities_from_database(&ability_set_data),
-            map_marker: char_map_marker,
-        },
-        UpdateCharacterMetadata {
-            skill_set_persistence_load_error,
-        },
-    ))
+        .collect::<Vec<(comp::Pet, comp::Body, comp::Stats)>>()
 }
 
 /// Loads a list of characters belonging to the player. This data is a small
@@ -353,8 +439,6 @@ pub fn load_character_list(player_uuid_: &str, connection: &Connection) -> Chara
     characters
         .iter()
         .map(|character_data| {
-            let char = convert_character_from_database(character_data);
-
             let mut stmt = connection.prepare_cached(
                 "
                 SELECT  body_id,
@@ -363,7 +447,7 @@ pub fn load_character_list(player_uuid_: &str, connection: &Connection) -> Chara
                 FROM    body
                 WHERE   body_id = ?1",
             )?;
-            let db_body = stmt.query_row([char.id.map(|c| c.0)], |row| {
+            let db_body = stmt.query_row([character_data.character_id], |row| {
                 Ok(Body {
                     body_id: row.get(0)?,
                     variant: row.get(1)?,
@@ -372,10 +456,6 @@ pub fn load_character_list(player_uuid_: &str, connection: &Connection) -> Chara
             })?;
             drop(stmt);
 
-            let char_body = convert_body_from_database(&db_body.variant, &db_body.body_data)?;
-
-            let hardcore = convert_hardcore_from_database(character_data.hardcore)?;
-
             let loadout_container_id = get_pseudo_container_id(
                 connection,
                 CharacterId(character_data.character_id),
@@ -384,9 +464,6 @@ pub fn load_character_list(player_uuid_: &str, connection: &Connection) -> Chara
 
             let loadout_items = load_items(connection, loadout_container_id)?;
 
-            let loadout =
-                convert_loadout_from_database_items(loadout_container_id, &loadout_items)?;
-
             let recipe_book_container_id = get_pseudo_container_id(
                 connection,
                 CharacterId(character_data.character_id),
@@ -395,26 +472,46 @@ pub fn load_character_list(player_uuid_: &str, connection: &Connection) -> Chara
 
             let recipe_book_items = load_items(connection, recipe_book_container_id)?;
 
-            let (recipe_book, _) = convert_recipe_book_from_database_items(&recipe_book_items)?;
-
-            let (char_waypoint, _char_map_marker) = convert_waypoint_or_warn(
-                character_data.waypoint.as_deref(),
-                CharacterId(character_data.character_id),
-            );
-            let location = char_waypoint.map(|w| w.get_pos());
-
-            Ok(CharacterItem {
-                character: char,
-                body: char_body,
-                hardcore: hardcore.is_some(),
-                inventory: Inventory::with_loadout(loadout, char_body)
-                    .with_recipe_book(recipe_book),
-                location,
-            })
+            convert_character_list_item(
+                character_data,
+                &db_body,
+                loadout_container_id,
+                &loadout_items,
+                &recipe_book_items,
+            )
         })
         .collect()
 }
 
+/// Converts the rows of a character to its entry in the character list
+fn convert_character_list_item(
+    character_data: &Character,
+    db_body: &Body,
+    loadout_container_id: EntityId,
+    loadout_items: &[Item],
+    recipe_book_items: &[Item],
+) -> Result<CharacterItem<Vec3<f32>>, PersistenceError> {
+    let char = convert_character_from_database(character_data);
+    let char_body = convert_body_from_database(&db_body.variant, &db_body.body_data)?;
+    let hardcore = convert_hardcore_from_database(character_data.hardcore)?;
+    let loadout = convert_loadout_from_database_items(loadout_container_id, loadout_items)?;
+    let (recipe_book, _) = convert_recipe_book_from_database_items(recipe_book_items)?;
+
+    let (char_waypoint, _char_map_marker) = convert_waypoint_or_warn(
+        character_data.waypoint.as_deref(),
+        CharacterId(character_data.character_id),
+    );
+    let location = char_waypoint.map(|w| w.get_pos());
+
+    Ok(CharacterItem {
+        character: char,
+        body: char_body,
+        hardcore: hardcore.is_some(),
+        inventory: Inventory::with_loadout(loadout, char_body).with_recipe_book(recipe_book),
+        location,
+    })
+}
+
 pub fn create_character(
     uuid: &str,
     character_alias: &str,
@@ -429,15 +526,17 @@ pub fn create_character(
         stats: _,
         skill_set,
         inventory,
+        storage,
         waypoint,
         pets: _,
         active_abilities,
         map_marker,
+        reputation: _,
     } = persisted_components;
 
-    // Fetch new entity IDs for character, inventory, loadout, overflow items, and
-    // recipe book
-    let mut new_entity_ids = get_new_entity_ids(transaction, |next_id| next_id + 5)?;
+    // Fetch new entity IDs for character, inventory, loadout, overflow items,
+    // recipe book and storage
+    let mut new_entity_ids = get_new_entity_ids(transaction, |next_id| next_id + 6)?;
 
     // Create pseudo-container items for character
     let character_id = new_entity_ids.next().unwrap();
@@ -445,49 +544,15 @@ pub fn create_character(
     let loadout_container_id = new_entity_ids.next().unwrap();
     let overflow_items_container_id = new_entity_ids.next().unwrap();
     let recipe_book_container_id = new_entity_ids.next().unwrap();
+    let storage_container_id = new_entity_ids.next().unwrap();
 
-    let pseudo_containers = vec![
-        Item {
-            stack_size: 1,
-            item_id: character_id,
-            parent_container_item_id: WORLD_PSEUDO_CONTAINER_ID,
-            item_definition_id: CHARACTER_PSEUDO_CONTAINER_DEF_ID.to_owned(),
-            position: character_id.to_string(),
-            properties: String::new(),
-        },
-        Item {
-            stack_size: 1,
-            item_id: inventory_container_id,
-            parent_container_item_id: character_id,
-            item_definition_id: INVENTORY_PSEUDO_CONTAINER_DEF_ID.to_owned(),
-            position: INVENTORY_PSEUDO_CONTAINER_POSITION.to_owned(),
-            properties: String::new(),
-        },
-        Item {
-            stack_size: 1,
-            item_id: loadout_container_id,
-            parent_container_item_id: character_id,
-            item_definition_id: LOADOUT_PSEUDO_CONTAINER_DEF_ID.to_owned(),
-            position: LOADOUT_PSEUDO_CONTAINER_POSITION.to_owned(),
-            properties: String::new(),
-        },
-        Item {
-            stack_size: 1,
-            item_id: overflow_items_container_id,
-            parent_container_item_id: character_id,
-            item_definition_id: OVERFLOW_ITEMS_PSEUDO_CONTAINER_DEF_ID.to_owned(),
-            position: OVERFLOW_ITEMS_PSEUDO_CONTAINER_POSITION.to_owned(),
-            properties: String::new(),
-        },
-        Item {
-            stack_size: 1,
-            item_id: recipe_book_container_id,
-            parent_container_item_id: character_id,
-            item_definition_id: RECIPE_BOOK_PSEUDO_CONTAINER_DEF_ID.to_owned(),
-            position: RECIPE_BOOK_PSEUDO_CONTAINER_POSITION.to_owned(),
-            properties: String::new(),
-        },
-    ];
+    let pseudo_containers = pseudo_container_items(character_id, CharacterContainers {
+        inventory_container_id,
+        loadout_container_id,
+        overflow_items_container_id,
+        recipe_book_container_id,
+        storage_container_id,
+    });
 
     let mut stmt = transaction.prepare_cached(
         "
@@ -599,6 +664,8 @@ pub fn create_character(
             inventory_container_id,
             overflow_items_container_id,
             recipe_book_container_id,
+            &storage.inventory,
+            storage_container_id,
             &mut next_id,
         );
         inserts = inserts_;
@@ -631,6 +698,60 @@ pub fn create_character(
     load_character_list(uuid, transaction).map(|list| (CharacterId(character_id), list))
 }
 
+/// The pseudo-container items a new character's items are stored in
+fn pseudo_container_items(character_id: EntityId, containers: CharacterContainers) -> Vec<Item> {
+    vec![
+        Item {
+            stack_size: 1,
+            item_id: character_id,
+            parent_container_item_id: WORLD_PSEUDO_CONTAINER_ID,
+            item_definition_id: CHARACTER_PSEUDO_CONTAINER_DEF_ID.to_owned(),
+            position: character_id.to_string(),
+            properties: String::new(),
+        },
+        Item {
+            stack_size: 1,
+            item_id: containers.inventory_container_id,
+            parent_container_item_id: character_id,
+            item_definition_id: INVENTORY_PSEUDO_CONTAINER_DEF_ID.to_owned(),
+            position: INVENTORY_PSEUDO_CONTAINER_POSITION.to_owned(),
+            properties: String::new(),
+        },
+        Item {
+            stack_size: 1,
+            item_id: containers.loadout_container_id,
+            parent_container_item_id: character_id,
+            item_definition_id: LOADOUT_PSEUDO_CONTAINER_DEF_ID.to_owned(),
+            position: LOADOUT_PSEUDO_CONTAINER_POSITION.to_owned(),
+            properties: String::new(),
+        },
+        Item {
+            stack_size: 1,
+            item_id: containers.overflow_items_container_id,
+            parent_container_item_id: character_id,
+            item_definition_id: OVERFLOW_ITEMS_PSEUDO_CONTAINER_DEF_ID.to_owned(),
+            position: OVERFLOW_ITEMS_PSEUDO_CONTAINER_POSITION.to_owned(),
+            properties: String::new(),
+        },
+        Item {
+            stack_size: 1,
+            item_id: containers.recipe_book_container_id,
+            parent_container_item_id: character_id,
+            item_definition_id: RECIPE_BOOK_PSEUDO_CONTAINER_DEF_ID.to_owned(),
+            position: RECIPE_BOOK_PSEUDO_CONTAINER_POSITION.to_owned(),
+            properties: String::new(),
+        },
+        Item {
+            stack_size: 1,
+            item_id: containers.storage_container_id,
+            parent_container_item_id: character_id,
+            item_definition_id: STORAGE_PSEUDO_CONTAINER_DEF_ID.to_owned(),
+            position: STORAGE_PSEUDO_CONTAINER_POSITION.to_owned(),
+            properties: String::new(),
+        },
+    ]
+}
+
 pub fn edit_character(
     editable_components: EditableComponents,
     trusted_change: Option<PermanentChange>,
@@ -641,27 +762,13 @@ pub fn edit_character(
 ) -> CharacterCreationResult {
     let (body,) = editable_components;
     let mut char_list = load_character_list(uuid, transaction);
-
-    if let Ok(char_list) = &mut char_list
-        && let Some(char) = char_list
-            .iter_mut()
-            .find(|c| c.character.id == Some(character_id))
-        && let (comp::Body::Humanoid(new), comp::Body::Humanoid(old)) = (body, char.body)
-    {
-        let allow_change = match trusted_change {
-            Some(change) => change.expected_old_body == char.body,
-            None => new.species == old.species && new.body_type == old.body_type,
-        };
-        if !allow_change {
-            warn!(
-                "Character edit rejected due to failed validation - Character ID: {} Alias: {:?}",
-                character_id.0, character_alias
-            );
-            return Err(PersistenceError::CharacterDataError);
-        } else {
-            char.body = body;
-        }
-    }
+    validate_edit(
+        &mut char_list,
+        body,
+        trusted_change,
+        character_id,
+        character_alias,
+    )?;
 
     let mut stmt = transaction
         .prepare_cached("UPDATE body SET variant = ?1, body_data = ?2 WHERE body_id = ?3")?;
@@ -685,6 +792,39 @@ pub fn edit_character(
     char_list.map(|list| (character_id, list))
 }
 
+/// Checks that the body of a character may be changed like this, and updates
+/// the character list accordingly
+fn validate_edit(
+    char_list: &mut CharacterListResult,
+    body: comp::Body,
+    trusted_change: Option<PermanentChange>,
+    character_id: CharacterId,
+    character_alias: Option<&str>,
+) -> Result<(), PersistenceError> {
+    if let Ok(char_list) = char_list
+        && let Some(char) = char_list
+            .iter_mut()
+            .find(|c| c.character.id == Some(character_id))
+        && let (comp::Body::Humanoid(new), comp::Body::Humanoid(old)) = (body, char.body)
+    {
+        let allow_change = match trusted_change {
+            Some(change) => change.expected_old_body == char.body,
+            None => new.species == old.species && new.body_type == old.body_type,
+        };
+        if !allow_change {
+            warn!(
+                "Character edit rejected due to failed validation - Character ID: {} Alias: {:?}",
+                character_id.0, character_alias
+            );
+            return Err(PersistenceError::CharacterDataError);
+        } else {
+            char.body = body;
+        }
+    }
+
+    Ok(())
+}
+
 /// Permanently deletes a character
 pub fn delete_character(
     requesting_player_uuid: &str,
@@ -743,6 +883,60 @@ pub fn delete_character(
     stmt.execute([&char_id.0])?;
     drop(stmt);
 
+    // Delete reputation
+    let mut stmt = transaction.prepare_cached(
+        "
+        DELETE
+        FROM    character_reputation
+        WHERE   character_id = ?1",
+    )?;
+
+    stmt.execute([&char_id.0])?;
+    drop(stmt);
+
+    // Delete guild membership
+    let mut stmt = transaction.prepare_cached(
+        "
+        DELETE
+        FROM    guild_member
+        WHERE   character_id = ?1",
+    )?;
+
+    stmt.execute([&char_id.0])?;
+    drop(stmt);
+
+    // Delete the mail of the character, mail it sent stays with its recipients
+    let mut stmt = transaction.prepare_cached(
+        "
+        DELETE
+        FROM    mail
+        WHERE   recipient_id = ?1",
+    )?;
+
+    stmt.execute([&char_id.0])?;
+    drop(stmt);
+
+    let mut stmt = transaction.prepare_cached(
+        "
+        UPDATE  mail
+        SET     sender_id = NULL
+        WHERE   sender_id = ?1",
+    )?;
+
+    stmt.execute([&char_id.0])?;
+    drop(stmt);
+
+    // Take down the market listings of the character
+    let mut stmt = transaction.prepare_cached(
+        "
+        DELETE
+        FROM    market_listing
+        WHERE   seller_id = ?1",
+    )?;
+
+    stmt.execute([&char_id.0])?;
+    drop(stmt);
+
     // Delete character
     let mut stmt = transaction.prepare_cached(
         "
@@ -896,6 +1090,11 @@ fn get_pseudo_containers(
             character_id,
             RECIPE_BOOK_PSEUDO_CONTAINER_POSITION,
         )?,
+        storage_container_id: get_pseudo_container_id(
+            connection,
+            character_id,
+            STORAGE_PSEUDO_CONTAINER_POSITION,
+        )?,
     };
 
     Ok(character_containers)
@@ -950,16 +1149,8 @@ fn update_pets(
     let db_pets = get_pet_ids(char_id, transaction)?;
     if !db_pets.is_empty() {
         let dead_pet_ids = Rc::new(
-            db_pets
-                .iter()
-                .filter(|pet_id| {
-                    !pets.iter().any(|(pet, _, _)| {
-                        pet.get_database_id()
-                            .load()
-                            .is_some_and(|x| x.get() == **pet_id as u64)
-                    })
-                })
-                .map(|x| Value::from(*x))
+            dead_pet_ids(&db_pets, &pets)
+                .map(Value::from)
                 .collect::<Vec<Value>>(),
         );
 
@@ -1014,6 +1205,20 @@ fn update_pets(
     Ok(())
 }
 
+/// The ids of the pets in the database that the player no longer has
+fn dead_pet_ids<'a>(
+    db_pets: &'a [i64],
+    pets: &'a [PetPersistenceData],
+) -> impl Iterator<Item = i64> + 'a {
+    db_pets.iter().copied().filter(|pet_id| {
+        !pets.iter().any(|(pet, _, _)| {
+            pet.get_database_id()
+                .load()
+                .is_some_and(|x| x.get() == *pet_id as u64)
+        })
+    })
+}
+
 fn get_pet_ids(
     char_id: CharacterId,
     transaction: &mut Transaction,
@@ -1073,10 +1278,12 @@ pub fn update(
     char_id: CharacterId,
     char_skill_set: comp::SkillSet,
     inventory: Inventory,
+    storage: Inventory,
     pets: Vec<PetPersistenceData>,
     char_waypoint: Option<comp::Waypoint>,
     active_abilities: comp::ability::ActiveAbilities,
     map_marker: Option<comp::MapMarker>,
+    reputation: PersistedReputation,
     transaction: &mut Transaction,
 ) -> Result<(), PersistenceError> {
     // Run pet persistence
@@ -1093,6 +1300,8 @@ pub fn update(
             pseudo_containers.inventory_container_id,
             pseudo_containers.overflow_items_container_id,
             pseudo_containers.recipe_book_container_id,
+            &storage,
+            pseudo_containers.storage_container_id,
             &mut next_id,
         );
         upserts = upserts_;
@@ -1106,6 +1315,7 @@ pub fn update(
         Value::from(pseudo_containers.loadout_container_id),
         Value::from(pseudo_containers.overflow_items_container_id),
         Value::from(pseudo_containers.recipe_book_container_id),
+        Value::from(pseudo_containers.storage_container_id),
     ];
     for it in load_items(transaction, pseudo_containers.inventory_container_id)? {
         existing_item_ids.push(Value::from(it.item_id));
@@ -1119,6 +1329,9 @@ pub fn update(
     for it in load_items(transaction, pseudo_containers.recipe_book_container_id)? {
         existing_item_ids.push(Value::from(it.item_id));
     }
+    for it in load_items(transaction, pseudo_containers.storage_container_id)? {
+        existing_item_ids.push(Value::from(it.item_id));
+    }
 
     let non_upserted_items = upserts
         .iter()
@@ -1252,6 +1465,19 @@ pub fn update(
             char_id.0,
         )));
     }
+    drop(stmt);
+
+    let db_reputation = convert_reputation_to_database_json(&reputation)?;
+
+    let mut stmt = transaction.prepare_cached(
+        "
+        REPLACE
+        INTO    character_reputation (character_id,
+                                      reputation)
+        VALUES  (?1, ?2)",
+    )?;
+
+    stmt.execute([&char_id.0 as &dyn ToSql, &db_reputation])?;
 
     Ok(())
 }
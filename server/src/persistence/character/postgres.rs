@@ -0,0 +1,1048 @@
+//! PostgreSQL versions of the database operations of the parent module, used by
+//! [`PostgresBackend`](crate::persistence::backend::PostgresBackend).
+//!
+//! The queries mirror their SQLite counterparts while the conversions between
+//! rows and components are shared, see `migrations_postgres` for how the
+//! schemas differ.
+
+use super::{
+    CharacterContainers, CharacterRows, EntityId, INVENTORY_PSEUDO_CONTAINER_POSITION,
+    LOADOUT_PSEUDO_CONTAINER_POSITION, OVERFLOW_ITEMS_PSEUDO_CONTAINER_POSITION,
+    RECIPE_BOOK_PSEUDO_CONTAINER_POSITION, STORAGE_PSEUDO_CONTAINER_POSITION,
+    conversions::{
+        convert_active_abilities_to_database, convert_body_to_database_json,
+        convert_hardcore_to_database, convert_items_to_database_items,
+        convert_reputation_to_database_json, convert_skill_groups_to_database,
+        convert_waypoint_to_database_json,
+    },
+    convert_character_list_item, dead_pet_ids, pseudo_container_items,
+    transfer::{
+        CONTAINERS, CharacterExport, ExportedBody, ExportedCharacterRow, ExportedSkillGroup,
+        ImportedCharacter, already_imported, build_export, exported_items, prepare_import,
+        select_character,
+    },
+    validate_edit,
+};
+use crate::persistence::{
+    EditableComponents, PersistedComponents,
+    character_loader::{CharacterCreationResult, CharacterDataResult, CharacterListResult},
+    character_updater::PetPersistenceData,
+    error::PersistenceError,
+    models::*,
+};
+use common::{
+    character::{CharacterId, MAX_CHARACTERS_PER_PLAYER},
+    comp::{self, Inventory},
+    event::PermanentChange,
+    rtsim::PersistedReputation,
+};
+use core::ops::Range;
+use postgres::{GenericClient, Transaction};
+use std::num::NonZeroU64;
+use tracing::{debug, trace};
+
+const INSERT_ITEM: &str = "
+    INSERT INTO item (item_id,
+                      parent_container_item_id,
+                      item_definition_id,
+                      stack_size,
+                      position,
+                      properties)
+    VALUES ($1, $2, $3, $4, $5, $6)";
+
+/// See [`super::load_items`]
+pub fn load_items(
+    client: &mut impl GenericClient,
+    root: EntityId,
+) -> Result<Vec<Item>, PersistenceError> {
+    let rows = client.query(
+        "
+        WITH RECURSIVE
+        items_tree (
+            item_id,
+            parent_container_item_id,
+            item_definition_id,
+            stack_size,
+            position,
+            properties
+        ) AS (
+            SELECT  item_id,
+                    parent_container_item_id,
+                    item_definition_id,
+                    stack_size,
+                    position,
+                    properties
+            FROM item
+            WHERE parent_container_item_id = $1
+            UNION ALL
+            SELECT  item.item_id,
+                    item.parent_container_item_id,
+                    item.item_definition_id,
+                    item.stack_size,
+                    item.position,
+                    item.properties
+            FROM item, items_tree
+            WHERE item.parent_container_item_id = items_tree.item_id
+        )
+        SELECT  *
+        FROM    items_tree",
+        &[&root],
+    )?;
+
+    Ok(rows
+        .iter()
+        .map(|row| Item {
+            item_id: row.get(0),
+            parent_container_item_id: row.get(1),
+            item_definition_id: row.get(2),
+            stack_size: row.get(3),
+            position: row.get(4),
+            properties: row.get(5),
+        })
+        .collect())
+}
+
+/// See [`super::load_character_data`]
+pub fn load_character_data(
+    requesting_player_uuid: String,
+    char_id: CharacterId,
+    client: &mut impl GenericClient,
+) -> CharacterDataResult {
+    let character_containers = get_pseudo_containers(client, char_id)?;
+    let inventory_items = load_items(client, character_containers.inventory_container_id)?;
+    let loadout_items = load_items(client, character_containers.loadout_container_id)?;
+    let overflow_items_items =
+        load_items(client, character_containers.overflow_items_container_id)?;
+    let recipe_book_items = load_items(client, character_containers.recipe_book_container_id)?;
+    let storage_items = load_items(client, character_containers.storage_container_id)?;
+
+    let row = client.query_one(
+        "
+        SELECT  c.character_id,
+                c.alias,
+                c.waypoint,
+                c.hardcore,
+                b.variant,
+                b.body_data
+        FROM    character c
+        JOIN    body b ON (c.character_id = b.body_id)
+        WHERE   c.player_uuid = $1
+        AND     c.character_id = $2",
+        &[&requesting_player_uuid, &char_id.0],
+    )?;
+    let character_data = Character {
+        character_id: row.get(0),
+        player_uuid: requesting_player_uuid,
+        alias: row.get(1),
+        waypoint: row.get(2),
+        hardcore: row.get(3),
+    };
+    let body_data = Body {
+        body_id: row.get(0),
+        variant: row.get(4),
+        body_data: row.get(5),
+    };
+
+    let skill_group_data = client
+        .query(
+            "
+            SELECT  skill_group_kind,
+                    earned_exp,
+                    spent_exp,
+                    skills,
+                    hash_val
+            FROM    skill_group
+            WHERE   entity_id = $1",
+            &[&char_id.0],
+        )?
+        .iter()
+        .map(|row| SkillGroup {
+            entity_id: char_id.0,
+            skill_group_kind: row.get(0),
+            earned_exp: row.get(1),
+            spent_exp: row.get(2),
+            skills: row.get(3),
+            hash_val: row.get(4),
+        })
+        .collect();
+
+    let db_pets = client
+        .query(
+            "
+            SELECT  p.pet_id,
+                    p.name,
+                    b.variant,
+                    b.body_data
+            FROM    pet p
+            JOIN    body b ON (p.pet_id = b.body_id)
+            WHERE   p.character_id = $1",
+            &[&char_id.0],
+        )?
+        .iter()
+        .map(|row| Pet {
+            database_id: row.get(0),
+            name: row.get(1),
+            body_variant: row.get(2),
+            body_data: row.get(3),
+        })
+        .collect();
+
+    let ability_set_data = AbilitySets {
+        entity_id: char_id.0,
+        ability_sets: client
+            .query_one(
+                "
+                SELECT  ability_sets
+                FROM    ability_set
+                WHERE   entity_id = $1",
+                &[&char_id.0],
+            )?
+            .get(0),
+    };
+
+    let reputation = client
+        .query_opt(
+            "
+            SELECT  reputation
+            FROM    character_reputation
+            WHERE   character_id = $1",
+            &[&char_id.0],
+        )?
+        .map(|row| row.get(0));
+
+    CharacterRows {
+        character: character_data,
+        body: body_data,
+        containers: character_containers,
+        inventory_items,
+        loadout_items,
+        overflow_items_items,
+        recipe_book_items,
+        storage_items,
+        skill_groups: skill_group_data,
+        pets: db_pets,
+        ability_sets: ability_set_data,
+        reputation,
+    }
+    .into_character_data()
+}
+
+/// See [`super::load_character_list`]
+pub fn load_character_list(
+    player_uuid_: &str,
+    client: &mut impl GenericClient,
+) -> CharacterListResult {
+    let characters = client
+        .query(
+            "
+            SELECT  character_id,
+                    alias,
+                    waypoint,
+                    hardcore
+            FROM    character
+            WHERE   player_uuid = $1
+            ORDER BY character_id",
+            &[&player_uuid_],
+        )?
+        .iter()
+        .map(|row| Character {
+            character_id: row.get(0),
+            alias: row.get(1),
+            player_uuid: player_uuid_.to_owned(),
+            waypoint: row.get(2),
+            hardcore: row.get(3),
+        })
+        .collect::<Vec<Character>>();
+
+    characters
+        .iter()
+        .map(|character_data| {
+            let row = client.query_one(
+                "
+                SELECT  body_id,
+                        variant,
+                        body_data
+                FROM    body
+                WHERE   body_id = $1",
+                &[&character_data.character_id],
+            )?;
+            let db_body = Body {
+                body_id: row.get(0),
+                variant: row.get(1),
+                body_data: row.get(2),
+            };
+
+            let loadout_container_id = get_pseudo_container_id(
+                client,
+                CharacterId(character_data.character_id),
+                LOADOUT_PSEUDO_CONTAINER_POSITION,
+            )?;
+            let loadout_items = load_items(client, loadout_container_id)?;
+
+            let recipe_book_container_id = get_pseudo_container_id(
+                client,
+                CharacterId(character_data.character_id),
+                RECIPE_BOOK_PSEUDO_CONTAINER_POSITION,
+            )?;
+            let recipe_book_items = load_items(client, recipe_book_container_id)?;
+
+            convert_character_list_item(
+                character_data,
+                &db_body,
+                loadout_container_id,
+                &loadout_items,
+                &recipe_book_items,
+            )
+        })
+        .collect()
+}
+
+/// See [`super::create_character`]
+pub fn create_character(
+    uuid: &str,
+    character_alias: &str,
+    persisted_components: PersistedComponents,
+    transaction: &mut Transaction,
+) -> CharacterCreationResult {
+    check_character_limit(uuid, transaction)?;
+
+    let PersistedComponents {
+        body,
+        hardcore,
+        stats: _,
+        skill_set,
+        inventory,
+        storage,
+        waypoint,
+        pets: _,
+        active_abilities,
+        map_marker,
+        reputation: _,
+    } = persisted_components;
+
+    // Fetch new entity IDs for character, inventory, loadout, overflow items,
+    // recipe book and storage
+    let mut new_entity_ids = get_new_entity_ids(transaction, |next_id| next_id + 6)?;
+    let character_id = new_entity_ids.next().unwrap();
+    let containers = CharacterContainers {
+        inventory_container_id: new_entity_ids.next().unwrap(),
+        loadout_container_id: new_entity_ids.next().unwrap(),
+        overflow_items_container_id: new_entity_ids.next().unwrap(),
+        recipe_book_container_id: new_entity_ids.next().unwrap(),
+        storage_container_id: new_entity_ids.next().unwrap(),
+    };
+
+    let stmt = transaction.prepare(INSERT_ITEM)?;
+    for pseudo_container in pseudo_container_items(character_id, containers) {
+        transaction.execute(&stmt, &[
+            &pseudo_container.item_id,
+            &pseudo_container.parent_container_item_id,
+            &pseudo_container.item_definition_id,
+            &pseudo_container.stack_size,
+            &pseudo_container.position,
+            &pseudo_container.properties,
+        ])?;
+    }
+
+    let (body_variant, body_json) = convert_body_to_database_json(&body)?;
+    transaction.execute(
+        "
+        INSERT INTO body (body_id,
+                          variant,
+                          body_data)
+        VALUES ($1, $2, $3)",
+        &[&character_id, &body_variant.to_string(), &body_json],
+    )?;
+
+    transaction.execute(
+        "
+        INSERT INTO character (character_id,
+                               player_uuid,
+                               alias,
+                               waypoint,
+                               hardcore)
+        VALUES ($1, $2, $3, $4, $5)",
+        &[
+            &character_id,
+            &uuid,
+            &character_alias,
+            &convert_waypoint_to_database_json(waypoint, map_marker),
+            &convert_hardcore_to_database(hardcore),
+        ],
+    )?;
+
+    let db_skill_groups =
+        convert_skill_groups_to_database(CharacterId(character_id), skill_set.skill_groups());
+    let skill_group_stmt = transaction.prepare(
+        "
+        INSERT INTO skill_group (entity_id,
+                                 skill_group_kind,
+                                 earned_exp,
+                                 spent_exp,
+                                 skills,
+                                 hash_val)
+        VALUES ($1, $2, $3, $4, $5, $6)",
+    )?;
+    for skill_group in db_skill_groups {
+        transaction.execute(&skill_group_stmt, &[
+            &character_id,
+            &skill_group.skill_group_kind,
+            &skill_group.earned_exp,
+            &skill_group.spent_exp,
+            &skill_group.skills,
+            &skill_group.hash_val,
+        ])?;
+    }
+
+    let ability_sets =
+        convert_active_abilities_to_database(CharacterId(character_id), &active_abilities);
+    transaction.execute(
+        "
+        INSERT INTO ability_set (entity_id,
+                                 ability_sets)
+        VALUES ($1, $2)",
+        &[&character_id, &ability_sets.ability_sets],
+    )?;
+
+    // Insert default inventory and loadout item records
+    let mut inserts = Vec::new();
+    get_new_entity_ids(transaction, |mut next_id| {
+        inserts = convert_items_to_database_items(
+            containers.loadout_container_id,
+            &inventory,
+            containers.inventory_container_id,
+            containers.overflow_items_container_id,
+            containers.recipe_book_container_id,
+            &storage.inventory,
+            containers.storage_container_id,
+            &mut next_id,
+        );
+        next_id
+    })?;
+
+    for item in inserts {
+        transaction.execute(&stmt, &[
+            &item.model.item_id,
+            &item.model.parent_container_item_id,
+            &item.model.item_definition_id,
+            &item.model.stack_size,
+            &item.model.position,
+            &item.model.properties,
+        ])?;
+    }
+
+    load_character_list(uuid, transaction).map(|list| (CharacterId(character_id), list))
+}
+
+/// See [`super::edit_character`]
+pub fn edit_character(
+    editable_components: EditableComponents,
+    trusted_change: Option<PermanentChange>,
+    transaction: &mut Transaction,
+    character_id: CharacterId,
+    uuid: &str,
+    character_alias: Option<&str>,
+) -> CharacterCreationResult {
+    let (body,) = editable_components;
+    let mut char_list = load_character_list(uuid, transaction);
+    validate_edit(
+        &mut char_list,
+        body,
+        trusted_change,
+        character_id,
+        character_alias,
+    )?;
+
+    let (body_variant, body_data) = convert_body_to_database_json(&body)?;
+    transaction.execute(
+        "UPDATE body SET variant = $1, body_data = $2 WHERE body_id = $3",
+        &[&body_variant.to_string(), &body_data, &character_id.0],
+    )?;
+
+    if let Some(character_alias) = character_alias {
+        transaction.execute(
+            "UPDATE character SET alias = $1 WHERE character_id = $2",
+            &[&character_alias, &character_id.0],
+        )?;
+    }
+
+    char_list.map(|list| (character_id, list))
+}
+
+/// See [`super::delete_character`]
+pub fn delete_character(
+    requesting_player_uuid: &str,
+    char_id: CharacterId,
+    transaction: &mut Transaction,
+) -> Result<(), PersistenceError> {
+    debug!(?requesting_player_uuid, ?char_id, "Deleting character");
+
+    let result: i64 = transaction
+        .query_one(
+            "
+            SELECT  COUNT(1)
+            FROM    character
+            WHERE   character_id = $1
+            AND     player_uuid = $2",
+            &[&char_id.0, &requesting_player_uuid],
+        )?
+        .get(0);
+
+    if result != 1 {
+        // The character does not exist, or does not belong to the requesting player so
+        // silently drop the request.
+        return Ok(());
+    }
+
+    transaction.execute(
+        "DELETE FROM skill_group WHERE entity_id = $1",
+        &[&char_id.0],
+    )?;
+
+    let pet_ids = get_pet_ids(char_id, transaction)?;
+    if !pet_ids.is_empty() {
+        delete_pets(transaction, char_id, &pet_ids)?;
+    }
+
+    transaction.execute(
+        "DELETE FROM ability_set WHERE entity_id = $1",
+        &[&char_id.0],
+    )?;
+    transaction.execute(
+        "DELETE FROM character_reputation WHERE character_id = $1",
+        &[&char_id.0],
+    )?;
+    transaction.execute("DELETE FROM guild_member WHERE character_id = $1", &[
+        &char_id.0,
+    ])?;
+    transaction.execute("DELETE FROM mail WHERE recipient_id = $1", &[&char_id.0])?;
+    transaction.execute("UPDATE mail SET sender_id = NULL WHERE sender_id = $1", &[
+        &char_id.0,
+    ])?;
+    transaction.execute("DELETE FROM market_listing WHERE seller_id = $1", &[
+        &char_id.0,
+    ])?;
+    transaction.execute("DELETE FROM character WHERE character_id = $1", &[
+        &char_id.0
+    ])?;
+    transaction.execute("DELETE FROM body WHERE body_id = $1", &[&char_id.0])?;
+
+    // Delete all items, recursively walking all containers starting from the
+    // "character" pseudo-container that is the root for all items owned by
+    // a character.
+    let deleted_item_count = transaction.execute(
+        "
+        WITH RECURSIVE
+        parents AS (
+            SELECT  item_id
+            FROM    item
+            WHERE   item.item_id = $1 -- Item with character id is the character pseudo-container
+            UNION ALL
+            SELECT  item.item_id
+            FROM    item,
+                    parents
+            WHERE   item.parent_container_item_id = parents.item_id
+        )
+        DELETE
+        FROM    item
+        WHERE   item_id IN (SELECT item_id FROM parents)",
+        &[&char_id.0],
+    )?;
+
+    if deleted_item_count < 3 {
+        return Err(PersistenceError::OtherError(format!(
+            "Error deleting from item table for char_id {} (expected at least 3 deletions, found \
+             {})",
+            char_id.0, deleted_item_count
+        )));
+    }
+
+    Ok(())
+}
+
+/// See [`super::check_character_limit`]
+fn check_character_limit(
+    uuid: &str,
+    transaction: &mut Transaction,
+) -> Result<(), PersistenceError> {
+    let character_count: i64 = transaction
+        .query_one(
+            "
+            SELECT  COUNT(1)
+            FROM    character
+            WHERE   player_uuid = $1",
+            &[&uuid],
+        )?
+        .get(0);
+
+    if character_count < MAX_CHARACTERS_PER_PLAYER as i64 {
+        Ok(())
+    } else {
+        Err(PersistenceError::CharacterLimitReached)
+    }
+}
+
+/// See [`super::get_new_entity_ids`]
+fn get_new_entity_ids(
+    transaction: &mut Transaction,
+    mut max: impl FnMut(i64) -> i64,
+) -> Result<Range<EntityId>, PersistenceError> {
+    // Entities are never deleted, so the highest entity id is never reused. The
+    // lock is held until the end of the transaction and keeps other
+    // transactions from taking the same ids.
+    transaction.batch_execute("LOCK TABLE entity IN EXCLUSIVE MODE")?;
+    let next_entity_id: i64 = transaction
+        .query_one("SELECT COALESCE(MAX(entity_id), 0) + 1 FROM entity", &[])?
+        .get(0);
+    let max_entity_id = max(next_entity_id);
+
+    let new_ids: Range<EntityId> = next_entity_id..max_entity_id;
+    transaction.execute(
+        "INSERT INTO entity (entity_id) SELECT generate_series($1::BIGINT, $2::BIGINT - 1)",
+        &[&new_ids.start, &new_ids.end],
+    )?;
+
+    trace!(
+        "Created {} new persistence entity_ids: {:?}",
+        new_ids.end - new_ids.start,
+        new_ids
+    );
+    Ok(new_ids)
+}
+
+fn get_pseudo_containers(
+    client: &mut impl GenericClient,
+    character_id: CharacterId,
+) -> Result<CharacterContainers, PersistenceError> {
+    Ok(CharacterContainers {
+        loadout_container_id: get_pseudo_container_id(
+            client,
+            character_id,
+            LOADOUT_PSEUDO_CONTAINER_POSITION,
+        )?,
+        inventory_container_id: get_pseudo_container_id(
+            client,
+            character_id,
+            INVENTORY_PSEUDO_CONTAINER_POSITION,
+        )?,
+        overflow_items_container_id: get_pseudo_container_id(
+            client,
+            character_id,
+            OVERFLOW_ITEMS_PSEUDO_CONTAINER_POSITION,
+        )?,
+        recipe_book_container_id: get_pseudo_container_id(
+            client,
+            character_id,
+            RECIPE_BOOK_PSEUDO_CONTAINER_POSITION,
+        )?,
+        storage_container_id: get_pseudo_container_id(
+            client,
+            character_id,
+            STORAGE_PSEUDO_CONTAINER_POSITION,
+        )?,
+    })
+}
+
+fn get_pseudo_container_id(
+    client: &mut impl GenericClient,
+    character_id: CharacterId,
+    pseudo_container_position: &str,
+) -> Result<EntityId, PersistenceError> {
+    Ok(client
+        .query_one(
+            "
+            SELECT  item_id
+            FROM    item
+            WHERE   parent_container_item_id = $1
+            AND     position = $2",
+            &[&character_id.0, &pseudo_container_position],
+        )?
+        .get(0))
+}
+
+/// See [`super::update_pets`]
+fn update_pets(
+    char_id: CharacterId,
+    pets: Vec<PetPersistenceData>,
+    transaction: &mut Transaction,
+) -> Result<(), PersistenceError> {
+    debug!("Updating {} pets for character {}", pets.len(), char_id.0);
+
+    let db_pets = get_pet_ids(char_id, transaction)?;
+    let dead_pet_ids = dead_pet_ids(&db_pets, &pets).collect::<Vec<_>>();
+    if !dead_pet_ids.is_empty() {
+        delete_pets(transaction, char_id, &dead_pet_ids)?;
+    }
+
+    for (pet, body, _stats) in pets
+        .iter()
+        .filter(|(pet, _, _)| pet.get_database_id().load().is_none())
+    {
+        let pet_entity_id = get_new_entity_ids(transaction, |next_id| next_id + 1)?.start;
+
+        let (body_variant, body_json) = convert_body_to_database_json(body)?;
+        transaction.execute(
+            "INSERT INTO body (body_id, variant, body_data) VALUES ($1, $2, $3)",
+            &[&pet_entity_id, &body_variant.to_string(), &body_json],
+        )?;
+
+        // TODO: use pet names here, when such feature will be implemented
+        let pet_name = "";
+        transaction.execute(
+            "INSERT INTO pet (pet_id, character_id, name) VALUES ($1, $2, $3)",
+            &[&pet_entity_id, &char_id.0, &pet_name],
+        )?;
+
+        pet.get_database_id()
+            .store(NonZeroU64::new(pet_entity_id as u64));
+    }
+
+    Ok(())
+}
+
+fn get_pet_ids(
+    char_id: CharacterId,
+    transaction: &mut Transaction,
+) -> Result<Vec<i64>, PersistenceError> {
+    Ok(transaction
+        .query("SELECT pet_id FROM pet WHERE character_id = $1", &[
+            &char_id.0,
+        ])?
+        .iter()
+        .map(|row| row.get(0))
+        .collect())
+}
+
+fn delete_pets(
+    transaction: &mut Transaction,
+    char_id: CharacterId,
+    pet_ids: &[i64],
+) -> Result<(), PersistenceError> {
+    let delete_count =
+        transaction.execute("DELETE FROM pet WHERE pet_id = ANY($1)", &[&pet_ids])?;
+    debug!(
+        "Deleted {} pets for character id {}",
+        delete_count, char_id.0
+    );
+
+    let delete_count =
+        transaction.execute("DELETE FROM body WHERE body_id = ANY($1)", &[&pet_ids])?;
+    debug!(
+        "Deleted {} pet bodies for character id {}",
+        delete_count, char_id.0
+    );
+
+    Ok(())
+}
+
+/// See [`super::update`]
+pub fn update(
+    char_id: CharacterId,
+    char_skill_set: comp::SkillSet,
+    inventory: Inventory,
+    storage: Inventory,
+    pets: Vec<PetPersistenceData>,
+    char_waypoint: Option<comp::Waypoint>,
+    active_abilities: comp::ability::ActiveAbilities,
+    map_marker: Option<comp::MapMarker>,
+    reputation: PersistedReputation,
+    transaction: &mut Transaction,
+) -> Result<(), PersistenceError> {
+    // Moving items around can violate the foreign and unique keys of the item
+    // table until all items are written, the constraints are deferrable so they
+    // can be checked when the transaction commits instead.
+    transaction.batch_execute("SET CONSTRAINTS ALL DEFERRED")?;
+
+    update_pets(char_id, pets, transaction)?;
+
+    let pseudo_containers = get_pseudo_containers(transaction, char_id)?;
+    let mut upserts = Vec::new();
+    // First, get all the entity IDs for any new items, and identify which
+    // slots to upsert and which ones to delete.
+    get_new_entity_ids(transaction, |mut next_id| {
+        upserts = convert_items_to_database_items(
+            pseudo_containers.loadout_container_id,
+            &inventory,
+            pseudo_containers.inventory_container_id,
+            pseudo_containers.overflow_items_container_id,
+            pseudo_containers.recipe_book_container_id,
+            &storage,
+            pseudo_containers.storage_container_id,
+            &mut next_id,
+        );
+        next_id
+    })?;
+
+    // Next, delete any slots we aren't upserting.
+    trace!("Deleting items for character_id {}", char_id.0);
+    let mut existing_item_ids = vec![
+        pseudo_containers.inventory_container_id,
+        pseudo_containers.loadout_container_id,
+        pseudo_containers.overflow_items_container_id,
+        pseudo_containers.recipe_book_container_id,
+        pseudo_containers.storage_container_id,
+    ];
+    for container_id in existing_item_ids.clone() {
+        existing_item_ids.extend(
+            load_items(transaction, container_id)?
+                .iter()
+                .map(|item| item.item_id),
+        );
+    }
+    let non_upserted_items = upserts
+        .iter()
+        .map(|item_pair| item_pair.model.item_id)
+        .collect::<Vec<_>>();
+
+    let delete_count = transaction.execute(
+        "
+        DELETE
+        FROM    item
+        WHERE   parent_container_item_id = ANY($1)
+        AND     item_id <> ALL($2)",
+        &[&existing_item_ids, &non_upserted_items],
+    )?;
+    trace!("Deleted {} items", delete_count);
+
+    // Upsert items
+    if !upserts.is_empty() {
+        trace!(
+            "Upserting {} items for character_id {}",
+            upserts.len(),
+            char_id.0
+        );
+        let stmt = transaction.prepare(
+            "
+            INSERT
+            INTO    item (item_id,
+                          parent_container_item_id,
+                          item_definition_id,
+                          stack_size,
+                          position,
+                          properties)
+            VALUES  ($1, $2, $3, $4, $5, $6)
+            ON CONFLICT (item_id) DO UPDATE
+            SET     parent_container_item_id = EXCLUDED.parent_container_item_id,
+                    item_definition_id = EXCLUDED.item_definition_id,
+                    stack_size = EXCLUDED.stack_size,
+                    position = EXCLUDED.position,
+                    properties = EXCLUDED.properties",
+        )?;
+
+        for item in upserts.iter().map(|model_pair| &model_pair.model) {
+            transaction.execute(&stmt, &[
+                &item.item_id,
+                &item.parent_container_item_id,
+                &item.item_definition_id,
+                &item.stack_size,
+                &item.position,
+                &item.properties,
+            ])?;
+        }
+    }
+
+    let db_skill_groups = convert_skill_groups_to_database(char_id, char_skill_set.skill_groups());
+    let stmt = transaction.prepare(
+        "
+        INSERT
+        INTO    skill_group (entity_id,
+                             skill_group_kind,
+                             earned_exp,
+                             spent_exp,
+                             skills,
+                             hash_val)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        ON CONFLICT (entity_id, skill_group_kind) DO UPDATE
+        SET     earned_exp = EXCLUDED.earned_exp,
+                spent_exp = EXCLUDED.spent_exp,
+                skills = EXCLUDED.skills,
+                hash_val = EXCLUDED.hash_val",
+    )?;
+    for skill_group in db_skill_groups {
+        transaction.execute(&stmt, &[
+            &skill_group.entity_id,
+            &skill_group.skill_group_kind,
+            &skill_group.earned_exp,
+            &skill_group.spent_exp,
+            &skill_group.skills,
+            &skill_group.hash_val,
+        ])?;
+    }
+
+    let db_waypoint = convert_waypoint_to_database_json(char_waypoint, map_marker);
+    let waypoint_count = transaction.execute(
+        "UPDATE character SET waypoint = $1 WHERE character_id = $2",
+        &[&db_waypoint, &char_id.0],
+    )?;
+    if waypoint_count != 1 {
+        return Err(PersistenceError::OtherError(format!(
+            "Error updating character table for char_id {}",
+            char_id.0
+        )));
+    }
+
+    let ability_sets = convert_active_abilities_to_database(char_id, &active_abilities);
+    let ability_sets_count = transaction.execute(
+        "UPDATE ability_set SET ability_sets = $1 WHERE entity_id = $2",
+        &[&ability_sets.ability_sets, &char_id.0],
+    )?;
+    if ability_sets_count != 1 {
+        return Err(PersistenceError::OtherError(format!(
+            "Error updating ability_set table for char_id {}",
+            char_id.0,
+        )));
+    }
+
+    let db_reputation = convert_reputation_to_database_json(&reputation)?;
+    transaction.execute(
+        "
+        INSERT
+        INTO    character_reputation (character_id,
+                                      reputation)
+        VALUES  ($1, $2)
+        ON CONFLICT (character_id) DO UPDATE
+        SET     reputation = EXCLUDED.reputation",
+        &[&char_id.0, &db_reputation],
+    )?;
+
+    Ok(())
+}
+
+/// The newest migration applied to the database
+fn schema_version(client: &mut impl GenericClient) -> Result<i64, PersistenceError> {
+    Ok(client
+        .query_one(
+            "SELECT COALESCE(MAX(version), 0)::BIGINT FROM refinery_schema_history",
+            &[],
+        )?
+        .get(0))
+}
+
+/// See [`super::transfer::export_character`]
+pub fn export_character(
+    requesting_player_uuid: &str,
+    character: &str,
+    server_name: &str,
+    client: &mut impl GenericClient,
+) -> Result<CharacterExport, PersistenceError> {
+    let rows = client
+        .query(
+            "
+            SELECT  c.character_id,
+                    c.alias,
+                    c.waypoint,
+                    c.hardcore,
+                    b.variant,
+                    b.body_data
+            FROM    character c
+            JOIN    body b ON (c.character_id = b.body_id)
+            WHERE   c.player_uuid = $1",
+            &[&requesting_player_uuid],
+        )?
+        .iter()
+        .map(|row| {
+            (
+                row.get(0),
+                row.get(1),
+                row.get(2),
+                row.get(3),
+                ExportedBody {
+                    variant: row.get(4),
+                    data: row.get(5),
+                },
+            )
+        })
+        .collect::<Vec<ExportedCharacterRow>>();
+    let exported_character = select_character(rows, character)?;
+    let character_id = exported_character.0;
+
+    let skill_groups = client
+        .query(
+            "
+            SELECT  skill_group_kind,
+                    earned_exp,
+                    spent_exp,
+                    skills,
+                    hash_val
+            FROM    skill_group
+            WHERE   entity_id = $1",
+            &[&character_id],
+        )?
+        .iter()
+        .map(|row| ExportedSkillGroup {
+            kind: row.get(0),
+            earned_exp: row.get(1),
+            spent_exp: row.get(2),
+            skills: row.get(3),
+            hash_val: row.get(4),
+        })
+        .collect::<Vec<ExportedSkillGroup>>();
+
+    let ability_sets: String = client
+        .query_one(
+            "SELECT ability_sets FROM ability_set WHERE entity_id = $1",
+            &[&character_id],
+        )?
+        .get(0);
+
+    let mut items = Vec::new();
+    for (container, position) in CONTAINERS {
+        let container_id = get_pseudo_container_id(client, CharacterId(character_id), position)?;
+        items.extend(exported_items(
+            container,
+            container_id,
+            load_items(client, container_id)?,
+        ));
+    }
+
+    build_export(
+        exported_character,
+        skill_groups,
+        ability_sets,
+        items,
+        schema_version(client)?,
+        server_name,
+    )
+}
+
+/// See [`super::transfer::import_character`]
+pub fn import_character(
+    requesting_player_uuid: &str,
+    export: CharacterExport,
+    export_signature: &str,
+    alias: Option<String>,
+    transaction: &mut Transaction,
+) -> Result<ImportedCharacter, PersistenceError> {
+    let (original_alias, source) = (export.alias.clone(), export.server_name.clone());
+    let (alias, persisted_components, skills_reset) =
+        prepare_import(export, alias, schema_version(transaction)?)?;
+    let (character_id, _) = create_character(
+        requesting_player_uuid,
+        &alias,
+        persisted_components,
+        transaction,
+    )?;
+    // Waits for concurrent imports of the same export by other servers sharing
+    // the database
+    let recorded = transaction.execute(
+        "
+        INSERT INTO character_import (export_signature, character_id, imported_at)
+        VALUES ($1, $2, $3)
+        ON CONFLICT DO NOTHING",
+        &[
+            &export_signature,
+            &character_id.0,
+            &chrono::Utc::now().timestamp(),
+        ],
+    )?;
+    if recorded == 0 {
+        return Err(already_imported(export_signature));
+    }
+
+    Ok(ImportedCharacter {
+        character_id,
+        original_alias,
+        source,
+        skills_reset,
+    })
+}
@@ -0,0 +1,649 @@
+//! Transferring characters between servers.
+//!
+//! A character is exported as the database rows it consists of. Those are
+//! already versioned where it matters: items are stored by their definition
+//! id, bodies as tagged JSON and skill groups together with the hash of their
+//! skill tree. Importing an export on another server goes through the same
+//! conversions as loading a character, so items that don't exist there are
+//! rejected and outdated skill groups are refunded like they would be on
+//! login.
+//!
+//! Exports are signed with a secret shared between the servers that trust each
+//! other's characters, see [`Settings::character_transfer_key`]. The signature
+//! of every imported export is recorded, so an export can only be imported
+//! once.
+//!
+//! [`Settings::character_transfer_key`]: crate::Settings::character_transfer_key
+
+use super::{
+    INVENTORY_PSEUDO_CONTAINER_POSITION, LOADOUT_PSEUDO_CONTAINER_POSITION,
+    OVERFLOW_ITEMS_PSEUDO_CONTAINER_POSITION, RECIPE_BOOK_PSEUDO_CONTAINER_POSITION,
+    STORAGE_PSEUDO_CONTAINER_POSITION,
+    conversions::{
+        convert_active_abilities_from_database, convert_body_from_database,
+        convert_hardcore_from_database, convert_inventory_from_database_items,
+        convert_skill_set_from_database, convert_stats_from_database,
+        convert_storage_from_database_items, convert_waypoint_from_database_json,
+    },
+    create_character, get_pseudo_container_id, load_items,
+};
+use crate::persistence::{
+    PersistedComponents,
+    error::PersistenceError,
+    models::{AbilitySets, Item, SkillGroup},
+};
+use common::{
+    character::{CharacterId, MAX_NAME_LENGTH},
+    comp::{self, Inventory},
+};
+use hashbrown::HashMap;
+use hmac::{Hmac, KeyInit, Mac};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::{fs, path::Path};
+
+/// Version of the export file format, bumped whenever [`CharacterExport`]
+/// changes.
+pub const FORMAT_VERSION: u32 = 1;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CharacterExport {
+    pub format_version: u32,
+    /// The newest database migration applied on the exporting server
+    pub schema_version: i64,
+    /// Seconds since the unix epoch
+    pub exported_at: i64,
+    pub server_name: String,
+    pub alias: String,
+    pub hardcore: bool,
+    pub body: ExportedBody,
+    pub waypoint: Option<String>,
+    pub skill_groups: Vec<ExportedSkillGroup>,
+    pub ability_sets: String,
+    /// Items in the order they were loaded, components always come after the
+    /// item they belong to.
+    pub items: Vec<ExportedItem>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ExportedBody {
+    pub variant: String,
+    pub data: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ExportedSkillGroup {
+    pub kind: String,
+    pub earned_exp: i64,
+    pub spent_exp: i64,
+    pub skills: String,
+    pub hash_val: Vec<u8>,
+}
+
+/// The pseudo container of a character an item is stored in
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ItemContainer {
+    Inventory,
+    Loadout,
+    OverflowItems,
+    RecipeBook,
+    Storage,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ExportedItem {
+    /// Only meaningful within the export, items get new ids when imported
+    pub id: i64,
+    pub container: ItemContainer,
+    /// The item this item is a component of, `None` if it is stored directly
+    /// in its container.
+    pub parent: Option<i64>,
+    pub definition: String,
+    pub stack_size: i64,
+    pub position: String,
+    pub properties: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SignedExport {
+    character: CharacterExport,
+    /// Hex encoded HMAC-SHA256 of the JSON serialized character
+    signature: String,
+}
+
+/// Read before the rest of the file so that exports from a newer format get a
+/// useful error instead of a deserialization error.
+#[derive(Deserialize)]
+struct FormatProbe {
+    character: FormatVersion,
+}
+
+#[derive(Deserialize)]
+struct FormatVersion {
+    format_version: u32,
+}
+
+impl CharacterExport {
+    fn mac(&self, key: &str) -> Hmac<Sha256> {
+        let payload = serde_json::to_vec(self).expect("Failed to serialize character export");
+        let mut mac =
+            Hmac::<Sha256>::new_from_slice(key.as_bytes()).expect("HMAC accepts keys of any size");
+        mac.update(&payload);
+        mac
+    }
+
+    pub fn to_json(&self, key: &str) -> String {
+        let signed = SignedExport {
+            character: self.clone(),
+            signature: hex::encode(self.mac(key).finalize().into_bytes()),
+        };
+        serde_json::to_string_pretty(&signed).expect("Failed to serialize character export")
+    }
+
+    /// Parses an export, verifying that it was signed with `key`. Returns the
+    /// export together with its signature, which identifies it.
+    pub fn from_json(json: &str, key: &str) -> Result<(Self, String), String> {
+        let probe = serde_json::from_str::<FormatProbe>(json)
+            .map_err(|e| format!("Not a character export: {e}"))?;
+        if probe.character.format_version != FORMAT_VERSION {
+            return Err(format!(
+                "Unsupported character export format version {} (expected {FORMAT_VERSION})",
+                probe.character.format_version
+            ));
+        }
+        let signed = serde_json::from_str::<SignedExport>(json)
+            .map_err(|e| format!("Invalid character export: {e}"))?;
+
+        hex::decode(&signed.signature)
+            .ok()
+            .filter(|signature| signed.character.mac(key).verify_slice(signature).is_ok())
+            .ok_or_else(|| {
+                "The signature of the character export is invalid, it was either modified or \
+                 signed with a different key"
+                    .to_owned()
+            })?;
+        Ok((signed.character, signed.signature))
+    }
+
+    pub fn write(&self, path: &Path, key: &str) -> Result<(), String> {
+        fs::write(path, self.to_json(key))
+            .map_err(|e| format!("Failed to write {}: {e}", path.display()))
+    }
+
+    pub fn read(path: &Path, key: &str) -> Result<(Self, String), String> {
+        let json = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+        Self::from_json(&json, key)
+    }
+}
+
+pub(super) const CONTAINERS: [(ItemContainer, &str); 5] = [
+    (
+        ItemContainer::Inventory,
+        INVENTORY_PSEUDO_CONTAINER_POSITION,
+    ),
+    (ItemContainer::Loadout, LOADOUT_PSEUDO_CONTAINER_POSITION),
+    (
+        ItemContainer::OverflowItems,
+        OVERFLOW_ITEMS_PSEUDO_CONTAINER_POSITION,
+    ),
+    (
+        ItemContainer::RecipeBook,
+        RECIPE_BOOK_PSEUDO_CONTAINER_POSITION,
+    ),
+    (ItemContainer::Storage, STORAGE_PSEUDO_CONTAINER_POSITION),
+];
+
+/// The newest migration applied to the database
+fn schema_version(connection: &Connection) -> Result<i64, PersistenceError> {
+    Ok(connection.query_row(
+        "SELECT COALESCE(MAX(version), 0) FROM refinery_schema_history",
+        [],
+        |row| row.get(0),
+    )?)
+}
+
+/// Exports a character of the player, identified either by its alias or by
+/// its id.
+pub fn export_character(
+    requesting_player_uuid: &str,
+    character: &str,
+    server_name: &str,
+    connection: &Connection,
+) -> Result<CharacterExport, PersistenceError> {
+    let mut stmt = connection.prepare_cached(
+        "
+        SELECT  c.character_id,
+                c.alias,
+                c.waypoint,
+                c.hardcore,
+                b.variant,
+                b.body_data
+        FROM    character c
+        JOIN    body b ON (c.character_id = b.body_id)
+        WHERE   c.player_uuid = ?1",
+    )?;
+
+    let rows = stmt
+        .query_map([requesting_player_uuid], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, Option<String>>(2)?,
+                row.get::<_, i64>(3)?,
+                ExportedBody {
+                    variant: row.get(4)?,
+                    data: row.get(5)?,
+                },
+            ))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    drop(stmt);
+    let exported_character = select_character(rows, character)?;
+    let character_id = exported_character.0;
+
+    let mut stmt = connection.prepare_cached(
+        "
+        SELECT  skill_group_kind,
+                earned_exp,
+                spent_exp,
+                skills,
+                hash_val
+        FROM    skill_group
+        WHERE   entity_id = ?1",
+    )?;
+
+    let skill_groups = stmt
+        .query_map([character_id], |row| {
+            Ok(ExportedSkillGroup {
+                kind: row.get(0)?,
+                earned_exp: row.get(1)?,
+                spent_exp: row.get(2)?,
+                skills: row.get(3)?,
+                hash_val: row.get(4)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    drop(stmt);
+
+    let ability_sets = connection.query_row(
+        "SELECT ability_sets FROM ability_set WHERE entity_id = ?1",
+        [character_id],
+        |row| row.get(0),
+    )?;
+
+    let mut items = Vec::new();
+    for (container, position) in CONTAINERS {
+        let container_id =
+            get_pseudo_container_id(connection, CharacterId(character_id), position)?;
+        items.extend(exported_items(
+            container,
+            container_id,
+            load_items(connection, container_id)?,
+        ));
+    }
+
+    build_export(
+        exported_character,
+        skill_groups,
+        ability_sets,
+        items,
+        schema_version(connection)?,
+        server_name,
+    )
+}
+
+/// The character, body and waypoint columns of an exported character
+pub(super) type ExportedCharacterRow = (i64, String, Option<String>, i64, ExportedBody);
+
+/// Picks the character to export from all characters of the player
+pub(super) fn select_character(
+    rows: Vec<ExportedCharacterRow>,
+    character: &str,
+) -> Result<ExportedCharacterRow, PersistenceError> {
+    let mut matching = rows.into_iter().filter(|(character_id, alias, ..)| {
+        alias == character || character_id.to_string() == character
+    });
+    match (matching.next(), matching.next()) {
+        (Some(found), None) => Ok(found),
+        (None, _) => Err(PersistenceError::OtherError(format!(
+            "The player has no character named {character}"
+        ))),
+        (Some(_), Some(_)) => Err(PersistenceError::OtherError(format!(
+            "The player has several characters named {character}, use the character id instead"
+        ))),
+    }
+}
+
+/// Converts the items loaded from a pseudo container
+pub(super) fn exported_items(
+    container: ItemContainer,
+    container_id: i64,
+    items: Vec<Item>,
+) -> impl Iterator<Item = ExportedItem> {
+    items.into_iter().map(move |item| ExportedItem {
+        id: item.item_id,
+        container,
+        parent: (item.parent_container_item_id != container_id)
+            .then_some(item.parent_container_item_id),
+        definition: item.item_definition_id,
+        stack_size: item.stack_size,
+        position: item.position,
+        properties: item.properties,
+    })
+}
+
+pub(super) fn build_export(
+    (_, alias, waypoint, hardcore, body): ExportedCharacterRow,
+    skill_groups: Vec<ExportedSkillGroup>,
+    ability_sets: String,
+    items: Vec<ExportedItem>,
+    schema_version: i64,
+    server_name: &str,
+) -> Result<CharacterExport, PersistenceError> {
+    Ok(CharacterExport {
+        format_version: FORMAT_VERSION,
+        schema_version,
+        exported_at: chrono::Utc::now().timestamp(),
+        server_name: server_name.to_owned(),
+        alias,
+        hardcore: convert_hardcore_from_database(hardcore)?.is_some(),
+        body,
+        waypoint,
+        skill_groups,
+        ability_sets,
+        items,
+    })
+}
+
+/// A character created from an export
+#[derive(Debug)]
+pub struct ImportedCharacter {
+    pub character_id: CharacterId,
+    /// The alias of the character on the server it was exported from
+    pub original_alias: String,
+    /// The server it was exported from
+    pub source: String,
+    /// Whether its skills had to be reset because the skill trees differ
+    /// between the servers
+    pub skills_reset: bool,
+}
+
+pub(super) fn already_imported(export_signature: &str) -> PersistenceError {
+    PersistenceError::OtherError(format!(
+        "The character export with the signature {export_signature} has already been imported"
+    ))
+}
+
+/// Creates a new character for the player from an export, optionally under a
+/// different alias. `export_signature` is recorded to reject importing the
+/// same export again.
+pub fn import_character(
+    requesting_player_uuid: &str,
+    export: CharacterExport,
+    export_signature: &str,
+    alias: Option<String>,
+    transaction: &mut rusqlite::Transaction,
+) -> Result<ImportedCharacter, PersistenceError> {
+    let (original_alias, source) = (export.alias.clone(), export.server_name.clone());
+    let (alias, persisted_components, skills_reset) =
+        prepare_import(export, alias, schema_version(transaction)?)?;
+    let (character_id, _) = create_character(
+        requesting_player_uuid,
+        &alias,
+        persisted_components,
+        transaction,
+    )?;
+    // Nothing is inserted if the export was imported before, which rolls back the
+    // whole import
+    let recorded = transaction.execute(
+        "
+        INSERT INTO character_import (export_signature, character_id, imported_at)
+        VALUES (?1, ?2, ?3)
+        ON CONFLICT DO NOTHING",
+        rusqlite::params![
+            export_signature,
+            character_id.0,
+            chrono::Utc::now().timestamp()
+        ],
+    )?;
+    if recorded == 0 {
+        return Err(already_imported(export_signature));
+    }
+
+    Ok(ImportedCharacter {
+        character_id,
+        original_alias,
+        source,
+        skills_reset,
+    })
+}
+
+/// Converts an export to the components of the new character, together with
+/// its alias and whether its skills had to be reset.
+pub(super) fn prepare_import(
+    export: CharacterExport,
+    alias: Option<String>,
+    local_schema_version: i64,
+) -> Result<(String, PersistedComponents, bool), PersistenceError> {
+    if export.schema_version > local_schema_version {
+        return Err(PersistenceError::OtherError(format!(
+            "The character was exported from a newer database schema ({} > \
+             {local_schema_version}), update this server first",
+            export.schema_version
+        )));
+    }
+
+    let alias = alias.unwrap_or(export.alias);
+    if alias.is_empty() || alias.chars().count() > MAX_NAME_LENGTH {
+        return Err(PersistenceError::OtherError(format!(
+            "Invalid character alias {alias:?}"
+        )));
+    }
+
+    // Report every missing item at once instead of failing on the first one
+    let mut unknown_items = export
+        .items
+        .iter()
+        .map(|item| item.definition.as_str())
+        .filter(|definition| comp::Item::new_from_asset(definition).is_err())
+        .collect::<Vec<_>>();
+    if !unknown_items.is_empty() {
+        unknown_items.sort_unstable();
+        unknown_items.dedup();
+        return Err(PersistenceError::AssetError(format!(
+            "The character has items this server doesn't know: {}",
+            unknown_items.join(", ")
+        )));
+    }
+
+    // The conversions expect database rows, give the pseudo containers and items
+    // placeholder ids. The items are assigned real ids when the character is
+    // created.
+    let container_ids = CONTAINERS
+        .iter()
+        .zip(1..)
+        .map(|((container, _), id)| (*container, id))
+        .collect::<HashMap<_, i64>>();
+    let item_ids = export
+        .items
+        .iter()
+        .zip(container_ids.len() as i64 + 1..)
+        .map(|(item, id)| (item.id, id))
+        .collect::<HashMap<_, _>>();
+    let items_in = |container| {
+        export
+            .items
+            .iter()
+            .filter(|item| item.container == container)
+            .map(|item| {
+                let parent_container_item_id = match item.parent {
+                    Some(parent) => *item_ids.get(&parent).ok_or_else(|| {
+                        PersistenceError::ConversionError(format!(
+                            "Item {} is a component of the missing item {parent}",
+                            item.id
+                        ))
+                    })?,
+                    None => container_ids[&container],
+                };
+                Ok(Item {
+                    item_id: item_ids[&item.id],
+                    parent_container_item_id,
+                    item_definition_id: item.definition.clone(),
+                    stack_size: item.stack_size,
+                    position: item.position.clone(),
+                    properties: item.properties.clone(),
+                })
+            })
+            .collect::<Result<Vec<_>, PersistenceError>>()
+    };
+    let mut inventory = convert_inventory_from_database_items(
+        container_ids[&ItemContainer::Inventory],
+        &items_in(ItemContainer::Inventory)?,
+        container_ids[&ItemContainer::Loadout],
+        &items_in(ItemContainer::Loadout)?,
+        container_ids[&ItemContainer::OverflowItems],
+        &items_in(ItemContainer::OverflowItems)?,
+        &items_in(ItemContainer::RecipeBook)?,
+    )?;
+    let storage = convert_storage_from_database_items(
+        container_ids[&ItemContainer::Storage],
+        &items_in(ItemContainer::Storage)?,
+        &mut inventory,
+    )?;
+    reset_item_ids(&inventory, &storage);
+
+    let skill_groups = export
+        .skill_groups
+        .into_iter()
+        .map(|skill_group| SkillGroup {
+            entity_id: 0,
+            skill_group_kind: skill_group.kind,
+            earned_exp: skill_group.earned_exp,
+            spent_exp: skill_group.spent_exp,
+            skills: skill_group.skills,
+            hash_val: skill_group.hash_val,
+        })
+        .collect::<Vec<_>>();
+    let (skill_set, skill_set_persistence_load_error) =
+        convert_skill_set_from_database(&skill_groups);
+
+    let (waypoint, map_marker) = export
+        .waypoint
+        .as_deref()
+        .map(convert_waypoint_from_database_json)
+        .transpose()?
+        .unwrap_or((None, None));
+    let body = convert_body_from_database(&export.body.variant, &export.body.data)?;
+
+    let persisted_components = PersistedComponents {
+        body,
+        hardcore: export.hardcore.then_some(comp::Hardcore),
+        stats: convert_stats_from_database(alias.clone(), body),
+        skill_set,
+        inventory,
+        storage: comp::PersonalStorage::new(storage),
+        waypoint,
+        pets: Vec::new(),
+        active_abilities: convert_active_abilities_from_database(&AbilitySets {
+            entity_id: 0,
+            ability_sets: export.ability_sets,
+        }),
+        map_marker,
+        reputation: None,
+    };
+
+    Ok((
+        alias,
+        persisted_components,
+        skill_set_persistence_load_error.is_some(),
+    ))
+}
+
+/// Forgets the placeholder ids of the items, so that they are inserted as new
+/// items.
+fn reset_item_ids(inventory: &Inventory, storage: &Inventory) {
+    fn reset(item: &comp::Item) {
+        // NOTE: Since the items are freshly loaded, the atomics are *unique.*
+        item.get_item_id_for_database().store(None);
+        item.components().iter().for_each(reset);
+    }
+
+    inventory
+        .slots_with_id()
+        .filter_map(|(_, slot)| slot.as_ref())
+        .chain(
+            inventory
+                .loadout_items_with_persistence_key()
+                .filter_map(|(_, item)| item),
+        )
+        .chain(inventory.overflow_items())
+        .chain(
+            inventory
+                .persistence_recipes_iter_with_index()
+                .map(|(_, item)| item),
+        )
+        .chain(storage.slots().filter_map(|slot| slot.as_ref()))
+        .for_each(reset);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signed_roundtrip() {
+        let export = CharacterExport {
+            format_version: FORMAT_VERSION,
+            schema_version: 60,
+            exported_at: 1_700_000_000,
+            server_name: "Test".to_owned(),
+            alias: "Tester".to_owned(),
+            hardcore: false,
+            body: ExportedBody {
+                variant: "humanoid".to_owned(),
+                data: "{}".to_owned(),
+            },
+            waypoint: None,
+            skill_groups: vec![ExportedSkillGroup {
+                kind: "General".to_owned(),
+                earned_exp: 10,
+                spent_exp: 0,
+                skills: "[]".to_owned(),
+                hash_val: vec![1, 2, 3],
+            }],
+            ability_sets: "[]".to_owned(),
+            items: vec![ExportedItem {
+                id: 42,
+                container: ItemContainer::Inventory,
+                parent: None,
+                definition: "common.items.food.cheese".to_owned(),
+                stack_size: 3,
+                position: "{}".to_owned(),
+                properties: "{}".to_owned(),
+            }],
+        };
+
+        let json = export.to_json("secret");
+        let (imported, signature) = CharacterExport::from_json(&json, "secret").unwrap();
+        assert_eq!(imported, export);
+        assert!(json.contains(&signature));
+        assert!(CharacterExport::from_json(&json, "other secret").is_err());
+
+        let tampered = json.replace("\"stack_size\": 3", "\"stack_size\": 300");
+        assert_ne!(tampered, json);
+        assert!(CharacterExport::from_json(&tampered, "secret").is_err());
+
+        let newer = json.replace(
+            &format!("\"format_version\": {FORMAT_VERSION}"),
+            "\"format_version\": 999",
+        );
+        assert!(
+            CharacterExport::from_json(&newer, "secret")
+                .unwrap_err()
+                .contains("format version 999")
+        );
+    }
+}
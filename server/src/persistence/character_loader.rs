@@ -1,16 +1,18 @@
 use crate::persistence::{
-    ConnectionMode, DatabaseSettings, PersistedComponents,
-    character::{load_character_data, load_character_list},
+    ConnectionMode, DatabaseSettings, ImportedCharacter, PersistedComponents,
+    backend::{self, PersistenceBackend},
     error::PersistenceError,
-    establish_connection,
 };
 use common::{
     character::{CharacterId, CharacterItem},
     event::UpdateCharacterMetadata,
 };
 use crossbeam_channel::{self, TryIter};
-use rusqlite::Connection;
-use std::sync::{Arc, RwLock};
+use std::{
+    fmt,
+    path::PathBuf,
+    sync::{Arc, RwLock},
+};
 use tracing::{debug, error};
 use vek::Vec3;
 
@@ -37,6 +39,7 @@ enum CharacterLoaderRequestKind {
 pub enum CharacterUpdaterMessage {
     CharacterScreenResponse(CharacterScreenResponse),
     DatabaseBatchCompletion(u64),
+    CharacterTransferResponse(CharacterTransferResponse),
 }
 
 /// An event emitted from CharacterUpdater in response to a request made from
@@ -66,6 +69,41 @@ pub enum CharacterScreenResponseKind {
     CharacterEdit(CharacterEditResult),
 }
 
+/// The outcome of a character export or import requested from the
+/// [`CharacterUpdater`]
+///
+/// [`CharacterUpdater`]: super::character_updater::CharacterUpdater
+#[derive(Debug)]
+pub struct CharacterTransferResponse {
+    pub requester: TransferRequester,
+    pub username: String,
+    pub response_kind: CharacterTransferResponseKind,
+}
+
+#[derive(Debug)]
+pub enum CharacterTransferResponseKind {
+    /// The alias of the exported character and the file it was written to
+    Export(Result<(String, PathBuf), String>),
+    Import(Result<ImportedCharacter, String>),
+}
+
+/// Who gets told about the outcome of a character transfer
+pub enum TransferRequester {
+    /// An admin using the chat commands
+    Client(specs::Entity),
+    /// Called from the main thread, e.g. to answer the server CLI
+    Callback(Box<dyn FnOnce(Result<(), String>) + Send>),
+}
+
+impl fmt::Debug for TransferRequester {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Client(entity) => f.debug_tuple("Client").field(entity).finish(),
+            Self::Callback(_) => f.write_str("Callback"),
+        }
+    }
+}
+
 /// A bi-directional messaging resource for making requests to modify or load
 /// character data in a background thread.
 ///
@@ -96,13 +134,13 @@ impl CharacterLoader {
                 //
                 // This connection -must- remain read-only to avoid lock contention with the
                 // CharacterUpdater thread.
-                let mut conn =
-                    establish_connection(&settings.read().unwrap(), ConnectionMode::ReadOnly);
+                let mut backend =
+                    backend::connect(&settings.read().unwrap(), ConnectionMode::ReadOnly);
 
                 for request in internal_rx {
-                    conn.update_log_mode(&settings);
+                    backend.update_log_mode(&settings);
 
-                    let response = CharacterLoader::process_request(request, &conn);
+                    let response = CharacterLoader::process_request(request, backend.as_mut());
                     debug!("Processing complete");
                     if let Err(e) = internal_tx.send(response) {
                         error!(?e, "Could not send character loader response");
@@ -123,7 +161,7 @@ impl CharacterLoader {
     // CharacterScreenResponse::is_err()
     fn process_request(
         request: CharacterLoaderRequest,
-        connection: &Connection,
+        backend: &mut dyn PersistenceBackend,
     ) -> CharacterUpdaterMessage {
         let (entity, kind) = request;
         CharacterUpdaterMessage::CharacterScreenResponse(CharacterScreenResponse {
@@ -131,17 +169,16 @@ impl CharacterLoader {
             response_kind: match kind {
                 CharacterLoaderRequestKind::LoadCharacterList { player_uuid } => {
                     debug!(?player_uuid, "Loading character list");
-                    CharacterScreenResponseKind::CharacterList(load_character_list(
-                        &player_uuid,
-                        connection,
-                    ))
+                    CharacterScreenResponseKind::CharacterList(
+                        backend.load_character_list(&player_uuid),
+                    )
                 },
                 CharacterLoaderRequestKind::LoadCharacterData {
                     player_uuid,
                     character_id,
                 } => {
                     debug!(?player_uuid, ?character_id, "Loading character data");
-                    let result = load_character_data(player_uuid, character_id, connection);
+                    let result = backend.load_character_data(player_uuid, character_id);
                     if result.is_err() {
                         error!(
                             ?result,
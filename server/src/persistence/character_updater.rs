@@ -1,19 +1,26 @@
-use crate::comp;
+use crate::{backup::DatabaseCopy, comp};
 use common::{character::CharacterId, event::PermanentChange};
 
 use crate::persistence::{
-    ConnectionMode, DatabaseSettings, EditableComponents, PersistedComponents, VelorenConnection,
+    ConnectionMode, DatabaseSettings, EditableComponents, PersistedComponents,
+    backend::{self, PersistenceBackend},
+    character::transfer::CharacterExport,
     character_loader::{
-        CharacterScreenResponse, CharacterScreenResponseKind, CharacterUpdaterMessage,
+        CharacterScreenResponse, CharacterScreenResponseKind, CharacterTransferResponse,
+        CharacterTransferResponseKind, CharacterUpdaterMessage, TransferRequester,
     },
     error::PersistenceError,
-    establish_connection,
+    guild::GuildChange,
+    mail::MailChange,
+    market::ListingChange,
 };
+#[cfg(feature = "plugins")]
+use common_state::plugin::storage::PluginStorageChange;
 use crossbeam_channel::TryIter;
-use rusqlite::DropBehavior;
 use specs::Entity;
 use std::{
     collections::HashMap,
+    path::PathBuf,
     sync::{
         Arc, RwLock,
         atomic::{AtomicBool, Ordering},
@@ -25,10 +32,13 @@ pub type CharacterUpdateData = (
     CharacterId,
     comp::SkillSet,
     comp::Inventory,
+    // Content of the personal storage
+    comp::Inventory,
     Vec<PetPersistenceData>,
     Option<comp::Waypoint>,
     comp::ability::ActiveAbilities,
     Option<comp::MapMarker>,
+    common::rtsim::PersistedReputation,
 );
 
 pub type PetPersistenceData = (comp::Pet, comp::Body, comp::Stats);
@@ -54,6 +64,30 @@ enum CharacterUpdaterAction {
         trusted_change: Option<PermanentChange>,
     },
     DisconnectedSuccess,
+    #[cfg(feature = "plugins")]
+    UpdatePluginStorage(Vec<PluginStorageChange>),
+    UpdateGuilds(Vec<GuildChange>),
+    Snapshot {
+        path: PathBuf,
+        reply: crossbeam_channel::Sender<Result<DatabaseCopy, String>>,
+    },
+    ExportCharacter {
+        player_uuid: String,
+        character: String,
+        server_name: String,
+        path: PathBuf,
+        key: String,
+        username: String,
+        requester: TransferRequester,
+    },
+    ImportCharacter {
+        player_uuid: String,
+        path: PathBuf,
+        key: String,
+        alias: Option<String>,
+        username: String,
+        requester: TransferRequester,
+    },
 }
 
 #[derive(Clone)]
@@ -75,12 +109,17 @@ impl DatabaseAction {
 }
 
 #[derive(Clone)]
-enum DatabaseActionKind {
+pub(crate) enum DatabaseActionKind {
     UpdateCharacter(Box<CharacterUpdateData>),
     DeleteCharacter {
         requesting_player_uuid: String,
         character_id: CharacterId,
     },
+    /// Mail moves items between characters, so it is written together with
+    /// their inventories to not duplicate or lose any of them
+    UpdateMail(Vec<MailChange>),
+    /// Like mail, listings move items between characters
+    UpdateMarket(Vec<ListingChange>),
 }
 
 /// A unidirectional messaging resource for saving characters in a
@@ -114,8 +153,8 @@ impl CharacterUpdater {
             .spawn(move || {
                 // Unwrap here is safe as there is no code that can panic when the write lock is
                 // taken that could cause the RwLock to become poisoned.
-                let mut conn =
-                    establish_connection(&settings.read().unwrap(), ConnectionMode::ReadWrite);
+                let mut backend =
+                    backend::connect(&settings.read().unwrap(), ConnectionMode::ReadWrite);
                 while let Ok(action) = update_rx.recv() {
                     match action {
                         CharacterUpdaterAction::BatchUpdate { batch_id, updates } => {
@@ -126,9 +165,9 @@ impl CharacterUpdater {
                                 );
                                 continue;
                             }
-                            conn.update_log_mode(&settings);
+                            backend.update_log_mode(&settings);
 
-                            if let Err(e) = execute_batch_update(updates.into_iter(), &mut conn) {
+                            if let Err(e) = backend.batch_update(updates) {
                                 error!(
                                     ?e,
                                     "Error during character batch update, disconnecting all \
@@ -160,7 +199,7 @@ impl CharacterUpdater {
                                 character_alias,
                                 &player_uuid,
                                 persisted_components,
-                                &mut conn,
+                                backend.as_mut(),
                             ) {
                                 Ok(response) => {
                                     if let Err(e) = response_tx.send(response) {
@@ -193,7 +232,7 @@ impl CharacterUpdater {
                                 &player_uuid,
                                 editable_components,
                                 trusted_change,
-                                &mut conn,
+                                backend.as_mut(),
                             ) {
                                 Ok(response) => {
                                     if let Err(e) = response_tx.send(response) {
@@ -220,6 +259,83 @@ impl CharacterUpdater {
                             // clients have been disconnected
                             disconnect_all_clients_requested_clone.store(false, Ordering::Relaxed);
                         },
+                        #[cfg(feature = "plugins")]
+                        CharacterUpdaterAction::UpdatePluginStorage(changes) => {
+                            backend.update_log_mode(&settings);
+
+                            if let Err(e) = backend.update_plugin_storage(changes) {
+                                error!(?e, "Error during plugin storage update");
+                            }
+                        },
+                        CharacterUpdaterAction::UpdateGuilds(changes) => {
+                            backend.update_log_mode(&settings);
+
+                            if let Err(e) = backend.update_guilds(changes) {
+                                error!(?e, "Error during guild update");
+                            }
+                        },
+                        CharacterUpdaterAction::Snapshot { path, reply } => {
+                            // Every write queued before this one has been committed, and later
+                            // ones wait until the copy is complete.
+                            let result = backend.snapshot(&path);
+                            if reply.send(result).is_err() {
+                                warn!("Database snapshot was no longer awaited");
+                            }
+                        },
+                        CharacterUpdaterAction::ExportCharacter {
+                            player_uuid,
+                            character,
+                            server_name,
+                            path,
+                            key,
+                            username,
+                            requester,
+                        } => {
+                            let result = backend
+                                .export_character(&player_uuid, &character, &server_name)
+                                .map_err(|e| e.to_string())
+                                .and_then(|export| {
+                                    export.write(&path, &key)?;
+                                    Ok((export.alias, path))
+                                });
+                            let response = CharacterTransferResponse {
+                                requester,
+                                username,
+                                response_kind: CharacterTransferResponseKind::Export(result),
+                            };
+                            if let Err(e) = response_tx
+                                .send(CharacterUpdaterMessage::CharacterTransferResponse(response))
+                            {
+                                error!(?e, "Could not send character export response");
+                            }
+                        },
+                        CharacterUpdaterAction::ImportCharacter {
+                            player_uuid,
+                            path,
+                            key,
+                            alias,
+                            username,
+                            requester,
+                        } => {
+                            backend.update_log_mode(&settings);
+                            let result = CharacterExport::read(&path, &key).and_then(
+                                |(export, signature)| {
+                                    backend
+                                        .import_character(&player_uuid, export, &signature, alias)
+                                        .map_err(|e| e.to_string())
+                                },
+                            );
+                            let response = CharacterTransferResponse {
+                                requester,
+                                username,
+                                response_kind: CharacterTransferResponseKind::Import(result),
+                            };
+                            if let Err(e) = response_tx
+                                .send(CharacterUpdaterMessage::CharacterTransferResponse(response))
+                            {
+                                error!(?e, "Could not send character import response");
+                            }
+                        },
                     }
                 }
             })
@@ -358,8 +474,14 @@ impl CharacterUpdater {
         );
     }
 
-    /// Updates a collection of characters based on their id and components
-    pub fn batch_update(&mut self, updates: impl Iterator<Item = CharacterUpdateData>) {
+    /// Updates a collection of characters based on their id and components,
+    /// together with the changes of the mail and the market
+    pub fn batch_update(
+        &mut self,
+        updates: impl Iterator<Item = CharacterUpdateData>,
+        mail_changes: Vec<MailChange>,
+        market_changes: Vec<ListingChange>,
+    ) {
         let batch_id = self.next_pending_database_event_id();
 
         // Collect any new updates, ignoring updates from a previous update that are
@@ -369,9 +491,17 @@ impl CharacterUpdater {
             .iter_mut()
             .filter_map(|(_, event)| event.take_new(batch_id));
 
-        // Combine the pending actions with the updates for logged in characters
-        let pending_actions = existing_pending_actions
+        // Combine the pending actions with the updates for logged in characters. The
+        // mail and the market go first, deleting a character also deletes the mail it
+        // received and its listings.
+        let pending_actions = (!mail_changes.is_empty())
+            .then_some(DatabaseActionKind::UpdateMail(mail_changes))
             .into_iter()
+            .chain(
+                (!market_changes.is_empty())
+                    .then_some(DatabaseActionKind::UpdateMarket(market_changes)),
+            )
+            .chain(existing_pending_actions)
             .chain(updates.map(|update| DatabaseActionKind::UpdateCharacter(Box::new(update))))
             .collect::<Vec<DatabaseActionKind>>();
 
@@ -397,6 +527,39 @@ impl CharacterUpdater {
         }
     }
 
+    /// Persists the modifications of the plugin storage in the background
+    #[cfg(feature = "plugins")]
+    pub fn update_plugin_storage(&mut self, changes: Vec<PluginStorageChange>) {
+        if changes.is_empty() {
+            return;
+        }
+
+        if let Err(e) = self
+            .update_tx
+            .as_ref()
+            .unwrap()
+            .send(CharacterUpdaterAction::UpdatePluginStorage(changes))
+        {
+            error!(?e, "Could not send plugin storage update");
+        }
+    }
+
+    /// Persists the modifications of the guilds in the background
+    pub fn update_guilds(&mut self, changes: Vec<GuildChange>) {
+        if changes.is_empty() {
+            return;
+        }
+
+        if let Err(e) = self
+            .update_tx
+            .as_ref()
+            .unwrap()
+            .send(CharacterUpdaterAction::UpdateGuilds(changes))
+        {
+            error!(?e, "Could not send guild update");
+        }
+    }
+
     /// Indicates to the batch update thread that a requested disconnection of
     /// all clients has been processed
     pub fn disconnected_success(&mut self) {
@@ -410,50 +573,87 @@ impl CharacterUpdater {
             );
     }
 
-    /// Returns a non-blocking iterator over CharacterUpdaterMessage messages
-    pub fn messages(&self) -> TryIter<'_, CharacterUpdaterMessage> { self.response_rx.try_iter() }
-}
+    /// Copies the database to `path` once all previously queued writes have
+    /// been committed. Further writes are held back until the copy is
+    /// complete, the returned channel receives the outcome.
+    pub fn snapshot(
+        &mut self,
+        path: PathBuf,
+    ) -> crossbeam_channel::Receiver<Result<DatabaseCopy, String>> {
+        let (reply, outcome) = crossbeam_channel::bounded(1);
+        if let Err(e) = self
+            .update_tx
+            .as_ref()
+            .unwrap()
+            .send(CharacterUpdaterAction::Snapshot { path, reply })
+        {
+            error!(?e, "Could not send database snapshot request");
+        }
+        outcome
+    }
 
-fn execute_batch_update(
-    updates: impl Iterator<Item = DatabaseActionKind>,
-    connection: &mut VelorenConnection,
-) -> Result<(), PersistenceError> {
-    let mut transaction = connection.connection.transaction()?;
-    transaction.set_drop_behavior(DropBehavior::Rollback);
-    trace!("Transaction started for character batch update");
-    updates.into_iter().try_for_each(|event| match event {
-        DatabaseActionKind::UpdateCharacter(box (
-            character_id,
-            stats,
-            inventory,
-            pets,
-            waypoint,
-            active_abilities,
-            map_marker,
-        )) => super::character::update(
-            character_id,
-            stats,
-            inventory,
-            pets,
-            waypoint,
-            active_abilities,
-            map_marker,
-            &mut transaction,
-        ),
-        DatabaseActionKind::DeleteCharacter {
-            requesting_player_uuid,
-            character_id,
-        } => super::character::delete_character(
-            &requesting_player_uuid,
-            character_id,
-            &mut transaction,
-        ),
-    })?;
+    /// Exports a character of the player to `path`, signed with `key`, once
+    /// all previously queued writes have been committed. The outcome is sent
+    /// back as a [`CharacterTransferResponse`].
+    #[expect(clippy::too_many_arguments)]
+    pub fn export_character(
+        &mut self,
+        player_uuid: String,
+        character: String,
+        server_name: String,
+        path: PathBuf,
+        key: String,
+        username: String,
+        requester: TransferRequester,
+    ) {
+        if let Err(e) =
+            self.update_tx
+                .as_ref()
+                .unwrap()
+                .send(CharacterUpdaterAction::ExportCharacter {
+                    player_uuid,
+                    character,
+                    server_name,
+                    path,
+                    key,
+                    username,
+                    requester,
+                })
+        {
+            error!(?e, "Could not send character export request");
+        }
+    }
 
-    transaction.commit()?;
+    /// Creates a new character for the player from the export at `path`. The
+    /// outcome is sent back as a [`CharacterTransferResponse`].
+    pub fn import_character(
+        &mut self,
+        player_uuid: String,
+        path: PathBuf,
+        key: String,
+        alias: Option<String>,
+        username: String,
+        requester: TransferRequester,
+    ) {
+        if let Err(e) =
+            self.update_tx
+                .as_ref()
+                .unwrap()
+                .send(CharacterUpdaterAction::ImportCharacter {
+                    player_uuid,
+                    path,
+                    key,
+                    alias,
+                    username,
+                    requester,
+                })
+        {
+            error!(?e, "Could not send character import request");
+        }
+    }
 
-    trace!("Commit for character batch update completed");
-    Ok(())
+    /// Returns a non-blocking iterator over CharacterUpdaterMessage messages
+    pub fn messages(&self) -> TryIter<'_, CharacterUpdaterMessage> { self.response_rx.try_iter() }
 }
 
 fn execute_character_create(
@@ -461,25 +661,15 @@ fn execute_character_create(
     alias: String,
     requesting_player_uuid: &str,
     persisted_components: PersistedComponents,
-    connection: &mut VelorenConnection,
+    backend: &mut dyn PersistenceBackend,
 ) -> Result<CharacterUpdaterMessage, PersistenceError> {
-    let mut transaction = connection.connection.transaction()?;
-
-    let response = CharacterScreenResponse {
-        target_entity: entity,
-        response_kind: CharacterScreenResponseKind::CharacterCreation(
-            super::character::create_character(
-                requesting_player_uuid,
-                &alias,
-                persisted_components,
-                &mut transaction,
+    let response =
+        CharacterScreenResponse {
+            target_entity: entity,
+            response_kind: CharacterScreenResponseKind::CharacterCreation(
+                backend.create_character(requesting_player_uuid, &alias, persisted_components)?,
             ),
-        ),
-    };
-
-    if !response.is_err() {
-        transaction.commit()?;
-    };
+        };
 
     Ok(CharacterUpdaterMessage::CharacterScreenResponse(response))
 }
@@ -491,26 +681,17 @@ fn execute_character_edit(
     requesting_player_uuid: &str,
     editable_components: EditableComponents,
     trusted_change: Option<PermanentChange>,
-    connection: &mut VelorenConnection,
+    backend: &mut dyn PersistenceBackend,
 ) -> Result<CharacterUpdaterMessage, PersistenceError> {
-    let mut transaction = connection.connection.transaction()?;
-
     let response = CharacterScreenResponse {
         target_entity: entity,
-        response_kind: CharacterScreenResponseKind::CharacterEdit(
-            super::character::edit_character(
-                editable_components,
-                trusted_change,
-                &mut transaction,
-                character_id,
-                requesting_player_uuid,
-                alias.as_deref(),
-            ),
-        ),
-    };
-
-    if !response.is_err() {
-        transaction.commit()?;
+        response_kind: CharacterScreenResponseKind::CharacterEdit(backend.edit_character(
+            requesting_player_uuid,
+            character_id,
+            alias.as_deref(),
+            editable_components,
+            trusted_change,
+        )?),
     };
 
     Ok(CharacterUpdaterMessage::CharacterScreenResponse(response))
@@ -0,0 +1,261 @@
+//! Database operations for player guilds
+//!
+//! Guilds are loaded once on server startup into the
+//! [`GuildManager`](crate::guild::GuildManager), afterwards its changes are
+//! written in batches by the [`CharacterUpdater`] thread.
+//!
+//! [`CharacterUpdater`]: super::character_updater::CharacterUpdater
+
+use crate::persistence::{ConnectionMode, DatabaseSettings, backend, error::PersistenceError};
+use common::{
+    character::CharacterId,
+    comp::guild::{GuildId, GuildRank},
+};
+use rusqlite::{Connection, Transaction};
+use tracing::{debug, trace};
+
+/// A modification of the guilds, applied in order
+#[derive(Clone, Debug)]
+pub enum GuildChange {
+    Create {
+        guild_id: GuildId,
+        name: String,
+    },
+    /// Removes the guild together with all of its members
+    Disband(GuildId),
+    /// Adds the character to the guild, or changes its rank
+    SetMember {
+        character_id: CharacterId,
+        guild_id: GuildId,
+        rank: GuildRank,
+    },
+    RemoveMember(CharacterId),
+}
+
+#[derive(Debug)]
+pub struct GuildMemberRow {
+    pub character_id: CharacterId,
+    pub guild_id: GuildId,
+    pub alias: String,
+    pub player_uuid: String,
+    pub rank: GuildRank,
+}
+
+#[derive(Debug, Default)]
+pub struct GuildRows {
+    pub guilds: Vec<(GuildId, String)>,
+    pub members: Vec<GuildMemberRow>,
+}
+
+/// Reads all guilds and their members
+pub fn load_guilds(settings: &DatabaseSettings) -> Result<GuildRows, PersistenceError> {
+    let rows = backend::connect(settings, ConnectionMode::ReadOnly).load_guilds()?;
+    debug!(
+        "Loaded {} guilds with {} members",
+        rows.guilds.len(),
+        rows.members.len()
+    );
+    Ok(rows)
+}
+
+fn convert_rank_to_database(rank: GuildRank) -> &'static str {
+    match rank {
+        GuildRank::Member => "Member",
+        GuildRank::Officer => "Officer",
+        GuildRank::Leader => "Leader",
+    }
+}
+
+fn convert_rank_from_database(rank: &str) -> Result<GuildRank, PersistenceError> {
+    match rank {
+        "Member" => Ok(GuildRank::Member),
+        "Officer" => Ok(GuildRank::Officer),
+        "Leader" => Ok(GuildRank::Leader),
+        _ => Err(PersistenceError::ConversionError(format!(
+            "Unknown guild rank {rank}"
+        ))),
+    }
+}
+
+const SELECT_GUILDS: &str = "
+        SELECT  guild_id,
+                name
+        FROM    guild";
+
+const SELECT_MEMBERS: &str = "
+        SELECT  m.character_id,
+                m.guild_id,
+                c.alias,
+                c.player_uuid,
+                m.rank
+        FROM    guild_member m
+        JOIN    character c ON (m.character_id = c.character_id)";
+
+pub(super) fn load_guild_rows(connection: &Connection) -> Result<GuildRows, PersistenceError> {
+    let mut stmt = connection.prepare_cached(SELECT_GUILDS)?;
+    let guilds = stmt
+        .query_map([], |row| {
+            Ok((GuildId(row.get::<_, i64>(0)? as u64), row.get(1)?))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    drop(stmt);
+
+    let mut stmt = connection.prepare_cached(SELECT_MEMBERS)?;
+    let members = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, i64>(1)?,
+                row.get(2)?,
+                row.get(3)?,
+                row.get::<_, String>(4)?,
+            ))
+        })?
+        .map(|row| {
+            let (character_id, guild_id, alias, player_uuid, rank) = row?;
+            Ok(GuildMemberRow {
+                character_id: CharacterId(character_id),
+                guild_id: GuildId(guild_id as u64),
+                alias,
+                player_uuid,
+                rank: convert_rank_from_database(&rank)?,
+            })
+        })
+        .collect::<Result<Vec<_>, PersistenceError>>()?;
+
+    Ok(GuildRows { guilds, members })
+}
+
+pub(super) fn update_guilds(
+    changes: Vec<GuildChange>,
+    transaction: &mut Transaction,
+) -> Result<(), PersistenceError> {
+    trace!("Writing {} guild changes", changes.len());
+
+    for change in changes {
+        match change {
+            GuildChange::Create { guild_id, name } => {
+                transaction
+                    .prepare_cached("INSERT INTO guild (guild_id, name) VALUES (?1, ?2)")?
+                    .execute((guild_id.0 as i64, &name))?;
+            },
+            GuildChange::Disband(guild_id) => {
+                transaction
+                    .prepare_cached("DELETE FROM guild_member WHERE guild_id = ?1")?
+                    .execute([guild_id.0 as i64])?;
+                transaction
+                    .prepare_cached("DELETE FROM guild WHERE guild_id = ?1")?
+                    .execute([guild_id.0 as i64])?;
+            },
+            GuildChange::SetMember {
+                character_id,
+                guild_id,
+                rank,
+            } => {
+                transaction
+                    .prepare_cached(
+                        "
+                        REPLACE
+                        INTO    guild_member (character_id,
+                                              guild_id,
+                                              rank)
+                        VALUES  (?1, ?2, ?3)",
+                    )?
+                    .execute((
+                        character_id.0,
+                        guild_id.0 as i64,
+                        convert_rank_to_database(rank),
+                    ))?;
+            },
+            GuildChange::RemoveMember(character_id) => {
+                transaction
+                    .prepare_cached("DELETE FROM guild_member WHERE character_id = ?1")?
+                    .execute([character_id.0])?;
+            },
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "postgres")]
+pub(super) fn load_guild_rows_postgres(
+    client: &mut postgres::Client,
+) -> Result<GuildRows, PersistenceError> {
+    let guilds = client
+        .query(SELECT_GUILDS, &[])?
+        .iter()
+        .map(|row| (GuildId(row.get::<_, i64>(0) as u64), row.get(1)))
+        .collect();
+    let members = client
+        .query(SELECT_MEMBERS, &[])?
+        .iter()
+        .map(|row| {
+            Ok(GuildMemberRow {
+                character_id: CharacterId(row.get(0)),
+                guild_id: GuildId(row.get::<_, i64>(1) as u64),
+                alias: row.get(2),
+                player_uuid: row.get(3),
+                rank: convert_rank_from_database(row.get(4))?,
+            })
+        })
+        .collect::<Result<Vec<_>, PersistenceError>>()?;
+
+    Ok(GuildRows { guilds, members })
+}
+
+#[cfg(feature = "postgres")]
+pub(super) fn update_guilds_postgres(
+    changes: Vec<GuildChange>,
+    transaction: &mut postgres::Transaction,
+) -> Result<(), PersistenceError> {
+    trace!("Writing {} guild changes", changes.len());
+
+    for change in changes {
+        match change {
+            GuildChange::Create { guild_id, name } => {
+                transaction.execute("INSERT INTO guild (guild_id, name) VALUES ($1, $2)", &[
+                    &(guild_id.0 as i64),
+                    &name,
+                ])?;
+            },
+            GuildChange::Disband(guild_id) => {
+                transaction.execute("DELETE FROM guild_member WHERE guild_id = $1", &[
+                    &(guild_id.0 as i64),
+                ])?;
+                transaction.execute("DELETE FROM guild WHERE guild_id = $1", &[
+                    &(guild_id.0 as i64)
+                ])?;
+            },
+            GuildChange::SetMember {
+                character_id,
+                guild_id,
+                rank,
+            } => {
+                transaction.execute(
+                    "
+                    INSERT
+                    INTO    guild_member (character_id,
+                                          guild_id,
+                                          rank)
+                    VALUES  ($1, $2, $3)
+                    ON CONFLICT (character_id) DO UPDATE
+                    SET     guild_id = EXCLUDED.guild_id,
+                            rank = EXCLUDED.rank",
+                    &[
+                        &character_id.0,
+                        &(guild_id.0 as i64),
+                        &convert_rank_to_database(rank),
+                    ],
+                )?;
+            },
+            GuildChange::RemoveMember(character_id) => {
+                transaction.execute("DELETE FROM guild_member WHERE character_id = $1", &[
+                    &character_id.0,
+                ])?;
+            },
+        }
+    }
+
+    Ok(())
+}
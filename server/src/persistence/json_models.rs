@@ -73,6 +73,14 @@ pub struct CharacterPosition {
     pub map_marker: Option<Vec2<i32>>,
 }
 
+/// Reputation of a character, sites are keyed by their position and factions
+/// by their seed.
+#[derive(Serialize, Deserialize)]
+pub struct CharacterReputation {
+    pub sites: Vec<([i32; 2], f32)>,
+    pub factions: Vec<(u32, f32)>,
+}
+
 pub fn skill_group_to_db_string(skill_group: comp::skillset::SkillGroupKind) -> String {
     use comp::{item::tool::ToolKind, skillset::SkillGroupKind::*};
     let skill_group_string = match skill_group {
@@ -341,6 +349,19 @@ pub fn apply_db_item_properties(item: &mut comp::Item, properties: &DatabaseItem
     item.persistence_set_durability(*durability);
 }
 
+/// An item attached to a mail or listed on the market. These items aren't owned
+/// by a character, so unlike inventory items they aren't stored in the item
+/// table but as JSON in the mail or listing itself.
+#[derive(Serialize, Deserialize)]
+pub struct DatabaseMailItem {
+    pub item_definition_id: String,
+    pub stack_size: u32,
+    pub properties: DatabaseItemProperties,
+    /// Components of modular items
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub components: Vec<DatabaseMailItem>,
+}
+
 #[cfg(test)]
 pub mod tests {
     #[test]
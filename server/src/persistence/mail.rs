@@ -0,0 +1,308 @@
+//! Database operations for player mail
+//!
+//! Mail is loaded once on server startup into the
+//! [`MailManager`](crate::mail::MailManager). Its changes move items between
+//! characters, so they are written in the same transaction as the character
+//! batch updates of the [`CharacterUpdater`] thread.
+//!
+//! [`CharacterUpdater`]: super::character_updater::CharacterUpdater
+
+use crate::persistence::{
+    ConnectionMode, DatabaseSettings, backend,
+    character::{
+        convert_mail_attachments_from_database_json, convert_mail_attachments_to_database_json,
+    },
+    error::PersistenceError,
+};
+use common::{
+    character::CharacterId,
+    comp::{self, Content, mail::MailId},
+};
+use rusqlite::{Connection, Transaction};
+use tracing::{debug, trace};
+
+#[derive(Clone, Debug)]
+pub struct MailRow {
+    pub id: MailId,
+    /// `None` once the sender was deleted
+    pub sender: Option<CharacterId>,
+    pub sender_name: String,
+    pub recipient: CharacterId,
+    /// Localised by the client, only mail sent by the server isn't plain text
+    pub subject: Content,
+    pub body: Content,
+    pub coins: u32,
+    pub attachments: Vec<comp::Item>,
+    pub sent_at: i64,
+    pub expires_at: i64,
+    pub read: bool,
+    pub returned: bool,
+}
+
+/// A modification of the mail, applied in order
+#[derive(Clone, Debug)]
+pub enum MailChange {
+    Upsert(Box<MailRow>),
+    Delete(MailId),
+}
+
+#[derive(Debug, Default)]
+pub struct MailRows {
+    pub mail: Vec<MailRow>,
+    /// Id, alias and player uuid of every character, mail can be sent to
+    /// characters that aren't online
+    pub characters: Vec<(CharacterId, String, String)>,
+}
+
+/// Reads all mail and the characters it can be sent to
+pub fn load_mail(settings: &DatabaseSettings) -> Result<MailRows, PersistenceError> {
+    let rows = backend::connect(settings, ConnectionMode::ReadOnly).load_mail()?;
+    debug!(
+        "Loaded {} mail of {} characters",
+        rows.mail.len(),
+        rows.characters.len()
+    );
+    Ok(rows)
+}
+
+fn convert_text_to_database_json(text: &Content) -> String {
+    serde_json::to_string(text).expect("Failed to convert mail text to a json string.")
+}
+
+fn convert_text_from_database_json(text: &str) -> Result<Content, PersistenceError> {
+    Ok(serde_json::de::from_str(text)?)
+}
+
+const SELECT_MAIL: &str = "
+        SELECT  mail_id,
+                sender_id,
+                sender_name,
+                recipient_id,
+                subject,
+                body,
+                coins,
+                attachments,
+                sent_at,
+                expires_at,
+                is_read,
+                is_returned
+        FROM    mail";
+
+const SELECT_CHARACTERS: &str = "
+        SELECT  character_id,
+                alias,
+                player_uuid
+        FROM    character";
+
+pub(super) fn load_mail_rows(connection: &Connection) -> Result<MailRows, PersistenceError> {
+    let mut stmt = connection.prepare_cached(SELECT_MAIL)?;
+    let mail = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, Option<i64>>(1)?,
+                row.get(2)?,
+                row.get::<_, i64>(3)?,
+                row.get::<_, String>(4)?,
+                row.get::<_, String>(5)?,
+                row.get::<_, i64>(6)?,
+                row.get::<_, String>(7)?,
+                row.get(8)?,
+                row.get(9)?,
+                row.get(10)?,
+                row.get(11)?,
+            ))
+        })?
+        .map(|row| {
+            let (
+                mail_id,
+                sender_id,
+                sender_name,
+                recipient_id,
+                subject,
+                body,
+                coins,
+                attachments,
+                sent_at,
+                expires_at,
+                read,
+                returned,
+            ) = row?;
+            Ok(MailRow {
+                id: MailId(mail_id as u64),
+                sender: sender_id.map(CharacterId),
+                sender_name,
+                recipient: CharacterId(recipient_id),
+                subject: convert_text_from_database_json(&subject)?,
+                body: convert_text_from_database_json(&body)?,
+                coins: coins as u32,
+                attachments: convert_mail_attachments_from_database_json(&attachments)?,
+                sent_at,
+                expires_at,
+                read,
+                returned,
+            })
+        })
+        .collect::<Result<Vec<_>, PersistenceError>>()?;
+    drop(stmt);
+
+    let mut stmt = connection.prepare_cached(SELECT_CHARACTERS)?;
+    let characters = stmt
+        .query_map([], |row| {
+            Ok((CharacterId(row.get(0)?), row.get(1)?, row.get(2)?))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(MailRows { mail, characters })
+}
+
+pub(super) fn update_mail(
+    changes: Vec<MailChange>,
+    transaction: &mut Transaction,
+) -> Result<(), PersistenceError> {
+    trace!("Writing {} mail changes", changes.len());
+
+    for change in changes {
+        match change {
+            MailChange::Upsert(mail) => {
+                transaction
+                    .prepare_cached(
+                        "
+                        REPLACE
+                        INTO    mail (mail_id,
+                                      sender_id,
+                                      sender_name,
+                                      recipient_id,
+                                      subject,
+                                      body,
+                                      coins,
+                                      attachments,
+                                      sent_at,
+                                      expires_at,
+                                      is_read,
+                                      is_returned)
+                        VALUES  (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+                    )?
+                    .execute(rusqlite::params![
+                        mail.id.0 as i64,
+                        mail.sender.map(|sender| sender.0),
+                        &mail.sender_name,
+                        mail.recipient.0,
+                        convert_text_to_database_json(&mail.subject),
+                        convert_text_to_database_json(&mail.body),
+                        mail.coins as i64,
+                        convert_mail_attachments_to_database_json(&mail.attachments),
+                        mail.sent_at,
+                        mail.expires_at,
+                        mail.read,
+                        mail.returned,
+                    ])?;
+            },
+            MailChange::Delete(mail_id) => {
+                transaction
+                    .prepare_cached("DELETE FROM mail WHERE mail_id = ?1")?
+                    .execute([mail_id.0 as i64])?;
+            },
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "postgres")]
+pub(super) fn load_mail_rows_postgres(
+    client: &mut postgres::Client,
+) -> Result<MailRows, PersistenceError> {
+    let mail = client
+        .query(SELECT_MAIL, &[])?
+        .iter()
+        .map(|row| {
+            Ok(MailRow {
+                id: MailId(row.get::<_, i64>(0) as u64),
+                sender: row.get::<_, Option<i64>>(1).map(CharacterId),
+                sender_name: row.get(2),
+                recipient: CharacterId(row.get(3)),
+                subject: convert_text_from_database_json(row.get(4))?,
+                body: convert_text_from_database_json(row.get(5))?,
+                coins: row.get::<_, i64>(6) as u32,
+                attachments: convert_mail_attachments_from_database_json(row.get(7))?,
+                sent_at: row.get(8),
+                expires_at: row.get(9),
+                read: row.get::<_, i64>(10) != 0,
+                returned: row.get::<_, i64>(11) != 0,
+            })
+        })
+        .collect::<Result<Vec<_>, PersistenceError>>()?;
+    let characters = client
+        .query(SELECT_CHARACTERS, &[])?
+        .iter()
+        .map(|row| (CharacterId(row.get(0)), row.get(1), row.get(2)))
+        .collect();
+
+    Ok(MailRows { mail, characters })
+}
+
+#[cfg(feature = "postgres")]
+pub(super) fn update_mail_postgres(
+    changes: Vec<MailChange>,
+    transaction: &mut postgres::Transaction,
+) -> Result<(), PersistenceError> {
+    trace!("Writing {} mail changes", changes.len());
+
+    for change in changes {
+        match change {
+            MailChange::Upsert(mail) => {
+                transaction.execute(
+                    "
+                    INSERT
+                    INTO    mail (mail_id,
+                                  sender_id,
+                                  sender_name,
+                                  recipient_id,
+                                  subject,
+                                  body,
+                                  coins,
+                                  attachments,
+                                  sent_at,
+                                  expires_at,
+                                  is_read,
+                                  is_returned)
+                    VALUES  ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+                    ON CONFLICT (mail_id) DO UPDATE
+                    SET     sender_id = EXCLUDED.sender_id,
+                            sender_name = EXCLUDED.sender_name,
+                            recipient_id = EXCLUDED.recipient_id,
+                            subject = EXCLUDED.subject,
+                            body = EXCLUDED.body,
+                            coins = EXCLUDED.coins,
+                            attachments = EXCLUDED.attachments,
+                            sent_at = EXCLUDED.sent_at,
+                            expires_at = EXCLUDED.expires_at,
+                            is_read = EXCLUDED.is_read,
+                            is_returned = EXCLUDED.is_returned",
+                    &[
+                        &(mail.id.0 as i64),
+                        &mail.sender.map(|sender| sender.0),
+                        &mail.sender_name,
+                        &mail.recipient.0,
+                        &convert_text_to_database_json(&mail.subject),
+                        &convert_text_to_database_json(&mail.body),
+                        &(mail.coins as i64),
+                        &convert_mail_attachments_to_database_json(&mail.attachments),
+                        &mail.sent_at,
+                        &mail.expires_at,
+                        &(mail.read as i64),
+                        &(mail.returned as i64),
+                    ],
+                )?;
+            },
+            MailChange::Delete(mail_id) => {
+                transaction.execute("DELETE FROM mail WHERE mail_id = $1", &[
+                    &(mail_id.0 as i64)
+                ])?;
+            },
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,209 @@
+//! Database operations for the player market
+//!
+//! Listings are loaded once on server startup into the
+//! [`MarketManager`](crate::market::MarketManager). Listing and buying items
+//! moves them between characters, so changes are written in the same
+//! transaction as the character batch updates of the [`CharacterUpdater`]
+//! thread.
+//!
+//! [`CharacterUpdater`]: super::character_updater::CharacterUpdater
+
+use crate::persistence::{
+    ConnectionMode, DatabaseSettings, backend,
+    character::{convert_market_item_from_database_json, convert_market_item_to_database_json},
+    error::PersistenceError,
+};
+use common::{character::CharacterId, comp, comp::market::ListingId};
+use rusqlite::{Connection, Transaction};
+use tracing::{debug, trace, warn};
+
+#[derive(Clone, Debug)]
+pub struct ListingRow {
+    pub id: ListingId,
+    pub seller: CharacterId,
+    /// Not stored with the listing, but read from the character
+    pub seller_name: String,
+    pub item: comp::Item,
+    pub price: u32,
+    pub listed_at: i64,
+    pub expires_at: i64,
+}
+
+/// A modification of the listings, applied in order. Listings never change
+/// once they were posted.
+#[derive(Clone, Debug)]
+pub enum ListingChange {
+    Insert(Box<ListingRow>),
+    Delete(ListingId),
+}
+
+#[derive(Debug, Default)]
+pub struct ListingRows {
+    pub listings: Vec<ListingRow>,
+    /// Listings whose item can't be loaded anymore, for example because the
+    /// item was removed from the game. They are deleted with the next changes.
+    pub invalid: Vec<ListingId>,
+}
+
+/// Reads all listings of the market
+pub fn load_listings(settings: &DatabaseSettings) -> Result<ListingRows, PersistenceError> {
+    let rows = backend::connect(settings, ConnectionMode::ReadOnly).load_listings()?;
+    debug!("Loaded {} market listings", rows.listings.len());
+    Ok(rows)
+}
+
+const SELECT_LISTINGS: &str = "
+        SELECT  l.listing_id,
+                l.seller_id,
+                c.alias,
+                l.item,
+                l.price,
+                l.listed_at,
+                l.expires_at
+        FROM    market_listing l
+        JOIN    character c ON c.character_id = l.seller_id";
+
+type DatabaseListing = (i64, i64, String, String, i64, i64, i64);
+
+fn convert_listings(db_listings: impl Iterator<Item = DatabaseListing>) -> ListingRows {
+    let mut rows = ListingRows::default();
+    for (listing_id, seller_id, seller_name, item, price, listed_at, expires_at) in db_listings {
+        let id = ListingId(listing_id as u64);
+        match convert_market_item_from_database_json(&item) {
+            Ok(item) => rows.listings.push(ListingRow {
+                id,
+                seller: CharacterId(seller_id),
+                seller_name,
+                item,
+                price: price as u32,
+                listed_at,
+                expires_at,
+            }),
+            Err(error) => {
+                warn!(?error, ?listing_id, "Skipping market listing");
+                rows.invalid.push(id);
+            },
+        }
+    }
+    rows
+}
+
+pub(super) fn load_listing_rows(connection: &Connection) -> Result<ListingRows, PersistenceError> {
+    let mut stmt = connection.prepare_cached(SELECT_LISTINGS)?;
+    let db_listings = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get(0)?,
+                row.get(1)?,
+                row.get(2)?,
+                row.get(3)?,
+                row.get(4)?,
+                row.get(5)?,
+                row.get(6)?,
+            ))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(convert_listings(db_listings.into_iter()))
+}
+
+pub(super) fn update_listings(
+    changes: Vec<ListingChange>,
+    transaction: &mut Transaction,
+) -> Result<(), PersistenceError> {
+    trace!("Writing {} market listing changes", changes.len());
+
+    for change in changes {
+        match change {
+            ListingChange::Insert(listing) => {
+                transaction
+                    .prepare_cached(
+                        "
+                        INSERT
+                        INTO    market_listing (listing_id,
+                                                seller_id,
+                                                item,
+                                                price,
+                                                listed_at,
+                                                expires_at)
+                        VALUES  (?1, ?2, ?3, ?4, ?5, ?6)",
+                    )?
+                    .execute(rusqlite::params![
+                        listing.id.0 as i64,
+                        listing.seller.0,
+                        convert_market_item_to_database_json(&listing.item),
+                        listing.price as i64,
+                        listing.listed_at,
+                        listing.expires_at,
+                    ])?;
+            },
+            ListingChange::Delete(listing_id) => {
+                transaction
+                    .prepare_cached("DELETE FROM market_listing WHERE listing_id = ?1")?
+                    .execute([listing_id.0 as i64])?;
+            },
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "postgres")]
+pub(super) fn load_listing_rows_postgres(
+    client: &mut postgres::Client,
+) -> Result<ListingRows, PersistenceError> {
+    Ok(convert_listings(
+        client.query(SELECT_LISTINGS, &[])?.iter().map(|row| {
+            (
+                row.get(0),
+                row.get(1),
+                row.get(2),
+                row.get(3),
+                row.get(4),
+                row.get(5),
+                row.get(6),
+            )
+        }),
+    ))
+}
+
+#[cfg(feature = "postgres")]
+pub(super) fn update_listings_postgres(
+    changes: Vec<ListingChange>,
+    transaction: &mut postgres::Transaction,
+) -> Result<(), PersistenceError> {
+    trace!("Writing {} market listing changes", changes.len());
+
+    for change in changes {
+        match change {
+            ListingChange::Insert(listing) => {
+                transaction.execute(
+                    "
+                    INSERT
+                    INTO    market_listing (listing_id,
+                                            seller_id,
+                                            item,
+                                            price,
+                                            listed_at,
+                                            expires_at)
+                    VALUES  ($1, $2, $3, $4, $5, $6)",
+                    &[
+                        &(listing.id.0 as i64),
+                        &listing.seller.0,
+                        &convert_market_item_to_database_json(&listing.item),
+                        &(listing.price as i64),
+                        &listing.listed_at,
+                        &listing.expires_at,
+                    ],
+                )?;
+            },
+            ListingChange::Delete(listing_id) => {
+                transaction.execute("DELETE FROM market_listing WHERE listing_id = $1", &[
+                    &(listing_id.0 as i64),
+                ])?;
+            },
+        }
+    }
+
+    Ok(())
+}
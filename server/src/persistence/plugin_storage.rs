@@ -0,0 +1,115 @@
+//! Database operations for the key-value storage of server plugins
+//!
+//! The storage is loaded once on server startup, afterwards changes are
+//! written in batches by the [`CharacterUpdater`] thread.
+//!
+//! [`CharacterUpdater`]: super::character_updater::CharacterUpdater
+
+use crate::persistence::{ConnectionMode, DatabaseSettings, backend, error::PersistenceError};
+use common_state::plugin::storage::PluginStorageChange;
+use rusqlite::{Connection, Transaction};
+use tracing::{debug, trace};
+
+type Entries = Vec<(String, String, Vec<u8>)>;
+
+/// Reads all plugin storage entries as `(plugin, key, value)`
+pub fn load_plugin_storage(settings: &DatabaseSettings) -> Result<Entries, PersistenceError> {
+    let entries = backend::connect(settings, ConnectionMode::ReadOnly).load_plugin_storage()?;
+    debug!("Loaded {} plugin storage entries", entries.len());
+    Ok(entries)
+}
+
+pub(super) fn load_entries(connection: &Connection) -> Result<Entries, PersistenceError> {
+    let mut stmt = connection.prepare_cached(
+        "
+        SELECT  plugin,
+                key,
+                value
+        FROM    plugin_storage",
+    )?;
+
+    Ok(stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+        .collect::<Result<Vec<_>, _>>()?)
+}
+
+pub(super) fn update_plugin_storage(
+    changes: Vec<PluginStorageChange>,
+    transaction: &mut Transaction,
+) -> Result<(), PersistenceError> {
+    trace!("Writing {} plugin storage changes", changes.len());
+
+    let mut upsert_stmt = transaction.prepare_cached(
+        "
+        REPLACE
+        INTO    plugin_storage (plugin,
+                                key,
+                                value)
+        VALUES  (?1, ?2, ?3)",
+    )?;
+    let mut delete_stmt = transaction.prepare_cached(
+        "
+        DELETE
+        FROM    plugin_storage
+        WHERE   plugin = ?1
+        AND     key = ?2",
+    )?;
+
+    for change in changes {
+        match change.value {
+            Some(value) => {
+                upsert_stmt.execute((&change.plugin, &change.key, &value))?;
+            },
+            None => {
+                delete_stmt.execute([&change.plugin, &change.key])?;
+            },
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "postgres")]
+pub(super) fn load_entries_postgres(
+    client: &mut postgres::Client,
+) -> Result<Entries, PersistenceError> {
+    Ok(client
+        .query("SELECT plugin, key, value FROM plugin_storage", &[])?
+        .iter()
+        .map(|row| (row.get(0), row.get(1), row.get(2)))
+        .collect())
+}
+
+#[cfg(feature = "postgres")]
+pub(super) fn update_plugin_storage_postgres(
+    changes: Vec<PluginStorageChange>,
+    transaction: &mut postgres::Transaction,
+) -> Result<(), PersistenceError> {
+    trace!("Writing {} plugin storage changes", changes.len());
+
+    let upsert_stmt = transaction.prepare(
+        "
+        INSERT
+        INTO    plugin_storage (plugin,
+                                key,
+                                value)
+        VALUES  ($1, $2, $3)
+        ON CONFLICT (plugin, key) DO UPDATE
+        SET     value = EXCLUDED.value",
+    )?;
+    let delete_stmt =
+        transaction.prepare("DELETE FROM plugin_storage WHERE plugin = $1 AND key = $2")?;
+
+    for change in changes {
+        match change.value {
+            Some(value) => {
+                transaction.execute(&upsert_stmt, &[&change.plugin, &change.key, &value])?;
+            },
+            None => {
+                transaction.execute(&delete_stmt, &[&change.plugin, &change.key])?;
+            },
+        }
+    }
+
+    Ok(())
+}
@@ -137,7 +137,7 @@ impl RtSim {
         Ok(this)
     }
 
-    fn get_file_path(mut data_dir: PathBuf) -> PathBuf {
+    pub(crate) fn get_file_path(mut data_dir: PathBuf) -> PathBuf {
         let mut path = std::env::var("VELOREN_RTSIM")
             .map(PathBuf::from)
             .unwrap_or_else(|_| {
@@ -349,6 +349,15 @@ impl RtSim {
             .unwrap_or_default()
     }
 
+    /// The reputation that `character` has with the home site and faction of
+    /// `npc`, in the range `-1.0..=1.0`.
+    pub fn reputation_standing(&self, npc: ActorId, character: ActorId) -> f32 {
+        let data = self.state.data();
+        data.actors
+            .get(npc)
+            .map_or(0.0, |npc| data.reputation_standing(npc, character))
+    }
+
     pub fn state(&self) -> &RtState { &self.state }
 
     pub fn set_should_purge(&mut self, should_purge: bool) {
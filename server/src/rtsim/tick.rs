@@ -12,7 +12,7 @@ use common::{
     event::{CreateNpcEvent, CreateShipEvent, DeleteEvent, EventBus, NpcBuilder},
     generation::{BodyBuilder, EntityConfig, EntityInfo},
     resources::{DeltaTime, Time, TimeOfDay},
-    rtsim::ActorId,
+    rtsim::{ActorId, PersistedReputation},
     slowjob::SlowJobPool,
     terrain::CoordinateConversions,
     trade::{Good, SiteInformation},
@@ -28,6 +28,7 @@ use rtsim::{
         Sites,
         actor::{Actor, Presence, Profession, SimulationMode},
     },
+    rule::reputation::persist_reputation,
 };
 use specs::{Entities, Join, LendJoin, Read, ReadExpect, ReadStorage, WriteExpect, WriteStorage};
 use std::{
@@ -469,6 +470,7 @@ impl<'a> System<'a> for Sys {
         WriteExpect<'a, comp::gizmos::RtsimGizmos>,
         ReadExpect<'a, comp::tool::AbilityMap>,
         ReadExpect<'a, comp::item::MaterialStatManifest>,
+        WriteStorage<'a, PersistedReputation>,
     );
 
     const NAME: &'static str = "rtsim::tick";
@@ -501,6 +503,7 @@ impl<'a> System<'a> for Sys {
             rtsim_gizmos,
             ability_map,
             msm,
+            mut reputations,
         ): Self::SystemData,
     ) {
         let mut create_ship_emitter = create_ship_events.emitter();
@@ -670,12 +673,13 @@ impl<'a> System<'a> for Sys {
         }
 
         // Synchronise rtsim NPC with entity data
-        for (entity, pos, body, rtsim_actor, mut agent) in (
+        for (entity, pos, body, rtsim_actor, mut agent, reputation) in (
             &entities,
             &positions,
             &bodies,
             &rtsim_actors,
             (&mut agents).maybe(),
+            (&mut reputations).maybe(),
         )
             .join()
         {
@@ -687,6 +691,14 @@ impl<'a> System<'a> for Sys {
                 // For players, let rtsim know that the character is present in the world
                 if let Some(character) = actor.character_mut() {
                     character.last_present_at = Some(data.tick);
+                    // Mirror the reputation so that it gets persisted with the character
+                    if let Some(reputation) = reputation {
+                        let persisted =
+                            persist_reputation(&character.reputation, &data.sites, &data.factions);
+                        if *reputation != persisted {
+                            *reputation = persisted;
+                        }
+                    }
                     actor.presence.get_or_insert(Presence {
                         // TODO: This isn't correct, use the actual health %
                         health_fraction: 1.0,
@@ -15,6 +15,7 @@ pub use banlist::{
 pub use server_description::ServerDescriptions;
 pub use whitelist::{Whitelist, WhitelistInfo, WhitelistRecord};
 
+use crate::persistence::DatabaseBackend;
 use chrono::Utc;
 use common::{
     calendar::{Calendar, CalendarEvent},
@@ -96,6 +97,12 @@ pub enum Protocol {
     Tcp {
         address: SocketAddr,
     },
+    /// TCP wrapped in TLS, allowing streams that require encryption
+    Tls {
+        address: SocketAddr,
+        cert_file_path: PathBuf,
+        key_file_path: PathBuf,
+    },
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -154,6 +161,32 @@ impl Default for ModerationSettings {
     }
 }
 
+/// Scheduling and retention of world snapshots, see [`crate::backup`].
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SnapshotSettings {
+    /// Take a snapshot this often. Snapshots can still be taken manually when
+    /// this is `None`.
+    pub interval: Option<Duration>,
+    /// Number of snapshots to keep, older ones are deleted. `0` keeps all.
+    pub keep: usize,
+    /// Delete snapshots older than this. The newest snapshot is always kept.
+    pub max_age: Option<Duration>,
+    /// Directory the snapshots are stored in, relative to the data directory.
+    pub dir: PathBuf,
+}
+
+impl Default for SnapshotSettings {
+    fn default() -> Self {
+        Self {
+            interval: None,
+            keep: 10,
+            max_age: None,
+            dir: PathBuf::from("snapshots"),
+        }
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, Default)]
 pub enum CalendarMode {
     None,
@@ -180,6 +213,8 @@ pub struct Settings {
     pub gameserver_protocols: Vec<Protocol>,
     pub auth_server_address: Option<String>,
     pub query_address: Option<SocketAddr>,
+    /// Share the names of online players with anyone using the query server.
+    pub query_player_list: bool,
     pub max_players: u16,
     pub world_seed: u32,
     pub server_name: String,
@@ -194,15 +229,23 @@ pub struct Settings {
     pub max_player_for_kill_broadcast: Option<usize>,
     pub calendar_mode: CalendarMode,
 
-    /// Experimental feature. No guaranteed forwards-compatibility, may be
-    /// removed at *any time* with no migration.
-    #[serde(default, skip_serializing)]
-    pub experimental_terrain_persistence: bool,
+    /// Persist terrain modifications, e.g. from build mode, across restarts.
+    #[serde(default, alias = "experimental_terrain_persistence")]
+    pub terrain_persistence: bool,
+    /// Secret shared with other servers to sign and verify character exports.
+    /// Characters can only be exported and imported when this is set.
+    pub character_transfer_key: Option<String>,
+    /// The database characters are stored in, PostgreSQL requires the server
+    /// to be compiled with the `postgres` feature.
+    #[serde(default)]
+    pub database: DatabaseBackend,
 
     #[serde(default)]
     pub gameplay: GameplaySettings,
     #[serde(default)]
     pub moderation: ModerationSettings,
+    #[serde(default)]
+    pub snapshots: SnapshotSettings,
 
     #[serde(default)]
     pub world: WorldSettings,
@@ -221,6 +264,7 @@ impl Default for Settings {
             ],
             auth_server_address: Some("https://auth.veloren.net".into()),
             query_address: Some(SocketAddr::from((Ipv4Addr::UNSPECIFIED, 14006))),
+            query_player_list: false,
             world_seed: DEFAULT_WORLD_SEED,
             server_name: "Veloren Server".into(),
             max_players: 100,
@@ -231,9 +275,12 @@ impl Default for Settings {
             calendar_mode: CalendarMode::Auto,
             client_timeout: Duration::from_secs(40),
             max_player_for_kill_broadcast: None,
-            experimental_terrain_persistence: false,
+            terrain_persistence: false,
+            character_transfer_key: None,
+            database: DatabaseBackend::default(),
             gameplay: GameplaySettings::default(),
             moderation: ModerationSettings::default(),
+            snapshots: SnapshotSettings::default(),
             world: WorldSettings::default(),
         }
     }
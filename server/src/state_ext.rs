@@ -6,6 +6,9 @@ use crate::{
     chat::ChatExporter,
     client::Client,
     events::{self, shared::update_map_markers},
+    guild::GuildManager,
+    mail::MailManager,
+    market::MarketManager,
     persistence::PersistedComponents,
     pet::restore_pet,
     presence::RepositionToFreeSpace,
@@ -32,7 +35,7 @@ use common::{
 #[cfg(feature = "worldgen")]
 use common::{calendar::Calendar, resources::TimeOfDay, slowjob::SlowJobPool};
 use common_net::{
-    msg::{CharacterInfo, PlayerListUpdate, ServerGeneral},
+    msg::{CharacterInfo, Notification, PlayerListUpdate, ServerGeneral},
     sync::WorldSyncExt,
 };
 use common_state::State;
@@ -697,10 +700,12 @@ impl StateExt for State {
             stats,
             skill_set,
             inventory,
+            storage,
             waypoint,
             pets,
             active_abilities,
             map_marker,
+            reputation,
         } = components;
 
         if let Some(player_uid) = self.read_component_copied::<Uid>(entity) {
@@ -748,6 +753,7 @@ impl StateExt for State {
                 entity,
                 comp::InventoryUpdateBuffer::new(comp::InventoryUpdateEvent::Init),
             );
+            self.write_component_ignore_entity_dead(entity, storage);
 
             if let Some(hardcore) = hardcore {
                 self.write_component_ignore_entity_dead(entity, hardcore);
@@ -844,11 +850,15 @@ impl StateExt for State {
 
                 #[cfg(feature = "worldgen")]
                 {
-                    use ::rtsim::data::{Actor, actor::SimulationMode};
-                    let actor_id = {
+                    use ::rtsim::{
+                        data::{Actor, actor::SimulationMode},
+                        rule::reputation::{persist_reputation, restore_reputation},
+                    };
+                    let (actor_id, reputation) = {
                         let rtsim = self.ecs().write_resource::<RtSim>();
                         let mut data = rtsim.state().data_mut();
-                        data
+                        let data = &mut *data;
+                        let actor_id = data
                             .actors
                             .iter()
                             .find(|(_, a)| a.character().map_or(false, |c| c.id == char_id))
@@ -864,13 +874,83 @@ impl StateExt for State {
                                 // very soon afterwards as part of server <-> rtsim sync.
                                 comp::Body::default(),
                                 SimulationMode::Loaded,
-                            )))
+                            )));
+                        let reputation = data
+                            .actors
+                            .get_mut(actor_id)
+                            .and_then(|actor| actor.character_mut())
+                            .map(|character| {
+                                // The persisted reputation takes precedence over whatever rtsim
+                                // remembers
+                                if let Some(reputation) = reputation {
+                                    character.reputation = restore_reputation(
+                                        &reputation,
+                                        &data.sites,
+                                        &data.factions,
+                                    );
+                                }
+                                persist_reputation(
+                                    &character.reputation,
+                                    &data.sites,
+                                    &data.factions,
+                                )
+                            });
+                        (actor_id, reputation)
                     };
                     self.write_component_ignore_entity_dead(entity, actor_id);
                     self.ecs()
                         .write_resource::<IdMaps>()
                         .add_rtsim(actor_id, entity);
+                    if let Some(reputation) = reputation {
+                        self.write_component_ignore_entity_dead(entity, reputation);
+                    }
+                }
+                #[cfg(not(feature = "worldgen"))]
+                if let Some(reputation) = reputation {
+                    self.write_component_ignore_entity_dead(entity, reputation);
                 }
+
+                // Rejoin the guild of the character, if any
+                let guild = {
+                    let mut guild_manager = self.ecs().write_resource::<GuildManager>();
+                    guild_manager.update_member_name(char_id, &name);
+                    guild_manager
+                        .guild_of(char_id)
+                        .and_then(|id| guild_manager.guild(id).map(|guild| guild.component(id)))
+                };
+                if let Some(guild) = guild {
+                    let id = guild.id;
+                    self.write_component_ignore_entity_dead(entity, guild);
+                    let ecs = self.ecs();
+                    events::shared::send_guild_roster(
+                        &ecs.read_resource(),
+                        &ecs.read_resource(),
+                        &ecs.read_storage(),
+                        &ecs.read_storage(),
+                        id,
+                    );
+                }
+
+                // Tell the character about mail that arrived while it was away
+                let player_uuid = self
+                    .ecs()
+                    .read_storage::<comp::Player>()
+                    .get(entity)
+                    .map(|player| player.uuid().to_string())
+                    .unwrap_or_default();
+                let unread = self
+                    .ecs()
+                    .write_resource::<MailManager>()
+                    .character_logged_in(char_id, &name, &player_uuid);
+                if unread > 0
+                    && let Some(client) = self.ecs().read_storage::<Client>().get(entity)
+                {
+                    client.send_fallible(ServerGeneral::Notification(Notification::UnreadMail {
+                        count: unread,
+                    }));
+                }
+                // Forget a market board left open in a previous session
+                self.ecs().write_resource::<MarketManager>().close(char_id);
             }
 
             if self
@@ -1046,6 +1126,31 @@ impl StateExt for State {
                         ))
                     }
                 },
+                comp::ChatType::Duel(a, b) => {
+                    // Both duelists and everyone watching the duel
+                    let positions = ecs.read_storage::<comp::Pos>();
+                    let duelist_positions = [a, b]
+                        .into_iter()
+                        .filter_map(|uid| entity_from_uid(*uid))
+                        .filter_map(|e| positions.get(e))
+                        .collect::<Vec<_>>();
+                    for (client, uid, pos) in (
+                        &ecs.read_storage::<Client>(),
+                        &ecs.read_storage::<Uid>(),
+                        &positions,
+                    )
+                        .join()
+                    {
+                        if uid == a
+                            || uid == b
+                            || duelist_positions
+                                .iter()
+                                .any(|p| is_within(comp::ChatMsg::SAY_DISTANCE, pos, *p))
+                        {
+                            client.send_fallible(ServerGeneral::ChatMsg(resolved_msg.clone()));
+                        }
+                    }
+                },
                 comp::ChatType::Say(uid) => {
                     let entity_opt = entity_from_uid(*uid);
 
@@ -1104,13 +1209,27 @@ impl StateExt for State {
                     }
                 },
                 comp::ChatType::FactionMeta(s) | comp::ChatType::Faction(_, s) => {
-                    for (client, faction) in (
-                        &ecs.read_storage::<Client>(),
-                        &ecs.read_storage::<comp::Faction>(),
-                    )
-                        .join()
+                    let clients = ecs.read_storage::<Client>();
+                    let guilds = ecs.read_storage::<comp::Guild>();
+                    // The chat mode of the sender is reset when they leave their guild, but
+                    // messages can still be in flight
+                    if let comp::ChatType::Faction(from, _) = &msg.chat_type
+                        && entity_from_uid(*from)
+                            .and_then(|entity| guilds.get(entity))
+                            .is_none_or(|guild| &guild.name != s)
                     {
-                        if s == &faction.0 {
+                        let reply = comp::ChatType::CommandError
+                            .into_msg(Content::localized("command-message-guild-missing"));
+                        if let Some(client) =
+                            entity_from_uid(*from).and_then(|entity| clients.get(entity))
+                        {
+                            client.send_fallible(ServerGeneral::ChatMsg(reply));
+                        }
+                        return;
+                    }
+
+                    for (client, guild) in (&clients, &guilds).join() {
+                        if s == &guild.name {
                             client.send_fallible(ServerGeneral::ChatMsg(resolved_msg.clone()));
                         }
                     }
@@ -1,15 +1,15 @@
 use crate::{
     Tick,
-    chunk_serialize::{ChunkSendEntry, SerializedChunk},
+    chunk_serialize::{ChunkSendEntry, ChunkVersions, SerializedChunk},
     client::Client,
     metrics::NetworkRequestMetrics,
 };
 use common::{comp::Presence, event::EventBus, slowjob::SlowJobPool, terrain::TerrainGrid};
 use common_ecs::{Job, Origin, Phase, System};
-use common_net::msg::{SerializedTerrainChunk, ServerGeneral};
+use common_net::msg::{CompressedData, SerializedTerrainChunk, ServerGeneral};
 use hashbrown::{HashMap, hash_map::Entry};
 use network::StreamParams;
-use specs::{Entity, Read, ReadExpect, ReadStorage};
+use specs::{Entity, Read, ReadExpect, ReadStorage, Write};
 use std::sync::Arc;
 
 /// This system will handle sending terrain to clients by
@@ -27,6 +27,7 @@ impl<'a> System<'a> for Sys {
         ReadExpect<'a, SlowJobPool>,
         ReadExpect<'a, TerrainGrid>,
         ReadExpect<'a, crossbeam_channel::Sender<SerializedChunk>>,
+        Write<'a, ChunkVersions>,
     );
 
     const NAME: &'static str = "chunk_serialize";
@@ -44,6 +45,7 @@ impl<'a> System<'a> for Sys {
             slow_jobs,
             terrain,
             chunk_sender,
+            mut chunk_versions,
         ): Self::SystemData,
     ) {
         // Only operate twice per second
@@ -62,8 +64,28 @@ impl<'a> System<'a> for Sys {
         let mut chunks = HashMap::<_, Metadata>::new();
         let mut requests = 0u64;
         let mut distinct_requests = 0u64;
+        let mut deltas = 0u64;
 
         for queue_entry in chunk_send_queues_bus.recv_all() {
+            // Clients that still have an older version of the chunk only get the blocks
+            // changed since then. Those are few, so no need for a SlowJob.
+            if let Some(known_version) = queue_entry.known_version
+                && terrain.contains_key_real(queue_entry.chunk_key)
+                && let Some((version, blocks)) =
+                    chunk_versions.delta(queue_entry.chunk_key, known_version)
+            {
+                if let Some(client) = clients.get(queue_entry.entity) {
+                    client.send_fallible(ServerGeneral::TerrainChunkDelta {
+                        key: queue_entry.chunk_key,
+                        base_version: known_version,
+                        version,
+                        blocks: CompressedData::compress(&blocks, 1),
+                    });
+                    deltas += 1;
+                }
+                continue;
+            }
+
             let entry = chunks.entry(queue_entry.chunk_key);
             let meta = match entry {
                 Entry::Vacant(ve) => {
@@ -100,15 +122,17 @@ impl<'a> System<'a> for Sys {
         network_metrics
             .chunks_distinct_serialisation_requests
             .inc_by(distinct_requests);
+        network_metrics.chunks_served_delta.inc_by(deltas);
 
         // Trigger serialization in a SlowJob
         const CHUNK_SIZE: usize = 10; // trigger one job per 10 chunks to reduce SlowJob overhead. as we use a channel, there is no disadvantage to this
         let mut chunks_iter = chunks
             .into_iter()
             .filter_map(|(chunk_key, meta)| {
-                terrain
-                    .get_key_arc_real(chunk_key)
-                    .map(|chunk| (Arc::clone(chunk), chunk_key, meta))
+                terrain.get_key_arc_real(chunk_key).map(|chunk| {
+                    let version = chunk_versions.version(chunk_key);
+                    (Arc::clone(chunk), chunk_key, version, meta)
+                })
             })
             .peekable();
 
@@ -116,7 +140,7 @@ impl<'a> System<'a> for Sys {
             let chunks: Vec<_> = chunks_iter.by_ref().take(CHUNK_SIZE).collect();
             let chunk_sender = chunk_sender.clone();
             slow_jobs.spawn("CHUNK_SERIALIZER", move || {
-                for (chunk, chunk_key, mut meta) in chunks {
+                for (chunk, chunk_key, version, mut meta) in chunks {
                     let msg = Client::prepare_chunk_update_msg(
                         ServerGeneral::TerrainChunkUpdate {
                             key: chunk_key,
@@ -124,6 +148,7 @@ impl<'a> System<'a> for Sys {
                                 &chunk,
                                 meta.lossy_compression,
                             )),
+                            version,
                         },
                         &meta.params,
                     );
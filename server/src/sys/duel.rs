@@ -0,0 +1,257 @@
+use common::{
+    comp::{ChatType, Content, Duel, Health, Pos, Stats},
+    event::{ChatEvent, EventBus},
+    resources::Time,
+    uid::{IdMaps, Uid},
+};
+use common_ecs::{Job, Origin, Phase, System};
+use specs::{Entities, Join, Read, ReadStorage, WriteStorage};
+
+/// Why a duelist lost their duel
+#[derive(Copy, Clone, Debug)]
+enum Defeat {
+    LowHealth,
+    Forfeit,
+    LeftArena,
+}
+
+impl Defeat {
+    fn of(duel: &Duel, health: Option<&Health>, pos: Option<&Pos>) -> Option<Self> {
+        if duel.forfeited {
+            Some(Self::Forfeit)
+        } else if health.is_none_or(|health| health.is_dead || Duel::is_defeated(health)) {
+            Some(Self::LowHealth)
+        } else if pos.is_none_or(|pos| !duel.in_arena(pos.0)) {
+            Some(Self::LeftArena)
+        } else {
+            None
+        }
+    }
+
+    fn key(self) -> &'static str {
+        match self {
+            Self::LowHealth => "hud-chat-duel-won",
+            Self::Forfeit => "hud-chat-duel-won-forfeit",
+            Self::LeftArena => "hud-chat-duel-won-left_arena",
+        }
+    }
+}
+
+/// Ends duels once one of the duelists lost, or the time ran out
+#[derive(Default)]
+pub struct Sys;
+
+impl<'a> System<'a> for Sys {
+    type SystemData = (
+        Entities<'a>,
+        Read<'a, IdMaps>,
+        Read<'a, Time>,
+        Read<'a, EventBus<ChatEvent>>,
+        ReadStorage<'a, Uid>,
+        ReadStorage<'a, Health>,
+        ReadStorage<'a, Pos>,
+        ReadStorage<'a, Stats>,
+        WriteStorage<'a, Duel>,
+    );
+
+    const NAME: &'static str = "duel";
+    const ORIGIN: Origin = Origin::Server;
+    const PHASE: Phase = Phase::Create;
+
+    fn run(
+        _job: &mut Job<Self>,
+        (
+            entities,
+            id_maps,
+            time,
+            chat_events,
+            uids,
+            healths,
+            positions,
+            stats,
+            mut duels,
+        ): Self::SystemData,
+    ) {
+        let mut chat_emitter = chat_events.emitter();
+        let mut ended = Vec::new();
+        let name = |entity| {
+            stats.get(entity).map_or_else(
+                || Content::Plain("<?>".to_string()),
+                |stats| stats.name.clone(),
+            )
+        };
+
+        for (entity, uid, duel) in (&entities, &uids, &duels).join() {
+            let Some((opponent, opponent_duel)) =
+                id_maps.uid_entity(duel.opponent).and_then(|opponent| {
+                    duels
+                        .get(opponent)
+                        .filter(|opponent_duel| opponent_duel.opponent == *uid)
+                        .map(|opponent_duel| (opponent, opponent_duel))
+                })
+            else {
+                // The opponent is gone, most likely because it left the game
+                ended.push(entity);
+                chat_emitter.emit(ChatEvent {
+                    msg: ChatType::Duel(*uid, duel.opponent).into_msg(
+                        Content::localized_with_args("hud-chat-duel-abandoned", [(
+                            "winner",
+                            name(entity),
+                        )]),
+                    ),
+                    from_client: false,
+                });
+                continue;
+            };
+            // Both duelists see the same duel, only handle it once
+            if *uid > duel.opponent {
+                continue;
+            }
+
+            let defeat =
+                |entity, duel| Defeat::of(duel, healths.get(entity), positions.get(entity));
+            let content = match (defeat(entity, duel), defeat(opponent, opponent_duel)) {
+                (None, None) if time.0 < duel.end_time.0 => continue,
+                (None, None) => Content::localized_with_args("hud-chat-duel-draw-timeout", [
+                    ("a", name(entity)),
+                    ("b", name(opponent)),
+                ]),
+                (Some(_), Some(_)) => Content::localized_with_args("hud-chat-duel-draw", [
+                    ("a", name(entity)),
+                    ("b", name(opponent)),
+                ]),
+                (Some(defeat), None) => Content::localized_with_args(defeat.key(), [
+                    ("winner", name(opponent)),
+                    ("loser", name(entity)),
+                ]),
+                (None, Some(defeat)) => Content::localized_with_args(defeat.key(), [
+                    ("winner", name(entity)),
+                    ("loser", name(opponent)),
+                ]),
+            };
+
+            ended.extend([entity, opponent]);
+            chat_emitter.emit(ChatEvent {
+                msg: ChatType::Duel(*uid, duel.opponent).into_msg(content),
+                from_client: false,
+            });
+        }
+
+        for entity in ended {
+            duels.remove(entity);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::comp::{Body, duel::DUEL_DURATION, humanoid};
+    use common_ecs::{SysMetrics, run_now};
+    use specs::{Builder, Entity as EcsEntity, World, WorldExt};
+    use vek::Vec3;
+
+    fn setup() -> (World, EcsEntity, EcsEntity) {
+        let mut world = World::new();
+        world.insert(IdMaps::new());
+        world.insert(Time(0.0));
+        world.insert(EventBus::<ChatEvent>::default());
+        world.insert(SysMetrics::default());
+        world.register::<Uid>();
+        world.register::<Health>();
+        world.register::<Pos>();
+        world.register::<Stats>();
+        world.register::<Duel>();
+
+        let [a, b] = [(); 2].map(|_| {
+            world
+                .create_entity()
+                .with(Health::new(Body::Humanoid(humanoid::Body::random())))
+                .with(Pos(Vec3::zero()))
+                .build()
+        });
+        let (uid_a, uid_b) = {
+            let mut id_maps = world.write_resource::<IdMaps>();
+            (id_maps.allocate(a), id_maps.allocate(b))
+        };
+        let mut uids = world.write_storage::<Uid>();
+        uids.insert(a, uid_a).unwrap();
+        uids.insert(b, uid_b).unwrap();
+        let mut duels = world.write_storage::<Duel>();
+        duels
+            .insert(a, Duel::new(uid_b, Vec3::zero(), Time(0.0)))
+            .unwrap();
+        duels
+            .insert(b, Duel::new(uid_a, Vec3::zero(), Time(0.0)))
+            .unwrap();
+        drop((uids, duels));
+        (world, a, b)
+    }
+
+    fn duelists(world: &World) -> usize { world.read_storage::<Duel>().join().count() }
+
+    /// Runs the system and returns the i18n key of the announcement, if the
+    /// duel ended
+    fn resolve(world: &World) -> Option<String> {
+        run_now::<Sys>(world);
+        let mut events = world
+            .read_resource::<EventBus<ChatEvent>>()
+            .recv_all()
+            .collect::<Vec<_>>();
+        assert!(events.len() <= 1);
+        let key = match events.pop()?.msg.into_content() {
+            Content::Localized { key, .. } => key,
+            content => panic!("unexpected announcement {content:?}"),
+        };
+        assert_eq!(duelists(world), 0);
+        Some(key)
+    }
+
+    #[test]
+    fn ongoing_duel_continues() {
+        let (world, _, _) = setup();
+        assert_eq!(resolve(&world), None);
+        assert_eq!(duelists(&world), 2);
+    }
+
+    #[test]
+    fn low_health_loses() {
+        let (world, _, b) = setup();
+        world
+            .write_storage::<Health>()
+            .get_mut(b)
+            .unwrap()
+            .set_fraction(0.05);
+        assert_eq!(resolve(&world).as_deref(), Some("hud-chat-duel-won"));
+    }
+
+    #[test]
+    fn forfeit_loses() {
+        let (world, a, _) = setup();
+        world.write_storage::<Duel>().get_mut(a).unwrap().forfeited = true;
+        assert_eq!(
+            resolve(&world).as_deref(),
+            Some("hud-chat-duel-won-forfeit")
+        );
+    }
+
+    #[test]
+    fn leaving_arena_loses() {
+        let (world, _, b) = setup();
+        world.write_storage::<Pos>().get_mut(b).unwrap().0 = Vec3::new(1000.0, 0.0, 0.0);
+        assert_eq!(
+            resolve(&world).as_deref(),
+            Some("hud-chat-duel-won-left_arena")
+        );
+    }
+
+    #[test]
+    fn timeout_is_a_draw() {
+        let (mut world, _, _) = setup();
+        world.insert(Time(DUEL_DURATION + 1.0));
+        assert_eq!(
+            resolve(&world).as_deref(),
+            Some("hud-chat-duel-draw-timeout")
+        );
+    }
+}
@@ -0,0 +1,36 @@
+use crate::{
+    client::Client, events::shared::notify_mail_recipient, mail::MailManager, sys::SysScheduler,
+};
+use common::uid::IdMaps;
+use common_ecs::{Job, Origin, Phase, System};
+use specs::{Read, ReadStorage, Write, WriteExpect};
+
+/// Returns or deletes mail that stayed in a mailbox for too long
+#[derive(Default)]
+pub struct Sys;
+
+impl<'a> System<'a> for Sys {
+    type SystemData = (
+        Read<'a, IdMaps>,
+        ReadStorage<'a, Client>,
+        WriteExpect<'a, MailManager>,
+        Write<'a, SysScheduler<Self>>,
+    );
+
+    const NAME: &'static str = "mail";
+    const ORIGIN: Origin = Origin::Server;
+    const PHASE: Phase = Phase::Create;
+
+    fn run(
+        _job: &mut Job<Self>,
+        (id_maps, clients, mut mail_manager, mut scheduler): Self::SystemData,
+    ) {
+        if !scheduler.should_run() {
+            return;
+        }
+
+        for character_id in mail_manager.expire(chrono::Utc::now().timestamp()) {
+            notify_mail_recipient(&mail_manager, &id_maps, &clients, character_id);
+        }
+    }
+}
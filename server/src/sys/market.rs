@@ -0,0 +1,56 @@
+use crate::{
+    client::Client,
+    events::shared::{notify_mail_recipient, notify_market_viewers},
+    mail::MailManager,
+    market::MarketManager,
+    sys::SysScheduler,
+};
+use common::{comp::Content, uid::IdMaps};
+use common_ecs::{Job, Origin, Phase, System};
+use specs::{Read, ReadStorage, Write, WriteExpect};
+
+/// Sends the items of expired market listings back to their sellers by mail
+#[derive(Default)]
+pub struct Sys;
+
+impl<'a> System<'a> for Sys {
+    type SystemData = (
+        Read<'a, IdMaps>,
+        ReadStorage<'a, Client>,
+        WriteExpect<'a, MarketManager>,
+        WriteExpect<'a, MailManager>,
+        Write<'a, SysScheduler<Self>>,
+    );
+
+    const NAME: &'static str = "market";
+    const ORIGIN: Origin = Origin::Server;
+    const PHASE: Phase = Phase::Create;
+
+    fn run(
+        _job: &mut Job<Self>,
+        (id_maps, clients, mut market_manager, mut mail_manager, mut scheduler): Self::SystemData,
+    ) {
+        if !scheduler.should_run() {
+            return;
+        }
+
+        let now = chrono::Utc::now().timestamp();
+        let expired = market_manager.expire(now);
+        if expired.is_empty() {
+            return;
+        }
+        for listing in expired {
+            mail_manager.deliver(
+                "Market",
+                listing.seller,
+                Content::localized("hud-market-expired_subject"),
+                Content::localized("hud-market-expired_body"),
+                0,
+                vec![listing.item],
+                now,
+            );
+            notify_mail_recipient(&mail_manager, &id_maps, &clients, listing.seller);
+        }
+        notify_market_viewers(&market_manager, &id_maps, &clients);
+    }
+}
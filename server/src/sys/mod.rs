@@ -1,15 +1,19 @@
 pub mod agent;
 pub mod chunk_send;
 pub mod chunk_serialize;
+pub mod duel;
 pub mod entity_sync;
 pub mod invite_timeout;
 pub mod item;
 pub mod loot;
+pub mod mail;
+pub mod market;
 pub mod metrics;
 pub mod msg;
 pub mod object;
 pub mod persistence;
 pub mod pets;
+#[cfg(feature = "plugins")] pub mod plugin;
 pub mod sentinel;
 pub mod server_info;
 pub mod subscription;
@@ -37,6 +41,9 @@ pub fn add_server_systems(dispatch_builder: &mut DispatcherBuilder) {
     dispatch::<waypoint::Sys>(dispatch_builder, &[]);
     dispatch::<teleporter::Sys>(dispatch_builder, &[]);
     dispatch::<invite_timeout::Sys>(dispatch_builder, &[]);
+    dispatch::<duel::Sys>(dispatch_builder, &[]);
+    dispatch::<mail::Sys>(dispatch_builder, &[]);
+    dispatch::<market::Sys>(dispatch_builder, &[]);
     dispatch::<persistence::Sys>(dispatch_builder, &[]);
     dispatch::<object::Sys>(dispatch_builder, &[]);
     dispatch::<wiring::Sys>(dispatch_builder, &[]);
@@ -46,6 +53,8 @@ pub fn add_server_systems(dispatch_builder: &mut DispatcherBuilder) {
     dispatch::<chunk_send::Sys>(dispatch_builder, &[]);
     dispatch::<item::Sys>(dispatch_builder, &[]);
     dispatch::<server_info::Sys>(dispatch_builder, &[]);
+    #[cfg(feature = "plugins")]
+    dispatch::<plugin::Sys>(dispatch_builder, &[]);
 }
 
 pub fn run_sync_systems(ecs: &mut specs::World) {
@@ -63,6 +72,7 @@ pub fn run_sync_systems(ecs: &mut specs::World) {
 pub struct SysScheduler<S> {
     interval: Duration,
     last_run: Instant,
+    run_next: bool,
     _phantom: PhantomData<S>,
 }
 
@@ -71,13 +81,19 @@ impl<S> SysScheduler<S> {
         Self {
             interval,
             last_run: Instant::now(),
+            run_next: false,
             _phantom: PhantomData,
         }
     }
 
+    /// Makes the system run the next time it is dispatched, regardless of the
+    /// interval
+    pub fn run_next(&mut self) { self.run_next = true; }
+
     pub fn should_run(&mut self) -> bool {
-        if self.last_run.elapsed() > self.interval {
+        if self.run_next || self.last_run.elapsed() > self.interval {
             self.last_run = Instant::now();
+            self.run_next = false;
 
             true
         } else {
@@ -91,6 +107,7 @@ impl<S> Default for SysScheduler<S> {
         Self {
             interval: Duration::from_secs(30),
             last_run: Instant::now(),
+            run_next: false,
             _phantom: PhantomData,
         }
     }
@@ -12,7 +12,7 @@ use common::{
     mounting::{Rider, VolumeRider},
     resources::{DeltaTime, PlayerPhysicsSetting, PlayerPhysicsSettings},
     slowjob::SlowJobPool,
-    terrain::TerrainGrid,
+    terrain::{Block, TerrainGrid},
     uid::IdMaps,
     vol::ReadVol,
 };
@@ -77,6 +77,7 @@ impl Sys {
         player_physics_setting: Option<&mut PlayerPhysicsSetting>,
         server_physics_forced: bool,
         maybe_admin: &Option<&Admin>,
+        _maybe_player: Option<&Player>,
         time_for_vd_changes: Instant,
         msg: ClientGeneral,
         player_physics: &mut Option<(Pos, Vel, Ori)>,
@@ -180,7 +181,12 @@ impl Sys {
                                 && let Some(terrain_persistence) =
                                     guard._terrain_persistence.as_mut()
                             {
-                                terrain_persistence.set_block(pos, new_block);
+                                terrain_persistence.set_block(
+                                    pos,
+                                    old_block,
+                                    new_block,
+                                    _maybe_player.map(Player::uuid),
+                                );
                             }
                         }
                     }
@@ -199,6 +205,8 @@ impl Sys {
                                 .filter(|aabb| aabb.contains_point(pos))
                                 .is_some()
                         {
+                            let _old_block =
+                                terrain.get(pos).copied().unwrap_or_else(|_| Block::empty());
                             // Take the rare writes lock as briefly as possible.
                             let mut guard = rare_writes.lock();
                             let _was_set = guard.block_changes.try_set(pos, new_block).is_some();
@@ -207,7 +215,12 @@ impl Sys {
                                 && let Some(terrain_persistence) =
                                     guard._terrain_persistence.as_mut()
                             {
-                                terrain_persistence.set_block(pos, new_block);
+                                terrain_persistence.set_block(
+                                    pos,
+                                    _old_block,
+                                    new_block,
+                                    _maybe_player.map(Player::uuid),
+                                );
                             }
                         }
                     }
@@ -436,6 +449,7 @@ impl<'a> System<'a> for Sys {
                             new_player_physics_setting.as_mut(),
                             is_server_physics_forced,
                             &maybe_admin,
+                            maybe_player,
                             time_for_vd_changes,
                             msg,
                             &mut player_physics,
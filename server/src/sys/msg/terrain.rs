@@ -80,7 +80,7 @@ impl<'a> System<'a> for Sys {
                                 },
                             };
                             match msg {
-                                ClientGeneral::TerrainChunkRequest { key } => {
+                                ClientGeneral::TerrainChunkRequest { key, known_version } => {
                                     let in_vd = if let Some(pos) = positions.get(entity) {
                                         pos.0.xy().map(|e| e as f64).distance_squared(
                                             key.map(|e| e as f64 + 0.5)
@@ -98,6 +98,7 @@ impl<'a> System<'a> for Sys {
                                             chunk_send_emitter.emit(ChunkSendEntry {
                                                 chunk_key: key,
                                                 entity,
+                                                known_version,
                                             });
                                         } else {
                                             network_metrics.chunks_generation_triggered.inc();
@@ -1,10 +1,14 @@
-use crate::{persistence::character_updater, sys::SysScheduler};
+use crate::{
+    guild::GuildManager, mail::MailManager, market::MarketManager, persistence::character_updater,
+    sys::SysScheduler,
+};
 use common::{
     comp::{
-        ActiveAbilities, Alignment, Body, Inventory, MapMarker, Presence, PresenceKind, SkillSet,
-        Stats, Waypoint,
+        ActiveAbilities, Alignment, Body, Inventory, MapMarker, PersonalStorage, Presence,
+        PresenceKind, SkillSet, Stats, Waypoint,
         pet::{Pet, is_tameable},
     },
+    rtsim::PersistedReputation,
     uid::Uid,
 };
 use common_ecs::{Job, Origin, Phase, System};
@@ -21,13 +25,18 @@ impl<'a> System<'a> for Sys {
         ReadStorage<'a, Presence>,
         ReadStorage<'a, SkillSet>,
         ReadStorage<'a, Inventory>,
+        ReadStorage<'a, PersonalStorage>,
         ReadStorage<'a, Uid>,
         ReadStorage<'a, Waypoint>,
         ReadStorage<'a, MapMarker>,
         ReadStorage<'a, Pet>,
         ReadStorage<'a, Stats>,
         ReadStorage<'a, ActiveAbilities>,
+        ReadStorage<'a, PersistedReputation>,
         WriteExpect<'a, character_updater::CharacterUpdater>,
+        WriteExpect<'a, GuildManager>,
+        WriteExpect<'a, MailManager>,
+        WriteExpect<'a, MarketManager>,
         Write<'a, SysScheduler<Self>>,
     );
 
@@ -43,26 +52,36 @@ impl<'a> System<'a> for Sys {
             presences,
             player_skill_set,
             player_inventories,
+            storages,
             uids,
             player_waypoints,
             map_markers,
             pets,
             stats,
             active_abilities,
+            reputations,
             mut updater,
+            mut guild_manager,
+            mut mail_manager,
+            mut market_manager,
             mut scheduler,
         ): Self::SystemData,
     ) {
+        // Guilds change rarely, but right away, so don't wait for the next batch
+        updater.update_guilds(guild_manager.take_changes());
+
         if scheduler.should_run() {
             updater.batch_update(
                 (
                     &presences,
                     &player_skill_set,
                     &player_inventories,
+                    &storages,
                     &uids,
                     player_waypoints.maybe(),
                     &active_abilities,
                     map_markers.maybe(),
+                    reputations.maybe(),
                 )
                     .join()
                     .filter_map(
@@ -70,10 +89,12 @@ impl<'a> System<'a> for Sys {
                             presence,
                             skill_set,
                             inventory,
+                            storage,
                             player_uid,
                             waypoint,
                             active_abilities,
                             map_marker,
+                            reputation,
                         )| match presence.kind {
                             PresenceKind::LoadingCharacter(_char_id) => {
                                 error!(
@@ -103,15 +124,19 @@ impl<'a> System<'a> for Sys {
                                     id,
                                     skill_set.clone(),
                                     inventory.clone(),
+                                    storage.inventory.clone(),
                                     pets,
                                     waypoint.cloned(),
                                     active_abilities.clone(),
                                     map_marker.cloned(),
+                                    reputation.cloned().unwrap_or_default(),
                                 ))
                             },
                             PresenceKind::Spectator | PresenceKind::Possessor => None,
                         },
                     ),
+                mail_manager.take_changes(),
+                market_manager.take_changes(),
             );
         }
     }
@@ -0,0 +1,82 @@
+use crate::{persistence::character_updater::CharacterUpdater, sys::SysScheduler};
+use common::{
+    comp::{CharacterState, Health, Player},
+    event::{EventBus, PluginGameEvent},
+    resources::DeltaTime,
+    uid::{IdMaps, Uid},
+};
+use common_ecs::{Job, Origin, Phase, System};
+use common_state::plugin::{PluginMgr, memory_manager::EcsWorld};
+use hashbrown::HashMap;
+use specs::{Entities, Join, Read, ReadStorage, SystemData, Write, WriteExpect, shred};
+use std::mem::Discriminant;
+
+#[derive(SystemData)]
+pub struct Data<'a> {
+    entities: Entities<'a>,
+    dt: Read<'a, DeltaTime>,
+    id_maps: Read<'a, IdMaps>,
+    plugin_mgr: WriteExpect<'a, PluginMgr>,
+    plugin_events: Read<'a, EventBus<PluginGameEvent>>,
+    uids: ReadStorage<'a, Uid>,
+    healths: ReadStorage<'a, Health>,
+    players: ReadStorage<'a, Player>,
+    character_states: ReadStorage<'a, CharacterState>,
+    character_updater: WriteExpect<'a, CharacterUpdater>,
+    storage_scheduler: Write<'a, SysScheduler<Sys>>,
+}
+
+/// This system drives server plugins:
+///     1. Reports character state changes of players
+///     2. Calls the `tick` export and services expired plugin timers
+///     3. Regularly persists changes to the plugin storage
+///
+/// Character states of NPCs are left out, they change far too often to call
+/// into every plugin for each of them.
+#[derive(Default)]
+pub struct Sys {
+    last_states: HashMap<Uid, Discriminant<CharacterState>>,
+}
+
+impl<'a> System<'a> for Sys {
+    type SystemData = Data<'a>;
+
+    const NAME: &'static str = "plugin";
+    const ORIGIN: Origin = Origin::Server;
+    const PHASE: Phase = Phase::Create;
+
+    fn run(job: &mut Job<Self>, mut data: Self::SystemData) {
+        let mut emitter = data.plugin_events.emitter();
+        let last_states = core::mem::take(&mut job.own.last_states);
+
+        job.own.last_states = (&data.uids, &data.players, &data.character_states)
+            .join()
+            .map(|(uid, _, character_state)| {
+                let state = core::mem::discriminant(character_state);
+                if last_states.get(uid).is_some_and(|last| *last != state) {
+                    emitter.emit(PluginGameEvent::CharacterStateChange {
+                        entity: *uid,
+                        state: character_state.to_string(),
+                    });
+                }
+                (*uid, state)
+            })
+            .collect();
+
+        // Actions queued by the plugins are applied together with the ones from
+        // game events, see `handle_plugin_game_events`
+        let ecs_world = EcsWorld {
+            entities: &data.entities,
+            health: (&data.healths).into(),
+            uid: (&data.uids).into(),
+            id_maps: &data.id_maps,
+            player: (&data.players).into(),
+        };
+        data.plugin_mgr.tick(&ecs_world, data.dt.0);
+
+        if data.storage_scheduler.should_run() {
+            let changes = data.plugin_mgr.storage().take_changes();
+            data.character_updater.update_plugin_storage(changes);
+        }
+    }
+}
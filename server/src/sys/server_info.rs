@@ -1,56 +1,136 @@
 use common::{
     comp::Player,
+    resources::{Time, TimeOfDay},
+    terrain::TerrainGrid,
     util::{GIT_HASH, GIT_TIMESTAMP},
+    weather::WeatherGrid,
 };
 use common_ecs::{Origin, Phase, System};
-use specs::{Join, Read, ReadStorage};
+#[cfg(feature = "plugins")]
+use common_state::plugin::PluginMgr;
+use specs::{Join, Read, ReadExpect, ReadStorage, SystemData, shred};
 use tracing::warn;
-use veloren_query_server::proto::ServerInfo;
+use veloren_query_server::{
+    proto::{ServerInfo, WorldInfo, WorldWeather},
+    server::ServerDetails,
+};
 
 use crate::{Settings, Tick, client::Client};
 
 // Update the server stats every 60 ticks
 const INFO_SEND_INTERVAL: u64 = 60;
 
+#[derive(SystemData)]
+pub struct ReadData<'a> {
+    tick: Read<'a, Tick>,
+    settings: Read<'a, Settings>,
+    info_sender: Option<Read<'a, tokio::sync::watch::Sender<ServerInfo>>>,
+    details_sender: Option<Read<'a, tokio::sync::watch::Sender<ServerDetails>>>,
+    time: Read<'a, Time>,
+    time_of_day: Read<'a, TimeOfDay>,
+    terrain: ReadExpect<'a, TerrainGrid>,
+    weather_grid: ReadExpect<'a, WeatherGrid>,
+    #[cfg(feature = "plugins")]
+    plugin_mgr: Read<'a, PluginMgr>,
+    players: ReadStorage<'a, Player>,
+    clients: ReadStorage<'a, Client>,
+}
+
 #[derive(Default)]
 pub struct Sys;
 
 impl<'a> System<'a> for Sys {
-    type SystemData = (
-        Read<'a, Tick>,
-        Read<'a, Settings>,
-        Option<Read<'a, tokio::sync::watch::Sender<ServerInfo>>>,
-        ReadStorage<'a, Player>,
-        ReadStorage<'a, Client>,
-    );
+    type SystemData = ReadData<'a>;
 
     const NAME: &'static str = "server_info";
     const ORIGIN: Origin = Origin::Server;
     const PHASE: Phase = Phase::Create;
 
-    fn run(
-        _job: &mut common_ecs::Job<Self>,
-        (tick, settings, sender, players, clients): Self::SystemData,
-    ) {
-        if let Some(sender) = sender.as_ref()
-            && tick.0 % INFO_SEND_INTERVAL == 0
-        {
-            let count = (&players, &clients)
+    fn run(_job: &mut common_ecs::Job<Self>, data: Self::SystemData) {
+        if data.tick.0 % INFO_SEND_INTERVAL != 0 {
+            return;
+        }
+
+        // Hide silent spectators from the player count and list
+        let players = || {
+            (&data.players, &data.clients)
                 .join()
-                // Hide silent spectators from the player count
                 .filter(|(_, client)| client.client_type.emit_login_events())
-                .count()
-                .try_into()
-                .unwrap_or(u16::MAX);
+                .map(|(player, _)| player)
+        };
+
+        if let Some(sender) = data.info_sender.as_ref() {
+            let count = players().count().try_into().unwrap_or(u16::MAX);
             if let Err(e) = sender.send(ServerInfo {
                 git_hash: *GIT_HASH,
                 git_timestamp: *GIT_TIMESTAMP,
                 players_count: count,
-                player_cap: settings.max_players,
-                battlemode: settings.gameplay.battle_mode.into(),
+                player_cap: data.settings.max_players,
+                battlemode: data.settings.gameplay.battle_mode.into(),
             }) {
                 warn!(?e, "Failed to send server info to the query server");
             }
         }
+
+        if let Some(sender) = data.details_sender.as_ref() {
+            let world_size = data.terrain.map_size_lg().chunks();
+            #[cfg(feature = "plugins")]
+            let plugins = data
+                .plugin_mgr
+                .plugin_list()
+                .into_iter()
+                .filter_map(|hash| {
+                    Some(veloren_query_server::proto::PluginInfo {
+                        name: data.plugin_mgr.find(&hash)?.name().to_owned(),
+                        hash,
+                    })
+                })
+                .collect();
+            #[cfg(not(feature = "plugins"))]
+            let plugins = Vec::new();
+
+            if let Err(e) = sender.send(ServerDetails {
+                world: WorldInfo {
+                    seed: data.settings.world_seed,
+                    size_x: world_size.x.into(),
+                    size_y: world_size.y.into(),
+                    uptime: data.time.0 as u64,
+                    time_of_day: data.time_of_day.0,
+                    weather: average_weather(&data.weather_grid),
+                    battlemode: data.settings.gameplay.battle_mode.into(),
+                },
+                players: data
+                    .settings
+                    .query_player_list
+                    .then(|| players().map(|player| player.alias.clone()).collect()),
+                plugins,
+            }) {
+                warn!(?e, "Failed to send server details to the query server");
+            }
+        }
+    }
+}
+
+fn average_weather(weather_grid: &WeatherGrid) -> WorldWeather {
+    let (cells, total) = weather_grid.iter().fold(
+        (0, WorldWeather {
+            cloud: 0.0,
+            rain: 0.0,
+            wind: 0.0,
+        }),
+        |(cells, total), (_, weather)| {
+            (cells + 1, WorldWeather {
+                cloud: total.cloud + weather.cloud,
+                rain: total.rain + weather.rain,
+                wind: total.wind + weather.wind.magnitude(),
+            })
+        },
+    );
+    let cells = cells.max(1) as f32;
+
+    WorldWeather {
+        cloud: total.cloud / cells,
+        rain: total.rain / cells,
+        wind: total.wind / cells,
     }
 }
@@ -8,8 +8,12 @@ use world::{IndexOwned, World};
 
 #[cfg(feature = "worldgen")] use crate::rtsim;
 use crate::{
-    ChunkRequest, Tick, chunk_generator::ChunkGenerator, chunk_serialize::ChunkSendEntry,
-    client::Client, presence::RepositionToFreeSpace, settings::Settings,
+    ChunkRequest, Tick,
+    chunk_generator::ChunkGenerator,
+    chunk_serialize::{ChunkSendEntry, ChunkVersions},
+    client::Client,
+    presence::RepositionToFreeSpace,
+    settings::Settings,
 };
 use common::{
     SkillSetBuilder,
@@ -63,6 +67,8 @@ event_emitters! {
         create_npc: CreateNpcEvent,
         create_npc_group: CreateNpcGroupEvent,
         create_waypoint: CreateSpecialEntityEvent,
+        #[cfg(feature = "plugins")]
+        plugin: common::event::PluginGameEvent,
     }
 }
 
@@ -77,6 +83,7 @@ pub struct Data<'a> {
     index: ReadExpect<'a, IndexOwned>,
     world: ReadExpect<'a, Arc<World>>,
     chunk_send_bus: ReadExpect<'a, EventBus<ChunkSendEntry>>,
+    chunk_versions: Write<'a, ChunkVersions>,
     chunk_generator: WriteExpect<'a, ChunkGenerator>,
     terrain: WriteExpect<'a, TerrainGrid>,
     terrain_changes: Write<'a, TerrainChanges>,
@@ -142,6 +149,7 @@ impl<'a> System<'a> for Sys {
                         client.send_fallible(ServerGeneral::TerrainChunkUpdate {
                             key,
                             chunk: Err(()),
+                            version: 0,
                         });
                     }
                     continue 'insert_terrain_chunks;
@@ -160,18 +168,21 @@ impl<'a> System<'a> for Sys {
             // Arcify the chunk
             let chunk = Arc::new(chunk);
 
-            // Add to list of chunks to send to nearby players.
-            new_chunks.push(key);
-
             // TODO: code duplication for chunk insertion between here and state.rs
             // Insert the chunk into terrain changes
-            if data.terrain.insert(key, chunk).is_some() {
+            if let Some(old_chunk) = data.terrain.insert(key, Arc::clone(&chunk)) {
+                // Sent by `terrain_sync`, as a delta where possible
+                data.chunk_versions.replace_with(key, &old_chunk, &chunk);
                 data.terrain_changes.modified_chunks.insert(key);
             } else {
+                // Add to list of chunks to send to nearby players.
+                new_chunks.push(key);
                 data.terrain_changes.new_chunks.insert(key);
                 #[cfg(feature = "worldgen")]
                 data.rtsim
                     .hook_load_chunk(key, supplement.rtsim_max_resources, &data.world);
+                #[cfg(feature = "plugins")]
+                emitters.emit(common::event::PluginGameEvent::ChunkLoad { key });
             }
 
             // Handle chunk supplement
@@ -321,6 +332,7 @@ impl<'a> System<'a> for Sys {
                         chunk_send_emitter.emit(ChunkSendEntry {
                             entity: *entity,
                             chunk_key: *chunk_key,
+                            known_version: None,
                         });
                     });
             },
@@ -1,6 +1,10 @@
 #[cfg(not(feature = "worldgen"))]
 use crate::test_world::World;
-use crate::{Settings, chunk_serialize::ChunkSendEntry, client::Client};
+use crate::{
+    Settings,
+    chunk_serialize::{ChunkSendEntry, ChunkVersions},
+    client::Client,
+};
 use common::{
     comp::{Pos, Presence},
     event::EventBus,
@@ -9,12 +13,13 @@ use common_ecs::{Job, Origin, Phase, System};
 use common_net::msg::{CompressedData, ServerGeneral};
 use common_state::TerrainChanges;
 use rayon::prelude::*;
-use specs::{Entities, Join, Read, ReadExpect, ReadStorage};
+use specs::{Entities, Join, Read, ReadExpect, ReadStorage, Write};
 use std::sync::Arc;
 #[cfg(feature = "worldgen")] use world::World;
 
 /// This systems sends modified chunks (existing chunks that had a new chunk
 /// generated) to clients as well as block modifications in existing chunks.
+/// Modified chunks are sent as a delta where possible.
 #[derive(Default)]
 pub struct Sys;
 impl<'a> System<'a> for Sys {
@@ -27,6 +32,7 @@ impl<'a> System<'a> for Sys {
         ReadStorage<'a, Pos>,
         ReadStorage<'a, Presence>,
         ReadStorage<'a, Client>,
+        Write<'a, ChunkVersions>,
     );
 
     const NAME: &'static str = "terrain_sync";
@@ -44,6 +50,7 @@ impl<'a> System<'a> for Sys {
             positions,
             presences,
             clients,
+            mut chunk_versions,
         ): Self::SystemData,
     ) {
         let max_view_distance = server_settings.max_view_distance.unwrap_or(u32::MAX);
@@ -62,6 +69,10 @@ impl<'a> System<'a> for Sys {
         let real_max_view_distance =
             super::terrain::convert_to_loaded_vd(u32::MAX, max_view_distance);
 
+        // Chunks replaced by `terrain` are sent as a delta against the version clients
+        // had before, clients without it request the full chunk again
+        let replaced = chunk_versions.take_replaced();
+
         // Sync changed chunks
         terrain_changes.modified_chunks.par_iter().for_each_init(
             || chunk_send_bus.emitter(),
@@ -100,20 +111,36 @@ impl<'a> System<'a> for Sys {
                         chunk_send_emitter.emit(ChunkSendEntry {
                             entity: *entity,
                             chunk_key,
+                            known_version: replaced.get(&chunk_key).copied(),
                         });
                     });
             },
         );
 
+        // Keep track of chunk versions
+        for &chunk_key in &terrain_changes.removed_chunks {
+            chunk_versions.remove(chunk_key);
+        }
+        for &chunk_key in &terrain_changes.modified_chunks {
+            if !replaced.contains_key(&chunk_key) {
+                chunk_versions.replace(chunk_key);
+            }
+        }
+        for &chunk_key in &terrain_changes.new_chunks {
+            chunk_versions.version(chunk_key);
+        }
+        let versions = chunk_versions.record_blocks(&terrain_changes.modified_blocks);
+
         // TODO: Don't send all changed blocks to all clients
         // Sync changed blocks
         if !terrain_changes.modified_blocks.is_empty() {
             let mut lazy_msg = None;
             for (_, client) in (&presences, &clients).join() {
                 if lazy_msg.is_none() {
-                    lazy_msg = Some(client.prepare(ServerGeneral::TerrainBlockUpdates(
-                        CompressedData::compress(&terrain_changes.modified_blocks, 1),
-                    )));
+                    lazy_msg = Some(client.prepare(ServerGeneral::TerrainBlockUpdates {
+                        blocks: CompressedData::compress(&terrain_changes.modified_blocks, 1),
+                        versions: versions.clone(),
+                    }));
                 }
                 lazy_msg.as_ref().map(|msg| client.send_prepared(msg));
             }
@@ -0,0 +1,488 @@
+//! A journal of every persisted block edit, used to inspect the history of a
+//! block and to roll back edits, e.g. after griefing.
+//!
+//! The journal is split into one file of fixed size records per day, records
+//! are only ever appended. Queries are run on a [`Snapshot`] of the journal off
+//! the main thread and skip the days they don't need. Days older than
+//! [`RETENTION`] are deleted.
+
+use authc::Uuid;
+use common::terrain::Block;
+use hashbrown::HashMap;
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+use tracing::{error, warn};
+use vek::*;
+
+/// time, position, old block, new block, author
+const RECORD_LEN: usize = 8 + 3 * 4 + 4 + 4 + 16;
+
+/// A single block edit
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BlockEdit {
+    /// Seconds since the unix epoch
+    pub time: u64,
+    pub pos: Vec3<i32>,
+    pub old: Block,
+    pub new: Block,
+    /// The player who made the edit, if any
+    pub author: Option<Uuid>,
+}
+
+impl BlockEdit {
+    pub fn now(pos: Vec3<i32>, old: Block, new: Block, author: Option<Uuid>) -> Self {
+        Self {
+            time: unix_time(),
+            pos,
+            old,
+            new,
+            author,
+        }
+    }
+
+    fn to_bytes(self) -> [u8; RECORD_LEN] {
+        let mut bytes = [0; RECORD_LEN];
+        bytes[0..8].copy_from_slice(&self.time.to_le_bytes());
+        bytes[8..12].copy_from_slice(&self.pos.x.to_le_bytes());
+        bytes[12..16].copy_from_slice(&self.pos.y.to_le_bytes());
+        bytes[16..20].copy_from_slice(&self.pos.z.to_le_bytes());
+        bytes[20..24].copy_from_slice(&self.old.to_u32().to_le_bytes());
+        bytes[24..28].copy_from_slice(&self.new.to_u32().to_le_bytes());
+        bytes[28..44].copy_from_slice(self.author.unwrap_or_else(Uuid::nil).as_bytes());
+        bytes
+    }
+
+    fn from_bytes(bytes: &[u8; RECORD_LEN]) -> Self {
+        let u32_at = |i: usize| u32::from_le_bytes(bytes[i..i + 4].try_into().unwrap());
+        let block_at = |i: usize| Block::from_u32(u32_at(i)).unwrap_or_else(Block::empty);
+        let author = Uuid::from_bytes(bytes[28..44].try_into().unwrap());
+        Self {
+            time: u64::from_le_bytes(bytes[0..8].try_into().unwrap()),
+            pos: Vec3::new(u32_at(8) as i32, u32_at(12) as i32, u32_at(16) as i32),
+            old: block_at(20),
+            new: block_at(24),
+            author: (!author.is_nil()).then_some(author),
+        }
+    }
+}
+
+pub fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_secs())
+}
+
+/// Selects the edits to roll back. `None` matches everything.
+#[derive(Clone, Debug, Default)]
+pub struct EditFilter {
+    pub author: Option<Uuid>,
+    pub area: Option<Aabb<i32>>,
+    /// Seconds since the unix epoch
+    pub since: Option<u64>,
+}
+
+impl EditFilter {
+    fn matches(&self, edit: &BlockEdit) -> bool {
+        self.author.is_none_or(|author| edit.author == Some(author))
+            && self.area.is_none_or(|area| area.contains_point(edit.pos))
+            && self.since.is_none_or(|since| edit.time >= since)
+    }
+}
+
+/// A block that should be reverted by a rollback
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Revert {
+    pub pos: Vec3<i32>,
+    /// The block as left behind by the rolled back edits
+    pub current: Block,
+    /// The block before the first rolled back edit
+    pub restore: Block,
+}
+
+/// How long edits are kept in the journal
+const RETENTION: u64 = 30 * DAY;
+const DAY: u64 = 24 * 60 * 60;
+
+/// The edits of one day, or of several days for a journal written by an older
+/// version.
+struct Segment {
+    /// Day of the newest edit, all edits are older than the end of this day
+    day: u64,
+    path: PathBuf,
+}
+
+impl Segment {
+    fn new(dir: &Path, day: u64) -> Self {
+        Self {
+            day,
+            path: dir.join(format!("{day}.journal")),
+        }
+    }
+}
+
+pub struct Journal {
+    dir: PathBuf,
+    /// Oldest first, the newest segment is the one written to
+    segments: Vec<Segment>,
+    writer: Option<BufWriter<File>>,
+}
+
+impl Journal {
+    /// Opens the journal in `dir`, creating it if needed. The single journal
+    /// file of older versions at `legacy_path` is moved into it.
+    pub fn open(dir: PathBuf, legacy_path: &Path) -> io::Result<Self> {
+        std::fs::create_dir_all(&dir)?;
+        if legacy_path.exists() {
+            migrate_legacy(&dir, legacy_path)?;
+        }
+
+        let mut segments = std::fs::read_dir(&dir)?
+            .filter_map(|entry| {
+                let path = entry.ok()?.path();
+                let day = path.file_name()?.to_str()?.strip_suffix(".journal")?;
+                Some(Segment {
+                    day: day.parse().ok()?,
+                    path,
+                })
+            })
+            .collect::<Vec<_>>();
+        segments.sort_by_key(|segment| segment.day);
+        // Only the segment written to last can end with an incomplete record
+        if let Some(segment) = segments.last() {
+            cut_torn_record(&segment.path)?;
+        }
+
+        Ok(Self {
+            dir,
+            segments,
+            writer: None,
+        })
+    }
+
+    /// Appends an edit. Edits are buffered until the next [`Journal::flush`].
+    pub fn append(&mut self, edit: BlockEdit) -> io::Result<()> {
+        let day = edit.time / DAY;
+        // If the clock went backwards the edit stays in the newest segment
+        if self.segments.last().is_none_or(|segment| segment.day < day) {
+            self.flush()?;
+            self.writer = None;
+            self.segments.push(Segment::new(&self.dir, day));
+        }
+        if self.writer.is_none() {
+            let path = &self.segments.last().expect("Segment was added above").path;
+            let file = OpenOptions::new().create(true).append(true).open(path)?;
+            self.writer = Some(BufWriter::new(file));
+        }
+        self.writer
+            .as_mut()
+            .expect("Writer was opened above")
+            .write_all(&edit.to_bytes())
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.as_mut().map_or(Ok(()), BufWriter::flush)
+    }
+
+    /// Deletes the segments that only contain edits older than [`RETENTION`].
+    pub fn prune(&mut self, now: u64) {
+        let cutoff = now.saturating_sub(RETENTION);
+        // The newest segment is kept, it is being written to
+        while self.segments.len() > 1 && (self.segments[0].day + 1) * DAY <= cutoff {
+            let segment = self.segments.remove(0);
+            match std::fs::remove_file(&segment.path) {
+                Ok(()) => {},
+                Err(err) if err.kind() == io::ErrorKind::NotFound => {},
+                Err(err) => {
+                    error!(?err, path = ?segment.path, "Failed to delete old terrain edits");
+                    self.segments.insert(0, segment);
+                    return;
+                },
+            }
+        }
+    }
+
+    /// Everything appended so far, to be queried without blocking further
+    /// appends.
+    pub fn snapshot(&mut self) -> io::Result<Snapshot> {
+        self.flush()?;
+        let segments = self
+            .segments
+            .iter()
+            .map(|segment| {
+                let len = match std::fs::metadata(&segment.path) {
+                    Ok(metadata) => metadata.len(),
+                    Err(err) if err.kind() == io::ErrorKind::NotFound => 0,
+                    Err(err) => return Err(err),
+                };
+                Ok(SegmentSnapshot {
+                    day: segment.day,
+                    path: segment.path.clone(),
+                    len: len - len % RECORD_LEN as u64,
+                })
+            })
+            .collect::<io::Result<_>>()?;
+        Ok(Snapshot { segments })
+    }
+}
+
+fn cut_torn_record(path: &Path) -> io::Result<()> {
+    let file = OpenOptions::new().write(true).open(path)?;
+    let len = file.metadata()?.len();
+    let torn = len % RECORD_LEN as u64;
+    if torn != 0 {
+        warn!(
+            ?path,
+            "Terrain edit journal ends with an incomplete record, cutting it off."
+        );
+        file.set_len(len - torn)?;
+    }
+    Ok(())
+}
+
+/// Moves the journal file of older versions into a single segment, named after
+/// its newest edit.
+fn migrate_legacy(dir: &Path, legacy_path: &Path) -> io::Result<()> {
+    cut_torn_record(legacy_path)?;
+    let mut file = File::open(legacy_path)?;
+    let len = file.metadata()?.len();
+    if len == 0 {
+        return std::fs::remove_file(legacy_path);
+    }
+    file.seek(SeekFrom::Start(len - RECORD_LEN as u64))?;
+    let mut bytes = [0; RECORD_LEN];
+    file.read_exact(&mut bytes)?;
+    drop(file);
+    let segment = Segment::new(dir, BlockEdit::from_bytes(&bytes).time / DAY);
+    std::fs::rename(legacy_path, segment.path)
+}
+
+struct SegmentSnapshot {
+    day: u64,
+    path: PathBuf,
+    /// Records appended after the snapshot was taken are ignored
+    len: u64,
+}
+
+impl SegmentSnapshot {
+    /// Calls `f` for every edit, from oldest to newest.
+    fn for_each(&self, f: &mut impl FnMut(BlockEdit)) -> io::Result<()> {
+        let file = match File::open(&self.path) {
+            Ok(file) => file,
+            // Deleted because it got too old
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(()),
+            Err(err) => return Err(err),
+        };
+        let mut reader = BufReader::new(file.take(self.len));
+        let mut bytes = [0; RECORD_LEN];
+        loop {
+            match reader.read_exact(&mut bytes) {
+                Ok(()) => f(BlockEdit::from_bytes(&bytes)),
+                Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(()),
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+/// The journal at one point in time, see [`Journal::snapshot`].
+#[derive(Default)]
+pub struct Snapshot {
+    /// Oldest first
+    segments: Vec<SegmentSnapshot>,
+}
+
+impl Snapshot {
+    /// The newest `limit` edits of the block at `pos`, oldest first.
+    pub fn history(&self, pos: Vec3<i32>, limit: usize) -> io::Result<Vec<BlockEdit>> {
+        let mut edits = Vec::new();
+        for segment in self.segments.iter().rev() {
+            if edits.len() >= limit {
+                break;
+            }
+            let mut older = Vec::new();
+            segment.for_each(&mut |edit| {
+                if edit.pos == pos {
+                    older.push(edit);
+                }
+            })?;
+            older.append(&mut edits);
+            edits = older;
+        }
+        edits.drain(..edits.len().saturating_sub(limit));
+        Ok(edits)
+    }
+
+    /// Determines which blocks to revert to undo the edits matching `filter`.
+    ///
+    /// Blocks that were edited again afterwards by edits not matching the
+    /// filter are left alone, so rolling back a griefer does not undo the
+    /// repairs of other players.
+    pub fn rollback(&self, filter: &EditFilter) -> io::Result<Vec<Revert>> {
+        let mut reverts = HashMap::<Vec3<i32>, (Revert, bool)>::new();
+        let mut f = |edit: BlockEdit| {
+            if filter.matches(&edit) {
+                let (revert, _) = reverts.entry(edit.pos).or_insert((
+                    Revert {
+                        pos: edit.pos,
+                        current: edit.new,
+                        restore: edit.old,
+                    },
+                    false,
+                ));
+                revert.current = edit.new;
+            } else if let Some((_, clobbered)) = reverts.get_mut(&edit.pos) {
+                *clobbered = true;
+            }
+        };
+        for segment in &self.segments {
+            if filter
+                .since
+                .is_none_or(|since| (segment.day + 1) * DAY > since)
+            {
+                segment.for_each(&mut f)?;
+            }
+        }
+        Ok(reverts
+            .into_values()
+            .filter(|(revert, clobbered)| !clobbered && revert.current != revert.restore)
+            .map(|(revert, _)| revert)
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::terrain::{BlockKind, SpriteKind};
+
+    const GRIEFER: Uuid = Uuid::from_u128(1);
+    const BUILDER: Uuid = Uuid::from_u128(2);
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "veloren_journal_test_{}_{name}",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn stone() -> Block { Block::new(BlockKind::Rock, Rgb::zero()) }
+
+    fn air() -> Block { Block::air(SpriteKind::Empty) }
+
+    fn edit(time: u64, pos: Vec3<i32>, old: Block, new: Block, author: Uuid) -> BlockEdit {
+        BlockEdit {
+            time,
+            pos,
+            old,
+            new,
+            author: Some(author),
+        }
+    }
+
+    #[test]
+    fn rollback_keeps_later_edits() {
+        let dir = temp_dir("rollback");
+        let mut journal = Journal::open(dir.join("journal"), &dir.join("edits.journal")).unwrap();
+
+        let (stone, air) = (stone(), air());
+        let a = Vec3::new(0, 0, 0);
+        let b = Vec3::new(1, 0, 0);
+        journal.append(edit(10, a, stone, air, GRIEFER)).unwrap();
+        journal.append(edit(11, b, stone, air, GRIEFER)).unwrap();
+        journal.append(edit(12, b, air, stone, BUILDER)).unwrap();
+
+        let snapshot = journal.snapshot().unwrap();
+        let reverts = snapshot
+            .rollback(&EditFilter {
+                author: Some(GRIEFER),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(reverts, vec![Revert {
+            pos: a,
+            current: air,
+            restore: stone,
+        }]);
+        assert!(
+            snapshot
+                .rollback(&EditFilter {
+                    author: Some(GRIEFER),
+                    since: Some(11),
+                    ..Default::default()
+                })
+                .unwrap()
+                .is_empty()
+        );
+        assert_eq!(snapshot.history(b, 1).unwrap(), vec![edit(
+            12, b, air, stone, BUILDER
+        )]);
+
+        // Reopening cuts off a torn record
+        drop(journal);
+        let mut file = OpenOptions::new()
+            .append(true)
+            .open(dir.join("journal/0.journal"))
+            .unwrap();
+        file.write_all(&[1, 2, 3]).unwrap();
+        drop(file);
+        let mut journal = Journal::open(dir.join("journal"), &dir.join("edits.journal")).unwrap();
+        assert_eq!(journal.snapshot().unwrap().history(b, 10).unwrap().len(), 2);
+
+        drop(journal);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn segments_and_retention() {
+        let dir = temp_dir("segments");
+        let legacy_path = dir.join("edits.journal");
+        let (stone, air) = (stone(), air());
+        let pos = Vec3::new(3, 2, 1);
+
+        // Journals of older versions become a single segment
+        let mut legacy = File::create(&legacy_path).unwrap();
+        legacy
+            .write_all(&edit(5, pos, stone, air, GRIEFER).to_bytes())
+            .unwrap();
+        legacy
+            .write_all(&edit(DAY + 5, pos, air, stone, BUILDER).to_bytes())
+            .unwrap();
+        drop(legacy);
+        let mut journal = Journal::open(dir.join("journal"), &legacy_path).unwrap();
+        assert!(!legacy_path.exists());
+        assert!(dir.join("journal/1.journal").exists());
+
+        journal
+            .append(edit(2 * DAY, pos, stone, air, GRIEFER))
+            .unwrap();
+        journal
+            .append(edit(40 * DAY, pos, air, stone, BUILDER))
+            .unwrap();
+        assert_eq!(journal.segments.len(), 3);
+        assert_eq!(
+            journal.snapshot().unwrap().history(pos, 10).unwrap().len(),
+            4
+        );
+        assert_eq!(journal.snapshot().unwrap().history(pos, 2).unwrap(), vec![
+            edit(2 * DAY, pos, stone, air, GRIEFER),
+            edit(40 * DAY, pos, air, stone, BUILDER),
+        ]);
+
+        journal.prune(41 * DAY);
+        assert_eq!(journal.segments.len(), 1);
+        assert!(!dir.join("journal/2.journal").exists());
+        assert_eq!(journal.snapshot().unwrap().history(pos, 10).unwrap(), vec![
+            edit(40 * DAY, pos, air, stone, BUILDER)
+        ]);
+
+        drop(journal);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
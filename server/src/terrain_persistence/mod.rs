@@ -0,0 +1,858 @@
+mod journal;
+mod region;
+
+pub use journal::{BlockEdit, EditFilter, Revert, unix_time};
+
+use atomicwrites::{AtomicFile, OverwriteBehavior};
+use authc::Uuid;
+use bincode::{
+    config::legacy,
+    error::DecodeError,
+    serde::{decode_from_std_read, encode_to_vec},
+};
+use common::{
+    terrain::{Block, TerrainChunk},
+    vol::{RectRasterableVol, WriteVol},
+};
+use hashbrown::{HashMap, HashSet};
+use journal::{Journal, Snapshot};
+use parking_lot::Mutex;
+use region::{Region, region_key};
+use schnellru::{Limiter, LruMap};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use specs::Entity as EcsEntity;
+use std::{
+    any::{Any, type_name},
+    io::{self, Write as _},
+    path::{Path, PathBuf},
+    sync::Arc,
+    thread::JoinHandle,
+    time::{Duration, Instant},
+};
+use tracing::{debug, error, info, warn};
+use vek::*;
+
+const MAX_BLOCK_CACHE: usize = 64_000_000;
+/// How often regions are checked for whether they need to be compacted
+const COMPACTION_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+pub struct TerrainPersistence {
+    path: PathBuf,
+    chunks: HashMap<Vec2<i32>, LoadedChunk>,
+    /// A cache of recently unloaded chunks
+    cached_chunks: LruMap<Vec2<i32>, Chunk, ByBlockLimiter>,
+    /// Open region files, shared with the compaction thread. Regions without
+    /// loaded chunks are closed again periodically.
+    regions: HashMap<Vec2<i32>, Arc<Mutex<Region>>>,
+    compaction: Option<JoinHandle<()>>,
+    last_compaction_check: Instant,
+    /// `None` if the journal could not be opened, edits are still persisted
+    journal: Option<Journal>,
+    /// Queries of the journal running in the background
+    journal_queries: Vec<JoinHandle<JournalResponse>>,
+}
+
+/// The result of a journal query, see [`TerrainPersistence::query_history`]
+/// and [`TerrainPersistence::query_rollback`].
+pub enum JournalResponse {
+    History {
+        client: EcsEntity,
+        pos: Vec3<i32>,
+        edits: io::Result<Vec<BlockEdit>>,
+    },
+    Rollback {
+        client: EcsEntity,
+        /// The player whose edits are rolled back
+        player: String,
+        /// The player rolling them back
+        author: Option<Uuid>,
+        reverts: io::Result<Vec<Revert>>,
+    },
+}
+
+/// Wrapper over a [`Chunk`] that keeps track of modifications
+#[derive(Default)]
+pub struct LoadedChunk {
+    chunk: Chunk,
+    modified: bool,
+}
+
+impl TerrainPersistence {
+    /// Create a new terrain persistence system using the given data directory.
+    ///
+    /// If the `VELOREN_TERRAIN` environment variable is set, this will be used
+    /// as the persistence directory instead.
+    pub fn new(data_dir: PathBuf) -> Self {
+        let path = Self::dir(data_dir);
+
+        std::fs::create_dir_all(&path).expect("Failed to create terrain persistence directory");
+
+        info!("Using {:?} as the terrain persistence path", path);
+
+        let journal = Journal::open(path.join("journal"), &path.join("edits.journal"))
+            .map_err(|err| {
+                error!(
+                    "Failed to open terrain edit journal, edits will not be recorded: {:?}",
+                    err
+                )
+            })
+            .ok();
+
+        let mut this = Self {
+            path,
+            chunks: HashMap::default(),
+            cached_chunks: LruMap::new(ByBlockLimiter::new(MAX_BLOCK_CACHE)),
+            regions: HashMap::default(),
+            compaction: None,
+            last_compaction_check: Instant::now(),
+            journal,
+            journal_queries: Vec::new(),
+        };
+        this.migrate_chunk_files();
+        this
+    }
+
+    /// The persistence directory used for the given data directory.
+    pub fn dir(mut data_dir: PathBuf) -> PathBuf {
+        std::env::var("VELOREN_TERRAIN")
+            .map(PathBuf::from)
+            .unwrap_or_else(|_| {
+                data_dir.push("terrain");
+                data_dir
+            })
+    }
+
+    /// Moves chunks stored in the old format, one file per chunk, into region
+    /// files.
+    fn migrate_chunk_files(&mut self) {
+        let entries = match std::fs::read_dir(&self.path) {
+            Ok(entries) => entries,
+            Err(err) => {
+                error!("Failed to read terrain persistence directory: {:?}", err);
+                return;
+            },
+        };
+        let chunk_files = entries
+            .filter_map(|entry| {
+                let path = entry.ok()?.path();
+                let name = path.file_name()?.to_str()?;
+                let (x, y) = name
+                    .strip_prefix("chunk_")?
+                    .strip_suffix(".dat")?
+                    .split_once('_')?;
+                let key = Vec2::new(x.parse().ok()?, y.parse().ok()?);
+                Some((key, path))
+            })
+            .collect::<Vec<_>>();
+        if chunk_files.is_empty() {
+            return;
+        }
+
+        info!(
+            "Migrating {} persisted chunks to region files",
+            chunk_files.len()
+        );
+        let mut migrated = 0;
+        for (key, path) in chunk_files {
+            let chunk = match std::fs::read(&path) {
+                Ok(bytes) => Chunk::deserialize_from(io::Cursor::new(bytes.as_slice())),
+                Err(err) => {
+                    error!("Failed to read chunk file {:?}: {:?}", path, err);
+                    continue;
+                },
+            };
+            let Some(chunk) = chunk else {
+                warn!(
+                    "Failed to load chunk file {:?}, leaving possibly corrupt data in place for \
+                     you to repair.",
+                    path
+                );
+                continue;
+            };
+            let Some(region) = self.region(region_key(key)) else {
+                continue;
+            };
+            let mut region = region.lock();
+            // Chunks already in a region are newer than the old file
+            if !region.contains(key) && !chunk.blocks.is_empty() {
+                let Some(bytes) = chunk.serialize() else {
+                    continue;
+                };
+                if let Err(err) = region.write(key, &bytes) {
+                    error!("Failed to write chunk {:?} to region file: {:?}", key, err);
+                    continue;
+                }
+            }
+            if let Err(err) = std::fs::remove_file(&path) {
+                error!("Failed to remove migrated chunk file {:?}: {:?}", path, err);
+            }
+            migrated += 1;
+        }
+        info!("Migrated {} persisted chunks to region files", migrated);
+    }
+
+    /// Apply persistence changes to a newly generated chunk.
+    pub fn apply_changes(&mut self, key: Vec2<i32>, terrain_chunk: &mut TerrainChunk) {
+        let loaded_chunk = self.load_chunk(key);
+
+        let mut resets = Vec::new();
+        for (rpos, new_block) in loaded_chunk.chunk.blocks() {
+            if let Err(e) = terrain_chunk.map(rpos, |block| {
+                if block == new_block {
+                    resets.push(rpos);
+                }
+                new_block
+            }) {
+                warn!(
+                    "Could not set block in chunk {:?} with position {:?} (out of bounds?): {:?}",
+                    key, rpos, e
+                );
+            }
+        }
+
+        // Reset any unchanged blocks (this is an optimisation only)
+        for rpos in resets {
+            loaded_chunk.chunk.reset_block(rpos);
+            loaded_chunk.modified = true;
+        }
+    }
+
+    /// Maintain terrain persistence (compacting region files in the
+    /// background, etc.)
+    ///
+    /// Changes are written back to the region files when a chunk unloads.
+    pub fn maintain(&mut self) {
+        if let Some(Err(err)) = self.journal.as_mut().map(Journal::flush) {
+            error!("Failed to write terrain edit journal: {:?}", err);
+        }
+
+        if self.last_compaction_check.elapsed() < COMPACTION_CHECK_INTERVAL
+            || self
+                .compaction
+                .as_ref()
+                .is_some_and(|compaction| !compaction.is_finished())
+        {
+            return;
+        }
+        self.last_compaction_check = Instant::now();
+        if let Some(compaction) = self.compaction.take()
+            && compaction.join().is_err()
+        {
+            error!("Terrain persistence compaction thread panicked");
+        }
+        if let Some(journal) = &mut self.journal {
+            journal.prune(unix_time());
+        }
+        self.close_unused_regions();
+
+        let regions = self
+            .regions
+            .values()
+            .filter(|region| region.lock().needs_compaction())
+            .cloned()
+            .collect::<Vec<_>>();
+        if regions.is_empty() {
+            return;
+        }
+
+        debug!("Compacting {} terrain persistence regions", regions.len());
+        let compaction = std::thread::Builder::new()
+            .name("terrain_compaction".to_owned())
+            .spawn(move || {
+                for region in regions {
+                    // Only lock the region while starting and finishing, chunks of it are
+                    // read and written by the main thread in the meantime
+                    let mut compaction = region.lock().start_compaction();
+                    let path = compaction.path().to_owned();
+                    if let Err(err) = compaction
+                        .write()
+                        .and_then(|()| region.lock().finish_compaction(compaction))
+                    {
+                        error!("Failed to compact region {:?}: {:?}", path, err);
+                    }
+                }
+            });
+        match compaction {
+            Ok(compaction) => self.compaction = Some(compaction),
+            Err(err) => error!("Failed to spawn terrain compaction thread: {:?}", err),
+        }
+    }
+
+    /// Closes regions without loaded chunks, they are opened again when
+    /// needed. Regions still to be compacted are kept until that's done.
+    fn close_unused_regions(&mut self) {
+        let in_use = self
+            .chunks
+            .keys()
+            .map(|key| region_key(*key))
+            .collect::<HashSet<_>>();
+        self.regions.retain(|key, region| {
+            in_use.contains(key)
+                || Arc::strong_count(region) > 1
+                || region.lock().needs_compaction()
+        });
+    }
+
+    /// Returns the region with the given key, opening its file if needed.
+    fn region(&mut self, key: Vec2<i32>) -> Option<Arc<Mutex<Region>>> {
+        if let Some(region) = self.regions.get(&key) {
+            return Some(Arc::clone(region));
+        }
+
+        let path = self.path.join(Region::file_name(key));
+        let region = match Region::open(path.clone()) {
+            Ok(region) => region,
+            Err(err) if err.kind() == io::ErrorKind::InvalidData => {
+                let backup_path = backup_path(&path);
+                error!(
+                    "Failed to load region {:?}, moving possibly corrupt (or too new) data to \
+                     {:?} for you to repair: {:?}",
+                    key, backup_path, err
+                );
+                if let Err(err) = std::fs::rename(&path, backup_path) {
+                    error!("Failed to rename invalid region file: {:?}", err);
+                    return None;
+                }
+                Region::open(path).ok()?
+            },
+            Err(err) => {
+                error!("Failed to open region file {:?}: {:?}", path, err);
+                return None;
+            },
+        };
+        let region = Arc::new(Mutex::new(region));
+        self.regions.insert(key, Arc::clone(&region));
+        Some(region)
+    }
+
+    fn read_chunk(&mut self, key: Vec2<i32>) -> Chunk {
+        let Some(region) = self.region(region_key(key)) else {
+            return Chunk::default();
+        };
+        let bytes = match region.lock().read(key) {
+            Ok(Some(bytes)) => bytes,
+            Ok(None) => return Chunk::default(),
+            Err(err) => {
+                error!(
+                    "Failed to read data for chunk {:?}, its changes are lost: {:?}",
+                    key, err
+                );
+                return Chunk::default();
+            },
+        };
+
+        Chunk::deserialize_from(io::Cursor::new(bytes.as_slice())).unwrap_or_else(|| {
+            let backup_path =
+                backup_path(&self.path.join(format!("chunk_{}_{}.dat", key.x, key.y)));
+            error!(
+                "Failed to load chunk {:?}, writing possibly corrupt (or too new) data to {:?} \
+                 for you to repair.",
+                key, backup_path
+            );
+            let atomic_file = AtomicFile::new(backup_path, OverwriteBehavior::DisallowOverwrite);
+            if let Err(err) = atomic_file.write(|file| file.write_all(&bytes)) {
+                error!("Failed to write invalid chunk data to file: {:?}", err);
+            }
+            Chunk::default()
+        })
+    }
+
+    fn load_chunk(&mut self, key: Vec2<i32>) -> &mut LoadedChunk {
+        if !self.chunks.contains_key(&key) {
+            // If the chunk has been recently unloaded and is still cached, dont read it
+            // from disk
+            let chunk = match self.cached_chunks.remove(&key) {
+                Some(chunk) => chunk,
+                None => self.read_chunk(key),
+            };
+            self.chunks.insert(key, LoadedChunk {
+                chunk,
+                modified: false,
+            });
+        }
+        self.chunks.get_mut(&key).expect("Chunk was inserted above")
+    }
+
+    pub fn unload_chunk(&mut self, key: Vec2<i32>) {
+        if let Some(LoadedChunk { chunk, modified }) = self.chunks.remove(&key) {
+            if modified || self.cached_chunks.peek(&key).is_none() {
+                self.cached_chunks.insert(key, chunk.clone());
+            }
+
+            // Prevent any uneccesarry IO when nothing in this chunk has changed
+            if modified {
+                self.write_chunk(key, chunk);
+            }
+        }
+    }
+
+    fn write_chunk(&mut self, key: Vec2<i32>, chunk: Chunk) {
+        // An empty record removes the chunk from its region
+        let bytes = if chunk.blocks.is_empty() {
+            Vec::new()
+        } else {
+            match chunk.serialize() {
+                Some(bytes) => bytes,
+                None => return,
+            }
+        };
+
+        if let Some(region) = self.region(region_key(key))
+            && let Err(err) = region.lock().write(key, &bytes)
+        {
+            error!("Failed to write chunk data to region file: {:?}", err);
+        }
+    }
+
+    /// Writes all changes to disk without unloading any chunks and waits for a
+    /// running compaction, so the persistence directory can be copied.
+    pub fn flush(&mut self) {
+        let modified = self
+            .chunks
+            .iter_mut()
+            .filter(|(_, loaded_chunk)| loaded_chunk.modified)
+            .map(|(key, loaded_chunk)| {
+                loaded_chunk.modified = false;
+                (*key, loaded_chunk.chunk.clone())
+            })
+            .collect::<Vec<_>>();
+        for (key, chunk) in modified {
+            self.write_chunk(key, chunk);
+        }
+
+        if let Some(Err(err)) = self.journal.as_mut().map(Journal::flush) {
+            error!("Failed to write terrain edit journal: {:?}", err);
+        }
+        if let Some(compaction) = self.compaction.take()
+            && compaction.join().is_err()
+        {
+            error!("Terrain persistence compaction thread panicked");
+        }
+    }
+
+    pub fn clear_chunk(&mut self, chunk: Vec2<i32>) {
+        self.cached_chunks.remove(&chunk);
+        self.chunks.insert(chunk, LoadedChunk {
+            chunk: Chunk::default(),
+            modified: true,
+        });
+    }
+
+    pub fn unload_all(&mut self) {
+        for key in self.chunks.keys().copied().collect::<Vec<_>>() {
+            self.unload_chunk(key);
+        }
+    }
+
+    /// Persist a block edit and record it in the edit journal. `old_block` is
+    /// the block that was replaced and `author` the player making the edit.
+    pub fn set_block(
+        &mut self,
+        pos: Vec3<i32>,
+        old_block: Block,
+        block: Block,
+        author: Option<Uuid>,
+    ) {
+        let (key, rpos) = chunk_pos(pos);
+        let loaded_chunk = self.load_chunk(key);
+        let old_persisted = loaded_chunk.chunk.blocks.insert(rpos, block);
+        if old_persisted != Some(block) {
+            loaded_chunk.modified = true;
+        }
+
+        if old_block != block
+            && let Some(journal) = &mut self.journal
+            && let Err(err) = journal.append(BlockEdit::now(pos, old_block, block, author))
+        {
+            error!(
+                "Failed to record block edit in terrain edit journal: {:?}",
+                err
+            );
+        }
+    }
+
+    /// Looks up the newest `limit` recorded edits of the block at `pos` in the
+    /// background, the response is returned by
+    /// [`TerrainPersistence::finished_journal_queries`].
+    pub fn query_history(
+        &mut self,
+        client: EcsEntity,
+        pos: Vec3<i32>,
+        limit: usize,
+    ) -> io::Result<()> {
+        self.query_journal(move |snapshot| JournalResponse::History {
+            client,
+            pos,
+            edits: snapshot.history(pos, limit),
+        })
+    }
+
+    /// Determines the blocks to revert to undo the edits matching `filter` in
+    /// the background. Blocks edited again afterwards by edits not matching
+    /// the filter are left alone. The reverts are applied with
+    /// [`TerrainPersistence::rollback`] once returned by
+    /// [`TerrainPersistence::finished_journal_queries`].
+    pub fn query_rollback(
+        &mut self,
+        client: EcsEntity,
+        filter: EditFilter,
+        player: String,
+        author: Option<Uuid>,
+    ) -> io::Result<()> {
+        self.query_journal(move |snapshot| JournalResponse::Rollback {
+            client,
+            player,
+            author,
+            reverts: snapshot.rollback(&filter),
+        })
+    }
+
+    fn query_journal(
+        &mut self,
+        query: impl FnOnce(Snapshot) -> JournalResponse + Send + 'static,
+    ) -> io::Result<()> {
+        let snapshot = match &mut self.journal {
+            Some(journal) => journal.snapshot()?,
+            None => Snapshot::default(),
+        };
+        let handle = std::thread::Builder::new()
+            .name("terrain_journal".to_owned())
+            .spawn(move || query(snapshot))?;
+        self.journal_queries.push(handle);
+        Ok(())
+    }
+
+    /// Responses of the journal queries that finished since the last call.
+    pub fn finished_journal_queries(&mut self) -> Vec<JournalResponse> {
+        let (finished, running) = self
+            .journal_queries
+            .drain(..)
+            .partition::<Vec<_>, _>(JoinHandle::is_finished);
+        self.journal_queries = running;
+        finished
+            .into_iter()
+            .filter_map(|query| {
+                query
+                    .join()
+                    .map_err(|_| error!("Terrain journal query thread panicked"))
+                    .ok()
+            })
+            .collect()
+    }
+
+    /// Persist the reversal of the blocks determined by a rollback query, the
+    /// reversal itself is recorded as edits by `author`.
+    ///
+    /// Blocks are only reverted if they are still as left behind by the rolled
+    /// back edits, i.e. they weren't changed by anything not recorded in the
+    /// journal or since the journal was queried. `live_block` returns the
+    /// block in the loaded terrain, blocks of unloaded chunks are compared
+    /// against the persisted ones.
+    ///
+    /// Returns the blocks that were reverted, the caller is responsible for
+    /// applying them to the loaded terrain, and the number of skipped blocks.
+    pub fn rollback(
+        &mut self,
+        reverts: Vec<Revert>,
+        author: Option<Uuid>,
+        live_block: impl Fn(Vec3<i32>) -> Option<Block>,
+    ) -> (Vec<Revert>, usize) {
+        let (reverted, skipped) = reverts.into_iter().partition::<Vec<_>, _>(|revert| {
+            let live = live_block(revert.pos).or_else(|| {
+                let (key, rpos) = chunk_pos(revert.pos);
+                self.load_chunk(key).chunk.blocks.get(&rpos).copied()
+            });
+            live == Some(revert.current)
+        });
+        for revert in &reverted {
+            self.set_block(revert.pos, revert.current, revert.restore, author);
+        }
+        (reverted, skipped.len())
+    }
+}
+
+/// The key of the chunk containing `pos` and the position within it
+fn chunk_pos(pos: Vec3<i32>) -> (Vec2<i32>, Vec3<i32>) {
+    let key = pos
+        .xy()
+        .map2(TerrainChunk::RECT_SIZE, |e, sz| e.div_euclid(sz as i32));
+    (key, pos - key * TerrainChunk::RECT_SIZE.map(|e| e as i32))
+}
+
+impl Drop for TerrainPersistence {
+    fn drop(&mut self) {
+        self.unload_all();
+        if let Some(Err(err)) = self.journal.as_mut().map(Journal::flush) {
+            error!("Failed to write terrain edit journal: {:?}", err);
+        }
+        if let Some(compaction) = self.compaction.take()
+            && compaction.join().is_err()
+        {
+            error!("Terrain persistence compaction thread panicked");
+        }
+    }
+}
+
+/// Find an untaken name for a backup of the file at `path`
+fn backup_path(path: &Path) -> PathBuf {
+    let mut backup_path = path.to_path_buf();
+    backup_path.set_extension("dat_backup_0");
+    let mut i = 1;
+    while backup_path.exists() {
+        backup_path.set_extension(format!("dat_backup_{}", i));
+        i += 1;
+    }
+    backup_path
+}
+
+#[derive(Default, Serialize, Deserialize, Clone)]
+pub struct Chunk {
+    blocks: HashMap<Vec3<i32>, Block>,
+}
+
+impl Chunk {
+    fn deserialize_from<R: io::Read + Clone>(reader: R) -> Option<Self> {
+        version::try_load(reader)
+    }
+
+    fn serialize(self) -> Option<Vec<u8>> {
+        encode_to_vec::<version::Current, _>(self.prepare_raw(), legacy())
+            .map_err(|err| error!("Failed to serialize chunk data: {:?}", err))
+            .ok()
+    }
+
+    fn prepare_raw(self) -> version::Current { self.into() }
+
+    fn blocks(&self) -> impl Iterator<Item = (Vec3<i32>, Block)> + '_ {
+        self.blocks.iter().map(|(k, b)| (*k, *b))
+    }
+
+    fn reset_block(&mut self, rpos: Vec3<i32>) { self.blocks.remove(&rpos); }
+
+    /// Get the number of blocks this chunk contains
+    fn len(&self) -> usize { self.blocks.len() }
+}
+
+/// LRU limiter that limits by the number of blocks
+struct ByBlockLimiter {
+    /// Maximum number of blocks that can be contained
+    block_limit: usize,
+    /// Total number of blocks that are currently contained in the LRU
+    counted_blocks: usize,
+}
+
+impl Limiter<Vec2<i32>, Chunk> for ByBlockLimiter {
+    type KeyToInsert<'a> = Vec2<i32>;
+    type LinkType = u32;
+
+    fn is_over_the_limit(&self, _length: usize) -> bool { self.counted_blocks > self.block_limit }
+
+    fn on_insert(
+        &mut self,
+        _length: usize,
+        key: Self::KeyToInsert<'_>,
+        chunk: Chunk,
+    ) -> Option<(Vec2<i32>, Chunk)> {
+        let chunk_size = chunk.len();
+
+        if self.counted_blocks + chunk_size > self.block_limit {
+            None
+        } else {
+            self.counted_blocks += chunk_size;
+            Some((key, chunk))
+        }
+    }
+
+    fn on_replace(
+        &mut self,
+        _length: usize,
+        _old_key: &mut Vec2<i32>,
+        _new_key: Self::KeyToInsert<'_>,
+        old_chunk: &mut Chunk,
+        new_chunk: &mut Chunk,
+    ) -> bool {
+        let old_size = old_chunk.len() as isize; // I assume chunks are never larger than a few thousand blocks anyways, cast should be OK
+        let new_size = new_chunk.len() as isize;
+        let new_total = self
+            .counted_blocks
+            .saturating_add_signed(new_size - old_size);
+
+        if new_total > self.block_limit {
+            false
+        } else {
+            self.counted_blocks = new_total;
+            true
+        }
+    }
+
+    fn on_removed(&mut self, _key: &mut Vec2<i32>, chunk: &mut Chunk) {
+        self.counted_blocks = self.counted_blocks.saturating_sub(chunk.len());
+    }
+
+    fn on_cleared(&mut self) { self.counted_blocks = 0; }
+
+    fn on_grow(&mut self, _new_memory_usage: usize) -> bool { true }
+}
+
+impl ByBlockLimiter {
+    /// Creates a new by-block limit
+    fn new(block_limit: usize) -> Self {
+        Self {
+            block_limit,
+            counted_blocks: 0,
+        }
+    }
+}
+
+/// # Adding a new chunk format version
+///
+/// Chunk formats are designed to be backwards-compatible when loading, but are
+/// not required to be backwards-compatible when saving (i.e: we must always be
+/// able to load old formats, but we're not required to save old formats because
+/// newer formats might contain richer information that is incompatible with an
+/// older format).
+///
+/// The steps for doing this are as follows:
+///
+/// 1. Create a new 'raw format' type that implements [`Serialize`] and
+///    `Deserialize`]. Make sure to add a version field. If in doubt, copy the
+///    last raw format and increment the version number wherever it appears.
+///    Don't forget to increment the version number in the
+///    `serde(deserialize_with = ...}` attribute! Conventionally, these types
+///    are named `V{N}` where `{N}` is the number succeeding the previous raw
+///    format type.
+///
+/// 2. Add an implementation of `From<{YourRawFormat}>` for `Chunk`. As before,
+///    see previous versions if in doubt.
+///
+/// 3. Change the type of [`version::Current`] to your new raw format type.
+///
+/// 4. Add an entry for your raw format at the top of the array in
+///    [`version::loaders`].
+///
+/// 5. Remove the `Serialize` implementation from the previous raw format type:
+///    we don't need it any longer!
+mod version {
+    use super::*;
+
+    /// The newest supported raw format type. This should be changed every time
+    /// a new raw format is added.
+    // Step [3]
+    pub type Current = V3;
+
+    type LoadChunkFn<R> = fn(R) -> Result<Chunk, (&'static str, Box<DecodeError>)>;
+    fn loaders<'a, R: io::Read + Clone>() -> &'a [LoadChunkFn<R>] {
+        // Step [4]
+        &[load_raw::<V3, _>, load_raw::<V2, _>, load_raw::<V1, _>]
+    }
+
+    // Convert back to current
+
+    impl From<Chunk> for Current {
+        fn from(chunk: Chunk) -> Self {
+            Self {
+                version: version_magic(3),
+                blocks: chunk
+                    .blocks
+                    .into_iter()
+                    .map(|(pos, b)| (pos.x as u8, pos.y as u8, pos.z as i16, b.to_u32()))
+                    .collect(),
+            }
+        }
+    }
+
+    /// Version 3 of the raw chunk format.
+    #[derive(Serialize, Deserialize)]
+    pub struct V3 {
+        #[serde(deserialize_with = "version::<_, 3>")]
+        pub version: u64,
+        pub blocks: Vec<(u8, u8, i16, u32)>,
+    }
+
+    impl From<V3> for Chunk {
+        fn from(v3: V3) -> Self {
+            Self {
+                blocks: v3
+                    .blocks
+                    .into_iter()
+                    .map(|(x, y, z, b)| {
+                        (
+                            Vec3::new(x as i32, y as i32, z as i32),
+                            Block::from_u32(b).unwrap_or_else(Block::empty),
+                        )
+                    })
+                    .collect(),
+            }
+        }
+    }
+
+    /// Version 2 of the raw chunk format.
+    #[derive(Deserialize)]
+    pub struct V2 {
+        #[serde(deserialize_with = "version::<_, 2>")]
+        pub version: u64,
+        pub blocks: Vec<(u8, u8, i16, Block)>,
+    }
+
+    impl From<V2> for Chunk {
+        fn from(v2: V2) -> Self {
+            Self {
+                blocks: v2
+                    .blocks
+                    .into_iter()
+                    .map(|(x, y, z, b)| (Vec3::new(x as i32, y as i32, z as i32), b))
+                    .collect(),
+            }
+        }
+    }
+
+    /// Version 1 of the raw chunk format.
+    #[derive(Deserialize)]
+    pub struct V1 {
+        pub blocks: HashMap<Vec3<i32>, Block>,
+    }
+
+    impl From<V1> for Chunk {
+        fn from(v1: V1) -> Self { Self { blocks: v1.blocks } }
+    }
+
+    // Utility things
+
+    fn version_magic(n: u16) -> u64 { (n as u64) | (0x3352ACEEA789 << 16) }
+
+    fn version<'de, D: serde::Deserializer<'de>, const V: u16>(de: D) -> Result<u64, D::Error> {
+        u64::deserialize(de).and_then(|x| {
+            if x == version_magic(V) {
+                Ok(x)
+            } else {
+                Err(serde::de::Error::invalid_value(
+                    serde::de::Unexpected::Unsigned(x),
+                    &"incorrect magic/version bytes",
+                ))
+            }
+        })
+    }
+
+    fn load_raw<RawChunk: Any + Into<Chunk> + DeserializeOwned, R: io::Read + Clone>(
+        mut reader: R,
+    ) -> Result<Chunk, (&'static str, Box<DecodeError>)> {
+        decode_from_std_read::<RawChunk, _, _>(&mut reader, legacy())
+            .map(Into::into)
+            .map_err(|e| (type_name::<RawChunk>(), Box::new(e)))
+    }
+
+    pub fn try_load<R: io::Read + Clone>(reader: R) -> Option<Chunk> {
+        loaders()
+            .iter()
+            .find_map(|load_raw| match load_raw(reader.clone()) {
+                Ok(chunk) => Some(chunk),
+                Err((raw_name, e)) => {
+                    debug!(
+                        "Attempt to load chunk with raw format `{}` failed: {:?}",
+                        raw_name, e
+                    );
+                    None
+                },
+            })
+    }
+}
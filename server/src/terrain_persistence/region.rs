@@ -0,0 +1,508 @@
+//! Region files store the persisted changes of many chunks in a single file.
+//!
+//! A region file starts with [`MAGIC`] and the format version, followed by
+//! records that are only ever appended. A record is a [`RecordHeader`]
+//! followed by the serialized chunk. The newest record of a chunk wins, an
+//! empty record marks the chunk as unchanged. The index of the newest record
+//! of each chunk is built when the region is opened.
+//!
+//! Appending leaves outdated records behind. They are removed by compacting
+//! the region: all live records are written into a new file which atomically
+//! replaces the old one. The new file is written without locking the region,
+//! records appended meanwhile are copied over when swapping the files.
+
+use hashbrown::HashMap;
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+use tracing::{error, warn};
+use vek::*;
+
+/// Regions are `2^REGION_SIZE_LG` chunks wide in both directions
+const REGION_SIZE_LG: u32 = 4;
+const MAGIC: [u8; 4] = *b"VTRG";
+const FORMAT_VERSION: u16 = 1;
+const FILE_HEADER_LEN: u64 = 6;
+const RECORD_HEADER_LEN: u64 = 10;
+/// Regions with less garbage than this are not worth compacting
+const MIN_COMPACTION_GARBAGE: u64 = 1 << 20;
+
+/// Key of the region containing the given chunk
+pub fn region_key(chunk_key: Vec2<i32>) -> Vec2<i32> { chunk_key.map(|e| e >> REGION_SIZE_LG) }
+
+/// Position of a chunk inside its region
+fn chunk_offset(chunk_key: Vec2<i32>) -> Vec2<u8> {
+    chunk_key.map(|e| (e & ((1 << REGION_SIZE_LG) - 1)) as u8)
+}
+
+fn invalid_data(msg: &str) -> io::Error { io::Error::new(io::ErrorKind::InvalidData, msg) }
+
+struct RecordHeader {
+    offset: Vec2<u8>,
+    len: u32,
+    checksum: u32,
+}
+
+impl RecordHeader {
+    fn to_bytes(&self) -> [u8; RECORD_HEADER_LEN as usize] {
+        let mut bytes = [0; RECORD_HEADER_LEN as usize];
+        bytes[0] = self.offset.x;
+        bytes[1] = self.offset.y;
+        bytes[2..6].copy_from_slice(&self.len.to_le_bytes());
+        bytes[6..10].copy_from_slice(&self.checksum.to_le_bytes());
+        bytes
+    }
+
+    fn from_bytes(bytes: [u8; RECORD_HEADER_LEN as usize]) -> Self {
+        Self {
+            offset: Vec2::new(bytes[0], bytes[1]),
+            len: u32::from_le_bytes([bytes[2], bytes[3], bytes[4], bytes[5]]),
+            checksum: u32::from_le_bytes([bytes[6], bytes[7], bytes[8], bytes[9]]),
+        }
+    }
+}
+
+/// Where the newest record of a chunk is stored
+#[derive(Clone, Copy)]
+struct Entry {
+    /// Position of the payload in the file
+    pos: u64,
+    len: u32,
+    checksum: u32,
+}
+
+pub struct Region {
+    path: PathBuf,
+    index: HashMap<Vec2<u8>, Entry>,
+    /// `0` if the file does not exist yet
+    file_len: u64,
+    /// Bytes used by the records in `index`
+    live_bytes: u64,
+}
+
+impl Region {
+    pub fn file_name(region_key: Vec2<i32>) -> String {
+        format!("region_{}_{}.dat", region_key.x, region_key.y)
+    }
+
+    /// Opens the region file at `path`, or an empty region if there is none.
+    ///
+    /// An incomplete record at the end of the file, e.g. left behind by a
+    /// crash while writing, is cut off.
+    pub fn open(path: PathBuf) -> io::Result<Self> {
+        let mut region = Self {
+            path,
+            index: HashMap::new(),
+            file_len: 0,
+            live_bytes: 0,
+        };
+        let file = match File::open(&region.path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(region),
+            Err(e) => return Err(e),
+        };
+        let file_len = file.metadata()?.len();
+        if file_len < FILE_HEADER_LEN {
+            // Crashed while creating the file, nothing has been stored yet
+            OpenOptions::new()
+                .write(true)
+                .open(&region.path)?
+                .set_len(0)?;
+            return Ok(region);
+        }
+        let mut reader = BufReader::new(file);
+
+        let mut header = [0; FILE_HEADER_LEN as usize];
+        reader.read_exact(&mut header)?;
+        if header[..4] != MAGIC {
+            return Err(invalid_data("not a region file"));
+        }
+        if u16::from_le_bytes([header[4], header[5]]) != FORMAT_VERSION {
+            return Err(invalid_data("unsupported region format version"));
+        }
+
+        let mut pos = FILE_HEADER_LEN;
+        while pos + RECORD_HEADER_LEN <= file_len {
+            let mut bytes = [0; RECORD_HEADER_LEN as usize];
+            reader.read_exact(&mut bytes)?;
+            let header = RecordHeader::from_bytes(bytes);
+            if header.offset.reduce_max() >= 1 << REGION_SIZE_LG {
+                return Err(invalid_data("record of a chunk outside of the region"));
+            }
+            let payload_pos = pos + RECORD_HEADER_LEN;
+            if payload_pos + header.len as u64 > file_len {
+                break;
+            }
+            reader.seek_relative(header.len as i64)?;
+            region.set_entry(
+                header.offset,
+                (header.len > 0).then_some(Entry {
+                    pos: payload_pos,
+                    len: header.len,
+                    checksum: header.checksum,
+                }),
+            );
+            pos = payload_pos + header.len as u64;
+        }
+
+        if pos < file_len {
+            warn!(
+                path = ?region.path,
+                "Region file ends with an incomplete record, probably left behind by a crash. \
+                 Cutting it off."
+            );
+            OpenOptions::new()
+                .write(true)
+                .open(&region.path)?
+                .set_len(pos)?;
+        }
+        region.file_len = pos;
+        Ok(region)
+    }
+
+    fn set_entry(&mut self, offset: Vec2<u8>, entry: Option<Entry>) {
+        if let Some(old) = self.index.remove(&offset) {
+            self.live_bytes -= RECORD_HEADER_LEN + old.len as u64;
+        }
+        if let Some(entry) = entry {
+            self.live_bytes += RECORD_HEADER_LEN + entry.len as u64;
+            self.index.insert(offset, entry);
+        }
+    }
+
+    pub fn contains(&self, chunk_key: Vec2<i32>) -> bool {
+        self.index.contains_key(&chunk_offset(chunk_key))
+    }
+
+    /// Reads the newest record of a chunk, verifying its checksum.
+    pub fn read(&self, chunk_key: Vec2<i32>) -> io::Result<Option<Vec<u8>>> {
+        let Some(entry) = self.index.get(&chunk_offset(chunk_key)) else {
+            return Ok(None);
+        };
+        let mut file = File::open(&self.path)?;
+        file.seek(SeekFrom::Start(entry.pos))?;
+        let mut data = vec![0; entry.len as usize];
+        file.read_exact(&mut data)?;
+        if crc32fast::hash(&data) != entry.checksum {
+            return Err(invalid_data("checksum mismatch"));
+        }
+        Ok(Some(data))
+    }
+
+    /// Appends a record for the chunk. An empty `data` removes the chunk.
+    ///
+    /// The record is synced to disk before this returns.
+    pub fn write(&mut self, chunk_key: Vec2<i32>, data: &[u8]) -> io::Result<()> {
+        let offset = chunk_offset(chunk_key);
+        if data.is_empty() && !self.index.contains_key(&offset) {
+            return Ok(());
+        }
+        let len = u32::try_from(data.len()).map_err(|_| invalid_data("chunk data too large"))?;
+        let checksum = crc32fast::hash(data);
+
+        let mut bytes =
+            Vec::with_capacity((FILE_HEADER_LEN + RECORD_HEADER_LEN) as usize + data.len());
+        if self.file_len == 0 {
+            bytes.extend_from_slice(&MAGIC);
+            bytes.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+        }
+        let payload_pos = self.file_len.max(FILE_HEADER_LEN) + RECORD_HEADER_LEN;
+        bytes.extend_from_slice(
+            &RecordHeader {
+                offset,
+                len,
+                checksum,
+            }
+            .to_bytes(),
+        );
+        bytes.extend_from_slice(data);
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        if let Err(e) = file.write_all(&bytes).and_then(|()| file.sync_data()) {
+            // Don't leave a partial record behind
+            if let Err(e) = file.set_len(self.file_len) {
+                error!(?e, path = ?self.path, "Failed to remove partially written record");
+            }
+            return Err(e);
+        }
+
+        self.file_len = payload_pos + len as u64;
+        self.set_entry(
+            offset,
+            (len > 0).then_some(Entry {
+                pos: payload_pos,
+                len,
+                checksum,
+            }),
+        );
+        Ok(())
+    }
+
+    fn garbage_bytes(&self) -> u64 {
+        self.file_len
+            .saturating_sub(FILE_HEADER_LEN + self.live_bytes)
+    }
+
+    pub fn needs_compaction(&self) -> bool {
+        let garbage = self.garbage_bytes();
+        garbage >= MIN_COMPACTION_GARBAGE && garbage > self.live_bytes
+    }
+
+    /// Rewrites the region file with only the newest record of every chunk.
+    /// Records failing their integrity check are dropped.
+    ///
+    /// The compaction thread instead calls [`Region::start_compaction`],
+    /// [`Compaction::write`] and [`Region::finish_compaction`] itself, so the
+    /// region is only locked while starting and finishing.
+    pub fn compact(&mut self) -> io::Result<()> {
+        let mut compaction = self.start_compaction();
+        compaction.write()?;
+        self.finish_compaction(compaction)
+    }
+
+    /// Takes a snapshot of the index to compact the file against.
+    pub fn start_compaction(&self) -> Compaction {
+        let mut entries = self.index.iter().map(|(o, e)| (*o, *e)).collect::<Vec<_>>();
+        entries.sort_by_key(|(_, entry)| entry.pos);
+        Compaction {
+            path: self.path.clone(),
+            temp_path: self.path.with_extension("dat_compacting"),
+            entries,
+            file_len: self.file_len,
+            index: HashMap::new(),
+            compacted_len: 0,
+        }
+    }
+
+    /// Replaces the region file with the compacted one written by
+    /// [`Compaction::write`]. Records appended in the meantime are copied
+    /// over.
+    pub fn finish_compaction(&mut self, compaction: Compaction) -> io::Result<()> {
+        let mut tail = vec![0; (self.file_len - compaction.file_len) as usize];
+        if !tail.is_empty() {
+            let mut file = File::open(&self.path)?;
+            file.seek(SeekFrom::Start(compaction.file_len))?;
+            file.read_exact(&mut tail)?;
+        }
+
+        let mut index = HashMap::with_capacity(self.index.len());
+        for (offset, entry) in &self.index {
+            let entry = if entry.pos >= compaction.file_len {
+                Entry {
+                    pos: entry.pos - compaction.file_len + compaction.compacted_len,
+                    ..*entry
+                }
+            } else if let Some(entry) = compaction.index.get(offset) {
+                *entry
+            } else {
+                // Dropped as corrupt
+                continue;
+            };
+            index.insert(*offset, entry);
+        }
+
+        let mut file = OpenOptions::new()
+            .append(true)
+            .open(&compaction.temp_path)?;
+        file.write_all(&tail)?;
+        file.sync_data()?;
+        std::fs::rename(&compaction.temp_path, &self.path)?;
+        #[cfg(unix)]
+        if let Some(dir) = self.path.parent() {
+            File::open(dir)?.sync_all()?;
+        }
+
+        self.file_len = compaction.compacted_len + tail.len() as u64;
+        self.live_bytes = index
+            .values()
+            .map(|entry| RECORD_HEADER_LEN + entry.len as u64)
+            .sum();
+        self.index = index;
+        Ok(())
+    }
+}
+
+/// A compaction in progress, the compacted file is written next to the region
+/// file without holding the region.
+pub struct Compaction {
+    path: PathBuf,
+    temp_path: PathBuf,
+    /// The index when the compaction was started, ordered by position
+    entries: Vec<(Vec2<u8>, Entry)>,
+    /// Length of the region file when the compaction was started
+    file_len: u64,
+    /// Index of the compacted file
+    index: HashMap<Vec2<u8>, Entry>,
+    compacted_len: u64,
+}
+
+impl Compaction {
+    /// Writes the live records into the compacted file. Only records written
+    /// before the compaction was started are read, so the region can keep
+    /// appending to its file in the meantime.
+    pub fn write(&mut self) -> io::Result<()> {
+        let mut source = BufReader::new(File::open(&self.path)?);
+        let mut target = BufWriter::new(File::create(&self.temp_path)?);
+        target.write_all(&MAGIC)?;
+        target.write_all(&FORMAT_VERSION.to_le_bytes())?;
+        let mut pos = FILE_HEADER_LEN;
+        for (offset, entry) in &self.entries {
+            source.seek(SeekFrom::Start(entry.pos))?;
+            let mut data = vec![0; entry.len as usize];
+            source.read_exact(&mut data)?;
+            if crc32fast::hash(&data) != entry.checksum {
+                error!(
+                    ?offset,
+                    path = ?self.path,
+                    "Dropping corrupt chunk record while compacting region"
+                );
+                continue;
+            }
+            target.write_all(
+                &RecordHeader {
+                    offset: *offset,
+                    len: entry.len,
+                    checksum: entry.checksum,
+                }
+                .to_bytes(),
+            )?;
+            target.write_all(&data)?;
+            self.index.insert(*offset, Entry {
+                pos: pos + RECORD_HEADER_LEN,
+                ..*entry
+            });
+            pos += RECORD_HEADER_LEN + entry.len as u64;
+        }
+        target
+            .into_inner()
+            .map_err(|e| e.into_error())?
+            .sync_all()?;
+        self.compacted_len = pos;
+        Ok(())
+    }
+
+    pub fn path(&self) -> &Path { &self.path }
+}
+
+impl Drop for Compaction {
+    fn drop(&mut self) {
+        // Only left behind if the compaction failed
+        let _ = std::fs::remove_file(&self.temp_path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_region(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "veloren_region_test_{}_{name}.dat",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn region_keys() {
+        assert_eq!(region_key(Vec2::new(0, 15)), Vec2::new(0, 0));
+        assert_eq!(region_key(Vec2::new(16, -1)), Vec2::new(1, -1));
+        assert_eq!(chunk_offset(Vec2::new(-1, 17)), Vec2::new(15, 1));
+    }
+
+    #[test]
+    fn write_reopen_compact() {
+        let path = temp_region("compact");
+        let a = Vec2::new(3, 4);
+        let b = Vec2::new(-1, 0);
+
+        let mut region = Region::open(path.clone()).unwrap();
+        region.write(a, b"first").unwrap();
+        region.write(b, b"other").unwrap();
+        region.write(a, b"second").unwrap();
+        region.write(b, &[]).unwrap();
+        assert_eq!(region.read(a).unwrap().as_deref(), Some(&b"second"[..]));
+        assert_eq!(region.read(b).unwrap(), None);
+
+        let mut region = Region::open(path.clone()).unwrap();
+        assert_eq!(region.read(a).unwrap().as_deref(), Some(&b"second"[..]));
+        assert!(!region.contains(b));
+        assert!(region.garbage_bytes() > 0);
+
+        region.compact().unwrap();
+        assert_eq!(region.garbage_bytes(), 0);
+        let region = Region::open(path.clone()).unwrap();
+        assert_eq!(region.read(a).unwrap().as_deref(), Some(&b"second"[..]));
+        assert_eq!(region.file_len, std::fs::metadata(&path).unwrap().len());
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn write_while_compacting() {
+        let path = temp_region("concurrent");
+        let a = Vec2::new(0, 0);
+        let b = Vec2::new(5, 5);
+        let c = Vec2::new(7, 2);
+
+        let mut region = Region::open(path.clone()).unwrap();
+        region.write(a, b"old").unwrap();
+        region.write(a, b"kept").unwrap();
+        region.write(b, b"removed").unwrap();
+        let mut compaction = region.start_compaction();
+        compaction.write().unwrap();
+
+        // Written while the compacted file is being built
+        region.write(b, &[]).unwrap();
+        region.write(c, b"new").unwrap();
+        region.finish_compaction(compaction).unwrap();
+
+        for region in [region, Region::open(path.clone()).unwrap()] {
+            assert_eq!(region.read(a).unwrap().as_deref(), Some(&b"kept"[..]));
+            assert_eq!(region.read(b).unwrap(), None);
+            assert_eq!(region.read(c).unwrap().as_deref(), Some(&b"new"[..]));
+        }
+        assert!(!path.with_extension("dat_compacting").exists());
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn torn_and_corrupt_records() {
+        let path = temp_region("torn");
+        let a = Vec2::new(1, 1);
+        let mut region = Region::open(path.clone()).unwrap();
+        region.write(a, b"intact").unwrap();
+        let intact_len = region.file_len;
+        region.write(a, b"torn record").unwrap();
+
+        // Simulate a crash in the middle of the last write
+        OpenOptions::new()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_len(intact_len + 4)
+            .unwrap();
+        let region = Region::open(path.clone()).unwrap();
+        assert_eq!(region.read(a).unwrap().as_deref(), Some(&b"intact"[..]));
+        assert_eq!(std::fs::metadata(&path).unwrap().len(), intact_len);
+
+        // Flip a byte of the payload
+        let mut bytes = std::fs::read(&path).unwrap();
+        *bytes.last_mut().unwrap() ^= 0xFF;
+        std::fs::write(&path, bytes).unwrap();
+        let region = Region::open(path.clone()).unwrap();
+        assert_eq!(
+            region.read(a).unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -199,7 +199,8 @@ pub fn localize_chat_message(
         | ChatType::CommandInfo
         | ChatType::Meta
         | ChatType::FactionMeta(_)
-        | ChatType::GroupMeta(_) => localization.get_content(msg.content()),
+        | ChatType::GroupMeta(_)
+        | ChatType::Duel(_, _) => localization.get_content(msg.content()),
         ChatType::Tell(from, to) => {
             // If `from` is you, it means you're writing to someone
             // and you want to see who you're writing to.
@@ -10,9 +10,10 @@
 //!
 //! Likewise Airshipper should only use the following subcommands:
 //! * `ListWgpuBackends`
-use std::str::FromStr;
+use std::{path::PathBuf, str::FromStr};
 
 use clap::{Parser, Subcommand};
+use client::addr::ConnectionArgs;
 use common_net::msg::ClientType;
 
 #[derive(Parser, Clone)]
@@ -30,10 +31,35 @@ pub struct Args {
     #[clap(short, long, env = "VELOREN_CLIENT_TYPE", default_value_t = VoxygenClientType(ClientType::Game))]
     pub client_type: VoxygenClientType,
 
+    /// Record every message received from the server to this file.
+    #[clap(long, env = "VELOREN_RECORD", conflicts_with = "replay")]
+    pub record: Option<PathBuf>,
+
+    /// Replay a session previously recorded with `--record` instead of
+    /// connecting to the selected server.
+    #[clap(long, env = "VELOREN_REPLAY")]
+    pub replay: Option<PathBuf>,
+
     #[clap(subcommand)]
     pub command: Option<Commands>,
 }
 
+impl Args {
+    /// Applies `--record` and `--replay` to the arguments used to connect.
+    pub fn session_connection_args(&self, args: ConnectionArgs) -> ConnectionArgs {
+        if let Some(path) = &self.replay {
+            ConnectionArgs::Replay(path.clone())
+        } else if let Some(path) = &self.record {
+            ConnectionArgs::Recorded {
+                args: Box::new(args),
+                path: path.clone(),
+            }
+        } else {
+            args
+        }
+    }
+}
+
 #[derive(Subcommand, Clone)]
 pub enum Commands {
     /// List available wgpu backends. This is called by Airshipper to show a
@@ -828,6 +828,18 @@ impl Widget for Group<'_> {
                         "name" => &name,
                     },
                 ),
+                InviteKind::Guild => self.localized_strings.get_msg_ctx(
+                    "hud-group-invite_to_guild",
+                    &i18n::fluent_args! {
+                        "name" => &name,
+                    },
+                ),
+                InviteKind::Duel => self.localized_strings.get_msg_ctx(
+                    "hud-group-invite_to_duel",
+                    &i18n::fluent_args! {
+                        "name" => &name,
+                    },
+                ),
             };
             Text::new(&invite_text)
                 .mid_top_with_margin_on(state.ids.bg, 5.0)
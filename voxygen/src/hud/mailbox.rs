@@ -0,0 +1,738 @@
+use conrod_core::{
+    Color, Colorable, Labelable, Positionable, Sizeable, UiCell, Widget, WidgetCommon, color,
+    position::Relative,
+    widget::{self, Button, Image, Rectangle, State as ConrodState, Text, TextEdit},
+    widget_ids,
+};
+use vek::*;
+
+use client::Client;
+use common::{
+    comp::{
+        Inventory,
+        inventory::item::{ItemDesc, ItemI18n, MaterialStatManifest, Quality},
+        mail::{
+            MAX_ATTACHMENTS, MAX_BODY_LEN, MAX_SUBJECT_LEN, Mail, MailDraft, MailId, NEVER_EXPIRES,
+        },
+        slot::InvSlotId,
+    },
+    recipe::RecipeBookManifest,
+};
+use i18n::Localization;
+
+use crate::{
+    GlobalState,
+    ui::{
+        ImageFrame, ItemTooltip, ItemTooltipManager, ItemTooltipable,
+        fonts::Fonts,
+        slot::{ContentSize, SlotMaker},
+    },
+};
+
+use super::{
+    HudInfo, TEXT_COLOR, TEXT_GRAY_COLOR, UI_HIGHLIGHT_0, UI_MAIN,
+    img_ids::{Imgs, ImgsRot},
+    item_imgs::{ItemImgs, animate_by_pulse},
+    slots::{MailSlot, SlotManager},
+};
+
+pub enum Event {
+    Close,
+    Send(MailDraft),
+    MarkRead(MailId),
+    TakeAttachments(MailId),
+    Return(MailId),
+    Delete(MailId),
+}
+
+/// What the mailbox window shows, kept while the window is open
+#[derive(Default)]
+pub struct MailboxShow {
+    pub compose: bool,
+    pub selected: Option<MailId>,
+    pub recipient: String,
+    pub subject: String,
+    pub body: String,
+    pub coins: String,
+    /// Inventory slots of the items to attach, filled by dragging items onto
+    /// the attachment slots
+    pub attachments: [Option<InvSlotId>; MAX_ATTACHMENTS],
+}
+
+impl MailboxShow {
+    /// Attaches the item in the inventory slot, unless it already is
+    pub fn attach(&mut self, index: usize, slot: InvSlotId) {
+        if !self.attachments.contains(&Some(slot))
+            && let Some(attachment) = self.attachments.get_mut(index)
+        {
+            *attachment = Some(slot);
+        }
+    }
+
+    pub fn detach(&mut self, index: usize) {
+        if let Some(attachment) = self.attachments.get_mut(index) {
+            *attachment = None;
+        }
+    }
+
+    fn draft(&self) -> MailDraft {
+        MailDraft {
+            recipient: self.recipient.trim().to_owned(),
+            subject: self.subject.clone(),
+            body: self.body.clone(),
+            coins: self.coins.trim().parse().unwrap_or(0),
+            attachments: self.attachments.iter().flatten().copied().collect(),
+        }
+    }
+}
+
+pub struct State {
+    ids: Ids,
+}
+
+widget_ids! {
+    pub struct Ids {
+        mailbox_close,
+        bg,
+        bg_frame,
+        mailbox_title_bg,
+        mailbox_title,
+        inbox_tab,
+        compose_tab,
+        // Inbox
+        list_align,
+        list_entries[],
+        list_empty,
+        mail_sender,
+        mail_subject,
+        mail_expiry,
+        mail_body,
+        mail_coins,
+        mail_attachment_bgs[],
+        mail_attachments[],
+        take_button,
+        return_button,
+        delete_button,
+        // Compose
+        recipient_label,
+        recipient_bg,
+        recipient_input,
+        subject_label,
+        subject_bg,
+        subject_input,
+        body_label,
+        body_bg,
+        body_input,
+        coins_label,
+        coins_bg,
+        coins_input,
+        attachments_label,
+        attachment_slots[],
+        send_button,
+    }
+}
+
+/// The window of the mailbox while it is open
+#[derive(WidgetCommon)]
+pub struct Mailbox<'a> {
+    client: &'a Client,
+    global_state: &'a GlobalState,
+    info: &'a HudInfo<'a>,
+    imgs: &'a Imgs,
+    item_imgs: &'a ItemImgs,
+    fonts: &'a Fonts,
+    rot_imgs: &'a ImgsRot,
+    item_tooltip_manager: &'a mut ItemTooltipManager,
+    #[conrod(common_builder)]
+    common: widget::CommonBuilder,
+    slot_manager: &'a mut SlotManager,
+    localized_strings: &'a Localization,
+    item_i18n: &'a ItemI18n,
+    msm: &'a MaterialStatManifest,
+    rbm: &'a RecipeBookManifest,
+    pulse: f32,
+    show: &'a mut MailboxShow,
+}
+
+impl<'a> Mailbox<'a> {
+    #[expect(clippy::too_many_arguments)]
+    pub fn new(
+        client: &'a Client,
+        global_state: &'a GlobalState,
+        info: &'a HudInfo,
+        imgs: &'a Imgs,
+        item_imgs: &'a ItemImgs,
+        fonts: &'a Fonts,
+        rot_imgs: &'a ImgsRot,
+        item_tooltip_manager: &'a mut ItemTooltipManager,
+        slot_manager: &'a mut SlotManager,
+        localized_strings: &'a Localization,
+        item_i18n: &'a ItemI18n,
+        msm: &'a MaterialStatManifest,
+        rbm: &'a RecipeBookManifest,
+        pulse: f32,
+        show: &'a mut MailboxShow,
+    ) -> Self {
+        Self {
+            client,
+            global_state,
+            info,
+            imgs,
+            item_imgs,
+            fonts,
+            rot_imgs,
+            item_tooltip_manager,
+            common: widget::CommonBuilder::default(),
+            slot_manager,
+            localized_strings,
+            item_i18n,
+            msm,
+            rbm,
+            pulse,
+            show,
+        }
+    }
+}
+
+const WINDOW_SIZE: [f64; 2] = [440.0, 400.0];
+const LIST_WIDTH: f64 = 170.0;
+const DETAILS_WIDTH: f64 = 210.0;
+const SLOT_SIZE: f64 = 34.0;
+
+fn quality_img(imgs: &Imgs, quality: Quality) -> conrod_core::image::Id {
+    match quality {
+        Quality::Low => imgs.inv_slot_grey,
+        Quality::Common => imgs.inv_slot_common,
+        Quality::Moderate => imgs.inv_slot_green,
+        Quality::High => imgs.inv_slot_blue,
+        Quality::Epic => imgs.inv_slot_purple,
+        Quality::Legendary => imgs.inv_slot_gold,
+        Quality::Artifact => imgs.inv_slot_orange,
+        _ => imgs.inv_slot_red,
+    }
+}
+
+impl Mailbox<'_> {
+    fn background(&mut self, state: &mut ConrodState<'_, State>, ui: &mut UiCell<'_>) {
+        Image::new(self.imgs.inv_middle_bg_bag)
+            .wh(WINDOW_SIZE)
+            .color(Some(UI_MAIN))
+            .mid_bottom_with_margin_on(ui.window, 295.0)
+            .set(state.ids.bg, ui);
+        Image::new(self.imgs.inv_middle_frame)
+            .wh(WINDOW_SIZE)
+            .middle_of(state.ids.bg)
+            .color(Some(UI_HIGHLIGHT_0))
+            .set(state.ids.bg_frame, ui);
+    }
+
+    fn title(&mut self, state: &mut ConrodState<'_, State>, ui: &mut UiCell<'_>) {
+        Text::new(&self.localized_strings.get_msg("hud-mail-mailbox"))
+            .mid_top_with_margin_on(state.ids.bg_frame, 9.0)
+            .font_id(self.fonts.cyri.conrod_id)
+            .font_size(self.fonts.cyri.scale(20))
+            .color(Color::Rgba(0.0, 0.0, 0.0, 1.0))
+            .set(state.ids.mailbox_title_bg, ui);
+        Text::new(&self.localized_strings.get_msg("hud-mail-mailbox"))
+            .top_left_with_margins_on(state.ids.mailbox_title_bg, 2.0, 2.0)
+            .font_id(self.fonts.cyri.conrod_id)
+            .font_size(self.fonts.cyri.scale(20))
+            .color(TEXT_COLOR)
+            .set(state.ids.mailbox_title, ui);
+    }
+
+    fn button<'b>(&self, label: &'b str) -> Button<'b, widget::button::Image> {
+        Button::image(self.imgs.button)
+            .w_h(31.0 * 4.0, 12.0 * 2.0)
+            .hover_image(self.imgs.button_hover)
+            .press_image(self.imgs.button_press)
+            .label(label)
+            .label_font_size(self.fonts.cyri.scale(14))
+            .label_color(TEXT_COLOR)
+            .label_font_id(self.fonts.cyri.conrod_id)
+            .label_y(Relative::Scalar(2.0))
+    }
+
+    fn tabs(&mut self, state: &mut ConrodState<'_, State>, ui: &mut UiCell<'_>) {
+        let inbox = self.localized_strings.get_msg("hud-mail-inbox");
+        if self
+            .button(&inbox)
+            .image_color(if self.show.compose {
+                TEXT_GRAY_COLOR
+            } else {
+                TEXT_COLOR
+            })
+            .top_left_with_margins_on(state.ids.bg, 42.0, 20.0)
+            .set(state.ids.inbox_tab, ui)
+            .was_clicked()
+        {
+            self.show.compose = false;
+        }
+        let compose = self.localized_strings.get_msg("hud-mail-compose");
+        if self
+            .button(&compose)
+            .image_color(if self.show.compose {
+                TEXT_COLOR
+            } else {
+                TEXT_GRAY_COLOR
+            })
+            .right_from(state.ids.inbox_tab, 10.0)
+            .set(state.ids.compose_tab, ui)
+            .was_clicked()
+        {
+            self.show.compose = true;
+        }
+    }
+
+    fn inbox(
+        &mut self,
+        state: &mut ConrodState<'_, State>,
+        ui: &mut UiCell<'_>,
+        mailbox: &[Mail],
+    ) -> Option<Event> {
+        let mut event = None;
+
+        Rectangle::fill_with([LIST_WIDTH, 300.0], color::TRANSPARENT)
+            .top_left_with_margins_on(state.ids.bg, 80.0, 20.0)
+            .scroll_kids_vertically()
+            .set(state.ids.list_align, ui);
+
+        if mailbox.is_empty() {
+            Text::new(&self.localized_strings.get_msg("hud-mail-empty"))
+                .mid_top_with_margin_on(state.ids.list_align, 10.0)
+                .font_id(self.fonts.cyri.conrod_id)
+                .font_size(self.fonts.cyri.scale(14))
+                .color(TEXT_GRAY_COLOR)
+                .set(state.ids.list_empty, ui);
+        }
+
+        if state.ids.list_entries.len() < mailbox.len() {
+            state.update(|s| {
+                s.ids
+                    .list_entries
+                    .resize(mailbox.len(), &mut ui.widget_id_generator());
+            });
+        }
+        for (i, mail) in mailbox.iter().enumerate() {
+            let selected = self.show.selected == Some(mail.id);
+            let subject = if mail.subject.as_plain().is_some_and(str::is_empty) {
+                self.localized_strings
+                    .get_msg("hud-mail-no_subject")
+                    .into_owned()
+            } else {
+                self.localized_strings.get_content(&mail.subject)
+            };
+            let label = if subject.chars().count() > 20 {
+                format!("{}...", subject.chars().take(17).collect::<String>())
+            } else {
+                subject
+            };
+            let button = Button::image(if selected {
+                self.imgs.selection
+            } else {
+                self.imgs.nothing
+            })
+            .hover_image(if selected {
+                self.imgs.selection
+            } else {
+                self.imgs.selection_hover
+            })
+            .press_image(self.imgs.selection_press)
+            .w_h(LIST_WIDTH, 20.0)
+            .image_color(color::rgba(1.0, 0.82, 0.27, 1.0))
+            .label(&label)
+            .label_font_size(self.fonts.cyri.scale(14))
+            .label_font_id(self.fonts.cyri.conrod_id)
+            .label_color(if mail.read {
+                TEXT_GRAY_COLOR
+            } else {
+                TEXT_COLOR
+            });
+            let button = if i == 0 {
+                button.mid_top_of(state.ids.list_align)
+            } else {
+                button.down_from(state.ids.list_entries[i - 1], 1.0)
+            };
+            if button.set(state.ids.list_entries[i], ui).was_clicked() {
+                self.show.selected = Some(mail.id);
+                if !mail.read {
+                    event = Some(Event::MarkRead(mail.id));
+                }
+            }
+        }
+
+        if let Some(mail) = mailbox
+            .iter()
+            .find(|mail| self.show.selected == Some(mail.id))
+        {
+            event = self.details(state, ui, mail).or(event);
+        }
+        event
+    }
+
+    fn details(
+        &mut self,
+        state: &mut ConrodState<'_, State>,
+        ui: &mut UiCell<'_>,
+        mail: &Mail,
+    ) -> Option<Event> {
+        let text = |text: &str| {
+            Text::new(text)
+                .font_id(self.fonts.cyri.conrod_id)
+                .font_size(self.fonts.cyri.scale(14))
+                .color(TEXT_COLOR)
+                .w(DETAILS_WIDTH)
+        };
+
+        let sender = self.localized_strings.get_msg_ctx(
+            if mail.returned {
+                "hud-mail-returned_by"
+            } else {
+                "hud-mail-from"
+            },
+            &i18n::fluent_args! { "sender" => mail.sender.clone() },
+        );
+        text(&sender)
+            .top_left_with_margins_on(state.ids.bg, 80.0, 40.0 + LIST_WIDTH)
+            .set(state.ids.mail_sender, ui);
+        text(&self.localized_strings.get_content(&mail.subject))
+            .down_from(state.ids.mail_sender, 4.0)
+            .set(state.ids.mail_subject, ui);
+        let expiry = if mail.expires_at == NEVER_EXPIRES {
+            self.localized_strings.get_msg("hud-mail-kept")
+        } else {
+            let days = (mail.expires_at - chrono::Utc::now().timestamp()).max(0) / (24 * 60 * 60);
+            self.localized_strings
+                .get_msg_ctx("hud-mail-expires", &i18n::fluent_args! { "days" => days })
+        };
+        text(&expiry)
+            .color(TEXT_GRAY_COLOR)
+            .font_size(self.fonts.cyri.scale(12))
+            .down_from(state.ids.mail_subject, 4.0)
+            .set(state.ids.mail_expiry, ui);
+        text(&self.localized_strings.get_content(&mail.body))
+            .h(130.0)
+            .wrap_by_word()
+            .down_from(state.ids.mail_expiry, 8.0)
+            .set(state.ids.mail_body, ui);
+
+        if mail.coins > 0 {
+            text(&self.localized_strings.get_msg_ctx(
+                "hud-mail-coins",
+                &i18n::fluent_args! { "amount" => mail.coins },
+            ))
+            .down_from(state.ids.mail_body, 8.0)
+            .set(state.ids.mail_coins, ui);
+        }
+
+        let item_tooltip = ItemTooltip::new(
+            {
+                // Edge images [t, b, r, l]
+                // Corner images [tr, tl, br, bl]
+                let edge = &self.rot_imgs.tt_side;
+                let corner = &self.rot_imgs.tt_corner;
+                ImageFrame::new(
+                    [edge.cw180, edge.none, edge.cw270, edge.cw90],
+                    [corner.none, corner.cw270, corner.cw90, corner.cw180],
+                    Color::Rgba(0.08, 0.07, 0.04, 1.0),
+                    5.0,
+                )
+            },
+            self.client,
+            self.info,
+            self.imgs,
+            self.item_imgs,
+            self.pulse,
+            self.msm,
+            self.rbm,
+            self.client.inventories().get(self.client.entity()),
+            self.localized_strings,
+            self.item_i18n,
+        )
+        .title_font_size(self.fonts.cyri.scale(20))
+        .parent(ui.window)
+        .desc_font_size(self.fonts.cyri.scale(12))
+        .font_id(self.fonts.cyri.conrod_id)
+        .desc_text_color(TEXT_COLOR);
+
+        if state.ids.mail_attachments.len() < mail.attachments.len() {
+            state.update(|s| {
+                s.ids
+                    .mail_attachment_bgs
+                    .resize(mail.attachments.len(), &mut ui.widget_id_generator());
+                s.ids
+                    .mail_attachments
+                    .resize(mail.attachments.len(), &mut ui.widget_id_generator());
+            });
+        }
+        for (i, item) in mail.attachments.iter().enumerate() {
+            let bg = Image::new(quality_img(self.imgs, item.quality())).w_h(SLOT_SIZE, SLOT_SIZE);
+            let bg = if i == 0 {
+                bg.top_left_with_margins_on(state.ids.bg, 290.0, 40.0 + LIST_WIDTH)
+            } else {
+                bg.right_from(state.ids.mail_attachment_bgs[i - 1], 2.0)
+            };
+            bg.set(state.ids.mail_attachment_bgs[i], ui);
+            Image::new(animate_by_pulse(
+                &self.item_imgs.img_ids_or_not_found_img(item.into()),
+                self.pulse,
+            ))
+            .w_h(SLOT_SIZE * 0.75, SLOT_SIZE * 0.75)
+            .middle_of(state.ids.mail_attachment_bgs[i])
+            .with_item_tooltip(
+                self.item_tooltip_manager,
+                core::iter::once(item as &dyn ItemDesc),
+                &None,
+                &item_tooltip,
+            )
+            .set(state.ids.mail_attachments[i], ui);
+        }
+
+        let mut event = None;
+        let take = self.localized_strings.get_msg("hud-mail-take");
+        let return_ = self.localized_strings.get_msg("hud-mail-return");
+        let delete = self.localized_strings.get_msg("hud-mail-delete");
+        if mail.has_attachments() {
+            if self
+                .button(&take)
+                .bottom_left_with_margins_on(state.ids.bg, 20.0, 40.0 + LIST_WIDTH)
+                .set(state.ids.take_button, ui)
+                .was_clicked()
+            {
+                event = Some(Event::TakeAttachments(mail.id));
+            }
+            if !mail.returned
+                && self
+                    .button(&return_)
+                    .w(31.0 * 2.5)
+                    .right_from(state.ids.take_button, 6.0)
+                    .set(state.ids.return_button, ui)
+                    .was_clicked()
+            {
+                event = Some(Event::Return(mail.id));
+            }
+        } else if self
+            .button(&delete)
+            .bottom_left_with_margins_on(state.ids.bg, 20.0, 40.0 + LIST_WIDTH)
+            .set(state.ids.delete_button, ui)
+            .was_clicked()
+        {
+            self.show.selected = None;
+            event = Some(Event::Delete(mail.id));
+        }
+        event
+    }
+
+    /// A text input with a label above it
+    fn input(
+        &mut self,
+        ui: &mut UiCell<'_>,
+        [label_id, bg_id, input_id]: [widget::Id; 3],
+        label: &str,
+        above: widget::Id,
+        height: f64,
+        value: &str,
+    ) -> Option<String> {
+        Text::new(label)
+            .down_from(above, 8.0)
+            .align_left_of(above)
+            .font_id(self.fonts.cyri.conrod_id)
+            .font_size(self.fonts.cyri.scale(14))
+            .color(TEXT_COLOR)
+            .set(label_id, ui);
+        Rectangle::fill_with(
+            [WINDOW_SIZE[0] - 40.0, height],
+            color::rgba(0.0, 0.0, 0.0, 0.5),
+        )
+        .down_from(label_id, 4.0)
+        .align_left_of(label_id)
+        .set(bg_id, ui);
+        TextEdit::new(value)
+            .top_left_with_margins_on(bg_id, 2.0, 4.0)
+            .w_h(WINDOW_SIZE[0] - 48.0, height - 4.0)
+            .font_id(self.fonts.cyri.conrod_id)
+            .font_size(self.fonts.cyri.scale(14))
+            .color(TEXT_COLOR)
+            .set(input_id, ui)
+    }
+
+    fn compose(
+        &mut self,
+        state: &mut ConrodState<'_, State>,
+        ui: &mut UiCell<'_>,
+    ) -> Option<Event> {
+        let ids = &state.ids;
+        let inputs = [
+            (
+                [ids.recipient_label, ids.recipient_bg, ids.recipient_input],
+                "hud-mail-recipient",
+                ids.inbox_tab,
+                22.0,
+            ),
+            (
+                [ids.subject_label, ids.subject_bg, ids.subject_input],
+                "hud-mail-subject",
+                ids.recipient_bg,
+                22.0,
+            ),
+            (
+                [ids.body_label, ids.body_bg, ids.body_input],
+                "hud-mail-body",
+                ids.subject_bg,
+                100.0,
+            ),
+            (
+                [ids.coins_label, ids.coins_bg, ids.coins_input],
+                "hud-mail-coins_input",
+                ids.body_bg,
+                22.0,
+            ),
+        ];
+        for (i, (ids, key, above, height)) in inputs.into_iter().enumerate() {
+            let label = self.localized_strings.get_msg(key);
+            let value = match i {
+                0 => &self.show.recipient,
+                1 => &self.show.subject,
+                2 => &self.show.body,
+                _ => &self.show.coins,
+            }
+            .clone();
+            if let Some(new_value) = self.input(ui, ids, &label, above, height, &value) {
+                match i {
+                    0 => self.show.recipient = new_value,
+                    1 => self.show.subject = new_value.chars().take(MAX_SUBJECT_LEN).collect(),
+                    2 => self.show.body = new_value.chars().take(MAX_BODY_LEN).collect(),
+                    _ => {
+                        if new_value.trim().chars().all(|c| c.is_ascii_digit()) {
+                            self.show.coins = new_value.trim().to_owned();
+                        }
+                    },
+                }
+            }
+        }
+
+        Text::new(&self.localized_strings.get_msg("hud-mail-attachments"))
+            .down_from(state.ids.coins_bg, 8.0)
+            .align_left_of(state.ids.coins_bg)
+            .font_id(self.fonts.cyri.conrod_id)
+            .font_size(self.fonts.cyri.scale(14))
+            .color(TEXT_COLOR)
+            .set(state.ids.attachments_label, ui);
+
+        if state.ids.attachment_slots.len() < MAX_ATTACHMENTS {
+            state.update(|s| {
+                s.ids
+                    .attachment_slots
+                    .resize(MAX_ATTACHMENTS, &mut ui.widget_id_generator());
+            });
+        }
+        let inventories = self.client.inventories();
+        let empty = Inventory::with_empty();
+        let inventory = inventories.get(self.client.entity()).unwrap_or(&empty);
+        let mut slot_maker = SlotMaker {
+            empty_slot: self.imgs.inv_slot,
+            hovered_slot: self.imgs.skillbar_index,
+            filled_slot: self.imgs.inv_slot,
+            selected_slot: self.imgs.inv_slot_sel,
+            background_color: Some(UI_MAIN),
+            content_size: ContentSize {
+                width_height_ratio: 1.0,
+                max_fraction: 0.75,
+            },
+            selected_content_scale: 1.067,
+            amount_font: self.fonts.cyri.conrod_id,
+            amount_margins: Vec2::new(-4.0, 0.0),
+            amount_font_size: self.fonts.cyri.scale(12),
+            amount_text_color: TEXT_COLOR,
+            content_source: inventory,
+            image_source: self.item_imgs,
+            slot_manager: Some(self.slot_manager),
+            last_input: &self.global_state.window.last_input(),
+            pulse: self.pulse,
+        };
+        for (index, slot) in self.show.attachments.iter().enumerate() {
+            let slot_widget = slot_maker
+                .fabricate(
+                    MailSlot { index, slot: *slot },
+                    [SLOT_SIZE as f32; 2],
+                    false,
+                    false,
+                )
+                .down_from(state.ids.attachments_label, 4.0)
+                .x_relative_to(
+                    state.ids.attachments_label,
+                    index as f64 * (SLOT_SIZE + 2.0),
+                );
+            match slot.and_then(|slot| inventory.get(slot)) {
+                Some(item) => slot_widget
+                    .filled_slot(quality_img(self.imgs, item.quality()))
+                    .set(state.ids.attachment_slots[index], ui),
+                None => slot_widget.set(state.ids.attachment_slots[index], ui),
+            };
+        }
+
+        let send = self.localized_strings.get_msg("hud-mail-send");
+        if self
+            .button(&send)
+            .bottom_right_with_margins_on(state.ids.bg, 20.0, 20.0)
+            .set(state.ids.send_button, ui)
+            .was_clicked()
+            && !self.show.recipient.trim().is_empty()
+        {
+            let draft = self.show.draft();
+            *self.show = MailboxShow::default();
+            self.show.compose = true;
+            return Some(Event::Send(draft));
+        }
+        None
+    }
+
+    fn close_button(
+        &mut self,
+        state: &mut ConrodState<'_, State>,
+        ui: &mut UiCell<'_>,
+    ) -> Option<Event> {
+        Button::image(self.imgs.close_btn)
+            .w_h(24.0, 25.0)
+            .hover_image(self.imgs.close_btn_hover)
+            .press_image(self.imgs.close_btn_press)
+            .top_right_with_margins_on(state.ids.bg, 0.0, 0.0)
+            .set(state.ids.mailbox_close, ui)
+            .was_clicked()
+            .then_some(Event::Close)
+    }
+}
+
+impl Widget for Mailbox<'_> {
+    type Event = Option<Event>;
+    type State = State;
+    type Style = ();
+
+    fn init_state(&self, id_gen: widget::id::Generator) -> Self::State {
+        State {
+            ids: Ids::new(id_gen),
+        }
+    }
+
+    fn style(&self) -> Self::Style {}
+
+    fn update(mut self, args: widget::UpdateArgs<Self>) -> Self::Event {
+        common_base::prof_span!("Mailbox::update");
+        let widget::UpdateArgs { state, ui, .. } = args;
+
+        let client = self.client;
+        let Some((_, mailbox)) = client.mailbox() else {
+            return Some(Event::Close);
+        };
+
+        self.background(state, ui);
+        self.title(state, ui);
+        self.tabs(state, ui);
+        let event = if self.show.compose {
+            self.compose(state, ui)
+        } else {
+            self.inbox(state, ui, mailbox)
+        };
+        self.close_button(state, ui).or(event)
+    }
+}
@@ -0,0 +1,670 @@
+use conrod_core::{
+    Color, Colorable, Labelable, Positionable, Sizeable, UiCell, Widget, WidgetCommon, color,
+    position::Relative,
+    widget::{self, Button, Image, Rectangle, State as ConrodState, Text, TextEdit},
+    widget_ids,
+};
+use vek::*;
+
+use client::Client;
+use common::{
+    comp::{
+        Inventory,
+        inventory::item::{ItemDesc, ItemI18n, MaterialStatManifest, Quality},
+        market::{Listing, ListingId, MAX_PRICE, Market as MarketView, listing_fee, sale_proceeds},
+        slot::InvSlotId,
+    },
+    recipe::RecipeBookManifest,
+};
+use i18n::Localization;
+
+use crate::{
+    GlobalState,
+    ui::{
+        ImageFrame, ItemTooltip, ItemTooltipManager, ItemTooltipable,
+        fonts::Fonts,
+        slot::{ContentSize, SlotMaker},
+    },
+};
+
+use super::{
+    HudInfo, TEXT_COLOR, TEXT_GRAY_COLOR, UI_HIGHLIGHT_0, UI_MAIN,
+    img_ids::{Imgs, ImgsRot},
+    item_imgs::{ItemImgs, animate_by_pulse},
+    slots::{MarketSlot, SlotManager},
+};
+
+pub enum Event {
+    Close,
+    Post { slot: InvSlotId, price: u32 },
+    Buy(ListingId),
+    Cancel(ListingId),
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MarketTab {
+    #[default]
+    Browse,
+    Own,
+    Sell,
+}
+
+/// What the market window shows, kept while the window is open
+#[derive(Default)]
+pub struct MarketShow {
+    pub tab: MarketTab,
+    pub search: String,
+    pub selected: Option<ListingId>,
+    /// Inventory slot of the item to list, filled by dragging an item onto the
+    /// slot of the sell tab
+    pub item: Option<InvSlotId>,
+    pub price: String,
+    /// Whether the price should be replaced by the suggested price of the item
+    prefill_price: bool,
+}
+
+impl MarketShow {
+    /// Selects the item in the inventory slot to be listed, its price is
+    /// suggested from the prices of the site
+    pub fn select(&mut self, slot: InvSlotId) {
+        self.item = Some(slot);
+        self.prefill_price = true;
+    }
+}
+
+pub struct State {
+    ids: Ids,
+}
+
+widget_ids! {
+    pub struct Ids {
+        market_close,
+        bg,
+        bg_frame,
+        market_title_bg,
+        market_title,
+        browse_tab,
+        own_tab,
+        sell_tab,
+        // Listings
+        search_bg,
+        search_input,
+        list_align,
+        list_entries[],
+        list_empty,
+        listing_item_bg,
+        listing_item,
+        listing_name,
+        listing_seller,
+        listing_price,
+        listing_expiry,
+        buy_button,
+        cancel_button,
+        // Sell
+        item_label,
+        item_slot,
+        item_name,
+        price_label,
+        price_bg,
+        price_input,
+        suggested_price,
+        fees,
+        post_button,
+    }
+}
+
+/// The window of the market while it is open
+#[derive(WidgetCommon)]
+pub struct Market<'a> {
+    client: &'a Client,
+    global_state: &'a GlobalState,
+    info: &'a HudInfo<'a>,
+    imgs: &'a Imgs,
+    item_imgs: &'a ItemImgs,
+    fonts: &'a Fonts,
+    rot_imgs: &'a ImgsRot,
+    item_tooltip_manager: &'a mut ItemTooltipManager,
+    #[conrod(common_builder)]
+    common: widget::CommonBuilder,
+    slot_manager: &'a mut SlotManager,
+    localized_strings: &'a Localization,
+    item_i18n: &'a ItemI18n,
+    msm: &'a MaterialStatManifest,
+    rbm: &'a RecipeBookManifest,
+    pulse: f32,
+    show: &'a mut MarketShow,
+}
+
+impl<'a> Market<'a> {
+    #[expect(clippy::too_many_arguments)]
+    pub fn new(
+        client: &'a Client,
+        global_state: &'a GlobalState,
+        info: &'a HudInfo,
+        imgs: &'a Imgs,
+        item_imgs: &'a ItemImgs,
+        fonts: &'a Fonts,
+        rot_imgs: &'a ImgsRot,
+        item_tooltip_manager: &'a mut ItemTooltipManager,
+        slot_manager: &'a mut SlotManager,
+        localized_strings: &'a Localization,
+        item_i18n: &'a ItemI18n,
+        msm: &'a MaterialStatManifest,
+        rbm: &'a RecipeBookManifest,
+        pulse: f32,
+        show: &'a mut MarketShow,
+    ) -> Self {
+        Self {
+            client,
+            global_state,
+            info,
+            imgs,
+            item_imgs,
+            fonts,
+            rot_imgs,
+            item_tooltip_manager,
+            common: widget::CommonBuilder::default(),
+            slot_manager,
+            localized_strings,
+            item_i18n,
+            msm,
+            rbm,
+            pulse,
+            show,
+        }
+    }
+}
+
+const WINDOW_SIZE: [f64; 2] = [440.0, 400.0];
+const LIST_WIDTH: f64 = 170.0;
+const DETAILS_WIDTH: f64 = 210.0;
+const SLOT_SIZE: f64 = 34.0;
+
+fn quality_img(imgs: &Imgs, quality: Quality) -> conrod_core::image::Id {
+    match quality {
+        Quality::Low => imgs.inv_slot_grey,
+        Quality::Common => imgs.inv_slot_common,
+        Quality::Moderate => imgs.inv_slot_green,
+        Quality::High => imgs.inv_slot_blue,
+        Quality::Epic => imgs.inv_slot_purple,
+        Quality::Legendary => imgs.inv_slot_gold,
+        Quality::Artifact => imgs.inv_slot_orange,
+        _ => imgs.inv_slot_red,
+    }
+}
+
+impl Market<'_> {
+    fn background(&mut self, state: &mut ConrodState<'_, State>, ui: &mut UiCell<'_>) {
+        Image::new(self.imgs.inv_middle_bg_bag)
+            .wh(WINDOW_SIZE)
+            .color(Some(UI_MAIN))
+            .mid_bottom_with_margin_on(ui.window, 295.0)
+            .set(state.ids.bg, ui);
+        Image::new(self.imgs.inv_middle_frame)
+            .wh(WINDOW_SIZE)
+            .middle_of(state.ids.bg)
+            .color(Some(UI_HIGHLIGHT_0))
+            .set(state.ids.bg_frame, ui);
+    }
+
+    fn title(&mut self, state: &mut ConrodState<'_, State>, ui: &mut UiCell<'_>) {
+        Text::new(&self.localized_strings.get_msg("hud-market-market"))
+            .mid_top_with_margin_on(state.ids.bg_frame, 9.0)
+            .font_id(self.fonts.cyri.conrod_id)
+            .font_size(self.fonts.cyri.scale(20))
+            .color(Color::Rgba(0.0, 0.0, 0.0, 1.0))
+            .set(state.ids.market_title_bg, ui);
+        Text::new(&self.localized_strings.get_msg("hud-market-market"))
+            .top_left_with_margins_on(state.ids.market_title_bg, 2.0, 2.0)
+            .font_id(self.fonts.cyri.conrod_id)
+            .font_size(self.fonts.cyri.scale(20))
+            .color(TEXT_COLOR)
+            .set(state.ids.market_title, ui);
+    }
+
+    fn button<'b>(&self, label: &'b str) -> Button<'b, widget::button::Image> {
+        Button::image(self.imgs.button)
+            .w_h(31.0 * 4.0, 12.0 * 2.0)
+            .hover_image(self.imgs.button_hover)
+            .press_image(self.imgs.button_press)
+            .label(label)
+            .label_font_size(self.fonts.cyri.scale(14))
+            .label_color(TEXT_COLOR)
+            .label_font_id(self.fonts.cyri.conrod_id)
+            .label_y(Relative::Scalar(2.0))
+    }
+
+    fn text<'b>(&self, text: &'b str) -> Text<'b> {
+        Text::new(text)
+            .font_id(self.fonts.cyri.conrod_id)
+            .font_size(self.fonts.cyri.scale(14))
+            .color(TEXT_COLOR)
+    }
+
+    fn tabs(&mut self, state: &mut ConrodState<'_, State>, ui: &mut UiCell<'_>) {
+        let tabs = [
+            (MarketTab::Browse, "hud-market-browse", state.ids.browse_tab),
+            (MarketTab::Own, "hud-market-own", state.ids.own_tab),
+            (MarketTab::Sell, "hud-market-sell", state.ids.sell_tab),
+        ];
+        for (i, (tab, key, id)) in tabs.into_iter().enumerate() {
+            let label = self.localized_strings.get_msg(key);
+            let button = self.button(&label).image_color(if self.show.tab == tab {
+                TEXT_COLOR
+            } else {
+                TEXT_GRAY_COLOR
+            });
+            let button = if i == 0 {
+                button.top_left_with_margins_on(state.ids.bg, 42.0, 20.0)
+            } else {
+                button.right_from(tabs[i - 1].2, 10.0)
+            };
+            if button.set(id, ui).was_clicked() && self.show.tab != tab {
+                self.show.tab = tab;
+                self.show.selected = None;
+            }
+        }
+    }
+
+    /// The localized name of the item
+    fn item_name(&self, item: &dyn ItemDesc) -> String {
+        let (name, _) = item.i18n(self.item_i18n);
+        self.localized_strings.get_content(&name)
+    }
+
+    fn listings(
+        &mut self,
+        state: &mut ConrodState<'_, State>,
+        ui: &mut UiCell<'_>,
+        listings: &[Listing],
+        own: bool,
+    ) -> Option<Event> {
+        // Only other characters' listings can be searched, a character can't
+        // have that many listings
+        let list_top = if own {
+            80.0
+        } else {
+            Rectangle::fill_with([LIST_WIDTH, 22.0], color::rgba(0.0, 0.0, 0.0, 0.5))
+                .top_left_with_margins_on(state.ids.bg, 80.0, 20.0)
+                .set(state.ids.search_bg, ui);
+            if let Some(search) = TextEdit::new(&self.show.search)
+                .top_left_with_margins_on(state.ids.search_bg, 2.0, 4.0)
+                .w_h(LIST_WIDTH - 8.0, 18.0)
+                .font_id(self.fonts.cyri.conrod_id)
+                .font_size(self.fonts.cyri.scale(14))
+                .color(TEXT_COLOR)
+                .set(state.ids.search_input, ui)
+            {
+                self.show.search = search;
+            }
+            108.0
+        };
+
+        Rectangle::fill_with([LIST_WIDTH, 380.0 - list_top], color::TRANSPARENT)
+            .top_left_with_margins_on(state.ids.bg, list_top, 20.0)
+            .scroll_kids_vertically()
+            .set(state.ids.list_align, ui);
+
+        let search = self.show.search.trim().to_lowercase();
+        let listings = listings
+            .iter()
+            .map(|listing| (listing, self.item_name(&listing.item)))
+            .filter(|(_, name)| own || name.to_lowercase().contains(&search))
+            .collect::<Vec<_>>();
+
+        if listings.is_empty() {
+            self.text(&self.localized_strings.get_msg("hud-market-empty"))
+                .mid_top_with_margin_on(state.ids.list_align, 10.0)
+                .color(TEXT_GRAY_COLOR)
+                .set(state.ids.list_empty, ui);
+        }
+
+        if state.ids.list_entries.len() < listings.len() {
+            state.update(|s| {
+                s.ids
+                    .list_entries
+                    .resize(listings.len(), &mut ui.widget_id_generator());
+            });
+        }
+        for (i, (listing, name)) in listings.iter().enumerate() {
+            let selected = self.show.selected == Some(listing.id);
+            let label = if name.chars().count() > 20 {
+                format!("{}...", name.chars().take(17).collect::<String>())
+            } else {
+                name.clone()
+            };
+            let button = Button::image(if selected {
+                self.imgs.selection
+            } else {
+                self.imgs.nothing
+            })
+            .hover_image(if selected {
+                self.imgs.selection
+            } else {
+                self.imgs.selection_hover
+            })
+            .press_image(self.imgs.selection_press)
+            .w_h(LIST_WIDTH, 20.0)
+            .image_color(color::rgba(1.0, 0.82, 0.27, 1.0))
+            .label(&label)
+            .label_font_size(self.fonts.cyri.scale(14))
+            .label_font_id(self.fonts.cyri.conrod_id)
+            .label_color(TEXT_COLOR);
+            let button = if i == 0 {
+                button.mid_top_of(state.ids.list_align)
+            } else {
+                button.down_from(state.ids.list_entries[i - 1], 1.0)
+            };
+            if button.set(state.ids.list_entries[i], ui).was_clicked() {
+                self.show.selected = Some(listing.id);
+            }
+        }
+
+        let (listing, name) = listings
+            .iter()
+            .find(|(listing, _)| self.show.selected == Some(listing.id))?;
+        self.details(state, ui, listing, name, own)
+    }
+
+    fn details(
+        &mut self,
+        state: &mut ConrodState<'_, State>,
+        ui: &mut UiCell<'_>,
+        listing: &Listing,
+        name: &str,
+        own: bool,
+    ) -> Option<Event> {
+        let item_tooltip = ItemTooltip::new(
+            {
+                // Edge images [t, b, r, l]
+                // Corner images [tr, tl, br, bl]
+                let edge = &self.rot_imgs.tt_side;
+                let corner = &self.rot_imgs.tt_corner;
+                ImageFrame::new(
+                    [edge.cw180, edge.none, edge.cw270, edge.cw90],
+                    [corner.none, corner.cw270, corner.cw90, corner.cw180],
+                    Color::Rgba(0.08, 0.07, 0.04, 1.0),
+                    5.0,
+                )
+            },
+            self.client,
+            self.info,
+            self.imgs,
+            self.item_imgs,
+            self.pulse,
+            self.msm,
+            self.rbm,
+            self.client.inventories().get(self.client.entity()),
+            self.localized_strings,
+            self.item_i18n,
+        )
+        .title_font_size(self.fonts.cyri.scale(20))
+        .parent(ui.window)
+        .desc_font_size(self.fonts.cyri.scale(12))
+        .font_id(self.fonts.cyri.conrod_id)
+        .desc_text_color(TEXT_COLOR);
+
+        Image::new(quality_img(self.imgs, listing.item.quality()))
+            .w_h(SLOT_SIZE, SLOT_SIZE)
+            .top_left_with_margins_on(state.ids.bg, 80.0, 40.0 + LIST_WIDTH)
+            .set(state.ids.listing_item_bg, ui);
+        Image::new(animate_by_pulse(
+            &self
+                .item_imgs
+                .img_ids_or_not_found_img((&listing.item).into()),
+            self.pulse,
+        ))
+        .w_h(SLOT_SIZE * 0.75, SLOT_SIZE * 0.75)
+        .middle_of(state.ids.listing_item_bg)
+        .with_item_tooltip(
+            self.item_tooltip_manager,
+            core::iter::once(&listing.item as &dyn ItemDesc),
+            &None,
+            &item_tooltip,
+        )
+        .set(state.ids.listing_item, ui);
+
+        let amount = listing.item.amount();
+        let name = if amount > 1 {
+            format!("{amount} x {name}")
+        } else {
+            name.to_owned()
+        };
+        self.text(&name)
+            .w(DETAILS_WIDTH - SLOT_SIZE - 6.0)
+            .right_from(state.ids.listing_item_bg, 6.0)
+            .set(state.ids.listing_name, ui);
+        self.text(&self.localized_strings.get_msg_ctx(
+            "hud-market-seller",
+            &i18n::fluent_args! { "seller" => listing.seller.clone() },
+        ))
+        .w(DETAILS_WIDTH)
+        .down_from(state.ids.listing_item_bg, 8.0)
+        .set(state.ids.listing_seller, ui);
+        self.text(&self.localized_strings.get_msg_ctx(
+            "hud-market-price",
+            &i18n::fluent_args! { "price" => listing.price },
+        ))
+        .w(DETAILS_WIDTH)
+        .down_from(state.ids.listing_seller, 4.0)
+        .set(state.ids.listing_price, ui);
+        let days = (listing.expires_at - chrono::Utc::now().timestamp()).max(0) / (24 * 60 * 60);
+        self.text(
+            &self
+                .localized_strings
+                .get_msg_ctx("hud-market-expires", &i18n::fluent_args! { "days" => days }),
+        )
+        .w(DETAILS_WIDTH)
+        .color(TEXT_GRAY_COLOR)
+        .font_size(self.fonts.cyri.scale(12))
+        .down_from(state.ids.listing_price, 4.0)
+        .set(state.ids.listing_expiry, ui);
+
+        if own {
+            let cancel = self.localized_strings.get_msg("hud-market-cancel");
+            self.button(&cancel)
+                .bottom_left_with_margins_on(state.ids.bg, 20.0, 40.0 + LIST_WIDTH)
+                .set(state.ids.cancel_button, ui)
+                .was_clicked()
+                .then(|| {
+                    self.show.selected = None;
+                    Event::Cancel(listing.id)
+                })
+        } else {
+            let buy = self.localized_strings.get_msg("hud-market-buy");
+            self.button(&buy)
+                .bottom_left_with_margins_on(state.ids.bg, 20.0, 40.0 + LIST_WIDTH)
+                .set(state.ids.buy_button, ui)
+                .was_clicked()
+                .then(|| {
+                    self.show.selected = None;
+                    Event::Buy(listing.id)
+                })
+        }
+    }
+
+    fn sell(
+        &mut self,
+        state: &mut ConrodState<'_, State>,
+        ui: &mut UiCell<'_>,
+        market: &MarketView,
+    ) -> Option<Event> {
+        self.text(&self.localized_strings.get_msg("hud-market-item"))
+            .top_left_with_margins_on(state.ids.bg, 80.0, 20.0)
+            .set(state.ids.item_label, ui);
+
+        let inventories = self.client.inventories();
+        let empty = Inventory::with_empty();
+        let inventory = inventories.get(self.client.entity()).unwrap_or(&empty);
+        let item = self.show.item.and_then(|slot| inventory.get(slot));
+        if item.is_none() {
+            self.show.item = None;
+        }
+        let suggested_price = item.and_then(|item| market.prices.as_ref()?.coin_value(item));
+        if self.show.prefill_price {
+            self.show.prefill_price = false;
+            self.show.price = suggested_price
+                .map(|price| price.clamp(1, MAX_PRICE).to_string())
+                .unwrap_or_default();
+        }
+
+        let mut slot_maker = SlotMaker {
+            empty_slot: self.imgs.inv_slot,
+            hovered_slot: self.imgs.skillbar_index,
+            filled_slot: self.imgs.inv_slot,
+            selected_slot: self.imgs.inv_slot_sel,
+            background_color: Some(UI_MAIN),
+            content_size: ContentSize {
+                width_height_ratio: 1.0,
+                max_fraction: 0.75,
+            },
+            selected_content_scale: 1.067,
+            amount_font: self.fonts.cyri.conrod_id,
+            amount_margins: Vec2::new(-4.0, 0.0),
+            amount_font_size: self.fonts.cyri.scale(12),
+            amount_text_color: TEXT_COLOR,
+            content_source: inventory,
+            image_source: self.item_imgs,
+            slot_manager: Some(self.slot_manager),
+            last_input: &self.global_state.window.last_input(),
+            pulse: self.pulse,
+        };
+        let slot_widget = slot_maker
+            .fabricate(
+                MarketSlot {
+                    slot: self.show.item,
+                },
+                [SLOT_SIZE as f32; 2],
+                false,
+                false,
+            )
+            .down_from(state.ids.item_label, 4.0);
+        match item {
+            Some(item) => slot_widget
+                .filled_slot(quality_img(self.imgs, item.quality()))
+                .set(state.ids.item_slot, ui),
+            None => slot_widget.set(state.ids.item_slot, ui),
+        };
+        if let Some(item) = item {
+            self.text(&self.item_name(item))
+                .w(WINDOW_SIZE[0] - 46.0 - SLOT_SIZE)
+                .right_from(state.ids.item_slot, 6.0)
+                .set(state.ids.item_name, ui);
+        }
+
+        self.text(&self.localized_strings.get_msg("hud-market-price_input"))
+            .down_from(state.ids.item_slot, 8.0)
+            .align_left_of(state.ids.item_slot)
+            .set(state.ids.price_label, ui);
+        Rectangle::fill_with([LIST_WIDTH, 22.0], color::rgba(0.0, 0.0, 0.0, 0.5))
+            .down_from(state.ids.price_label, 4.0)
+            .align_left_of(state.ids.price_label)
+            .set(state.ids.price_bg, ui);
+        if let Some(new_price) = TextEdit::new(&self.show.price)
+            .top_left_with_margins_on(state.ids.price_bg, 2.0, 4.0)
+            .w_h(LIST_WIDTH - 8.0, 18.0)
+            .font_id(self.fonts.cyri.conrod_id)
+            .font_size(self.fonts.cyri.scale(14))
+            .color(TEXT_COLOR)
+            .set(state.ids.price_input, ui)
+            && new_price.trim().chars().all(|c| c.is_ascii_digit())
+        {
+            self.show.price = new_price.trim().to_owned();
+        }
+
+        if let Some(suggested_price) = suggested_price {
+            self.text(&self.localized_strings.get_msg_ctx(
+                "hud-market-suggested_price",
+                &i18n::fluent_args! { "price" => suggested_price },
+            ))
+            .color(TEXT_GRAY_COLOR)
+            .font_size(self.fonts.cyri.scale(12))
+            .right_from(state.ids.price_bg, 10.0)
+            .set(state.ids.suggested_price, ui);
+        }
+
+        let price = self
+            .show
+            .price
+            .parse::<u32>()
+            .ok()
+            .filter(|price| (1..=MAX_PRICE).contains(price));
+        if let Some(price) = price {
+            self.text(&self.localized_strings.get_msg_ctx(
+                "hud-market-fees",
+                &i18n::fluent_args! {
+                    "listing_fee" => listing_fee(price),
+                    "proceeds" => sale_proceeds(price),
+                },
+            ))
+            .w(WINDOW_SIZE[0] - 40.0)
+            .wrap_by_word()
+            .down_from(state.ids.price_bg, 8.0)
+            .align_left_of(state.ids.price_bg)
+            .set(state.ids.fees, ui);
+        }
+
+        let post = self.localized_strings.get_msg("hud-market-post");
+        if self
+            .button(&post)
+            .bottom_right_with_margins_on(state.ids.bg, 20.0, 20.0)
+            .set(state.ids.post_button, ui)
+            .was_clicked()
+            && let (Some(slot), Some(price)) = (self.show.item, price)
+        {
+            self.show.item = None;
+            self.show.price.clear();
+            return Some(Event::Post { slot, price });
+        }
+        None
+    }
+
+    fn close_button(
+        &mut self,
+        state: &mut ConrodState<'_, State>,
+        ui: &mut UiCell<'_>,
+    ) -> Option<Event> {
+        Button::image(self.imgs.close_btn)
+            .w_h(24.0, 25.0)
+            .hover_image(self.imgs.close_btn_hover)
+            .press_image(self.imgs.close_btn_press)
+            .top_right_with_margins_on(state.ids.bg, 0.0, 0.0)
+            .set(state.ids.market_close, ui)
+            .was_clicked()
+            .then_some(Event::Close)
+    }
+}
+
+impl Widget for Market<'_> {
+    type Event = Option<Event>;
+    type State = State;
+    type Style = ();
+
+    fn init_state(&self, id_gen: widget::id::Generator) -> Self::State {
+        State {
+            ids: Ids::new(id_gen),
+        }
+    }
+
+    fn style(&self) -> Self::Style {}
+
+    fn update(mut self, args: widget::UpdateArgs<Self>) -> Self::Event {
+        common_base::prof_span!("Market::update");
+        let widget::UpdateArgs { state, ui, .. } = args;
+
+        let client = self.client;
+        let Some((_, market)) = client.market() else {
+            return Some(Event::Close);
+        };
+
+        self.background(state, ui);
+        self.title(state, ui);
+        self.tabs(state, ui);
+        let event = match self.show.tab {
+            MarketTab::Browse => self.listings(state, ui, &market.listings, false),
+            MarketTab::Own => self.listings(state, ui, &market.own_listings, true),
+            MarketTab::Sell => self.sell(state, ui, market),
+        };
+        self.close_button(state, ui).or(event)
+    }
+}
@@ -11,7 +11,9 @@ mod esc_menu;
 mod group;
 mod hotbar;
 mod loot_scroller;
+mod mailbox;
 mod map;
+mod market;
 mod minimap;
 mod overhead;
 mod overitem;
@@ -23,6 +25,7 @@ mod skillbar;
 mod slot_grid;
 mod slots;
 mod social;
+mod storage;
 mod subtitles;
 mod trade;
 
@@ -53,7 +56,9 @@ use group::Group;
 use img_ids::Imgs;
 use item_imgs::ItemImgs;
 use loot_scroller::LootScroller;
+use mailbox::Mailbox;
 use map::Map;
+use market::Market;
 use minimap::{MiniMap, VoxelMinimap};
 use popup::Popup;
 use prompt_dialog::PromptDialog;
@@ -63,6 +68,7 @@ use settings_window::{SettingsTab, SettingsWindow};
 use skillbar::Skillbar;
 use slot_grid::SlotGrid;
 use social::Social;
+use storage::Storage;
 use subtitles::Subtitles;
 use trade::Trade;
 use tutorial::Tutorial;
@@ -111,6 +117,8 @@ use common::{
             tool::ToolKind,
         },
         loot_owner::LootOwnerKind,
+        mail::{MailDraft, MailId},
+        market::ListingId,
         skillset::{SkillGroupKind, SkillsPersistenceError, skills::Skill},
     },
     consts::{MAX_NPCINTERACT_RANGE, MAX_PICKUP_RANGE},
@@ -311,6 +319,9 @@ widget_ids! {
         prompt_dialog,
         bag,
         trade,
+        storage,
+        mailbox,
+        market,
         social,
         quest,
         diary,
@@ -696,6 +707,32 @@ pub enum Event {
     SortInventory(InventorySortOrder),
     ChangeHotbarState(Box<HotbarState>),
     TradeAction(TradeAction),
+    StoreItem {
+        inv_slot: InvSlotId,
+        storage_slot: InvSlotId,
+    },
+    RetrieveItem {
+        storage_slot: InvSlotId,
+        inv_slot: InvSlotId,
+    },
+    SwapStorageSlots {
+        slot_a: InvSlotId,
+        slot_b: InvSlotId,
+    },
+    CloseStorage,
+    SendMail(MailDraft),
+    MarkMailRead(MailId),
+    TakeMailAttachments(MailId),
+    ReturnMail(MailId),
+    DeleteMail(MailId),
+    CloseMailbox,
+    PostListing {
+        slot: InvSlotId,
+        price: u32,
+    },
+    BuyListing(ListingId),
+    CancelListing(ListingId),
+    CloseMarket,
     Ability {
         idx: usize,
         state: bool,
@@ -919,6 +956,9 @@ pub struct Show {
     bag_details: bool,
     trade: bool,
     trade_details: bool,
+    storage: bool,
+    mailbox: bool,
+    market: bool,
     social: bool,
     diary: bool,
     group: bool,
@@ -932,6 +972,8 @@ pub struct Show {
     settings_tab: SettingsTab,
     diary_fields: diary::DiaryShow,
     crafting_fields: crafting::CraftingShow,
+    mailbox_fields: mailbox::MailboxShow,
+    market_fields: market::MarketShow,
     social_search_key: Option<String>,
     want_grab: bool,
     stats: bool,
@@ -960,6 +1002,9 @@ impl Show {
             bag_details: false,
             trade: false,
             trade_details: false,
+            storage: false,
+            mailbox: false,
+            market: false,
             social: false,
             diary: false,
             group: false,
@@ -973,6 +1018,8 @@ impl Show {
             settings_tab: SettingsTab::Interface,
             diary_fields: diary::DiaryShow::default(),
             crafting_fields: crafting::CraftingShow::default(),
+            mailbox_fields: mailbox::MailboxShow::default(),
+            market_fields: market::MarketShow::default(),
             social_search_key: None,
             want_grab: true,
             stats: false,
@@ -1022,6 +1069,35 @@ impl Show {
         }
     }
 
+    fn storage(&mut self, open: bool) {
+        if !self.esc_menu {
+            self.set_bag_state(open);
+            self.storage = open;
+            self.map = false;
+            self.want_grab = !self.any_window_requires_cursor();
+        }
+    }
+
+    fn mailbox(&mut self, open: bool) {
+        if !self.esc_menu {
+            self.set_bag_state(open);
+            self.mailbox = open;
+            self.mailbox_fields = mailbox::MailboxShow::default();
+            self.map = false;
+            self.want_grab = !self.any_window_requires_cursor();
+        }
+    }
+
+    fn market(&mut self, open: bool) {
+        if !self.esc_menu {
+            self.set_bag_state(open);
+            self.market = open;
+            self.market_fields = market::MarketShow::default();
+            self.map = false;
+            self.want_grab = !self.any_window_requires_cursor();
+        }
+    }
+
     fn map(&mut self, open: bool) {
         if !self.esc_menu {
             self.map = open;
@@ -1122,6 +1198,12 @@ impl Show {
 
     fn toggle_trade(&mut self) { self.trade(!self.trade); }
 
+    fn toggle_storage(&mut self) { self.storage(!self.storage); }
+
+    fn toggle_mailbox(&mut self) { self.mailbox(!self.mailbox); }
+
+    fn toggle_market(&mut self) { self.market(!self.market); }
+
     fn toggle_map(&mut self) { self.map(!self.map) }
 
     fn toggle_social(&mut self) { self.social(!self.social); }
@@ -1603,6 +1685,45 @@ impl Hud {
                 self.show.toggle_trade();
             }
 
+            if client.storage().is_some() != self.show.storage {
+                self.show.toggle_storage();
+            }
+
+            // Close the personal storage once the player walks away from it
+            if let Some((storage_pos, _)) = client.storage()
+                && client.position().is_none_or(|player_pos| {
+                    (storage_pos.as_::<f32>() + 0.5).distance(player_pos) > MAX_PICKUP_RANGE
+                })
+            {
+                events.push(Event::CloseStorage);
+            }
+
+            if client.mailbox().is_some() != self.show.mailbox {
+                self.show.toggle_mailbox();
+            }
+
+            // Close the mailbox once the player walks away from it
+            if let Some((mailbox_pos, _)) = client.mailbox()
+                && client.position().is_none_or(|player_pos| {
+                    (mailbox_pos.as_::<f32>() + 0.5).distance(player_pos) > MAX_PICKUP_RANGE
+                })
+            {
+                events.push(Event::CloseMailbox);
+            }
+
+            if client.market().is_some() != self.show.market {
+                self.show.toggle_market();
+            }
+
+            // Close the market once the player walks away from the market board
+            if let Some((market_pos, _)) = client.market()
+                && client.position().is_none_or(|player_pos| {
+                    (market_pos.as_::<f32>() + 0.5).distance(player_pos) > MAX_PICKUP_RANGE
+                })
+            {
+                events.push(Event::CloseMarket);
+            }
+
             //self.input = client.read_storage::<comp::ControllerInputs>();
             if let Some(health) = healths.get(me) {
                 // Hurt Frame
@@ -2256,6 +2377,21 @@ impl Hud {
                         i18n.get_msg("hud-read").to_string(),
                         overitem::TEXT_COLOR,
                     ),
+                    BlockInteraction::Storage => (
+                        Some(GameInput::Interact),
+                        i18n.get_msg("hud-open-storage").to_string(),
+                        overitem::TEXT_COLOR,
+                    ),
+                    BlockInteraction::Mailbox => (
+                        Some(GameInput::Interact),
+                        i18n.get_msg("hud-open-mailbox").to_string(),
+                        overitem::TEXT_COLOR,
+                    ),
+                    BlockInteraction::MarketBoard => (
+                        Some(GameInput::Interact),
+                        i18n.get_msg("hud-open-market").to_string(),
+                        overitem::TEXT_COLOR,
+                    ),
                     // TODO: change to turn on/turn off?
                     BlockInteraction::LightToggle(enable) => (
                         Some(GameInput::Interact),
@@ -3575,6 +3711,15 @@ impl Hud {
                         if self.show.trade {
                             self.events.push(Event::TradeAction(TradeAction::Decline));
                         }
+                        if self.show.storage {
+                            self.events.push(Event::CloseStorage);
+                        }
+                        if self.show.mailbox {
+                            self.events.push(Event::CloseMailbox);
+                        }
+                        if self.show.market {
+                            self.events.push(Event::CloseMarket);
+                        }
                     },
                     bag::Event::ChangeInventorySortOrder(sort_order) => {
                         self.events
@@ -3654,6 +3799,89 @@ impl Hud {
             }
         }
 
+        // Personal storage window
+        if self.show.storage
+            && let Some(storage::Event::Close) = Storage::new(
+                client,
+                global_state,
+                &info,
+                &self.imgs,
+                &self.item_imgs,
+                &self.fonts,
+                &self.rot_imgs,
+                item_tooltip_manager,
+                &mut self.slot_manager,
+                i18n,
+                &self.item_i18n,
+                &msm,
+                &rbm,
+                self.pulse,
+            )
+            .set(self.ids.storage, ui_widgets)
+        {
+            events.push(Event::CloseStorage);
+        }
+
+        // Mailbox window
+        if self.show.mailbox
+            && let Some(event) = Mailbox::new(
+                client,
+                global_state,
+                &info,
+                &self.imgs,
+                &self.item_imgs,
+                &self.fonts,
+                &self.rot_imgs,
+                item_tooltip_manager,
+                &mut self.slot_manager,
+                i18n,
+                &self.item_i18n,
+                &msm,
+                &rbm,
+                self.pulse,
+                &mut self.show.mailbox_fields,
+            )
+            .set(self.ids.mailbox, ui_widgets)
+        {
+            events.push(match event {
+                mailbox::Event::Close => Event::CloseMailbox,
+                mailbox::Event::Send(draft) => Event::SendMail(draft),
+                mailbox::Event::MarkRead(id) => Event::MarkMailRead(id),
+                mailbox::Event::TakeAttachments(id) => Event::TakeMailAttachments(id),
+                mailbox::Event::Return(id) => Event::ReturnMail(id),
+                mailbox::Event::Delete(id) => Event::DeleteMail(id),
+            });
+        }
+
+        // Market window
+        if self.show.market
+            && let Some(event) = Market::new(
+                client,
+                global_state,
+                &info,
+                &self.imgs,
+                &self.item_imgs,
+                &self.fonts,
+                &self.rot_imgs,
+                item_tooltip_manager,
+                &mut self.slot_manager,
+                i18n,
+                &self.item_i18n,
+                &msm,
+                &rbm,
+                self.pulse,
+                &mut self.show.market_fields,
+            )
+            .set(self.ids.market, ui_widgets)
+        {
+            events.push(match event {
+                market::Event::Close => Event::CloseMarket,
+                market::Event::Post { slot, price } => Event::PostListing { slot, price },
+                market::Event::Buy(id) => Event::BuyListing(id),
+                market::Event::Cancel(id) => Event::CancelListing(id),
+            });
+        }
+
         self.new_messages.retain(chat::show_in_chatbox);
 
         // Chat box
@@ -4154,7 +4382,7 @@ impl Hud {
 
         // Maintain slot manager
         'slot_events: for event in self.slot_manager.maintain(ui_widgets) {
-            use slots::{AbilitySlot, InventorySlot, SlotKind::*};
+            use slots::{AbilitySlot, InventorySlot, MailSlot, SlotKind::*, StorageSlot};
             let to_slot = |slot_kind| match slot_kind {
                 Inventory(
                     i @ InventorySlot {
@@ -4174,6 +4402,9 @@ impl Hud {
                 Trade(_) => None,
                 Ability(_) => None,
                 Crafting(_) => None,
+                Storage(_) => None,
+                Mail(_) => None,
+                Market(_) => None,
             };
             match event {
                 slot::Event::Dragged(a, b) => {
@@ -4284,6 +4515,63 @@ impl Hud {
                     } else if let (Crafting(c), Inventory(_)) = (a, b) {
                         // Remove item from crafting input
                         self.show.crafting_fields.recipe_inputs.remove(&c.index);
+                    } else if let (
+                        Inventory(InventorySlot {
+                            slot: Slot::Inventory(inv_slot),
+                            ours: true,
+                            ..
+                        }),
+                        Storage(StorageSlot { slot: storage_slot }),
+                    ) = (a, b)
+                    {
+                        events.push(Event::StoreItem {
+                            inv_slot,
+                            storage_slot,
+                        });
+                    } else if let (
+                        Storage(StorageSlot { slot: storage_slot }),
+                        Inventory(InventorySlot {
+                            slot: Slot::Inventory(inv_slot),
+                            ours: true,
+                            ..
+                        }),
+                    ) = (a, b)
+                    {
+                        events.push(Event::RetrieveItem {
+                            storage_slot,
+                            inv_slot,
+                        });
+                    } else if let (Storage(a), Storage(b)) = (a, b) {
+                        events.push(Event::SwapStorageSlots {
+                            slot_a: a.slot,
+                            slot_b: b.slot,
+                        });
+                    } else if let (
+                        Inventory(InventorySlot {
+                            slot: Slot::Inventory(inv_slot),
+                            ours: true,
+                            ..
+                        }),
+                        Mail(MailSlot { index, .. }),
+                    ) = (a, b)
+                    {
+                        // Attach item to the mail being composed
+                        self.show.mailbox_fields.attach(index, inv_slot);
+                    } else if let (Mail(m), Inventory(_)) = (a, b) {
+                        self.show.mailbox_fields.detach(m.index);
+                    } else if let (
+                        Inventory(InventorySlot {
+                            slot: Slot::Inventory(inv_slot),
+                            ours: true,
+                            ..
+                        }),
+                        Market(_),
+                    ) = (a, b)
+                    {
+                        // Select the item to list on the market
+                        self.show.market_fields.select(inv_slot);
+                    } else if let (Market(_), Inventory(_)) = (a, b) {
+                        self.show.market_fields.item = None;
                     } else if let (Ability(AbilitySlot::Ability(ability)), Hotbar(slot)) = (a, b)
                         && let Some(Some(HotbarSlotContents::Ability(index))) =
                             self.hotbar.slots.get(slot as usize)
@@ -4313,6 +4601,10 @@ impl Hud {
                     } else if let Crafting(c) = from {
                         // Remove item from crafting input
                         self.show.crafting_fields.recipe_inputs.remove(&c.index);
+                    } else if let Mail(m) = from {
+                        self.show.mailbox_fields.detach(m.index);
+                    } else if let Market(_) = from {
+                        self.show.market_fields.item = None;
                     }
                 },
                 slot::Event::SplitDropped(from) => {
@@ -5000,6 +5292,12 @@ impl Hud {
                     self.force_chat = false;
                 } else if self.show.trade {
                     self.events.push(Event::TradeAction(TradeAction::Decline));
+                } else if self.show.storage {
+                    self.events.push(Event::CloseStorage);
+                } else if self.show.mailbox {
+                    self.events.push(Event::CloseMailbox);
+                } else if self.show.market {
+                    self.events.push(Event::CloseMarket);
                 } else {
                     // Close windows on esc
                     if self.show.bag {
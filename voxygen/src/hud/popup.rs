@@ -131,6 +131,19 @@ impl Widget for Popup<'_> {
                         s.infos.push_back(text.to_string());
                     });
                 },
+                UserNotification::UnreadMail(count) => {
+                    state.update(|s| {
+                        if s.infos.is_empty() {
+                            s.last_info_update = Instant::now();
+                        }
+                        let text = self
+                            .i18n
+                            .get_msg_ctx("hud-mail-unread", &i18n::fluent_args! {
+                                "count" => *count,
+                            });
+                        s.infos.push_back(text.to_string());
+                    });
+                },
             }
         }
 
@@ -29,6 +29,9 @@ pub enum SlotKind {
     Trade(TradeSlot),
     Ability(AbilitySlot),
     Crafting(CraftSlot),
+    Storage(StorageSlot),
+    Mail(MailSlot),
+    Market(MarketSlot),
     /* Spellbook(SpellbookSlot), TODO */
 }
 
@@ -75,6 +78,88 @@ impl SlotKey<Inventory, ItemImgs> for EquipSlot {
     }
 }
 
+/// A slot of the personal storage
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StorageSlot {
+    pub slot: InvSlotId,
+}
+
+impl SlotKey<Inventory, ItemImgs> for StorageSlot {
+    type ImageKey = ItemKey;
+
+    fn image_key(&self, source: &Inventory) -> Option<(Self::ImageKey, Option<Color>)> {
+        source.get(self.slot).map(|i| (i.into(), None))
+    }
+
+    fn amount(&self, source: &Inventory) -> Option<u32> {
+        source
+            .get(self.slot)
+            .map(|item| item.amount())
+            .filter(|amount| *amount > 1)
+    }
+
+    fn image_ids(key: &Self::ImageKey, source: &ItemImgs) -> Vec<image::Id> {
+        source.img_ids_or_not_found_img(key.clone())
+    }
+}
+
+/// An attachment of the mail that is being composed, which is still in the
+/// inventory until the mail is sent
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MailSlot {
+    pub index: usize,
+    pub slot: Option<InvSlotId>,
+}
+
+impl SlotKey<Inventory, ItemImgs> for MailSlot {
+    type ImageKey = ItemKey;
+
+    fn image_key(&self, source: &Inventory) -> Option<(Self::ImageKey, Option<Color>)> {
+        self.slot
+            .and_then(|slot| source.get(slot))
+            .map(|i| (i.into(), None))
+    }
+
+    fn amount(&self, source: &Inventory) -> Option<u32> {
+        self.slot
+            .and_then(|slot| source.get(slot))
+            .map(|item| item.amount())
+            .filter(|amount| *amount > 1)
+    }
+
+    fn image_ids(key: &Self::ImageKey, source: &ItemImgs) -> Vec<image::Id> {
+        source.img_ids_or_not_found_img(key.clone())
+    }
+}
+
+/// The item to list on the market, which is still in the inventory until it
+/// is posted
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MarketSlot {
+    pub slot: Option<InvSlotId>,
+}
+
+impl SlotKey<Inventory, ItemImgs> for MarketSlot {
+    type ImageKey = ItemKey;
+
+    fn image_key(&self, source: &Inventory) -> Option<(Self::ImageKey, Option<Color>)> {
+        self.slot
+            .and_then(|slot| source.get(slot))
+            .map(|i| (i.into(), None))
+    }
+
+    fn amount(&self, source: &Inventory) -> Option<u32> {
+        self.slot
+            .and_then(|slot| source.get(slot))
+            .map(|item| item.amount())
+            .filter(|amount| *amount > 1)
+    }
+
+    fn image_ids(key: &Self::ImageKey, source: &ItemImgs) -> Vec<image::Id> {
+        source.img_ids_or_not_found_img(key.clone())
+    }
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct TradeSlot {
     pub index: usize,
@@ -384,6 +469,18 @@ impl From<CraftSlot> for SlotKind {
     fn from(craft: CraftSlot) -> Self { Self::Crafting(craft) }
 }
 
+impl From<StorageSlot> for SlotKind {
+    fn from(storage: StorageSlot) -> Self { Self::Storage(storage) }
+}
+
+impl From<MailSlot> for SlotKind {
+    fn from(mail: MailSlot) -> Self { Self::Mail(mail) }
+}
+
+impl From<MarketSlot> for SlotKind {
+    fn from(market: MarketSlot) -> Self { Self::Market(market) }
+}
+
 impl SumSlot for SlotKind {
     fn drag_size(&self) -> Option<[f64; 2]> {
         Some(match self {
@@ -459,13 +459,13 @@ impl TutorialState {
     }
 
     pub(crate) fn event_notification(&mut self, notif: &UserNotification) {
-        #[allow(clippy::single_match)]
         match notif {
             UserNotification::WaypointUpdated => {
                 if self.earn_achievement(Achievement::SetWaypoint) {
                     self.show_hint(Hint::Waypoint, Duration::from_secs(1));
                 }
             },
+            UserNotification::UnreadMail(_) => {},
         }
     }
 
@@ -128,7 +128,9 @@ impl PlayState for MainMenuState {
                             &mut global_state.info_message,
                             "singleplayer".to_owned(),
                             "".to_owned(),
-                            ConnectionArgs::Mpsc(14004),
+                            global_state
+                                .args
+                                .session_connection_args(ConnectionArgs::Mpsc(14004)),
                             &mut self.init,
                             &global_state.tokio_runtime,
                             global_state.settings.language.send_to_server.then_some(
@@ -413,6 +415,7 @@ impl PlayState for MainMenuState {
                     let net_settings = &mut global_state.settings.networking;
                     let use_srv = net_settings.use_srv;
                     let use_quic = net_settings.use_quic;
+                    let use_tls = net_settings.use_tls;
                     let validate_tls = net_settings.validate_tls;
                     net_settings.username.clone_from(&username);
                     net_settings.default_server.clone_from(&server_address);
@@ -437,6 +440,12 @@ impl PlayState for MainMenuState {
                             prefer_ipv6: false,
                             validate_tls,
                         }
+                    } else if use_tls {
+                        ConnectionArgs::Tls {
+                            hostname: server_address,
+                            prefer_ipv6: false,
+                            validate_tls,
+                        }
                     } else {
                         ConnectionArgs::Tcp {
                             hostname: server_address,
@@ -447,7 +456,7 @@ impl PlayState for MainMenuState {
                         &mut global_state.info_message,
                         username,
                         password,
-                        connection_args,
+                        global_state.args.session_connection_args(connection_args),
                         &mut self.init,
                         &global_state.tokio_runtime,
                         global_state
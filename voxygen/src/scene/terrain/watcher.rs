@@ -17,6 +17,10 @@ pub enum Interaction {
     Mount,
     Read,
     LightToggle(bool),
+    /// Opens the personal storage
+    Storage,
+    Mailbox,
+    MarketBoard,
 }
 
 #[derive(Copy, Clone)]
@@ -204,6 +208,18 @@ impl BlocksOfInterest {
                             interactables.push((pos, Interaction::Mount));
                         }
 
+                        if sprite.is_storage() {
+                            interactables.push((pos, Interaction::Storage));
+                        }
+
+                        if sprite.is_mailbox() {
+                            interactables.push((pos, Interaction::Mailbox));
+                        }
+
+                        if sprite.is_market_board() {
+                            interactables.push((pos, Interaction::MarketBoard));
+                        }
+
                         match sprite {
                             SpriteKind::Ember => {
                                 fires.push(pos);
@@ -48,6 +48,9 @@ pub enum BlockInteraction {
     Mount,
     Read(Content),
     LightToggle(bool),
+    Storage,
+    Mailbox,
+    MarketBoard,
 }
 
 #[derive(Debug, Clone)]
@@ -127,6 +130,19 @@ impl BlockInteraction {
             Interaction::Craft(tab) => BlockInteraction::Craft(tab),
             Interaction::Mount => BlockInteraction::Mount,
             Interaction::LightToggle(enable) => BlockInteraction::LightToggle(enable),
+            Interaction::Storage => match volume_pos.kind {
+                Volume::Terrain => BlockInteraction::Storage,
+                // The personal storage can only be opened at terrain sprites
+                Volume::Entity(_) => return None,
+            },
+            Interaction::Mailbox => match volume_pos.kind {
+                Volume::Terrain => BlockInteraction::Mailbox,
+                Volume::Entity(_) => return None,
+            },
+            Interaction::MarketBoard => match volume_pos.kind {
+                Volume::Terrain => BlockInteraction::MarketBoard,
+                Volume::Entity(_) => return None,
+            },
         };
         Some((block, block_interaction))
     }
@@ -137,6 +153,9 @@ impl BlockInteraction {
             | BlockInteraction::Read(_)
             | BlockInteraction::LightToggle(_)
             | BlockInteraction::Craft(_)
+            | BlockInteraction::Storage
+            | BlockInteraction::Mailbox
+            | BlockInteraction::MarketBoard
             | BlockInteraction::Unlock { .. } => GameInput::Interact,
             BlockInteraction::Mine(_) => GameInput::Primary,
             BlockInteraction::Mount => GameInput::Mount,
@@ -149,7 +168,10 @@ impl BlockInteraction {
             BlockInteraction::Collect { .. }
             | BlockInteraction::Unlock { .. }
             | BlockInteraction::Mine(_)
-            | BlockInteraction::Craft(_) => consts::MAX_PICKUP_RANGE,
+            | BlockInteraction::Craft(_)
+            | BlockInteraction::Storage
+            | BlockInteraction::Mailbox
+            | BlockInteraction::MarketBoard => consts::MAX_PICKUP_RANGE,
             BlockInteraction::Mount => consts::MAX_SPRITE_MOUNT_RANGE,
             BlockInteraction::LightToggle(_) | BlockInteraction::Read(_) => {
                 consts::MAX_INTERACT_RANGE
@@ -517,6 +539,9 @@ impl Interactable {
             Self::Entity { interaction: EntityInteraction::ActivatePortal, .. }  => 4,
             Self::Entity { interaction: EntityInteraction::PickupItem, .. }      => 3,
             Self::Block  { interaction: BlockInteraction::Craft(_), .. }         => 3,
+            Self::Block  { interaction: BlockInteraction::Storage, .. }          => 3,
+            Self::Block  { interaction: BlockInteraction::Mailbox, .. }          => 3,
+            Self::Block  { interaction: BlockInteraction::MarketBoard, .. }      => 3,
             Self::Block  { interaction: BlockInteraction::Collect { .. }, .. }   => 3,
             Self::Entity { interaction: EntityInteraction::HelpDowned, .. }      => 2,
             Self::Block  { interaction: BlockInteraction::Unlock { .. }, .. }    => 1,
@@ -322,6 +322,12 @@ impl SessionState {
                         (InviteKind::Trade, InviteAnswer::Accepted) => "hud-trade-invite-accepted",
                         (InviteKind::Trade, InviteAnswer::Declined) => "hud-trade-invite-declined",
                         (InviteKind::Trade, InviteAnswer::TimedOut) => "hud-trade-invite-timed_out",
+                        (InviteKind::Guild, InviteAnswer::Accepted) => "hud-guild-invite-accepted",
+                        (InviteKind::Guild, InviteAnswer::Declined) => "hud-guild-invite-declined",
+                        (InviteKind::Guild, InviteAnswer::TimedOut) => "hud-guild-invite-timed_out",
+                        (InviteKind::Duel, InviteAnswer::Accepted) => "hud-duel-invite-accepted",
+                        (InviteKind::Duel, InviteAnswer::Declined) => "hud-duel-invite-declined",
+                        (InviteKind::Duel, InviteAnswer::TimedOut) => "hud-duel-invite-timed_out",
                     };
 
                     let msg = global_state
@@ -445,8 +451,17 @@ impl SessionState {
                 client::Event::SpectatePosition(pos) => {
                     self.scene.camera_mut().force_focus_pos(pos);
                 },
+                // The server reloaded a plugin, see `ServerGeneral::PluginReloaded`
+                #[cfg_attr(not(feature = "plugins"), expect(unused_variables))]
                 client::Event::PluginDataReceived(data) => {
-                    tracing::warn!("Received plugin data at wrong time {}", data.len());
+                    #[cfg(feature = "plugins")]
+                    {
+                        tracing::info!("plugin data {}", data.len());
+                        if let Err(e) = client.load_reloaded_plugin(&global_state.config_dir, data)
+                        {
+                            tracing::error!(?e, "load_reloaded_plugin");
+                        }
+                    }
                 },
                 client::Event::Gizmos(gizmos) => {
                     self.gizmos.retain(|gizmos| {
@@ -1135,6 +1150,27 @@ impl PlayState for SessionState {
                                                             *enable,
                                                         );
                                                     },
+                                                    BlockInteraction::Storage => {
+                                                        if let common::mounting::Volume::Terrain =
+                                                            volume_pos.kind
+                                                        {
+                                                            client.open_storage(volume_pos.pos);
+                                                        }
+                                                    },
+                                                    BlockInteraction::Mailbox => {
+                                                        if let common::mounting::Volume::Terrain =
+                                                            volume_pos.kind
+                                                        {
+                                                            client.open_mailbox(volume_pos.pos);
+                                                        }
+                                                    },
+                                                    BlockInteraction::MarketBoard => {
+                                                        if let common::mounting::Volume::Terrain =
+                                                            volume_pos.kind
+                                                        {
+                                                            client.open_market(volume_pos.pos);
+                                                        }
+                                                    },
                                                 }
                                             },
                                             Interactable::Entity {
@@ -2048,6 +2084,36 @@ impl PlayState for SessionState {
                     HudEvent::TradeAction(action) => {
                         self.client.borrow_mut().perform_trade_action(action);
                     },
+                    HudEvent::StoreItem {
+                        inv_slot,
+                        storage_slot,
+                    } => {
+                        self.client.borrow_mut().store(inv_slot, storage_slot);
+                    },
+                    HudEvent::RetrieveItem {
+                        storage_slot,
+                        inv_slot,
+                    } => {
+                        self.client.borrow_mut().retrieve(storage_slot, inv_slot);
+                    },
+                    HudEvent::SwapStorageSlots { slot_a, slot_b } => {
+                        self.client.borrow_mut().swap_storage_slots(slot_a, slot_b);
+                    },
+                    HudEvent::CloseStorage => self.client.borrow_mut().close_storage(),
+                    HudEvent::SendMail(draft) => self.client.borrow_mut().send_mail(draft),
+                    HudEvent::MarkMailRead(id) => self.client.borrow_mut().mark_mail_read(id),
+                    HudEvent::TakeMailAttachments(id) => {
+                        self.client.borrow_mut().take_mail_attachments(id);
+                    },
+                    HudEvent::ReturnMail(id) => self.client.borrow_mut().return_mail(id),
+                    HudEvent::DeleteMail(id) => self.client.borrow_mut().delete_mail(id),
+                    HudEvent::CloseMailbox => self.client.borrow_mut().close_mailbox(),
+                    HudEvent::PostListing { slot, price } => {
+                        self.client.borrow_mut().post_listing(slot, price);
+                    },
+                    HudEvent::BuyListing(id) => self.client.borrow_mut().buy_listing(id),
+                    HudEvent::CancelListing(id) => self.client.borrow_mut().cancel_listing(id),
+                    HudEvent::CloseMarket => self.client.borrow_mut().close_market(),
                     HudEvent::Ability { idx, state } => {
                         self.client.borrow_mut().handle_input(
                             InputKind::Ability(idx),
@@ -34,6 +34,7 @@ impl ChatFilter {
             },
             ChatType::CommandInfo | ChatType::CommandError => true,
             ChatType::Kill(_, u) => self.death_all || self.death_group && group_members.contains(u),
+            ChatType::Duel(..) => true,
             ChatType::GroupMeta(_) => true,   //todo
             ChatType::FactionMeta(_) => true, //todo
             ChatType::Tell(..) => true,
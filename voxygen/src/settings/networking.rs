@@ -11,6 +11,8 @@ pub struct NetworkingSettings {
     pub trusted_auth_servers: HashSet<String>,
     pub use_srv: bool,
     pub use_quic: bool,
+    /// wrap TCP connections in TLS, ignored if `use_quic` is set
+    pub use_tls: bool,
     pub validate_tls: bool,
     pub player_physics_behavior: bool,
     pub lossy_terrain_compression: bool,
@@ -29,6 +31,7 @@ impl Default for NetworkingSettings {
                 .collect(),
             use_srv: true,
             use_quic: false,
+            use_tls: false,
             validate_tls: true,
             player_physics_behavior: false,
             lossy_terrain_compression: false,
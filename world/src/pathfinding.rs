@@ -1,36 +1,249 @@
-use crate::sim::WorldSim;
-use common::path::Path;
-//use hashbrown::hash_map::DefaultHashBuilder;
+use crate::{
+    sim::{SimChunk, WorldSim},
+    util::NEIGHBORS,
+};
+use common::{astar::Astar, path::Path, terrain::TerrainChunkSize, vol::RectVolSize};
+use core::hash::BuildHasherDefault;
+use fxhash::FxHasher64;
 use vek::*;
 
-#[expect(dead_code)]
+/// Number of chunks explored before the search gives up, enough to cross the
+/// largest maps.
+const MAX_ITERS: usize = 1_000_000;
+/// Steepest slope (altitude change per horizontal metre) that can still be
+/// travelled between two chunks, anything steeper is a cliff.
+const MAX_GRADIENT: f32 = 1.5;
+/// Additional cost (in metres) of crossing a river where there is no bridge.
+const RIVER_CROSSING_COST: f32 = 256.0;
+
+#[derive(Copy, Clone, Debug)]
 pub struct SearchCfg {
     // 0.0 = no discount, 1.0 = free travel
-    path_discount: f32,
+    pub path_discount: f32,
     // Cost per metre altitude change per metre horizontal
     // 0.0 = no cost, 1.0 = same cost vertical as horizontal
-    gradient_aversion: f32,
+    pub gradient_aversion: f32,
+}
+
+impl Default for SearchCfg {
+    fn default() -> Self {
+        Self {
+            path_discount: 0.5,
+            gradient_aversion: 1.0,
+        }
+    }
+}
+
+impl SearchCfg {
+    /// Cost of travelling `dist` metres horizontally and `climb` metres
+    /// vertically, `None` if the slope is too steep to travel.
+    pub fn step_cost(&self, dist: f32, climb: f32, on_path: bool) -> Option<f32> {
+        if climb > dist * MAX_GRADIENT {
+            return None;
+        }
+        let discount = if on_path {
+            self.path_discount.clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        Some(dist * (1.0 - discount) + climb * self.gradient_aversion.max(0.0))
+    }
+
+    /// Lowest possible cost per horizontal metre, used to keep the heuristic
+    /// admissible.
+    fn min_cost_per_metre(&self) -> f32 { 1.0 - self.path_discount.clamp(0.0, 1.0) }
 }
 
-#[expect(dead_code)]
 pub struct Searcher<'a> {
     land: &'a WorldSim,
     pub cfg: SearchCfg,
+    max_iters: usize,
 }
 
-impl Searcher<'_> {
+impl<'a> Searcher<'a> {
+    pub fn new(land: &'a WorldSim, cfg: SearchCfg) -> Self {
+        Self {
+            land,
+            cfg,
+            max_iters: MAX_ITERS,
+        }
+    }
+
+    /// Give up after exploring `max_iters` chunks, for callers which can't
+    /// afford a search across the whole map.
+    pub fn with_max_iters(mut self, max_iters: usize) -> Self {
+        self.max_iters = max_iters.min(MAX_ITERS);
+        self
+    }
+
     /// Attempt to find a path between two chunks on the map.
-    pub fn search(self, _a: Vec2<i32>, _b: Vec2<i32>) -> Option<Path<i32>> {
-        // TODO: implement this function
-
-        //let heuristic = |pos: &Vec2<i32>| (pos - b).map(|e| e as f32).magnitude();
-        // Astar::new(
-        //     100_000,
-        //     a,
-        //     heuristc,
-        //     DefaultHashBuilder::default(),
-        // );
-
-        None
+    ///
+    /// Oceans and lakes can't be crossed, rivers only along a road or path
+    /// (i.e. over a bridge) or at an additional cost. Returns the chunks along
+    /// the path and its total cost.
+    pub fn search(self, a: Vec2<i32>, b: Vec2<i32>) -> Option<(Path<Vec2<i32>>, f32)> {
+        if !self.is_passable(a) || !self.is_passable(b) {
+            return None;
+        }
+
+        let this = &self;
+        let chunk_size = TerrainChunkSize::RECT_SIZE.x as f32;
+        let min_cost_per_metre = self.cfg.min_cost_per_metre();
+        let heuristic =
+            |pos: &Vec2<i32>| pos.as_::<f32>().distance(b.as_()) * chunk_size * min_cost_per_metre;
+        let neighbors = |pos: &Vec2<i32>| {
+            let pos = *pos;
+            let from = this.land.get(pos);
+            NEIGHBORS.into_iter().filter_map(move |dir| {
+                let to_pos = pos + dir;
+                let cost = this.travel_cost(from?, this.land.get(to_pos)?, dir)?;
+                Some((to_pos, cost))
+            })
+        };
+        let satisfied = |pos: &Vec2<i32>| *pos == b;
+
+        // Deterministic hasher, see `civ::find_path`
+        Astar::new(
+            self.max_iters,
+            a,
+            BuildHasherDefault::<FxHasher64>::default(),
+        )
+        .poll(self.max_iters, heuristic, neighbors, satisfied)
+        .into_path()
+    }
+
+    fn is_passable(&self, pos: Vec2<i32>) -> bool {
+        self.land
+            .get(pos)
+            .is_some_and(|chunk| !chunk.river.is_ocean() && !chunk.river.is_lake())
+    }
+
+    /// Cost of travelling from a chunk to its neighbour in direction `dir`
+    fn travel_cost(&self, from: &SimChunk, to: &SimChunk, dir: Vec2<i32>) -> Option<f32> {
+        if to.river.is_ocean() || to.river.is_lake() {
+            return None;
+        }
+
+        let dist = dir.as_::<f32>().magnitude() * TerrainChunkSize::RECT_SIZE.x as f32;
+        // Cliffs don't show up in the average altitude of a chunk
+        let climb = (to.alt - from.alt).abs() + to.cliff_height;
+        let on_path = from.path.0.is_way() && to.path.0.is_way();
+        let river_cost = if to.river.is_river() && !to.path.0.is_way() {
+            RIVER_CROSSING_COST
+        } else {
+            0.0
+        };
+
+        Some(self.cfg.step_cost(dist, climb, on_path)? + river_cost)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::{RiverKind, Way};
+    use common::terrain::MapSizeLg;
+
+    /// A flat 16x16 chunk map
+    fn flat_map() -> WorldSim { WorldSim::flat(MapSizeLg::new(Vec2::broadcast(4)).unwrap()) }
+
+    fn find_route(sim: &WorldSim, a: Vec2<i32>, b: Vec2<i32>) -> Option<Vec<Vec2<i32>>> {
+        Searcher::new(sim, SearchCfg::default())
+            .search(a, b)
+            .map(|(path, _)| path.nodes)
+    }
+
+    /// Check that consecutive chunks of the route are neighbours
+    fn assert_connected(route: &[Vec2<i32>], a: Vec2<i32>, b: Vec2<i32>) {
+        assert_eq!(route.first(), Some(&a));
+        assert_eq!(route.last(), Some(&b));
+        for (from, to) in route.iter().zip(route.iter().skip(1)) {
+            assert!(NEIGHBORS.contains(&(*to - *from)), "{from:?} -> {to:?}");
+        }
+    }
+
+    #[test]
+    fn paths_are_discounted() {
+        let cfg = SearchCfg {
+            path_discount: 0.75,
+            gradient_aversion: 1.0,
+        };
+        assert_eq!(cfg.step_cost(32.0, 0.0, false), Some(32.0));
+        assert_eq!(cfg.step_cost(32.0, 0.0, true), Some(8.0));
+    }
+
+    #[test]
+    fn cliffs_are_impassable() {
+        let cfg = SearchCfg::default();
+        assert_eq!(cfg.step_cost(32.0, 16.0, false), Some(48.0));
+        assert_eq!(cfg.step_cost(32.0, 64.0, true), None);
+    }
+
+    #[test]
+    fn route_goes_around_water() {
+        let mut sim = flat_map();
+        // A wall of ocean with a single gap at the top
+        for y in 0..15 {
+            sim.get_mut(Vec2::new(8, y)).unwrap().river.river_kind = Some(RiverKind::Ocean);
+        }
+        let (a, b) = (Vec2::new(2, 2), Vec2::new(14, 2));
+        let route = find_route(&sim, a, b).unwrap();
+        assert_connected(&route, a, b);
+        assert!(route.contains(&Vec2::new(8, 15)));
+
+        // Closing the gap leaves no way across
+        sim.get_mut(Vec2::new(8, 15)).unwrap().river.river_kind = Some(RiverKind::Ocean);
+        assert_eq!(find_route(&sim, a, b), None);
+        // Neither end can be in the water
+        assert_eq!(find_route(&sim, a, Vec2::new(8, 2)), None);
+    }
+
+    #[test]
+    fn route_avoids_cliffs() {
+        let mut sim = flat_map();
+        // A ridge which can only be climbed at the bottom
+        for y in 1..16 {
+            sim.get_mut(Vec2::new(8, y)).unwrap().cliff_height = 1000.0;
+        }
+        let (a, b) = (Vec2::new(2, 12), Vec2::new(14, 12));
+        let route = find_route(&sim, a, b).unwrap();
+        assert_connected(&route, a, b);
+        assert!(route.contains(&Vec2::new(8, 0)));
+    }
+
+    #[test]
+    fn route_follows_roads() {
+        let mut sim = flat_map();
+        let (a, b) = (Vec2::new(2, 8), Vec2::new(14, 8));
+        let straight = find_route(&sim, a, b).unwrap();
+        assert!(straight.iter().all(|pos| pos.y == 8));
+
+        // A road making a small detour is cheaper than walking straight across
+        let road = (2..=14)
+            .map(|x| Vec2::new(x, 10))
+            .chain([8, 9].map(|y| Vec2::new(2, y)))
+            .chain([8, 9].map(|y| Vec2::new(14, y)));
+        for pos in road {
+            sim.get_mut(pos).unwrap().path.0 = Way {
+                neighbors: 1,
+                ..Way::default()
+            };
+        }
+        let route = find_route(&sim, a, b).unwrap();
+        assert_connected(&route, a, b);
+        assert!(route.contains(&Vec2::new(8, 10)));
+    }
+
+    #[test]
+    fn search_can_be_bounded() {
+        let sim = flat_map();
+        let (a, b) = (Vec2::new(0, 0), Vec2::new(15, 15));
+        let searcher = |max_iters| {
+            Searcher::new(&sim, SearchCfg::default())
+                .with_max_iters(max_iters)
+                .search(a, b)
+        };
+        assert!(searcher(4).is_none());
+        assert!(searcher(MAX_ITERS).is_some());
     }
 }
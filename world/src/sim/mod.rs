@@ -726,29 +726,7 @@ impl WorldSim {
             seed: 0,
             map_size_lg: MapSizeLg::new(Vec2::one()).unwrap(),
             max_height: 0.0,
-            chunks: vec![SimChunk {
-                chaos: 0.0,
-                alt: 0.0,
-                basement: 0.0,
-                water_alt: 0.0,
-                downhill: None,
-                flux: 0.0,
-                temp: 0.0,
-                humidity: 0.0,
-                rockiness: 0.0,
-                tree_density: 0.0,
-                forest_kind: ForestKind::Dead,
-                spawn_rate: 0.0,
-                river: RiverData::default(),
-                surface_veg: 0.0,
-                sites: vec![],
-                place: None,
-                poi: None,
-                path: Default::default(),
-                cliff_height: 0.0,
-                spot: None,
-                contains_waypoint: false,
-            }],
+            chunks: vec![SimChunk::flat()],
             _locations: Vec::new(),
             gen_ctx,
             rng: rand_chacha::ChaCha20Rng::from_seed([0; 32]),
@@ -756,6 +734,18 @@ impl WorldSim {
         }
     }
 
+    /// A flat map of the given size made of [`SimChunk::flat`] chunks
+    #[cfg(test)]
+    pub(crate) fn flat(map_size_lg: MapSizeLg) -> Self {
+        Self {
+            map_size_lg,
+            chunks: (0..map_size_lg.chunks_len())
+                .map(|_| SimChunk::flat())
+                .collect(),
+            ..Self::empty()
+        }
+    }
+
     pub fn generate(
         seed: u32,
         opts: WorldOpts,
@@ -2545,6 +2535,33 @@ pub struct NearestWaysData<M, F: FnOnce() -> Vec2<f32>> {
 }
 
 impl SimChunk {
+    /// A chunk at sea level without any rivers, paths or sites
+    fn flat() -> Self {
+        Self {
+            chaos: 0.0,
+            alt: 0.0,
+            basement: 0.0,
+            water_alt: 0.0,
+            downhill: None,
+            flux: 0.0,
+            temp: 0.0,
+            humidity: 0.0,
+            rockiness: 0.0,
+            tree_density: 0.0,
+            forest_kind: ForestKind::Dead,
+            spawn_rate: 0.0,
+            river: RiverData::default(),
+            surface_veg: 0.0,
+            sites: vec![],
+            place: None,
+            poi: None,
+            path: Default::default(),
+            cliff_height: 0.0,
+            spot: None,
+            contains_waypoint: false,
+        }
+    }
+
     fn generate(map_size_lg: MapSizeLg, posi: usize, gen_ctx: &GenCtx, gen_cdf: &GenCdf) -> Self {
         let pos = uniform_idx_as_vec2(map_size_lg, posi);
         let wposf = (pos * TerrainChunkSize::RECT_SIZE.map(|e| e as i32)).map(|e| e as f64);
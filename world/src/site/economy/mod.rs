@@ -342,6 +342,7 @@ impl Economy {
                 });
                 prices.iter().map(|(g, v)| (Good::from(g), *v)).collect()
             },
+            reputation_factor: None,
         }
     }
 
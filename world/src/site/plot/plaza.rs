@@ -431,6 +431,7 @@ impl Structure for Plaza {
             max: site.wpos_tile_pos(self.aabr.max) - 1,
         };
 
+        let mut mailbox_placed = false;
         for dir in Dir2::iter() {
             let orth = dir.orthogonal();
 
@@ -447,6 +448,22 @@ impl Structure for Plaza {
                     let alt = self.hard_alt.unwrap_or(self.alt) + 1;
                     let wpos = wpos.with_z(alt);
                     self.kind.place_light(wpos, -dir, painter);
+
+                    // Every plaza has a mailbox and a market board, on both sides of the
+                    // first of its lights
+                    if !mailbox_placed {
+                        painter.rotated_sprite(
+                            wpos + orth.to_vec3() * 2,
+                            SpriteKind::Mailbox,
+                            (-dir).sprite_ori(),
+                        );
+                        painter.rotated_sprite(
+                            wpos - orth.to_vec3() * 2,
+                            SpriteKind::MarketBoard,
+                            (-dir).sprite_ori(),
+                        );
+                        mailbox_placed = true;
+                    }
                 }
             }
         }